@@ -0,0 +1,394 @@
+//! Predicates on the ancestors of the spending transaction
+//!
+//! [Ancestry] generalises [ParentTx](crate::bitcoin_predicates::parent_tx::ParentTx) to a chain of `K` ancestor
+//! transactions: the parent of the spending transaction, its parent, and so on. Each ancestor is linked to its
+//! child through the txid spent by one of the inputs of the child, and the locking script of one of its outputs
+//! can be checked against a committed script.
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::{AllocVar, AllocationMode},
+    eq::EqGadget,
+    prelude::Boolean,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+
+use chain_gang::messages::Tx;
+use chain_gang::script::Script;
+
+use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+use crate::constraints::{
+    script::ScriptVar,
+    tx::{TxVar, TxVarConfig},
+};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+
+/// The checks performed by [Ancestry] on a single ancestor
+#[derive(Clone)]
+pub struct AncestorLevel {
+    /// The index of the input of the child spending the ancestor
+    pub input_index: usize,
+    /// The index of the output of the ancestor whose locking script is checked
+    pub vout: usize,
+    /// The locking script of the output at `vout`, if any
+    pub lock_script: Option<Script>,
+}
+
+impl AncestorLevel {
+    pub fn new(input_index: usize, vout: usize, lock_script: Option<Script>) -> Self {
+        Self {
+            input_index,
+            vout,
+            lock_script,
+        }
+    }
+}
+
+/// The witness of [Ancestry]: the ancestors of the spending transaction, starting from its parent
+#[derive(Clone)]
+pub struct AncestryWitness<const K: usize> {
+    pub ancestors: [Tx; K],
+}
+
+/// The R1CS version of [AncestryWitness]
+pub struct AncestryWitnessVar<const K: usize, F: PrimeField, Q: TxVarConfig + Clone> {
+    pub ancestors: Vec<TxVar<F, Q>>,
+}
+
+impl<const K: usize, F: PrimeField, Q: TxVarConfig + Clone> AllocVar<AncestryWitness<K>, F>
+    for AncestryWitnessVar<K, F, Q>
+{
+    fn new_variable<T: Borrow<AncestryWitness<K>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let data: AncestryWitness<K> = f().map(|data| data.borrow().clone())?;
+        let mut ancestors: Vec<TxVar<F, Q>> = Vec::with_capacity(K);
+        for ancestor in data.ancestors.iter() {
+            ancestors.push(TxVar::<F, Q>::new_variable(
+                cs.clone(),
+                || Ok(ancestor),
+                mode,
+            )?);
+        }
+
+        Ok(Self { ancestors })
+    }
+}
+
+/// Bitcoin Predicate enforcing the checks in `levels` on the `K` ancestors of the spending transaction:
+/// for every `j`, the input at `levels[j].input_index` of the child of the `j`-th ancestor spends the `j`-th
+/// ancestor, and, if `levels[j].lock_script` is set, the output of the `j`-th ancestor at `levels[j].vout` has
+/// that locking script. The `0`-th ancestor is the parent of the spending transaction.
+///
+/// The shape of the ancestors is set by `Q`.
+pub struct Ancestry<const K: usize, F: PrimeField, P: TxVarConfig + Clone, Q: TxVarConfig + Clone> {
+    pub levels: [AncestorLevel; K],
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+    _phantom_ancestor_config: PhantomData<Q>,
+}
+
+impl<const K: usize, F: PrimeField, P: TxVarConfig + Clone, Q: TxVarConfig + Clone>
+    Ancestry<K, F, P, Q>
+{
+    /// Returns [Error::ConfigMismatch] if there are no ancestors or if the length of a locking script is different
+    /// from the one set in `Q`, and [Error::IndexOutOfBounds] if an index is not an input of the child or an
+    /// output of the ancestor
+    pub fn new(levels: [AncestorLevel; K]) -> Result<Self, Error> {
+        if K == 0 {
+            return Err(Error::ConfigMismatch(
+                "The number of ancestors must be positive".to_string(),
+            ));
+        }
+        for (j, level) in levels.iter().enumerate() {
+            let n_inputs = if j == 0 { P::N_INPUTS } else { Q::N_INPUTS };
+            if level.input_index >= n_inputs {
+                return Err(Error::IndexOutOfBounds(format!(
+                    "Input index: {} is larger than the number of inputs: {}",
+                    level.input_index, n_inputs
+                )));
+            }
+            if level.vout >= Q::N_OUTPUTS {
+                return Err(Error::IndexOutOfBounds(format!(
+                    "Index: {} is larger than the number of outputs of the ancestors: {}",
+                    level.vout,
+                    Q::N_OUTPUTS
+                )));
+            }
+            if let Some(lock_script) = &level.lock_script
+                && lock_script.0.len() != Q::LEN_LOCK_SCRIPTS[level.vout]
+            {
+                return Err(Error::ConfigMismatch(format!(
+                    "The length of the locking script: {} is different from the one set in the parameters: {}",
+                    lock_script.0.len(),
+                    Q::LEN_LOCK_SCRIPTS[level.vout]
+                )));
+            }
+        }
+        Ok(Self {
+            levels,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+            _phantom_ancestor_config: PhantomData,
+        })
+    }
+}
+
+impl<const K: usize, F: PrimeField, P: TxVarConfig + Clone, Q: TxVarConfig + Clone>
+    BitcoinPredicate<F, P> for Ancestry<K, F, P, Q>
+{
+    type LockingData = BitcoinUnit<F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = AncestryWitness<K>;
+
+    type LockingDataVar = BitcoinUnitVar<F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = AncestryWitnessVar<K, F, Q>;
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        _locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        // Validate input
+        if witness.ancestors.len() != K {
+            return Err(Error::MissingData(format!(
+                "The number of ancestors: {} is different from the number of levels: {}",
+                witness.ancestors.len(),
+                K
+            ))
+            .into());
+        }
+
+        let mut checks: Vec<Boolean<F>> = Vec::with_capacity(2 * K);
+        for (j, (level, ancestor)) in self.levels.iter().zip(witness.ancestors.iter()).enumerate() {
+            // The child of the `j`-th ancestor spends it
+            let child_input = if j == 0 {
+                &spending_data.inputs[level.input_index]
+            } else {
+                &witness.ancestors[j - 1].inputs[level.input_index]
+            };
+            checks.push(child_input.prev_output.prev_tx.is_eq(&ancestor.txid()?)?);
+
+            if let Some(lock_script) = &level.lock_script {
+                checks.push(ancestor.outputs[level.vout].lock_script.is_eq(
+                    &ScriptVar::<F>::new_constant(cs.clone(), lock_script.clone())?,
+                )?);
+            }
+        }
+
+        Boolean::<F>::kary_and(&checks)
+    }
+
+    fn evaluate_native(
+        &self,
+        _locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        let mut holds = true;
+        let mut child = tx;
+        for (level, ancestor) in self.levels.iter().zip(witness.ancestors.iter()) {
+            if level.input_index >= child.inputs.len() {
+                return Err(Error::IndexOutOfBounds(format!(
+                    "Input index: {} is larger than the number of inputs: {}",
+                    level.input_index,
+                    child.inputs.len()
+                )));
+            }
+            if level.vout >= ancestor.outputs.len() {
+                return Err(Error::IndexOutOfBounds(format!(
+                    "Index: {} is larger than the number of outputs of the ancestors: {}",
+                    level.vout,
+                    ancestor.outputs.len()
+                )));
+            }
+
+            // The child of the ancestor spends it
+            holds &= child.inputs[level.input_index].prev_output.hash == ancestor.hash();
+            if let Some(lock_script) = &level.lock_script {
+                holds &= ancestor.outputs[level.vout].lock_script.0 == lock_script.0;
+            }
+            child = ancestor;
+        }
+
+        Ok(holds)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::script::Script;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::util::{Hash160, Hash256};
+
+    use crate::bitcoin_predicates::data_structures::unit::BitcoinUnitVar;
+    use crate::constraints::tx::{TxVar, TxVarConfig};
+    use crate::error::Error;
+    use crate::traits::BitcoinPredicate;
+
+    use super::{AncestorLevel, Ancestry, AncestryWitness, AncestryWitnessVar};
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0];
+    }
+
+    #[derive(Clone)]
+    struct AncestorConfig;
+    impl TxVarConfig for AncestorConfig {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    fn lock_script(n: u8) -> Script {
+        p2pkh::create_lock_script(&Hash160([n; 20]))
+    }
+
+    fn spend(prev_tx: Hash256, lock_script: Script) -> Tx {
+        Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: prev_tx,
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 1000,
+                lock_script,
+            }],
+            lock_time: 0,
+        }
+    }
+
+    /// Return the spending transaction and its ancestors
+    fn chain() -> (Tx, [Tx; 2]) {
+        let grandparent = spend(
+            Hash256::decode("f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7")
+                .unwrap(),
+            lock_script(2),
+        );
+        let parent = spend(grandparent.hash(), lock_script(1));
+        (spend(parent.hash(), Script(vec![])), [parent, grandparent])
+    }
+
+    fn test_predicate(tx: Tx, ancestors: [Tx; 2], levels: [AncestorLevel; 2]) -> bool {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        let witness_var =
+            AncestryWitnessVar::<2, F, AncestorConfig>::new_witness(cs.clone(), || {
+                Ok(AncestryWitness { ancestors })
+            })
+            .unwrap();
+        Ancestry::<2, F, Config, AncestorConfig>::new(levels)
+            .unwrap()
+            .enforce_constraints(
+                cs.clone(),
+                &BitcoinUnitVar::default(),
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &witness_var,
+            )
+            .unwrap();
+
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_ancestry_is_ok() {
+        let (tx, ancestors) = chain();
+        assert!(test_predicate(
+            tx.clone(),
+            ancestors.clone(),
+            [
+                AncestorLevel::new(0, 0, Some(lock_script(1))),
+                AncestorLevel::new(0, 0, Some(lock_script(2))),
+            ]
+        ));
+        // Only check the script of the grandparent
+        assert!(test_predicate(
+            tx,
+            ancestors,
+            [
+                AncestorLevel::new(0, 0, None),
+                AncestorLevel::new(0, 0, Some(lock_script(2))),
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_ancestry_fails() {
+        let (tx, [parent, grandparent]) = chain();
+        let levels = || {
+            [
+                AncestorLevel::new(0, 0, Some(lock_script(1))),
+                AncestorLevel::new(0, 0, Some(lock_script(2))),
+            ]
+        };
+        // Wrong script in the grandparent
+        assert!(!test_predicate(
+            tx.clone(),
+            [parent.clone(), grandparent.clone()],
+            [
+                AncestorLevel::new(0, 0, Some(lock_script(1))),
+                AncestorLevel::new(0, 0, Some(lock_script(1))),
+            ]
+        ));
+        // The parent does not spend the grandparent
+        let other = spend(grandparent.hash(), lock_script(2));
+        assert!(!test_predicate(
+            tx.clone(),
+            [parent.clone(), other],
+            levels()
+        ));
+        // The ancestors are in the wrong order
+        assert!(!test_predicate(tx, [grandparent, parent], levels()));
+    }
+
+    #[test]
+    fn test_wrong_script_length() {
+        assert!(matches!(
+            Ancestry::<2, F, Config, AncestorConfig>::new([
+                AncestorLevel::new(0, 0, Some(Script(vec![]))),
+                AncestorLevel::new(0, 0, None),
+            ]),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_index_out_of_bounds() {
+        assert!(matches!(
+            Ancestry::<2, F, Config, AncestorConfig>::new([
+                AncestorLevel::new(0, 0, None),
+                AncestorLevel::new(0, 5, None),
+            ]),
+            Err(Error::IndexOutOfBounds(_))
+        ));
+    }
+}
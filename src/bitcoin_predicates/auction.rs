@@ -0,0 +1,410 @@
+//! English auction covenants
+//!
+//! The state of the auction, i.e., the current bid and the HASH160 of the current bidder, is published by each bid
+//! in an `OP_FALSE OP_RETURN` output, see [auction_state_script], and is the locking data of the next spend. A
+//! spend of the auction output either:
+//! - places a new bid: the bid is raised by at least `min_increment`, the auction output is re-created with the
+//!   same locking script and holds at least the new bid, and the previous bidder is refunded, or
+//! - settles the auction: the lock time is at least `deadline`, see [MinLockTime], and the seller is paid at least
+//!   the current bid.
+//!
+//! **Note**: Bitcoin cannot enforce an upper bound on the time of a spend, so bids after the deadline are only
+//! prevented by the seller settling the auction.
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    eq::EqGadget,
+    fields::{FieldVar, fp::FpVar},
+    prelude::{AllocVar, Boolean},
+    uint8::UInt8,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use chain_gang::script::Script;
+use chain_gang::script::op_codes::{
+    OP_CHECKSIG, OP_DUP, OP_EQUALVERIFY, OP_FALSE, OP_HASH160, OP_RETURN,
+};
+use chain_gang::util::Hash160;
+
+use crate::bitcoin_predicates::data_structures::{
+    byte_array::{ByteArray, ByteArrayVar},
+    unit::{BitcoinUnit, BitcoinUnitVar},
+};
+use crate::bitcoin_predicates::lock_time::MinLockTime;
+use crate::constraints::{
+    script::ScriptVar,
+    tx::{TxVar, TxVarConfig},
+};
+use crate::error::Error;
+use crate::traits::{BitcoinPredicate, SpendingContext};
+use crate::util::{is_less_or_equal, to_fp};
+
+/// The size of the state of the auction: the 8-byte little endian bid followed by the HASH160 of the bidder
+pub const AUCTION_STATE_SIZE: usize = 28;
+
+/// The index of the output re-creating the auction on a bid, paying the seller on settlement
+pub const AUCTION_OUTPUT: usize = 0;
+/// The index of the output publishing the new state on a bid
+pub const STATE_OUTPUT: usize = 1;
+/// The index of the output refunding the previous bidder on a bid
+pub const REFUND_OUTPUT: usize = 2;
+
+/// The state of the auction with current bid `bid` placed by `bidder`. The initial state has a zero bid
+pub fn auction_state(bid: u64, bidder: &Hash160) -> [u8; AUCTION_STATE_SIZE] {
+    let mut state = [0u8; AUCTION_STATE_SIZE];
+    state[..8].copy_from_slice(&bid.to_le_bytes());
+    state[8..].copy_from_slice(&bidder.0);
+    state
+}
+
+/// The locking script `OP_FALSE OP_RETURN <state>` of the output publishing the state of the auction
+pub fn auction_state_script(bid: u64, bidder: &Hash160) -> Script {
+    let mut script = vec![OP_FALSE, OP_RETURN, AUCTION_STATE_SIZE as u8];
+    script.extend(auction_state(bid, bidder));
+    Script(script)
+}
+
+/// Bitcoin Predicate implementing an English auction, see the [module documentation](self). The outputs of the
+/// spending transaction are at the fixed indices [AUCTION_OUTPUT], [STATE_OUTPUT] and [REFUND_OUTPUT].
+///
+/// The locking data is the current state of the auction, see [auction_state]. Bids require the integrity scheme to
+/// expose the locking script of the output being spent, see
+/// [IntegrityScheme::prev_lock_script](crate::traits::IntegrityScheme::prev_lock_script).
+pub struct Auction<F: PrimeField, P: TxVarConfig + Clone> {
+    pub seller_script: Script,
+    pub deadline: u32,
+    pub min_increment: u64,
+    pub input_index: usize,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> Auction<F, P> {
+    /// Returns [Error::ConfigMismatch] if `P` does not set the outputs of an auction transaction
+    pub fn new(
+        seller_script: Script,
+        deadline: u32,
+        min_increment: u64,
+        input_index: usize,
+    ) -> Result<Self, Error> {
+        if P::N_OUTPUTS <= REFUND_OUTPUT {
+            return Err(Error::ConfigMismatch(format!(
+                "An auction transaction has {} outputs, but the parameters set {}",
+                REFUND_OUTPUT + 1,
+                P::N_OUTPUTS
+            )));
+        }
+        Ok(Self {
+            seller_script,
+            deadline,
+            min_increment,
+            input_index,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        })
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for Auction<F, P> {
+    type LockingData = ByteArray<AUCTION_STATE_SIZE, F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = ByteArrayVar<AUCTION_STATE_SIZE, F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        self.generate_constraints_with_context(
+            cs,
+            locking_data,
+            unlocking_data,
+            spending_data,
+            witness,
+            &SpendingContext::default(),
+        )
+    }
+
+    fn generate_constraints_with_context(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+        context: &SpendingContext<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let prev_lock_script = context.prev_lock_script.ok_or(Error::MissingData(
+            "The locking script of the output being spent is not available".to_string(),
+        ))?;
+        let state_script = &spending_data.outputs[STATE_OUTPUT].lock_script.0;
+        if state_script.len() != 3 + AUCTION_STATE_SIZE {
+            return Err(Error::ConfigMismatch(format!(
+                "The length of the locking script of the output {}: {} is incompatible with the state of an auction",
+                STATE_OUTPUT,
+                state_script.len()
+            ))
+            .into());
+        }
+
+        let (prev_bid_bytes, prev_bidder) = locking_data.bytes.split_at(8);
+        let prev_bid = to_fp(&prev_bid_bytes.to_vec())?;
+        let (state_prefix, new_state) = state_script.split_at(3);
+        let new_bid = to_fp(&new_state[..8].to_vec())?;
+
+        let auction_output = &spending_data.outputs[AUCTION_OUTPUT];
+        let auction_amount = to_fp(&auction_output.satoshis)?;
+
+        // New bid
+        let is_recreated = if auction_output.lock_script.0.len() == prev_lock_script.0.len() {
+            auction_output.lock_script.is_eq(prev_lock_script)?
+        } else {
+            Boolean::<F>::FALSE
+        };
+        let is_bid = Boolean::<F>::kary_and(&[
+            state_prefix.is_eq(&UInt8::<F>::constant_vec(&[
+                OP_FALSE,
+                OP_RETURN,
+                AUCTION_STATE_SIZE as u8,
+            ]))?,
+            is_less_or_equal(
+                &(&prev_bid + FpVar::<F>::constant(F::from(self.min_increment))),
+                &new_bid,
+                65,
+            )?,
+            is_recreated,
+            is_less_or_equal(&new_bid, &auction_amount, 64)?,
+            self.is_refunded(spending_data, &prev_bid, prev_bidder)?,
+        ])?;
+
+        // Settlement
+        let is_settled = Boolean::<F>::kary_and(&[
+            MinLockTime::<F, P>::new(self.deadline, self.input_index).generate_constraints(
+                cs.clone(),
+                &BitcoinUnitVar::default(),
+                unlocking_data,
+                spending_data,
+                witness,
+            )?,
+            auction_output
+                .lock_script
+                .is_eq(&ScriptVar::<F>::new_constant(
+                    cs.clone(),
+                    self.seller_script.clone(),
+                )?)?,
+            is_less_or_equal(&prev_bid, &auction_amount, 64)?,
+        ])?;
+
+        Boolean::<F>::kary_or(&[is_bid, is_settled])
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> Auction<F, P> {
+    /// Check that the previous bidder is refunded their bid with a P2PKH output, unless there is no previous bid
+    fn is_refunded(
+        &self,
+        spending_data: &TxVar<F, P>,
+        prev_bid: &FpVar<F>,
+        prev_bidder: &[UInt8<F>],
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let refund_output = &spending_data.outputs[REFUND_OUTPUT];
+        let mut refund_script = UInt8::<F>::constant_vec(&[OP_DUP, OP_HASH160, 20]);
+        refund_script.extend_from_slice(prev_bidder);
+        refund_script.extend(UInt8::<F>::constant_vec(&[OP_EQUALVERIFY, OP_CHECKSIG]));
+
+        let is_refund_script = if refund_output.lock_script.0.len() == refund_script.len() {
+            refund_output.lock_script.0.is_eq(&refund_script)?
+        } else {
+            Boolean::<F>::FALSE
+        };
+
+        Boolean::<F>::kary_or(&[
+            prev_bid.is_zero()?,
+            Boolean::<F>::kary_and(&[
+                is_refund_script,
+                is_less_or_equal(prev_bid, &to_fp(&refund_output.satoshis)?, 64)?,
+            ])?,
+        ])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::script::Script;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::util::{Hash160, Hash256};
+
+    use crate::bitcoin_predicates::data_structures::{
+        byte_array::{ByteArray, ByteArrayVar},
+        unit::BitcoinUnitVar,
+    };
+    use crate::constraints::{
+        script::ScriptVar,
+        tx::{TxVar, TxVarConfig},
+    };
+    use crate::error::Error;
+    use crate::traits::{BitcoinPredicate, SpendingContext};
+
+    use super::{AUCTION_STATE_SIZE, Auction, auction_state, auction_state_script};
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 3;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19, 3 + AUCTION_STATE_SIZE, 0x19];
+    }
+
+    #[derive(Clone)]
+    struct NoRefundConfig;
+    impl TxVarConfig for NoRefundConfig {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 2;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19, 3 + AUCTION_STATE_SIZE];
+    }
+
+    const DEADLINE: u32 = 800_000;
+    const MIN_INCREMENT: u64 = 100;
+
+    fn lock_script(n: u8) -> Script {
+        p2pkh::create_lock_script(&Hash160([n; 20]))
+    }
+
+    /// Stand-in for the locking script of the auction output
+    fn auction_script() -> Script {
+        lock_script(9)
+    }
+
+    fn seller_script() -> Script {
+        lock_script(1)
+    }
+
+    fn output(satoshis: u64, lock_script: Script) -> TxOut {
+        TxOut {
+            satoshis: satoshis as i64,
+            lock_script,
+        }
+    }
+
+    /// A bid of `bid` by the bidder `n`, paying `satoshis` to the auction and `refund` to the bidder `refunded`
+    fn bid_tx(bid: u64, n: u8, satoshis: u64, refund: u64, refunded: u8) -> Tx {
+        tx(
+            0,
+            vec![
+                output(satoshis, auction_script()),
+                output(0, auction_state_script(bid, &Hash160([n; 20]))),
+                output(refund, lock_script(refunded)),
+            ],
+        )
+    }
+
+    fn settle_tx(lock_time: u32, satoshis: u64) -> Tx {
+        tx(
+            lock_time,
+            vec![
+                output(satoshis, seller_script()),
+                output(0, auction_state_script(0, &Hash160([0; 20]))),
+                output(0, lock_script(0)),
+            ],
+        )
+    }
+
+    fn tx(lock_time: u32, outputs: Vec<TxOut>) -> Tx {
+        Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xfffffffe,
+            }],
+            outputs,
+            lock_time,
+        }
+    }
+
+    /// Spend the auction with current bid `bid` by the bidder `n` with `tx`
+    fn test_predicate(bid: u64, n: u8, tx: Tx) -> bool {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let locking_data =
+            ByteArrayVar::<AUCTION_STATE_SIZE, F, Config>::new_input(cs.clone(), || {
+                Ok(ByteArray::new(auction_state(bid, &Hash160([n; 20]))))
+            })
+            .unwrap();
+        let tx_var = TxVar::<F, Config>::new_witness(cs.clone(), || Ok(tx)).unwrap();
+        let prev_lock_script =
+            ScriptVar::<F>::new_witness(cs.clone(), || Ok(auction_script())).unwrap();
+        Auction::<F, Config>::new(seller_script(), DEADLINE, MIN_INCREMENT, 0)
+            .unwrap()
+            .enforce_constraints_with_context(
+                cs.clone(),
+                &locking_data,
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &BitcoinUnitVar::default(),
+                &SpendingContext {
+                    prev_lock_script: Some(&prev_lock_script),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_auction_bid_is_ok() {
+        assert!(test_predicate(1000, 2, bid_tx(1100, 3, 1100, 1000, 2)));
+        // First bid: no refund
+        assert!(test_predicate(0, 0, bid_tx(100, 3, 100, 0, 4)));
+    }
+
+    #[test]
+    fn test_auction_bid_fails() {
+        // Increment too small
+        assert!(!test_predicate(1000, 2, bid_tx(1099, 3, 1100, 1000, 2)));
+        // Auction output underfunded
+        assert!(!test_predicate(1000, 2, bid_tx(1100, 3, 1099, 1000, 2)));
+        // Previous bidder not refunded
+        assert!(!test_predicate(1000, 2, bid_tx(1100, 3, 1100, 999, 2)));
+        assert!(!test_predicate(1000, 2, bid_tx(1100, 3, 1100, 1000, 4)));
+        // Auction not re-created
+        let mut tx = bid_tx(1100, 3, 1100, 1000, 2);
+        tx.outputs[0].lock_script = lock_script(4);
+        assert!(!test_predicate(1000, 2, tx));
+    }
+
+    #[test]
+    fn test_auction_settlement() {
+        assert!(test_predicate(1000, 2, settle_tx(DEADLINE, 1000)));
+        // Before the deadline
+        assert!(!test_predicate(1000, 2, settle_tx(DEADLINE - 1, 1000)));
+        // The seller is not paid the bid
+        assert!(!test_predicate(1000, 2, settle_tx(DEADLINE, 999)));
+    }
+
+    #[test]
+    fn test_auction_without_refund_output() {
+        assert!(matches!(
+            Auction::<F, NoRefundConfig>::new(seller_script(), DEADLINE, MIN_INCREMENT, 0),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+}
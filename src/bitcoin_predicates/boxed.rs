@@ -0,0 +1,781 @@
+//! Type-erased Bitcoin Predicates
+//!
+//! [BoxedPredicate] wraps any [BitcoinPredicate] behind the object-safe [ErasedBitcoinPredicate], so that
+//! predicates can be selected and combined at runtime, e.g., from a configuration file, instead of at compile
+//! time with the [combinators](crate::bitcoin_predicates::combinators) or the [macros](crate::macros). The data of
+//! a [BoxedPredicate] are [DynData], which carry the data of the wrapped predicate together with their conversion
+//! into `Vec<F>`.
+//!
+//! ```ignore
+//! let fix = BoxedPredicate::new(FixedLockScript::<F, Config>::new(lock_script, 0));
+//! let dust = BoxedPredicate::new(DustLimit::<F, Config>::new(546));
+//! let unit = || BitcoinUnit::<F, Config>::default();
+//! let locking_data = vec![fix.locking_data(unit())?, dust.locking_data(unit())?];
+//!
+//! let predicate = BoxedPredicate::all(vec![fix, dust]);
+//! let locking_data = predicate.locking_data(locking_data)?;
+//! ```
+use std::any::Any;
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::{AllocVar, AllocationMode},
+    prelude::Boolean,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+use chain_gang::messages::Tx;
+
+use crate::constraints::tx::{TxVar, TxVarConfig};
+use crate::error::Error;
+use crate::traits::{BitcoinPredicate, PublicInputSize, SpendingContext};
+
+/// Function allocating the R1CS version of the data wrapped in a [DynData]
+type Allocator<F> =
+    dyn Fn(ConstraintSystemRef<F>, AllocationMode) -> Result<Box<dyn Any>, SynthesisError>;
+
+/// Type-erased data of a [BoxedPredicate]: the wrapped data, the function allocating their R1CS version, and
+/// their conversion into `Vec<F>`
+#[derive(Clone)]
+pub struct DynData<F: PrimeField> {
+    value: Rc<dyn Any>,
+    allocator: Rc<Allocator<F>>,
+    elements: Vec<F>,
+}
+
+impl<F: PrimeField> DynData<F> {
+    fn new<T: 'static, V: AllocVar<T, F> + 'static>(value: T, elements: Vec<F>) -> Self {
+        let value = Rc::new(value);
+        let allocated = value.clone();
+        Self {
+            value,
+            allocator: Rc::new(move |cs: ConstraintSystemRef<F>, mode: AllocationMode| {
+                Ok(Box::new(V::new_variable(cs, || Ok(allocated.as_ref()), mode)?) as Box<dyn Any>)
+            }),
+            elements,
+        }
+    }
+
+    /// The data of a combination of [BoxedPredicate]s: the list of the data of the combined predicates
+    fn from_list(list: Vec<DynData<F>>) -> Self {
+        let elements = list
+            .iter()
+            .flat_map(|data| data.elements.iter().cloned())
+            .collect();
+        let allocated = list.clone();
+        Self {
+            value: Rc::new(list),
+            allocator: Rc::new(move |cs: ConstraintSystemRef<F>, mode: AllocationMode| {
+                let vars = allocated
+                    .iter()
+                    .map(|data| DynDataVar::<F>::new_variable(cs.clone(), || Ok(data), mode))
+                    .collect::<Result<Vec<DynDataVar<F>>, SynthesisError>>()?;
+                Ok(Box::new(vars) as Box<dyn Any>)
+            }),
+            elements,
+        }
+    }
+
+    /// The wrapped data, if they are of type `T`
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        self.value.downcast_ref::<T>()
+    }
+
+    /// The conversion into `Vec<F>` of the wrapped data
+    pub fn elements(&self) -> &[F] {
+        &self.elements
+    }
+}
+
+impl<F: PrimeField> From<DynData<F>> for Vec<F> {
+    fn from(data: DynData<F>) -> Self {
+        data.elements
+    }
+}
+
+/// The number of elements of type-erased data is only known at runtime, see [BoxedPredicate::locking_data_len]
+/// and [BoxedPredicate::unlocking_data_len], which check it when the data are wrapped. Type-erased data do not add
+/// any element to the static count: the constant is `0`, and the public input of a circuit with a [BoxedPredicate]
+/// must be sized through the methods of the predicate.
+impl<F: PrimeField> PublicInputSize<F> for DynData<F> {
+    const N_PUBLIC_INPUTS: usize = 0;
+}
+
+/// The R1CS version of [DynData]
+pub struct DynDataVar<F: PrimeField> {
+    value: Box<dyn Any>,
+    _phantom_field: PhantomData<F>,
+}
+
+impl<F: PrimeField> DynDataVar<F> {
+    /// The wrapped R1CS variable, if it is of type `T`
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        self.value.downcast_ref::<T>()
+    }
+}
+
+/// The wrapped data are allocated by the allocator of the [DynData], so they must be set, also when generating the
+/// parameters of the circuit
+impl<F: PrimeField> AllocVar<DynData<F>, F> for DynDataVar<F> {
+    fn new_variable<T: Borrow<DynData<F>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let data: DynData<F> = f().map(|data| data.borrow().clone())?;
+        Ok(Self {
+            value: (data.allocator)(cs, mode)?,
+            _phantom_field: PhantomData,
+        })
+    }
+}
+
+/// Downcast type-erased data, returning [Error::ConfigMismatch] if they are not of type `T`
+fn downcast<'a, T: 'static>(value: &'a dyn Any, data: &str, name: &str) -> Result<&'a T, Error> {
+    value.downcast_ref::<T>().ok_or_else(|| {
+        Error::ConfigMismatch(format!(
+            "The {} is not of the type expected by the predicate: {}",
+            data, name
+        ))
+    })
+}
+
+/// Object-safe version of [BitcoinPredicate], implemented by all the predicates whose data are `'static`.
+/// The data are wrapped in [DynData] and [DynDataVar].
+pub trait ErasedBitcoinPredicate<F: PrimeField, P: TxVarConfig + Clone> {
+    /// See [BitcoinPredicate::name]
+    fn erased_name(&self) -> &'static str;
+
+    /// See [BitcoinPredicate::locking_data_len]
+    fn erased_locking_data_len(&self) -> usize;
+
+    /// See [BitcoinPredicate::unlocking_data_len]
+    fn erased_unlocking_data_len(&self) -> usize;
+
+    /// Wrap the locking data of the predicate, returning [Error::ConfigMismatch] if `value` has the wrong type
+    fn erase_locking_data(&self, value: Box<dyn Any>) -> Result<DynData<F>, Error>;
+
+    /// Wrap the unlocking data of the predicate, returning [Error::ConfigMismatch] if `value` has the wrong type
+    fn erase_unlocking_data(&self, value: Box<dyn Any>) -> Result<DynData<F>, Error>;
+
+    /// Wrap the witness of the predicate, returning [Error::ConfigMismatch] if `value` has the wrong type
+    fn erase_witness(&self, value: Box<dyn Any>) -> Result<DynData<F>, Error>;
+
+    /// See [BitcoinPredicate::generate_constraints_with_context]
+    fn erased_generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &DynDataVar<F>,
+        unlocking_data: &DynDataVar<F>,
+        spending_data: &TxVar<F, P>,
+        witness: &DynDataVar<F>,
+        context: &SpendingContext<F>,
+    ) -> Result<Boolean<F>, SynthesisError>;
+
+    /// See [BitcoinPredicate::evaluate_native]
+    fn erased_evaluate_native(
+        &self,
+        locking_data: &DynData<F>,
+        unlocking_data: &DynData<F>,
+        tx: &Tx,
+        witness: &DynData<F>,
+    ) -> Result<bool, Error>;
+}
+
+impl<F, P, B> ErasedBitcoinPredicate<F, P> for B
+where
+    F: PrimeField,
+    P: TxVarConfig + Clone,
+    B: BitcoinPredicate<F, P>,
+    B::LockingData: 'static,
+    B::UnlockingData: 'static,
+    B::Witness: 'static,
+    B::LockingDataVar: 'static,
+    B::UnlockingDataVar: 'static,
+    B::WitnessVar: 'static,
+{
+    fn erased_name(&self) -> &'static str {
+        self.name()
+    }
+
+    fn erased_locking_data_len(&self) -> usize {
+        self.locking_data_len()
+    }
+
+    fn erased_unlocking_data_len(&self) -> usize {
+        self.unlocking_data_len()
+    }
+
+    fn erase_locking_data(&self, value: Box<dyn Any>) -> Result<DynData<F>, Error> {
+        let value = downcast::<B::LockingData>(value.as_ref(), "locking data", self.name())?;
+        Ok(DynData::new::<_, B::LockingDataVar>(
+            value.clone(),
+            value.clone().into(),
+        ))
+    }
+
+    fn erase_unlocking_data(&self, value: Box<dyn Any>) -> Result<DynData<F>, Error> {
+        let value = downcast::<B::UnlockingData>(value.as_ref(), "unlocking data", self.name())?;
+        Ok(DynData::new::<_, B::UnlockingDataVar>(
+            value.clone(),
+            value.clone().into(),
+        ))
+    }
+
+    fn erase_witness(&self, value: Box<dyn Any>) -> Result<DynData<F>, Error> {
+        let value = downcast::<B::Witness>(value.as_ref(), "witness", self.name())?;
+        Ok(DynData::new::<_, B::WitnessVar>(value.clone(), Vec::new()))
+    }
+
+    fn erased_generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &DynDataVar<F>,
+        unlocking_data: &DynDataVar<F>,
+        spending_data: &TxVar<F, P>,
+        witness: &DynDataVar<F>,
+        context: &SpendingContext<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        self.generate_constraints_with_context(
+            cs,
+            downcast(locking_data.value.as_ref(), "locking data", self.name())?,
+            downcast(unlocking_data.value.as_ref(), "unlocking data", self.name())?,
+            spending_data,
+            downcast(witness.value.as_ref(), "witness", self.name())?,
+            context,
+        )
+    }
+
+    fn erased_evaluate_native(
+        &self,
+        locking_data: &DynData<F>,
+        unlocking_data: &DynData<F>,
+        tx: &Tx,
+        witness: &DynData<F>,
+    ) -> Result<bool, Error> {
+        self.evaluate_native(
+            downcast(locking_data.value.as_ref(), "locking data", self.name())?,
+            downcast(unlocking_data.value.as_ref(), "unlocking data", self.name())?,
+            tx,
+            downcast(witness.value.as_ref(), "witness", self.name())?,
+        )
+    }
+}
+
+/// Combination of [BoxedPredicate]s: all of them must hold if `is_and`, at least one otherwise.
+/// Its data are the lists of the data of the combined predicates.
+struct ErasedCombination<F: PrimeField, P: TxVarConfig + Clone> {
+    predicates: Vec<BoxedPredicate<F, P>>,
+    is_and: bool,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> ErasedCombination<F, P> {
+    /// Downcast the list of data, checking that there is one for each predicate
+    fn list<'a, T: 'static>(&self, value: &'a dyn Any, data: &str) -> Result<&'a Vec<T>, Error> {
+        let list = downcast::<Vec<T>>(value, data, self.erased_name())?;
+        if list.len() != self.predicates.len() {
+            return Err(Error::ConfigMismatch(format!(
+                "The number of {} items: {} is different from the number of predicates: {}",
+                data,
+                list.len(),
+                self.predicates.len()
+            )));
+        }
+        Ok(list)
+    }
+
+    /// Wrap the lists of data, using `erase` to wrap the data of each predicate
+    fn erase_list(
+        &self,
+        value: Box<dyn Any>,
+        data: &str,
+        erase: impl Fn(&BoxedPredicate<F, P>, DynData<F>) -> Result<DynData<F>, Error>,
+    ) -> Result<DynData<F>, Error> {
+        let list = self.list::<DynData<F>>(value.as_ref(), data)?;
+        Ok(DynData::from_list(
+            self.predicates
+                .iter()
+                .zip(list.iter())
+                .map(|(predicate, data)| erase(predicate, data.clone()))
+                .collect::<Result<Vec<DynData<F>>, Error>>()?,
+        ))
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> ErasedBitcoinPredicate<F, P>
+    for ErasedCombination<F, P>
+{
+    fn erased_name(&self) -> &'static str {
+        if self.is_and { "All" } else { "Any" }
+    }
+
+    fn erased_locking_data_len(&self) -> usize {
+        self.predicates
+            .iter()
+            .map(|predicate| predicate.predicate.erased_locking_data_len())
+            .sum()
+    }
+
+    fn erased_unlocking_data_len(&self) -> usize {
+        self.predicates
+            .iter()
+            .map(|predicate| predicate.predicate.erased_unlocking_data_len())
+            .sum()
+    }
+
+    fn erase_locking_data(&self, value: Box<dyn Any>) -> Result<DynData<F>, Error> {
+        self.erase_list(value, "locking data", |predicate, data| {
+            predicate.check_data(&data, predicate.predicate.erased_locking_data_len())
+        })
+    }
+
+    fn erase_unlocking_data(&self, value: Box<dyn Any>) -> Result<DynData<F>, Error> {
+        self.erase_list(value, "unlocking data", |predicate, data| {
+            predicate.check_data(&data, predicate.predicate.erased_unlocking_data_len())
+        })
+    }
+
+    fn erase_witness(&self, value: Box<dyn Any>) -> Result<DynData<F>, Error> {
+        self.erase_list(value, "witness", |_, data| Ok(data))
+    }
+
+    fn erased_generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &DynDataVar<F>,
+        unlocking_data: &DynDataVar<F>,
+        spending_data: &TxVar<F, P>,
+        witness: &DynDataVar<F>,
+        context: &SpendingContext<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let locking_data =
+            self.list::<DynDataVar<F>>(locking_data.value.as_ref(), "locking data")?;
+        let unlocking_data =
+            self.list::<DynDataVar<F>>(unlocking_data.value.as_ref(), "unlocking data")?;
+        let witness = self.list::<DynDataVar<F>>(witness.value.as_ref(), "witness")?;
+
+        let mut results: Vec<Boolean<F>> = Vec::with_capacity(self.predicates.len());
+        for (j, predicate) in self.predicates.iter().enumerate() {
+            results.push(predicate.predicate.erased_generate_constraints(
+                cs.clone(),
+                &locking_data[j],
+                &unlocking_data[j],
+                spending_data,
+                &witness[j],
+                context,
+            )?);
+        }
+
+        if self.is_and {
+            Boolean::<F>::kary_and(&results)
+        } else {
+            Boolean::<F>::kary_or(&results)
+        }
+    }
+
+    fn erased_evaluate_native(
+        &self,
+        locking_data: &DynData<F>,
+        unlocking_data: &DynData<F>,
+        tx: &Tx,
+        witness: &DynData<F>,
+    ) -> Result<bool, Error> {
+        let locking_data = self.list::<DynData<F>>(locking_data.value.as_ref(), "locking data")?;
+        let unlocking_data =
+            self.list::<DynData<F>>(unlocking_data.value.as_ref(), "unlocking data")?;
+        let witness = self.list::<DynData<F>>(witness.value.as_ref(), "witness")?;
+
+        let mut results: Vec<bool> = Vec::with_capacity(self.predicates.len());
+        for (j, predicate) in self.predicates.iter().enumerate() {
+            results.push(predicate.predicate.erased_evaluate_native(
+                &locking_data[j],
+                &unlocking_data[j],
+                tx,
+                &witness[j],
+            )?);
+        }
+
+        if self.is_and {
+            Ok(results.iter().all(|result| *result))
+        } else {
+            Ok(results.iter().any(|result| *result))
+        }
+    }
+}
+
+/// Bitcoin Predicate wrapping a type-erased predicate, see [ErasedBitcoinPredicate]. It can itself be wrapped,
+/// e.g., by the [combinators](crate::bitcoin_predicates::combinators).
+pub struct BoxedPredicate<F: PrimeField, P: TxVarConfig + Clone> {
+    pub predicate: Box<dyn ErasedBitcoinPredicate<F, P>>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BoxedPredicate<F, P> {
+    pub fn new<B: BitcoinPredicate<F, P> + 'static>(predicate: B) -> Self
+    where
+        B::LockingData: 'static,
+        B::UnlockingData: 'static,
+        B::Witness: 'static,
+        B::LockingDataVar: 'static,
+        B::UnlockingDataVar: 'static,
+        B::WitnessVar: 'static,
+    {
+        Self {
+            predicate: Box::new(predicate),
+        }
+    }
+
+    /// Bitcoin Predicate enforcing that all the `predicates` hold. Its data are `Vec<DynData<F>>`, with the data
+    /// of each predicate, wrapped by [BoxedPredicate::locking_data] and the like
+    pub fn all(predicates: Vec<BoxedPredicate<F, P>>) -> Self {
+        Self {
+            predicate: Box::new(ErasedCombination {
+                predicates,
+                is_and: true,
+            }),
+        }
+    }
+
+    /// Bitcoin Predicate enforcing that at least one of the `predicates` holds, see [BoxedPredicate::all]
+    pub fn any(predicates: Vec<BoxedPredicate<F, P>>) -> Self {
+        Self {
+            predicate: Box::new(ErasedCombination {
+                predicates,
+                is_and: false,
+            }),
+        }
+    }
+
+    /// Wrap the locking data of the predicate, returning [Error::ConfigMismatch] if `value` has the wrong type
+    pub fn locking_data<T: Any>(&self, value: T) -> Result<DynData<F>, Error> {
+        self.predicate.erase_locking_data(Box::new(value))
+    }
+
+    /// Wrap the unlocking data of the predicate, returning [Error::ConfigMismatch] if `value` has the wrong type
+    pub fn unlocking_data<T: Any>(&self, value: T) -> Result<DynData<F>, Error> {
+        self.predicate.erase_unlocking_data(Box::new(value))
+    }
+
+    /// Wrap the witness of the predicate, returning [Error::ConfigMismatch] if `value` has the wrong type
+    pub fn witness<T: Any>(&self, value: T) -> Result<DynData<F>, Error> {
+        self.predicate.erase_witness(Box::new(value))
+    }
+
+    /// Check that `data`, wrapped for a combination, have the length expected by the predicate
+    fn check_data(&self, data: &DynData<F>, len: usize) -> Result<DynData<F>, Error> {
+        if data.elements.len() != len {
+            return Err(Error::ConfigMismatch(format!(
+                "The length of the data: {} is different from the one expected by the predicate {}: {}",
+                data.elements.len(),
+                self.predicate.erased_name(),
+                len
+            )));
+        }
+        Ok(data.clone())
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for BoxedPredicate<F, P> {
+    type LockingData = DynData<F>;
+    type UnlockingData = DynData<F>;
+    type Witness = DynData<F>;
+
+    type LockingDataVar = DynDataVar<F>;
+    type UnlockingDataVar = DynDataVar<F>;
+    type WitnessVar = DynDataVar<F>;
+
+    fn name(&self) -> &'static str {
+        self.predicate.erased_name()
+    }
+
+    fn locking_data_len(&self) -> usize {
+        self.predicate.erased_locking_data_len()
+    }
+
+    fn unlocking_data_len(&self) -> usize {
+        self.predicate.erased_unlocking_data_len()
+    }
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        self.generate_constraints_with_context(
+            cs,
+            locking_data,
+            unlocking_data,
+            spending_data,
+            witness,
+            &SpendingContext::default(),
+        )
+    }
+
+    fn generate_constraints_with_context(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+        context: &SpendingContext<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        self.predicate.erased_generate_constraints(
+            cs,
+            locking_data,
+            unlocking_data,
+            spending_data,
+            witness,
+            context,
+        )
+    }
+
+    fn evaluate_native(
+        &self,
+        locking_data: &Self::LockingData,
+        unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        self.predicate
+            .erased_evaluate_native(locking_data, unlocking_data, tx, witness)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::messages::{Tx, TxOut};
+    use chain_gang::script::Script;
+
+    use crate::bitcoin_predicates::combinators::{And, TupleDataVar};
+    use crate::bitcoin_predicates::data_structures::{field_array::FieldArray, unit::BitcoinUnit};
+    use crate::bitcoin_predicates::dust_limit::DustLimit;
+    use crate::bitcoin_predicates::fixed_lock_script::FixedLockScript;
+    use crate::bitcoin_predicates::not::Not;
+    use crate::bitcoin_predicates::percentage_split::{BASIS_POINTS, PercentageSplit};
+    use crate::constraints::tx::{TxVar, TxVarConfig};
+    use crate::error::Error;
+    use crate::traits::{BitcoinPredicate, PublicInputSize};
+
+    use super::{BoxedPredicate, DynData, DynDataVar, ErasedBitcoinPredicate};
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 0;
+        const N_OUTPUTS: usize = 2;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[1, 1];
+    }
+
+    fn spending_tx(scripts: [u8; 2], amount: i64) -> Tx {
+        Tx {
+            version: 2,
+            inputs: vec![],
+            outputs: scripts
+                .iter()
+                .map(|script| TxOut {
+                    satoshis: amount,
+                    lock_script: Script(vec![*script]),
+                })
+                .collect(),
+            lock_time: 0,
+        }
+    }
+
+    fn unit() -> BitcoinUnit<F, Config> {
+        BitcoinUnit::<F, Config>::default()
+    }
+
+    fn boxed(script: u8, index: usize) -> BoxedPredicate<F, Config> {
+        BoxedPredicate::new(FixedLockScript::<F, Config>::new(
+            Script(vec![script]),
+            index,
+        ))
+    }
+
+    fn dust_limit() -> BoxedPredicate<F, Config> {
+        BoxedPredicate::new(DustLimit::<F, Config>::new(546))
+    }
+
+    /// Wrap the data of a combination of predicates with unit data
+    fn units(predicates: &[BoxedPredicate<F, Config>]) -> [Vec<DynData<F>>; 3] {
+        [
+            predicates
+                .iter()
+                .map(|predicate| predicate.locking_data(unit()).unwrap())
+                .collect(),
+            predicates
+                .iter()
+                .map(|predicate| predicate.unlocking_data(unit()).unwrap())
+                .collect(),
+            predicates
+                .iter()
+                .map(|predicate| predicate.witness(unit()).unwrap())
+                .collect(),
+        ]
+    }
+
+    fn test_predicate(predicate: BoxedPredicate<F, Config>, data: [DynData<F>; 3], tx: Tx) -> bool {
+        let [locking_data, unlocking_data, witness] = data;
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx.clone())).unwrap();
+        let locking_data_var = DynDataVar::new_input(cs.clone(), || Ok(&locking_data)).unwrap();
+        let unlocking_data_var = DynDataVar::new_input(cs.clone(), || Ok(&unlocking_data)).unwrap();
+        let witness_var = DynDataVar::new_witness(cs.clone(), || Ok(&witness)).unwrap();
+        predicate
+            .enforce_constraints(
+                cs.clone(),
+                &locking_data_var,
+                &unlocking_data_var,
+                &tx_var,
+                &witness_var,
+            )
+            .unwrap();
+
+        let is_satisfied = cs.is_satisfied().unwrap();
+        assert_eq!(
+            predicate
+                .evaluate_native(&locking_data, &unlocking_data, &tx, &witness)
+                .unwrap(),
+            is_satisfied
+        );
+        is_satisfied
+    }
+
+    fn test_combination(is_and: bool, scripts: [u8; 2], amount: i64) -> bool {
+        let predicates = vec![boxed(0, 0), boxed(1, 1), dust_limit()];
+        let [locking_data, unlocking_data, witness] = units(&predicates);
+        let predicate = if is_and {
+            BoxedPredicate::all(predicates)
+        } else {
+            BoxedPredicate::any(predicates)
+        };
+        let data = [
+            predicate.locking_data(locking_data).unwrap(),
+            predicate.unlocking_data(unlocking_data).unwrap(),
+            predicate.witness(witness).unwrap(),
+        ];
+        test_predicate(predicate, data, spending_tx(scripts, amount))
+    }
+
+    #[test]
+    fn test_boxed_predicate() {
+        let predicate = boxed(0, 0);
+        let data = || {
+            [
+                predicate.locking_data(unit()).unwrap(),
+                predicate.unlocking_data(unit()).unwrap(),
+                predicate.witness(unit()).unwrap(),
+            ]
+        };
+        assert!(test_predicate(
+            boxed(0, 0),
+            data(),
+            spending_tx([0, 1], 1000)
+        ));
+        assert!(!test_predicate(
+            boxed(0, 0),
+            data(),
+            spending_tx([1, 1], 1000)
+        ));
+    }
+
+    #[test]
+    fn test_all() {
+        assert!(test_combination(true, [0, 1], 1000));
+        assert!(!test_combination(true, [0, 0], 1000));
+        assert!(!test_combination(true, [0, 1], 100));
+    }
+
+    #[test]
+    fn test_any() {
+        assert!(test_combination(false, [0, 0], 100));
+        assert!(test_combination(false, [2, 2], 1000));
+        assert!(!test_combination(false, [2, 2], 100));
+    }
+
+    #[test]
+    fn test_wrapped_boxed_predicate() {
+        // A BoxedPredicate combined with a negated BoxedPredicate
+        let predicate = And((boxed(0, 0), Not::new(boxed(1, 1))));
+        assert_eq!(predicate.locking_data_len(), 0);
+        assert_eq!(predicate.unlocking_data_len(), 0);
+
+        let is_satisfied = |tx: Tx| {
+            let (first, second) = (&(predicate.0).0, &(predicate.0).1.predicate);
+            let cs = ConstraintSystem::<F>::new_ref();
+            let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+            let alloc = |data: [DynData<F>; 2]| {
+                let [data_0, data_1] = data;
+                TupleDataVar((
+                    DynDataVar::new_input(cs.clone(), || Ok(&data_0)).unwrap(),
+                    DynDataVar::new_input(cs.clone(), || Ok(&data_1)).unwrap(),
+                ))
+            };
+            predicate
+                .enforce_constraints(
+                    cs.clone(),
+                    &alloc([
+                        first.locking_data(unit()).unwrap(),
+                        second.locking_data(unit()).unwrap(),
+                    ]),
+                    &alloc([
+                        first.unlocking_data(unit()).unwrap(),
+                        second.unlocking_data(unit()).unwrap(),
+                    ]),
+                    &tx_var,
+                    &alloc([
+                        first.witness(unit()).unwrap(),
+                        second.witness(unit()).unwrap(),
+                    ]),
+                )
+                .unwrap();
+            cs.is_satisfied().unwrap()
+        };
+
+        assert!(is_satisfied(spending_tx([0, 0], 1000)));
+        assert!(!is_satisfied(spending_tx([0, 1], 1000)));
+        assert!(!is_satisfied(spending_tx([1, 0], 1000)));
+    }
+
+    #[test]
+    fn test_wrong_data() {
+        let predicate = boxed(0, 0);
+        assert!(matches!(
+            predicate.locking_data(FieldArray::<1, F, Config>::default()),
+            Err(Error::ConfigMismatch(_))
+        ));
+
+        let predicate = BoxedPredicate::all(vec![boxed(0, 0), dust_limit()]);
+        assert!(matches!(
+            predicate.locking_data(vec![boxed(0, 0).locking_data(unit()).unwrap()]),
+            Err(Error::ConfigMismatch(_))
+        ));
+        assert_eq!(predicate.predicate.erased_locking_data_len(), 0);
+    }
+
+    #[test]
+    fn test_dyn_data_size() {
+        let predicate = BoxedPredicate::new(
+            PercentageSplit::<1, F, Config>::new([Script(vec![0])], [BASIS_POINTS]).unwrap(),
+        );
+        let locking_data = predicate
+            .locking_data(FieldArray::<1, F, Config>::new([F::from(1000u64)]))
+            .unwrap();
+
+        assert_eq!(<DynData<F> as PublicInputSize<F>>::N_PUBLIC_INPUTS, 0);
+        assert_eq!(predicate.locking_data_len(), 1);
+        assert_eq!(locking_data.elements().len(), predicate.locking_data_len());
+    }
+}
@@ -0,0 +1,255 @@
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    prelude::{Boolean, ToBytesGadget},
+    uint8::UInt8,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use chain_gang::messages::{Tx, TxIn, TxOut};
+
+use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+use crate::constraints::{
+    tx::{TxVar, TxVarConfig},
+    txin::TxInVar,
+    txout::TxOutVar,
+};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+use crate::util::bytes_is_less_or_equal;
+
+/// The BIP69 sorting key of an input: the previous transaction ID in reversed byte order, i.e., as it is displayed,
+/// followed by the big endian previous index
+fn input_key<F: PrimeField>(input: &TxInVar<F>) -> Result<Vec<UInt8<F>>, SynthesisError> {
+    let mut key: Vec<UInt8<F>> = input.prev_output.prev_tx.0.iter().rev().cloned().collect();
+    key.extend(
+        input
+            .prev_output
+            .prev_index
+            .to_bytes_le()?
+            .into_iter()
+            .rev(),
+    );
+    Ok(key)
+}
+
+/// The BIP69 sorting key of an output: the big endian amount, followed by the locking script
+fn output_key<F: PrimeField>(output: &TxOutVar<F>) -> Result<Vec<UInt8<F>>, SynthesisError> {
+    let mut key: Vec<UInt8<F>> = output.satoshis.to_bytes_le()?.into_iter().rev().collect();
+    key.extend_from_slice(&output.lock_script.0);
+    Ok(key)
+}
+
+/// Native counterpart of [input_key]
+fn native_input_key(input: &TxIn) -> Vec<u8> {
+    let mut key: Vec<u8> = input.prev_output.hash.0.iter().rev().cloned().collect();
+    key.extend(input.prev_output.index.to_be_bytes());
+    key
+}
+
+/// Native counterpart of [output_key]
+fn native_output_key(output: &TxOut) -> Vec<u8> {
+    let mut key: Vec<u8> = (output.satoshis as u64).to_be_bytes().to_vec();
+    key.extend_from_slice(&output.lock_script.0);
+    key
+}
+
+/// Bitcoin Predicate enforcing that the spending transaction is in the canonical form of BIP69: the inputs are
+/// sorted by previous transaction ID (in reversed byte order) and then by previous index, and the outputs are
+/// sorted by amount and then lexicographically by locking script.
+pub struct CanonicalOrdering<F: PrimeField, P: TxVarConfig + Clone> {
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> CanonicalOrdering<F, P> {
+    pub fn new() -> Self {
+        Self {
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> Default for CanonicalOrdering<F, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for CanonicalOrdering<F, P> {
+    type LockingData = BitcoinUnit<F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = BitcoinUnitVar<F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        _cs: ConstraintSystemRef<F>,
+        _locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let input_keys = spending_data
+            .inputs
+            .iter()
+            .map(input_key)
+            .collect::<Result<Vec<_>, SynthesisError>>()?;
+        let output_keys = spending_data
+            .outputs
+            .iter()
+            .map(output_key)
+            .collect::<Result<Vec<_>, SynthesisError>>()?;
+
+        // Sorting is checked on consecutive pairs
+        let mut checks: Vec<Boolean<F>> = Vec::new();
+        for keys in [input_keys, output_keys] {
+            for pair in keys.windows(2) {
+                checks.push(bytes_is_less_or_equal(&pair[0], &pair[1])?);
+            }
+        }
+
+        Boolean::<F>::kary_and(&checks)
+    }
+
+    fn evaluate_native(
+        &self,
+        _locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        let input_keys: Vec<Vec<u8>> = tx.inputs.iter().map(native_input_key).collect();
+        let output_keys: Vec<Vec<u8>> = tx.outputs.iter().map(native_output_key).collect();
+
+        Ok([input_keys, output_keys]
+            .iter()
+            .all(|keys| keys.windows(2).all(|pair| pair[0] <= pair[1])))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::script::Script;
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+    use crate::constraints::tx::{TxVar, TxVarConfig};
+    use crate::traits::BitcoinPredicate;
+
+    use super::CanonicalOrdering;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 2;
+        const N_OUTPUTS: usize = 2;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0, 0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[1, 2];
+    }
+
+    /// A transaction ID whose first and last bytes are `first` and `last`
+    fn txid(first: u8, last: u8) -> Hash256 {
+        let mut hash = [0u8; 32];
+        hash[0] = first;
+        hash[31] = last;
+        Hash256(hash)
+    }
+
+    fn test_predicate(outpoints: [(Hash256, u32); 2], outputs: [(u64, Vec<u8>); 2]) -> bool {
+        let tx = Tx {
+            version: 2,
+            inputs: outpoints
+                .into_iter()
+                .map(|(hash, index)| TxIn {
+                    prev_output: OutPoint { hash, index },
+                    unlock_script: Script(vec![]),
+                    sequence: 0xffffffff,
+                })
+                .collect(),
+            outputs: outputs
+                .into_iter()
+                .map(|(amount, script)| TxOut {
+                    satoshis: amount as i64,
+                    lock_script: Script(script),
+                })
+                .collect(),
+            lock_time: 0,
+        };
+
+        let predicate = CanonicalOrdering::<F, Config>::new();
+        let native = predicate
+            .evaluate_native(
+                &BitcoinUnit::default(),
+                &BitcoinUnit::default(),
+                &tx,
+                &BitcoinUnit::default(),
+            )
+            .unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        predicate
+            .enforce_constraints(
+                cs.clone(),
+                &BitcoinUnitVar::default(),
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &BitcoinUnitVar::default(),
+            )
+            .unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), native);
+        native
+    }
+
+    fn sorted_outputs() -> [(u64, Vec<u8>); 2] {
+        [(100, vec![0x51]), (200, vec![0x51, 0x00])]
+    }
+
+    #[test]
+    fn test_canonical_ordering_is_ok() {
+        // Transaction IDs are compared in reversed byte order
+        assert!(test_predicate(
+            [(txid(2, 1), 0), (txid(1, 2), 0)],
+            sorted_outputs()
+        ));
+        // Same transaction ID, sorted by index
+        assert!(test_predicate(
+            [(txid(1, 1), 1), (txid(1, 1), 0x100)],
+            sorted_outputs()
+        ));
+        // Same amount, sorted by locking script
+        assert!(test_predicate(
+            [(txid(1, 1), 0), (txid(1, 1), 1)],
+            [(100, vec![0x51]), (100, vec![0x51, 0x00])]
+        ));
+    }
+
+    #[test]
+    fn test_canonical_ordering_fails() {
+        assert!(!test_predicate(
+            [(txid(1, 2), 0), (txid(2, 1), 0)],
+            sorted_outputs()
+        ));
+        assert!(!test_predicate(
+            [(txid(1, 1), 0x100), (txid(1, 1), 1)],
+            sorted_outputs()
+        ));
+        assert!(!test_predicate(
+            [(txid(1, 1), 0), (txid(1, 1), 1)],
+            [(200, vec![0x51]), (100, vec![0x51, 0x00])]
+        ));
+        assert!(!test_predicate(
+            [(txid(1, 1), 0), (txid(1, 1), 1)],
+            [(100, vec![0x52]), (100, vec![0x51, 0x00])]
+        ));
+    }
+}
@@ -0,0 +1,259 @@
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{eq::EqGadget, prelude::Boolean, uint8::UInt8};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use chain_gang::messages::Tx;
+
+use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+use crate::constraints::tx::{TxVar, TxVarConfig};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+use crate::transaction_integrity_gadget::TransactionIntegrityConfig;
+use crate::util::push_data_prefix;
+
+/// Bitcoin Predicate enforcing that the unlocking script of the input at `input_index` has the layout
+/// `<signature || sighash_flag> <public_key>` (see [checksig_unlock_script](crate::util::checksig_unlock_script)),
+/// with a DER signature of `signature_len` bytes, a public key of `public_key_len` bytes, and `sighash_flag`
+/// equal to `P::SIGHASH_FLAG`. The signature is not verified.
+///
+/// **Note**: The predicate catches the mismatch between the flag of the on-chain signature and the flag
+/// used to compute the integrity tag. As the sighash does not commit to the unlocking scripts, the predicate
+/// is only meaningful if the spending transaction is bound by other means, e.g., through its txid.
+pub struct CheckSigFlag<F: PrimeField, P: TxVarConfig + TransactionIntegrityConfig + Clone> {
+    pub input_index: usize,
+    pub signature_len: usize,
+    pub public_key_len: usize,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + TransactionIntegrityConfig + Clone> CheckSigFlag<F, P> {
+    pub fn new(input_index: usize, signature_len: usize, public_key_len: usize) -> Self {
+        Self {
+            input_index,
+            signature_len,
+            public_key_len,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        }
+    }
+
+    /// The lengths of the data pushed by the unlocking script
+    pub fn push_lengths(&self) -> [usize; 2] {
+        [self.signature_len + 1, self.public_key_len]
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + TransactionIntegrityConfig + Clone> BitcoinPredicate<F, P>
+    for CheckSigFlag<F, P>
+{
+    type LockingData = BitcoinUnit<F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = BitcoinUnitVar<F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        _cs: ConstraintSystemRef<F>,
+        _locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        // Validate input
+        if self.input_index >= spending_data.inputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of inputs: {}",
+                self.input_index,
+                spending_data.inputs.len()
+            ))
+            .into());
+        }
+
+        let unlock_script = &spending_data.inputs[self.input_index].unlock_script;
+        let push_lengths = self.push_lengths();
+        let chunks = unlock_script.split_pushes(&push_lengths)?;
+
+        Boolean::<F>::kary_and(&[
+            unlock_script.has_push_layout(&push_lengths)?,
+            chunks[0][self.signature_len].is_eq(&UInt8::<F>::constant(P::SIGHASH_FLAG))?,
+        ])
+    }
+
+    fn evaluate_native(
+        &self,
+        _locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        // Validate input
+        if self.input_index >= tx.inputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of inputs: {}",
+                self.input_index,
+                tx.inputs.len()
+            )));
+        }
+
+        let unlock_script = &tx.inputs[self.input_index].unlock_script.0;
+        let push_lengths = self.push_lengths();
+        let prefixes = push_lengths
+            .iter()
+            .map(|length| {
+                push_data_prefix(*length).map_err(|error| Error::ConfigMismatch(error.to_string()))
+            })
+            .collect::<Result<Vec<Vec<u8>>, Error>>()?;
+        let declared_len: usize = prefixes
+            .iter()
+            .zip(push_lengths.iter())
+            .map(|(prefix, length)| prefix.len() + length)
+            .sum();
+        if declared_len != unlock_script.len() {
+            return Err(Error::ConfigMismatch(format!(
+                "The declared pushes span {} bytes, but the length of the script is {}",
+                declared_len,
+                unlock_script.len()
+            )));
+        }
+
+        let mut offset: usize = 0;
+        let mut has_push_layout = true;
+        for (prefix, length) in prefixes.iter().zip(push_lengths.iter()) {
+            has_push_layout &= unlock_script[offset..].starts_with(prefix);
+            offset += prefix.len() + length;
+        }
+        let flag = unlock_script[prefixes[0].len() + self.signature_len];
+
+        Ok(has_push_layout && flag == P::SIGHASH_FLAG)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use ark_bls12_381::Fr as F;
+
+    use ark_r1cs_std::alloc::AllocVar;
+
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::address::addr_decode;
+    use chain_gang::script::Script;
+    use chain_gang::transaction::sighash::{SIGHASH_ALL, SIGHASH_FORKID, SIGHASH_SINGLE};
+
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::network::Network;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+    use crate::constraints::tx::TxVar;
+    use crate::transaction_integrity_gadget::TransactionIntegrityConfig;
+    use crate::util::checksig_unlock_script;
+
+    use crate::{constraints::tx::TxVarConfig, traits::BitcoinPredicate};
+
+    use super::CheckSigFlag;
+
+    const SIGNATURE_LEN: usize = 71;
+    const PUBLIC_KEY_LEN: usize = 33;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[1 + SIGNATURE_LEN + 1 + 1 + PUBLIC_KEY_LEN];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    impl TransactionIntegrityConfig for Config {
+        const N_INPUT: usize = 0;
+        const LEN_PREV_LOCK_SCRIPT: usize = 0x19;
+        const SIGHASH_FLAG: u8 = SIGHASH_ALL | SIGHASH_FORKID;
+    }
+
+    fn test_predicate(unlock_script: Script, expected: bool) {
+        let addr = "mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec";
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script,
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 100,
+                lock_script: p2pkh::create_lock_script(&hash160),
+            }],
+            lock_time: 0,
+        };
+
+        let predicate = CheckSigFlag::<F, Config>::new(0, SIGNATURE_LEN, PUBLIC_KEY_LEN);
+        assert_eq!(
+            predicate
+                .evaluate_native(
+                    &BitcoinUnit::default(),
+                    &BitcoinUnit::default(),
+                    &tx,
+                    &BitcoinUnit::default(),
+                )
+                .unwrap(),
+            expected
+        );
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        predicate
+            .enforce_constraints(
+                cs.clone(),
+                &BitcoinUnitVar::default(),
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &BitcoinUnitVar::default(),
+            )
+            .unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_checksig_flag_is_ok() {
+        test_predicate(
+            checksig_unlock_script(
+                &[0x30; SIGNATURE_LEN],
+                SIGHASH_ALL | SIGHASH_FORKID,
+                &[0x02; PUBLIC_KEY_LEN],
+            ),
+            true,
+        );
+    }
+
+    #[test]
+    fn test_checksig_flag_fails() {
+        // The signature uses a different flag from the one committed to in the tag
+        test_predicate(
+            checksig_unlock_script(
+                &[0x30; SIGNATURE_LEN],
+                SIGHASH_SINGLE | SIGHASH_FORKID,
+                &[0x02; PUBLIC_KEY_LEN],
+            ),
+            false,
+        );
+        // The unlocking script does not have the expected layout
+        test_predicate(
+            Script(vec![0x30; 1 + SIGNATURE_LEN + 1 + 1 + PUBLIC_KEY_LEN]),
+            false,
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{eq::EqGadget, prelude::Boolean};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use chain_gang::messages::Tx;
+
+use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+use crate::constraints::{
+    outpoint::NULL_OUTPOINT_INDEX,
+    tx::{TxVar, TxVarConfig},
+};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+
+/// Bitcoin Predicate to enforce that the input of the transaction at `input_index` is a coinbase input if
+/// `is_coinbase` is `true`, and that it is not one otherwise, see
+/// [TxInVar::is_coinbase](crate::constraints::txin::TxInVar::is_coinbase)
+pub struct Coinbase<F: PrimeField, P: TxVarConfig + Clone> {
+    pub input_index: usize,
+    pub is_coinbase: bool,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> Coinbase<F, P> {
+    pub fn new(input_index: usize, is_coinbase: bool) -> Self {
+        Self {
+            input_index,
+            is_coinbase,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for Coinbase<F, P> {
+    type LockingData = BitcoinUnit<F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = BitcoinUnitVar<F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        _cs: ConstraintSystemRef<F>,
+        _locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let input = spending_data.input(self.input_index)?;
+
+        input
+            .is_coinbase()?
+            .is_eq(&Boolean::<F>::constant(self.is_coinbase))
+    }
+
+    fn evaluate_native(
+        &self,
+        _locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        // Validate input
+        if self.input_index >= tx.inputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Input index: {} is larger than the number of inputs: {}",
+                self.input_index,
+                tx.inputs.len()
+            )));
+        }
+
+        let prev_output = &tx.inputs[self.input_index].prev_output;
+        let is_coinbase =
+            prev_output.hash.0 == [0u8; 32] && prev_output.index == NULL_OUTPOINT_INDEX;
+        Ok(is_coinbase == self.is_coinbase)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::{R1CSVar, alloc::AllocVar};
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::script::Script;
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::unit::BitcoinUnitVar;
+    use crate::constraints::outpoint::NULL_OUTPOINT_INDEX;
+    use crate::constraints::tx::{TxVar, TxVarConfig};
+    use crate::traits::BitcoinPredicate;
+
+    use super::Coinbase;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[4];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0];
+    }
+
+    fn tx(prev_output: OutPoint) -> Tx {
+        Tx {
+            version: 1,
+            inputs: vec![TxIn {
+                prev_output,
+                // The block height, as required by BIP34 for coinbase inputs
+                unlock_script: Script(vec![0x03, 0x40, 0x0d, 0x03]),
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 312_500_000,
+                lock_script: Script(vec![]),
+            }],
+            lock_time: 0,
+        }
+    }
+
+    fn coinbase_tx() -> Tx {
+        tx(OutPoint {
+            hash: Hash256([0; 32]),
+            index: NULL_OUTPOINT_INDEX,
+        })
+    }
+
+    fn regular_tx() -> Tx {
+        tx(OutPoint {
+            hash: Hash256::decode(
+                "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+            )
+            .unwrap(),
+            index: 0,
+        })
+    }
+
+    fn test_predicate(tx: Tx, is_coinbase: bool) -> bool {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        Coinbase::<F, Config>::new(0, is_coinbase)
+            .enforce_constraints(
+                cs.clone(),
+                &BitcoinUnitVar::default(),
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &BitcoinUnitVar::default(),
+            )
+            .unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_coinbase() {
+        assert!(test_predicate(coinbase_tx(), true));
+        assert!(!test_predicate(regular_tx(), true));
+    }
+
+    #[test]
+    fn test_not_coinbase() {
+        assert!(test_predicate(regular_tx(), false));
+        assert!(!test_predicate(coinbase_tx(), false));
+    }
+
+    #[test]
+    fn test_is_coinbase_tx() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        for (tx, expected) in [(coinbase_tx(), true), (regular_tx(), false)] {
+            let tx_var = TxVar::<F, Config>::new_witness(cs.clone(), || Ok(tx)).unwrap();
+            assert_eq!(tx_var.is_coinbase().unwrap().value().unwrap(), expected);
+        }
+    }
+}
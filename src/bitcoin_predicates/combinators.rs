@@ -0,0 +1,336 @@
+//! Generic combinations of Bitcoin Predicates
+//!
+//! [And] and [Or] combine a tuple of predicates sharing the same `F` and `P`, without generating new structs as
+//! [and_combine_predicates](crate::and_combine_predicates) and [or_combine_predicates](crate::or_combine_predicates)
+//! do. The data of the combination is the tuple of the data of the predicates, wrapped in [TupleData]. Tuples of
+//! up to six predicates are supported, and combinations can be nested.
+//!
+//! ```ignore
+//! let predicate = And((
+//!     FixedLockScript::<F, Config>::new(lock_script, 0),
+//!     Or((MinLockTime::<F, Config>::new(lock_time), signed_by)),
+//! ));
+//! ```
+use std::borrow::Borrow;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::{AllocVar, AllocationMode},
+    fields::fp::FpVar,
+    prelude::Boolean,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+use chain_gang::messages::Tx;
+
+use crate::constraints::tx::{TxVar, TxVarConfig};
+use crate::error::Error;
+use crate::traits::{BitcoinPredicate, PublicInputSize, SpendingContext, ToPublicInputGadget};
+
+/// The data of a combination of predicates: the tuple of the data of the combined predicates
+#[derive(Clone)]
+pub struct TupleData<T>(pub T);
+
+/// The R1CS version of [TupleData]
+pub struct TupleDataVar<T>(pub T);
+
+/// Bitcoin Predicate enforcing that all the predicates in the tuple hold
+pub struct And<T>(pub T);
+
+/// Bitcoin Predicate enforcing that at least one of the predicates in the tuple holds
+pub struct Or<T>(pub T);
+
+macro_rules! impl_tuple_data {
+    ($($B:ident . $idx:tt),+) => {
+        paste::paste! {
+            impl<F: PrimeField, $($B: Into<Vec<F>>,)+> From<TupleData<($($B,)+)>> for Vec<F> {
+                fn from(data: TupleData<($($B,)+)>) -> Vec<F> {
+                    let mut out = Vec::<F>::new();
+                    $(
+                        out.extend_from_slice(&Into::<Vec<F>>::into((data.0).$idx));
+                    )+
+                    out
+                }
+            }
+
+            impl<F: PrimeField, $($B: PublicInputSize<F>,)+> PublicInputSize<F> for TupleData<($($B,)+)> {
+                const N_PUBLIC_INPUTS: usize = 0 $(+ <$B as PublicInputSize<F>>::N_PUBLIC_INPUTS)+;
+            }
+
+            impl<F: PrimeField, $($B: ToPublicInputGadget<F>,)+> ToPublicInputGadget<F>
+                for TupleDataVar<($($B,)+)>
+            {
+                fn to_public_input(&self) -> Result<Vec<FpVar<F>>, SynthesisError> {
+                    let mut out = Vec::<FpVar<F>>::new();
+                    $(
+                        out.extend_from_slice(&(self.0).$idx.to_public_input()?);
+                    )+
+                    Ok(out)
+                }
+            }
+
+            impl<F: PrimeField, $($B, [<$B Var>]: AllocVar<$B, F>,)+> AllocVar<TupleData<($($B,)+)>, F>
+                for TupleDataVar<($([<$B Var>],)+)>
+            {
+                fn new_variable<T: Borrow<TupleData<($($B,)+)>>>(
+                    cs: impl Into<Namespace<F>>,
+                    f: impl FnOnce() -> Result<T, SynthesisError>,
+                    mode: AllocationMode,
+                ) -> Result<Self, SynthesisError> {
+                    let ns = cs.into();
+                    let cs = ns.cs();
+
+                    let data = f()?;
+                    let data: &TupleData<($($B,)+)> = data.borrow();
+                    Ok(Self((
+                        $(
+                            [<$B Var>]::new_variable(cs.clone(), || Ok(&(data.0).$idx), mode)?,
+                        )+
+                    )))
+                }
+            }
+        }
+    };
+}
+
+macro_rules! impl_combinator {
+    ($combinator:ident, $combine:ident, $combine_native:ident, $($B:ident . $idx:tt),+) => {
+        impl<F: PrimeField, P: TxVarConfig + Clone, $($B: BitcoinPredicate<F, P>,)+> BitcoinPredicate<F, P>
+            for $combinator<($($B,)+)>
+        {
+            type LockingData = TupleData<($($B::LockingData,)+)>;
+            type UnlockingData = TupleData<($($B::UnlockingData,)+)>;
+            type Witness = TupleData<($($B::Witness,)+)>;
+
+            type LockingDataVar = TupleDataVar<($($B::LockingDataVar,)+)>;
+            type UnlockingDataVar = TupleDataVar<($($B::UnlockingDataVar,)+)>;
+            type WitnessVar = TupleDataVar<($($B::WitnessVar,)+)>;
+
+            fn locking_data_len(&self) -> usize {
+                0 $(+ (self.0).$idx.locking_data_len())+
+            }
+
+            fn unlocking_data_len(&self) -> usize {
+                0 $(+ (self.0).$idx.unlocking_data_len())+
+            }
+
+            fn generate_constraints(
+                &self,
+                cs: ConstraintSystemRef<F>,
+                locking_data: &Self::LockingDataVar,
+                unlocking_data: &Self::UnlockingDataVar,
+                spending_data: &TxVar<F, P>,
+                witness: &Self::WitnessVar,
+            ) -> Result<Boolean<F>, SynthesisError> {
+                self.generate_constraints_with_context(
+                    cs,
+                    locking_data,
+                    unlocking_data,
+                    spending_data,
+                    witness,
+                    &SpendingContext::default(),
+                )
+            }
+
+            fn generate_constraints_with_context(
+                &self,
+                cs: ConstraintSystemRef<F>,
+                locking_data: &Self::LockingDataVar,
+                unlocking_data: &Self::UnlockingDataVar,
+                spending_data: &TxVar<F, P>,
+                witness: &Self::WitnessVar,
+                context: &SpendingContext<F>,
+            ) -> Result<Boolean<F>, SynthesisError> {
+                Boolean::<F>::$combine(&[
+                    $(
+                        (self.0).$idx.generate_constraints_with_context(
+                            cs.clone(),
+                            &(locking_data.0).$idx,
+                            &(unlocking_data.0).$idx,
+                            spending_data,
+                            &(witness.0).$idx,
+                            context,
+                        )?,
+                    )+
+                ])
+            }
+
+            fn evaluate_native(
+                &self,
+                locking_data: &Self::LockingData,
+                unlocking_data: &Self::UnlockingData,
+                tx: &Tx,
+                witness: &Self::Witness,
+            ) -> Result<bool, Error> {
+                let results = [
+                    $(
+                        (self.0).$idx.evaluate_native(
+                            &(locking_data.0).$idx,
+                            &(unlocking_data.0).$idx,
+                            tx,
+                            &(witness.0).$idx,
+                        )?,
+                    )+
+                ];
+                Ok(results.iter().$combine_native(|result| *result))
+            }
+        }
+    };
+}
+
+macro_rules! impl_tuple_combinators {
+    ($($B:ident . $idx:tt),+) => {
+        impl_tuple_data!($($B . $idx),+);
+        impl_combinator!(And, kary_and, all, $($B . $idx),+);
+        impl_combinator!(Or, kary_or, any, $($B . $idx),+);
+    };
+}
+
+impl_tuple_combinators!(B0.0, B1.1);
+impl_tuple_combinators!(B0.0, B1.1, B2.2);
+impl_tuple_combinators!(B0.0, B1.1, B2.2, B3.3);
+impl_tuple_combinators!(B0.0, B1.1, B2.2, B3.3, B4.4);
+impl_tuple_combinators!(B0.0, B1.1, B2.2, B3.3, B4.4, B5.5);
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_relations::r1cs::{ConstraintSystem, ConstraintSystemRef};
+    use chain_gang::messages::{Tx, TxOut};
+    use chain_gang::script::Script;
+
+    use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+    use crate::bitcoin_predicates::fixed_lock_script::FixedLockScript;
+    use crate::bitcoin_predicates::not::Not;
+    use crate::constraints::tx::{TxVar, TxVarConfig};
+    use crate::traits::{BitcoinPredicate, PublicInputSize};
+
+    use super::{And, Or, TupleData, TupleDataVar};
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 0;
+        const N_OUTPUTS: usize = 2;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[1, 1];
+    }
+
+    type Unit = BitcoinUnit<F, Config>;
+    type UnitVar = BitcoinUnitVar<F, Config>;
+
+    fn spending_tx(scripts: [u8; 2]) -> Tx {
+        Tx {
+            version: 2,
+            inputs: vec![],
+            outputs: scripts
+                .iter()
+                .map(|script| TxOut {
+                    satoshis: 100,
+                    lock_script: Script(vec![*script]),
+                })
+                .collect(),
+            lock_time: 0,
+        }
+    }
+
+    fn spending_tx_var(cs: ConstraintSystemRef<F>, scripts: [u8; 2]) -> TxVar<F, Config> {
+        TxVar::<F, Config>::new_input(cs, || Ok(spending_tx(scripts))).unwrap()
+    }
+
+    fn fix(script: u8, index: usize) -> FixedLockScript<F, Config> {
+        FixedLockScript::<F, Config>::new(Script(vec![script]), index)
+    }
+
+    fn not(script: u8, index: usize) -> Not<F, Config, FixedLockScript<F, Config>> {
+        Not::new(fix(script, index))
+    }
+
+    fn test_predicate<B>(predicate: B, scripts: [u8; 2]) -> bool
+    where
+        B: BitcoinPredicate<
+                F,
+                Config,
+                LockingData = TupleData<(Unit, Unit)>,
+                UnlockingData = TupleData<(Unit, Unit)>,
+                Witness = TupleData<(Unit, Unit)>,
+            >,
+    {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = spending_tx_var(cs.clone(), scripts);
+        let data = || Ok(TupleData((Unit::default(), Unit::default())));
+        let locking_data = B::LockingDataVar::new_input(cs.clone(), data).unwrap();
+        let unlocking_data = B::UnlockingDataVar::new_input(cs.clone(), data).unwrap();
+        let witness = B::WitnessVar::new_witness(cs.clone(), data).unwrap();
+        predicate
+            .enforce_constraints(
+                cs.clone(),
+                &locking_data,
+                &unlocking_data,
+                &tx_var,
+                &witness,
+            )
+            .unwrap();
+
+        let is_satisfied = cs.is_satisfied().unwrap();
+        let data = TupleData((Unit::default(), Unit::default()));
+        assert_eq!(
+            predicate
+                .evaluate_native(&data, &data, &spending_tx(scripts), &data)
+                .unwrap(),
+            is_satisfied
+        );
+        is_satisfied
+    }
+
+    #[test]
+    fn test_and_is_ok() {
+        assert!(test_predicate(And((fix(0, 0), fix(1, 1))), [0, 1]));
+        assert!(test_predicate(And((fix(0, 0), not(0, 1))), [0, 1]));
+    }
+
+    #[test]
+    fn test_and_fails() {
+        assert!(!test_predicate(And((fix(0, 0), fix(1, 1))), [0, 0]));
+        assert!(!test_predicate(And((fix(0, 0), not(1, 1))), [0, 1]));
+    }
+
+    #[test]
+    fn test_or_is_ok() {
+        assert!(test_predicate(Or((fix(0, 0), fix(1, 1))), [0, 0]));
+        assert!(test_predicate(Or((fix(0, 0), fix(1, 1))), [1, 1]));
+    }
+
+    #[test]
+    fn test_or_fails() {
+        assert!(!test_predicate(Or((fix(0, 0), fix(1, 1))), [1, 2]));
+    }
+
+    #[test]
+    fn test_nested_combination() {
+        let predicate = || Or((And((fix(0, 0), fix(1, 1))), fix(2, 1), fix(3, 0)));
+        for (scripts, expected) in [
+            ([0, 1], true),
+            ([5, 2], true),
+            ([3, 5], true),
+            ([0, 0], false),
+        ] {
+            let cs = ConstraintSystem::<F>::new_ref();
+            let tx_var = spending_tx_var(cs.clone(), scripts);
+            let units = || TupleDataVar((UnitVar::default(), UnitVar::default()));
+            let data = || TupleDataVar((units(), UnitVar::default(), UnitVar::default()));
+            predicate()
+                .enforce_constraints(cs.clone(), &data(), &data(), &tx_var, &data())
+                .unwrap();
+            assert_eq!(cs.is_satisfied().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_public_input_size() {
+        assert_eq!(
+            <TupleData<(Unit, Unit, Unit)> as PublicInputSize<F>>::N_PUBLIC_INPUTS,
+            3 * <Unit as PublicInputSize<F>>::N_PUBLIC_INPUTS
+        );
+    }
+}
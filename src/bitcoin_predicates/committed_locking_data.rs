@@ -0,0 +1,309 @@
+//! Commit-and-open mode for the locking data
+//!
+//! [CommittedLockingData] wraps a Bitcoin Predicate so that its locking data is not exposed as a public input:
+//! only its Poseidon commitment is, as a single field element, while the raw locking data becomes part of the
+//! witness and the opening of the commitment is checked in the circuit. The size of the public input of the
+//! verifier is then independent of the size of the locking data of the predicate.
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use ark_crypto_primitives::sponge::{
+    CryptographicSponge,
+    constraints::CryptographicSpongeVar,
+    poseidon::{PoseidonConfig, PoseidonSponge, constraints::PoseidonSpongeVar},
+};
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::{AllocVar, AllocationMode},
+    eq::EqGadget,
+    prelude::Boolean,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+use chain_gang::messages::Tx;
+
+use crate::bitcoin_predicates::data_structures::field_array::{FieldArray, FieldArrayVar};
+use crate::constraints::tx::{TxVar, TxVarConfig};
+use crate::error::Error;
+use crate::traits::{BitcoinPredicate, SpendingContext, ToPublicInputGadget};
+
+/// The commitment to `locking_data`: the Poseidon hash of its conversion into `Vec<F>`
+pub fn commit_locking_data<F: PrimeField, P: TxVarConfig + Clone, L: Into<Vec<F>>>(
+    locking_data: L,
+    poseidon_config: &PoseidonConfig<F>,
+) -> FieldArray<1, F, P> {
+    let elements: Vec<F> = locking_data.into();
+    let mut sponge = PoseidonSponge::<F>::new(poseidon_config);
+    sponge.absorb(&elements);
+
+    FieldArray::new([sponge.squeeze_field_elements::<F>(1)[0]])
+}
+
+/// The witness of [CommittedLockingData]: the opening of the commitment and the witness of the wrapped predicate
+#[derive(Clone)]
+pub struct LockingDataOpening<L, W> {
+    pub locking_data: L,
+    pub witness: W,
+}
+
+/// The R1CS version of [LockingDataOpening]
+pub struct LockingDataOpeningVar<LV, WV> {
+    pub locking_data: LV,
+    pub witness: WV,
+}
+
+impl<F, L, W, LV, WV> AllocVar<LockingDataOpening<L, W>, F> for LockingDataOpeningVar<LV, WV>
+where
+    F: PrimeField,
+    LV: AllocVar<L, F>,
+    WV: AllocVar<W, F>,
+{
+    fn new_variable<T: Borrow<LockingDataOpening<L, W>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let data = f()?;
+        let opening: &LockingDataOpening<L, W> = data.borrow();
+        Ok(Self {
+            locking_data: LV::new_variable(cs.clone(), || Ok(&opening.locking_data), mode)?,
+            witness: WV::new_variable(cs.clone(), || Ok(&opening.witness), mode)?,
+        })
+    }
+}
+
+/// Bitcoin Predicate enforcing the wrapped predicate on the locking data committed to in its own locking data,
+/// see [commit_locking_data]
+pub struct CommittedLockingData<F: PrimeField, P: TxVarConfig + Clone, B: BitcoinPredicate<F, P>> {
+    pub predicate: B,
+    pub poseidon_config: PoseidonConfig<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone, B: BitcoinPredicate<F, P>>
+    CommittedLockingData<F, P, B>
+{
+    pub fn new(predicate: B, poseidon_config: PoseidonConfig<F>) -> Self {
+        Self {
+            predicate,
+            poseidon_config,
+            _phantom_config: PhantomData,
+        }
+    }
+}
+
+impl<F, P, B> BitcoinPredicate<F, P> for CommittedLockingData<F, P, B>
+where
+    F: PrimeField,
+    P: TxVarConfig + Clone,
+    B: BitcoinPredicate<F, P>,
+    B::LockingDataVar: ToPublicInputGadget<F>,
+{
+    type LockingData = FieldArray<1, F, P>;
+    type UnlockingData = B::UnlockingData;
+    type Witness = LockingDataOpening<B::LockingData, B::Witness>;
+
+    type LockingDataVar = FieldArrayVar<1, F, P>;
+    type UnlockingDataVar = B::UnlockingDataVar;
+    type WitnessVar = LockingDataOpeningVar<B::LockingDataVar, B::WitnessVar>;
+
+    fn locking_data_len(&self) -> usize {
+        1
+    }
+
+    fn unlocking_data_len(&self) -> usize {
+        self.predicate.unlocking_data_len()
+    }
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        self.generate_constraints_with_context(
+            cs,
+            locking_data,
+            unlocking_data,
+            spending_data,
+            witness,
+            &SpendingContext::default(),
+        )
+    }
+
+    fn generate_constraints_with_context(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+        context: &SpendingContext<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        // Open the commitment, as in [commit_locking_data]
+        let mut sponge = PoseidonSpongeVar::<F>::new(cs.clone(), &self.poseidon_config);
+        sponge.absorb(&witness.locking_data.to_public_input()?)?;
+        let computed_commitment = sponge.squeeze_field_elements(1)?;
+
+        Boolean::<F>::kary_and(&[
+            locking_data.elements[0].is_eq(&computed_commitment[0])?,
+            self.predicate.generate_constraints_with_context(
+                cs.clone(),
+                &witness.locking_data,
+                unlocking_data,
+                spending_data,
+                &witness.witness,
+                context,
+            )?,
+        ])
+    }
+
+    fn evaluate_native(
+        &self,
+        locking_data: &Self::LockingData,
+        unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        let computed_commitment =
+            commit_locking_data::<F, P, _>(witness.locking_data.clone(), &self.poseidon_config);
+        let is_satisfied = self.predicate.evaluate_native(
+            &witness.locking_data,
+            unlocking_data,
+            tx,
+            &witness.witness,
+        )?;
+
+        Ok(locking_data.elements[0] == computed_commitment.elements[0] && is_satisfied)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::address::addr_decode;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::network::Network;
+    use chain_gang::script::Script;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::consolidation::Consolidation;
+    use crate::bitcoin_predicates::data_structures::{
+        field_array::{FieldArray, FieldArrayVar},
+        unit::{BitcoinUnit, BitcoinUnitVar},
+    };
+    use crate::constraints::tx::{TxVar, TxVarConfig};
+    use crate::traits::BitcoinPredicate;
+    use crate::transaction_integrity_gadget::poseidon::poseidon_config;
+
+    use super::{
+        CommittedLockingData, LockingDataOpening, LockingDataOpeningVar, commit_locking_data,
+    };
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 2;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0, 0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    type InnerPredicate = Consolidation<2, F, Config>;
+    type Predicate = CommittedLockingData<F, Config, InnerPredicate>;
+
+    fn lock_script() -> Script {
+        let hash160 = addr_decode("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec", Network::BSV_Testnet)
+            .unwrap()
+            .0;
+        p2pkh::create_lock_script(&hash160)
+    }
+
+    fn tx() -> Tx {
+        let input = |index| TxIn {
+            prev_output: OutPoint {
+                hash: Hash256::decode(
+                    "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                )
+                .unwrap(),
+                index,
+            },
+            unlock_script: Script(vec![]),
+            sequence: 0xffffffff,
+        };
+        Tx {
+            version: 2,
+            inputs: vec![input(0), input(1)],
+            outputs: vec![TxOut {
+                satoshis: 2900,
+                lock_script: lock_script(),
+            }],
+            lock_time: 0,
+        }
+    }
+
+    fn amounts(amounts: [u64; 2]) -> FieldArray<2, F, Config> {
+        FieldArray::new(amounts.map(F::from))
+    }
+
+    /// Commit to `committed_amounts`, open to `opened_amounts`
+    fn test_predicate(committed_amounts: [u64; 2], opened_amounts: [u64; 2]) -> bool {
+        let config = poseidon_config::<F>();
+        let commitment = commit_locking_data::<F, Config, _>(amounts(committed_amounts), &config);
+        let opening = LockingDataOpening {
+            locking_data: amounts(opened_amounts),
+            witness: BitcoinUnit::<F, Config>::default(),
+        };
+
+        let predicate = Predicate::new(InnerPredicate::new(lock_script(), 200).unwrap(), config);
+        let native = predicate
+            .evaluate_native(&commitment, &BitcoinUnit::default(), &tx(), &opening)
+            .unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let locking_data =
+            FieldArrayVar::<1, F, Config>::new_input(cs.clone(), || Ok(commitment)).unwrap();
+        let witness = LockingDataOpeningVar::<
+            FieldArrayVar<2, F, Config>,
+            BitcoinUnitVar<F, Config>,
+        >::new_witness(cs.clone(), || Ok(opening))
+        .unwrap();
+        let tx_var = TxVar::<F, Config>::new_witness(cs.clone(), || Ok(tx())).unwrap();
+
+        predicate
+            .enforce_constraints(
+                cs.clone(),
+                &locking_data,
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &witness,
+            )
+            .unwrap();
+
+        // The locking data is a single public input, instead of one per amount
+        assert_eq!(cs.num_instance_variables() - 1, 1);
+
+        assert_eq!(cs.is_satisfied().unwrap(), native);
+        native
+    }
+
+    #[test]
+    fn test_committed_locking_data_is_ok() {
+        assert!(test_predicate([1000, 2000], [1000, 2000]));
+    }
+
+    #[test]
+    fn test_committed_locking_data_fails() {
+        // The opening does not match the commitment
+        assert!(!test_predicate([1000, 2000], [2000, 1000]));
+        // The opening matches the commitment, but the predicate is not satisfied
+        assert!(!test_predicate([1000, 1000], [1000, 1000]));
+    }
+}
@@ -0,0 +1,294 @@
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    eq::EqGadget,
+    fields::{FieldVar, fp::FpVar},
+    prelude::{AllocVar, Boolean},
+    select::CondSelectGadget,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+
+use chain_gang::{messages::Tx, script::Script};
+
+use crate::bitcoin_predicates::data_structures::{
+    field_array::{FieldArray, FieldArrayVar},
+    unit::{BitcoinUnit, BitcoinUnitVar},
+};
+use crate::constraints::{
+    script::ScriptVar,
+    tx::{TxVar, TxVarConfig},
+};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+use crate::util::{fits_in_bits, is_less_or_equal, to_fp, to_u64};
+
+/// Bit size bounding the sum of the amounts of the inputs: the amounts are 64-bit numbers, and there are
+/// less than `2^8` of them
+const SUM_BIT_SIZE: usize = 72;
+
+/// Bitcoin Predicate enforcing that the spending transaction consolidates `N_INPUTS` outputs locked by `lock_script`:
+/// the transaction must have a single output, locked by `lock_script`, whose amount is equal to the sum of
+/// the amounts of the inputs minus a fee of at most `max_fee` satoshis.
+///
+/// The locking data is the array of the amounts of the outputs being spent, in the order of the inputs.
+pub struct Consolidation<const N_INPUTS: usize, F: PrimeField, P: TxVarConfig + Clone> {
+    pub lock_script: Script,
+    pub max_fee: u64,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<const N_INPUTS: usize, F: PrimeField, P: TxVarConfig + Clone> Consolidation<N_INPUTS, F, P> {
+    /// Returns [Error::ConfigMismatch] if `N_INPUTS` is different from the number of inputs set in `P`, or if `P`
+    /// does not set a single output
+    pub fn new(lock_script: Script, max_fee: u64) -> Result<Self, Error> {
+        if N_INPUTS != P::N_INPUTS {
+            return Err(Error::ConfigMismatch(format!(
+                "The number of amounts: {} is different from the number of inputs set in the parameters: {}",
+                N_INPUTS,
+                P::N_INPUTS
+            )));
+        }
+        if N_INPUTS >= 1 << (SUM_BIT_SIZE - 64) {
+            return Err(Error::ConfigMismatch(format!(
+                "The number of inputs: {} is too large",
+                N_INPUTS
+            )));
+        }
+        if P::N_OUTPUTS != 1 {
+            return Err(Error::ConfigMismatch(format!(
+                "A consolidation transaction must have a single output, but the parameters set {} outputs",
+                P::N_OUTPUTS
+            )));
+        }
+        Ok(Self {
+            lock_script,
+            max_fee,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        })
+    }
+}
+
+impl<const N_INPUTS: usize, F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P>
+    for Consolidation<N_INPUTS, F, P>
+{
+    type LockingData = FieldArray<N_INPUTS, F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = FieldArrayVar<N_INPUTS, F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let output = spending_data.output(0)?;
+
+        // The amounts are public inputs, so they must be range checked before being used in comparisons
+        let mut checks: Vec<Boolean<F>> = Vec::with_capacity(N_INPUTS + 3);
+        let mut total = FpVar::<F>::zero();
+        for amount in locking_data.elements.iter() {
+            checks.push(fits_in_bits(amount, 64)?);
+            total += amount;
+        }
+
+        // Compute the fee, set to zero if the output exceeds the inputs
+        let output_amount = to_fp(&output.satoshis)?;
+        let is_output_bounded = is_less_or_equal(&output_amount, &total, SUM_BIT_SIZE)?;
+        let fee = FpVar::<F>::conditionally_select(
+            &is_output_bounded,
+            &(&total - &output_amount),
+            &FpVar::<F>::zero(),
+        )?;
+
+        checks.push(is_output_bounded);
+        checks.push(is_less_or_equal(
+            &fee,
+            &FpVar::<F>::constant(F::from(self.max_fee)),
+            SUM_BIT_SIZE,
+        )?);
+        checks.push(output.lock_script.is_eq(&ScriptVar::<F>::new_constant(
+            cs.clone(),
+            self.lock_script.clone(),
+        )?)?);
+
+        Boolean::<F>::kary_and(&checks)
+    }
+
+    fn evaluate_native(
+        &self,
+        locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        let output = tx.outputs.first().ok_or_else(|| {
+            Error::IndexOutOfBounds(format!(
+                "Index: 0 is larger than the number of outputs: {}",
+                tx.outputs.len()
+            ))
+        })?;
+
+        let mut total: u128 = 0;
+        for amount in locking_data.elements.iter() {
+            let Some(amount) = to_u64(amount) else {
+                return Ok(false);
+            };
+            total += amount as u128;
+        }
+
+        let output_amount = output.satoshis as u64 as u128;
+        Ok(output_amount <= total
+            && total - output_amount <= self.max_fee as u128
+            && output.lock_script.0 == self.lock_script.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use ark_bls12_381::Fr as F;
+
+    use ark_r1cs_std::alloc::AllocVar;
+
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::address::addr_decode;
+    use chain_gang::script::Script;
+
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::network::Network;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::field_array::{FieldArray, FieldArrayVar};
+    use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+    use crate::constraints::tx::TxVar;
+    use crate::error::Error;
+
+    use crate::{constraints::tx::TxVarConfig, traits::BitcoinPredicate};
+
+    use super::Consolidation;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 3;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0, 0, 0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    const MAX_FEE: u64 = 50;
+
+    fn p2pkh_script(addr: &str) -> Script {
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        p2pkh::create_lock_script(&hash160)
+    }
+
+    fn test_predicate(amounts: [F; 3], output_amount: u64, lock_script: Script, expected: bool) {
+        let tx = Tx {
+            version: 2,
+            inputs: (0..3)
+                .map(|i| TxIn {
+                    prev_output: OutPoint {
+                        hash: Hash256::decode(
+                            "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                        )
+                        .unwrap(),
+                        index: i,
+                    },
+                    unlock_script: Script(vec![]),
+                    sequence: 0xffffffff,
+                })
+                .collect(),
+            outputs: vec![TxOut {
+                satoshis: output_amount as i64,
+                lock_script,
+            }],
+            lock_time: 0,
+        };
+
+        let predicate = Consolidation::<3, F, Config>::new(
+            p2pkh_script("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec"),
+            MAX_FEE,
+        )
+        .unwrap();
+        assert_eq!(
+            predicate
+                .evaluate_native(
+                    &FieldArray::<3, F, Config>::new(amounts),
+                    &BitcoinUnit::default(),
+                    &tx,
+                    &BitcoinUnit::default(),
+                )
+                .unwrap(),
+            expected
+        );
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let amounts_var = FieldArrayVar::<3, F, Config>::new_input(cs.clone(), || {
+            Ok(FieldArray::<3, F, Config>::new(amounts))
+        })
+        .unwrap();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        predicate
+            .enforce_constraints(
+                cs.clone(),
+                &amounts_var,
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &BitcoinUnitVar::default(),
+            )
+            .unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), expected);
+    }
+
+    fn amounts(amounts: [u64; 3]) -> [F; 3] {
+        amounts.map(F::from)
+    }
+
+    #[test]
+    fn test_consolidation_is_ok() {
+        let lock_script = p2pkh_script("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec");
+        test_predicate(amounts([100, 200, 300]), 600, lock_script.clone(), true);
+        test_predicate(amounts([100, 200, 300]), 550, lock_script, true);
+    }
+
+    #[test]
+    fn test_consolidation_fails() {
+        let lock_script = p2pkh_script("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec");
+        // Fee too high
+        test_predicate(amounts([100, 200, 300]), 549, lock_script.clone(), false);
+        // Output larger than the inputs
+        test_predicate(amounts([100, 200, 300]), 601, lock_script.clone(), false);
+        // Output to a different script
+        test_predicate(
+            amounts([100, 200, 300]),
+            600,
+            p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz"),
+            false,
+        );
+        // Amounts which are not 64-bit numbers: the sum wraps around the modulus
+        let large = F::from(u64::MAX) * F::from(u64::MAX);
+        test_predicate([large, -large, F::from(600u64)], 600, lock_script, false);
+    }
+
+    #[test]
+    fn test_consolidation_wrong_number_of_inputs() {
+        assert!(matches!(
+            Consolidation::<2, F, Config>::new(
+                p2pkh_script("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec"),
+                MAX_FEE
+            ),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+}
@@ -0,0 +1,237 @@
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    eq::EqGadget,
+    fields::{FieldVar, fp::FpVar},
+    prelude::Boolean,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use chain_gang::messages::Tx;
+
+use crate::bitcoin_predicates::data_structures::{
+    field_array::{FieldArray, FieldArrayVar},
+    unit::{BitcoinUnit, BitcoinUnitVar},
+};
+use crate::constraints::tx::{TxVar, TxVarConfig};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+use crate::util::to_fp;
+
+/// Bitcoin Predicate enforcing that the locking script of the output of the spending transaction at `index`
+/// contains, at `offset`, an `n_bytes` little endian counter equal to the previous counter plus one.
+///
+/// The locking data is the previous counter. This is the simplest state-transition covenant, see
+/// [StateTransition](crate::bitcoin_predicates::state_transition::StateTransition) for the general case.
+pub struct Counter<F: PrimeField, P: TxVarConfig + Clone> {
+    pub index: usize,
+    pub offset: usize,
+    pub n_bytes: usize,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> Counter<F, P> {
+    /// Returns [Error::ConfigMismatch] if a counter of `n_bytes` bytes does not fit in a field element
+    pub fn new(index: usize, offset: usize, n_bytes: usize) -> Result<Self, Error> {
+        if n_bytes == 0 || 8 * n_bytes >= F::MODULUS_BIT_SIZE as usize {
+            return Err(Error::ConfigMismatch(format!(
+                "The size of the counter: {} bytes is not compatible with the field",
+                n_bytes
+            )));
+        }
+        Ok(Self {
+            index,
+            offset,
+            n_bytes,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        })
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for Counter<F, P> {
+    type LockingData = FieldArray<1, F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = FieldArrayVar<1, F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        _cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        // Validate input
+        if self.index >= spending_data.outputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of outputs: {}",
+                self.index,
+                spending_data.outputs.len()
+            ))
+            .into());
+        }
+        let lock_script = &spending_data.outputs[self.index].lock_script.0;
+        if self.offset + self.n_bytes > lock_script.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "The counter at: {}..{} is not contained in the locking script of length: {}",
+                self.offset,
+                self.offset + self.n_bytes,
+                lock_script.len()
+            ))
+            .into());
+        }
+
+        let counter: FpVar<F> =
+            to_fp(&lock_script[self.offset..self.offset + self.n_bytes].to_vec())?;
+        counter.is_eq(&(&locking_data.elements[0] + FpVar::<F>::one()))
+    }
+
+    fn evaluate_native(
+        &self,
+        locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        // Validate input
+        if self.index >= tx.outputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of outputs: {}",
+                self.index,
+                tx.outputs.len()
+            )));
+        }
+        let lock_script = &tx.outputs[self.index].lock_script.0;
+        if self.offset + self.n_bytes > lock_script.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "The counter at: {}..{} is not contained in the locking script of length: {}",
+                self.offset,
+                self.offset + self.n_bytes,
+                lock_script.len()
+            )));
+        }
+
+        let counter =
+            F::from_le_bytes_mod_order(&lock_script[self.offset..self.offset + self.n_bytes]);
+        Ok(counter == locking_data.elements[0] + F::one())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::script::Script;
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::{
+        field_array::{FieldArray, FieldArrayVar},
+        unit::{BitcoinUnit, BitcoinUnitVar},
+    };
+    use crate::constraints::tx::{TxVar, TxVarConfig};
+    use crate::error::Error;
+    use crate::traits::BitcoinPredicate;
+
+    use super::Counter;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[6];
+    }
+
+    /// Locking script `<counter> OP_DROP`, with a 4-byte counter
+    fn lock_script(counter: u32) -> Script {
+        let mut script = vec![0x04];
+        script.extend(counter.to_le_bytes());
+        script.push(0x75);
+        Script(script)
+    }
+
+    fn test_predicate(prev_counter: u64, counter: u32) -> bool {
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 1,
+                lock_script: lock_script(counter),
+            }],
+            lock_time: 0,
+        };
+
+        let predicate = Counter::<F, Config>::new(0, 1, 4).unwrap();
+        let native = predicate
+            .evaluate_native(
+                &FieldArray::<1, F, Config>::new([F::from(prev_counter)]),
+                &BitcoinUnit::default(),
+                &tx,
+                &BitcoinUnit::default(),
+            )
+            .unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let locking_data = FieldArrayVar::<1, F, Config>::new_input(cs.clone(), || {
+            Ok(FieldArray::<1, F, Config>::new([F::from(prev_counter)]))
+        })
+        .unwrap();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        predicate
+            .enforce_constraints(
+                cs.clone(),
+                &locking_data,
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &BitcoinUnitVar::default(),
+            )
+            .unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), native);
+        native
+    }
+
+    #[test]
+    fn test_counter_is_ok() {
+        assert!(test_predicate(0, 1));
+        assert!(test_predicate(0xff, 0x100));
+    }
+
+    #[test]
+    fn test_counter_fails() {
+        assert!(!test_predicate(1, 1));
+        assert!(!test_predicate(1, 3));
+        // The counter cannot overflow
+        assert!(!test_predicate(u32::MAX as u64, 0));
+    }
+
+    #[test]
+    fn test_counter_invalid_size() {
+        assert!(matches!(
+            Counter::<F, Config>::new(0, 1, 0),
+            Err(Error::ConfigMismatch(_))
+        ));
+        assert!(matches!(
+            Counter::<F, Config>::new(0, 1, 32),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+}
@@ -0,0 +1,407 @@
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    eq::EqGadget,
+    fields::fp::FpVar,
+    prelude::{AllocVar, Boolean},
+};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+
+use chain_gang::{messages::Tx, script::Script};
+
+use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+use crate::constraints::{
+    script::ScriptVar,
+    tx::{TxVar, TxVarConfig},
+};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+use crate::util::{is_less_or_equal, to_fp};
+
+/// Bitcoin Predicate implementing a crowdfund (assurance contract): the output of the transaction at `index`
+/// must pay at least `goal` satoshis to `beneficiary_script`. The inputs of the transaction are unconstrained.
+///
+/// **Note**: The predicate is meant to be used with the sighash flag `SIGHASH_SINGLE | SIGHASH_ANYONECANPAY`, so
+/// that each pledger only commits to their own input and to the beneficiary output, and other pledgers can add
+/// their inputs to the transaction. With this flag, the output covered by the integrity tag of the pledge at
+/// input `N_INPUT` is the output at index `N_INPUT`, hence `index` should be equal to `N_INPUT`.
+///
+/// For campaigns whose transaction has a single output, use [Crowdfund::single_output] with the sighash flag
+/// `SIGHASH_ALL | SIGHASH_ANYONECANPAY`: each pledger then commits to the whole set of outputs.
+pub struct Crowdfund<F: PrimeField, P: TxVarConfig + Clone> {
+    pub beneficiary_script: Script,
+    pub goal: u64,
+    pub index: usize,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> Crowdfund<F, P> {
+    pub fn new(beneficiary_script: Script, goal: u64, index: usize) -> Self {
+        Self {
+            beneficiary_script,
+            goal,
+            index,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        }
+    }
+
+    /// Crowdfund whose transaction has a single output, paying at least `goal` satoshis to `beneficiary_script`
+    ///
+    /// Returns [Error::ConfigMismatch] if `P` does not set a single output.
+    pub fn single_output(beneficiary_script: Script, goal: u64) -> Result<Self, Error> {
+        if P::N_OUTPUTS != 1 {
+            return Err(Error::ConfigMismatch(format!(
+                "A single output crowdfund must have a single output, but the parameters set {} outputs",
+                P::N_OUTPUTS
+            )));
+        }
+        Ok(Self::new(beneficiary_script, goal, 0))
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for Crowdfund<F, P> {
+    type LockingData = BitcoinUnit<F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = BitcoinUnitVar<F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        _locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let beneficiary_output = spending_data.output(self.index)?;
+
+        Boolean::<F>::kary_and(&[
+            beneficiary_output
+                .lock_script
+                .is_eq(&ScriptVar::<F>::new_constant(
+                    cs.clone(),
+                    self.beneficiary_script.clone(),
+                )?)?,
+            is_less_or_equal(
+                &FpVar::<F>::constant(F::from(self.goal)),
+                &to_fp(&beneficiary_output.satoshis)?,
+                64,
+            )?,
+        ])
+    }
+
+    fn evaluate_native(
+        &self,
+        _locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        // Validate input
+        if self.index >= tx.outputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of outputs: {}",
+                self.index,
+                tx.outputs.len()
+            )));
+        }
+        let beneficiary_output = &tx.outputs[self.index];
+
+        Ok(
+            beneficiary_output.lock_script.0 == self.beneficiary_script.0
+                && beneficiary_output.satoshis as u64 >= self.goal,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use ark_bls12_381::Fr as F;
+
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use chain_gang::address::addr_decode;
+    use chain_gang::script::Script;
+    use chain_gang::transaction::sighash::{
+        SIGHASH_ALL, SIGHASH_ANYONECANPAY, SIGHASH_FORKID, SIGHASH_SINGLE, SigHashCache,
+    };
+
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::network::Network;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::unit::BitcoinUnit;
+    use crate::constraints::tx::TxVarConfig;
+    use crate::error::Error;
+    use crate::reftx::RefTxCircuit;
+    use crate::traits::BitcoinPredicate;
+    use crate::transaction_integrity_gadget::{
+        TransactionIntegrityConfig, TransactionIntegrityScheme,
+        scheme::{PrevOutput, SighashIntegrity, SighashWitness},
+    };
+
+    use super::Crowdfund;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 2;
+        const N_OUTPUTS: usize = 2;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0, 0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19, 0x19];
+    }
+
+    impl TransactionIntegrityConfig for Config {
+        const N_INPUT: usize = 0;
+        const LEN_PREV_LOCK_SCRIPT: usize = 0x19;
+        const SIGHASH_FLAG: u8 = SIGHASH_SINGLE | SIGHASH_ANYONECANPAY | SIGHASH_FORKID;
+    }
+
+    #[derive(Clone)]
+    struct SingleOutputConfig;
+    impl TxVarConfig for SingleOutputConfig {
+        const N_INPUTS: usize = 2;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0, 0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    impl TransactionIntegrityConfig for SingleOutputConfig {
+        const N_INPUT: usize = 0;
+        const LEN_PREV_LOCK_SCRIPT: usize = 0x19;
+        const SIGHASH_FLAG: u8 = SIGHASH_ALL | SIGHASH_ANYONECANPAY | SIGHASH_FORKID;
+    }
+
+    const GOAL: u64 = 1_000_000;
+    const PLEDGE: u64 = 400_000;
+
+    fn p2pkh_script(addr: &str) -> Script {
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        p2pkh::create_lock_script(&hash160)
+    }
+
+    fn beneficiary_script() -> Script {
+        p2pkh_script("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec")
+    }
+
+    /// Transaction with the pledge at input 0 and the beneficiary output at index 0
+    fn crowdfund_tx(other_pledge: &str, beneficiary_amount: u64, change_amount: u64) -> Tx {
+        Tx {
+            version: 2,
+            inputs: vec![
+                TxIn {
+                    prev_output: OutPoint {
+                        hash: Hash256::decode(
+                            "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                        )
+                        .unwrap(),
+                        index: 0,
+                    },
+                    unlock_script: Script(vec![]),
+                    sequence: 0xffffffff,
+                },
+                TxIn {
+                    prev_output: OutPoint {
+                        hash: Hash256::decode(other_pledge).unwrap(),
+                        index: 1,
+                    },
+                    unlock_script: Script(vec![]),
+                    sequence: 0xffffffff,
+                },
+            ],
+            outputs: vec![
+                TxOut {
+                    satoshis: beneficiary_amount as i64,
+                    lock_script: beneficiary_script(),
+                },
+                TxOut {
+                    satoshis: change_amount as i64,
+                    lock_script: p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz"),
+                },
+            ],
+            lock_time: 0,
+        }
+    }
+
+    /// Commit to the pledge in `pledge_tx` and prove the predicate against `final_tx`
+    fn test_crowdfund(pledge_tx: Tx, final_tx: Tx, expected: bool) {
+        let prev_lock_script = p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz");
+        let tag = TransactionIntegrityScheme::<Config>::commit(
+            &pledge_tx,
+            &prev_lock_script,
+            PLEDGE,
+            &mut SigHashCache::new(),
+        )
+        .unwrap();
+
+        let circuit = RefTxCircuit::<Crowdfund<F, Config>, F, Config> {
+            locking_data: BitcoinUnit::default(),
+            integrity_tag: Some(tag),
+            unlocking_data: BitcoinUnit::default(),
+            witness: BitcoinUnit::default(),
+            spending_data: Some(final_tx),
+            integrity_witness: Some(SighashWitness {
+                prev_output: PrevOutput {
+                    prev_lock_script,
+                    prev_amount: PLEDGE,
+                },
+                sighash_cache: SigHashCache::new(),
+            }),
+            predicate: Crowdfund::new(beneficiary_script(), GOAL, 0),
+            integrity_scheme: SighashIntegrity::new(),
+            setup_mode: false,
+        };
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), expected);
+    }
+
+    const PLEDGE_A: &str = "a671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7";
+    const PLEDGE_B: &str = "b671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7";
+
+    #[test]
+    fn test_crowdfund_is_ok() {
+        test_crowdfund(
+            crowdfund_tx(PLEDGE_A, GOAL, 100),
+            crowdfund_tx(PLEDGE_A, GOAL, 100),
+            true,
+        );
+    }
+
+    #[test]
+    fn test_crowdfund_other_inputs_and_outputs_are_free() {
+        // The tag does not commit to the other inputs, nor to the outputs other than the beneficiary one
+        test_crowdfund(
+            crowdfund_tx(PLEDGE_A, GOAL, 100),
+            crowdfund_tx(PLEDGE_B, GOAL, 200),
+            true,
+        );
+    }
+
+    #[test]
+    fn test_crowdfund_fails() {
+        // The beneficiary output is below the goal
+        test_crowdfund(
+            crowdfund_tx(PLEDGE_A, GOAL - 1, 100),
+            crowdfund_tx(PLEDGE_A, GOAL - 1, 100),
+            false,
+        );
+        // The beneficiary output differs from the one committed to in the tag
+        test_crowdfund(
+            crowdfund_tx(PLEDGE_A, GOAL, 100),
+            crowdfund_tx(PLEDGE_A, GOAL + 1, 100),
+            false,
+        );
+    }
+
+    #[test]
+    fn test_crowdfund_native() {
+        let predicate = Crowdfund::<F, Config>::new(beneficiary_script(), GOAL, 0);
+        for (amount, expected) in [(GOAL, true), (GOAL + 1, true), (GOAL - 1, false)] {
+            assert_eq!(
+                predicate
+                    .evaluate_native(
+                        &BitcoinUnit::default(),
+                        &BitcoinUnit::default(),
+                        &crowdfund_tx(PLEDGE_A, amount, 100),
+                        &BitcoinUnit::default(),
+                    )
+                    .unwrap(),
+                expected
+            );
+        }
+        assert!(matches!(
+            Crowdfund::<F, Config>::new(beneficiary_script(), GOAL, 2).evaluate_native(
+                &BitcoinUnit::default(),
+                &BitcoinUnit::default(),
+                &crowdfund_tx(PLEDGE_A, GOAL, 100),
+                &BitcoinUnit::default(),
+            ),
+            Err(Error::IndexOutOfBounds(_))
+        ));
+    }
+
+    /// Transaction with the pledge at input 0 and the beneficiary output as its only output
+    fn single_output_tx(other_pledge: &str, beneficiary_amount: u64) -> Tx {
+        let mut tx = crowdfund_tx(other_pledge, beneficiary_amount, 0);
+        tx.outputs.truncate(1);
+        tx
+    }
+
+    /// Commit to the pledge in `pledge_tx` and prove the single output predicate against `final_tx`
+    fn test_single_output_crowdfund(pledge_tx: Tx, final_tx: Tx, expected: bool) {
+        let prev_lock_script = p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz");
+        let tag = TransactionIntegrityScheme::<SingleOutputConfig>::commit(
+            &pledge_tx,
+            &prev_lock_script,
+            PLEDGE,
+            &mut SigHashCache::new(),
+        )
+        .unwrap();
+
+        let circuit = RefTxCircuit::<Crowdfund<F, SingleOutputConfig>, F, SingleOutputConfig> {
+            locking_data: BitcoinUnit::default(),
+            integrity_tag: Some(tag),
+            unlocking_data: BitcoinUnit::default(),
+            witness: BitcoinUnit::default(),
+            spending_data: Some(final_tx),
+            integrity_witness: Some(SighashWitness {
+                prev_output: PrevOutput {
+                    prev_lock_script,
+                    prev_amount: PLEDGE,
+                },
+                sighash_cache: SigHashCache::new(),
+            }),
+            predicate: Crowdfund::single_output(beneficiary_script(), GOAL).unwrap(),
+            integrity_scheme: SighashIntegrity::new(),
+            setup_mode: false,
+        };
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_single_output_crowdfund() {
+        test_single_output_crowdfund(
+            single_output_tx(PLEDGE_A, GOAL),
+            single_output_tx(PLEDGE_A, GOAL),
+            true,
+        );
+        // With ANYONECANPAY, the tag does not commit to the other inputs
+        test_single_output_crowdfund(
+            single_output_tx(PLEDGE_A, GOAL),
+            single_output_tx(PLEDGE_B, GOAL),
+            true,
+        );
+        // The output differs from the one committed to in the tag
+        test_single_output_crowdfund(
+            single_output_tx(PLEDGE_A, GOAL),
+            single_output_tx(PLEDGE_A, GOAL + 1),
+            false,
+        );
+        // The output is below the goal
+        test_single_output_crowdfund(
+            single_output_tx(PLEDGE_A, GOAL - 1),
+            single_output_tx(PLEDGE_A, GOAL - 1),
+            false,
+        );
+    }
+
+    #[test]
+    fn test_single_output_crowdfund_with_several_outputs() {
+        assert!(matches!(
+            Crowdfund::<F, Config>::single_output(beneficiary_script(), GOAL),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+}
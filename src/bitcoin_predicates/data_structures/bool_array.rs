@@ -0,0 +1,114 @@
+//! Implement [BoolArray], to be used as a variable in Bitcoin Predicates, e.g., for flags
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::AllocVar,
+    fields::fp::FpVar,
+    prelude::{AllocationMode, Boolean},
+};
+use ark_relations::r1cs::{Namespace, SynthesisError};
+
+use crate::constraints::tx::TxVarConfig;
+use crate::traits::{PublicInputSize, ToPublicInputGadget};
+
+#[derive(Clone)]
+pub struct BoolArray<const N: usize, F: PrimeField, P: TxVarConfig + Clone> {
+    pub elements: [bool; N],
+    _field: PhantomData<F>,
+    _config: PhantomData<P>,
+}
+
+/// Each element is allocated as a field element equal to zero or one
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> From<BoolArray<N, F, P>> for Vec<F> {
+    fn from(value: BoolArray<N, F, P>) -> Self {
+        value
+            .elements
+            .iter()
+            .map(|element| F::from(*element))
+            .collect()
+    }
+}
+
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> PublicInputSize<F>
+    for BoolArray<N, F, P>
+{
+    const N_PUBLIC_INPUTS: usize = N;
+}
+
+/// R1CS version of [BoolArray]: as the elements are allocated as [Boolean]s, they are enforced to be bits
+#[derive(Clone)]
+pub struct BoolArrayVar<const N: usize, F: PrimeField, P: TxVarConfig + Clone> {
+    pub elements: [Boolean<F>; N],
+    _config: PhantomData<P>,
+}
+
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> Default for BoolArray<N, F, P> {
+    fn default() -> Self {
+        Self::new([false; N])
+    }
+}
+
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> BoolArray<N, F, P> {
+    pub fn new(elements: [bool; N]) -> Self {
+        Self {
+            elements,
+            _field: PhantomData,
+            _config: PhantomData,
+        }
+    }
+}
+
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> BoolArrayVar<N, F, P> {
+    pub fn new(elements: [Boolean<F>; N]) -> Self {
+        Self {
+            elements,
+            _config: PhantomData,
+        }
+    }
+}
+
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> AllocVar<BoolArray<N, F, P>, F>
+    for BoolArrayVar<N, F, P>
+{
+    fn new_variable<T: Borrow<BoolArray<N, F, P>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let data: BoolArray<N, F, P> = f().map(|data| data.borrow().clone())?;
+        let mut elements: Vec<Boolean<F>> = Vec::new();
+
+        for element in data.elements.iter() {
+            elements.push(Boolean::<F>::new_variable(
+                cs.clone(),
+                || Ok(element),
+                mode,
+            )?);
+        }
+
+        Ok(Self {
+            elements: elements
+                .try_into()
+                .expect("The length of `elements` is wrong"),
+            _config: PhantomData,
+        })
+    }
+}
+
+/// Each element as a field element, as in the conversion of [BoolArray] into `Vec<F>`
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> ToPublicInputGadget<F>
+    for BoolArrayVar<N, F, P>
+{
+    fn to_public_input(&self) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        Ok(self
+            .elements
+            .iter()
+            .map(|element| FpVar::<F>::from(element.clone()))
+            .collect())
+    }
+}
@@ -3,10 +3,20 @@ use std::borrow::Borrow;
 use std::marker::PhantomData;
 
 use ark_ff::PrimeField;
-use ark_r1cs_std::{alloc::AllocVar, prelude::AllocationMode, uint8::UInt8};
+use ark_r1cs_std::{
+    alloc::AllocVar,
+    convert::{ToBitsGadget, ToBytesGadget},
+    eq::EqGadget,
+    fields::fp::FpVar,
+    prelude::{AllocationMode, Boolean},
+    uint8::UInt8,
+};
 use ark_relations::r1cs::{Namespace, SynthesisError};
 
-use crate::constraints::tx::TxVarConfig;
+use crate::constraints::{script::ScriptVar, tx::TxVarConfig};
+use crate::error::Error;
+use crate::traits::{PublicInputSize, ToPublicInputGadget};
+use crate::util::to_fp_bits;
 
 #[derive(Clone)]
 pub struct ByteArray<const N: usize, F: PrimeField, P: TxVarConfig + Clone> {
@@ -15,16 +25,20 @@ pub struct ByteArray<const N: usize, F: PrimeField, P: TxVarConfig + Clone> {
     _config: PhantomData<P>,
 }
 
+/// Each byte is allocated as its bits, see [to_fp_bits]
 impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> From<ByteArray<N, F, P>> for Vec<F> {
     fn from(value: ByteArray<N, F, P>) -> Self {
-        value
-            .bytes
-            .iter()
-            .map(|byte| F::from_le_bytes_mod_order(&[*byte]))
-            .collect::<Vec<F>>()
+        to_fp_bits(&value.bytes)
     }
 }
 
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> PublicInputSize<F>
+    for ByteArray<N, F, P>
+{
+    const N_PUBLIC_INPUTS: usize = 8 * N;
+}
+
+#[derive(Clone)]
 pub struct ByteArrayVar<const N: usize, F: PrimeField, P: TxVarConfig + Clone> {
     pub bytes: [UInt8<F>; N],
     _config: PhantomData<P>,
@@ -50,6 +64,55 @@ impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> ByteArray<N, F, P> {
     }
 }
 
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> ByteArrayVar<N, F, P> {
+    pub fn new(bytes: [UInt8<F>; N]) -> Self {
+        Self {
+            bytes,
+            _config: PhantomData,
+        }
+    }
+
+    /// The bytes of `self` as a [ScriptVar], e.g., to compare them with a slice of a script
+    pub fn as_script_var(&self) -> ScriptVar<F> {
+        ScriptVar(self.bytes.to_vec())
+    }
+}
+
+/// Returns [Error::ConfigMismatch] if the length of `bytes` is not `N`
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> TryFrom<&[UInt8<F>]>
+    for ByteArrayVar<N, F, P>
+{
+    type Error = SynthesisError;
+
+    fn try_from(bytes: &[UInt8<F>]) -> Result<Self, Self::Error> {
+        let bytes: [UInt8<F>; N] = bytes.to_vec().try_into().map_err(|bytes: Vec<UInt8<F>>| {
+            Error::ConfigMismatch(format!(
+                "The length of `bytes`: {} is different from {}",
+                bytes.len(),
+                N
+            ))
+        })?;
+        Ok(Self::new(bytes))
+    }
+}
+
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> EqGadget<F> for ByteArrayVar<N, F, P> {
+    fn is_eq(&self, other: &Self) -> Result<Boolean<F>, SynthesisError> {
+        if N == 0 {
+            return Ok(Boolean::<F>::TRUE);
+        }
+        self.bytes[..].is_eq(&other.bytes[..])
+    }
+}
+
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> ToBytesGadget<F>
+    for ByteArrayVar<N, F, P>
+{
+    fn to_bytes_le(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        Ok(self.bytes.to_vec())
+    }
+}
+
 impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> AllocVar<ByteArray<N, F, P>, F>
     for ByteArrayVar<N, F, P>
 {
@@ -74,3 +137,47 @@ impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> AllocVar<ByteArray<N
         })
     }
 }
+
+/// The little endian bits of each byte, as in the conversion of [ByteArray] into `Vec<F>`
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> ToPublicInputGadget<F>
+    for ByteArrayVar<N, F, P>
+{
+    fn to_public_input(&self) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        Ok(self
+            .bytes
+            .to_bits_le()?
+            .into_iter()
+            .map(FpVar::<F>::from)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::uint8::UInt8;
+    use ark_relations::r1cs::SynthesisError;
+
+    use crate::constraints::tx::TxVarConfig;
+
+    use super::ByteArrayVar;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    #[test]
+    fn test_try_from_wrong_length() {
+        let bytes = UInt8::<F>::constant_vec(&[1, 2, 3]);
+        assert!(ByteArrayVar::<3, F, Config>::try_from(bytes.as_slice()).is_ok());
+        assert!(matches!(
+            ByteArrayVar::<2, F, Config>::try_from(bytes.as_slice()),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+}
@@ -0,0 +1,177 @@
+//! Implement [Digest32], to be used as a variable in Bitcoin Predicates publishing hashes, e.g., merkle roots or txids
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use ark_crypto_primitives::crh::sha256::constraints::DigestVar;
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::AllocVar,
+    convert::ToBytesGadget,
+    eq::EqGadget,
+    fields::fp::FpVar,
+    prelude::{AllocationMode, Boolean},
+    uint8::UInt8,
+};
+use ark_relations::r1cs::{Namespace, SynthesisError};
+
+use crate::constraints::tx::TxVarConfig;
+use crate::error::Error;
+use crate::traits::{PublicInputSize, ToPublicInputGadget};
+use crate::transaction_integrity_gadget::utils::{get_chunk_size, to_fp_chunks};
+use crate::util::to_fp;
+
+#[derive(Clone)]
+pub struct Digest32<F: PrimeField, P: TxVarConfig + Clone> {
+    pub bytes: [u8; 32],
+    _field: PhantomData<F>,
+    _config: PhantomData<P>,
+}
+
+/// The digest is packed into little endian chunks, see [to_fp_chunks]
+impl<F: PrimeField, P: TxVarConfig + Clone> From<Digest32<F, P>> for Vec<F> {
+    fn from(value: Digest32<F, P>) -> Self {
+        to_fp_chunks(&value.bytes)
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> PublicInputSize<F> for Digest32<F, P> {
+    const N_PUBLIC_INPUTS: usize = 32 / get_chunk_size::<F>();
+}
+
+/// R1CS version of [Digest32]: each chunk of the digest is a field element
+#[derive(Clone)]
+pub struct Digest32Var<F: PrimeField, P: TxVarConfig + Clone> {
+    pub chunks: Vec<FpVar<F>>,
+    _config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> Default for Digest32<F, P> {
+    fn default() -> Self {
+        Self::new([0; 32])
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> Digest32<F, P> {
+    pub fn new(bytes: [u8; 32]) -> Self {
+        Self {
+            bytes,
+            _field: PhantomData,
+            _config: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> Digest32Var<F, P> {
+    /// Returns [Error::ConfigMismatch] if `chunks` are not the chunks of a 32 bytes digest, see [get_chunk_size]
+    pub fn new(chunks: Vec<FpVar<F>>) -> Result<Self, SynthesisError> {
+        if chunks.len() != 32 / get_chunk_size::<F>() {
+            return Err(Error::ConfigMismatch(format!(
+                "The number of chunks: {} is different from {}",
+                chunks.len(),
+                32 / get_chunk_size::<F>()
+            ))
+            .into());
+        }
+
+        Ok(Self {
+            chunks,
+            _config: PhantomData,
+        })
+    }
+
+    /// Check whether `self` is equal to `digest`, e.g., the output of a hash gadget.
+    /// The bytes of `digest` are packed as in [to_fp_chunks], and compared chunk by chunk.
+    pub fn is_eq_digest(&self, digest: &DigestVar<F>) -> Result<Boolean<F>, SynthesisError> {
+        let mut packed: Vec<FpVar<F>> = Vec::with_capacity(self.chunks.len());
+        for chunk in digest.0.chunks_exact(get_chunk_size::<F>()) {
+            packed.push(to_fp(&chunk.to_vec())?);
+        }
+        self.chunks.is_eq(&packed)
+    }
+
+    /// Enforce that `self` is equal to `digest`, see [Digest32Var::is_eq_digest]
+    pub fn enforce_equal_digest(&self, digest: &DigestVar<F>) -> Result<(), SynthesisError> {
+        self.is_eq_digest(digest)?
+            .enforce_equal(&Boolean::<F>::TRUE)
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> EqGadget<F> for Digest32Var<F, P> {
+    fn is_eq(&self, other: &Self) -> Result<Boolean<F>, SynthesisError> {
+        self.chunks.is_eq(&other.chunks)
+    }
+}
+
+/// The 32 bytes of the digest, obtained from the little endian representation of each chunk
+impl<F: PrimeField, P: TxVarConfig + Clone> ToBytesGadget<F> for Digest32Var<F, P> {
+    fn to_bytes_le(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        let chunk_size = get_chunk_size::<F>();
+        let mut bytes: Vec<UInt8<F>> = Vec::with_capacity(32);
+        for chunk in self.chunks.iter() {
+            bytes.extend_from_slice(&chunk.to_bytes_le()?[..chunk_size]);
+        }
+        Ok(bytes)
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> AllocVar<Digest32<F, P>, F> for Digest32Var<F, P> {
+    fn new_variable<T: Borrow<Digest32<F, P>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let data: Digest32<F, P> = f().map(|data| data.borrow().clone())?;
+        let mut chunks: Vec<FpVar<F>> = Vec::new();
+
+        for chunk in to_fp_chunks::<F>(&data.bytes).iter() {
+            chunks.push(FpVar::<F>::new_variable(cs.clone(), || Ok(chunk), mode)?);
+        }
+
+        Ok(Self {
+            chunks,
+            _config: PhantomData,
+        })
+    }
+}
+
+/// The chunks of the digest, as in the conversion of [Digest32] into `Vec<F>`
+impl<F: PrimeField, P: TxVarConfig + Clone> ToPublicInputGadget<F> for Digest32Var<F, P> {
+    fn to_public_input(&self) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        Ok(self.chunks.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_ff::Zero;
+    use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar};
+    use ark_relations::r1cs::SynthesisError;
+
+    use crate::constraints::tx::TxVarConfig;
+    use crate::transaction_integrity_gadget::utils::get_chunk_size;
+
+    use super::Digest32Var;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    #[test]
+    fn test_wrong_number_of_chunks() {
+        let chunks = vec![FpVar::<F>::constant(F::zero()); 32 / get_chunk_size::<F>()];
+        assert!(Digest32Var::<F, Config>::new(chunks.clone()).is_ok());
+        assert!(matches!(
+            Digest32Var::<F, Config>::new(chunks[1..].to_vec()),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+}
@@ -0,0 +1,240 @@
+//! Implement [EcdsaSigData], to be used as a variable in Bitcoin Predicates
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::AllocVar,
+    convert::{ToBitsGadget, ToBytesGadget},
+    eq::EqGadget,
+    fields::fp::FpVar,
+    prelude::{AllocationMode, Boolean},
+    uint8::UInt8,
+};
+use ark_relations::r1cs::{Namespace, SynthesisError};
+
+use crate::bitcoin_predicates::data_structures::pub_key_data::PubKeyDataVar;
+use crate::constraints::{script::ScriptVar, tx::TxVarConfig};
+use crate::error::Error;
+use crate::traits::{PublicInputSize, ToPublicInputGadget};
+use crate::util::{is_der_signature, push_data_prefix, to_fp_bits};
+
+/// The length of a compact signature: `r || s`
+pub const COMPACT_SIGNATURE_LEN: usize = 64;
+
+/// An ECDSA signature of `LEN` bytes and its sighash flag, as pushed by the unlocking script of a P2PKH output.
+/// The signature is either DER encoded, or compact if `LEN` is [COMPACT_SIGNATURE_LEN].
+///
+/// **NOTE**: the signature is not verified, as ECDSA over secp256k1 would require non-native arithmetic.
+#[derive(Clone)]
+pub struct EcdsaSigData<const LEN: usize, F: PrimeField, P: TxVarConfig + Clone> {
+    pub signature: [u8; LEN],
+    pub sighash_flag: u8,
+    _field: PhantomData<F>,
+    _config: PhantomData<P>,
+}
+
+impl<const LEN: usize, F: PrimeField, P: TxVarConfig + Clone> EcdsaSigData<LEN, F, P> {
+    /// The data pushed by the unlocking script: the signature followed by the sighash flag
+    pub fn push_data(&self) -> Vec<u8> {
+        let mut data = self.signature.to_vec();
+        data.push(self.sighash_flag);
+        data
+    }
+}
+
+/// Each byte of [EcdsaSigData::push_data] is allocated as its bits, see [to_fp_bits]
+impl<const LEN: usize, F: PrimeField, P: TxVarConfig + Clone> From<EcdsaSigData<LEN, F, P>>
+    for Vec<F>
+{
+    fn from(value: EcdsaSigData<LEN, F, P>) -> Self {
+        to_fp_bits(&value.push_data())
+    }
+}
+
+impl<const LEN: usize, F: PrimeField, P: TxVarConfig + Clone> PublicInputSize<F>
+    for EcdsaSigData<LEN, F, P>
+{
+    const N_PUBLIC_INPUTS: usize = 8 * (LEN + 1);
+}
+
+/// R1CS version of [EcdsaSigData]
+#[derive(Clone)]
+pub struct EcdsaSigDataVar<const LEN: usize, F: PrimeField, P: TxVarConfig + Clone> {
+    pub signature: [UInt8<F>; LEN],
+    pub sighash_flag: UInt8<F>,
+    _config: PhantomData<P>,
+}
+
+/// A signature made of zeros, which is never valid
+impl<const LEN: usize, F: PrimeField, P: TxVarConfig + Clone> Default for EcdsaSigData<LEN, F, P> {
+    fn default() -> Self {
+        Self {
+            signature: [0; LEN],
+            sighash_flag: 0,
+            _field: PhantomData,
+            _config: PhantomData,
+        }
+    }
+}
+
+impl<const LEN: usize, F: PrimeField, P: TxVarConfig + Clone> EcdsaSigData<LEN, F, P> {
+    /// Returns [Error::ConfigMismatch] if the signature is neither DER encoded nor compact
+    pub fn new(signature: [u8; LEN], sighash_flag: u8) -> Result<Self, Error> {
+        if LEN != COMPACT_SIGNATURE_LEN && !is_der_signature(&signature) {
+            return Err(Error::ConfigMismatch(
+                "The signature is neither DER encoded nor compact".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            signature,
+            sighash_flag,
+            _field: PhantomData,
+            _config: PhantomData,
+        })
+    }
+}
+
+impl<const LEN: usize, F: PrimeField, P: TxVarConfig + Clone> EcdsaSigDataVar<LEN, F, P> {
+    pub fn new(signature: [UInt8<F>; LEN], sighash_flag: UInt8<F>) -> Self {
+        Self {
+            signature,
+            sighash_flag,
+            _config: PhantomData,
+        }
+    }
+
+    /// R1CS version of [EcdsaSigData::push_data]
+    pub fn push_data(&self) -> Vec<UInt8<F>> {
+        let mut data = self.signature.to_vec();
+        data.push(self.sighash_flag.clone());
+        data
+    }
+
+    /// R1CS version of [checksig_unlock_script](crate::util::checksig_unlock_script): the unlocking script
+    /// `<signature || sighash_flag> <public_key>`
+    pub fn checksig_unlock_script(&self, public_key: &PubKeyDataVar<F, P>) -> ScriptVar<F> {
+        let mut script: Vec<UInt8<F>> = UInt8::<F>::constant_vec(
+            &push_data_prefix(LEN + 1).expect("The signature is too long to be pushed"),
+        );
+        script.extend(self.push_data());
+        script.extend(UInt8::<F>::constant_vec(
+            &push_data_prefix(public_key.bytes.len()).unwrap(),
+        ));
+        script.extend_from_slice(&public_key.bytes);
+        ScriptVar(script)
+    }
+}
+
+/// Construct the variable from the data pushed by an unlocking script, e.g., a chunk returned by
+/// [ScriptVar::parse_pushes].
+///
+/// Returns [Error::ConfigMismatch] if the length of `bytes` is not `LEN + 1`
+impl<const LEN: usize, F: PrimeField, P: TxVarConfig + Clone> TryFrom<&[UInt8<F>]>
+    for EcdsaSigDataVar<LEN, F, P>
+{
+    type Error = SynthesisError;
+
+    fn try_from(bytes: &[UInt8<F>]) -> Result<Self, Self::Error> {
+        if bytes.len() != LEN + 1 {
+            return Err(Error::ConfigMismatch(format!(
+                "The length of `bytes`: {} is different from {}",
+                bytes.len(),
+                LEN + 1
+            ))
+            .into());
+        }
+        Ok(Self::new(
+            bytes[..LEN].to_vec().try_into().unwrap(),
+            bytes[LEN].clone(),
+        ))
+    }
+}
+
+impl<const LEN: usize, F: PrimeField, P: TxVarConfig + Clone> EqGadget<F>
+    for EcdsaSigDataVar<LEN, F, P>
+{
+    fn is_eq(&self, other: &Self) -> Result<Boolean<F>, SynthesisError> {
+        self.push_data().is_eq(&other.push_data())
+    }
+}
+
+impl<const LEN: usize, F: PrimeField, P: TxVarConfig + Clone> ToBytesGadget<F>
+    for EcdsaSigDataVar<LEN, F, P>
+{
+    fn to_bytes_le(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        Ok(self.push_data())
+    }
+}
+
+impl<const LEN: usize, F: PrimeField, P: TxVarConfig + Clone> AllocVar<EcdsaSigData<LEN, F, P>, F>
+    for EcdsaSigDataVar<LEN, F, P>
+{
+    fn new_variable<T: Borrow<EcdsaSigData<LEN, F, P>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let data: EcdsaSigData<LEN, F, P> = f().map(|data| data.borrow().clone())?;
+        let bytes = Vec::<UInt8<F>>::new_variable(cs.clone(), || Ok(data.push_data()), mode)?;
+
+        Self::try_from(bytes.as_slice())
+    }
+}
+
+/// The little endian bits of each byte, as in the conversion of [EcdsaSigData] into `Vec<F>`
+impl<const LEN: usize, F: PrimeField, P: TxVarConfig + Clone> ToPublicInputGadget<F>
+    for EcdsaSigDataVar<LEN, F, P>
+{
+    fn to_public_input(&self) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        Ok(self
+            .push_data()
+            .to_bits_le()?
+            .into_iter()
+            .map(FpVar::<F>::from)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::uint8::UInt8;
+    use ark_relations::r1cs::SynthesisError;
+
+    use crate::constraints::tx::TxVarConfig;
+    use crate::error::Error;
+
+    use super::{COMPACT_SIGNATURE_LEN, EcdsaSigData, EcdsaSigDataVar};
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    #[test]
+    fn test_invalid_signature() {
+        assert!(EcdsaSigData::<COMPACT_SIGNATURE_LEN, F, Config>::new([1; 64], 0x41).is_ok());
+        assert!(matches!(
+            EcdsaSigData::<70, F, Config>::new([1; 70], 0x41),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_try_from_wrong_length() {
+        let bytes = UInt8::<F>::constant_vec(&[1; COMPACT_SIGNATURE_LEN]);
+        assert!(matches!(
+            EcdsaSigDataVar::<COMPACT_SIGNATURE_LEN, F, Config>::try_from(bytes.as_slice()),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+}
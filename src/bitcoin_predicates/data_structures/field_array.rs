@@ -7,6 +7,7 @@ use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar, prelude::AllocationMode};
 use ark_relations::r1cs::{Namespace, SynthesisError};
 
 use crate::constraints::tx::TxVarConfig;
+use crate::traits::{PublicInputSize, ToPublicInputGadget};
 
 #[derive(Clone)]
 pub struct FieldArray<const N: usize, F: PrimeField, P: TxVarConfig + Clone> {
@@ -20,6 +21,12 @@ impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> From<FieldArray<N, F
     }
 }
 
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> PublicInputSize<F>
+    for FieldArray<N, F, P>
+{
+    const N_PUBLIC_INPUTS: usize = N;
+}
+
 pub struct FieldArrayVar<const N: usize, F: PrimeField, P: TxVarConfig + Clone> {
     pub elements: [FpVar<F>; N],
     _config: PhantomData<P>,
@@ -69,3 +76,11 @@ impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> AllocVar<FieldArray<
         })
     }
 }
+
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> ToPublicInputGadget<F>
+    for FieldArrayVar<N, F, P>
+{
+    fn to_public_input(&self) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        Ok(self.elements.to_vec())
+    }
+}
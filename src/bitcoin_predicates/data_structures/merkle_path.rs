@@ -0,0 +1,229 @@
+//! Implement [MerklePath], to be used as a variable in Bitcoin Predicates proving membership in a Poseidon Merkle tree
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use ark_crypto_primitives::sponge::{
+    CryptographicSponge,
+    constraints::CryptographicSpongeVar,
+    poseidon::{PoseidonConfig, PoseidonSponge, constraints::PoseidonSpongeVar},
+};
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::{AllocVar, AllocationMode},
+    eq::EqGadget,
+    fields::fp::FpVar,
+    prelude::Boolean,
+    select::CondSelectGadget,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+
+use crate::constraints::tx::TxVarConfig;
+use crate::error::Error;
+use crate::traits::{PublicInputSize, ToPublicInputGadget};
+
+/// Domain separator of [node_hash]
+pub const NODE_DOMAIN: u64 = 1;
+
+/// The parent of the nodes `left` and `right` in the Merkle tree
+pub fn node_hash<F: PrimeField>(left: F, right: F, poseidon_config: &PoseidonConfig<F>) -> F {
+    let mut sponge = PoseidonSponge::<F>::new(poseidon_config);
+    sponge.absorb(&F::from(NODE_DOMAIN));
+    sponge.absorb(&left);
+    sponge.absorb(&right);
+    sponge.squeeze_field_elements::<F>(1)[0]
+}
+
+/// The Merkle path of a leaf in a tree of depth `DEPTH`: its index, and the siblings of the nodes from the leaf
+/// to the root
+#[derive(Clone)]
+pub struct MerklePath<const DEPTH: usize, F: PrimeField, P: TxVarConfig + Clone> {
+    pub index: usize,
+    pub siblings: [F; DEPTH],
+    _config: PhantomData<P>,
+}
+
+/// The bits of the index, followed by the siblings, as allocated by [MerklePathVar]
+impl<const DEPTH: usize, F: PrimeField, P: TxVarConfig + Clone> From<MerklePath<DEPTH, F, P>>
+    for Vec<F>
+{
+    fn from(value: MerklePath<DEPTH, F, P>) -> Self {
+        let mut elements: Vec<F> = (0..DEPTH)
+            .map(|level| F::from(((value.index >> level) & 1) as u64))
+            .collect();
+        elements.extend_from_slice(&value.siblings);
+        elements
+    }
+}
+
+impl<const DEPTH: usize, F: PrimeField, P: TxVarConfig + Clone> PublicInputSize<F>
+    for MerklePath<DEPTH, F, P>
+{
+    const N_PUBLIC_INPUTS: usize = 2 * DEPTH;
+}
+
+/// The R1CS version of [MerklePath]. The index is allocated as its little endian bits: the bit at `level` is
+/// `TRUE` if the node at `level` is a right child
+pub struct MerklePathVar<const DEPTH: usize, F: PrimeField, P: TxVarConfig + Clone> {
+    pub is_right: Vec<Boolean<F>>,
+    pub siblings: Vec<FpVar<F>>,
+    _config: PhantomData<P>,
+}
+
+impl<const DEPTH: usize, F: PrimeField, P: TxVarConfig + Clone> Default
+    for MerklePath<DEPTH, F, P>
+{
+    fn default() -> Self {
+        Self {
+            index: 0,
+            siblings: [F::zero(); DEPTH],
+            _config: PhantomData,
+        }
+    }
+}
+
+impl<const DEPTH: usize, F: PrimeField, P: TxVarConfig + Clone> MerklePath<DEPTH, F, P> {
+    /// Returns [Error::IndexOutOfBounds] if `index` is not smaller than the number of leaves `2^DEPTH`
+    pub fn new(index: usize, siblings: [F; DEPTH]) -> Result<Self, Error> {
+        if index >= 1 << DEPTH {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of leaves: {}",
+                index,
+                1 << DEPTH
+            )));
+        }
+
+        Ok(Self {
+            index,
+            siblings,
+            _config: PhantomData,
+        })
+    }
+
+    /// The root of the tree containing `leaf` at the position of the path, see [node_hash]
+    pub fn compute_root(&self, leaf: F, poseidon_config: &PoseidonConfig<F>) -> F {
+        let mut node = leaf;
+        for (level, sibling) in self.siblings.iter().enumerate() {
+            node = if (self.index >> level) & 1 == 1 {
+                node_hash(*sibling, node, poseidon_config)
+            } else {
+                node_hash(node, *sibling, poseidon_config)
+            };
+        }
+        node
+    }
+
+    /// Check whether `leaf` is in the tree with root `root` at the position of the path
+    pub fn verify(&self, leaf: F, root: F, poseidon_config: &PoseidonConfig<F>) -> bool {
+        self.compute_root(leaf, poseidon_config) == root
+    }
+}
+
+impl<const DEPTH: usize, F: PrimeField, P: TxVarConfig + Clone> MerklePathVar<DEPTH, F, P> {
+    /// R1CS version of [MerklePath::compute_root]
+    pub fn compute_root(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        leaf: &FpVar<F>,
+        poseidon_config: &PoseidonConfig<F>,
+    ) -> Result<FpVar<F>, SynthesisError> {
+        let mut node: FpVar<F> = leaf.clone();
+        for (is_right, sibling) in self.is_right.iter().zip(self.siblings.iter()) {
+            let left = FpVar::<F>::conditionally_select(is_right, sibling, &node)?;
+            let right = FpVar::<F>::conditionally_select(is_right, &node, sibling)?;
+            let mut sponge = PoseidonSpongeVar::<F>::new(cs.clone(), poseidon_config);
+            sponge.absorb(&FpVar::<F>::constant(F::from(NODE_DOMAIN)))?;
+            sponge.absorb(&left)?;
+            sponge.absorb(&right)?;
+            node = sponge.squeeze_field_elements(1)?[0].clone();
+        }
+        Ok(node)
+    }
+
+    /// R1CS version of [MerklePath::verify]
+    pub fn verify(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        leaf: &FpVar<F>,
+        root: &FpVar<F>,
+        poseidon_config: &PoseidonConfig<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        self.compute_root(cs, leaf, poseidon_config)?.is_eq(root)
+    }
+}
+
+impl<const DEPTH: usize, F: PrimeField, P: TxVarConfig + Clone> AllocVar<MerklePath<DEPTH, F, P>, F>
+    for MerklePathVar<DEPTH, F, P>
+{
+    fn new_variable<T: Borrow<MerklePath<DEPTH, F, P>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let data = f()?;
+        let path: &MerklePath<DEPTH, F, P> = data.borrow();
+        Ok(Self {
+            is_right: Vec::<Boolean<F>>::new_variable(
+                cs.clone(),
+                || {
+                    Ok((0..DEPTH)
+                        .map(|level| (path.index >> level) & 1 == 1)
+                        .collect::<Vec<bool>>())
+                },
+                mode,
+            )?,
+            siblings: Vec::<FpVar<F>>::new_variable(
+                cs.clone(),
+                || Ok(path.siblings.to_vec()),
+                mode,
+            )?,
+            _config: PhantomData,
+        })
+    }
+}
+
+/// The bits of the index, followed by the siblings, as in the conversion of [MerklePath] into `Vec<F>`
+impl<const DEPTH: usize, F: PrimeField, P: TxVarConfig + Clone> ToPublicInputGadget<F>
+    for MerklePathVar<DEPTH, F, P>
+{
+    fn to_public_input(&self) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        let mut elements: Vec<FpVar<F>> = self
+            .is_right
+            .iter()
+            .map(|bit| FpVar::<F>::from(bit.clone()))
+            .collect();
+        elements.extend_from_slice(&self.siblings);
+        Ok(elements)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_ff::Zero;
+
+    use crate::constraints::tx::TxVarConfig;
+    use crate::error::Error;
+
+    use super::MerklePath;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    #[test]
+    fn test_index_out_of_bounds() {
+        assert!(MerklePath::<2, F, Config>::new(3, [F::zero(); 2]).is_ok());
+        assert!(matches!(
+            MerklePath::<2, F, Config>::new(4, [F::zero(); 2]),
+            Err(Error::IndexOutOfBounds(_))
+        ));
+    }
+}
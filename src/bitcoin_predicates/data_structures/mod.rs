@@ -1,3 +1,11 @@
+pub mod bool_array;
 pub mod byte_array;
+pub mod digest32;
+pub mod ecdsa_sig_data;
 pub mod field_array;
+pub mod merkle_path;
+pub mod outpoint;
+pub mod pub_key_data;
+pub mod u64_array;
 pub mod unit;
+pub mod var_len_byte_array;
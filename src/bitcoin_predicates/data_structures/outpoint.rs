@@ -0,0 +1,87 @@
+//! Implement [BitcoinOutPoint], to be used as a variable in Bitcoin Predicates
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::AllocVar,
+    convert::{ToBitsGadget, ToBytesGadget},
+    fields::fp::FpVar,
+    prelude::AllocationMode,
+};
+use ark_relations::r1cs::{Namespace, SynthesisError};
+use chain_gang::messages::OutPoint;
+use chain_gang::util::Hash256;
+
+use crate::constraints::{outpoint::OutPointVar, tx::TxVarConfig};
+use crate::traits::{PublicInputSize, ToPublicInputGadget};
+
+#[derive(Clone)]
+pub struct BitcoinOutPoint<F: PrimeField, P: TxVarConfig + Clone> {
+    pub outpoint: OutPoint,
+    _field: PhantomData<F>,
+    _config: PhantomData<P>,
+}
+
+/// The outpoint is allocated as the bits of its serialisation, see [OutPointVar::public_input]
+impl<F: PrimeField, P: TxVarConfig + Clone> From<BitcoinOutPoint<F, P>> for Vec<F> {
+    fn from(value: BitcoinOutPoint<F, P>) -> Self {
+        OutPointVar::<F>::public_input(&value.outpoint)
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> PublicInputSize<F> for BitcoinOutPoint<F, P> {
+    const N_PUBLIC_INPUTS: usize = 8 * 36;
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> Default for BitcoinOutPoint<F, P> {
+    fn default() -> Self {
+        Self::new(OutPoint {
+            hash: Hash256([0; 32]),
+            index: 0,
+        })
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinOutPoint<F, P> {
+    pub fn new(outpoint: OutPoint) -> Self {
+        Self {
+            outpoint,
+            _field: PhantomData,
+            _config: PhantomData,
+        }
+    }
+}
+
+pub struct BitcoinOutPointVar<F: PrimeField, P: TxVarConfig + Clone> {
+    pub outpoint: OutPointVar<F>,
+    _config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> AllocVar<BitcoinOutPoint<F, P>, F>
+    for BitcoinOutPointVar<F, P>
+{
+    fn new_variable<T: Borrow<BitcoinOutPoint<F, P>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let data: BitcoinOutPoint<F, P> = f().map(|data| data.borrow().clone())?;
+
+        Ok(Self {
+            outpoint: OutPointVar::<F>::new_variable(cs.clone(), || Ok(data.outpoint), mode)?,
+            _config: PhantomData,
+        })
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> ToPublicInputGadget<F> for BitcoinOutPointVar<F, P> {
+    fn to_public_input(&self) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        let mut bits = self.outpoint.prev_tx.to_bytes_le()?.to_bits_le()?;
+        bits.extend(self.outpoint.prev_index.to_bits_le()?);
+        Ok(bits.into_iter().map(FpVar::<F>::from).collect())
+    }
+}
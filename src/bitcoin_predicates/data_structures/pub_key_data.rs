@@ -0,0 +1,202 @@
+//! Implement [PubKeyData], to be used as a variable in Bitcoin Predicates
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::AllocVar,
+    convert::{ToBitsGadget, ToBytesGadget},
+    eq::EqGadget,
+    fields::fp::FpVar,
+    prelude::{AllocationMode, Boolean},
+    uint8::UInt8,
+};
+use ark_relations::r1cs::{Namespace, SynthesisError};
+
+use crate::constraints::{hash160::Hash160Gadget, script::ScriptVar, tx::TxVarConfig};
+use crate::error::Error;
+use crate::traits::{PublicInputSize, ToPublicInputGadget};
+use crate::util::to_fp_bits;
+
+/// The length of a compressed public key
+pub const PUBLIC_KEY_LEN: usize = 33;
+
+/// A compressed secp256k1 public key, as pushed by the unlocking script of a P2PKH output
+#[derive(Clone)]
+pub struct PubKeyData<F: PrimeField, P: TxVarConfig + Clone> {
+    pub bytes: [u8; PUBLIC_KEY_LEN],
+    _field: PhantomData<F>,
+    _config: PhantomData<P>,
+}
+
+/// Each byte is allocated as its bits, see [to_fp_bits]
+impl<F: PrimeField, P: TxVarConfig + Clone> From<PubKeyData<F, P>> for Vec<F> {
+    fn from(value: PubKeyData<F, P>) -> Self {
+        to_fp_bits(&value.bytes)
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> PublicInputSize<F> for PubKeyData<F, P> {
+    const N_PUBLIC_INPUTS: usize = 8 * PUBLIC_KEY_LEN;
+}
+
+/// R1CS version of [PubKeyData]: the allocation enforces the prefix of the compressed encoding
+#[derive(Clone)]
+pub struct PubKeyDataVar<F: PrimeField, P: TxVarConfig + Clone> {
+    pub bytes: [UInt8<F>; PUBLIC_KEY_LEN],
+    _config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> Default for PubKeyData<F, P> {
+    fn default() -> Self {
+        let mut bytes = [0; PUBLIC_KEY_LEN];
+        bytes[0] = 0x02;
+        Self {
+            bytes,
+            _field: PhantomData,
+            _config: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> PubKeyData<F, P> {
+    /// Returns [Error::ConfigMismatch] if `bytes` is not a compressed public key
+    pub fn new(bytes: [u8; PUBLIC_KEY_LEN]) -> Result<Self, Error> {
+        if bytes[0] != 0x02 && bytes[0] != 0x03 {
+            return Err(Error::ConfigMismatch(format!(
+                "The prefix of a compressed public key must be 0x02 or 0x03, found: {:#04x}",
+                bytes[0]
+            )));
+        }
+
+        Ok(Self {
+            bytes,
+            _field: PhantomData,
+            _config: PhantomData,
+        })
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> PubKeyDataVar<F, P> {
+    /// Check whether the first byte of `bytes` is `0x02` or `0x03`
+    pub fn has_valid_prefix(
+        bytes: &[UInt8<F>; PUBLIC_KEY_LEN],
+    ) -> Result<Boolean<F>, SynthesisError> {
+        Ok(bytes[0].is_eq(&UInt8::<F>::constant(0x02))?
+            | bytes[0].is_eq(&UInt8::<F>::constant(0x03))?)
+    }
+
+    /// Construct the variable from `bytes`, enforcing that they are a compressed public key
+    pub fn new(bytes: [UInt8<F>; PUBLIC_KEY_LEN]) -> Result<Self, SynthesisError> {
+        Self::has_valid_prefix(&bytes)?.enforce_equal(&Boolean::<F>::TRUE)?;
+        Ok(Self {
+            bytes,
+            _config: PhantomData,
+        })
+    }
+
+    /// The HASH160 of the public key, e.g., to compare it with the hash in a P2PKH locking script
+    pub fn hash160(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        Hash160Gadget::<F>::evaluate(&self.bytes)
+    }
+
+    /// The bytes of `self` as a [ScriptVar], e.g., to compare them with a slice of an unlocking script
+    pub fn as_script_var(&self) -> ScriptVar<F> {
+        ScriptVar(self.bytes.to_vec())
+    }
+}
+
+/// Returns [Error::ConfigMismatch] if the length of `bytes` is not [PUBLIC_KEY_LEN]. The prefix is not enforced,
+/// use [PubKeyDataVar::new] for that.
+impl<F: PrimeField, P: TxVarConfig + Clone> TryFrom<&[UInt8<F>]> for PubKeyDataVar<F, P> {
+    type Error = SynthesisError;
+
+    fn try_from(bytes: &[UInt8<F>]) -> Result<Self, Self::Error> {
+        let bytes: [UInt8<F>; PUBLIC_KEY_LEN] =
+            bytes.to_vec().try_into().map_err(|bytes: Vec<UInt8<F>>| {
+                Error::ConfigMismatch(format!(
+                    "The length of `bytes`: {} is different from {}",
+                    bytes.len(),
+                    PUBLIC_KEY_LEN
+                ))
+            })?;
+        Ok(Self {
+            bytes,
+            _config: PhantomData,
+        })
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> EqGadget<F> for PubKeyDataVar<F, P> {
+    fn is_eq(&self, other: &Self) -> Result<Boolean<F>, SynthesisError> {
+        self.bytes[..].is_eq(&other.bytes[..])
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> ToBytesGadget<F> for PubKeyDataVar<F, P> {
+    fn to_bytes_le(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        Ok(self.bytes.to_vec())
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> AllocVar<PubKeyData<F, P>, F> for PubKeyDataVar<F, P> {
+    fn new_variable<T: Borrow<PubKeyData<F, P>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let data: PubKeyData<F, P> = f().map(|data| data.borrow().clone())?;
+        let mut bytes: Vec<UInt8<F>> = Vec::new();
+
+        for byte in data.bytes.iter() {
+            bytes.push(UInt8::<F>::new_variable(cs.clone(), || Ok(byte), mode)?);
+        }
+
+        Self::new(bytes.try_into().expect("The length of `bytes` is wrong"))
+    }
+}
+
+/// The little endian bits of each byte, as in the conversion of [PubKeyData] into `Vec<F>`
+impl<F: PrimeField, P: TxVarConfig + Clone> ToPublicInputGadget<F> for PubKeyDataVar<F, P> {
+    fn to_public_input(&self) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        Ok(self
+            .bytes
+            .to_bits_le()?
+            .into_iter()
+            .map(FpVar::<F>::from)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+
+    use crate::constraints::tx::TxVarConfig;
+    use crate::error::Error;
+
+    use super::{PUBLIC_KEY_LEN, PubKeyData};
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    #[test]
+    fn test_invalid_prefix() {
+        let mut bytes = [1; PUBLIC_KEY_LEN];
+        assert!(matches!(
+            PubKeyData::<F, Config>::new(bytes),
+            Err(Error::ConfigMismatch(_))
+        ));
+        bytes[0] = 0x03;
+        assert!(PubKeyData::<F, Config>::new(bytes).is_ok());
+    }
+}
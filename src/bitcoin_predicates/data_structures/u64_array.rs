@@ -0,0 +1,111 @@
+//! Implement [U64Array], to be used as a variable in Bitcoin Predicates, e.g., for amounts in satoshis
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::AllocVar, convert::ToBitsGadget, fields::fp::FpVar, prelude::AllocationMode,
+    uint64::UInt64,
+};
+use ark_relations::r1cs::{Namespace, SynthesisError};
+
+use crate::constraints::tx::TxVarConfig;
+use crate::traits::{PublicInputSize, ToPublicInputGadget};
+use crate::util::to_fp_bits;
+
+#[derive(Clone)]
+pub struct U64Array<const N: usize, F: PrimeField, P: TxVarConfig + Clone> {
+    pub elements: [u64; N],
+    _field: PhantomData<F>,
+    _config: PhantomData<P>,
+}
+
+/// Each element is allocated as its 64 little endian bits, see [to_fp_bits]
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> From<U64Array<N, F, P>> for Vec<F> {
+    fn from(value: U64Array<N, F, P>) -> Self {
+        value
+            .elements
+            .iter()
+            .flat_map(|element| to_fp_bits(&element.to_le_bytes()))
+            .collect()
+    }
+}
+
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> PublicInputSize<F>
+    for U64Array<N, F, P>
+{
+    const N_PUBLIC_INPUTS: usize = 64 * N;
+}
+
+/// R1CS version of [U64Array]: as the elements are allocated as [UInt64]s, they are range checked by construction
+#[derive(Clone)]
+pub struct U64ArrayVar<const N: usize, F: PrimeField, P: TxVarConfig + Clone> {
+    pub elements: [UInt64<F>; N],
+    _config: PhantomData<P>,
+}
+
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> Default for U64Array<N, F, P> {
+    fn default() -> Self {
+        Self::new([0; N])
+    }
+}
+
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> U64Array<N, F, P> {
+    pub fn new(elements: [u64; N]) -> Self {
+        Self {
+            elements,
+            _field: PhantomData,
+            _config: PhantomData,
+        }
+    }
+}
+
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> U64ArrayVar<N, F, P> {
+    pub fn new(elements: [UInt64<F>; N]) -> Self {
+        Self {
+            elements,
+            _config: PhantomData,
+        }
+    }
+}
+
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> AllocVar<U64Array<N, F, P>, F>
+    for U64ArrayVar<N, F, P>
+{
+    fn new_variable<T: Borrow<U64Array<N, F, P>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let data: U64Array<N, F, P> = f().map(|data| data.borrow().clone())?;
+        let mut elements: Vec<UInt64<F>> = Vec::new();
+
+        for element in data.elements.iter() {
+            elements.push(UInt64::<F>::new_variable(cs.clone(), || Ok(element), mode)?);
+        }
+
+        Ok(Self {
+            elements: elements
+                .try_into()
+                .expect("The length of `elements` is wrong"),
+            _config: PhantomData,
+        })
+    }
+}
+
+/// The little endian bits of each element, as in the conversion of [U64Array] into `Vec<F>`
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> ToPublicInputGadget<F>
+    for U64ArrayVar<N, F, P>
+{
+    fn to_public_input(&self) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        Ok(self
+            .elements
+            .to_bits_le()?
+            .into_iter()
+            .map(FpVar::<F>::from)
+            .collect())
+    }
+}
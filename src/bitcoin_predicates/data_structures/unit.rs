@@ -3,10 +3,11 @@ use std::borrow::Borrow;
 use std::marker::PhantomData;
 
 use ark_ff::PrimeField;
-use ark_r1cs_std::{alloc::AllocVar, prelude::AllocationMode};
+use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar, prelude::AllocationMode};
 use ark_relations::r1cs::{Namespace, SynthesisError};
 
 use crate::constraints::tx::TxVarConfig;
+use crate::traits::{PublicInputSize, ToPublicInputGadget};
 
 #[derive(Clone)]
 pub struct BitcoinUnit<F: PrimeField, P: TxVarConfig + Clone> {
@@ -25,6 +26,10 @@ impl<F: PrimeField, P: TxVarConfig + Clone> From<BitcoinUnit<F, P>> for Vec<F> {
     }
 }
 
+impl<F: PrimeField, P: TxVarConfig + Clone> PublicInputSize<F> for BitcoinUnit<F, P> {
+    const N_PUBLIC_INPUTS: usize = 0;
+}
+
 impl<F: PrimeField, P: TxVarConfig + Clone> Default for BitcoinUnit<F, P> {
     fn default() -> Self {
         Self {
@@ -54,3 +59,9 @@ impl<F: PrimeField, P: TxVarConfig + Clone> AllocVar<BitcoinUnit<F, P>, F>
         Ok(BitcoinUnitVar::default())
     }
 }
+
+impl<F: PrimeField, P: TxVarConfig + Clone> ToPublicInputGadget<F> for BitcoinUnitVar<F, P> {
+    fn to_public_input(&self) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        Ok(Vec::<FpVar<F>>::new())
+    }
+}
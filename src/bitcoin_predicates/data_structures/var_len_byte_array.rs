@@ -0,0 +1,165 @@
+//! Implement [VarLenByteArray], to be used as a variable in Bitcoin Predicates
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::AllocVar, convert::ToBitsGadget, fields::fp::FpVar, prelude::AllocationMode,
+    prelude::Boolean,
+};
+use ark_relations::r1cs::{Namespace, SynthesisError};
+
+use crate::constraints::{script::ScriptVar, tx::TxVarConfig, var_bytes::VarBytesVar};
+use crate::error::Error;
+use crate::traits::{PublicInputSize, ToPublicInputGadget};
+
+/// A byte array of length at most `MAX`, e.g., a memo
+#[derive(Clone)]
+pub struct VarLenByteArray<const MAX: usize, F: PrimeField, P: TxVarConfig + Clone> {
+    pub bytes: Vec<u8>,
+    _field: PhantomData<F>,
+    _config: PhantomData<P>,
+}
+
+/// The length, followed by the bits of the bytes padded with zeros to `MAX`, see [VarBytesVar::public_input]
+impl<const MAX: usize, F: PrimeField, P: TxVarConfig + Clone> From<VarLenByteArray<MAX, F, P>>
+    for Vec<F>
+{
+    fn from(value: VarLenByteArray<MAX, F, P>) -> Self {
+        VarBytesVar::<F>::public_input(&value.bytes, MAX)
+    }
+}
+
+impl<const MAX: usize, F: PrimeField, P: TxVarConfig + Clone> PublicInputSize<F>
+    for VarLenByteArray<MAX, F, P>
+{
+    const N_PUBLIC_INPUTS: usize = 1 + 8 * MAX;
+}
+
+/// R1CS version of [VarLenByteArray]: the length of the array is a variable of the circuit
+#[derive(Clone)]
+pub struct VarLenByteArrayVar<const MAX: usize, F: PrimeField, P: TxVarConfig + Clone> {
+    pub data: VarBytesVar<F>,
+    _config: PhantomData<P>,
+}
+
+impl<const MAX: usize, F: PrimeField, P: TxVarConfig + Clone> Default
+    for VarLenByteArray<MAX, F, P>
+{
+    fn default() -> Self {
+        Self {
+            bytes: Vec::new(),
+            _field: PhantomData,
+            _config: PhantomData,
+        }
+    }
+}
+
+impl<const MAX: usize, F: PrimeField, P: TxVarConfig + Clone> VarLenByteArray<MAX, F, P> {
+    /// Returns [Error::ConfigMismatch] if `bytes` is longer than `MAX`
+    pub fn new(bytes: Vec<u8>) -> Result<Self, Error> {
+        if bytes.len() > MAX {
+            return Err(Error::ConfigMismatch(format!(
+                "The length of the array: {} is larger than the maximum length: {}",
+                bytes.len(),
+                MAX
+            )));
+        }
+
+        Ok(Self {
+            bytes,
+            _field: PhantomData,
+            _config: PhantomData,
+        })
+    }
+}
+
+impl<const MAX: usize, F: PrimeField, P: TxVarConfig + Clone> VarLenByteArrayVar<MAX, F, P> {
+    /// Returns [Error::ConfigMismatch] if the maximum length of `data` is not `MAX`
+    pub fn new(data: VarBytesVar<F>) -> Result<Self, SynthesisError> {
+        if data.max_len() != MAX {
+            return Err(Error::ConfigMismatch(format!(
+                "The maximum length of the data: {} is different from {}",
+                data.max_len(),
+                MAX
+            ))
+            .into());
+        }
+
+        Ok(Self {
+            data,
+            _config: PhantomData,
+        })
+    }
+
+    /// Check whether the array is a prefix of `script`, see [VarBytesVar::is_prefix_of]
+    pub fn is_prefix_of(&self, script: &ScriptVar<F>) -> Result<Boolean<F>, SynthesisError> {
+        self.data.is_prefix_of(&script.0)
+    }
+}
+
+/// The length of the array is enforced to be at most `MAX`, and the padding to be zero
+impl<const MAX: usize, F: PrimeField, P: TxVarConfig + Clone>
+    AllocVar<VarLenByteArray<MAX, F, P>, F> for VarLenByteArrayVar<MAX, F, P>
+{
+    fn new_variable<T: Borrow<VarLenByteArray<MAX, F, P>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let data: VarLenByteArray<MAX, F, P> = f().map(|data| data.borrow().clone())?;
+
+        Ok(Self {
+            data: VarBytesVar::<F>::new_variable(cs.clone(), || Ok(data.bytes), MAX, mode)?,
+            _config: PhantomData,
+        })
+    }
+}
+
+/// The length, followed by the little endian bits of each byte, as in the conversion of [VarLenByteArray] into `Vec<F>`
+impl<const MAX: usize, F: PrimeField, P: TxVarConfig + Clone> ToPublicInputGadget<F>
+    for VarLenByteArrayVar<MAX, F, P>
+{
+    fn to_public_input(&self) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        let mut input = vec![self.data.len.clone()];
+        input.extend(
+            self.data
+                .bytes
+                .to_bits_le()?
+                .into_iter()
+                .map(FpVar::<F>::from),
+        );
+        Ok(input)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+
+    use crate::constraints::tx::TxVarConfig;
+    use crate::error::Error;
+
+    use super::VarLenByteArray;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    #[test]
+    fn test_array_too_long() {
+        assert!(VarLenByteArray::<3, F, Config>::new(vec![1, 2, 3]).is_ok());
+        assert!(matches!(
+            VarLenByteArray::<2, F, Config>::new(vec![1, 2, 3]),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+}
@@ -0,0 +1,148 @@
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{prelude::Boolean, uint64::UInt64};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use chain_gang::messages::Tx;
+
+use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+use crate::constraints::tx::{TxVar, TxVarConfig};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+use crate::util::uint64::is_le;
+
+/// Bitcoin Predicate enforcing that every output of the spending transaction has an amount of at least
+/// `dust_limit` satoshis. Meant to be AND-combined with application predicates, together with
+/// [FeeLimit](crate::bitcoin_predicates::value::FeeLimit).
+pub struct DustLimit<F: PrimeField, P: TxVarConfig + Clone> {
+    pub dust_limit: u64,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> DustLimit<F, P> {
+    pub fn new(dust_limit: u64) -> Self {
+        Self {
+            dust_limit,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for DustLimit<F, P> {
+    type LockingData = BitcoinUnit<F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = BitcoinUnitVar<F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        _cs: ConstraintSystemRef<F>,
+        _locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let dust_limit = UInt64::<F>::constant(self.dust_limit);
+        let checks = spending_data
+            .outputs
+            .iter()
+            .map(|output| is_le(&dust_limit, &output.satoshis))
+            .collect::<Result<Vec<Boolean<F>>, SynthesisError>>()?;
+
+        Boolean::<F>::kary_and(&checks)
+    }
+
+    fn evaluate_native(
+        &self,
+        _locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        Ok(tx
+            .outputs
+            .iter()
+            .all(|output| self.dust_limit <= output.satoshis as u64))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::script::Script;
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::unit::BitcoinUnitVar;
+    use crate::constraints::tx::{TxVar, TxVarConfig};
+    use crate::traits::BitcoinPredicate;
+
+    use super::DustLimit;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 2;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0, 0];
+    }
+
+    const DUST_LIMIT: u64 = 546;
+
+    fn test_predicate(output_amounts: [u64; 2]) -> bool {
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: output_amounts
+                .iter()
+                .map(|amount| TxOut {
+                    satoshis: *amount as i64,
+                    lock_script: Script(vec![]),
+                })
+                .collect(),
+            lock_time: 0,
+        };
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        DustLimit::<F, Config>::new(DUST_LIMIT)
+            .enforce_constraints(
+                cs.clone(),
+                &BitcoinUnitVar::default(),
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &BitcoinUnitVar::default(),
+            )
+            .unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_dust_limit_is_ok() {
+        assert!(test_predicate([DUST_LIMIT, 10_000]));
+    }
+
+    #[test]
+    fn test_dust_limit_fails() {
+        assert!(!test_predicate([10_000, DUST_LIMIT - 1]));
+        assert!(!test_predicate([0, 10_000]));
+    }
+}
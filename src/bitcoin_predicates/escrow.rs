@@ -0,0 +1,511 @@
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use ark_ec::twisted_edwards::{Affine, TECurveConfig};
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    eq::EqGadget,
+    prelude::{AllocVar, AllocationMode, Boolean},
+};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+
+use chain_gang::{messages::Tx, script::Script};
+
+use crate::bitcoin_predicates::{
+    combinators::{Or, TupleData, TupleDataVar},
+    data_structures::{
+        field_array::{FieldArray, FieldArrayVar},
+        unit::{BitcoinUnit, BitcoinUnitVar},
+    },
+    multisig::{MultiSig, MultiSigWitness, MultiSigWitnessVar},
+    signed_by::sighash_message,
+};
+use crate::constraints::{
+    script::ScriptVar,
+    tx::{TxVar, TxVarConfig},
+};
+use crate::error::Error;
+use crate::schnorr::SchnorrSignature;
+use crate::traits::{BitcoinPredicate, SpendingContext};
+use crate::transaction_integrity_gadget::{TransactionIntegrityConfig, scheme::PrevOutput};
+use crate::util::to_fp;
+
+/// The mutual agreement path of an [Escrow]: buyer and seller signed the spending transaction
+pub type MutualAgreement<F, C, P> = MultiSig<2, 2, F, C, P>;
+
+/// The dispute resolution path of an [Escrow]: the arbiter signed the spending transaction
+pub type DisputeResolution<F, C, P> = MultiSig<1, 1, F, C, P>;
+
+/// The signatures of the parties of an [Escrow] on [Escrow::message]
+/// A missing signature is replaced by [SchnorrSignature::default], which is never valid
+pub struct EscrowSignatures<F: PrimeField, C: TECurveConfig<BaseField = F>, P: TxVarConfig + Clone>
+{
+    pub buyer_signature: Option<SchnorrSignature<C>>,
+    pub seller_signature: Option<SchnorrSignature<C>>,
+    pub arbiter_signature: Option<SchnorrSignature<C>>,
+    _config: PhantomData<P>,
+}
+
+impl<F: PrimeField, C: TECurveConfig<BaseField = F>, P: TxVarConfig + Clone> Clone
+    for EscrowSignatures<F, C, P>
+{
+    fn clone(&self) -> Self {
+        Self {
+            buyer_signature: self.buyer_signature.clone(),
+            seller_signature: self.seller_signature.clone(),
+            arbiter_signature: self.arbiter_signature.clone(),
+            _config: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, C: TECurveConfig<BaseField = F>, P: TxVarConfig + Clone>
+    EscrowSignatures<F, C, P>
+{
+    pub fn new(
+        buyer_signature: Option<SchnorrSignature<C>>,
+        seller_signature: Option<SchnorrSignature<C>>,
+        arbiter_signature: Option<SchnorrSignature<C>>,
+    ) -> Self {
+        Self {
+            buyer_signature,
+            seller_signature,
+            arbiter_signature,
+            _config: PhantomData,
+        }
+    }
+
+    /// The witness of the paths of the [Escrow]: the signatures of buyer and seller, with the indices of their
+    /// keys in [MutualAgreement], and the signature of the arbiter
+    fn paths(&self) -> TupleData<(MultiSigWitness<C>, MultiSigWitness<C>)> {
+        let signatures = |signatures: &[(usize, &Option<SchnorrSignature<C>>)]| MultiSigWitness {
+            signatures: signatures
+                .iter()
+                .filter_map(|&(index, signature)| {
+                    signature.clone().map(|signature| (index, signature))
+                })
+                .collect(),
+        };
+        TupleData((
+            signatures(&[(0, &self.buyer_signature), (1, &self.seller_signature)]),
+            signatures(&[(0, &self.arbiter_signature)]),
+        ))
+    }
+}
+
+/// The R1CS version of [EscrowSignatures]: the witness of the paths of the [Escrow]
+pub struct EscrowSignaturesVar<
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+    P: TxVarConfig + Clone,
+> {
+    pub paths: TupleDataVar<(
+        MultiSigWitnessVar<2, 2, F, C>,
+        MultiSigWitnessVar<1, 1, F, C>,
+    )>,
+    _config: PhantomData<P>,
+}
+
+impl<F: PrimeField, C: TECurveConfig<BaseField = F>, P: TxVarConfig + Clone>
+    AllocVar<EscrowSignatures<F, C, P>, F> for EscrowSignaturesVar<F, C, P>
+{
+    fn new_variable<T: Borrow<EscrowSignatures<F, C, P>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let signatures: EscrowSignatures<F, C, P> = f().map(|data| data.borrow().clone())?;
+
+        Ok(Self {
+            paths: TupleDataVar::new_variable(cs.clone(), || Ok(signatures.paths()), mode)?,
+            _config: PhantomData,
+        })
+    }
+}
+
+/// Bitcoin Predicate implementing an escrow between a buyer, a seller and an arbiter.
+///
+/// The unlocking data is the payout `[buyer_amount, seller_amount]`. The predicate enforces that the outputs
+/// at `buyer_index` and `seller_index` pay `buyer_amount` to `buyer_lock_script` and `seller_amount` to
+/// `seller_lock_script`, and that either:
+/// - buyer and seller signed the spending transaction (mutual agreement), or
+/// - the arbiter signed the spending transaction (dispute resolution).
+///
+/// The signed message is [Escrow::message], the sighash of the spending transaction, so that signatures cannot be
+/// replayed to spend another escrow with the same payout. As for
+/// [SignedBy](crate::bitcoin_predicates::signed_by::SignedBy), the sighash is the one computed by the integrity
+/// scheme.
+pub struct Escrow<
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+    P: TransactionIntegrityConfig + TxVarConfig + Clone,
+> {
+    pub paths: Or<(MutualAgreement<F, C, P>, DisputeResolution<F, C, P>)>,
+    pub buyer_lock_script: Script,
+    pub seller_lock_script: Script,
+    pub buyer_index: usize,
+    pub seller_index: usize,
+}
+
+impl<F, C, P> Escrow<F, C, P>
+where
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+    P: TransactionIntegrityConfig + TxVarConfig + Clone,
+{
+    /// Returns [Error::ConfigMismatch] if buyer and seller are paid in the same output
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        buyer_public_key: Affine<C>,
+        seller_public_key: Affine<C>,
+        arbiter_public_key: Affine<C>,
+        buyer_lock_script: Script,
+        seller_lock_script: Script,
+        buyer_index: usize,
+        seller_index: usize,
+    ) -> Result<Self, Error> {
+        if buyer_index == seller_index {
+            return Err(Error::ConfigMismatch(format!(
+                "Buyer and seller must be paid in different outputs, but both are paid in output {}",
+                buyer_index
+            )));
+        }
+        Ok(Self {
+            paths: Or((
+                MutualAgreement::new([buyer_public_key, seller_public_key])?,
+                DisputeResolution::new([arbiter_public_key])?,
+            )),
+            buyer_lock_script,
+            seller_lock_script,
+            buyer_index,
+            seller_index,
+        })
+    }
+
+    /// The message signed by the parties: the sighash of `tx`, see [sighash_message]
+    pub fn message(tx: &Tx, prev_output: &PrevOutput) -> Result<Vec<u8>, Error> {
+        sighash_message::<P>(tx, prev_output)
+    }
+}
+
+impl<F, C, P> BitcoinPredicate<F, P> for Escrow<F, C, P>
+where
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+    P: TransactionIntegrityConfig + TxVarConfig + Clone,
+{
+    type LockingData = BitcoinUnit<F, P>;
+    type UnlockingData = FieldArray<2, F, P>;
+    type Witness = EscrowSignatures<F, C, P>;
+
+    type LockingDataVar = BitcoinUnitVar<F, P>;
+    type UnlockingDataVar = FieldArrayVar<2, F, P>;
+    type WitnessVar = EscrowSignaturesVar<F, C, P>;
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        self.generate_constraints_with_context(
+            cs,
+            locking_data,
+            unlocking_data,
+            spending_data,
+            witness,
+            &SpendingContext::default(),
+        )
+    }
+
+    fn generate_constraints_with_context(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        _locking_data: &Self::LockingDataVar,
+        unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+        context: &SpendingContext<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        // Validate input
+        if self.buyer_index >= spending_data.outputs.len()
+            || self.seller_index >= spending_data.outputs.len()
+        {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Indices: ({}, {}) are not compatible with the number of outputs: {}",
+                self.buyer_index,
+                self.seller_index,
+                spending_data.outputs.len()
+            ))
+            .into());
+        }
+
+        let buyer_output = &spending_data.outputs[self.buyer_index];
+        let seller_output = &spending_data.outputs[self.seller_index];
+
+        // Check that the outputs match the payout
+        let is_payout_correct = Boolean::<F>::kary_and(&[
+            buyer_output
+                .lock_script
+                .is_eq(&ScriptVar::<F>::new_constant(
+                    cs.clone(),
+                    self.buyer_lock_script.clone(),
+                )?)?,
+            seller_output
+                .lock_script
+                .is_eq(&ScriptVar::<F>::new_constant(
+                    cs.clone(),
+                    self.seller_lock_script.clone(),
+                )?)?,
+            to_fp(&buyer_output.satoshis)?.is_eq(&unlocking_data.elements[0])?,
+            to_fp(&seller_output.satoshis)?.is_eq(&unlocking_data.elements[1])?,
+        ])?;
+
+        // The data of the paths are empty
+        let units = || TupleDataVar((BitcoinUnitVar::default(), BitcoinUnitVar::default()));
+        let is_authorised = self.paths.generate_constraints_with_context(
+            cs.clone(),
+            &units(),
+            &units(),
+            spending_data,
+            &witness.paths,
+            context,
+        )?;
+
+        Boolean::<F>::kary_and(&[is_payout_correct, is_authorised])
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use ark_bls12_381::Fr as F;
+    use ark_ed_on_bls12_381::EdwardsConfig;
+
+    use ark_r1cs_std::alloc::AllocVar;
+
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::address::addr_decode;
+    use chain_gang::script::Script;
+
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::network::Network;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::transaction::sighash::{SIGHASH_ALL, SIGHASH_FORKID};
+    use chain_gang::util::Hash256;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::bitcoin_predicates::data_structures::field_array::{FieldArray, FieldArrayVar};
+    use crate::bitcoin_predicates::data_structures::unit::BitcoinUnitVar;
+    use crate::constraints::{sighash_cache::SigHashCacheVar, tx::TxVar};
+    use crate::error::Error;
+    use crate::schnorr::Schnorr;
+    use crate::transaction_integrity_gadget::{
+        TransactionIntegrityConfig,
+        scheme::{PrevOutput, PrevOutputVar},
+    };
+
+    use crate::{
+        constraints::tx::TxVarConfig,
+        traits::{BitcoinPredicate, SpendingContext},
+    };
+
+    use super::{Escrow, EscrowSignatures, EscrowSignaturesVar};
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 2;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19, 0x19];
+    }
+
+    impl TransactionIntegrityConfig for Config {
+        const N_INPUT: usize = 0;
+        const LEN_PREV_LOCK_SCRIPT: usize = 0x19;
+        const SIGHASH_FLAG: u8 = SIGHASH_ALL | SIGHASH_FORKID;
+    }
+
+    const BUYER: &str = "mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec";
+    const SELLER: &str = "mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz";
+
+    fn p2pkh_script(addr: &str) -> Script {
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        p2pkh::create_lock_script(&hash160)
+    }
+
+    fn prev_output() -> PrevOutput {
+        PrevOutput {
+            prev_lock_script: p2pkh_script(BUYER),
+            prev_amount: 1000,
+        }
+    }
+
+    /// The transaction spending the output at `index` and paying `amounts` to buyer and seller
+    fn tx(index: u32, amounts: [i64; 2]) -> Tx {
+        Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![
+                TxOut {
+                    satoshis: amounts[0],
+                    lock_script: p2pkh_script(BUYER),
+                },
+                TxOut {
+                    satoshis: amounts[1],
+                    lock_script: p2pkh_script(SELLER),
+                },
+            ],
+            lock_time: 0,
+        }
+    }
+
+    /// Check the predicate on the transaction spending the escrow and paying `amounts`, against the payout `payout`,
+    /// with the signatures of the parties in `signers` (0: buyer, 1: seller, 2: arbiter) on `signed_tx`
+    fn test_predicate(
+        amounts: [i64; 2],
+        payout: [u64; 2],
+        signers: &[usize],
+        signed_tx: Tx,
+        expected: bool,
+    ) {
+        let tx = tx(0, amounts);
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let keys: Vec<_> = (0..3)
+            .map(|_| Schnorr::<EdwardsConfig>::keygen(&mut rng))
+            .collect();
+        let message =
+            Escrow::<F, EdwardsConfig, Config>::message(&signed_tx, &prev_output()).unwrap();
+        let mut signatures: Vec<_> = keys
+            .iter()
+            .enumerate()
+            .map(|(i, (secret_key, _))| {
+                signers
+                    .contains(&i)
+                    .then(|| Schnorr::<EdwardsConfig>::sign(&mut rng, secret_key, &message))
+            })
+            .collect();
+
+        let predicate = Escrow::<F, EdwardsConfig, Config>::new(
+            keys[0].1,
+            keys[1].1,
+            keys[2].1,
+            p2pkh_script(BUYER),
+            p2pkh_script(SELLER),
+            0,
+            1,
+        )
+        .unwrap();
+        let witness = EscrowSignatures::<F, EdwardsConfig, Config>::new(
+            signatures.remove(0),
+            signatures.remove(0),
+            signatures.remove(0),
+        );
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        // The sighash computed by the integrity scheme
+        let prev_output_var =
+            PrevOutputVar::<F>::new_witness(cs.clone(), || Ok(prev_output())).unwrap();
+        let sighash = tx_var
+            .sighash(
+                Config::N_INPUT,
+                &prev_output_var.prev_lock_script,
+                &prev_output_var.prev_amount,
+                &Config::SIGHASH_FLAG,
+                &mut SigHashCacheVar::<F>::new(),
+            )
+            .unwrap();
+        let unlocking_data_var = FieldArrayVar::<2, F, Config>::new_input(cs.clone(), || {
+            Ok(FieldArray::<2, F, Config>::new([
+                F::from(payout[0]),
+                F::from(payout[1]),
+            ]))
+        })
+        .unwrap();
+        let witness_var =
+            EscrowSignaturesVar::<F, EdwardsConfig, Config>::new_witness(cs.clone(), || {
+                Ok(witness)
+            })
+            .unwrap();
+        predicate
+            .enforce_constraints_with_context(
+                cs.clone(),
+                &BitcoinUnitVar::default(),
+                &unlocking_data_var,
+                &tx_var,
+                &witness_var,
+                &SpendingContext {
+                    sighash: Some(&sighash),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_mutual_agreement() {
+        let signed_tx = || tx(0, [700, 300]);
+        test_predicate([700, 300], [700, 300], &[0, 1], signed_tx(), true);
+        test_predicate([700, 300], [700, 300], &[0], signed_tx(), false);
+        test_predicate([700, 300], [700, 300], &[1], signed_tx(), false);
+    }
+
+    #[test]
+    fn test_dispute() {
+        let signed_tx = || tx(0, [0, 1000]);
+        test_predicate([0, 1000], [0, 1000], &[2], signed_tx(), true);
+        test_predicate([0, 1000], [0, 1000], &[0, 2], signed_tx(), true);
+    }
+
+    #[test]
+    fn test_payout_mismatch() {
+        test_predicate([1000, 0], [0, 1000], &[0, 1], tx(0, [1000, 0]), false);
+        test_predicate([1000, 0], [0, 1000], &[2], tx(0, [1000, 0]), false);
+        test_predicate([0, 1000], [0, 1000], &[], tx(0, [0, 1000]), false);
+    }
+
+    #[test]
+    fn test_replayed_signatures() {
+        // Signatures on the same payout from another escrow
+        test_predicate([700, 300], [700, 300], &[0, 1], tx(1, [700, 300]), false);
+        test_predicate([0, 1000], [0, 1000], &[2], tx(1, [0, 1000]), false);
+    }
+
+    #[test]
+    fn test_same_payout_output() {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let (_, public_key) = Schnorr::<EdwardsConfig>::keygen(&mut rng);
+
+        assert!(matches!(
+            Escrow::<F, EdwardsConfig, Config>::new(
+                public_key,
+                public_key,
+                public_key,
+                p2pkh_script(BUYER),
+                p2pkh_script(SELLER),
+                0,
+                0,
+            ),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+}
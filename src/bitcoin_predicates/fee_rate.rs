@@ -0,0 +1,251 @@
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{prelude::Boolean, uint64::UInt64};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use chain_gang::messages::Tx;
+
+use crate::bitcoin_predicates::data_structures::{
+    field_array::{FieldArray, FieldArrayVar},
+    unit::{BitcoinUnit, BitcoinUnitVar},
+};
+use crate::bitcoin_predicates::value::{check_single_input, fee, native_fee, prev_amount};
+use crate::constraints::tx::{TxVar, TxVarConfig};
+use crate::error::Error;
+use crate::traits::{BitcoinPredicate, SpendingContext};
+use crate::util::{
+    to_u64, tx_size,
+    uint64::{is_le, mul_by_const},
+};
+
+/// Bitcoin Predicate enforcing that the spending transaction pays a fee between `min_rate` and `max_rate`
+/// satoshis per byte, both included: `min_rate * size <= amount - sum(outputs) <= max_rate * size`, where
+/// `amount` is the amount of the output being spent and `size` is the length of the serialised transaction,
+/// see [TxVar::serialised_len].
+///
+/// The locking data is `amount`, bound to the amount the integrity scheme commits to as for
+/// [ValueConservation](crate::bitcoin_predicates::value::ValueConservation): the spending transaction must have a
+/// single input.
+pub struct FeeRate<F: PrimeField, P: TxVarConfig + Clone> {
+    pub min_rate: u64,
+    pub max_rate: u64,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> FeeRate<F, P> {
+    /// Returns [Error::ConfigMismatch] if the spending transaction does not have a single input, if the range is
+    /// empty, or if the maximum fee does not fit in 64 bits
+    pub fn new(min_rate: u64, max_rate: u64) -> Result<Self, Error> {
+        check_single_input::<P>()?;
+        if min_rate > max_rate {
+            return Err(Error::ConfigMismatch(format!(
+                "The range is empty: min_rate = {} is larger than max_rate = {}",
+                min_rate, max_rate
+            )));
+        }
+        if max_rate
+            .checked_mul(TxVar::<F, P>::serialised_len() as u64)
+            .is_none()
+        {
+            return Err(Error::ConfigMismatch(format!(
+                "The maximum fee for a rate of {} satoshis per byte does not fit in 64 bits",
+                max_rate
+            )));
+        }
+        Ok(Self {
+            min_rate,
+            max_rate,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        })
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for FeeRate<F, P> {
+    type LockingData = FieldArray<1, F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = FieldArrayVar<1, F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        self.generate_constraints_with_context(
+            cs,
+            locking_data,
+            unlocking_data,
+            spending_data,
+            witness,
+            &SpendingContext::default(),
+        )
+    }
+
+    fn generate_constraints_with_context(
+        &self,
+        _cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+        context: &SpendingContext<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let (amount, is_amount_correct) = prev_amount(context, &locking_data.elements[0])?;
+        let (is_bounded, fee) = fee(amount, spending_data)?;
+
+        // The size is a constant of the circuit, as the lengths of the scripts are set in `P`
+        let size = UInt64::<F>::constant(TxVar::<F, P>::serialised_len() as u64);
+        Boolean::<F>::kary_and(&[
+            is_amount_correct,
+            is_bounded,
+            is_le(&mul_by_const(&size, self.min_rate)?, &fee)?,
+            is_le(&fee, &mul_by_const(&size, self.max_rate)?)?,
+        ])
+    }
+
+    /// The amount in the locking data is assumed to be the one committed to by the integrity scheme
+    fn evaluate_native(
+        &self,
+        locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        // The amount must fit in 64 bits, as enforced in the circuit
+        let fee = to_u64(&locking_data.elements[0]).and_then(|amount| native_fee(amount, tx));
+        let size = tx_size(tx) as u128;
+
+        Ok(fee.is_some_and(|fee| {
+            size * self.min_rate as u128 <= fee as u128
+                && fee as u128 <= size * self.max_rate as u128
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::{alloc::AllocVar, uint64::UInt64};
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::script::Script;
+    use chain_gang::util::{Hash256, Serializable};
+
+    use crate::bitcoin_predicates::data_structures::field_array::{FieldArray, FieldArrayVar};
+    use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+    use crate::constraints::tx::{TxVar, TxVarConfig};
+    use crate::error::Error;
+    use crate::traits::{BitcoinPredicate, SpendingContext};
+
+    use super::FeeRate;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0];
+    }
+
+    /// The size of the serialised transaction
+    const TX_SIZE: u64 = 60;
+
+    /// The amount of the output being spent
+    const AMOUNT: u64 = 3000;
+
+    fn test_predicate(output_amount: u64, expected: bool) {
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![TxOut {
+                satoshis: output_amount as i64,
+                lock_script: Script(vec![]),
+            }],
+            lock_time: 0,
+        };
+        let mut tx_bytes: Vec<u8> = Vec::new();
+        tx.write(&mut tx_bytes).unwrap();
+        assert_eq!(tx_bytes.len() as u64, TX_SIZE);
+
+        let locking_data = FieldArray::<1, F, Config>::new([F::from(AMOUNT)]);
+        let cs = ConstraintSystem::<F>::new_ref();
+        let locking_data_var =
+            FieldArrayVar::<1, F, Config>::new_input(cs.clone(), || Ok(locking_data.clone()))
+                .unwrap();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx.clone())).unwrap();
+        let prev_amount = UInt64::<F>::new_witness(cs.clone(), || Ok(AMOUNT)).unwrap();
+        let predicate = FeeRate::<F, Config>::new(1, 2).unwrap();
+        predicate
+            .enforce_constraints_with_context(
+                cs.clone(),
+                &locking_data_var,
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &BitcoinUnitVar::default(),
+                &SpendingContext {
+                    prev_amount: Some(&prev_amount),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), expected);
+        assert_eq!(
+            predicate
+                .evaluate_native(
+                    &locking_data,
+                    &BitcoinUnit::default(),
+                    &tx,
+                    &BitcoinUnit::default()
+                )
+                .unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_fee_rate_is_ok() {
+        test_predicate(AMOUNT - TX_SIZE, true);
+        test_predicate(AMOUNT - 2 * TX_SIZE, true);
+    }
+
+    #[test]
+    fn test_fee_rate_fails() {
+        // Fee rate too low
+        test_predicate(AMOUNT - TX_SIZE + 1, false);
+        // Fee rate too high
+        test_predicate(AMOUNT - 2 * TX_SIZE - 1, false);
+        // Outputs larger than the input
+        test_predicate(AMOUNT + 1, false);
+    }
+
+    #[test]
+    fn test_invalid_range() {
+        assert!(matches!(
+            FeeRate::<F, Config>::new(2, 1),
+            Err(Error::ConfigMismatch(_))
+        ));
+        assert!(matches!(
+            FeeRate::<F, Config>::new(0, u64::MAX),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+}
@@ -7,6 +7,7 @@ use ark_r1cs_std::{
 };
 use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
 
+use chain_gang::messages::Tx;
 use chain_gang::script::Script;
 
 use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
@@ -14,6 +15,7 @@ use crate::constraints::{
     script::ScriptVar,
     tx::{TxVar, TxVarConfig},
 };
+use crate::error::Error;
 use crate::traits::BitcoinPredicate;
 
 /// Bitcoin Predicate to enforce that the output of the transaction at `index`
@@ -53,21 +55,32 @@ impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for FixedLock
         spending_data: &TxVar<F, P>,
         _witness: &Self::WitnessVar,
     ) -> Result<Boolean<F>, SynthesisError> {
-        // Validate input
-        assert!(
-            self.index <= spending_data.outputs.len(),
-            "Index: {} is larger the the number of outputs: {}",
-            self.index,
-            spending_data.outputs.len()
-        );
+        let output = spending_data.output(self.index)?;
 
         // Enforce that output at index `self.index` has the correct locking script
-        spending_data.outputs[self.index]
-            .lock_script
-            .is_eq(&ScriptVar::<F>::new_constant(
-                cs.clone(),
-                self.lock_script.clone(),
-            )?)
+        output.lock_script.is_eq(&ScriptVar::<F>::new_constant(
+            cs.clone(),
+            self.lock_script.clone(),
+        )?)
+    }
+
+    fn evaluate_native(
+        &self,
+        _locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        // Validate input
+        if self.index >= tx.outputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of outputs: {}",
+                self.index,
+                tx.outputs.len()
+            )));
+        }
+
+        Ok(tx.outputs[self.index].lock_script.0 == self.lock_script.0)
     }
 }
 
@@ -87,8 +100,9 @@ mod test {
     use chain_gang::transaction::p2pkh;
     use chain_gang::util::Hash256;
 
-    use crate::bitcoin_predicates::data_structures::unit::BitcoinUnitVar;
+    use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
     use crate::constraints::tx::TxVar;
+    use crate::error::Error;
 
     use crate::{constraints::tx::TxVarConfig, traits::BitcoinPredicate};
 
@@ -134,7 +148,7 @@ mod test {
         let predicate = FixedLockScript::<F, Config>::new(lock_script, index);
 
         let cs = ConstraintSystem::<F>::new_ref();
-        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx.clone())).unwrap();
         predicate
             .enforce_constraints(
                 cs.clone(),
@@ -145,6 +159,17 @@ mod test {
             )
             .unwrap();
         assert_eq!(cs.is_satisfied().unwrap(), expected);
+        assert_eq!(
+            predicate
+                .evaluate_native(
+                    &BitcoinUnit::default(),
+                    &BitcoinUnit::default(),
+                    &tx,
+                    &BitcoinUnit::default()
+                )
+                .unwrap(),
+            expected
+        );
     }
 
     #[test]
@@ -163,4 +188,24 @@ mod test {
         let lock_script = p2pkh::create_lock_script(&hash160);
         test_predicate(wrong_addr, lock_script, 1, false);
     }
+
+    #[test]
+    fn test_index_out_of_bounds() {
+        let predicate = FixedLockScript::<F, Config>::new(Script(vec![]), 2);
+        let tx = Tx {
+            version: 2,
+            inputs: vec![],
+            outputs: vec![],
+            lock_time: 0,
+        };
+        assert!(matches!(
+            predicate.evaluate_native(
+                &BitcoinUnit::default(),
+                &BitcoinUnit::default(),
+                &tx,
+                &BitcoinUnit::default()
+            ),
+            Err(Error::IndexOutOfBounds(_))
+        ));
+    }
 }
@@ -0,0 +1,180 @@
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{eq::EqGadget, prelude::Boolean};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use chain_gang::messages::Tx;
+
+use crate::bitcoin_predicates::data_structures::{
+    outpoint::{BitcoinOutPoint, BitcoinOutPointVar},
+    unit::{BitcoinUnit, BitcoinUnitVar},
+};
+use crate::constraints::tx::{TxVar, TxVarConfig};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+
+/// Bitcoin Predicate to enforce that the input of the transaction at `index` spends the outpoint
+/// `(hash, vout)` set in the locking data, so that the proof is only valid for the spending of that UTXO
+pub struct FixedOutPoint<F: PrimeField, P: TxVarConfig + Clone> {
+    pub index: usize,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> FixedOutPoint<F, P> {
+    pub fn new(index: usize) -> Self {
+        Self {
+            index,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for FixedOutPoint<F, P> {
+    type LockingData = BitcoinOutPoint<F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = BitcoinOutPointVar<F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        _cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let input = spending_data.input(self.index)?;
+
+        // Enforce that input at index `self.index` spends the outpoint in the locking data
+        input.prev_output.is_eq(&locking_data.outpoint)
+    }
+
+    fn evaluate_native(
+        &self,
+        locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        // Validate input
+        if self.index >= tx.inputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of inputs: {}",
+                self.index,
+                tx.inputs.len()
+            )));
+        }
+
+        Ok(tx.inputs[self.index].prev_output == locking_data.outpoint)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use ark_bls12_381::Fr as F;
+
+    use ark_r1cs_std::alloc::AllocVar;
+
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::script::Script;
+
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::outpoint::{
+        BitcoinOutPoint, BitcoinOutPointVar,
+    };
+    use crate::bitcoin_predicates::data_structures::unit::BitcoinUnitVar;
+    use crate::constraints::tx::TxVar;
+    use crate::traits::PublicInputSize;
+
+    use crate::{constraints::tx::TxVarConfig, traits::BitcoinPredicate};
+
+    use super::FixedOutPoint;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 2;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0, 0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0];
+    }
+
+    fn outpoint(index: u32) -> OutPoint {
+        OutPoint {
+            hash: Hash256::decode(
+                "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+            )
+            .unwrap(),
+            index,
+        }
+    }
+
+    fn test_predicate(expected_outpoint: OutPoint, index: usize, expected: bool) {
+        let tx = Tx {
+            version: 2,
+            inputs: (0..2)
+                .map(|i| TxIn {
+                    prev_output: outpoint(i),
+                    unlock_script: Script(vec![]),
+                    sequence: 0,
+                })
+                .collect(),
+            outputs: vec![TxOut {
+                satoshis: 100,
+                lock_script: Script(vec![]),
+            }],
+            lock_time: 0,
+        };
+
+        let predicate = FixedOutPoint::<F, Config>::new(index);
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let locking_data = BitcoinOutPoint::<F, Config>::new(expected_outpoint);
+        let locking_data_var =
+            BitcoinOutPointVar::<F, Config>::new_input(cs.clone(), || Ok(locking_data.clone()))
+                .unwrap();
+        // The locking data is allocated as expected by [RefTxCircuit::public_input]
+        assert_eq!(
+            cs.borrow().unwrap().instance_assignment[1..],
+            Into::<Vec<F>>::into(locking_data)
+        );
+        assert_eq!(
+            cs.num_instance_variables() - 1,
+            BitcoinOutPoint::<F, Config>::N_PUBLIC_INPUTS
+        );
+
+        let tx_var = TxVar::<F, Config>::new_witness(cs.clone(), || Ok(tx)).unwrap();
+        predicate
+            .enforce_constraints(
+                cs.clone(),
+                &locking_data_var,
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &BitcoinUnitVar::default(),
+            )
+            .unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_predicate_is_ok() {
+        test_predicate(outpoint(0), 0, true);
+        test_predicate(outpoint(1), 1, true);
+    }
+
+    #[test]
+    fn test_predicate_fails() {
+        test_predicate(outpoint(1), 0, false);
+        let mut wrong_outpoint = outpoint(0);
+        wrong_outpoint.hash.0[0] ^= 1;
+        test_predicate(wrong_outpoint, 0, false);
+    }
+}
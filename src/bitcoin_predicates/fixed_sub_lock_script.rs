@@ -7,6 +7,7 @@ use ark_r1cs_std::{
 };
 use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
 
+use chain_gang::messages::Tx;
 use chain_gang::script::Script;
 
 use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
@@ -14,6 +15,7 @@ use crate::constraints::{
     script::ScriptVar,
     tx::{TxVar, TxVarConfig},
 };
+use crate::error::Error;
 use crate::traits::BitcoinPredicate;
 
 /// Bitcoin Predicate to enforce that the output of the transaction at `index`
@@ -59,25 +61,40 @@ impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for FixedSubL
         spending_data: &TxVar<F, P>,
         _witness: &Self::WitnessVar,
     ) -> Result<Boolean<F>, SynthesisError> {
-        // Validate input
-        assert!(
-            self.index <= spending_data.outputs.len(),
-            "Index: {} is larger the the number of outputs: {}",
-            self.index,
-            spending_data.outputs.len()
-        );
-
-        assert!(
-            self.end <= spending_data.outputs[self.index].lock_script.0.len(),
-            "End index: {} is larger the the size of the locking script: {}",
-            self.end,
-            spending_data.outputs[self.index].lock_script.0.len()
-        );
-
         // Enforce that output at index `self.index` has the correct sub locking script
         let fixed_sub_lock = ScriptVar::<F>::new_constant(cs.clone(), self.lock_script.clone())?;
-        spending_data.outputs[self.index].lock_script.0[self.start..self.end]
-            .is_eq(&fixed_sub_lock.0)
+        spending_data
+            .output(self.index)?
+            .lock_script
+            .slice(self.start..self.end)?
+            .is_eq(&fixed_sub_lock)
+    }
+
+    fn evaluate_native(
+        &self,
+        _locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        // Validate input
+        if self.index >= tx.outputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of outputs: {}",
+                self.index,
+                tx.outputs.len()
+            )));
+        }
+        if self.start > self.end || self.end > tx.outputs[self.index].lock_script.0.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Range: {}..{} is not compatible with the size of the locking script: {}",
+                self.start,
+                self.end,
+                tx.outputs[self.index].lock_script.0.len()
+            )));
+        }
+
+        Ok(tx.outputs[self.index].lock_script.0[self.start..self.end] == self.lock_script.0[..])
     }
 }
 
@@ -88,7 +105,7 @@ mod test {
 
     use ark_r1cs_std::alloc::AllocVar;
 
-    use ark_relations::r1cs::ConstraintSystem;
+    use ark_relations::r1cs::{ConstraintSystem, SynthesisError};
     use chain_gang::address::addr_decode;
     use chain_gang::script::Script;
     use chain_gang::script::op_codes::{OP_0, OP_1};
@@ -114,18 +131,11 @@ mod test {
         const LEN_LOCK_SCRIPTS: &[usize] = &[0x1c, 0x19];
     }
 
-    fn test_predicate(
-        addr: &str,
-        lock_script: Script,
-        index: usize,
-        start: usize,
-        end: usize,
-        expected: bool,
-    ) {
+    fn spending_tx(addr: &str, lock_script: &Script) -> Tx {
         let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
         let mut extended_lock_script = lock_script.clone();
         extended_lock_script.append_slice(&[OP_0, OP_0, OP_1]);
-        let tx = Tx {
+        Tx {
             version: 2,
             inputs: vec![TxIn {
                 prev_output: OutPoint {
@@ -149,22 +159,36 @@ mod test {
                 },
             ],
             lock_time: 0,
-        };
-
-        let predicate = FixedSubLockScript::<F, Config>::new(lock_script, index, start, end);
+        }
+    }
 
+    fn enforce_predicate(
+        predicate: FixedSubLockScript<F, Config>,
+        tx: Tx,
+    ) -> Result<bool, SynthesisError> {
         let cs = ConstraintSystem::<F>::new_ref();
-        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
-        predicate
-            .enforce_constraints(
-                cs.clone(),
-                &BitcoinUnitVar::default(),
-                &BitcoinUnitVar::default(),
-                &tx_var,
-                &BitcoinUnitVar::default(),
-            )
-            .unwrap();
-        assert_eq!(cs.is_satisfied().unwrap(), expected);
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx))?;
+        predicate.enforce_constraints(
+            cs.clone(),
+            &BitcoinUnitVar::default(),
+            &BitcoinUnitVar::default(),
+            &tx_var,
+            &BitcoinUnitVar::default(),
+        )?;
+        cs.is_satisfied()
+    }
+
+    fn test_predicate(
+        addr: &str,
+        lock_script: Script,
+        index: usize,
+        start: usize,
+        end: usize,
+        expected: bool,
+    ) {
+        let tx = spending_tx(addr, &lock_script);
+        let predicate = FixedSubLockScript::<F, Config>::new(lock_script, index, start, end);
+        assert_eq!(enforce_predicate(predicate, tx).unwrap(), expected);
     }
 
     #[test]
@@ -199,4 +223,27 @@ mod test {
         let lock_script = p2pkh::create_lock_script(&hash160);
         test_predicate(addr, lock_script, 0, 1, 0x1a, false);
     }
+
+    #[test]
+    fn test_predicate_out_of_bounds() {
+        let addr = "mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec";
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        let lock_script = p2pkh::create_lock_script(&hash160);
+        // The output does not exist
+        assert_eq!(
+            enforce_predicate(
+                FixedSubLockScript::<F, Config>::new(lock_script.clone(), 2, 0, 0x19),
+                spending_tx(addr, &lock_script)
+            ),
+            Err(SynthesisError::Unsatisfiable)
+        );
+        // The range exceeds the locking script
+        assert_eq!(
+            enforce_predicate(
+                FixedSubLockScript::<F, Config>::new(lock_script.clone(), 1, 0, 0x1a),
+                spending_tx(addr, &lock_script)
+            ),
+            Err(SynthesisError::Unsatisfiable)
+        );
+    }
 }
@@ -0,0 +1,220 @@
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    eq::EqGadget,
+    prelude::{AllocVar, Boolean},
+};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+
+use chain_gang::messages::Tx;
+use chain_gang::script::Script;
+
+use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+use crate::constraints::{
+    script::ScriptVar,
+    tx::{TxVar, TxVarConfig},
+};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+
+/// Bitcoin Predicate to enforce that the input of the transaction at `index`
+/// has unlocking script equal to `unlock_script` between bytes `start` and `end`
+/// **Note**: Even though only a sub unlocking script is enforced, the total length of
+/// the unlocking script is fixed by `P`
+pub struct FixedSubUnlockScript<F: PrimeField, P: TxVarConfig + Clone> {
+    pub unlock_script: Script,
+    pub index: usize,
+    pub start: usize,
+    pub end: usize,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> FixedSubUnlockScript<F, P> {
+    pub fn new(unlock_script: Script, index: usize, start: usize, end: usize) -> Self {
+        Self {
+            unlock_script,
+            index,
+            start,
+            end,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for FixedSubUnlockScript<F, P> {
+    type LockingData = BitcoinUnit<F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = BitcoinUnitVar<F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        _locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        // Enforce that input at index `self.index` has the correct sub unlocking script
+        let fixed_sub_unlock =
+            ScriptVar::<F>::new_constant(cs.clone(), self.unlock_script.clone())?;
+        spending_data
+            .input(self.index)?
+            .unlock_script
+            .slice(self.start..self.end)?
+            .is_eq(&fixed_sub_unlock)
+    }
+
+    fn evaluate_native(
+        &self,
+        _locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        // Validate input
+        if self.index >= tx.inputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of inputs: {}",
+                self.index,
+                tx.inputs.len()
+            )));
+        }
+        if self.start > self.end || self.end > tx.inputs[self.index].unlock_script.0.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Range: {}..{} is not compatible with the size of the unlocking script: {}",
+                self.start,
+                self.end,
+                tx.inputs[self.index].unlock_script.0.len()
+            )));
+        }
+
+        Ok(tx.inputs[self.index].unlock_script.0[self.start..self.end] == self.unlock_script.0[..])
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use ark_bls12_381::Fr as F;
+
+    use ark_r1cs_std::alloc::AllocVar;
+
+    use ark_relations::r1cs::{ConstraintSystem, SynthesisError};
+    use chain_gang::script::Script;
+
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::unit::BitcoinUnitVar;
+    use crate::constraints::tx::TxVar;
+    use crate::util::checksig_unlock_script;
+
+    use crate::{constraints::tx::TxVarConfig, traits::BitcoinPredicate};
+
+    use super::FixedSubUnlockScript;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 2;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0x6b, 0x6b];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0];
+    }
+
+    /// Unlocking script `<signature || 0x41> <public_key>`, the public key being at bytes `0x4a..0x6b`
+    fn checksig_script(public_key_byte: u8) -> Script {
+        checksig_unlock_script(&[0x30; 71], 0x41, &[public_key_byte; 33])
+    }
+
+    fn spending_tx() -> Tx {
+        let input = |i: u32, unlock_script: Script| TxIn {
+            prev_output: OutPoint {
+                hash: Hash256::decode(
+                    "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                )
+                .unwrap(),
+                index: i,
+            },
+            unlock_script,
+            sequence: 0,
+        };
+        Tx {
+            version: 2,
+            inputs: vec![
+                input(0, checksig_script(0x02)),
+                input(1, checksig_script(0x03)),
+            ],
+            outputs: vec![TxOut {
+                satoshis: 100,
+                lock_script: Script(vec![]),
+            }],
+            lock_time: 0,
+        }
+    }
+
+    fn enforce_predicate(
+        predicate: FixedSubUnlockScript<F, Config>,
+    ) -> Result<bool, SynthesisError> {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(spending_tx()))?;
+        predicate.enforce_constraints(
+            cs.clone(),
+            &BitcoinUnitVar::default(),
+            &BitcoinUnitVar::default(),
+            &tx_var,
+            &BitcoinUnitVar::default(),
+        )?;
+        cs.is_satisfied()
+    }
+
+    fn public_key(byte: u8) -> Script {
+        Script(vec![byte; 33])
+    }
+
+    #[test]
+    fn test_predicate_is_ok() {
+        for (index, byte) in [(0, 0x02), (1, 0x03)] {
+            let predicate =
+                FixedSubUnlockScript::<F, Config>::new(public_key(byte), index, 0x4a, 0x6b);
+            assert!(enforce_predicate(predicate).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_predicate_fails() {
+        let predicate = FixedSubUnlockScript::<F, Config>::new(public_key(0x03), 0, 0x4a, 0x6b);
+        assert!(!enforce_predicate(predicate).unwrap());
+    }
+
+    #[test]
+    fn test_predicate_out_of_bounds() {
+        // The input does not exist
+        assert_eq!(
+            enforce_predicate(FixedSubUnlockScript::<F, Config>::new(
+                public_key(0x02),
+                2,
+                0x4a,
+                0x6b
+            )),
+            Err(SynthesisError::Unsatisfiable)
+        );
+        // The range exceeds the unlocking script
+        assert_eq!(
+            enforce_predicate(FixedSubUnlockScript::<F, Config>::new(
+                public_key(0x02),
+                0,
+                0x4b,
+                0x6c
+            )),
+            Err(SynthesisError::Unsatisfiable)
+        );
+    }
+}
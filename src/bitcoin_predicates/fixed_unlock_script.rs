@@ -0,0 +1,172 @@
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    eq::EqGadget,
+    prelude::{AllocVar, Boolean},
+};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+
+use chain_gang::messages::Tx;
+use chain_gang::script::Script;
+
+use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+use crate::constraints::{
+    script::ScriptVar,
+    tx::{TxVar, TxVarConfig},
+};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+
+/// Bitcoin Predicate to enforce that the input of the transaction at `index`
+/// has unlocking script equal to `unlock_script`
+pub struct FixedUnlockScript<F: PrimeField, P: TxVarConfig + Clone> {
+    pub unlock_script: Script,
+    pub index: usize,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> FixedUnlockScript<F, P> {
+    pub fn new(unlock_script: Script, index: usize) -> Self {
+        Self {
+            unlock_script,
+            index,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for FixedUnlockScript<F, P> {
+    type LockingData = BitcoinUnit<F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = BitcoinUnitVar<F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        _locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let input = spending_data.input(self.index)?;
+
+        // Enforce that input at index `self.index` has the correct unlocking script
+        input.unlock_script.is_eq(&ScriptVar::<F>::new_constant(
+            cs.clone(),
+            self.unlock_script.clone(),
+        )?)
+    }
+
+    fn evaluate_native(
+        &self,
+        _locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        // Validate input
+        if self.index >= tx.inputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of inputs: {}",
+                self.index,
+                tx.inputs.len()
+            )));
+        }
+
+        Ok(tx.inputs[self.index].unlock_script.0 == self.unlock_script.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use ark_bls12_381::Fr as F;
+
+    use ark_r1cs_std::alloc::AllocVar;
+
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::script::Script;
+
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::unit::BitcoinUnitVar;
+    use crate::constraints::tx::TxVar;
+    use crate::util::checksig_unlock_script;
+
+    use crate::{constraints::tx::TxVarConfig, traits::BitcoinPredicate};
+
+    use super::FixedUnlockScript;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 2;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0x6b, 0x6b];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0];
+    }
+
+    fn checksig_script(byte: u8) -> Script {
+        checksig_unlock_script(&[byte; 71], 0x41, &[0x02; 33])
+    }
+
+    fn test_predicate(unlock_script: Script, index: usize, expected: bool) {
+        let input = |i: u32, unlock_script: Script| TxIn {
+            prev_output: OutPoint {
+                hash: Hash256::decode(
+                    "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                )
+                .unwrap(),
+                index: i,
+            },
+            unlock_script,
+            sequence: 0,
+        };
+        let tx = Tx {
+            version: 2,
+            inputs: vec![
+                input(0, checksig_script(0x30)),
+                input(1, checksig_script(0x31)),
+            ],
+            outputs: vec![TxOut {
+                satoshis: 100,
+                lock_script: Script(vec![]),
+            }],
+            lock_time: 0,
+        };
+
+        let predicate = FixedUnlockScript::<F, Config>::new(unlock_script, index);
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        predicate
+            .enforce_constraints(
+                cs.clone(),
+                &BitcoinUnitVar::default(),
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &BitcoinUnitVar::default(),
+            )
+            .unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_predicate_is_ok() {
+        test_predicate(checksig_script(0x30), 0, true);
+        test_predicate(checksig_script(0x31), 1, true);
+    }
+
+    #[test]
+    fn test_predicate_fails() {
+        test_predicate(checksig_script(0x31), 0, false);
+    }
+}
@@ -0,0 +1,247 @@
+use std::marker::PhantomData;
+
+use ark_crypto_primitives::crh::{
+    CRHScheme,
+    sha256::{Sha256, constraints::Sha256Gadget},
+};
+use ark_ff::PrimeField;
+use ark_r1cs_std::{eq::EqGadget, prelude::Boolean, uint8::UInt8};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use chain_gang::{
+    messages::Tx,
+    util::{hash160, sha256d},
+};
+
+use crate::bitcoin_predicates::data_structures::{
+    byte_array::{ByteArray, ByteArrayVar},
+    unit::{BitcoinUnit, BitcoinUnitVar},
+};
+use crate::constraints::{
+    hash160::Hash160Gadget,
+    hash256::Hash256Gadget,
+    tx::{TxVar, TxVarConfig},
+};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+
+/// The hash functions supported by [HashPreimage], as the corresponding Bitcoin opcodes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashFunction {
+    /// `OP_SHA256`
+    Sha256,
+    /// `OP_HASH256`: Sha256 applied twice
+    Hash256,
+    /// `OP_HASH160`: Ripemd160 after Sha256
+    Hash160,
+}
+
+impl HashFunction {
+    /// The size of the digest in bytes
+    pub fn digest_size(&self) -> usize {
+        match self {
+            HashFunction::Sha256 | HashFunction::Hash256 => 32,
+            HashFunction::Hash160 => 20,
+        }
+    }
+
+    /// Compute the digest of `data`
+    pub fn hash(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            HashFunction::Sha256 => <Sha256 as CRHScheme>::evaluate(&(), data).unwrap(),
+            HashFunction::Hash256 => sha256d(data).0.to_vec(),
+            HashFunction::Hash160 => hash160(data).0.to_vec(),
+        }
+    }
+
+    /// Compute the digest of `data` in the circuit
+    pub fn hash_gadget<F: PrimeField>(
+        &self,
+        data: &[UInt8<F>],
+    ) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        match self {
+            HashFunction::Sha256 => Ok(Sha256Gadget::<F>::digest(data)?.0),
+            HashFunction::Hash256 => Ok(Hash256Gadget::<F>::evaluate(data)?.0),
+            HashFunction::Hash160 => Hash160Gadget::<F>::evaluate(data),
+        }
+    }
+}
+
+/// Bitcoin Predicate implementing a hash puzzle: the witness is a preimage of `N_PREIMAGE` bytes of the
+/// digest in the locking data under `hash_function`
+pub struct HashPreimage<
+    const N_PREIMAGE: usize,
+    const N_DIGEST: usize,
+    F: PrimeField,
+    P: TxVarConfig + Clone,
+> {
+    pub hash_function: HashFunction,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<const N_PREIMAGE: usize, const N_DIGEST: usize, F: PrimeField, P: TxVarConfig + Clone>
+    HashPreimage<N_PREIMAGE, N_DIGEST, F, P>
+{
+    /// Returns [Error::ConfigMismatch] if `N_DIGEST` is not the size of the digests of `hash_function`
+    pub fn new(hash_function: HashFunction) -> Result<Self, Error> {
+        if N_DIGEST != hash_function.digest_size() {
+            return Err(Error::ConfigMismatch(format!(
+                "The size of the digest: {} is different from the size of the digests of {:?}: {}",
+                N_DIGEST,
+                hash_function,
+                hash_function.digest_size()
+            )));
+        }
+        Ok(Self {
+            hash_function,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        })
+    }
+}
+
+impl<const N_PREIMAGE: usize, const N_DIGEST: usize, F: PrimeField, P: TxVarConfig + Clone>
+    BitcoinPredicate<F, P> for HashPreimage<N_PREIMAGE, N_DIGEST, F, P>
+{
+    type LockingData = ByteArray<N_DIGEST, F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = ByteArray<N_PREIMAGE, F, P>;
+
+    type LockingDataVar = ByteArrayVar<N_DIGEST, F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = ByteArrayVar<N_PREIMAGE, F, P>;
+
+    fn generate_constraints(
+        &self,
+        _cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        _spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        self.hash_function
+            .hash_gadget(&witness.bytes)?
+            .is_eq(&locking_data.bytes.to_vec())
+    }
+
+    fn evaluate_native(
+        &self,
+        locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        _tx: &Tx,
+        witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        Ok(self.hash_function.hash(&witness.bytes) == locking_data.bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::script::Script;
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::{
+        byte_array::{ByteArray, ByteArrayVar},
+        unit::{BitcoinUnit, BitcoinUnitVar},
+    };
+    use crate::constraints::tx::{TxVar, TxVarConfig};
+    use crate::error::Error;
+    use crate::traits::BitcoinPredicate;
+
+    use super::{HashFunction, HashPreimage};
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0];
+    }
+
+    const PREIMAGE: [u8; 10] = *b"zkcovenant";
+
+    fn test_predicate<const N_DIGEST: usize>(
+        hash_function: HashFunction,
+        preimage: [u8; 10],
+    ) -> bool {
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 100,
+                lock_script: Script(vec![]),
+            }],
+            lock_time: 0,
+        };
+        let digest: [u8; N_DIGEST] = hash_function.hash(&PREIMAGE).try_into().unwrap();
+        let predicate = HashPreimage::<10, N_DIGEST, F, Config>::new(hash_function).unwrap();
+        let native = predicate
+            .evaluate_native(
+                &ByteArray::<N_DIGEST, F, Config>::new(digest),
+                &BitcoinUnit::default(),
+                &tx,
+                &ByteArray::<10, F, Config>::new(preimage),
+            )
+            .unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let locking_data = ByteArrayVar::<N_DIGEST, F, Config>::new_input(cs.clone(), || {
+            Ok(ByteArray::<N_DIGEST, F, Config>::new(digest))
+        })
+        .unwrap();
+        let witness = ByteArrayVar::<10, F, Config>::new_witness(cs.clone(), || {
+            Ok(ByteArray::<10, F, Config>::new(preimage))
+        })
+        .unwrap();
+        let tx_var = TxVar::<F, Config>::new_witness(cs.clone(), || Ok(tx)).unwrap();
+        predicate
+            .enforce_constraints(
+                cs.clone(),
+                &locking_data,
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &witness,
+            )
+            .unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), native);
+        native
+    }
+
+    #[test]
+    fn test_hash_preimage_is_ok() {
+        assert!(test_predicate::<32>(HashFunction::Sha256, PREIMAGE));
+        assert!(test_predicate::<32>(HashFunction::Hash256, PREIMAGE));
+        assert!(test_predicate::<20>(HashFunction::Hash160, PREIMAGE));
+    }
+
+    #[test]
+    fn test_hash_preimage_fails() {
+        let wrong_preimage = *b"zkcovenanu";
+        assert!(!test_predicate::<32>(HashFunction::Sha256, wrong_preimage));
+        assert!(!test_predicate::<32>(HashFunction::Hash256, wrong_preimage));
+        assert!(!test_predicate::<20>(HashFunction::Hash160, wrong_preimage));
+    }
+
+    #[test]
+    fn test_hash_preimage_wrong_digest_size() {
+        assert!(matches!(
+            HashPreimage::<10, 20, F, Config>::new(HashFunction::Sha256),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+}
@@ -0,0 +1,310 @@
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use ark_crypto_primitives::crh::{
+    CRHScheme,
+    sha256::{Sha256, constraints::Sha256Gadget},
+};
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    eq::EqGadget,
+    prelude::{AllocVar, AllocationMode, Boolean},
+    uint8::UInt8,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+
+use chain_gang::{messages::Tx, script::Script};
+
+use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+use crate::bitcoin_predicates::lock_time::MinLockTime;
+use crate::constraints::{
+    script::ScriptVar,
+    tx::{TxVar, TxVarConfig},
+};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+
+/// The witness of an [Htlc]: the preimage of the hash, missing for the refund.
+/// A missing preimage is replaced by `[0; N]`
+#[derive(Clone)]
+pub struct HtlcWitness<const N: usize, F: PrimeField, P: TxVarConfig + Clone> {
+    pub preimage: Option<[u8; N]>,
+    _field: PhantomData<F>,
+    _config: PhantomData<P>,
+}
+
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> HtlcWitness<N, F, P> {
+    pub fn new(preimage: Option<[u8; N]>) -> Self {
+        Self {
+            preimage,
+            _field: PhantomData,
+            _config: PhantomData,
+        }
+    }
+}
+
+pub struct HtlcWitnessVar<const N: usize, F: PrimeField, P: TxVarConfig + Clone> {
+    pub preimage: Vec<UInt8<F>>,
+    _config: PhantomData<P>,
+}
+
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> AllocVar<HtlcWitness<N, F, P>, F>
+    for HtlcWitnessVar<N, F, P>
+{
+    fn new_variable<T: Borrow<HtlcWitness<N, F, P>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let preimage: [u8; N] = f().map(|data| data.borrow().preimage.unwrap_or([0; N]))?;
+
+        Ok(Self {
+            preimage: Vec::<UInt8<F>>::new_variable(cs.clone(), || Ok(&preimage[..]), mode)?,
+            _config: PhantomData,
+        })
+    }
+}
+
+/// Bitcoin Predicate implementing a hash-timelock contract.
+///
+/// The predicate enforces that either:
+/// - the witness is a preimage of `hash` under SHA256, and the output at `index` pays to `recipient_lock_script`
+///   (claim), or
+/// - the lock time of the spending transaction is at least `deadline`, see [MinLockTime], and the output at
+///   `index` pays to `sender_lock_script` (refund).
+pub struct Htlc<const N: usize, F: PrimeField, P: TxVarConfig + Clone> {
+    pub hash: [u8; 32],
+    pub recipient_lock_script: Script,
+    pub sender_lock_script: Script,
+    pub index: usize,
+    pub deadline: u32,
+    pub input_index: usize,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> Htlc<N, F, P> {
+    pub fn new(
+        hash: [u8; 32],
+        recipient_lock_script: Script,
+        sender_lock_script: Script,
+        index: usize,
+        deadline: u32,
+        input_index: usize,
+    ) -> Self {
+        Self {
+            hash,
+            recipient_lock_script,
+            sender_lock_script,
+            index,
+            deadline,
+            input_index,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        }
+    }
+}
+
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P>
+    for Htlc<N, F, P>
+{
+    type LockingData = BitcoinUnit<F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = HtlcWitness<N, F, P>;
+
+    type LockingDataVar = BitcoinUnitVar<F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = HtlcWitnessVar<N, F, P>;
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let output = spending_data.output(self.index)?;
+
+        let lock_script = &output.lock_script;
+        let pays_to = |script: &Script| -> Result<Boolean<F>, SynthesisError> {
+            if script.0.len() != lock_script.0.len() {
+                return Ok(Boolean::<F>::FALSE);
+            }
+            lock_script.is_eq(&ScriptVar::<F>::new_constant(cs.clone(), script.clone())?)
+        };
+
+        let is_claimed = Boolean::<F>::kary_and(&[
+            Sha256Gadget::<F>::digest(&witness.preimage)?
+                .0
+                .is_eq(&UInt8::<F>::constant_vec(&self.hash))?,
+            pays_to(&self.recipient_lock_script)?,
+        ])?;
+        let is_refunded = Boolean::<F>::kary_and(&[
+            MinLockTime::<F, P>::new(self.deadline, self.input_index).generate_constraints(
+                cs.clone(),
+                locking_data,
+                unlocking_data,
+                spending_data,
+                &BitcoinUnitVar::default(),
+            )?,
+            pays_to(&self.sender_lock_script)?,
+        ])?;
+
+        Boolean::<F>::kary_or(&[is_claimed, is_refunded])
+    }
+
+    fn evaluate_native(
+        &self,
+        locking_data: &Self::LockingData,
+        unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        // Validate input
+        if self.index >= tx.outputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of outputs: {}",
+                self.index,
+                tx.outputs.len()
+            )));
+        }
+
+        let lock_script = &tx.outputs[self.index].lock_script;
+        let preimage = witness.preimage.unwrap_or([0; N]);
+        let is_claimed = <Sha256 as CRHScheme>::evaluate(&(), preimage.as_slice()).unwrap()
+            == self.hash
+            && lock_script.0 == self.recipient_lock_script.0;
+        let is_refunded = MinLockTime::<F, P>::new(self.deadline, self.input_index)
+            .evaluate_native(locking_data, unlocking_data, tx, &BitcoinUnit::default())?
+            && lock_script.0 == self.sender_lock_script.0;
+
+        Ok(is_claimed || is_refunded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use ark_bls12_381::Fr as F;
+    use ark_crypto_primitives::crh::{CRHScheme, sha256::Sha256};
+
+    use ark_r1cs_std::alloc::AllocVar;
+
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::address::addr_decode;
+    use chain_gang::script::Script;
+
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::network::Network;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+    use crate::constraints::tx::TxVar;
+
+    use crate::{constraints::tx::TxVarConfig, traits::BitcoinPredicate};
+
+    use super::{Htlc, HtlcWitness, HtlcWitnessVar};
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    const RECIPIENT: &str = "mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec";
+    const SENDER: &str = "mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz";
+    const PREIMAGE: [u8; 32] = [7; 32];
+    const DEADLINE: u32 = 800_000;
+
+    fn p2pkh_script(addr: &str) -> Script {
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        p2pkh::create_lock_script(&hash160)
+    }
+
+    fn test_predicate(preimage: Option<[u8; 32]>, addr: &str, lock_time: u32) -> bool {
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 1000,
+                lock_script: p2pkh_script(addr),
+            }],
+            lock_time,
+        };
+        let hash: [u8; 32] = <Sha256 as CRHScheme>::evaluate(&(), PREIMAGE.as_slice())
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let predicate = Htlc::<32, F, Config>::new(
+            hash,
+            p2pkh_script(RECIPIENT),
+            p2pkh_script(SENDER),
+            0,
+            DEADLINE,
+            0,
+        );
+
+        let native = predicate
+            .evaluate_native(
+                &BitcoinUnit::default(),
+                &BitcoinUnit::default(),
+                &tx,
+                &HtlcWitness::<32, F, Config>::new(preimage),
+            )
+            .unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        let witness = HtlcWitnessVar::<32, F, Config>::new_witness(cs.clone(), || {
+            Ok(HtlcWitness::<32, F, Config>::new(preimage))
+        })
+        .unwrap();
+        predicate
+            .enforce_constraints(
+                cs.clone(),
+                &BitcoinUnitVar::default(),
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &witness,
+            )
+            .unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), native);
+        native
+    }
+
+    #[test]
+    fn test_claim() {
+        assert!(test_predicate(Some(PREIMAGE), RECIPIENT, 0));
+        // Wrong preimage
+        assert!(!test_predicate(Some([8; 32]), RECIPIENT, 0));
+        // Paid to the sender before the deadline
+        assert!(!test_predicate(Some(PREIMAGE), SENDER, 0));
+    }
+
+    #[test]
+    fn test_refund() {
+        assert!(test_predicate(None, SENDER, DEADLINE));
+        // Too early
+        assert!(!test_predicate(None, SENDER, DEADLINE - 1));
+        // Paid to the recipient without the preimage
+        assert!(!test_predicate(None, RECIPIENT, DEADLINE));
+    }
+}
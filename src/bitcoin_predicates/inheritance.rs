@@ -0,0 +1,399 @@
+//! Inheritance (dead man's switch) covenants
+//!
+//! [Inheritance] is the OR combination of an owner path, [SignedBy], and of an heir path, the AND combination of
+//! [MinLockTime] and [FixedLockScript]: the owner can spend at any time, while the heir can claim the funds once
+//! the deadline has passed. Refreshing the coins before the deadline keeps the switch armed.
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use ark_ec::twisted_edwards::{Affine, TECurveConfig};
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::{AllocVar, AllocationMode},
+    fields::fp::FpVar,
+    prelude::Boolean,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+use chain_gang::script::Script;
+
+use crate::bitcoin_predicates::data_structures::{
+    field_array::{FieldArray, FieldArrayVar},
+    unit::{BitcoinUnit, BitcoinUnitVar},
+};
+use crate::bitcoin_predicates::{
+    fixed_lock_script::FixedLockScript,
+    lock_time::MinLockTime,
+    signed_by::{SignatureWitness, SignatureWitnessVar, SignedBy},
+};
+use crate::constraints::tx::{TxVar, TxVarConfig};
+use crate::traits::{BitcoinPredicate, PublicInputSize, SpendingContext, ToPublicInputGadget};
+use crate::transaction_integrity_gadget::TransactionIntegrityConfig;
+
+/// The locking data of [Inheritance]: the public key of the owner, and the (empty) locking data of the heir path
+#[derive(Clone)]
+pub struct InheritanceLockingData<F: PrimeField, P: TxVarConfig + Clone> {
+    pub owner: FieldArray<2, F, P>,
+    pub heir: BitcoinUnit<F, P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> From<InheritanceLockingData<F, P>> for Vec<F> {
+    fn from(data: InheritanceLockingData<F, P>) -> Vec<F> {
+        let mut out: Vec<F> = data.owner.into();
+        out.extend(Into::<Vec<F>>::into(data.heir));
+        out
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> PublicInputSize<F> for InheritanceLockingData<F, P> {
+    const N_PUBLIC_INPUTS: usize = <FieldArray<2, F, P> as PublicInputSize<F>>::N_PUBLIC_INPUTS
+        + <BitcoinUnit<F, P> as PublicInputSize<F>>::N_PUBLIC_INPUTS;
+}
+
+/// The R1CS version of [InheritanceLockingData]
+pub struct InheritanceLockingDataVar<F: PrimeField, P: TxVarConfig + Clone> {
+    pub owner: FieldArrayVar<2, F, P>,
+    pub heir: BitcoinUnitVar<F, P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> AllocVar<InheritanceLockingData<F, P>, F>
+    for InheritanceLockingDataVar<F, P>
+{
+    fn new_variable<T: Borrow<InheritanceLockingData<F, P>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let data: InheritanceLockingData<F, P> = f().map(|data| data.borrow().clone())?;
+        Ok(Self {
+            owner: FieldArrayVar::<2, F, P>::new_variable(cs.clone(), || Ok(data.owner), mode)?,
+            heir: BitcoinUnitVar::<F, P>::new_variable(cs.clone(), || Ok(data.heir), mode)?,
+        })
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> ToPublicInputGadget<F>
+    for InheritanceLockingDataVar<F, P>
+{
+    fn to_public_input(&self) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        let mut out = self.owner.to_public_input()?;
+        out.extend(self.heir.to_public_input()?);
+        Ok(out)
+    }
+}
+
+/// The witness of [Inheritance]: the witness of the owner path, with a missing signature if the heir is spending,
+/// and the (empty) witness of the heir path
+pub struct InheritanceWitness<F: PrimeField, C: TECurveConfig, P: TxVarConfig + Clone> {
+    pub owner: SignatureWitness<C>,
+    pub heir: BitcoinUnit<F, P>,
+}
+
+impl<F: PrimeField, C: TECurveConfig, P: TxVarConfig + Clone> Clone
+    for InheritanceWitness<F, C, P>
+{
+    fn clone(&self) -> Self {
+        Self {
+            owner: self.owner.clone(),
+            heir: self.heir.clone(),
+        }
+    }
+}
+
+/// The R1CS version of [InheritanceWitness]
+pub struct InheritanceWitnessVar<
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+    P: TxVarConfig + Clone,
+> {
+    pub owner: SignatureWitnessVar<F, C>,
+    pub heir: BitcoinUnitVar<F, P>,
+}
+
+impl<F, C, P> AllocVar<InheritanceWitness<F, C, P>, F> for InheritanceWitnessVar<F, C, P>
+where
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+    P: TxVarConfig + Clone,
+{
+    fn new_variable<T: Borrow<InheritanceWitness<F, C, P>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let witness: InheritanceWitness<F, C, P> = f().map(|data| data.borrow().clone())?;
+        Ok(Self {
+            owner: SignatureWitnessVar::<F, C>::new_variable(
+                cs.clone(),
+                || Ok(witness.owner),
+                mode,
+            )?,
+            heir: BitcoinUnitVar::<F, P>::new_variable(cs.clone(), || Ok(witness.heir), mode)?,
+        })
+    }
+}
+
+/// Bitcoin Predicate enforcing that either the spending transaction is signed by the owner, see [SignedBy], or its
+/// lock time is at least `deadline`, see [MinLockTime], and its output at `index` pays to `heir_lock_script`.
+/// As for [SignedBy], the owner path requires the sighash computed by the integrity scheme
+pub struct Inheritance<
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+    P: TransactionIntegrityConfig + TxVarConfig + Clone,
+> {
+    pub owner: SignedBy<F, C, P>,
+    pub heir_lock_time: MinLockTime<F, P>,
+    pub heir_lock_script: FixedLockScript<F, P>,
+}
+
+impl<F, C, P> Inheritance<F, C, P>
+where
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+    P: TransactionIntegrityConfig + TxVarConfig + Clone,
+{
+    pub fn new(deadline: u32, heir_lock_script: Script, index: usize, input_index: usize) -> Self {
+        Self {
+            owner: SignedBy::new(),
+            heir_lock_time: MinLockTime::new(deadline, input_index),
+            heir_lock_script: FixedLockScript::new(heir_lock_script, index),
+        }
+    }
+
+    /// The locking data for the owner with public key `public_key`
+    pub fn locking_data(public_key: &Affine<C>) -> InheritanceLockingData<F, P> {
+        InheritanceLockingData {
+            owner: SignedBy::<F, C, P>::locking_data(public_key),
+            heir: BitcoinUnit::default(),
+        }
+    }
+}
+
+impl<F, C, P> BitcoinPredicate<F, P> for Inheritance<F, C, P>
+where
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+    P: TransactionIntegrityConfig + TxVarConfig + Clone,
+{
+    type LockingData = InheritanceLockingData<F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = InheritanceWitness<F, C, P>;
+
+    type LockingDataVar = InheritanceLockingDataVar<F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = InheritanceWitnessVar<F, C, P>;
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        self.generate_constraints_with_context(
+            cs,
+            locking_data,
+            unlocking_data,
+            spending_data,
+            witness,
+            &SpendingContext::default(),
+        )
+    }
+
+    fn generate_constraints_with_context(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+        context: &SpendingContext<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let is_signed_by_owner = self.owner.generate_constraints_with_context(
+            cs.clone(),
+            &locking_data.owner,
+            unlocking_data,
+            spending_data,
+            &witness.owner,
+            context,
+        )?;
+        let is_claimed_by_heir = Boolean::<F>::kary_and(&[
+            self.heir_lock_time.generate_constraints(
+                cs.clone(),
+                &locking_data.heir,
+                unlocking_data,
+                spending_data,
+                &witness.heir,
+            )?,
+            self.heir_lock_script.generate_constraints(
+                cs.clone(),
+                &locking_data.heir,
+                unlocking_data,
+                spending_data,
+                &witness.heir,
+            )?,
+        ])?;
+
+        Boolean::<F>::kary_or(&[is_signed_by_owner, is_claimed_by_heir])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_ed_on_bls12_381::EdwardsConfig;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::script::Script;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::transaction::sighash::{SIGHASH_ALL, SIGHASH_FORKID};
+    use chain_gang::util::{Hash160, Hash256};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+    use crate::bitcoin_predicates::signed_by::{SignatureWitness, sighash_message};
+    use crate::constraints::{
+        sighash_cache::SigHashCacheVar,
+        tx::{TxVar, TxVarConfig},
+    };
+    use crate::schnorr::Schnorr;
+    use crate::traits::{BitcoinPredicate, SpendingContext};
+    use crate::transaction_integrity_gadget::{
+        TransactionIntegrityConfig,
+        scheme::{PrevOutput, PrevOutputVar},
+    };
+
+    use super::{
+        Inheritance, InheritanceLockingDataVar, InheritanceWitness, InheritanceWitnessVar,
+    };
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    impl TransactionIntegrityConfig for Config {
+        const N_INPUT: usize = 0;
+        const LEN_PREV_LOCK_SCRIPT: usize = 0x19;
+        const SIGHASH_FLAG: u8 = SIGHASH_ALL | SIGHASH_FORKID;
+    }
+
+    type Predicate = Inheritance<F, EdwardsConfig, Config>;
+
+    const DEADLINE: u32 = 800_000;
+
+    fn lock_script(n: u8) -> Script {
+        p2pkh::create_lock_script(&Hash160([n; 20]))
+    }
+
+    fn heir_script() -> Script {
+        lock_script(1)
+    }
+
+    fn tx(lock_time: u32, recipient: Script) -> Tx {
+        Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xfffffffe,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 900,
+                lock_script: recipient,
+            }],
+            lock_time,
+        }
+    }
+
+    fn prev_output() -> PrevOutput {
+        PrevOutput {
+            prev_lock_script: lock_script(3),
+            prev_amount: 1000,
+        }
+    }
+
+    fn test_predicate(tx: Tx, is_signed: bool) -> bool {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let (secret_key, public_key) = Schnorr::<EdwardsConfig>::keygen(&mut rng);
+        let signature = is_signed.then(|| {
+            let message = sighash_message::<Config>(&tx, &prev_output()).unwrap();
+            Schnorr::<EdwardsConfig>::sign(&mut rng, &secret_key, &message)
+        });
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let locking_data = InheritanceLockingDataVar::<F, Config>::new_input(cs.clone(), || {
+            Ok(Predicate::locking_data(&public_key))
+        })
+        .unwrap();
+        let witness =
+            InheritanceWitnessVar::<F, EdwardsConfig, Config>::new_witness(cs.clone(), || {
+                Ok(InheritanceWitness {
+                    owner: SignatureWitness { signature },
+                    heir: BitcoinUnit::default(),
+                })
+            })
+            .unwrap();
+        let tx_var = TxVar::<F, Config>::new_witness(cs.clone(), || Ok(tx)).unwrap();
+        // The sighash computed by the integrity scheme
+        let prev_output_var =
+            PrevOutputVar::<F>::new_witness(cs.clone(), || Ok(prev_output())).unwrap();
+        let sighash = tx_var
+            .sighash(
+                Config::N_INPUT,
+                &prev_output_var.prev_lock_script,
+                &prev_output_var.prev_amount,
+                &Config::SIGHASH_FLAG,
+                &mut SigHashCacheVar::<F>::new(),
+            )
+            .unwrap();
+        Predicate::new(DEADLINE, heir_script(), 0, 0)
+            .enforce_constraints_with_context(
+                cs.clone(),
+                &locking_data,
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &witness,
+                &SpendingContext {
+                    sighash: Some(&sighash),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_inheritance_is_ok() {
+        // The owner spends before the deadline
+        assert!(test_predicate(tx(0, lock_script(2)), true));
+        // The heir claims after the deadline
+        assert!(test_predicate(tx(DEADLINE, heir_script()), false));
+    }
+
+    #[test]
+    fn test_inheritance_fails() {
+        // The heir claims before the deadline
+        assert!(!test_predicate(tx(DEADLINE - 1, heir_script()), false));
+        // After the deadline, the funds must go to the heir
+        assert!(!test_predicate(tx(DEADLINE, lock_script(2)), false));
+    }
+}
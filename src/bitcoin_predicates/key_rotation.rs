@@ -0,0 +1,312 @@
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    eq::EqGadget,
+    fields::fp::FpVar,
+    prelude::{AllocVar, Boolean},
+    select::CondSelectGadget,
+    uint32::UInt32,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+
+use chain_gang::{messages::Tx, script::Script};
+
+use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+use crate::constraints::{
+    script::ScriptVar,
+    tx::{TxVar, TxVarConfig},
+};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+use crate::util::{LOCKTIME_THRESHOLD, SEQUENCE_FINAL, is_less_than, to_fp};
+
+/// Bitcoin Predicate implementing a time-locked key rotation: the output of the transaction at `index`
+/// must have locking script `lock_script_after` if the transaction is time-locked at a block height
+/// of at least `rotation_height`, and locking script `lock_script_before` otherwise.
+///
+/// The lock time is treated with `OP_CHECKLOCKTIMEVERIFY` semantics: it only counts if it is a block height
+/// (i.e., it is below [LOCKTIME_THRESHOLD]) and the input at `input_index` is not final.
+///
+/// **Note**: A lock time only bounds from below the height at which the transaction can be mined.
+/// Hence, the predicate guarantees that `lock_script_after` is used from `rotation_height` onwards whenever
+/// the spender claims that height, but it cannot prevent a spend to `lock_script_before` after `rotation_height`.
+/// Both locking scripts must have the length set in `P` for the output at `index`.
+pub struct KeyRotation<F: PrimeField, P: TxVarConfig + Clone> {
+    pub lock_script_before: Script,
+    pub lock_script_after: Script,
+    pub rotation_height: u32,
+    pub index: usize,
+    pub input_index: usize,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> KeyRotation<F, P> {
+    /// Returns [Error::ConfigMismatch] if `rotation_height` is not a block height
+    pub fn new(
+        lock_script_before: Script,
+        lock_script_after: Script,
+        rotation_height: u32,
+        index: usize,
+        input_index: usize,
+    ) -> Result<Self, Error> {
+        if rotation_height >= LOCKTIME_THRESHOLD {
+            return Err(Error::ConfigMismatch(format!(
+                "The rotation height: {} is not a block height",
+                rotation_height
+            )));
+        }
+        Ok(Self {
+            lock_script_before,
+            lock_script_after,
+            rotation_height,
+            index,
+            input_index,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        })
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for KeyRotation<F, P> {
+    type LockingData = BitcoinUnit<F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = BitcoinUnitVar<F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        _locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        // Validate input
+        if self.index >= spending_data.outputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of outputs: {}",
+                self.index,
+                spending_data.outputs.len()
+            ))
+            .into());
+        }
+        if self.input_index >= spending_data.inputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Input index: {} is larger than the number of inputs: {}",
+                self.input_index,
+                spending_data.inputs.len()
+            ))
+            .into());
+        }
+
+        // Check whether the transaction is time-locked at a height of at least `self.rotation_height`
+        let lock_time: FpVar<F> = to_fp(&spending_data.lock_time)?;
+        let is_block_height = is_less_than(
+            &lock_time,
+            &FpVar::<F>::constant(F::from(LOCKTIME_THRESHOLD)),
+            32,
+        )?;
+        let is_not_final = spending_data.inputs[self.input_index]
+            .sequence
+            .is_neq(&UInt32::<F>::constant(SEQUENCE_FINAL))?;
+        let is_after_rotation = !is_less_than(
+            &lock_time,
+            &FpVar::<F>::constant(F::from(self.rotation_height)),
+            32,
+        )?;
+        let is_rotated =
+            Boolean::<F>::kary_and(&[is_block_height, is_not_final, is_after_rotation])?;
+
+        // Enforce that output at index `self.index` has the locking script of the current key
+        let lock_script = &spending_data.outputs[self.index].lock_script;
+        let pays_before = lock_script.is_eq(&ScriptVar::<F>::new_constant(
+            cs.clone(),
+            self.lock_script_before.clone(),
+        )?)?;
+        let pays_after = lock_script.is_eq(&ScriptVar::<F>::new_constant(
+            cs.clone(),
+            self.lock_script_after.clone(),
+        )?)?;
+
+        Boolean::<F>::conditionally_select(&is_rotated, &pays_after, &pays_before)
+    }
+
+    fn evaluate_native(
+        &self,
+        _locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        // Validate input
+        if self.index >= tx.outputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of outputs: {}",
+                self.index,
+                tx.outputs.len()
+            )));
+        }
+        if self.input_index >= tx.inputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Input index: {} is larger than the number of inputs: {}",
+                self.input_index,
+                tx.inputs.len()
+            )));
+        }
+
+        let is_rotated = tx.lock_time < LOCKTIME_THRESHOLD
+            && tx.inputs[self.input_index].sequence != SEQUENCE_FINAL
+            && tx.lock_time >= self.rotation_height;
+        let lock_script = &tx.outputs[self.index].lock_script;
+        if is_rotated {
+            Ok(lock_script.0 == self.lock_script_after.0)
+        } else {
+            Ok(lock_script.0 == self.lock_script_before.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use ark_bls12_381::Fr as F;
+
+    use ark_r1cs_std::alloc::AllocVar;
+
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::address::addr_decode;
+    use chain_gang::script::Script;
+
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::network::Network;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+    use crate::constraints::tx::TxVar;
+    use crate::error::Error;
+    use crate::util::LOCKTIME_THRESHOLD;
+
+    use crate::{constraints::tx::TxVarConfig, traits::BitcoinPredicate};
+
+    use super::KeyRotation;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    const ROTATION_HEIGHT: u32 = 800_000;
+
+    fn p2pkh_script(addr: &str) -> Script {
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        p2pkh::create_lock_script(&hash160)
+    }
+
+    fn test_predicate(lock_script: Script, lock_time: u32, sequence: u32, expected: bool) {
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 100,
+                lock_script,
+            }],
+            lock_time,
+        };
+
+        let predicate = KeyRotation::<F, Config>::new(
+            p2pkh_script("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec"),
+            p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz"),
+            ROTATION_HEIGHT,
+            0,
+            0,
+        )
+        .unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx.clone())).unwrap();
+        predicate
+            .enforce_constraints(
+                cs.clone(),
+                &BitcoinUnitVar::default(),
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &BitcoinUnitVar::default(),
+            )
+            .unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), expected);
+        assert_eq!(
+            predicate
+                .evaluate_native(
+                    &BitcoinUnit::default(),
+                    &BitcoinUnit::default(),
+                    &tx,
+                    &BitcoinUnit::default()
+                )
+                .unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_predicate_before_rotation() {
+        let key_a = p2pkh_script("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec");
+        let key_b = p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz");
+        test_predicate(key_a, ROTATION_HEIGHT - 1, 0, true);
+        test_predicate(key_b, ROTATION_HEIGHT - 1, 0, false);
+    }
+
+    #[test]
+    fn test_predicate_after_rotation() {
+        let key_a = p2pkh_script("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec");
+        let key_b = p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz");
+        test_predicate(key_b.clone(), ROTATION_HEIGHT, 0, true);
+        test_predicate(key_a, ROTATION_HEIGHT, 0, false);
+        test_predicate(key_b, ROTATION_HEIGHT + 1, 0, true);
+    }
+
+    #[test]
+    fn test_predicate_lock_time_not_enforced() {
+        let key_a = p2pkh_script("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec");
+        let key_b = p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz");
+        // Final input: the lock time is ignored
+        test_predicate(key_b.clone(), ROTATION_HEIGHT, 0xffffffff, false);
+        test_predicate(key_a.clone(), ROTATION_HEIGHT, 0xffffffff, true);
+        // The lock time is a timestamp
+        test_predicate(key_b, 1_700_000_000, 0, false);
+        test_predicate(key_a, 1_700_000_000, 0, true);
+    }
+
+    #[test]
+    fn test_rotation_height_not_a_block_height() {
+        assert!(matches!(
+            KeyRotation::<F, Config>::new(
+                p2pkh_script("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec"),
+                p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz"),
+                LOCKTIME_THRESHOLD,
+                0,
+                0,
+            ),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+}
@@ -0,0 +1,288 @@
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    eq::EqGadget,
+    fields::fp::FpVar,
+    prelude::{AllocVar, Boolean},
+    uint32::UInt32,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use chain_gang::messages::Tx;
+
+use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+use crate::constraints::tx::{TxVar, TxVarConfig};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+use crate::util::{LOCKTIME_THRESHOLD, SEQUENCE_FINAL, is_less_or_equal, is_less_than, to_fp};
+
+/// Bitcoin Predicate to enforce that the lock time of the spending transaction is at least `min_lock_time`,
+/// as `OP_CHECKLOCKTIMEVERIFY` does:
+/// - the lock time is of the same kind as `min_lock_time`: a block height if below [LOCKTIME_THRESHOLD],
+///   a UNIX timestamp otherwise
+/// - the input at `input_index` is not final, so that the lock time is enforced by the network
+pub struct MinLockTime<F: PrimeField, P: TxVarConfig + Clone> {
+    pub min_lock_time: u32,
+    pub input_index: usize,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> MinLockTime<F, P> {
+    pub fn new(min_lock_time: u32, input_index: usize) -> Self {
+        Self {
+            min_lock_time,
+            input_index,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for MinLockTime<F, P> {
+    type LockingData = BitcoinUnit<F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = BitcoinUnitVar<F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        _cs: ConstraintSystemRef<F>,
+        _locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        // Validate input
+        if self.input_index >= spending_data.inputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Input index: {} is larger than the number of inputs: {}",
+                self.input_index,
+                spending_data.inputs.len()
+            ))
+            .into());
+        }
+
+        let lock_time: FpVar<F> = to_fp(&spending_data.lock_time)?;
+        let is_block_height = is_less_than(
+            &lock_time,
+            &FpVar::<F>::constant(F::from(LOCKTIME_THRESHOLD)),
+            32,
+        )?;
+        let is_same_kind = is_block_height.is_eq(&Boolean::<F>::constant(
+            self.min_lock_time < LOCKTIME_THRESHOLD,
+        ))?;
+        let is_not_final = spending_data.inputs[self.input_index]
+            .sequence
+            .is_neq(&UInt32::<F>::constant(SEQUENCE_FINAL))?;
+
+        Boolean::<F>::kary_and(&[
+            is_same_kind,
+            is_less_or_equal(
+                &FpVar::<F>::constant(F::from(self.min_lock_time)),
+                &lock_time,
+                32,
+            )?,
+            is_not_final,
+        ])
+    }
+
+    fn evaluate_native(
+        &self,
+        _locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        // Validate input
+        if self.input_index >= tx.inputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Input index: {} is larger than the number of inputs: {}",
+                self.input_index,
+                tx.inputs.len()
+            )));
+        }
+
+        let is_same_kind =
+            (tx.lock_time < LOCKTIME_THRESHOLD) == (self.min_lock_time < LOCKTIME_THRESHOLD);
+        let is_not_final = tx.inputs[self.input_index].sequence != SEQUENCE_FINAL;
+        Ok(is_same_kind && self.min_lock_time <= tx.lock_time && is_not_final)
+    }
+}
+
+/// Bitcoin Predicate to enforce that the lock time of the spending transaction is equal to `lock_time`
+pub struct ExactLockTime<F: PrimeField, P: TxVarConfig + Clone> {
+    pub lock_time: u32,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> ExactLockTime<F, P> {
+    pub fn new(lock_time: u32) -> Self {
+        Self {
+            lock_time,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for ExactLockTime<F, P> {
+    type LockingData = BitcoinUnit<F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = BitcoinUnitVar<F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        _cs: ConstraintSystemRef<F>,
+        _locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        spending_data
+            .lock_time
+            .is_eq(&UInt32::<F>::constant(self.lock_time))
+    }
+
+    fn evaluate_native(
+        &self,
+        _locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        Ok(tx.lock_time == self.lock_time)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use ark_bls12_381::Fr as F;
+
+    use ark_r1cs_std::alloc::AllocVar;
+
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::script::Script;
+
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::unit::BitcoinUnitVar;
+    use crate::constraints::tx::TxVar;
+    use crate::util::LOCKTIME_THRESHOLD;
+
+    use crate::{constraints::tx::TxVarConfig, traits::BitcoinPredicate};
+
+    use super::{ExactLockTime, MinLockTime};
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0];
+    }
+
+    const HEIGHT: u32 = 800_000;
+    const TIMESTAMP: u32 = 1_700_000_000;
+
+    fn enforce_predicate<B>(predicate: B, lock_time: u32, sequence: u32) -> bool
+    where
+        B: BitcoinPredicate<
+                F,
+                Config,
+                LockingDataVar = BitcoinUnitVar<F, Config>,
+                UnlockingDataVar = BitcoinUnitVar<F, Config>,
+                WitnessVar = BitcoinUnitVar<F, Config>,
+            >,
+    {
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 100,
+                lock_script: Script(vec![]),
+            }],
+            lock_time,
+        };
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        predicate
+            .enforce_constraints(
+                cs.clone(),
+                &BitcoinUnitVar::default(),
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &BitcoinUnitVar::default(),
+            )
+            .unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_min_lock_time_is_ok() {
+        for min_lock_time in [HEIGHT, TIMESTAMP] {
+            let predicate = || MinLockTime::<F, Config>::new(min_lock_time, 0);
+            assert!(enforce_predicate(predicate(), min_lock_time, 0));
+            assert!(enforce_predicate(predicate(), min_lock_time + 10, 0));
+        }
+    }
+
+    #[test]
+    fn test_min_lock_time_fails() {
+        for min_lock_time in [HEIGHT, TIMESTAMP] {
+            let predicate = || MinLockTime::<F, Config>::new(min_lock_time, 0);
+            // Lock time too early
+            assert!(!enforce_predicate(predicate(), min_lock_time - 1, 0));
+            // Lock time not enforced
+            assert!(!enforce_predicate(predicate(), min_lock_time, 0xffffffff));
+        }
+        // Timestamp against a block height, which is numerically larger
+        assert!(!enforce_predicate(
+            MinLockTime::<F, Config>::new(HEIGHT, 0),
+            TIMESTAMP,
+            0
+        ));
+        // Block height against a timestamp
+        assert!(!enforce_predicate(
+            MinLockTime::<F, Config>::new(LOCKTIME_THRESHOLD, 0),
+            LOCKTIME_THRESHOLD - 1,
+            0
+        ));
+    }
+
+    #[test]
+    fn test_exact_lock_time() {
+        assert!(enforce_predicate(
+            ExactLockTime::<F, Config>::new(HEIGHT),
+            HEIGHT,
+            0
+        ));
+        assert!(!enforce_predicate(
+            ExactLockTime::<F, Config>::new(HEIGHT),
+            HEIGHT + 1,
+            0
+        ));
+    }
+}
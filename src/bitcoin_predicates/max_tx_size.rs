@@ -0,0 +1,178 @@
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    fields::{FieldVar, fp::FpVar},
+    prelude::Boolean,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use chain_gang::messages::Tx;
+
+use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+use crate::constraints::tx::{TxVar, TxVarConfig};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+use crate::util::{is_less_or_equal, tx_size};
+
+/// Bit size bounding the size of a transaction
+pub const TX_SIZE_BIT_SIZE: usize = 32;
+
+/// Bitcoin Predicate enforcing that the serialised spending transaction is at most `max_size` bytes long,
+/// e.g., to guarantee that it does not exceed the policy limits of the miners.
+///
+/// **Note**: As the lengths of the scripts are set in `P`, the size is a constant of the circuit, see
+/// [TxVar::serialised_len], and the predicate does not generate any constraint.
+pub struct MaxTxSize<F: PrimeField, P: TxVarConfig + Clone> {
+    pub max_size: usize,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> MaxTxSize<F, P> {
+    /// Returns [Error::ConfigMismatch] if `max_size` does not fit in [TX_SIZE_BIT_SIZE] bits
+    pub fn new(max_size: usize) -> Result<Self, Error> {
+        if max_size >= 1 << TX_SIZE_BIT_SIZE {
+            return Err(Error::ConfigMismatch(format!(
+                "The maximum size: {} does not fit in {} bits",
+                max_size, TX_SIZE_BIT_SIZE
+            )));
+        }
+        Ok(Self {
+            max_size,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        })
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for MaxTxSize<F, P> {
+    type LockingData = BitcoinUnit<F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = BitcoinUnitVar<F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        _cs: ConstraintSystemRef<F>,
+        _locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        _spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        is_less_or_equal(
+            &FpVar::<F>::constant(F::from(TxVar::<F, P>::serialised_len() as u64)),
+            &FpVar::<F>::constant(F::from(self.max_size as u64)),
+            TX_SIZE_BIT_SIZE,
+        )
+    }
+
+    fn evaluate_native(
+        &self,
+        _locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        Ok(tx_size(tx) <= self.max_size)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use ark_bls12_381::Fr as F;
+
+    use ark_r1cs_std::alloc::AllocVar;
+
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::address::addr_decode;
+    use chain_gang::script::Script;
+
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::network::Network;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::util::{Hash256, Serializable};
+
+    use crate::bitcoin_predicates::data_structures::unit::BitcoinUnitVar;
+    use crate::constraints::tx::TxVar;
+    use crate::error::Error;
+
+    use crate::{constraints::tx::TxVarConfig, traits::BitcoinPredicate};
+
+    use super::MaxTxSize;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    fn test_predicate(max_size: usize, expected: bool) {
+        let hash160 = addr_decode("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec", Network::BSV_Testnet)
+            .unwrap()
+            .0;
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 100,
+                lock_script: p2pkh::create_lock_script(&hash160),
+            }],
+            lock_time: 0,
+        };
+        let mut tx_bytes: Vec<u8> = Vec::new();
+        tx.write(&mut tx_bytes).unwrap();
+        assert_eq!(tx_bytes.len(), 85);
+
+        let predicate = MaxTxSize::<F, Config>::new(max_size).unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        // The predicate is a constant, so enforcing it fails at synthesis time if it is false
+        let is_enforced = predicate
+            .enforce_constraints(
+                cs.clone(),
+                &BitcoinUnitVar::default(),
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &BitcoinUnitVar::default(),
+            )
+            .is_ok();
+        assert_eq!(is_enforced && cs.is_satisfied().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_max_tx_size_is_ok() {
+        test_predicate(85, true);
+        test_predicate(100_000, true);
+    }
+
+    #[test]
+    fn test_max_tx_size_fails() {
+        test_predicate(84, false);
+    }
+
+    #[test]
+    fn test_max_tx_size_too_large() {
+        assert!(matches!(
+            MaxTxSize::<F, Config>::new(1 << 32),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+}
@@ -1,3 +1,173 @@
+pub mod ancestry;
+pub mod auction;
+pub mod boxed;
+pub mod canonical_ordering;
+pub mod checksig_flag;
+pub mod coinbase;
+pub mod combinators;
+pub mod committed_locking_data;
+pub mod consolidation;
+pub mod counter;
+pub mod crowdfund;
 pub mod data_structures;
+pub mod dust_limit;
+pub mod escrow;
+pub mod fee_rate;
 pub mod fixed_lock_script;
+pub mod fixed_outpoint;
 pub mod fixed_sub_lock_script;
+pub mod fixed_sub_unlock_script;
+pub mod fixed_unlock_script;
+pub mod hash_preimage;
+pub mod htlc;
+pub mod inheritance;
+pub mod key_rotation;
+pub mod lock_time;
+pub mod max_tx_size;
+pub mod multisig;
+pub mod not;
+pub mod nullifier;
+pub mod oracle;
+pub mod output_amount_range;
+pub mod output_template;
+pub mod parent_tx;
+pub mod percentage_split;
+#[cfg(feature = "recursion")]
+pub mod recursive_proof;
+pub mod relative_lock_time;
+pub mod self_replicating_script;
+pub mod signed_by;
+pub mod state_transition;
+pub mod subscription;
+pub mod token;
+pub mod value;
+pub mod version;
+pub mod vesting;
+pub mod whitelist;
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_ec::{AffineRepr, twisted_edwards::Affine};
+    use ark_ed_on_bls12_381::EdwardsConfig;
+    use ark_r1cs_std::{fields::fp::FpVar, prelude::Boolean};
+    use ark_relations::r1cs::SynthesisError;
+    use chain_gang::script::Script;
+    use chain_gang::transaction::sighash::{SIGHASH_ALL, SIGHASH_FORKID};
+
+    use crate::bitcoin_predicates::{
+        auction::Auction,
+        data_structures::{byte_array::ByteArray, field_array::FieldArray, unit::BitcoinUnit},
+        escrow::{Escrow, EscrowSignatures},
+        inheritance::{Inheritance, InheritanceWitness},
+        multisig::{MultiSig, MultiSigWitness},
+        self_replicating_script::SelfReplicatingScript,
+        signed_by::{KeyHashSignatureWitness, SignatureWitness, SignedBy, SignedByKeyHash},
+        state_transition::StateTransition,
+    };
+    use crate::constraints::tx::TxVarConfig;
+    use crate::error::Error;
+    use crate::traits::BitcoinPredicate;
+    use crate::transaction_integrity_gadget::TransactionIntegrityConfig;
+    use crate::util::default_tx;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 3;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0, 0, 0];
+    }
+
+    impl TransactionIntegrityConfig for Config {
+        const N_INPUT: usize = 0;
+        const LEN_PREV_LOCK_SCRIPT: usize = 0;
+        const SIGHASH_FLAG: u8 = SIGHASH_ALL | SIGHASH_FORKID;
+    }
+
+    fn is_not_implemented(result: Result<bool, Error>) -> bool {
+        matches!(result, Err(Error::NotImplemented(_)))
+    }
+
+    /// The predicates which depend on data only available in the circuit: the sighash or the locking script of
+    /// the output being spent, exposed by the integrity scheme, or a gadget supplied by the caller.
+    /// Every other predicate supports [BitcoinPredicate::evaluate_native]
+    #[test]
+    fn test_native_evaluation_not_implemented() {
+        let tx = default_tx::<Config>();
+        let unit = BitcoinUnit::<F, Config>::default();
+        let public_key = Affine::<EdwardsConfig>::generator();
+        let signature = SignatureWitness::<EdwardsConfig> { signature: None };
+
+        assert!(is_not_implemented(
+            SignedBy::<F, EdwardsConfig, Config>::new().evaluate_native(
+                &FieldArray::default(),
+                &unit,
+                &tx,
+                &signature,
+            )
+        ));
+        assert!(is_not_implemented(
+            SignedByKeyHash::<F, EdwardsConfig, Config>::new().evaluate_native(
+                &ByteArray::default(),
+                &unit,
+                &tx,
+                &KeyHashSignatureWitness {
+                    public_key,
+                    signature_witness: signature.clone(),
+                },
+            )
+        ));
+        assert!(is_not_implemented(
+            MultiSig::<1, 1, F, EdwardsConfig, Config>::new([public_key])
+                .unwrap()
+                .evaluate_native(&unit, &unit, &tx, &MultiSigWitness { signatures: vec![] },)
+        ));
+        assert!(is_not_implemented(
+            Escrow::<F, EdwardsConfig, Config>::new(
+                public_key,
+                public_key,
+                public_key,
+                Script::new(),
+                Script::new(),
+                0,
+                1,
+            )
+            .unwrap()
+            .evaluate_native(
+                &unit,
+                &FieldArray::default(),
+                &tx,
+                &EscrowSignatures::new(None, None, None),
+            )
+        ));
+        assert!(is_not_implemented(
+            Inheritance::<F, EdwardsConfig, Config>::new(0, Script::new(), 0, 0).evaluate_native(
+                &Inheritance::<F, EdwardsConfig, Config>::locking_data(&public_key),
+                &unit,
+                &tx,
+                &InheritanceWitness {
+                    owner: signature,
+                    heir: unit.clone(),
+                },
+            )
+        ));
+        assert!(is_not_implemented(
+            Auction::<F, Config>::new(Script::new(), 0, 0, 0)
+                .unwrap()
+                .evaluate_native(&ByteArray::default(), &unit, &tx, &unit)
+        ));
+        assert!(is_not_implemented(
+            SelfReplicatingScript::<F, Config>::new(0).evaluate_native(&unit, &unit, &tx, &unit)
+        ));
+        let transition = |_old_state: &[FpVar<F>],
+                          _new_state: &[FpVar<F>]|
+         -> Result<Boolean<F>, SynthesisError> { Ok(Boolean::<F>::TRUE) };
+        assert!(is_not_implemented(
+            StateTransition::<1, F, Config, _>::new(0, 4, transition)
+                .unwrap()
+                .evaluate_native(&FieldArray::default(), &unit, &tx, &unit)
+        ));
+    }
+}
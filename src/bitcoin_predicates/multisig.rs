@@ -0,0 +1,372 @@
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use ark_ec::twisted_edwards::{Affine, TECurveConfig};
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::{AllocVar, AllocationMode},
+    eq::EqGadget,
+    fields::{FieldVar, fp::FpVar},
+    groups::curves::twisted_edwards::AffineVar,
+    prelude::Boolean,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+
+use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+use crate::bitcoin_predicates::signed_by::expect_sighash;
+use crate::constraints::tx::{TxVar, TxVarConfig};
+use crate::error::Error;
+use crate::schnorr::{
+    SchnorrSignature,
+    constraints::{SchnorrGadget, SchnorrSignatureVar},
+};
+use crate::traits::{BitcoinPredicate, SpendingContext};
+use crate::transaction_integrity_gadget::TransactionIntegrityConfig;
+
+/// The witness of [MultiSig]: the signatures of [sighash_message](crate::bitcoin_predicates::signed_by::sighash_message),
+/// each with the index of the public key it is valid against. Missing signatures are replaced by
+/// [SchnorrSignature::default] against the first public key, which is never valid
+pub struct MultiSigWitness<C: TECurveConfig> {
+    pub signatures: Vec<(usize, SchnorrSignature<C>)>,
+}
+
+impl<C: TECurveConfig> Clone for MultiSigWitness<C> {
+    fn clone(&self) -> Self {
+        Self {
+            signatures: self.signatures.clone(),
+        }
+    }
+}
+
+/// The R1CS version of [MultiSigWitness]. The index of the public key of each signature is allocated as
+/// a one-hot vector of `N` selectors
+pub struct MultiSigWitnessVar<
+    const K: usize,
+    const N: usize,
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+> {
+    pub selectors: Vec<Vec<Boolean<F>>>,
+    pub signatures: Vec<SchnorrSignatureVar<F, C>>,
+}
+
+impl<const K: usize, const N: usize, F: PrimeField, C: TECurveConfig<BaseField = F>>
+    AllocVar<MultiSigWitness<C>, F> for MultiSigWitnessVar<K, N, F, C>
+{
+    fn new_variable<T: Borrow<MultiSigWitness<C>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let witness: MultiSigWitness<C> = f().map(|data| data.borrow().clone())?;
+        if witness.signatures.len() > K || witness.signatures.iter().any(|(index, _)| *index >= N) {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        let mut selectors: Vec<Vec<Boolean<F>>> = Vec::with_capacity(K);
+        let mut signatures: Vec<SchnorrSignatureVar<F, C>> = Vec::with_capacity(K);
+        for i in 0..K {
+            let (index, signature) = witness
+                .signatures
+                .get(i)
+                .cloned()
+                .unwrap_or((0, SchnorrSignature::default()));
+            selectors.push(Vec::<Boolean<F>>::new_variable(
+                cs.clone(),
+                || Ok((0..N).map(|j| j == index).collect::<Vec<bool>>()),
+                mode,
+            )?);
+            signatures.push(SchnorrSignatureVar::<F, C>::new_variable(
+                cs.clone(),
+                || Ok(signature),
+                mode,
+            )?);
+        }
+
+        Ok(Self {
+            selectors,
+            signatures,
+        })
+    }
+}
+
+/// Bitcoin Predicate enforcing that the witness contains `K` signatures of the sighash of the spending transaction
+/// (see [SignedBy](crate::bitcoin_predicates::signed_by::SignedBy)) under `K` distinct keys among `public_keys`.
+///
+/// Each signature selects its public key with a one-hot vector, and each public key is selected at most once, so
+/// that a signature cannot be counted twice. As for [SignedBy](crate::bitcoin_predicates::signed_by::SignedBy),
+/// the sighash is the one computed by the integrity scheme.
+pub struct MultiSig<
+    const K: usize,
+    const N: usize,
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+    P: TransactionIntegrityConfig + TxVarConfig + Clone,
+> {
+    pub public_keys: [Affine<C>; N],
+    _phantom_config: PhantomData<P>,
+}
+
+impl<const K: usize, const N: usize, F, C, P> MultiSig<K, N, F, C, P>
+where
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+    P: TransactionIntegrityConfig + TxVarConfig + Clone,
+{
+    /// Returns [Error::ConfigMismatch] if the threshold `K` is not in `1..=N`
+    pub fn new(public_keys: [Affine<C>; N]) -> Result<Self, Error> {
+        if K == 0 || K > N {
+            return Err(Error::ConfigMismatch(format!(
+                "The threshold: {} is not compatible with the number of public keys: {}",
+                K, N
+            )));
+        }
+        Ok(Self {
+            public_keys,
+            _phantom_config: PhantomData,
+        })
+    }
+}
+
+impl<const K: usize, const N: usize, F, C, P> BitcoinPredicate<F, P> for MultiSig<K, N, F, C, P>
+where
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+    P: TransactionIntegrityConfig + TxVarConfig + Clone,
+{
+    type LockingData = BitcoinUnit<F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = MultiSigWitness<C>;
+
+    type LockingDataVar = BitcoinUnitVar<F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = MultiSigWitnessVar<K, N, F, C>;
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        self.generate_constraints_with_context(
+            cs,
+            locking_data,
+            unlocking_data,
+            spending_data,
+            witness,
+            &SpendingContext::default(),
+        )
+    }
+
+    fn generate_constraints_with_context(
+        &self,
+        _cs: ConstraintSystemRef<F>,
+        _locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        _spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+        context: &SpendingContext<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let sighash = expect_sighash(context.sighash)?;
+
+        let mut checks: Vec<Boolean<F>> = Vec::with_capacity(2 * K + N);
+        let mut key_usage: Vec<FpVar<F>> = vec![FpVar::<F>::zero(); N];
+        for (selector, signature) in witness.selectors.iter().zip(witness.signatures.iter()) {
+            // The selector is one-hot, so the selected key is a linear combination of the public keys
+            let mut n_selected = FpVar::<F>::zero();
+            let mut x = FpVar::<F>::zero();
+            let mut y = FpVar::<F>::zero();
+            for (j, (bit, public_key)) in selector.iter().zip(self.public_keys.iter()).enumerate() {
+                let bit = FpVar::<F>::from(bit.clone());
+                n_selected += &bit;
+                key_usage[j] += &bit;
+                x += &bit * public_key.x;
+                y += &bit * public_key.y;
+            }
+            checks.push(n_selected.is_one()?);
+            checks.push(SchnorrGadget::<F, C>::verify(
+                &AffineVar::<C, FpVar<F>>::new(x, y),
+                &sighash.0,
+                signature,
+            )?);
+        }
+
+        // Each public key is used at most once
+        for usage in key_usage.iter() {
+            checks.push(Boolean::<F>::kary_or(&[usage.is_zero()?, usage.is_one()?])?);
+        }
+
+        Boolean::<F>::kary_and(&checks)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_ed_on_bls12_381::EdwardsConfig;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::address::addr_decode;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::network::Network;
+    use chain_gang::script::Script;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::transaction::sighash::{SIGHASH_ALL, SIGHASH_FORKID};
+    use chain_gang::util::Hash256;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::bitcoin_predicates::data_structures::unit::BitcoinUnitVar;
+    use crate::bitcoin_predicates::signed_by::sighash_message;
+    use crate::constraints::{
+        sighash_cache::SigHashCacheVar,
+        tx::{TxVar, TxVarConfig},
+    };
+    use crate::error::Error;
+    use crate::schnorr::Schnorr;
+    use crate::traits::{BitcoinPredicate, SpendingContext};
+    use crate::transaction_integrity_gadget::{
+        TransactionIntegrityConfig,
+        scheme::{PrevOutput, PrevOutputVar},
+    };
+
+    use super::{MultiSig, MultiSigWitness, MultiSigWitnessVar};
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    impl TransactionIntegrityConfig for Config {
+        const N_INPUT: usize = 0;
+        const LEN_PREV_LOCK_SCRIPT: usize = 0x19;
+        const SIGHASH_FLAG: u8 = SIGHASH_ALL | SIGHASH_FORKID;
+    }
+
+    type Predicate = MultiSig<2, 3, F, EdwardsConfig, Config>;
+
+    fn p2pkh_script(addr: &str) -> Script {
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        p2pkh::create_lock_script(&hash160)
+    }
+
+    fn tx() -> Tx {
+        Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 900,
+                lock_script: p2pkh_script("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec"),
+            }],
+            lock_time: 0,
+        }
+    }
+
+    /// Check the predicate against the signatures of the parties in `signers`, assigned to the keys in `indices`
+    fn test_predicate(signers: &[usize], indices: &[usize]) -> bool {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let keys: Vec<_> = (0..3)
+            .map(|_| Schnorr::<EdwardsConfig>::keygen(&mut rng))
+            .collect();
+        let prev_output = PrevOutput {
+            prev_lock_script: p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz"),
+            prev_amount: 1000,
+        };
+        let message = sighash_message::<Config>(&tx(), &prev_output).unwrap();
+        let witness = MultiSigWitness {
+            signatures: signers
+                .iter()
+                .zip(indices.iter())
+                .map(|(signer, index)| {
+                    (
+                        *index,
+                        Schnorr::<EdwardsConfig>::sign(&mut rng, &keys[*signer].0, &message),
+                    )
+                })
+                .collect(),
+        };
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_witness(cs.clone(), || Ok(tx())).unwrap();
+        // The sighash computed by the integrity scheme
+        let prev_output_var =
+            PrevOutputVar::<F>::new_witness(cs.clone(), || Ok(prev_output)).unwrap();
+        let sighash = tx_var
+            .sighash(
+                Config::N_INPUT,
+                &prev_output_var.prev_lock_script,
+                &prev_output_var.prev_amount,
+                &Config::SIGHASH_FLAG,
+                &mut SigHashCacheVar::<F>::new(),
+            )
+            .unwrap();
+        let witness_var =
+            MultiSigWitnessVar::<2, 3, F, EdwardsConfig>::new_witness(cs.clone(), || Ok(witness))
+                .unwrap();
+        Predicate::new([keys[0].1, keys[1].1, keys[2].1])
+            .unwrap()
+            .enforce_constraints_with_context(
+                cs.clone(),
+                &BitcoinUnitVar::default(),
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &witness_var,
+                &SpendingContext {
+                    sighash: Some(&sighash),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_multisig_is_ok() {
+        assert!(test_predicate(&[0, 2], &[0, 2]));
+        assert!(test_predicate(&[2, 1], &[2, 1]));
+    }
+
+    #[test]
+    fn test_multisig_fails() {
+        // Not enough signatures
+        assert!(!test_predicate(&[0], &[0]));
+        // Signature assigned to the wrong key
+        assert!(!test_predicate(&[0, 2], &[0, 1]));
+        // The same key counted twice
+        assert!(!test_predicate(&[0, 0], &[0, 0]));
+    }
+
+    #[test]
+    fn test_invalid_threshold() {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let (_, public_key) = Schnorr::<EdwardsConfig>::keygen(&mut rng);
+
+        assert!(matches!(
+            MultiSig::<0, 1, F, EdwardsConfig, Config>::new([public_key]),
+            Err(Error::ConfigMismatch(_))
+        ));
+        assert!(matches!(
+            MultiSig::<2, 1, F, EdwardsConfig, Config>::new([public_key]),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+}
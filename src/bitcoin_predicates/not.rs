@@ -0,0 +1,186 @@
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::prelude::Boolean;
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use chain_gang::messages::Tx;
+
+use crate::constraints::tx::{TxVar, TxVarConfig};
+use crate::error::Error;
+use crate::traits::{BitcoinPredicate, SpendingContext};
+
+/// Bitcoin Predicate negating the wrapped predicate: it holds if and only if `predicate` does not.
+///
+/// **Note**: Only the [Boolean] returned by the wrapped predicate is negated. The constraints the wrapped predicate
+/// enforces directly, e.g., the range checks of its locking data, must still be satisfied, and the errors it returns
+/// at synthesis time are propagated.
+pub struct Not<F: PrimeField, P: TxVarConfig + Clone, B: BitcoinPredicate<F, P>> {
+    pub predicate: B,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone, B: BitcoinPredicate<F, P>> Not<F, P, B> {
+    pub fn new(predicate: B) -> Self {
+        Self {
+            predicate,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone, B: BitcoinPredicate<F, P>> BitcoinPredicate<F, P>
+    for Not<F, P, B>
+{
+    type LockingData = B::LockingData;
+    type UnlockingData = B::UnlockingData;
+    type Witness = B::Witness;
+
+    type LockingDataVar = B::LockingDataVar;
+    type UnlockingDataVar = B::UnlockingDataVar;
+    type WitnessVar = B::WitnessVar;
+
+    fn locking_data_len(&self) -> usize {
+        self.predicate.locking_data_len()
+    }
+
+    fn unlocking_data_len(&self) -> usize {
+        self.predicate.unlocking_data_len()
+    }
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        self.generate_constraints_with_context(
+            cs,
+            locking_data,
+            unlocking_data,
+            spending_data,
+            witness,
+            &SpendingContext::default(),
+        )
+    }
+
+    fn generate_constraints_with_context(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+        context: &SpendingContext<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        Ok(!self.predicate.generate_constraints_with_context(
+            cs,
+            locking_data,
+            unlocking_data,
+            spending_data,
+            witness,
+            context,
+        )?)
+    }
+
+    fn evaluate_native(
+        &self,
+        locking_data: &Self::LockingData,
+        unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        Ok(!self
+            .predicate
+            .evaluate_native(locking_data, unlocking_data, tx, witness)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::script::Script;
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+    use crate::bitcoin_predicates::fixed_lock_script::FixedLockScript;
+    use crate::constraints::tx::{TxVar, TxVarConfig};
+    use crate::traits::BitcoinPredicate;
+
+    use super::Not;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[1];
+    }
+
+    fn test_predicate(lock_script: Script, excluded_script: Script) -> bool {
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 100,
+                lock_script,
+            }],
+            lock_time: 0,
+        };
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx.clone())).unwrap();
+        let predicate =
+            Not::<F, Config, _>::new(FixedLockScript::<F, Config>::new(excluded_script, 0));
+        predicate
+            .enforce_constraints(
+                cs.clone(),
+                &BitcoinUnitVar::default(),
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &BitcoinUnitVar::default(),
+            )
+            .unwrap();
+
+        let is_satisfied = cs.is_satisfied().unwrap();
+        assert_eq!(
+            predicate
+                .evaluate_native(
+                    &BitcoinUnit::default(),
+                    &BitcoinUnit::default(),
+                    &tx,
+                    &BitcoinUnit::default()
+                )
+                .unwrap(),
+            is_satisfied
+        );
+        is_satisfied
+    }
+
+    #[test]
+    fn test_not_is_ok() {
+        assert!(test_predicate(Script(vec![0]), Script(vec![1])));
+    }
+
+    #[test]
+    fn test_not_fails() {
+        assert!(!test_predicate(Script(vec![1]), Script(vec![1])));
+    }
+}
@@ -0,0 +1,422 @@
+//! Nullifiers for spend-once semantics
+//!
+//! [WithNullifier] extends a Bitcoin Predicate so that every proof exposes a nullifier: the Poseidon hash of a
+//! secret key and of the outpoint spent by the input `n_input` of the spending transaction. The locking data
+//! carries a commitment to the secret key, so that the prover cannot pick a fresh key for each proof, and an
+//! application can detect two proofs for the same locking data and the same outpoint by comparing their nullifiers.
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use ark_crypto_primitives::sponge::{
+    CryptographicSponge,
+    constraints::CryptographicSpongeVar,
+    poseidon::{PoseidonConfig, PoseidonSponge, constraints::PoseidonSpongeVar},
+};
+use ark_ff::{PrimeField, ToConstraintField};
+use ark_r1cs_std::{
+    alloc::{AllocVar, AllocationMode},
+    convert::{ToBytesGadget, ToConstraintFieldGadget},
+    eq::EqGadget,
+    fields::fp::FpVar,
+    prelude::Boolean,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+use chain_gang::{
+    messages::{OutPoint, Tx},
+    util::Serializable,
+};
+
+use crate::constraints::tx::{TxVar, TxVarConfig};
+use crate::error::Error;
+use crate::traits::{BitcoinPredicate, PublicInputSize, SpendingContext, ToPublicInputGadget};
+
+/// Domain separator of [key_commitment]
+const KEY_COMMITMENT_DOMAIN: u64 = 0;
+/// Domain separator of [nullifier]
+const NULLIFIER_DOMAIN: u64 = 1;
+
+/// The commitment to `secret_key` set in the locking data of [WithNullifier]
+pub fn key_commitment<F: PrimeField>(secret_key: F, poseidon_config: &PoseidonConfig<F>) -> F {
+    let mut sponge = PoseidonSponge::<F>::new(poseidon_config);
+    sponge.absorb(&F::from(KEY_COMMITMENT_DOMAIN));
+    sponge.absorb(&secret_key);
+    sponge.squeeze_field_elements::<F>(1)[0]
+}
+
+/// The nullifier of `outpoint` under `secret_key`
+pub fn nullifier<F: PrimeField>(
+    secret_key: F,
+    outpoint: &OutPoint,
+    poseidon_config: &PoseidonConfig<F>,
+) -> F {
+    let mut ser: Vec<u8> = Vec::new();
+    outpoint.write(&mut ser).unwrap();
+    let mut elements: Vec<F> = vec![F::from(NULLIFIER_DOMAIN), secret_key];
+    elements.extend(ser.to_field_elements().unwrap());
+
+    let mut sponge = PoseidonSponge::<F>::new(poseidon_config);
+    sponge.absorb(&elements);
+    sponge.squeeze_field_elements::<F>(1)[0]
+}
+
+/// The data of the wrapped predicate extended by a field element: the key commitment in the locking data, the
+/// nullifier in the unlocking data and the secret key in the witness. The element comes after the data of the
+/// wrapped predicate in the public input.
+#[derive(Clone, Default)]
+pub struct WithField<F: PrimeField, T> {
+    pub inner: T,
+    pub element: F,
+}
+
+impl<F: PrimeField, T> WithField<F, T> {
+    pub fn new(inner: T, element: F) -> Self {
+        Self { inner, element }
+    }
+}
+
+impl<F: PrimeField, T: Into<Vec<F>>> From<WithField<F, T>> for Vec<F> {
+    fn from(value: WithField<F, T>) -> Self {
+        let mut elements: Vec<F> = value.inner.into();
+        elements.push(value.element);
+        elements
+    }
+}
+
+impl<F: PrimeField, T: PublicInputSize<F>> PublicInputSize<F> for WithField<F, T> {
+    const N_PUBLIC_INPUTS: usize = T::N_PUBLIC_INPUTS + 1;
+}
+
+/// The R1CS version of [WithField]
+pub struct WithFieldVar<F: PrimeField, V> {
+    pub inner: V,
+    pub element: FpVar<F>,
+}
+
+impl<F: PrimeField, T, V: AllocVar<T, F>> AllocVar<WithField<F, T>, F> for WithFieldVar<F, V> {
+    fn new_variable<D: Borrow<WithField<F, T>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<D, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let data = f()?;
+        let data: &WithField<F, T> = data.borrow();
+        Ok(Self {
+            inner: V::new_variable(cs.clone(), || Ok(&data.inner), mode)?,
+            element: FpVar::<F>::new_variable(cs.clone(), || Ok(data.element), mode)?,
+        })
+    }
+}
+
+impl<F: PrimeField, V: ToPublicInputGadget<F>> ToPublicInputGadget<F> for WithFieldVar<F, V> {
+    fn to_public_input(&self) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        let mut elements = self.inner.to_public_input()?;
+        elements.push(self.element.clone());
+        Ok(elements)
+    }
+}
+
+/// Bitcoin Predicate enforcing the wrapped predicate together with the correctness of a nullifier:
+/// - the unlocking data ends with the nullifier of the outpoint spent by the input `n_input`, see [nullifier]
+/// - the locking data ends with the commitment to the secret key used, see [key_commitment]
+pub struct WithNullifier<F: PrimeField, P: TxVarConfig + Clone, B: BitcoinPredicate<F, P>> {
+    pub predicate: B,
+    pub n_input: usize,
+    pub poseidon_config: PoseidonConfig<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone, B: BitcoinPredicate<F, P>> WithNullifier<F, P, B> {
+    pub fn new(predicate: B, n_input: usize, poseidon_config: PoseidonConfig<F>) -> Self {
+        Self {
+            predicate,
+            n_input,
+            poseidon_config,
+            _phantom_config: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone, B: BitcoinPredicate<F, P>> BitcoinPredicate<F, P>
+    for WithNullifier<F, P, B>
+{
+    type LockingData = WithField<F, B::LockingData>;
+    type UnlockingData = WithField<F, B::UnlockingData>;
+    type Witness = WithField<F, B::Witness>;
+
+    type LockingDataVar = WithFieldVar<F, B::LockingDataVar>;
+    type UnlockingDataVar = WithFieldVar<F, B::UnlockingDataVar>;
+    type WitnessVar = WithFieldVar<F, B::WitnessVar>;
+
+    fn locking_data_len(&self) -> usize {
+        self.predicate.locking_data_len() + 1
+    }
+
+    fn unlocking_data_len(&self) -> usize {
+        self.predicate.unlocking_data_len() + 1
+    }
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        self.generate_constraints_with_context(
+            cs,
+            locking_data,
+            unlocking_data,
+            spending_data,
+            witness,
+            &SpendingContext::default(),
+        )
+    }
+
+    fn generate_constraints_with_context(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+        context: &SpendingContext<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        if self.n_input >= spending_data.inputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is not compatible with the number of inputs: {}",
+                self.n_input,
+                spending_data.inputs.len()
+            ))
+            .into());
+        }
+
+        let is_predicate_satisfied = self.predicate.generate_constraints_with_context(
+            cs.clone(),
+            &locking_data.inner,
+            &unlocking_data.inner,
+            spending_data,
+            &witness.inner,
+            context,
+        )?;
+
+        // Key commitment, as in [key_commitment]
+        let mut sponge = PoseidonSpongeVar::<F>::new(cs.clone(), &self.poseidon_config);
+        sponge.absorb(&FpVar::<F>::constant(F::from(KEY_COMMITMENT_DOMAIN)))?;
+        sponge.absorb(&witness.element)?;
+        let computed_key_commitment = sponge.squeeze_field_elements(1)?;
+
+        // Nullifier, as in [nullifier]
+        let mut elements: Vec<FpVar<F>> = vec![
+            FpVar::<F>::constant(F::from(NULLIFIER_DOMAIN)),
+            witness.element.clone(),
+        ];
+        elements.extend(
+            spending_data.inputs[self.n_input]
+                .prev_output
+                .to_bytes_le()?
+                .to_constraint_field()?,
+        );
+        let mut sponge = PoseidonSpongeVar::<F>::new(cs.clone(), &self.poseidon_config);
+        sponge.absorb(&elements)?;
+        let computed_nullifier = sponge.squeeze_field_elements(1)?;
+
+        Boolean::<F>::kary_and(&[
+            is_predicate_satisfied,
+            locking_data.element.is_eq(&computed_key_commitment[0])?,
+            unlocking_data.element.is_eq(&computed_nullifier[0])?,
+        ])
+    }
+
+    fn evaluate_native(
+        &self,
+        locking_data: &Self::LockingData,
+        unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        if self.n_input >= tx.inputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is not compatible with the number of inputs: {}",
+                self.n_input,
+                tx.inputs.len()
+            )));
+        }
+
+        let is_predicate_satisfied = self.predicate.evaluate_native(
+            &locking_data.inner,
+            &unlocking_data.inner,
+            tx,
+            &witness.inner,
+        )?;
+        let computed_key_commitment = key_commitment(witness.element, &self.poseidon_config);
+        let computed_nullifier = nullifier(
+            witness.element,
+            &tx.inputs[self.n_input].prev_output,
+            &self.poseidon_config,
+        );
+
+        Ok(is_predicate_satisfied
+            && locking_data.element == computed_key_commitment
+            && unlocking_data.element == computed_nullifier)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_relations::r1cs::{ConstraintSystem, SynthesisError};
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::script::Script;
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+    use crate::bitcoin_predicates::max_tx_size::MaxTxSize;
+    use crate::constraints::tx::{TxVar, TxVarConfig};
+    use crate::error::Error;
+    use crate::traits::BitcoinPredicate;
+    use crate::transaction_integrity_gadget::poseidon::poseidon_config;
+
+    use super::{WithField, WithFieldVar, WithNullifier, key_commitment, nullifier};
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0];
+    }
+
+    type Predicate = WithNullifier<F, Config, MaxTxSize<F, Config>>;
+
+    fn outpoint(index: u32) -> OutPoint {
+        OutPoint {
+            hash: Hash256::decode(
+                "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+            )
+            .unwrap(),
+            index,
+        }
+    }
+
+    fn tx(index: u32) -> Tx {
+        Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: outpoint(index),
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 900,
+                lock_script: Script(vec![]),
+            }],
+            lock_time: 0,
+        }
+    }
+
+    fn enforce_predicate(
+        n_input: usize,
+        committed_key: F,
+        secret_key: F,
+        nullifier: F,
+    ) -> Result<bool, SynthesisError> {
+        let config = poseidon_config::<F>();
+        let locking_data = WithField::new(
+            BitcoinUnit::<F, Config>::default(),
+            key_commitment(committed_key, &config),
+        );
+        let unlocking_data = WithField::new(BitcoinUnit::<F, Config>::default(), nullifier);
+        let witness = WithField::new(BitcoinUnit::<F, Config>::default(), secret_key);
+        let predicate = Predicate::new(MaxTxSize::new(1000).unwrap(), n_input, config);
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let locking_data_var =
+            WithFieldVar::<F, BitcoinUnitVar<F, Config>>::new_input(cs.clone(), || {
+                Ok(&locking_data)
+            })?;
+        let unlocking_data_var =
+            WithFieldVar::<F, BitcoinUnitVar<F, Config>>::new_input(cs.clone(), || {
+                Ok(&unlocking_data)
+            })?;
+        let witness_var =
+            WithFieldVar::<F, BitcoinUnitVar<F, Config>>::new_witness(cs.clone(), || Ok(&witness))?;
+        let tx_var = TxVar::<F, Config>::new_witness(cs.clone(), || Ok(tx(0)))?;
+
+        predicate.enforce_constraints(
+            cs.clone(),
+            &locking_data_var,
+            &unlocking_data_var,
+            &tx_var,
+            &witness_var,
+        )?;
+
+        let native = predicate.evaluate_native(&locking_data, &unlocking_data, &tx(0), &witness)?;
+        assert_eq!(cs.is_satisfied()?, native);
+        Ok(native)
+    }
+
+    #[test]
+    fn test_nullifier_native() {
+        let config = poseidon_config::<F>();
+        let secret_key = F::from(7u64);
+
+        assert_eq!(
+            nullifier(secret_key, &outpoint(0), &config),
+            nullifier(secret_key, &outpoint(0), &config)
+        );
+        assert_ne!(
+            nullifier(secret_key, &outpoint(0), &config),
+            nullifier(secret_key, &outpoint(1), &config)
+        );
+        assert_ne!(
+            nullifier(secret_key, &outpoint(0), &config),
+            nullifier(F::from(8u64), &outpoint(0), &config)
+        );
+    }
+
+    #[test]
+    fn test_nullifier_is_ok() {
+        let secret_key = F::from(7u64);
+        let nullifier = nullifier(secret_key, &outpoint(0), &poseidon_config::<F>());
+        assert!(enforce_predicate(0, secret_key, secret_key, nullifier).unwrap());
+    }
+
+    #[test]
+    fn test_nullifier_fails() {
+        let secret_key = F::from(7u64);
+        let config = poseidon_config::<F>();
+
+        // Nullifier of a different outpoint
+        let wrong_nullifier = nullifier(secret_key, &outpoint(1), &config);
+        assert!(!enforce_predicate(0, secret_key, secret_key, wrong_nullifier).unwrap());
+
+        // Secret key different from the committed one
+        let other_key = F::from(8u64);
+        let other_nullifier = nullifier(other_key, &outpoint(0), &config);
+        assert!(!enforce_predicate(0, secret_key, other_key, other_nullifier).unwrap());
+    }
+
+    #[test]
+    fn test_nullifier_out_of_bounds() {
+        let secret_key = F::from(7u64);
+        let nullifier = nullifier(secret_key, &outpoint(0), &poseidon_config::<F>());
+        assert!(enforce_predicate(1, secret_key, secret_key, nullifier).is_err());
+
+        let predicate = Predicate::new(MaxTxSize::new(1000).unwrap(), 1, poseidon_config::<F>());
+        assert!(matches!(
+            predicate.evaluate_native(
+                &WithField::default(),
+                &WithField::default(),
+                &tx(0),
+                &WithField::default(),
+            ),
+            Err(Error::IndexOutOfBounds(_))
+        ));
+    }
+}
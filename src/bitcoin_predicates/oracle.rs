@@ -0,0 +1,442 @@
+//! Oracle attestations
+//!
+//! [OracleAttestation] verifies a [Schnorr](crate::schnorr::Schnorr) signature of an oracle, whose public key is
+//! the locking data, over the message `event_id || message`, where `message` is an `N`-byte witness, e.g., a
+//! price and a timestamp, and then enforces an [OracleCondition] on the message and the spending transaction.
+//! [BinaryOutcome] is the reference condition for events with two outcomes.
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use ark_ec::twisted_edwards::{Affine, TECurveConfig};
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::{AllocVar, AllocationMode},
+    eq::EqGadget,
+    groups::curves::twisted_edwards::AffineVar,
+    prelude::Boolean,
+    uint8::UInt8,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+use chain_gang::{messages::Tx, script::Script};
+
+use crate::bitcoin_predicates::data_structures::{
+    field_array::{FieldArray, FieldArrayVar},
+    unit::{BitcoinUnit, BitcoinUnitVar},
+};
+use crate::constraints::{
+    script::ScriptVar,
+    tx::{TxVar, TxVarConfig},
+};
+use crate::error::Error;
+use crate::schnorr::{
+    Schnorr, SchnorrSignature,
+    constraints::{SchnorrGadget, SchnorrSignatureVar},
+};
+use crate::traits::BitcoinPredicate;
+
+/// The message signed by the oracle for the event `event_id`
+pub fn attested_message(event_id: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut data = event_id.to_vec();
+    data.extend_from_slice(message);
+    data
+}
+
+/// Gadget checking the conditions on `spending_data` implied by the attested `message`
+pub trait OracleCondition<F: PrimeField, P: TxVarConfig + Clone> {
+    fn is_satisfied(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        message: &[UInt8<F>],
+        spending_data: &TxVar<F, P>,
+    ) -> Result<Boolean<F>, SynthesisError>;
+
+    /// Native version of [OracleCondition::is_satisfied], see [BitcoinPredicate::evaluate_native].
+    /// By default, it returns [Error::NotImplemented]
+    fn is_satisfied_native(&self, _message: &[u8], _tx: &Tx) -> Result<bool, Error> {
+        Err(Error::NotImplemented(
+            "Native evaluation of the oracle condition".to_string(),
+        ))
+    }
+}
+
+/// The witness of [OracleAttestation]: the attested message and the signature of the oracle.
+/// A missing signature is replaced by [SchnorrSignature::default], which is never valid
+pub struct OracleWitness<const N: usize, C: TECurveConfig> {
+    pub message: [u8; N],
+    pub signature: Option<SchnorrSignature<C>>,
+}
+
+impl<const N: usize, C: TECurveConfig> Clone for OracleWitness<N, C> {
+    fn clone(&self) -> Self {
+        Self {
+            message: self.message,
+            signature: self.signature.clone(),
+        }
+    }
+}
+
+/// The R1CS version of [OracleWitness]
+pub struct OracleWitnessVar<const N: usize, F: PrimeField, C: TECurveConfig<BaseField = F>> {
+    pub message: Vec<UInt8<F>>,
+    pub signature: SchnorrSignatureVar<F, C>,
+}
+
+impl<const N: usize, F: PrimeField, C: TECurveConfig<BaseField = F>>
+    AllocVar<OracleWitness<N, C>, F> for OracleWitnessVar<N, F, C>
+{
+    fn new_variable<T: Borrow<OracleWitness<N, C>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let witness: OracleWitness<N, C> = f().map(|data| data.borrow().clone())?;
+        Ok(Self {
+            message: Vec::<UInt8<F>>::new_variable(
+                cs.clone(),
+                || Ok(witness.message.to_vec()),
+                mode,
+            )?,
+            signature: SchnorrSignatureVar::<F, C>::new_variable(
+                cs.clone(),
+                || Ok(witness.signature.unwrap_or_default()),
+                mode,
+            )?,
+        })
+    }
+}
+
+/// Bitcoin Predicate enforcing that the witness contains a message of `N` bytes attested by the oracle for the
+/// event `event_id`, and that `condition` is satisfied by the message and the spending transaction.
+///
+/// The locking data is the public key of the oracle, as its affine coordinates `[x, y]`.
+pub struct OracleAttestation<
+    const N: usize,
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+    P: TxVarConfig + Clone,
+    T: OracleCondition<F, P>,
+> {
+    pub event_id: Vec<u8>,
+    pub condition: T,
+    _phantom_field: PhantomData<F>,
+    _phantom_curve: PhantomData<C>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<const N: usize, F, C, P, T> OracleAttestation<N, F, C, P, T>
+where
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+    P: TxVarConfig + Clone,
+    T: OracleCondition<F, P>,
+{
+    pub fn new(event_id: Vec<u8>, condition: T) -> Self {
+        Self {
+            event_id,
+            condition,
+            _phantom_field: PhantomData,
+            _phantom_curve: PhantomData,
+            _phantom_config: PhantomData,
+        }
+    }
+
+    /// The locking data for the oracle with public key `public_key`
+    pub fn locking_data(public_key: &Affine<C>) -> FieldArray<2, F, P> {
+        FieldArray::new([public_key.x, public_key.y])
+    }
+}
+
+impl<const N: usize, F, C, P, T> BitcoinPredicate<F, P> for OracleAttestation<N, F, C, P, T>
+where
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+    P: TxVarConfig + Clone,
+    T: OracleCondition<F, P>,
+{
+    type LockingData = FieldArray<2, F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = OracleWitness<N, C>;
+
+    type LockingDataVar = FieldArrayVar<2, F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = OracleWitnessVar<N, F, C>;
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let public_key = AffineVar::<C, _>::new(
+            locking_data.elements[0].clone(),
+            locking_data.elements[1].clone(),
+        );
+
+        // Build the message as in [attested_message]
+        let mut data: Vec<UInt8<F>> = UInt8::<F>::constant_vec(&self.event_id);
+        data.extend_from_slice(&witness.message);
+
+        Boolean::<F>::kary_and(&[
+            SchnorrGadget::<F, C>::verify(&public_key, &data, &witness.signature)?,
+            self.condition
+                .is_satisfied(cs.clone(), &witness.message, spending_data)?,
+        ])
+    }
+
+    fn evaluate_native(
+        &self,
+        locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        let is_condition_satisfied = self.condition.is_satisfied_native(&witness.message, tx)?;
+
+        let public_key =
+            Affine::<C>::new_unchecked(locking_data.elements[0], locking_data.elements[1]);
+        let is_attested = Schnorr::<C>::verify(
+            &public_key,
+            &attested_message(&self.event_id, &witness.message),
+            &witness.signature.clone().unwrap_or_default(),
+        );
+
+        Ok(is_attested && is_condition_satisfied)
+    }
+}
+
+/// [OracleCondition] for an event with two outcomes: the message is a single byte, `0` or `1`, and the output of
+/// the spending transaction at `index` must be locked by `lock_scripts[outcome]`
+pub struct BinaryOutcome<F: PrimeField, P: TxVarConfig + Clone> {
+    pub lock_scripts: [Script; 2],
+    pub index: usize,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BinaryOutcome<F, P> {
+    /// Returns [Error::ConfigMismatch] if the locking scripts of the two outcomes have different lengths
+    pub fn new(lock_scripts: [Script; 2], index: usize) -> Result<Self, Error> {
+        if lock_scripts[0].0.len() != lock_scripts[1].0.len() {
+            return Err(Error::ConfigMismatch(format!(
+                "The locking scripts of the two outcomes have different lengths: {} and {}",
+                lock_scripts[0].0.len(),
+                lock_scripts[1].0.len()
+            )));
+        }
+        Ok(Self {
+            lock_scripts,
+            index,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        })
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> OracleCondition<F, P> for BinaryOutcome<F, P> {
+    fn is_satisfied(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        message: &[UInt8<F>],
+        spending_data: &TxVar<F, P>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        // Validate input
+        if self.index >= spending_data.outputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of outputs: {}",
+                self.index,
+                spending_data.outputs.len()
+            ))
+            .into());
+        }
+        if message.len() != 1 {
+            return Err(Error::ConfigMismatch(format!(
+                "The message of a binary outcome has length: {}, expected: 1",
+                message.len()
+            ))
+            .into());
+        }
+
+        let lock_script = &spending_data.outputs[self.index].lock_script;
+        let mut branches: Vec<Boolean<F>> = Vec::with_capacity(2);
+        for (outcome, outcome_script) in self.lock_scripts.iter().enumerate() {
+            branches.push(Boolean::<F>::kary_and(&[
+                message[0].is_eq(&UInt8::<F>::constant(outcome as u8))?,
+                lock_script.is_eq(&ScriptVar::<F>::new_constant(
+                    cs.clone(),
+                    outcome_script.clone(),
+                )?)?,
+            ])?);
+        }
+
+        Boolean::<F>::kary_or(&branches)
+    }
+
+    fn is_satisfied_native(&self, message: &[u8], tx: &Tx) -> Result<bool, Error> {
+        // Validate input
+        if self.index >= tx.outputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of outputs: {}",
+                self.index,
+                tx.outputs.len()
+            )));
+        }
+        if message.len() != 1 {
+            return Err(Error::ConfigMismatch(format!(
+                "The message of a binary outcome has length: {}, expected: 1",
+                message.len()
+            )));
+        }
+
+        let lock_script = &tx.outputs[self.index].lock_script;
+        Ok(self
+            .lock_scripts
+            .get(message[0] as usize)
+            .is_some_and(|outcome_script| outcome_script.0 == lock_script.0))
+    }
+}
+
+/// [OracleAttestation] for an event with two outcomes, see [BinaryOutcome]
+pub type BinaryOracle<F, C, P> = OracleAttestation<1, F, C, P, BinaryOutcome<F, P>>;
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_ed_on_bls12_381::EdwardsConfig;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::script::Script;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::util::{Hash160, Hash256};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::bitcoin_predicates::data_structures::{
+        field_array::FieldArrayVar,
+        unit::{BitcoinUnit, BitcoinUnitVar},
+    };
+    use crate::constraints::tx::{TxVar, TxVarConfig};
+    use crate::error::Error;
+    use crate::schnorr::Schnorr;
+    use crate::traits::BitcoinPredicate;
+
+    use super::{BinaryOracle, BinaryOutcome, OracleWitness, OracleWitnessVar, attested_message};
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    type Predicate = BinaryOracle<F, EdwardsConfig, Config>;
+
+    const EVENT_ID: &[u8] = b"match-2026-10-14";
+
+    fn lock_script(n: u8) -> Script {
+        p2pkh::create_lock_script(&Hash160([n; 20]))
+    }
+
+    fn tx(recipient: u8) -> Tx {
+        Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 1000,
+                lock_script: lock_script(recipient),
+            }],
+            lock_time: 0,
+        }
+    }
+
+    /// The oracle signs `signed_outcome` for `signed_event`, the spender claims `outcome` and pays `recipient`
+    fn test_predicate(signed_event: &[u8], signed_outcome: u8, outcome: u8, recipient: u8) -> bool {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let (secret_key, public_key) = Schnorr::<EdwardsConfig>::keygen(&mut rng);
+        let signature = Schnorr::<EdwardsConfig>::sign(
+            &mut rng,
+            &secret_key,
+            &attested_message(signed_event, &[signed_outcome]),
+        );
+
+        let witness = OracleWitness {
+            message: [outcome],
+            signature: Some(signature),
+        };
+        let condition =
+            BinaryOutcome::<F, Config>::new([lock_script(1), lock_script(2)], 0).unwrap();
+        let predicate = Predicate::new(EVENT_ID.to_vec(), condition);
+        let native = predicate
+            .evaluate_native(
+                &Predicate::locking_data(&public_key),
+                &BitcoinUnit::default(),
+                &tx(recipient),
+                &witness,
+            )
+            .unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let locking_data = FieldArrayVar::<2, F, Config>::new_input(cs.clone(), || {
+            Ok(Predicate::locking_data(&public_key))
+        })
+        .unwrap();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx(recipient))).unwrap();
+        let witness =
+            OracleWitnessVar::<1, F, EdwardsConfig>::new_witness(cs.clone(), || Ok(witness))
+                .unwrap();
+        predicate
+            .enforce_constraints(
+                cs.clone(),
+                &locking_data,
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &witness,
+            )
+            .unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), native);
+        native
+    }
+
+    #[test]
+    fn test_oracle_attestation_is_ok() {
+        assert!(test_predicate(EVENT_ID, 0, 0, 1));
+        assert!(test_predicate(EVENT_ID, 1, 1, 2));
+    }
+
+    #[test]
+    fn test_oracle_attestation_fails() {
+        // Payout to the wrong outcome
+        assert!(!test_predicate(EVENT_ID, 0, 0, 2));
+        // Outcome not attested by the oracle
+        assert!(!test_predicate(EVENT_ID, 0, 1, 2));
+        // Attestation for another event
+        assert!(!test_predicate(b"match-2026-10-15", 1, 1, 2));
+        // Invalid outcome
+        assert!(!test_predicate(EVENT_ID, 2, 2, 2));
+    }
+
+    #[test]
+    fn test_binary_outcome_different_lengths() {
+        assert!(matches!(
+            BinaryOutcome::<F, Config>::new([lock_script(1), Script(vec![0x51])], 0),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+}
@@ -0,0 +1,184 @@
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{prelude::Boolean, uint64::UInt64};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use chain_gang::messages::Tx;
+
+use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+use crate::constraints::tx::{TxVar, TxVarConfig};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+use crate::util::uint64::is_le;
+
+/// Bitcoin Predicate to enforce that the output of the transaction at `index`
+/// has amount between `min` and `max` satoshis, both included
+pub struct OutputAmountRange<F: PrimeField, P: TxVarConfig + Clone> {
+    pub index: usize,
+    pub min: u64,
+    pub max: u64,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> OutputAmountRange<F, P> {
+    /// Returns [Error::ConfigMismatch] if the range is empty
+    pub fn new(index: usize, min: u64, max: u64) -> Result<Self, Error> {
+        if min > max {
+            return Err(Error::ConfigMismatch(format!(
+                "The range is empty: min = {} is larger than max = {}",
+                min, max
+            )));
+        }
+        Ok(Self {
+            index,
+            min,
+            max,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        })
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for OutputAmountRange<F, P> {
+    type LockingData = BitcoinUnit<F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = BitcoinUnitVar<F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        _cs: ConstraintSystemRef<F>,
+        _locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let output = spending_data.output(self.index)?;
+
+        let amount = &output.satoshis;
+        Boolean::<F>::kary_and(&[
+            is_le(&UInt64::<F>::constant(self.min), amount)?,
+            is_le(amount, &UInt64::<F>::constant(self.max))?,
+        ])
+    }
+
+    fn evaluate_native(
+        &self,
+        _locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        // Validate input
+        if self.index >= tx.outputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of outputs: {}",
+                self.index,
+                tx.outputs.len()
+            )));
+        }
+
+        let amount = tx.outputs[self.index].satoshis as u64;
+        Ok(self.min <= amount && amount <= self.max)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use ark_bls12_381::Fr as F;
+
+    use ark_r1cs_std::alloc::AllocVar;
+
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::script::Script;
+
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::unit::BitcoinUnitVar;
+    use crate::constraints::tx::TxVar;
+    use crate::error::Error;
+
+    use crate::{constraints::tx::TxVarConfig, traits::BitcoinPredicate};
+
+    use super::OutputAmountRange;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 2;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0, 0];
+    }
+
+    fn test_predicate(amount: u64, index: usize, expected: bool) {
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0,
+            }],
+            outputs: vec![
+                TxOut {
+                    satoshis: amount as i64,
+                    lock_script: Script(vec![]),
+                },
+                TxOut {
+                    satoshis: 10,
+                    lock_script: Script(vec![]),
+                },
+            ],
+            lock_time: 0,
+        };
+
+        let predicate = OutputAmountRange::<F, Config>::new(index, 100, 200).unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        predicate
+            .enforce_constraints(
+                cs.clone(),
+                &BitcoinUnitVar::default(),
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &BitcoinUnitVar::default(),
+            )
+            .unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_predicate_is_ok() {
+        test_predicate(100, 0, true);
+        test_predicate(150, 0, true);
+        test_predicate(200, 0, true);
+    }
+
+    #[test]
+    fn test_predicate_fails() {
+        test_predicate(99, 0, false);
+        test_predicate(201, 0, false);
+        test_predicate(150, 1, false);
+    }
+
+    #[test]
+    fn test_empty_range() {
+        assert!(matches!(
+            OutputAmountRange::<F, Config>::new(0, 200, 100),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+}
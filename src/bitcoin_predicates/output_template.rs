@@ -0,0 +1,326 @@
+//! Declarative output templates
+//!
+//! [TxTemplate] enforces an [OutputTemplate] on each output of the spending transaction: a constraint on the
+//! locking script, see [ScriptTemplate], and a range for the amount. Most covenants restricting the outputs of
+//! the spending transaction can be expressed as a template, without writing a custom predicate.
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{eq::EqGadget, prelude::Boolean, uint8::UInt8, uint64::UInt64};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use chain_gang::{
+    messages::{Tx, TxOut},
+    script::Script,
+};
+
+use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+use crate::constraints::{
+    tx::{TxVar, TxVarConfig},
+    txout::TxOutVar,
+};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+use crate::util::uint64::is_le;
+
+/// The constraint on the locking script of an output
+#[derive(Clone, Debug)]
+pub enum ScriptTemplate {
+    /// The locking script is equal to the given script
+    Exact(Script),
+    /// The locking script starts with the given bytes
+    Prefix(Vec<u8>),
+    /// The locking script is unconstrained
+    Any,
+}
+
+/// The constraints on an output: a [ScriptTemplate], and an amount between `min_amount` and `max_amount`
+/// satoshis, both included
+#[derive(Clone, Debug)]
+pub struct OutputTemplate {
+    pub script: ScriptTemplate,
+    pub min_amount: u64,
+    pub max_amount: u64,
+}
+
+impl OutputTemplate {
+    /// Returns [Error::ConfigMismatch] if the range of amounts is empty
+    pub fn new(script: ScriptTemplate, min_amount: u64, max_amount: u64) -> Result<Self, Error> {
+        if min_amount > max_amount {
+            return Err(Error::ConfigMismatch(format!(
+                "The range is empty: min = {} is larger than max = {}",
+                min_amount, max_amount
+            )));
+        }
+        Ok(Self {
+            script,
+            min_amount,
+            max_amount,
+        })
+    }
+
+    /// Template with an unconstrained amount
+    pub fn with_script(script: ScriptTemplate) -> Self {
+        Self {
+            script,
+            min_amount: 0,
+            max_amount: u64::MAX,
+        }
+    }
+
+    /// Template accepting any output
+    pub fn any() -> Self {
+        Self::with_script(ScriptTemplate::Any)
+    }
+
+    /// Check whether `output` satisfies the template
+    fn is_satisfied<F: PrimeField>(
+        &self,
+        output: &TxOutVar<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let lock_script = &output.lock_script.0;
+        let is_script_ok = match &self.script {
+            ScriptTemplate::Exact(script) if script.0.len() == lock_script.len() => {
+                lock_script.is_eq(&UInt8::<F>::constant_vec(&script.0))?
+            }
+            ScriptTemplate::Prefix(prefix) if prefix.len() <= lock_script.len() => {
+                lock_script[..prefix.len()].is_eq(&UInt8::<F>::constant_vec(prefix))?
+            }
+            ScriptTemplate::Any => Boolean::<F>::TRUE,
+            // The lengths of the scripts are fixed by the parameters
+            _ => Boolean::<F>::FALSE,
+        };
+
+        Boolean::<F>::kary_and(&[
+            is_script_ok,
+            is_le(&UInt64::<F>::constant(self.min_amount), &output.satoshis)?,
+            is_le(&output.satoshis, &UInt64::<F>::constant(self.max_amount))?,
+        ])
+    }
+
+    /// Native counterpart of [OutputTemplate::is_satisfied]
+    fn is_satisfied_native(&self, output: &TxOut) -> bool {
+        let lock_script = &output.lock_script.0;
+        let is_script_ok = match &self.script {
+            ScriptTemplate::Exact(script) => script.0 == *lock_script,
+            ScriptTemplate::Prefix(prefix) => lock_script.starts_with(prefix),
+            ScriptTemplate::Any => true,
+        };
+        let amount = output.satoshis as u64;
+
+        is_script_ok && self.min_amount <= amount && amount <= self.max_amount
+    }
+}
+
+/// Bitcoin Predicate enforcing that the output of the spending transaction at index `i` satisfies `outputs[i]`
+pub struct TxTemplate<F: PrimeField, P: TxVarConfig + Clone> {
+    pub outputs: Vec<OutputTemplate>,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> TxTemplate<F, P> {
+    /// Returns [Error::ConfigMismatch] if there is not one template per output set in `P`
+    pub fn new(outputs: Vec<OutputTemplate>) -> Result<Self, Error> {
+        if outputs.len() != P::N_OUTPUTS {
+            return Err(Error::ConfigMismatch(format!(
+                "The number of output templates: {} is different from the number of outputs set in the parameters: {}",
+                outputs.len(),
+                P::N_OUTPUTS
+            )));
+        }
+        Ok(Self {
+            outputs,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        })
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for TxTemplate<F, P> {
+    type LockingData = BitcoinUnit<F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = BitcoinUnitVar<F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        _cs: ConstraintSystemRef<F>,
+        _locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let checks = self
+            .outputs
+            .iter()
+            .zip(spending_data.outputs.iter())
+            .map(|(template, output)| template.is_satisfied(output))
+            .collect::<Result<Vec<Boolean<F>>, SynthesisError>>()?;
+
+        Boolean::<F>::kary_and(&checks)
+    }
+
+    fn evaluate_native(
+        &self,
+        _locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        Ok(self
+            .outputs
+            .iter()
+            .zip(tx.outputs.iter())
+            .all(|(template, output)| template.is_satisfied_native(output)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::script::Script;
+    use chain_gang::script::op_codes::{OP_FALSE, OP_RETURN};
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::util::{Hash160, Hash256};
+
+    use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+    use crate::constraints::tx::{TxVar, TxVarConfig};
+    use crate::error::Error;
+    use crate::traits::BitcoinPredicate;
+
+    use super::{OutputTemplate, ScriptTemplate, TxTemplate};
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 3;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19, 6, 0x19];
+    }
+
+    fn lock_script(n: u8) -> Script {
+        p2pkh::create_lock_script(&Hash160([n; 20]))
+    }
+
+    fn data_script(data: [u8; 3]) -> Script {
+        let mut script = vec![OP_FALSE, OP_RETURN, 0x03];
+        script.extend(data);
+        Script(script)
+    }
+
+    /// Pay at least 1000 satoshis to the first script, publish data, and send the change anywhere
+    fn template() -> TxTemplate<F, Config> {
+        TxTemplate::new(vec![
+            OutputTemplate::new(ScriptTemplate::Exact(lock_script(1)), 1000, u64::MAX).unwrap(),
+            OutputTemplate::new(ScriptTemplate::Prefix(vec![OP_FALSE, OP_RETURN]), 0, 0).unwrap(),
+            OutputTemplate::any(),
+        ])
+        .unwrap()
+    }
+
+    fn test_predicate(outputs: Vec<TxOut>) -> bool {
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs,
+            lock_time: 0,
+        };
+
+        let predicate = template();
+        let native = predicate
+            .evaluate_native(
+                &BitcoinUnit::default(),
+                &BitcoinUnit::default(),
+                &tx,
+                &BitcoinUnit::default(),
+            )
+            .unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        predicate
+            .enforce_constraints(
+                cs.clone(),
+                &BitcoinUnitVar::default(),
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &BitcoinUnitVar::default(),
+            )
+            .unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), native);
+        native
+    }
+
+    fn outputs(amount: u64, payee: u8, data: Script, data_amount: u64) -> Vec<TxOut> {
+        vec![
+            TxOut {
+                satoshis: amount as i64,
+                lock_script: lock_script(payee),
+            },
+            TxOut {
+                satoshis: data_amount as i64,
+                lock_script: data,
+            },
+            TxOut {
+                satoshis: 5000,
+                lock_script: lock_script(3),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_tx_template_is_ok() {
+        assert!(test_predicate(outputs(1000, 1, data_script([1, 2, 3]), 0)));
+        assert!(test_predicate(outputs(2000, 1, data_script([4, 5, 6]), 0)));
+    }
+
+    #[test]
+    fn test_tx_template_fails() {
+        // Amount below the minimum
+        assert!(!test_predicate(outputs(999, 1, data_script([1, 2, 3]), 0)));
+        // Wrong script
+        assert!(!test_predicate(outputs(1000, 2, data_script([1, 2, 3]), 0)));
+        // Wrong prefix
+        assert!(!test_predicate(outputs(
+            1000,
+            1,
+            Script(vec![OP_RETURN, OP_FALSE, 0x03, 1, 2, 3]),
+            0
+        )));
+        // Amount above the maximum
+        assert!(!test_predicate(outputs(1000, 1, data_script([1, 2, 3]), 1)));
+    }
+
+    #[test]
+    fn test_output_template_empty_range() {
+        assert!(matches!(
+            OutputTemplate::new(ScriptTemplate::Any, 1000, 999),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_tx_template_wrong_number_of_outputs() {
+        assert!(matches!(
+            TxTemplate::<F, Config>::new(vec![OutputTemplate::any(), OutputTemplate::any()]),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+}
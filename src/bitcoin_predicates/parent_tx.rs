@@ -0,0 +1,276 @@
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    eq::EqGadget,
+    prelude::{AllocVar, Boolean},
+};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+
+use chain_gang::messages::Tx;
+use chain_gang::script::Script;
+
+use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+use crate::constraints::{
+    script::ScriptVar,
+    tx::{TxVar, TxVarConfig},
+};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+
+/// Bitcoin Predicate enforcing that the input of the spending transaction at `input_index` spends an output of
+/// the parent transaction, and that the output of the parent transaction at `vout` has locking script equal to
+/// `lock_script`.
+///
+/// The parent transaction is the witness, and it is linked to the spending transaction by computing its txid
+/// in the circuit, see [TxVar::txid]. Its shape is set by `Q`. As the parent transaction need not be known when
+/// the predicate is set up, this enables two-hop covenants.
+pub struct ParentTx<F: PrimeField, P: TxVarConfig + Clone, Q: TxVarConfig + Clone> {
+    pub input_index: usize,
+    pub vout: usize,
+    pub lock_script: Script,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+    _phantom_parent_config: PhantomData<Q>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone, Q: TxVarConfig + Clone> ParentTx<F, P, Q> {
+    /// Returns [Error::IndexOutOfBounds] if `vout` is not an output of the parent transaction, and
+    /// [Error::ConfigMismatch] if the length of `lock_script` is different from the one set in `Q`
+    pub fn new(input_index: usize, vout: usize, lock_script: Script) -> Result<Self, Error> {
+        if vout >= Q::N_OUTPUTS {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of outputs of the parent transaction: {}",
+                vout,
+                Q::N_OUTPUTS
+            )));
+        }
+        if lock_script.0.len() != Q::LEN_LOCK_SCRIPTS[vout] {
+            return Err(Error::ConfigMismatch(format!(
+                "The length of the locking script: {} is different from the one set in the parameters: {}",
+                lock_script.0.len(),
+                Q::LEN_LOCK_SCRIPTS[vout]
+            )));
+        }
+        Ok(Self {
+            input_index,
+            vout,
+            lock_script,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+            _phantom_parent_config: PhantomData,
+        })
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone, Q: TxVarConfig + Clone> BitcoinPredicate<F, P>
+    for ParentTx<F, P, Q>
+{
+    type LockingData = BitcoinUnit<F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = Tx;
+
+    type LockingDataVar = BitcoinUnitVar<F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = TxVar<F, Q>;
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        _locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        // Validate input
+        if self.input_index >= spending_data.inputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Input index: {} is larger than the number of inputs: {}",
+                self.input_index,
+                spending_data.inputs.len()
+            ))
+            .into());
+        }
+
+        Boolean::<F>::kary_and(&[
+            spending_data.inputs[self.input_index]
+                .prev_output
+                .prev_tx
+                .is_eq(&witness.txid()?)?,
+            witness.outputs[self.vout]
+                .lock_script
+                .is_eq(&ScriptVar::<F>::new_constant(
+                    cs.clone(),
+                    self.lock_script.clone(),
+                )?)?,
+        ])
+    }
+
+    fn evaluate_native(
+        &self,
+        _locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        // Validate input
+        if self.input_index >= tx.inputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Input index: {} is larger than the number of inputs: {}",
+                self.input_index,
+                tx.inputs.len()
+            )));
+        }
+        if self.vout >= witness.outputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of outputs of the parent transaction: {}",
+                self.vout,
+                witness.outputs.len()
+            )));
+        }
+
+        Ok(
+            tx.inputs[self.input_index].prev_output.hash == witness.hash()
+                && witness.outputs[self.vout].lock_script.0 == self.lock_script.0,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::script::Script;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::util::{Hash160, Hash256};
+
+    use crate::bitcoin_predicates::data_structures::unit::BitcoinUnitVar;
+    use crate::constraints::tx::{TxVar, TxVarConfig};
+    use crate::error::Error;
+    use crate::traits::BitcoinPredicate;
+
+    use super::ParentTx;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0];
+    }
+
+    #[derive(Clone)]
+    struct ParentConfig;
+    impl TxVarConfig for ParentConfig {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 2;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19, 0x19];
+    }
+
+    fn lock_script(n: u8) -> Script {
+        p2pkh::create_lock_script(&Hash160([n; 20]))
+    }
+
+    fn parent_tx() -> Tx {
+        Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![
+                TxOut {
+                    satoshis: 1000,
+                    lock_script: lock_script(1),
+                },
+                TxOut {
+                    satoshis: 2000,
+                    lock_script: lock_script(2),
+                },
+            ],
+            lock_time: 0,
+        }
+    }
+
+    fn test_predicate(parent: Tx, prev_tx: Hash256, vout: usize, script: Script) -> bool {
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: prev_tx,
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 900,
+                lock_script: Script(vec![]),
+            }],
+            lock_time: 0,
+        };
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        let parent_var = TxVar::<F, ParentConfig>::new_witness(cs.clone(), || Ok(parent)).unwrap();
+        ParentTx::<F, Config, ParentConfig>::new(0, vout, script)
+            .unwrap()
+            .enforce_constraints(
+                cs.clone(),
+                &BitcoinUnitVar::default(),
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &parent_var,
+            )
+            .unwrap();
+
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_parent_tx_is_ok() {
+        let parent = parent_tx();
+        let txid = parent.hash();
+        assert!(test_predicate(parent.clone(), txid, 0, lock_script(1)));
+        assert!(test_predicate(parent, txid, 1, lock_script(2)));
+    }
+
+    #[test]
+    fn test_parent_tx_fails() {
+        let parent = parent_tx();
+        let txid = parent.hash();
+        // Wrong script in the parent transaction
+        assert!(!test_predicate(parent.clone(), txid, 1, lock_script(1)));
+        // The spending transaction does not spend the parent transaction
+        let mut other = parent.clone();
+        other.lock_time = 1;
+        assert!(!test_predicate(parent, other.hash(), 0, lock_script(1)));
+    }
+
+    #[test]
+    fn test_wrong_script_length() {
+        assert!(matches!(
+            ParentTx::<F, Config, ParentConfig>::new(0, 0, Script(vec![])),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_vout_out_of_bounds() {
+        assert!(matches!(
+            ParentTx::<F, Config, ParentConfig>::new(0, 2, lock_script(1)),
+            Err(Error::IndexOutOfBounds(_))
+        ));
+    }
+}
@@ -0,0 +1,282 @@
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    eq::EqGadget,
+    fields::{FieldVar, fp::FpVar},
+    prelude::{AllocVar, Boolean},
+};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+
+use chain_gang::{messages::Tx, script::Script};
+
+use crate::bitcoin_predicates::data_structures::{
+    field_array::{FieldArray, FieldArrayVar},
+    unit::{BitcoinUnit, BitcoinUnitVar},
+};
+use crate::constraints::{
+    script::ScriptVar,
+    tx::{TxVar, TxVarConfig},
+};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+use crate::util::{fits_in_bits, is_less_than, to_fp, to_u64};
+
+/// The total of the shares of a [PercentageSplit]: shares are expressed in basis points
+pub const BASIS_POINTS: u64 = 10_000;
+
+/// Bit size bounding the remainders of the divisions by [BASIS_POINTS]
+const REMAINDER_BIT_SIZE: usize = 14;
+
+/// Bitcoin Predicate enforcing that the spending transaction splits an amount across `M` fixed scripts: for every
+/// `i < M`, the output at index `i` is locked by `lock_scripts[i]` and its amount is `floor(amount * shares[i] / 10_000)`.
+///
+/// The locking data is the amount to split, e.g., the amount of the output being spent minus the fee. The rounding
+/// is enforced through the remainders `amount * shares[i] - 10_000 * output_amount`, which must lie in `[0, 10_000)`.
+pub struct PercentageSplit<const M: usize, F: PrimeField, P: TxVarConfig + Clone> {
+    pub lock_scripts: [Script; M],
+    pub shares: [u64; M],
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<const M: usize, F: PrimeField, P: TxVarConfig + Clone> PercentageSplit<M, F, P> {
+    /// Returns [Error::ConfigMismatch] if there are more recipients than outputs, or if the shares do not sum
+    /// to [BASIS_POINTS]
+    pub fn new(lock_scripts: [Script; M], shares: [u64; M]) -> Result<Self, Error> {
+        if M > P::N_OUTPUTS {
+            return Err(Error::ConfigMismatch(format!(
+                "The number of recipients: {} is larger than the number of outputs set in the parameters: {}",
+                M,
+                P::N_OUTPUTS
+            )));
+        }
+        if shares
+            .iter()
+            .try_fold(0u64, |total, share| total.checked_add(*share))
+            != Some(BASIS_POINTS)
+        {
+            return Err(Error::ConfigMismatch(format!(
+                "The shares: {:?} do not sum to {} basis points",
+                shares, BASIS_POINTS
+            )));
+        }
+        Ok(Self {
+            lock_scripts,
+            shares,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        })
+    }
+}
+
+impl<const M: usize, F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P>
+    for PercentageSplit<M, F, P>
+{
+    type LockingData = FieldArray<1, F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = FieldArrayVar<1, F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let amount = &locking_data.elements[0];
+        let basis_points = FpVar::<F>::constant(F::from(BASIS_POINTS));
+
+        // The amount is a public input, so it must be range checked before computing the shares
+        let mut checks: Vec<Boolean<F>> = Vec::with_capacity(3 * M + 1);
+        checks.push(fits_in_bits(amount, 64)?);
+        for (i, (lock_script, share)) in
+            self.lock_scripts.iter().zip(self.shares.iter()).enumerate()
+        {
+            let output = &spending_data.outputs[i];
+
+            // The remainder may be negative, so it is fully decomposed before the comparison
+            let remainder = amount * FpVar::<F>::constant(F::from(*share))
+                - &basis_points * to_fp(&output.satoshis)?;
+            checks.push(fits_in_bits(&remainder, REMAINDER_BIT_SIZE)?);
+            checks.push(is_less_than(&remainder, &basis_points, REMAINDER_BIT_SIZE)?);
+            checks.push(output.lock_script.is_eq(&ScriptVar::<F>::new_constant(
+                cs.clone(),
+                lock_script.clone(),
+            )?)?);
+        }
+
+        Boolean::<F>::kary_and(&checks)
+    }
+
+    fn evaluate_native(
+        &self,
+        locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        // Validate input
+        if M > tx.outputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "The number of recipients: {} is larger than the number of outputs: {}",
+                M,
+                tx.outputs.len()
+            )));
+        }
+
+        let Some(amount) = to_u64(&locking_data.elements[0]) else {
+            return Ok(false);
+        };
+        Ok(self
+            .lock_scripts
+            .iter()
+            .zip(self.shares.iter())
+            .zip(tx.outputs.iter())
+            .all(|((lock_script, share), output)| {
+                let remainder = amount as i128 * *share as i128
+                    - BASIS_POINTS as i128 * output.satoshis as u64 as i128;
+                (0..BASIS_POINTS as i128).contains(&remainder)
+                    && output.lock_script.0 == lock_script.0
+            }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::script::Script;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::util::{Hash160, Hash256};
+
+    use crate::bitcoin_predicates::data_structures::{
+        field_array::{FieldArray, FieldArrayVar},
+        unit::{BitcoinUnit, BitcoinUnitVar},
+    };
+    use crate::constraints::tx::{TxVar, TxVarConfig};
+    use crate::error::Error;
+    use crate::traits::BitcoinPredicate;
+
+    use super::PercentageSplit;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 3;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19, 0x19, 0x19];
+    }
+
+    const SHARES: [u64; 3] = [7_000, 2_000, 1_000];
+
+    fn lock_script(n: u8) -> Script {
+        p2pkh::create_lock_script(&Hash160([n; 20]))
+    }
+
+    fn test_predicate(amount: u64, output_amounts: [u64; 3], scripts: [u8; 3]) -> bool {
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: output_amounts
+                .iter()
+                .zip(scripts.iter())
+                .map(|(amount, n)| TxOut {
+                    satoshis: *amount as i64,
+                    lock_script: lock_script(*n),
+                })
+                .collect(),
+            lock_time: 0,
+        };
+
+        let predicate =
+            PercentageSplit::<3, F, Config>::new([1, 2, 3].map(lock_script), SHARES).unwrap();
+        let native = predicate
+            .evaluate_native(
+                &FieldArray::<1, F, Config>::new([F::from(amount)]),
+                &BitcoinUnit::default(),
+                &tx,
+                &BitcoinUnit::default(),
+            )
+            .unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let locking_data = FieldArrayVar::<1, F, Config>::new_input(cs.clone(), || {
+            Ok(FieldArray::<1, F, Config>::new([F::from(amount)]))
+        })
+        .unwrap();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        predicate
+            .enforce_constraints(
+                cs.clone(),
+                &locking_data,
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &BitcoinUnitVar::default(),
+            )
+            .unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), native);
+        native
+    }
+
+    #[test]
+    fn test_percentage_split_is_ok() {
+        assert!(test_predicate(10_000, [7_000, 2_000, 1_000], [1, 2, 3]));
+        // Amounts rounded down
+        assert!(test_predicate(10_009, [7_006, 2_001, 1_000], [1, 2, 3]));
+    }
+
+    #[test]
+    fn test_percentage_split_fails() {
+        // Amounts rounded up
+        assert!(!test_predicate(10_009, [7_007, 2_002, 1_001], [1, 2, 3]));
+        // Amounts rounded down too much
+        assert!(!test_predicate(10_000, [6_999, 2_000, 1_000], [1, 2, 3]));
+        // Wrong recipients
+        assert!(!test_predicate(10_000, [7_000, 2_000, 1_000], [2, 1, 3]));
+    }
+
+    #[test]
+    fn test_percentage_split_invalid_shares() {
+        assert!(matches!(
+            PercentageSplit::<3, F, Config>::new([1, 2, 3].map(lock_script), [7_000, 2_000, 2_000]),
+            Err(Error::ConfigMismatch(_))
+        ));
+        assert!(matches!(
+            PercentageSplit::<3, F, Config>::new(
+                [1, 2, 3].map(lock_script),
+                [u64::MAX, 2_000, 8_001]
+            ),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_percentage_split_too_many_recipients() {
+        assert!(matches!(
+            PercentageSplit::<4, F, Config>::new(
+                [1, 2, 3, 4].map(lock_script),
+                [7_000, 2_000, 500, 500]
+            ),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+}
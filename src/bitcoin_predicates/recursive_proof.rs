@@ -0,0 +1,289 @@
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use ark_crypto_primitives::snark::{SNARK, SNARKGadget};
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::{AllocVar, AllocationMode},
+    prelude::Boolean,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+use chain_gang::messages::Tx;
+
+use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+use crate::constraints::tx::{TxVar, TxVarConfig};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+
+/// The proof verified by [RecursiveProof], together with the public input it verifies against
+pub struct RecursiveProofWitness<InnerF: PrimeField, S: SNARK<InnerF>> {
+    pub proof: S::Proof,
+    pub public_input: Vec<InnerF>,
+}
+
+impl<InnerF: PrimeField, S: SNARK<InnerF>> Clone for RecursiveProofWitness<InnerF, S> {
+    fn clone(&self) -> Self {
+        Self {
+            proof: self.proof.clone(),
+            public_input: self.public_input.clone(),
+        }
+    }
+}
+
+/// The R1CS version of [RecursiveProofWitness]
+pub struct RecursiveProofWitnessVar<
+    InnerF: PrimeField,
+    F: PrimeField,
+    S: SNARK<InnerF>,
+    G: SNARKGadget<InnerF, F, S>,
+> {
+    pub proof: G::ProofVar,
+    pub public_input: G::InputVar,
+}
+
+impl<InnerF, F, S, G> AllocVar<RecursiveProofWitness<InnerF, S>, F>
+    for RecursiveProofWitnessVar<InnerF, F, S, G>
+where
+    InnerF: PrimeField,
+    F: PrimeField,
+    S: SNARK<InnerF>,
+    G: SNARKGadget<InnerF, F, S>,
+{
+    fn new_variable<T: Borrow<RecursiveProofWitness<InnerF, S>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let data = f()?;
+        let witness: &RecursiveProofWitness<InnerF, S> = data.borrow();
+        Ok(Self {
+            proof: G::ProofVar::new_variable(cs.clone(), || Ok(witness.proof.clone()), mode)?,
+            public_input: G::InputVar::new_variable(
+                cs.clone(),
+                || Ok(witness.public_input.clone()),
+                mode,
+            )?,
+        })
+    }
+}
+
+/// Bitcoin Predicate enforcing the validity of a SNARK proof for the circuit with verifying key `vk`.
+///
+/// The proof is verified inside the circuit with the gadget `G`, so the field `F` of the circuit must be the
+/// field the verifier of `S` operates on, e.g., the base field of the pairing-friendly curve for Groth16. Chained
+/// covenants alternate between the two curves of a cycle, e.g., MNT4-298 and MNT6-298, each spend proving the
+/// validity of the proof of the previous one.
+///
+/// **Note**: The verifying key is a constant of the circuit, and the public input of the verified proof is a
+/// private witness: it is up to the caller to bind it to the spending transaction if needed.
+pub struct RecursiveProof<
+    InnerF: PrimeField,
+    F: PrimeField,
+    P: TxVarConfig + Clone,
+    S: SNARK<InnerF>,
+    G: SNARKGadget<InnerF, F, S>,
+> {
+    pub vk: S::VerifyingKey,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+    _phantom_gadget: PhantomData<G>,
+}
+
+impl<InnerF, F, P, S, G> RecursiveProof<InnerF, F, P, S, G>
+where
+    InnerF: PrimeField,
+    F: PrimeField,
+    P: TxVarConfig + Clone,
+    S: SNARK<InnerF>,
+    G: SNARKGadget<InnerF, F, S>,
+{
+    pub fn new(vk: S::VerifyingKey) -> Self {
+        Self {
+            vk,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+            _phantom_gadget: PhantomData,
+        }
+    }
+}
+
+impl<InnerF, F, P, S, G> BitcoinPredicate<F, P> for RecursiveProof<InnerF, F, P, S, G>
+where
+    InnerF: PrimeField,
+    F: PrimeField,
+    P: TxVarConfig + Clone,
+    S: SNARK<InnerF>,
+    G: SNARKGadget<InnerF, F, S>,
+{
+    type LockingData = BitcoinUnit<F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = RecursiveProofWitness<InnerF, S>;
+
+    type LockingDataVar = BitcoinUnitVar<F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = RecursiveProofWitnessVar<InnerF, F, S, G>;
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        _locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        _spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let vk = G::VerifyingKeyVar::new_constant(cs.clone(), self.vk.clone())?;
+
+        G::verify(&vk, &witness.public_input, &witness.proof)
+    }
+
+    /// Returns [Error::ConfigMismatch] if the verifier of `S` fails, e.g., if the length of the public input does
+    /// not match the verifying key
+    fn evaluate_native(
+        &self,
+        _locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        _tx: &Tx,
+        witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        S::verify(&self.vk, &witness.public_input, &witness.proof)
+            .map_err(|error| Error::ConfigMismatch(error.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_crypto_primitives::snark::SNARK;
+    use ark_groth16::{Groth16, constraints::Groth16VerifierGadget};
+    use ark_mnt4_298::{Fq as F, Fr as InnerF, MNT4_298, constraints::PairingVar};
+    use ark_r1cs_std::{alloc::AllocVar, eq::EqGadget, fields::fp::FpVar};
+    use ark_relations::r1cs::{
+        ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, SynthesisError,
+    };
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::script::Script;
+    use chain_gang::util::Hash256;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+    use crate::constraints::tx::{TxVar, TxVarConfig};
+    use crate::traits::BitcoinPredicate;
+
+    use super::{RecursiveProof, RecursiveProofWitness, RecursiveProofWitnessVar};
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0];
+    }
+
+    type InnerSnark = Groth16<MNT4_298>;
+    type Gadget = Groth16VerifierGadget<MNT4_298, PairingVar>;
+    type Predicate = RecursiveProof<InnerF, F, Config, InnerSnark, Gadget>;
+
+    /// Circuit enforcing `x * x = y`, with `y` public
+    struct SquareCircuit {
+        x: InnerF,
+    }
+
+    impl ConstraintSynthesizer<InnerF> for SquareCircuit {
+        fn generate_constraints(
+            self,
+            cs: ConstraintSystemRef<InnerF>,
+        ) -> Result<(), SynthesisError> {
+            let x = FpVar::<InnerF>::new_witness(cs.clone(), || Ok(self.x))?;
+            let y = FpVar::<InnerF>::new_input(cs.clone(), || Ok(self.x * self.x))?;
+            (&x * &x).enforce_equal(&y)
+        }
+    }
+
+    fn tx() -> Tx {
+        Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 900,
+                lock_script: Script(vec![]),
+            }],
+            lock_time: 0,
+        }
+    }
+
+    fn test_predicate(public_input: InnerF) -> bool {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let (pk, vk) = InnerSnark::circuit_specific_setup(
+            SquareCircuit {
+                x: InnerF::from(0u64),
+            },
+            &mut rng,
+        )
+        .unwrap();
+        let proof = InnerSnark::prove(
+            &pk,
+            SquareCircuit {
+                x: InnerF::from(3u64),
+            },
+            &mut rng,
+        )
+        .unwrap();
+        let witness = RecursiveProofWitness::<InnerF, InnerSnark> {
+            proof,
+            public_input: vec![public_input],
+        };
+        let predicate = Predicate::new(vk);
+        let native = predicate
+            .evaluate_native(
+                &BitcoinUnit::default(),
+                &BitcoinUnit::default(),
+                &tx(),
+                &witness,
+            )
+            .unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_witness(cs.clone(), || Ok(tx())).unwrap();
+        let witness_var = RecursiveProofWitnessVar::<InnerF, F, InnerSnark, Gadget>::new_witness(
+            cs.clone(),
+            || Ok(witness),
+        )
+        .unwrap();
+        predicate
+            .enforce_constraints(
+                cs.clone(),
+                &BitcoinUnitVar::default(),
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &witness_var,
+            )
+            .unwrap();
+
+        assert_eq!(cs.is_satisfied().unwrap(), native);
+        native
+    }
+
+    #[test]
+    fn test_recursive_proof_is_ok() {
+        assert!(test_predicate(InnerF::from(9u64)));
+    }
+
+    #[test]
+    fn test_recursive_proof_fails() {
+        assert!(!test_predicate(InnerF::from(10u64)));
+    }
+}
@@ -0,0 +1,271 @@
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    convert::ToBitsGadget,
+    eq::EqGadget,
+    fields::fp::FpVar,
+    prelude::{AllocVar, Boolean},
+};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use chain_gang::messages::Tx;
+
+use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+use crate::constraints::tx::{TxVar, TxVarConfig};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+use crate::util::{is_less_or_equal, to_fp};
+
+/// Position of the bit of the sequence disabling the relative lock time, see BIP68
+const SEQUENCE_DISABLE_FLAG_BIT: usize = 31;
+/// Position of the bit of the sequence setting the relative lock time in units of 512 seconds, see BIP68
+const SEQUENCE_TYPE_FLAG_BIT: usize = 22;
+/// Number of bits of the sequence encoding the relative lock time, see BIP68
+const SEQUENCE_VALUE_BITS: usize = 16;
+/// Granularity, in seconds, of time-based relative lock times, see BIP68
+pub const SEQUENCE_GRANULARITY: u32 = 512;
+
+/// Bitcoin Predicate to enforce that the input at `input_index` of the spending transaction has a relative lock
+/// time of at least `min_value`, as `OP_CHECKSEQUENCEVERIFY` does:
+/// - the spending transaction has version at least 2
+/// - the relative lock time of the input is enabled
+/// - the relative lock time is of the same kind as `min_value`: blocks, or units of [SEQUENCE_GRANULARITY] seconds
+///   if `is_time_based`
+pub struct RelativeLockTime<F: PrimeField, P: TxVarConfig + Clone> {
+    pub input_index: usize,
+    pub min_value: u16,
+    pub is_time_based: bool,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> RelativeLockTime<F, P> {
+    pub fn new(input_index: usize, min_value: u16, is_time_based: bool) -> Self {
+        Self {
+            input_index,
+            min_value,
+            is_time_based,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        }
+    }
+
+    /// Relative lock time of at least `blocks` blocks
+    pub fn blocks(input_index: usize, blocks: u16) -> Self {
+        Self::new(input_index, blocks, false)
+    }
+
+    /// Relative lock time of at least `seconds` seconds, rounded up to a multiple of [SEQUENCE_GRANULARITY]
+    ///
+    /// Returns [Error::ConfigMismatch] if the relative lock time cannot be encoded in the sequence
+    pub fn seconds(input_index: usize, seconds: u32) -> Result<Self, Error> {
+        let min_value = seconds.div_ceil(SEQUENCE_GRANULARITY);
+        if min_value > u16::MAX as u32 {
+            return Err(Error::ConfigMismatch(format!(
+                "The relative lock time: {} seconds cannot be encoded in the sequence",
+                seconds
+            )));
+        }
+        Ok(Self::new(input_index, min_value as u16, true))
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for RelativeLockTime<F, P> {
+    type LockingData = BitcoinUnit<F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = BitcoinUnitVar<F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        _cs: ConstraintSystemRef<F>,
+        _locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        // Validate input
+        if self.input_index >= spending_data.inputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Input index: {} is larger than the number of inputs: {}",
+                self.input_index,
+                spending_data.inputs.len()
+            ))
+            .into());
+        }
+
+        let sequence_bits = spending_data.inputs[self.input_index]
+            .sequence
+            .to_bits_le()?;
+        let value = Boolean::<F>::le_bits_to_fp(&sequence_bits[..SEQUENCE_VALUE_BITS])?;
+
+        Boolean::<F>::kary_and(&[
+            is_less_or_equal(
+                &FpVar::<F>::constant(F::from(2u64)),
+                &to_fp(&spending_data.version)?,
+                32,
+            )?,
+            !&sequence_bits[SEQUENCE_DISABLE_FLAG_BIT],
+            sequence_bits[SEQUENCE_TYPE_FLAG_BIT]
+                .is_eq(&Boolean::<F>::constant(self.is_time_based))?,
+            is_less_or_equal(
+                &FpVar::<F>::constant(F::from(self.min_value)),
+                &value,
+                SEQUENCE_VALUE_BITS,
+            )?,
+        ])
+    }
+
+    fn evaluate_native(
+        &self,
+        _locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        // Validate input
+        if self.input_index >= tx.inputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Input index: {} is larger than the number of inputs: {}",
+                self.input_index,
+                tx.inputs.len()
+            )));
+        }
+
+        let sequence = tx.inputs[self.input_index].sequence;
+        let value = sequence & ((1 << SEQUENCE_VALUE_BITS) - 1);
+        Ok(2 <= tx.version
+            && (sequence >> SEQUENCE_DISABLE_FLAG_BIT) & 1 == 0
+            && ((sequence >> SEQUENCE_TYPE_FLAG_BIT) & 1 == 1) == self.is_time_based
+            && self.min_value as u32 <= value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use ark_bls12_381::Fr as F;
+
+    use ark_r1cs_std::alloc::AllocVar;
+
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::script::Script;
+
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::unit::BitcoinUnitVar;
+    use crate::constraints::tx::TxVar;
+    use crate::error::Error;
+
+    use crate::{constraints::tx::TxVarConfig, traits::BitcoinPredicate};
+
+    use super::RelativeLockTime;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0];
+    }
+
+    const TYPE_FLAG: u32 = 1 << 22;
+    const DISABLE_FLAG: u32 = 1 << 31;
+
+    fn test_predicate(
+        predicate: RelativeLockTime<F, Config>,
+        version: u32,
+        sequence: u32,
+        expected: bool,
+    ) {
+        let tx = Tx {
+            version,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 100,
+                lock_script: Script(vec![]),
+            }],
+            lock_time: 0,
+        };
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        predicate
+            .enforce_constraints(
+                cs.clone(),
+                &BitcoinUnitVar::default(),
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &BitcoinUnitVar::default(),
+            )
+            .unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_blocks_is_ok() {
+        test_predicate(RelativeLockTime::blocks(0, 144), 2, 144, true);
+        test_predicate(RelativeLockTime::blocks(0, 144), 2, 1000, true);
+        // Bits outside of the BIP68 layout are ignored
+        test_predicate(RelativeLockTime::blocks(0, 144), 2, 144 | 1 << 16, true);
+    }
+
+    #[test]
+    fn test_blocks_fails() {
+        // Too early
+        test_predicate(RelativeLockTime::blocks(0, 144), 2, 143, false);
+        // Relative lock time disabled
+        test_predicate(
+            RelativeLockTime::blocks(0, 144),
+            2,
+            144 | DISABLE_FLAG,
+            false,
+        );
+        // Time-based relative lock time
+        test_predicate(RelativeLockTime::blocks(0, 144), 2, 144 | TYPE_FLAG, false);
+        // Relative lock times are not enforced for version 1
+        test_predicate(RelativeLockTime::blocks(0, 144), 1, 144, false);
+    }
+
+    #[test]
+    fn test_seconds() {
+        // One day, rounded up to 169 units of 512 seconds
+        test_predicate(
+            RelativeLockTime::seconds(0, 86400).unwrap(),
+            2,
+            169 | TYPE_FLAG,
+            true,
+        );
+        test_predicate(
+            RelativeLockTime::seconds(0, 86400).unwrap(),
+            2,
+            168 | TYPE_FLAG,
+            false,
+        );
+        // Block-based relative lock time
+        test_predicate(RelativeLockTime::seconds(0, 86400).unwrap(), 2, 169, false);
+    }
+
+    #[test]
+    fn test_seconds_too_large() {
+        assert!(matches!(
+            RelativeLockTime::<F, Config>::seconds(0, u32::MAX),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+}
@@ -0,0 +1,221 @@
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{eq::EqGadget, prelude::Boolean};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+
+use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+use crate::constraints::tx::{TxVar, TxVarConfig};
+use crate::error::Error;
+use crate::traits::{BitcoinPredicate, SpendingContext};
+
+/// Bitcoin Predicate to enforce that the output of the transaction at `index` has the same locking script as the
+/// output being spent, i.e., a perpetual covenant.
+///
+/// The locking script of the output being spent is the one bound to the tag by the integrity scheme, see
+/// [IntegrityScheme::prev_lock_script](crate::traits::IntegrityScheme::prev_lock_script): the predicate is
+/// unsatisfiable with schemes which do not expose it.
+pub struct SelfReplicatingScript<F: PrimeField, P: TxVarConfig + Clone> {
+    pub index: usize,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> SelfReplicatingScript<F, P> {
+    pub fn new(index: usize) -> Self {
+        Self {
+            index,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for SelfReplicatingScript<F, P> {
+    type LockingData = BitcoinUnit<F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = BitcoinUnitVar<F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        self.generate_constraints_with_context(
+            cs,
+            locking_data,
+            unlocking_data,
+            spending_data,
+            witness,
+            &SpendingContext::default(),
+        )
+    }
+
+    fn generate_constraints_with_context(
+        &self,
+        _cs: ConstraintSystemRef<F>,
+        _locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+        context: &SpendingContext<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        // Validate input
+        if self.index >= spending_data.outputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of outputs: {}",
+                self.index,
+                spending_data.outputs.len()
+            ))
+            .into());
+        }
+        let prev_lock_script = context.prev_lock_script.ok_or(Error::MissingData(
+            "The locking script of the output being spent is not available".to_string(),
+        ))?;
+
+        let lock_script = &spending_data.outputs[self.index].lock_script;
+        if lock_script.0.len() != prev_lock_script.0.len() {
+            return Ok(Boolean::<F>::FALSE);
+        }
+
+        lock_script.is_eq(prev_lock_script)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use chain_gang::address::addr_decode;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::network::Network;
+    use chain_gang::script::Script;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::transaction::sighash::{SIGHASH_ALL, SIGHASH_FORKID};
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+    use crate::constraints::tx::{TxVar, TxVarConfig};
+    use crate::reftx::RefTxCircuit;
+    use crate::traits::{BitcoinPredicate, IntegrityScheme};
+    use crate::transaction_integrity_gadget::{
+        TransactionIntegrityConfig,
+        scheme::{PoseidonIntegrity, PrevOutput},
+    };
+
+    use super::SelfReplicatingScript;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    impl TransactionIntegrityConfig for Config {
+        const N_INPUT: usize = 0;
+        const LEN_PREV_LOCK_SCRIPT: usize = 0x19;
+        const SIGHASH_FLAG: u8 = SIGHASH_ALL | SIGHASH_FORKID;
+    }
+
+    type Predicate = SelfReplicatingScript<F, Config>;
+    type Circuit = RefTxCircuit<Predicate, F, Config, PoseidonIntegrity<F, Config>>;
+
+    fn p2pkh_script(addr: &str) -> Script {
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        p2pkh::create_lock_script(&hash160)
+    }
+
+    fn tx(addr: &str) -> Tx {
+        Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 900,
+                lock_script: p2pkh_script(addr),
+            }],
+            lock_time: 0,
+        }
+    }
+
+    fn test_predicate(prev_addr: &str, addr: &str) -> bool {
+        let tx = tx(addr);
+        let integrity_scheme = PoseidonIntegrity::<F, Config>::default();
+        let mut integrity_witness = PrevOutput {
+            prev_lock_script: p2pkh_script(prev_addr),
+            prev_amount: 1000,
+        };
+        let integrity_tag = integrity_scheme
+            .commit(&tx, &mut integrity_witness)
+            .unwrap();
+        let circuit = Circuit {
+            locking_data: BitcoinUnit::default(),
+            integrity_tag: Some(integrity_tag),
+            unlocking_data: BitcoinUnit::default(),
+            witness: BitcoinUnit::default(),
+            spending_data: Some(tx),
+            integrity_witness: Some(integrity_witness),
+            predicate: Predicate::new(0),
+            integrity_scheme,
+            setup_mode: false,
+        };
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_self_replicating_script_is_ok() {
+        let addr = "mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec";
+        assert!(test_predicate(addr, addr));
+    }
+
+    #[test]
+    fn test_self_replicating_script_fails() {
+        assert!(!test_predicate(
+            "mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec",
+            "mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz"
+        ));
+    }
+
+    #[test]
+    fn test_missing_prev_lock_script() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_witness(cs.clone(), || {
+            Ok(tx("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec"))
+        })
+        .unwrap();
+        assert!(
+            Predicate::new(0)
+                .generate_constraints(
+                    cs.clone(),
+                    &BitcoinUnitVar::default(),
+                    &BitcoinUnitVar::default(),
+                    &tx_var,
+                    &BitcoinUnitVar::default(),
+                )
+                .is_err()
+        );
+    }
+}
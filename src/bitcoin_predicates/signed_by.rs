@@ -0,0 +1,595 @@
+//! Signatures over the sighash of the spending transaction
+//!
+//! [SignedBy] and [SignedByKeyHash] verify a [Schnorr](crate::schnorr::Schnorr) signature over the sighash of the
+//! input `P::N_INPUT` of the spending transaction under `P::SIGHASH_FLAG`, i.e., over the tag of
+//! [SighashIntegrity](crate::transaction_integrity_gadget::scheme::SighashIntegrity) with the same parameters.
+//! With the public key, or its HASH160, in the locking data, they are the zk analogues of P2PK and P2PKH.
+//!
+//! The sighash is not recomputed by the predicates: they verify the signature over the sighash computed by the
+//! integrity scheme, see [IntegrityScheme::enforce_integrity](crate::traits::IntegrityScheme::enforce_integrity),
+//! which binds it to the integrity tag. Hence, they must be used with an integrity scheme exposing it, e.g.,
+//! [SighashIntegrity](crate::transaction_integrity_gadget::scheme::SighashIntegrity), otherwise they return
+//! [Error::MissingData].
+//!
+//! The signatures are over a twisted Edwards curve whose base field is the field of the circuit, as ECDSA over
+//! secp256k1 would require non-native arithmetic.
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use ark_crypto_primitives::crh::sha256::constraints::DigestVar;
+use ark_ec::twisted_edwards::{Affine, TECurveConfig};
+use ark_ff::{BigInteger, PrimeField};
+use ark_r1cs_std::{
+    alloc::{AllocVar, AllocationMode},
+    convert::ToBytesGadget,
+    eq::EqGadget,
+    groups::curves::twisted_edwards::AffineVar,
+    prelude::Boolean,
+    uint8::UInt8,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+use chain_gang::{messages::Tx, transaction::sighash::SigHashCache, util::hash160};
+
+use crate::bitcoin_predicates::data_structures::{
+    byte_array::{ByteArray, ByteArrayVar},
+    field_array::{FieldArray, FieldArrayVar},
+    unit::{BitcoinUnit, BitcoinUnitVar},
+};
+use crate::constraints::{
+    hash160::Hash160Gadget,
+    script::ScriptVar,
+    tx::{TxVar, TxVarConfig},
+};
+use crate::error::Error;
+use crate::schnorr::{
+    SchnorrSignature,
+    constraints::{SchnorrGadget, SchnorrPublicKeyVar, SchnorrSignatureVar},
+};
+use crate::traits::{BitcoinPredicate, SpendingContext};
+use crate::transaction_integrity_gadget::{
+    TransactionIntegrityConfig, TransactionIntegrityScheme, scheme::PrevOutput,
+};
+
+/// The message signed for [SignedBy] and [SignedByKeyHash]: the sighash of `tx`
+pub fn sighash_message<P: TransactionIntegrityConfig>(
+    tx: &Tx,
+    prev_output: &PrevOutput,
+) -> Result<Vec<u8>, Error> {
+    Ok(TransactionIntegrityScheme::<P>::commit(
+        tx,
+        &prev_output.prev_lock_script,
+        prev_output.prev_amount,
+        &mut SigHashCache::new(),
+    )?
+    .inner
+    .to_vec())
+}
+
+/// The HASH160 of `public_key`, serialised as the 32-byte little endian representations of its coordinates
+pub fn key_hash<C: TECurveConfig>(public_key: &Affine<C>) -> [u8; 20]
+where
+    C::BaseField: PrimeField,
+{
+    let mut data: Vec<u8> = public_key.x.into_bigint().to_bytes_le();
+    data.extend(public_key.y.into_bigint().to_bytes_le());
+    hash160(&data).0
+}
+
+/// The witness of [SignedBy]: the signature. A missing signature is replaced by [SchnorrSignature::default],
+/// which is never valid
+pub struct SignatureWitness<C: TECurveConfig> {
+    pub signature: Option<SchnorrSignature<C>>,
+}
+
+impl<C: TECurveConfig> Clone for SignatureWitness<C> {
+    fn clone(&self) -> Self {
+        Self {
+            signature: self.signature.clone(),
+        }
+    }
+}
+
+/// The R1CS version of [SignatureWitness]
+pub struct SignatureWitnessVar<F: PrimeField, C: TECurveConfig<BaseField = F>> {
+    pub signature: SchnorrSignatureVar<F, C>,
+}
+
+impl<F: PrimeField, C: TECurveConfig<BaseField = F>> AllocVar<SignatureWitness<C>, F>
+    for SignatureWitnessVar<F, C>
+{
+    fn new_variable<T: Borrow<SignatureWitness<C>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let witness: SignatureWitness<C> = f().map(|data| data.borrow().clone())?;
+        Ok(Self {
+            signature: SchnorrSignatureVar::<F, C>::new_variable(
+                cs.clone(),
+                || Ok(witness.signature.unwrap_or_default()),
+                mode,
+            )?,
+        })
+    }
+}
+
+/// The witness of [SignedByKeyHash]: the public key and the witness of [SignedBy]
+pub struct KeyHashSignatureWitness<C: TECurveConfig> {
+    pub public_key: Affine<C>,
+    pub signature_witness: SignatureWitness<C>,
+}
+
+impl<C: TECurveConfig> Clone for KeyHashSignatureWitness<C> {
+    fn clone(&self) -> Self {
+        Self {
+            public_key: self.public_key,
+            signature_witness: self.signature_witness.clone(),
+        }
+    }
+}
+
+/// The R1CS version of [KeyHashSignatureWitness]
+pub struct KeyHashSignatureWitnessVar<F: PrimeField, C: TECurveConfig<BaseField = F>> {
+    pub public_key: SchnorrPublicKeyVar<F, C>,
+    pub signature_witness: SignatureWitnessVar<F, C>,
+}
+
+impl<F: PrimeField, C: TECurveConfig<BaseField = F>> AllocVar<KeyHashSignatureWitness<C>, F>
+    for KeyHashSignatureWitnessVar<F, C>
+{
+    fn new_variable<T: Borrow<KeyHashSignatureWitness<C>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let witness: KeyHashSignatureWitness<C> = f().map(|data| data.borrow().clone())?;
+        Ok(Self {
+            public_key: SchnorrPublicKeyVar::<F, C>::new_variable(
+                cs.clone(),
+                || Ok(witness.public_key),
+                mode,
+            )?,
+            signature_witness: SignatureWitnessVar::<F, C>::new_variable(
+                cs.clone(),
+                || Ok(witness.signature_witness),
+                mode,
+            )?,
+        })
+    }
+}
+
+/// The sighash of the spending transaction computed by the integrity scheme, returning [Error::MissingData] if it
+/// is not available
+pub(crate) fn expect_sighash<F: PrimeField>(
+    sighash: Option<&DigestVar<F>>,
+) -> Result<&DigestVar<F>, Error> {
+    sighash.ok_or(Error::MissingData(
+        "The sighash of the spending transaction is not available".to_string(),
+    ))
+}
+
+/// Check that `witness` contains a signature under `public_key` of `sighash`, the sighash of the spending
+/// transaction computed by the integrity scheme. It returns [Error::MissingData] if `sighash` is not available
+fn is_sighash_signed<F, C>(
+    sighash: Option<&DigestVar<F>>,
+    public_key: &SchnorrPublicKeyVar<F, C>,
+    witness: &SignatureWitnessVar<F, C>,
+) -> Result<Boolean<F>, SynthesisError>
+where
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+{
+    let sighash = expect_sighash(sighash)?;
+
+    SchnorrGadget::<F, C>::verify(public_key, &sighash.0, &witness.signature)
+}
+
+/// Bitcoin Predicate enforcing that the witness contains a signature of the sighash of the spending transaction
+/// under the public key in the locking data, as its affine coordinates `[x, y]`
+pub struct SignedBy<
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+    P: TransactionIntegrityConfig + TxVarConfig + Clone,
+> {
+    _phantom_field: PhantomData<F>,
+    _phantom_curve: PhantomData<C>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F, C, P> SignedBy<F, C, P>
+where
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+    P: TransactionIntegrityConfig + TxVarConfig + Clone,
+{
+    pub fn new() -> Self {
+        Self {
+            _phantom_field: PhantomData,
+            _phantom_curve: PhantomData,
+            _phantom_config: PhantomData,
+        }
+    }
+
+    /// The locking data for `public_key`
+    pub fn locking_data(public_key: &Affine<C>) -> FieldArray<2, F, P> {
+        FieldArray::new([public_key.x, public_key.y])
+    }
+}
+
+impl<F, C, P> Default for SignedBy<F, C, P>
+where
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+    P: TransactionIntegrityConfig + TxVarConfig + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F, C, P> BitcoinPredicate<F, P> for SignedBy<F, C, P>
+where
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+    P: TransactionIntegrityConfig + TxVarConfig + Clone,
+{
+    type LockingData = FieldArray<2, F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = SignatureWitness<C>;
+
+    type LockingDataVar = FieldArrayVar<2, F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = SignatureWitnessVar<F, C>;
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        self.generate_constraints_with_context(
+            cs,
+            locking_data,
+            unlocking_data,
+            spending_data,
+            witness,
+            &SpendingContext::default(),
+        )
+    }
+
+    fn generate_constraints_with_context(
+        &self,
+        _cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        _spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+        context: &SpendingContext<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let public_key = AffineVar::<C, _>::new(
+            locking_data.elements[0].clone(),
+            locking_data.elements[1].clone(),
+        );
+
+        is_sighash_signed(context.sighash, &public_key, witness)
+    }
+}
+
+/// Bitcoin Predicate enforcing that the witness contains a public key whose HASH160 is the locking data,
+/// see [key_hash], and a signature of the sighash of the spending transaction under it
+pub struct SignedByKeyHash<
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+    P: TransactionIntegrityConfig + TxVarConfig + Clone,
+> {
+    _phantom_field: PhantomData<F>,
+    _phantom_curve: PhantomData<C>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F, C, P> SignedByKeyHash<F, C, P>
+where
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+    P: TransactionIntegrityConfig + TxVarConfig + Clone,
+{
+    pub fn new() -> Self {
+        Self {
+            _phantom_field: PhantomData,
+            _phantom_curve: PhantomData,
+            _phantom_config: PhantomData,
+        }
+    }
+
+    /// The locking data for `public_key`
+    pub fn locking_data(public_key: &Affine<C>) -> ByteArray<20, F, P> {
+        ByteArray::new(key_hash(public_key))
+    }
+}
+
+impl<F, C, P> Default for SignedByKeyHash<F, C, P>
+where
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+    P: TransactionIntegrityConfig + TxVarConfig + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F, C, P> BitcoinPredicate<F, P> for SignedByKeyHash<F, C, P>
+where
+    F: PrimeField,
+    C: TECurveConfig<BaseField = F>,
+    P: TransactionIntegrityConfig + TxVarConfig + Clone,
+{
+    type LockingData = ByteArray<20, F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = KeyHashSignatureWitness<C>;
+
+    type LockingDataVar = ByteArrayVar<20, F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = KeyHashSignatureWitnessVar<F, C>;
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        self.generate_constraints_with_context(
+            cs,
+            locking_data,
+            unlocking_data,
+            spending_data,
+            witness,
+            &SpendingContext::default(),
+        )
+    }
+
+    fn generate_constraints_with_context(
+        &self,
+        _cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        _spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+        context: &SpendingContext<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        // Serialise the public key as in [key_hash]
+        let mut data: Vec<UInt8<F>> = witness.public_key.x.to_bytes_le()?;
+        data.extend(witness.public_key.y.to_bytes_le()?);
+
+        Boolean::<F>::kary_and(&[
+            Hash160Gadget::<F>::evaluate(&data)?.is_eq(&locking_data.bytes.to_vec())?,
+            is_sighash_signed(
+                context.sighash,
+                &witness.public_key,
+                &witness.signature_witness,
+            )?,
+        ])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_ed_on_bls12_381::EdwardsConfig;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::address::addr_decode;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::network::Network;
+    use chain_gang::script::Script;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::transaction::sighash::{SIGHASH_ALL, SIGHASH_FORKID};
+    use chain_gang::util::Hash256;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::bitcoin_predicates::data_structures::{
+        byte_array::ByteArrayVar, field_array::FieldArrayVar, unit::BitcoinUnitVar,
+    };
+    use crate::constraints::{
+        sighash_cache::SigHashCacheVar,
+        tx::{TxVar, TxVarConfig},
+    };
+    use crate::schnorr::Schnorr;
+    use crate::traits::{BitcoinPredicate, SpendingContext};
+    use crate::transaction_integrity_gadget::{
+        TransactionIntegrityConfig,
+        scheme::{PrevOutput, PrevOutputVar},
+    };
+
+    use super::{
+        KeyHashSignatureWitness, KeyHashSignatureWitnessVar, SignatureWitness, SignatureWitnessVar,
+        SignedBy, SignedByKeyHash, sighash_message,
+    };
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    impl TransactionIntegrityConfig for Config {
+        const N_INPUT: usize = 0;
+        const LEN_PREV_LOCK_SCRIPT: usize = 0x19;
+        const SIGHASH_FLAG: u8 = SIGHASH_ALL | SIGHASH_FORKID;
+    }
+
+    fn p2pkh_script(addr: &str) -> Script {
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        p2pkh::create_lock_script(&hash160)
+    }
+
+    fn tx(satoshis: i64) -> Tx {
+        Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![TxOut {
+                satoshis,
+                lock_script: p2pkh_script("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec"),
+            }],
+            lock_time: 0,
+        }
+    }
+
+    fn prev_output() -> PrevOutput {
+        PrevOutput {
+            prev_lock_script: p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz"),
+            prev_amount: 1000,
+        }
+    }
+
+    /// Sign the transaction paying `signed_amount`, and check the predicate on the one paying `amount`
+    fn test_predicate(signed_amount: i64, amount: i64, key_hash: bool) -> bool {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let (secret_key, public_key) = Schnorr::<EdwardsConfig>::keygen(&mut rng);
+        let message = sighash_message::<Config>(&tx(signed_amount), &prev_output()).unwrap();
+        let signature_witness = SignatureWitness {
+            signature: Some(Schnorr::<EdwardsConfig>::sign(
+                &mut rng,
+                &secret_key,
+                &message,
+            )),
+        };
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_witness(cs.clone(), || Ok(tx(amount))).unwrap();
+        // The sighash computed by the integrity scheme
+        let prev_output_var =
+            PrevOutputVar::<F>::new_witness(cs.clone(), || Ok(prev_output())).unwrap();
+        let sighash = tx_var
+            .sighash(
+                Config::N_INPUT,
+                &prev_output_var.prev_lock_script,
+                &prev_output_var.prev_amount,
+                &Config::SIGHASH_FLAG,
+                &mut SigHashCacheVar::<F>::new(),
+            )
+            .unwrap();
+        if key_hash {
+            let locking_data = ByteArrayVar::<20, F, Config>::new_input(cs.clone(), || {
+                Ok(SignedByKeyHash::<F, EdwardsConfig, Config>::locking_data(
+                    &public_key,
+                ))
+            })
+            .unwrap();
+            let witness =
+                KeyHashSignatureWitnessVar::<F, EdwardsConfig>::new_witness(cs.clone(), || {
+                    Ok(KeyHashSignatureWitness {
+                        public_key,
+                        signature_witness,
+                    })
+                })
+                .unwrap();
+            SignedByKeyHash::<F, EdwardsConfig, Config>::new()
+                .enforce_constraints_with_context(
+                    cs.clone(),
+                    &locking_data,
+                    &BitcoinUnitVar::default(),
+                    &tx_var,
+                    &witness,
+                    &SpendingContext {
+                        sighash: Some(&sighash),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+        } else {
+            let locking_data = FieldArrayVar::<2, F, Config>::new_input(cs.clone(), || {
+                Ok(SignedBy::<F, EdwardsConfig, Config>::locking_data(
+                    &public_key,
+                ))
+            })
+            .unwrap();
+            let witness = SignatureWitnessVar::<F, EdwardsConfig>::new_witness(cs.clone(), || {
+                Ok(signature_witness)
+            })
+            .unwrap();
+            SignedBy::<F, EdwardsConfig, Config>::new()
+                .enforce_constraints_with_context(
+                    cs.clone(),
+                    &locking_data,
+                    &BitcoinUnitVar::default(),
+                    &tx_var,
+                    &witness,
+                    &SpendingContext {
+                        sighash: Some(&sighash),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+        }
+
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_signed_by() {
+        assert!(test_predicate(900, 900, false));
+        assert!(!test_predicate(900, 800, false));
+    }
+
+    #[test]
+    fn test_signed_by_without_sighash() {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let (_, public_key) = Schnorr::<EdwardsConfig>::keygen(&mut rng);
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_witness(cs.clone(), || Ok(tx(900))).unwrap();
+        let locking_data = FieldArrayVar::<2, F, Config>::new_input(cs.clone(), || {
+            Ok(SignedBy::<F, EdwardsConfig, Config>::locking_data(
+                &public_key,
+            ))
+        })
+        .unwrap();
+        let witness = SignatureWitnessVar::<F, EdwardsConfig>::new_witness(cs.clone(), || {
+            Ok(SignatureWitness { signature: None })
+        })
+        .unwrap();
+
+        assert!(
+            SignedBy::<F, EdwardsConfig, Config>::new()
+                .generate_constraints(
+                    cs.clone(),
+                    &locking_data,
+                    &BitcoinUnitVar::default(),
+                    &tx_var,
+                    &witness,
+                )
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_signed_by_key_hash() {
+        assert!(test_predicate(900, 900, true));
+        assert!(!test_predicate(900, 800, true));
+    }
+}
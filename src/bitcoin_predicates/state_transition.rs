@@ -0,0 +1,302 @@
+//! State-transition covenants
+//!
+//! [StateTransition] enforces that the spending transaction publishes, in an `OP_FALSE OP_RETURN` output, a new
+//! state which is a valid transition from the old state stored in the locking data. The validity of the transition
+//! is defined by a [StateTransitionGadget], e.g., a closure over the old and the new state.
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    eq::EqGadget,
+    fields::fp::FpVar,
+    prelude::{AllocVar, Boolean},
+    uint8::UInt8,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use chain_gang::script::Script;
+use chain_gang::script::op_codes::{OP_FALSE, OP_RETURN};
+
+use crate::bitcoin_predicates::data_structures::{
+    field_array::{FieldArray, FieldArrayVar},
+    unit::{BitcoinUnit, BitcoinUnitVar},
+};
+use crate::constraints::tx::{TxVar, TxVarConfig};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+use crate::util::{push_data_prefix, to_fp};
+
+/// Gadget checking the validity of the transition from `old_state` to `new_state`
+pub trait StateTransitionGadget<F: PrimeField> {
+    fn is_valid_transition(
+        &self,
+        old_state: &[FpVar<F>],
+        new_state: &[FpVar<F>],
+    ) -> Result<Boolean<F>, SynthesisError>;
+}
+
+impl<F, T> StateTransitionGadget<F> for T
+where
+    F: PrimeField,
+    T: Fn(&[FpVar<F>], &[FpVar<F>]) -> Result<Boolean<F>, SynthesisError>,
+{
+    fn is_valid_transition(
+        &self,
+        old_state: &[FpVar<F>],
+        new_state: &[FpVar<F>],
+    ) -> Result<Boolean<F>, SynthesisError> {
+        self(old_state, new_state)
+    }
+}
+
+/// The locking script `OP_FALSE OP_RETURN <state>`, where `state` is the serialisation of the new state:
+/// each element is encoded in little endian with `element_size` bytes
+///
+/// Returns [Error::ConfigMismatch] if an element does not fit in `element_size` bytes
+pub fn state_lock_script(state: &[u64], element_size: usize) -> Result<Script, Error> {
+    let mut data: Vec<u8> = Vec::with_capacity(state.len() * element_size);
+    for element in state.iter() {
+        let bytes = element.to_le_bytes();
+        if bytes[element_size.min(8)..].iter().any(|byte| *byte != 0) {
+            return Err(Error::ConfigMismatch(format!(
+                "The element: {} does not fit in {} bytes",
+                element, element_size
+            )));
+        }
+        data.extend(
+            bytes
+                .iter()
+                .chain(std::iter::repeat(&0u8))
+                .take(element_size),
+        );
+    }
+
+    let mut script = vec![OP_FALSE, OP_RETURN];
+    script.extend(push_data_prefix(data.len()).unwrap());
+    script.extend(data);
+    Ok(Script(script))
+}
+
+/// Bitcoin Predicate enforcing that the output of the spending transaction at `index` is an `OP_FALSE OP_RETURN`
+/// output publishing a new state of `N_STATE` elements (see [state_lock_script]), and that `transition` is
+/// satisfied by the old state, i.e., the locking data, and the new state
+pub struct StateTransition<
+    const N_STATE: usize,
+    F: PrimeField,
+    P: TxVarConfig + Clone,
+    T: StateTransitionGadget<F>,
+> {
+    pub index: usize,
+    pub element_size: usize,
+    pub transition: T,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<const N_STATE: usize, F: PrimeField, P: TxVarConfig + Clone, T: StateTransitionGadget<F>>
+    StateTransition<N_STATE, F, P, T>
+{
+    /// Returns [Error::ConfigMismatch] if an element of `element_size` bytes does not fit in a field element
+    pub fn new(index: usize, element_size: usize, transition: T) -> Result<Self, Error> {
+        if element_size == 0 || 8 * element_size >= F::MODULUS_BIT_SIZE as usize {
+            return Err(Error::ConfigMismatch(format!(
+                "The size of the elements of the state: {} bytes is not compatible with the field",
+                element_size
+            )));
+        }
+        Ok(Self {
+            index,
+            element_size,
+            transition,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        })
+    }
+
+    /// The prefix of the locking script of the output publishing the new state
+    fn script_prefix(&self) -> Vec<u8> {
+        let mut prefix = vec![OP_FALSE, OP_RETURN];
+        prefix.extend(push_data_prefix(N_STATE * self.element_size).unwrap());
+        prefix
+    }
+}
+
+impl<const N_STATE: usize, F, P, T> BitcoinPredicate<F, P> for StateTransition<N_STATE, F, P, T>
+where
+    F: PrimeField,
+    P: TxVarConfig + Clone,
+    T: StateTransitionGadget<F>,
+{
+    type LockingData = FieldArray<N_STATE, F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = FieldArrayVar<N_STATE, F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        _cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        // Validate input
+        if self.index >= spending_data.outputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of outputs: {}",
+                self.index,
+                spending_data.outputs.len()
+            ))
+            .into());
+        }
+        let prefix = self.script_prefix();
+        let lock_script = &spending_data.outputs[self.index].lock_script.0;
+        if lock_script.len() != prefix.len() + N_STATE * self.element_size {
+            return Err(Error::ConfigMismatch(format!(
+                "The length of the locking script of the output {}: {} is incompatible with a state of {} elements of {} bytes",
+                self.index,
+                lock_script.len(),
+                N_STATE,
+                self.element_size
+            ))
+            .into());
+        }
+
+        // Parse the new state
+        let (script_prefix, data) = lock_script.split_at(prefix.len());
+        let new_state = data
+            .chunks(self.element_size)
+            .map(|element| to_fp(&element.to_vec()))
+            .collect::<Result<Vec<FpVar<F>>, SynthesisError>>()?;
+
+        Boolean::<F>::kary_and(&[
+            script_prefix.is_eq(&UInt8::<F>::constant_vec(&prefix))?,
+            self.transition
+                .is_valid_transition(&locking_data.elements, &new_state)?,
+        ])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::{alloc::AllocVar, eq::EqGadget, fields::fp::FpVar, prelude::Boolean};
+    use ark_relations::r1cs::{ConstraintSystem, SynthesisError};
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::script::Script;
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::{
+        field_array::{FieldArray, FieldArrayVar},
+        unit::BitcoinUnitVar,
+    };
+    use crate::constraints::tx::{TxVar, TxVarConfig};
+    use crate::error::Error;
+    use crate::traits::BitcoinPredicate;
+
+    use super::{StateTransition, state_lock_script};
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[11];
+    }
+
+    /// Transfer of `amount` from the first to the second balance of the state
+    fn transfer(
+        old_state: &[FpVar<F>],
+        new_state: &[FpVar<F>],
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let amount = &old_state[0] - &new_state[0];
+        Boolean::<F>::kary_and(&[
+            new_state[1].is_eq(&(&old_state[1] + &amount))?,
+            amount.is_eq(&FpVar::<F>::constant(F::from(10u64)))?,
+        ])
+    }
+
+    fn test_predicate(old_state: [u64; 2], lock_script: Script) -> bool {
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 0,
+                lock_script,
+            }],
+            lock_time: 0,
+        };
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let locking_data = FieldArrayVar::<2, F, Config>::new_input(cs.clone(), || {
+            Ok(FieldArray::<2, F, Config>::new(old_state.map(F::from)))
+        })
+        .unwrap();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        StateTransition::<2, F, Config, _>::new(0, 4, transfer)
+            .unwrap()
+            .enforce_constraints(
+                cs.clone(),
+                &locking_data,
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &BitcoinUnitVar::default(),
+            )
+            .unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_state_transition_is_ok() {
+        assert!(test_predicate(
+            [100, 0],
+            state_lock_script(&[90, 10], 4).unwrap()
+        ));
+        assert!(test_predicate(
+            [0x01000000, 5],
+            state_lock_script(&[0x00fffff6, 15], 4).unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_state_transition_fails() {
+        // Invalid transition
+        assert!(!test_predicate(
+            [100, 0],
+            state_lock_script(&[80, 20], 4).unwrap()
+        ));
+        // The output is not an OP_RETURN output
+        let mut lock_script = state_lock_script(&[90, 10], 4).unwrap();
+        lock_script.0[0] = 0x51;
+        assert!(!test_predicate([100, 0], lock_script));
+    }
+
+    #[test]
+    fn test_state_element_too_large() {
+        assert!(matches!(
+            state_lock_script(&[0x100000000, 0], 4),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_state_transition_invalid_element_size() {
+        assert!(matches!(
+            StateTransition::<2, F, Config, _>::new(0, 32, transfer),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+}
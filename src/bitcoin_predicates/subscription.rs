@@ -0,0 +1,434 @@
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    eq::EqGadget,
+    fields::{FieldVar, fp::FpVar},
+    prelude::{AllocVar, AllocationMode, Boolean},
+    select::CondSelectGadget,
+    uint32::UInt32,
+    uint64::UInt64,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+
+use chain_gang::{messages::Tx, script::Script};
+
+use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+use crate::constraints::{
+    script::ScriptVar,
+    tx::{TxVar, TxVarConfig},
+};
+use crate::error::Error;
+use crate::traits::{BitcoinPredicate, PublicInputSize};
+use crate::util::{LOCKTIME_THRESHOLD, SEQUENCE_FINAL, is_less_or_equal, is_less_than, to_fp};
+
+/// The state of a [Subscription]: the balance locked in the covenant output being spent,
+/// and the lock time of the transaction which created it
+#[derive(Clone)]
+pub struct SubscriptionState<F: PrimeField, P: TxVarConfig + Clone> {
+    pub balance: u64,
+    pub last_payment_time: u32,
+    _field: PhantomData<F>,
+    _config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> SubscriptionState<F, P> {
+    pub fn new(balance: u64, last_payment_time: u32) -> Self {
+        Self {
+            balance,
+            last_payment_time,
+            _field: PhantomData,
+            _config: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> From<SubscriptionState<F, P>> for Vec<F> {
+    fn from(value: SubscriptionState<F, P>) -> Self {
+        vec![F::from(value.balance), F::from(value.last_payment_time)]
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> PublicInputSize<F> for SubscriptionState<F, P> {
+    const N_PUBLIC_INPUTS: usize = 2;
+}
+
+/// R1CS version of [SubscriptionState]
+/// The state is allocated as two field elements, matching its conversion into `Vec<F>`, which are then
+/// range checked against witnessed [UInt64] and [UInt32] variables
+pub struct SubscriptionStateVar<F: PrimeField, P: TxVarConfig + Clone> {
+    pub balance: UInt64<F>,
+    pub last_payment_time: UInt32<F>,
+    _config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> AllocVar<SubscriptionState<F, P>, F>
+    for SubscriptionStateVar<F, P>
+{
+    fn new_variable<T: Borrow<SubscriptionState<F, P>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let state: SubscriptionState<F, P> = f().map(|data| data.borrow().clone())?;
+        let bits_mode = match mode {
+            AllocationMode::Constant => AllocationMode::Constant,
+            _ => AllocationMode::Witness,
+        };
+
+        let balance = UInt64::<F>::new_variable(cs.clone(), || Ok(state.balance), bits_mode)?;
+        let last_payment_time =
+            UInt32::<F>::new_variable(cs.clone(), || Ok(state.last_payment_time), bits_mode)?;
+        let elements = Vec::<FpVar<F>>::new_variable(
+            cs.clone(),
+            || Ok(Into::<Vec<F>>::into(state.clone())),
+            mode,
+        )?;
+        to_fp(&balance)?.enforce_equal(&elements[0])?;
+        to_fp(&last_payment_time)?.enforce_equal(&elements[1])?;
+
+        Ok(Self {
+            balance,
+            last_payment_time,
+            _config: PhantomData,
+        })
+    }
+}
+
+/// Bitcoin Predicate implementing a subscription covenant: each spend can withdraw at most
+/// `rate * elapsed` satoshis from the prepaid balance, and the remainder must be returned to `covenant_script`
+/// in the output at `index`.
+///
+/// The locking data is the [SubscriptionState] of the output being spent. The elapsed time is the difference between
+/// the lock time of the spending transaction and `last_payment_time`, the lock time of the parent transaction.
+/// The lock time of the spending transaction must be a block height and must be enforced, i.e., the input at
+/// `input_index` must not be final.
+///
+/// **Note**: The predicate only checks the spending transaction against the state. Linking the state to the actual
+/// parent transaction (e.g., the amount of the output being spent) is left to the verifier of the predicate.
+pub struct Subscription<F: PrimeField, P: TxVarConfig + Clone> {
+    pub covenant_script: Script,
+    pub rate: u64,
+    pub index: usize,
+    pub input_index: usize,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> Subscription<F, P> {
+    /// Returns [Error::ConfigMismatch] if `rate` does not fit in 32 bits
+    pub fn new(
+        covenant_script: Script,
+        rate: u64,
+        index: usize,
+        input_index: usize,
+    ) -> Result<Self, Error> {
+        if rate > u32::MAX as u64 {
+            return Err(Error::ConfigMismatch(format!(
+                "The rate: {} is larger than the maximum rate: {}",
+                rate,
+                u32::MAX
+            )));
+        }
+        Ok(Self {
+            covenant_script,
+            rate,
+            index,
+            input_index,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        })
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for Subscription<F, P> {
+    type LockingData = SubscriptionState<F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = SubscriptionStateVar<F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        // Validate input
+        if self.index >= spending_data.outputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of outputs: {}",
+                self.index,
+                spending_data.outputs.len()
+            ))
+            .into());
+        }
+        if self.input_index >= spending_data.inputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Input index: {} is larger than the number of inputs: {}",
+                self.input_index,
+                spending_data.inputs.len()
+            ))
+            .into());
+        }
+
+        // Check that the lock time of the spending transaction is an enforced block height
+        let lock_time: FpVar<F> = to_fp(&spending_data.lock_time)?;
+        let is_block_height = is_less_than(
+            &lock_time,
+            &FpVar::<F>::constant(F::from(LOCKTIME_THRESHOLD)),
+            32,
+        )?;
+        let is_not_final = spending_data.inputs[self.input_index]
+            .sequence
+            .is_neq(&UInt32::<F>::constant(SEQUENCE_FINAL))?;
+
+        // Compute the elapsed time, set to zero if the lock time precedes the last payment
+        let last_payment_time: FpVar<F> = to_fp(&locking_data.last_payment_time)?;
+        let is_after_last_payment = is_less_or_equal(&last_payment_time, &lock_time, 32)?;
+        let elapsed = FpVar::<F>::conditionally_select(
+            &is_after_last_payment,
+            &(&lock_time - &last_payment_time),
+            &FpVar::<F>::zero(),
+        )?;
+
+        // Compute the withdrawn amount, set to zero if the remainder exceeds the balance
+        let remainder_output = &spending_data.outputs[self.index];
+        let balance: FpVar<F> = to_fp(&locking_data.balance)?;
+        let remainder: FpVar<F> = to_fp(&remainder_output.satoshis)?;
+        let is_remainder_bounded = is_less_or_equal(&remainder, &balance, 64)?;
+        let withdrawn = FpVar::<F>::conditionally_select(
+            &is_remainder_bounded,
+            &(&balance - &remainder),
+            &FpVar::<F>::zero(),
+        )?;
+
+        // `withdrawn < 2^64` and `rate * elapsed < 2^64`
+        let is_withdrawal_allowed =
+            is_less_or_equal(&withdrawn, &(elapsed * F::from(self.rate)), 64)?;
+
+        // Check that the remainder is returned to the covenant
+        let is_returned = remainder_output
+            .lock_script
+            .is_eq(&ScriptVar::<F>::new_constant(
+                cs.clone(),
+                self.covenant_script.clone(),
+            )?)?;
+
+        Boolean::<F>::kary_and(&[
+            is_block_height,
+            is_not_final,
+            is_after_last_payment,
+            is_remainder_bounded,
+            is_withdrawal_allowed,
+            is_returned,
+        ])
+    }
+
+    fn evaluate_native(
+        &self,
+        locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        // Validate input
+        if self.index >= tx.outputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of outputs: {}",
+                self.index,
+                tx.outputs.len()
+            )));
+        }
+        if self.input_index >= tx.inputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Input index: {} is larger than the number of inputs: {}",
+                self.input_index,
+                tx.inputs.len()
+            )));
+        }
+
+        // Check that the lock time of the spending transaction is an enforced block height
+        let is_block_height = tx.lock_time < LOCKTIME_THRESHOLD;
+        let is_not_final = tx.inputs[self.input_index].sequence != SEQUENCE_FINAL;
+        let Some(elapsed) = tx.lock_time.checked_sub(locking_data.last_payment_time) else {
+            return Ok(false);
+        };
+
+        // Check the withdrawn amount and that the remainder is returned to the covenant
+        let remainder_output = &tx.outputs[self.index];
+        let Some(withdrawn) = locking_data
+            .balance
+            .checked_sub(remainder_output.satoshis as u64)
+        else {
+            return Ok(false);
+        };
+        let is_withdrawal_allowed = withdrawn as u128 <= elapsed as u128 * self.rate as u128;
+        let is_returned = remainder_output.lock_script.0 == self.covenant_script.0;
+
+        Ok(is_block_height && is_not_final && is_withdrawal_allowed && is_returned)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use ark_bls12_381::Fr as F;
+
+    use ark_r1cs_std::alloc::AllocVar;
+
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::script::Script;
+
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+    use crate::constraints::tx::TxVar;
+    use crate::error::Error;
+
+    use crate::{constraints::tx::TxVarConfig, traits::BitcoinPredicate};
+
+    use super::{Subscription, SubscriptionState, SubscriptionStateVar};
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 2;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[3, 1];
+    }
+
+    const BALANCE: u64 = 1000;
+    const LAST_PAYMENT_TIME: u32 = 800_000;
+    const RATE: u64 = 10;
+
+    fn covenant_script() -> Script {
+        Script(vec![0x51, 0x52, 0x53])
+    }
+
+    fn test_predicate(
+        remainder: u64,
+        lock_script: Script,
+        lock_time: u32,
+        sequence: u32,
+        expected: bool,
+    ) {
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence,
+            }],
+            outputs: vec![
+                TxOut {
+                    satoshis: remainder as i64,
+                    lock_script,
+                },
+                TxOut {
+                    satoshis: 100,
+                    lock_script: Script(vec![0x51]),
+                },
+            ],
+            lock_time,
+        };
+
+        let predicate = Subscription::<F, Config>::new(covenant_script(), RATE, 0, 0).unwrap();
+        assert_eq!(
+            predicate
+                .evaluate_native(
+                    &SubscriptionState::new(BALANCE, LAST_PAYMENT_TIME),
+                    &BitcoinUnit::default(),
+                    &tx,
+                    &BitcoinUnit::default(),
+                )
+                .unwrap(),
+            expected
+        );
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let state_var = SubscriptionStateVar::<F, Config>::new_input(cs.clone(), || {
+            Ok(SubscriptionState::new(BALANCE, LAST_PAYMENT_TIME))
+        })
+        .unwrap();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        predicate
+            .enforce_constraints(
+                cs.clone(),
+                &state_var,
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &BitcoinUnitVar::default(),
+            )
+            .unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_withdrawal_within_rate() {
+        test_predicate(900, covenant_script(), LAST_PAYMENT_TIME + 10, 0, true);
+        test_predicate(950, covenant_script(), LAST_PAYMENT_TIME + 10, 0, true);
+        test_predicate(BALANCE, covenant_script(), LAST_PAYMENT_TIME, 0, true);
+        test_predicate(0, covenant_script(), LAST_PAYMENT_TIME + 1000, 0, true);
+    }
+
+    #[test]
+    fn test_withdrawal_above_rate() {
+        test_predicate(899, covenant_script(), LAST_PAYMENT_TIME + 10, 0, false);
+        test_predicate(999, covenant_script(), LAST_PAYMENT_TIME, 0, false);
+    }
+
+    #[test]
+    fn test_invalid_spend() {
+        // Remainder larger than the balance
+        test_predicate(
+            BALANCE + 1,
+            covenant_script(),
+            LAST_PAYMENT_TIME + 10,
+            0,
+            false,
+        );
+        // Remainder not returned to the covenant
+        test_predicate(
+            900,
+            Script(vec![0x51, 0x52, 0x54]),
+            LAST_PAYMENT_TIME + 10,
+            0,
+            false,
+        );
+        // Lock time before the last payment
+        test_predicate(BALANCE, covenant_script(), LAST_PAYMENT_TIME - 1, 0, false);
+        // Lock time not enforced
+        test_predicate(
+            900,
+            covenant_script(),
+            LAST_PAYMENT_TIME + 10,
+            0xffffffff,
+            false,
+        );
+    }
+
+    #[test]
+    fn test_rate_too_large() {
+        assert!(matches!(
+            Subscription::<F, Config>::new(covenant_script(), u32::MAX as u64 + 1, 0, 0),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+}
@@ -0,0 +1,284 @@
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    eq::EqGadget,
+    fields::{FieldVar, fp::FpVar},
+    prelude::Boolean,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use chain_gang::messages::Tx;
+
+use crate::bitcoin_predicates::data_structures::{
+    field_array::{FieldArray, FieldArrayVar},
+    unit::{BitcoinUnit, BitcoinUnitVar},
+};
+use crate::constraints::tx::{TxVar, TxVarConfig};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+use crate::util::to_fp;
+
+/// Bitcoin Predicate for fungible tokens: the locking scripts of the outputs at `indices` contain, at `offset`,
+/// an `n_bytes` little endian token amount, and the sum of these amounts is equal to the token amount of the
+/// output being spent, i.e., the locking data.
+///
+/// Each amount has at most `8 * n_bytes` bits and there are at most `2^8` of them, so the sum cannot overflow
+/// the field.
+pub struct TokenConservation<F: PrimeField, P: TxVarConfig + Clone> {
+    pub indices: Vec<usize>,
+    pub offset: usize,
+    pub n_bytes: usize,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> TokenConservation<F, P> {
+    /// Returns [Error::ConfigMismatch] if the number of token outputs is not in `1..2^8`, if the sum of the amounts
+    /// may overflow the field, or if the indices are not distinct
+    pub fn new(indices: Vec<usize>, offset: usize, n_bytes: usize) -> Result<Self, Error> {
+        if indices.is_empty() || indices.len() >= 1 << 8 {
+            return Err(Error::ConfigMismatch(format!(
+                "The number of token outputs: {} is not supported",
+                indices.len()
+            )));
+        }
+        if n_bytes == 0 || 8 * n_bytes + 8 >= F::MODULUS_BIT_SIZE as usize {
+            return Err(Error::ConfigMismatch(format!(
+                "The size of the token amounts: {} bytes is not compatible with the field",
+                n_bytes
+            )));
+        }
+        let mut sorted_indices = indices.clone();
+        sorted_indices.sort();
+        sorted_indices.dedup();
+        if sorted_indices.len() != indices.len() {
+            return Err(Error::ConfigMismatch(format!(
+                "The indices of the token outputs: {:?} are not distinct",
+                indices
+            )));
+        }
+        Ok(Self {
+            indices,
+            offset,
+            n_bytes,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        })
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for TokenConservation<F, P> {
+    type LockingData = FieldArray<1, F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = FieldArrayVar<1, F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        _cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let mut total = FpVar::<F>::zero();
+        for index in self.indices.iter() {
+            // Validate input
+            if *index >= spending_data.outputs.len() {
+                return Err(Error::IndexOutOfBounds(format!(
+                    "Index: {} is larger than the number of outputs: {}",
+                    index,
+                    spending_data.outputs.len()
+                ))
+                .into());
+            }
+            let lock_script = &spending_data.outputs[*index].lock_script.0;
+            if self.offset + self.n_bytes > lock_script.len() {
+                return Err(Error::IndexOutOfBounds(format!(
+                    "The token amount at: {}..{} is not contained in the locking script of the output {} of length: {}",
+                    self.offset,
+                    self.offset + self.n_bytes,
+                    index,
+                    lock_script.len()
+                ))
+                .into());
+            }
+
+            total += to_fp(&lock_script[self.offset..self.offset + self.n_bytes].to_vec())?;
+        }
+
+        total.is_eq(&locking_data.elements[0])
+    }
+
+    fn evaluate_native(
+        &self,
+        locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        let mut total = F::zero();
+        for index in self.indices.iter() {
+            // Validate input
+            if *index >= tx.outputs.len() {
+                return Err(Error::IndexOutOfBounds(format!(
+                    "Index: {} is larger than the number of outputs: {}",
+                    index,
+                    tx.outputs.len()
+                )));
+            }
+            let lock_script = &tx.outputs[*index].lock_script.0;
+            if self.offset + self.n_bytes > lock_script.len() {
+                return Err(Error::IndexOutOfBounds(format!(
+                    "The token amount at: {}..{} is not contained in the locking script of the output {} of length: {}",
+                    self.offset,
+                    self.offset + self.n_bytes,
+                    index,
+                    lock_script.len()
+                )));
+            }
+
+            total +=
+                F::from_le_bytes_mod_order(&lock_script[self.offset..self.offset + self.n_bytes]);
+        }
+
+        Ok(total == locking_data.elements[0])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::script::Script;
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::{
+        field_array::{FieldArray, FieldArrayVar},
+        unit::{BitcoinUnit, BitcoinUnitVar},
+    };
+    use crate::constraints::tx::{TxVar, TxVarConfig};
+    use crate::error::Error;
+    use crate::traits::BitcoinPredicate;
+
+    use super::TokenConservation;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 3;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[10, 10, 0];
+    }
+
+    /// Locking script `<amount> OP_DROP`, with an 8-byte amount
+    fn lock_script(amount: u64) -> Script {
+        let mut script = vec![0x08];
+        script.extend(amount.to_le_bytes());
+        script.push(0x75);
+        Script(script)
+    }
+
+    fn test_predicate(input_amount: F, amounts: [u64; 2]) -> bool {
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![
+                TxOut {
+                    satoshis: 1,
+                    lock_script: lock_script(amounts[0]),
+                },
+                TxOut {
+                    satoshis: 1,
+                    lock_script: lock_script(amounts[1]),
+                },
+                TxOut {
+                    satoshis: 500,
+                    lock_script: Script(vec![]),
+                },
+            ],
+            lock_time: 0,
+        };
+
+        let predicate = TokenConservation::<F, Config>::new(vec![0, 1], 1, 8).unwrap();
+        let native = predicate
+            .evaluate_native(
+                &FieldArray::<1, F, Config>::new([input_amount]),
+                &BitcoinUnit::default(),
+                &tx,
+                &BitcoinUnit::default(),
+            )
+            .unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let locking_data = FieldArrayVar::<1, F, Config>::new_input(cs.clone(), || {
+            Ok(FieldArray::<1, F, Config>::new([input_amount]))
+        })
+        .unwrap();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        predicate
+            .enforce_constraints(
+                cs.clone(),
+                &locking_data,
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &BitcoinUnitVar::default(),
+            )
+            .unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), native);
+        native
+    }
+
+    #[test]
+    fn test_token_conservation_is_ok() {
+        assert!(test_predicate(F::from(1000u64), [700, 300]));
+        // The sum does not fit in 64 bits
+        assert!(test_predicate(
+            F::from(u64::MAX) + F::from(1u64),
+            [u64::MAX, 1]
+        ));
+    }
+
+    #[test]
+    fn test_token_conservation_fails() {
+        assert!(!test_predicate(F::from(1000u64), [700, 301]));
+        assert!(!test_predicate(F::from(1000u64), [700, 299]));
+        // The sum does not wrap around 64 bits
+        assert!(!test_predicate(F::from(0u64), [u64::MAX, 1]));
+    }
+
+    #[test]
+    fn test_token_conservation_invalid_config() {
+        // No token outputs
+        assert!(matches!(
+            TokenConservation::<F, Config>::new(vec![], 1, 8),
+            Err(Error::ConfigMismatch(_))
+        ));
+        // Amounts too large for the field
+        assert!(matches!(
+            TokenConservation::<F, Config>::new(vec![0, 1], 1, 32),
+            Err(Error::ConfigMismatch(_))
+        ));
+        // Repeated indices
+        assert!(matches!(
+            TokenConservation::<F, Config>::new(vec![0, 0], 1, 8),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+}
@@ -0,0 +1,326 @@
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{eq::EqGadget, fields::fp::FpVar, prelude::Boolean, uint64::UInt64};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use chain_gang::messages::Tx;
+
+use crate::bitcoin_predicates::data_structures::{
+    field_array::{FieldArray, FieldArrayVar},
+    unit::{BitcoinUnit, BitcoinUnitVar},
+};
+use crate::constraints::tx::{TxVar, TxVarConfig};
+use crate::error::Error;
+use crate::traits::{BitcoinPredicate, SpendingContext};
+use crate::util::{
+    to_fp, to_u64,
+    uint64::{checked_add, checked_sub, is_le},
+};
+
+/// Bitcoin Predicate enforcing that the spending transaction conserves value up to a fee of at most `max_fee`
+/// satoshis: `sum(outputs) <= amount` and `sum(outputs) + max_fee >= amount`, where `amount` is the amount of
+/// the output being spent.
+///
+/// The locking data is `amount`. As the locking data is fixed when the output is locked, nothing would tie it to
+/// the amount actually spent: the predicate binds it to the amount the integrity scheme commits to, see
+/// [SpendingContext::prev_amount], and returns [Error::MissingData] if the integrity scheme does not expose it.
+/// The integrity scheme only commits to the amount of the output spent by one input, so the spending transaction
+/// must have a single input.
+pub struct ValueConservation<F: PrimeField, P: TxVarConfig + Clone> {
+    pub max_fee: u64,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+/// Bitcoin Predicate enforcing that the fee implied by the spending transaction, i.e., the amount of the output
+/// being spent minus the sum of the amounts of the outputs, lies in `[0, max_fee]`. Meant to be AND-combined with
+/// application predicates, together with [DustLimit](crate::bitcoin_predicates::dust_limit::DustLimit).
+pub type FeeLimit<F, P> = ValueConservation<F, P>;
+
+impl<F: PrimeField, P: TxVarConfig + Clone> ValueConservation<F, P> {
+    /// Returns [Error::ConfigMismatch] if the spending transaction does not have a single input
+    pub fn new(max_fee: u64) -> Result<Self, Error> {
+        check_single_input::<P>()?;
+        Ok(Self {
+            max_fee,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        })
+    }
+}
+
+/// Check that the spending transaction has a single input, whose amount is the one committed to by the
+/// integrity scheme
+pub(crate) fn check_single_input<P: TxVarConfig>() -> Result<(), Error> {
+    if P::N_INPUTS != 1 {
+        return Err(Error::ConfigMismatch(format!(
+            "The amount of a single input is committed to by the integrity scheme, but the parameters set {} inputs",
+            P::N_INPUTS
+        )));
+    }
+
+    Ok(())
+}
+
+/// The amount of the output being spent, as committed to by the integrity scheme, together with the [Boolean]
+/// that is `TRUE` if it is equal to `locking_amount`. Returns [Error::MissingData] if the integrity scheme does
+/// not expose it
+pub(crate) fn prev_amount<'a, F: PrimeField>(
+    context: &SpendingContext<'a, F>,
+    locking_amount: &FpVar<F>,
+) -> Result<(&'a UInt64<F>, Boolean<F>), SynthesisError> {
+    let prev_amount = context.prev_amount.ok_or(Error::MissingData(
+        "The amount of the output being spent is not available".to_string(),
+    ))?;
+    // The amount is a 64-bit number, so the locking data is range checked by the equality
+    let is_equal = to_fp(prev_amount)?.is_eq(locking_amount)?;
+
+    Ok((prev_amount, is_equal))
+}
+
+/// The fee paid by a transaction spending `amount` satoshis, together with the [Boolean] that is `TRUE` if
+/// the sum of the amounts of its outputs does not exceed `amount`. If it does, the fee is set to zero. The
+/// constraint system is unsatisfiable if the sum of the amounts of the outputs is not smaller than `2^64`.
+pub(crate) fn fee<F: PrimeField, P: TxVarConfig + Clone>(
+    amount: &UInt64<F>,
+    spending_data: &TxVar<F, P>,
+) -> Result<(Boolean<F>, UInt64<F>), SynthesisError> {
+    let mut total_out = UInt64::<F>::constant(0);
+    for output in spending_data.outputs.iter() {
+        total_out = checked_add(&total_out, &output.satoshis)?;
+    }
+
+    let is_bounded = is_le(&total_out, amount)?;
+    let fee = checked_sub(amount, &is_bounded.select(&total_out, amount)?)?;
+
+    Ok((is_bounded, fee))
+}
+
+/// Native version of [fee]: `None` if the outputs exceed `amount`, or if their sum is not smaller than `2^64`
+pub(crate) fn native_fee(amount: u64, tx: &Tx) -> Option<u64> {
+    tx.outputs
+        .iter()
+        .try_fold(0u64, |total, output| {
+            total.checked_add(output.satoshis as u64)
+        })
+        .and_then(|total_out| amount.checked_sub(total_out))
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for ValueConservation<F, P> {
+    type LockingData = FieldArray<1, F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = FieldArrayVar<1, F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        self.generate_constraints_with_context(
+            cs,
+            locking_data,
+            unlocking_data,
+            spending_data,
+            witness,
+            &SpendingContext::default(),
+        )
+    }
+
+    fn generate_constraints_with_context(
+        &self,
+        _cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+        context: &SpendingContext<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let (amount, is_amount_correct) = prev_amount(context, &locking_data.elements[0])?;
+        let (is_bounded, fee) = fee(amount, spending_data)?;
+
+        Boolean::<F>::kary_and(&[
+            is_amount_correct,
+            is_bounded,
+            is_le(&fee, &UInt64::<F>::constant(self.max_fee))?,
+        ])
+    }
+
+    /// The amount in the locking data is assumed to be the one committed to by the integrity scheme
+    fn evaluate_native(
+        &self,
+        locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        // The amount must fit in 64 bits, as enforced in the circuit
+        let fee = to_u64(&locking_data.elements[0]).and_then(|amount| native_fee(amount, tx));
+
+        Ok(fee.is_some_and(|fee| fee <= self.max_fee))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::{alloc::AllocVar, uint64::UInt64};
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::script::Script;
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::field_array::{FieldArray, FieldArrayVar};
+    use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+    use crate::constraints::tx::{TxVar, TxVarConfig};
+    use crate::error::Error;
+    use crate::traits::{BitcoinPredicate, SpendingContext};
+
+    use super::ValueConservation;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 2;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0, 0];
+    }
+
+    const MAX_FEE: u64 = 50;
+
+    fn tx(output_amounts: [u64; 2]) -> Tx {
+        Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: output_amounts
+                .iter()
+                .map(|amount| TxOut {
+                    satoshis: *amount as i64,
+                    lock_script: Script(vec![]),
+                })
+                .collect(),
+            lock_time: 0,
+        }
+    }
+
+    /// Test the predicate when the locking data is `amount`, and the amount committed to by the integrity scheme
+    /// is `prev_amount`
+    fn test_predicate(amount: F, prev_amount: u64, output_amounts: [u64; 2], expected: bool) {
+        let tx = tx(output_amounts);
+        let locking_data = FieldArray::<1, F, Config>::new([amount]);
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let locking_data_var =
+            FieldArrayVar::<1, F, Config>::new_input(cs.clone(), || Ok(locking_data.clone()))
+                .unwrap();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx.clone())).unwrap();
+        let prev_amount_var = UInt64::<F>::new_witness(cs.clone(), || Ok(prev_amount)).unwrap();
+        let predicate = ValueConservation::<F, Config>::new(MAX_FEE).unwrap();
+        predicate
+            .enforce_constraints_with_context(
+                cs.clone(),
+                &locking_data_var,
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &BitcoinUnitVar::default(),
+                &SpendingContext {
+                    prev_amount: Some(&prev_amount_var),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), expected);
+        // The native evaluation assumes that the locking data is the amount committed to by the integrity scheme
+        if amount == F::from(prev_amount) {
+            assert_eq!(
+                predicate
+                    .evaluate_native(
+                        &locking_data,
+                        &BitcoinUnit::default(),
+                        &tx,
+                        &BitcoinUnit::default()
+                    )
+                    .unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_value_conservation_is_ok() {
+        test_predicate(F::from(300u64), 300, [150, 150], true);
+        test_predicate(F::from(300u64), 300, [150, 100], true);
+        // The sum of the outputs does not fit in 63 bits
+        let max = i64::MAX as u64;
+        test_predicate(F::from(2 * max), 2 * max, [max, max], true);
+    }
+
+    #[test]
+    fn test_value_conservation_fails() {
+        // Fee too high
+        test_predicate(F::from(300u64), 300, [150, 99], false);
+        // Outputs larger than the input
+        test_predicate(F::from(300u64), 300, [150, 151], false);
+        // The locking data is not the amount committed to by the integrity scheme
+        test_predicate(F::from(310u64), 300, [150, 150], false);
+        // The locking data is not a 64-bit number
+        let large = F::from(u64::MAX) * F::from(u64::MAX);
+        test_predicate(large + F::from(300u64), 300, [150, 150], false);
+    }
+
+    #[test]
+    fn test_value_conservation_without_prev_amount() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let locking_data_var = FieldArrayVar::<1, F, Config>::new_input(cs.clone(), || {
+            Ok(FieldArray::<1, F, Config>::new([F::from(300u64)]))
+        })
+        .unwrap();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx([150, 150]))).unwrap();
+        assert!(
+            ValueConservation::<F, Config>::new(MAX_FEE)
+                .unwrap()
+                .generate_constraints(
+                    cs.clone(),
+                    &locking_data_var,
+                    &BitcoinUnitVar::default(),
+                    &tx_var,
+                    &BitcoinUnitVar::default(),
+                )
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_value_conservation_with_several_inputs() {
+        #[derive(Clone)]
+        struct TwoInputsConfig;
+        impl TxVarConfig for TwoInputsConfig {
+            const N_INPUTS: usize = 2;
+            const N_OUTPUTS: usize = 1;
+            const LEN_UNLOCK_SCRIPTS: &[usize] = &[0, 0];
+            const LEN_LOCK_SCRIPTS: &[usize] = &[0];
+        }
+
+        assert!(matches!(
+            ValueConservation::<F, TwoInputsConfig>::new(MAX_FEE),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+}
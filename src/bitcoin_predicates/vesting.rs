@@ -0,0 +1,331 @@
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    eq::EqGadget,
+    fields::{FieldVar, fp::FpVar},
+    prelude::{AllocVar, Boolean},
+    select::CondSelectGadget,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+
+use chain_gang::{messages::Tx, script::Script};
+
+use crate::bitcoin_predicates::data_structures::{
+    field_array::{FieldArray, FieldArrayVar},
+    unit::{BitcoinUnit, BitcoinUnitVar},
+};
+use crate::bitcoin_predicates::lock_time::MinLockTime;
+use crate::constraints::{
+    script::ScriptVar,
+    tx::{TxVar, TxVarConfig},
+};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+use crate::util::{LOCKTIME_THRESHOLD, fits_in_bits, is_less_or_equal, to_fp, to_u64};
+
+/// Bitcoin Predicate enforcing a vesting schedule on a vault holding the amount in the locking data. The epoch is
+/// given by the lock time of the spending transaction, see [MinLockTime]:
+/// - before `first_unlock`, the output at `index` must pay the whole amount back to `vault_script`
+/// - between `first_unlock` and `full_unlock`, the output at `index` must pay back to `vault_script` the amount minus
+///   at most `max_withdrawal` satoshis
+/// - from `full_unlock`, spending is unrestricted
+///
+/// **Note**: As no amount may leave the vault before `first_unlock`, the fee must be paid by another input.
+pub struct Vesting<F: PrimeField, P: TxVarConfig + Clone> {
+    pub vault_script: Script,
+    pub index: usize,
+    pub first_unlock: u32,
+    pub full_unlock: u32,
+    pub max_withdrawal: u64,
+    pub input_index: usize,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> Vesting<F, P> {
+    /// Returns [Error::ConfigMismatch] if the unlock times are not both block heights or both timestamps, or if
+    /// `first_unlock` is later than `full_unlock`
+    pub fn new(
+        vault_script: Script,
+        index: usize,
+        first_unlock: u32,
+        full_unlock: u32,
+        max_withdrawal: u64,
+        input_index: usize,
+    ) -> Result<Self, Error> {
+        if (first_unlock < LOCKTIME_THRESHOLD) != (full_unlock < LOCKTIME_THRESHOLD) {
+            return Err(Error::ConfigMismatch(format!(
+                "The unlock times: {} and {} are not of the same kind",
+                first_unlock, full_unlock
+            )));
+        }
+        if first_unlock > full_unlock {
+            return Err(Error::ConfigMismatch(format!(
+                "The first unlock: {} is later than the full unlock: {}",
+                first_unlock, full_unlock
+            )));
+        }
+        Ok(Self {
+            vault_script,
+            index,
+            first_unlock,
+            full_unlock,
+            max_withdrawal,
+            input_index,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        })
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P> for Vesting<F, P> {
+    type LockingData = FieldArray<1, F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = BitcoinUnit<F, P>;
+
+    type LockingDataVar = FieldArrayVar<1, F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        _witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let output = spending_data.output(self.index)?;
+
+        let is_after = |lock_time: u32| {
+            MinLockTime::<F, P>::new(lock_time, self.input_index).generate_constraints(
+                cs.clone(),
+                &BitcoinUnitVar::default(),
+                &BitcoinUnitVar::default(),
+                spending_data,
+                &BitcoinUnitVar::default(),
+            )
+        };
+
+        // The amount that may leave the vault in the current epoch
+        let allowance = FpVar::<F>::conditionally_select(
+            &is_after(self.first_unlock)?,
+            &FpVar::<F>::constant(F::from(self.max_withdrawal)),
+            &FpVar::<F>::zero(),
+        )?;
+
+        // The amount is a public input, so it must be range checked before being used in comparisons
+        let amount = &locking_data.elements[0];
+        let is_vested = Boolean::<F>::kary_and(&[
+            fits_in_bits(amount, 64)?,
+            output.lock_script.is_eq(&ScriptVar::<F>::new_constant(
+                cs.clone(),
+                self.vault_script.clone(),
+            )?)?,
+            is_less_or_equal(amount, &(to_fp(&output.satoshis)? + allowance), 65)?,
+        ])?;
+
+        Boolean::<F>::kary_or(&[is_after(self.full_unlock)?, is_vested])
+    }
+
+    fn evaluate_native(
+        &self,
+        locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        // Validate input
+        if self.index >= tx.outputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of outputs: {}",
+                self.index,
+                tx.outputs.len()
+            )));
+        }
+        let output = &tx.outputs[self.index];
+
+        let is_after = |lock_time: u32| {
+            MinLockTime::<F, P>::new(lock_time, self.input_index).evaluate_native(
+                &BitcoinUnit::default(),
+                &BitcoinUnit::default(),
+                tx,
+                &BitcoinUnit::default(),
+            )
+        };
+        if is_after(self.full_unlock)? {
+            return Ok(true);
+        }
+
+        // The amount that may leave the vault in the current epoch
+        let allowance = match is_after(self.first_unlock)? {
+            true => self.max_withdrawal,
+            false => 0,
+        };
+
+        let Some(amount) = to_u64(&locking_data.elements[0]) else {
+            return Ok(false);
+        };
+        Ok(output.lock_script.0 == self.vault_script.0
+            && amount as u128 <= output.satoshis as u64 as u128 + allowance as u128)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::script::Script;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::util::{Hash160, Hash256};
+
+    use crate::bitcoin_predicates::data_structures::{
+        field_array::{FieldArray, FieldArrayVar},
+        unit::{BitcoinUnit, BitcoinUnitVar},
+    };
+    use crate::constraints::tx::{TxVar, TxVarConfig};
+    use crate::error::Error;
+    use crate::traits::BitcoinPredicate;
+    use crate::util::LOCKTIME_THRESHOLD;
+
+    use super::Vesting;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    const AMOUNT: u64 = 10_000;
+    const MAX_WITHDRAWAL: u64 = 1_000;
+    const FIRST_UNLOCK: u32 = 100;
+    const FULL_UNLOCK: u32 = 200;
+
+    fn vault_script() -> Script {
+        p2pkh::create_lock_script(&Hash160([1; 20]))
+    }
+
+    fn other_script() -> Script {
+        p2pkh::create_lock_script(&Hash160([2; 20]))
+    }
+
+    fn test_predicate(lock_time: u32, sequence: u32, satoshis: u64, lock_script: Script) -> bool {
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence,
+            }],
+            outputs: vec![TxOut {
+                satoshis: satoshis as i64,
+                lock_script,
+            }],
+            lock_time,
+        };
+
+        let predicate = Vesting::<F, Config>::new(
+            vault_script(),
+            0,
+            FIRST_UNLOCK,
+            FULL_UNLOCK,
+            MAX_WITHDRAWAL,
+            0,
+        )
+        .unwrap();
+        let native = predicate
+            .evaluate_native(
+                &FieldArray::<1, F, Config>::new([F::from(AMOUNT)]),
+                &BitcoinUnit::default(),
+                &tx,
+                &BitcoinUnit::default(),
+            )
+            .unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let locking_data = FieldArrayVar::<1, F, Config>::new_input(cs.clone(), || {
+            Ok(FieldArray::<1, F, Config>::new([F::from(AMOUNT)]))
+        })
+        .unwrap();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        predicate
+            .enforce_constraints(
+                cs.clone(),
+                &locking_data,
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &BitcoinUnitVar::default(),
+            )
+            .unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), native);
+        native
+    }
+
+    #[test]
+    fn test_vesting_is_ok() {
+        // Before the first unlock
+        assert!(test_predicate(50, 0, AMOUNT, vault_script()));
+        // Between the first and the full unlock
+        assert!(test_predicate(
+            150,
+            0,
+            AMOUNT - MAX_WITHDRAWAL,
+            vault_script()
+        ));
+        // After the full unlock
+        assert!(test_predicate(250, 0, 1, other_script()));
+    }
+
+    #[test]
+    fn test_vesting_fails() {
+        // Before the first unlock
+        assert!(!test_predicate(50, 0, AMOUNT - 1, vault_script()));
+        assert!(!test_predicate(50, 0, AMOUNT, other_script()));
+        // Between the first and the full unlock
+        assert!(!test_predicate(
+            150,
+            0,
+            AMOUNT - MAX_WITHDRAWAL - 1,
+            vault_script()
+        ));
+        // The lock time is not enforced by the network, as the input is final
+        assert!(!test_predicate(250, 0xffffffff, 1, other_script()));
+    }
+
+    #[test]
+    fn test_invalid_schedule() {
+        let vesting = |first_unlock: u32, full_unlock: u32| {
+            Vesting::<F, Config>::new(
+                vault_script(),
+                0,
+                first_unlock,
+                full_unlock,
+                MAX_WITHDRAWAL,
+                0,
+            )
+        };
+
+        // A block height and a timestamp
+        assert!(matches!(
+            vesting(FIRST_UNLOCK, LOCKTIME_THRESHOLD),
+            Err(Error::ConfigMismatch(_))
+        ));
+        // The first unlock is later than the full unlock
+        assert!(matches!(
+            vesting(FULL_UNLOCK, FIRST_UNLOCK),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+}
@@ -0,0 +1,301 @@
+//! Whitelists of destinations
+//!
+//! [Whitelist] enforces that an output of the spending transaction pays to one of a set of allowed locking
+//! scripts, committed to in the locking data as the root of a Poseidon Merkle tree of depth `DEPTH`. The leaves are
+//! the hashes of the allowed scripts (see [leaf_hash]), padded with zeros, and the witness is the Merkle path of the
+//! script of the output.
+use std::marker::PhantomData;
+
+use ark_crypto_primitives::sponge::{
+    CryptographicSponge,
+    constraints::CryptographicSpongeVar,
+    poseidon::{PoseidonConfig, PoseidonSponge, constraints::PoseidonSpongeVar},
+};
+use ark_ff::{PrimeField, ToConstraintField};
+use ark_r1cs_std::{convert::ToConstraintFieldGadget, fields::fp::FpVar, prelude::Boolean};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use chain_gang::{messages::Tx, script::Script};
+
+use crate::bitcoin_predicates::data_structures::{
+    field_array::{FieldArray, FieldArrayVar},
+    merkle_path::{MerklePath, MerklePathVar, node_hash},
+    unit::{BitcoinUnit, BitcoinUnitVar},
+};
+use crate::constraints::tx::{TxVar, TxVarConfig};
+use crate::error::Error;
+use crate::traits::BitcoinPredicate;
+
+/// Domain separator of [leaf_hash]
+const LEAF_DOMAIN: u64 = 0;
+
+/// The leaf of the Merkle tree for `script`
+pub fn leaf_hash<F: PrimeField>(script: &Script, poseidon_config: &PoseidonConfig<F>) -> F {
+    let mut elements: Vec<F> = vec![F::from(LEAF_DOMAIN)];
+    elements.extend(script.0.to_field_elements().unwrap());
+
+    let mut sponge = PoseidonSponge::<F>::new(poseidon_config);
+    sponge.absorb(&elements);
+    sponge.squeeze_field_elements::<F>(1)[0]
+}
+
+/// The Merkle tree of depth `DEPTH` of the whitelist `scripts`
+pub struct WhitelistTree<const DEPTH: usize, F: PrimeField> {
+    /// The layers of the tree, from the leaves to the root
+    layers: Vec<Vec<F>>,
+}
+
+impl<const DEPTH: usize, F: PrimeField> WhitelistTree<DEPTH, F> {
+    /// Returns [Error::ConfigMismatch] if there are more scripts than leaves
+    pub fn new(scripts: &[Script], poseidon_config: &PoseidonConfig<F>) -> Result<Self, Error> {
+        if scripts.len() > 1 << DEPTH {
+            return Err(Error::ConfigMismatch(format!(
+                "The number of scripts: {} is larger than the number of leaves: {}",
+                scripts.len(),
+                1 << DEPTH
+            )));
+        }
+        let mut leaves: Vec<F> = scripts
+            .iter()
+            .map(|script| leaf_hash(script, poseidon_config))
+            .collect();
+        leaves.resize(1 << DEPTH, F::zero());
+
+        let mut layers: Vec<Vec<F>> = vec![leaves];
+        for _ in 0..DEPTH {
+            let layer: Vec<F> = layers
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| node_hash(pair[0], pair[1], poseidon_config))
+                .collect();
+            layers.push(layer);
+        }
+
+        Ok(Self { layers })
+    }
+
+    /// The root of the tree, to be set in the locking data of [Whitelist]
+    pub fn root(&self) -> F {
+        self.layers[DEPTH][0]
+    }
+
+    /// The Merkle path of the script at `index`
+    ///
+    /// Returns [Error::IndexOutOfBounds] if `index` is not smaller than the number of leaves
+    pub fn path<P: TxVarConfig + Clone>(
+        &self,
+        index: usize,
+    ) -> Result<MerklePath<DEPTH, F, P>, Error> {
+        if index >= 1 << DEPTH {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of leaves: {}",
+                index,
+                1 << DEPTH
+            )));
+        }
+        MerklePath::new(
+            index,
+            std::array::from_fn(|level| self.layers[level][(index >> level) ^ 1]),
+        )
+    }
+}
+
+/// Bitcoin Predicate enforcing that the locking script of the output of the spending transaction at `index` is
+/// in the whitelist whose Merkle root is the locking data, see [WhitelistTree]
+pub struct Whitelist<const DEPTH: usize, F: PrimeField, P: TxVarConfig + Clone> {
+    pub index: usize,
+    pub poseidon_config: PoseidonConfig<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<const DEPTH: usize, F: PrimeField, P: TxVarConfig + Clone> Whitelist<DEPTH, F, P> {
+    pub fn new(index: usize, poseidon_config: PoseidonConfig<F>) -> Self {
+        Self {
+            index,
+            poseidon_config,
+            _phantom_config: PhantomData,
+        }
+    }
+}
+
+impl<const DEPTH: usize, F: PrimeField, P: TxVarConfig + Clone> BitcoinPredicate<F, P>
+    for Whitelist<DEPTH, F, P>
+{
+    type LockingData = FieldArray<1, F, P>;
+    type UnlockingData = BitcoinUnit<F, P>;
+    type Witness = MerklePath<DEPTH, F, P>;
+
+    type LockingDataVar = FieldArrayVar<1, F, P>;
+    type UnlockingDataVar = BitcoinUnitVar<F, P>;
+    type WitnessVar = MerklePathVar<DEPTH, F, P>;
+
+    fn generate_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        _unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let output = spending_data.output(self.index)?;
+
+        // Compute the leaf, as in [leaf_hash]
+        let mut sponge = PoseidonSpongeVar::<F>::new(cs.clone(), &self.poseidon_config);
+        sponge.absorb(&FpVar::<F>::constant(F::from(LEAF_DOMAIN)))?;
+        sponge.absorb(&output.lock_script.0.to_constraint_field()?)?;
+        let leaf: FpVar<F> = sponge.squeeze_field_elements(1)?[0].clone();
+
+        witness.verify(cs, &leaf, &locking_data.elements[0], &self.poseidon_config)
+    }
+
+    fn evaluate_native(
+        &self,
+        locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        tx: &Tx,
+        witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        // Validate input
+        if self.index >= tx.outputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of outputs: {}",
+                self.index,
+                tx.outputs.len()
+            )));
+        }
+
+        let leaf = leaf_hash(&tx.outputs[self.index].lock_script, &self.poseidon_config);
+        Ok(witness.verify(leaf, locking_data.elements[0], &self.poseidon_config))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::script::Script;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::util::{Hash160, Hash256};
+
+    use crate::bitcoin_predicates::data_structures::{
+        field_array::{FieldArray, FieldArrayVar},
+        merkle_path::MerklePathVar,
+        unit::{BitcoinUnit, BitcoinUnitVar},
+    };
+    use crate::constraints::tx::{TxVar, TxVarConfig};
+    use crate::error::Error;
+    use crate::traits::BitcoinPredicate;
+    use crate::transaction_integrity_gadget::poseidon::poseidon_config;
+
+    use super::{Whitelist, WhitelistTree};
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    const N_ALLOWED: u8 = 3;
+
+    /// P2PKH locking script to the public key hash `[n; 20]`
+    fn p2pkh_script(n: u8) -> Script {
+        p2pkh::create_lock_script(&Hash160([n; 20]))
+    }
+
+    /// Pay to `p2pkh_script(n)`, and prove that it is the script at `path_index` of the whitelist
+    fn test_predicate(n: u8, path_index: usize) -> bool {
+        let config = poseidon_config::<F>();
+        let scripts: Vec<Script> = (0..N_ALLOWED).map(p2pkh_script).collect();
+        let tree = WhitelistTree::<2, F>::new(&scripts, &config).unwrap();
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 900,
+                lock_script: p2pkh_script(n),
+            }],
+            lock_time: 0,
+        };
+
+        let predicate = Whitelist::<2, F, Config>::new(0, config);
+        let native = predicate
+            .evaluate_native(
+                &FieldArray::<1, F, Config>::new([tree.root()]),
+                &BitcoinUnit::default(),
+                &tx,
+                &tree.path::<Config>(path_index).unwrap(),
+            )
+            .unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let locking_data = FieldArrayVar::<1, F, Config>::new_input(cs.clone(), || {
+            Ok(FieldArray::<1, F, Config>::new([tree.root()]))
+        })
+        .unwrap();
+        let witness = MerklePathVar::<2, F, Config>::new_witness(cs.clone(), || {
+            Ok(tree.path::<Config>(path_index).unwrap())
+        })
+        .unwrap();
+        let tx_var = TxVar::<F, Config>::new_witness(cs.clone(), || Ok(tx)).unwrap();
+        predicate
+            .enforce_constraints(
+                cs.clone(),
+                &locking_data,
+                &BitcoinUnitVar::default(),
+                &tx_var,
+                &witness,
+            )
+            .unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), native);
+        native
+    }
+
+    #[test]
+    fn test_whitelist_is_ok() {
+        for n in 0..N_ALLOWED {
+            assert!(test_predicate(n, n as usize));
+        }
+    }
+
+    #[test]
+    fn test_whitelist_fails() {
+        // Wrong path
+        assert!(!test_predicate(0, 1));
+        // Destination not in the whitelist
+        assert!(!test_predicate(N_ALLOWED, N_ALLOWED as usize));
+    }
+
+    #[test]
+    fn test_whitelist_tree_too_many_scripts() {
+        let scripts: Vec<Script> = (0..5).map(p2pkh_script).collect();
+        assert!(matches!(
+            WhitelistTree::<2, F>::new(&scripts, &poseidon_config::<F>()),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_whitelist_path_out_of_bounds() {
+        let scripts: Vec<Script> = (0..N_ALLOWED).map(p2pkh_script).collect();
+        let tree = WhitelistTree::<2, F>::new(&scripts, &poseidon_config::<F>()).unwrap();
+        assert!(matches!(
+            tree.path::<Config>(4),
+            Err(Error::IndexOutOfBounds(_))
+        ));
+    }
+}
@@ -0,0 +1,149 @@
+//! Extraction of data envelopes embedded in unlocking scripts
+//!
+//! An envelope is the data pushed by one of the opcodes of a push-only unlocking script, e.g., a proof or some metadata
+//! about a sibling input. The gadget only extracts the bytes: their interpretation and verification is left to the caller.
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::prelude::Boolean;
+use ark_relations::r1cs::SynthesisError;
+
+use crate::bitcoin_predicates::data_structures::byte_array::ByteArrayVar;
+use crate::constraints::{tx::TxVarConfig, txin::TxInVar};
+use crate::error::Error;
+
+/// Gadget extracting an envelope of `N` bytes from the unlocking script of an input
+pub struct EnvelopeGadget<const N: usize, F: PrimeField, P: TxVarConfig + Clone> {
+    _field: PhantomData<F>,
+    _config: PhantomData<P>,
+}
+
+impl<const N: usize, F: PrimeField, P: TxVarConfig + Clone> EnvelopeGadget<N, F, P> {
+    /// Extract the data pushed by the push at `index` in the unlocking script of `txin`.
+    ///
+    /// The function returns a [Boolean] which is `TRUE` if the unlocking script has the push layout `push_lengths`
+    /// (see [ScriptVar::has_push_layout](crate::constraints::script::ScriptVar::has_push_layout)), and the envelope.
+    /// The envelope is only meaningful if the [Boolean] is `TRUE`.
+    ///
+    /// Returns [Error::IndexOutOfBounds] if `index` is out of range, and [Error::ConfigMismatch] if `push_lengths[index]`
+    /// is different from `N`.
+    pub fn extract(
+        txin: &TxInVar<F>,
+        push_lengths: &[usize],
+        index: usize,
+    ) -> Result<(Boolean<F>, ByteArrayVar<N, F, P>), SynthesisError> {
+        if index >= push_lengths.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of pushes: {}",
+                index,
+                push_lengths.len()
+            ))
+            .into());
+        }
+        if push_lengths[index] != N {
+            return Err(Error::ConfigMismatch(format!(
+                "The length of the push at index {}: {} is different from the length of the envelope: {}",
+                index, push_lengths[index], N
+            ))
+            .into());
+        }
+
+        let is_push_layout = txin.unlock_script.has_push_layout(push_lengths)?;
+        let envelope = txin
+            .unlock_script
+            .split_pushes(push_lengths)?
+            .swap_remove(index);
+
+        Ok((
+            is_push_layout,
+            ByteArrayVar::<N, F, P>::try_from(envelope.as_slice())?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::{R1CSVar, alloc::AllocVar};
+    use ark_relations::r1cs::{ConstraintSystem, SynthesisError};
+    use chain_gang::messages::{OutPoint, TxIn};
+    use chain_gang::script::Script;
+    use chain_gang::util::Hash256;
+
+    use crate::constraints::{tx::TxVarConfig, txin::TxInVar};
+    use crate::util::parse_push_lengths;
+
+    use super::EnvelopeGadget;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 0;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[116];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[];
+    }
+
+    fn txin(unlock_script: Script) -> TxIn {
+        TxIn {
+            prev_output: OutPoint {
+                hash: Hash256::decode(
+                    "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                )
+                .unwrap(),
+                index: 0,
+            },
+            unlock_script,
+            sequence: 0xffffffff,
+        }
+    }
+
+    #[test]
+    fn test_extract() {
+        let envelope: Vec<u8> = (0..40).collect();
+        let mut unlock_script = Script::new();
+        unlock_script.append_data(&[0xaa; 73]);
+        unlock_script.append_data(&envelope);
+        let push_lengths = parse_push_lengths(&unlock_script.0).unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let txin_var = TxInVar::<F>::new_witness(cs.clone(), || Ok(txin(unlock_script))).unwrap();
+        let (is_push_layout, envelope_var) =
+            EnvelopeGadget::<40, F, Config>::extract(&txin_var, &push_lengths, 1).unwrap();
+
+        assert!(is_push_layout.value().unwrap());
+        assert_eq!(envelope_var.bytes.value().unwrap(), envelope);
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_extract_wrong_layout() {
+        // The script has the length of the expected layout, but it is not a sequence of pushes
+        let unlock_script = Script(vec![0xaa; 116]);
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let txin_var = TxInVar::<F>::new_witness(cs.clone(), || Ok(txin(unlock_script))).unwrap();
+        let (is_push_layout, _) =
+            EnvelopeGadget::<40, F, Config>::extract(&txin_var, &[73, 40], 1).unwrap();
+
+        assert!(!is_push_layout.value().unwrap());
+    }
+
+    #[test]
+    fn test_extract_invalid_config() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let txin_var =
+            TxInVar::<F>::new_witness(cs.clone(), || Ok(txin(Script(vec![0xaa; 116])))).unwrap();
+
+        // Index out of range
+        assert!(matches!(
+            EnvelopeGadget::<40, F, Config>::extract(&txin_var, &[73, 40], 2),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+        // Wrong length of the envelope
+        assert!(matches!(
+            EnvelopeGadget::<39, F, Config>::extract(&txin_var, &[73, 40], 1),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+}
@@ -0,0 +1,18 @@
+//! R1CS implemenation of Ripemd160 after Sha256
+use std::marker::PhantomData;
+
+use ark_crypto_primitives::crh::sha256::constraints::Sha256Gadget;
+use ark_ff::PrimeField;
+use ark_r1cs_std::uint8::UInt8;
+use ark_relations::r1cs::Result;
+
+use crate::constraints::ripemd160::Ripemd160Gadget;
+
+/// Gadget for calculating Ripemd160 of Sha256
+pub struct Hash160Gadget<F: PrimeField>(PhantomData<F>);
+
+impl<F: PrimeField> Hash160Gadget<F> {
+    pub fn evaluate(data: &[UInt8<F>]) -> Result<Vec<UInt8<F>>> {
+        Ripemd160Gadget::evaluate(Sha256Gadget::digest(data)?.0.as_slice())
+    }
+}
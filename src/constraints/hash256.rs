@@ -7,6 +7,8 @@ use ark_ff::PrimeField;
 use ark_r1cs_std::uint8::UInt8;
 use ark_relations::r1cs::Result;
 
+use crate::constraints::{sha256::VarLenSha256Gadget, var_bytes::VarBytesVar};
+
 /// Gadget for calculating two rounds of Sha256
 pub struct Hash256Gadget<F: PrimeField>(PhantomData<F>);
 
@@ -14,4 +16,9 @@ impl<F: PrimeField> Hash256Gadget<F> {
     pub fn evaluate(data: &[UInt8<F>]) -> Result<DigestVar<F>> {
         Sha256Gadget::digest(Sha256Gadget::digest(data)?.0.as_slice())
     }
+
+    /// Calculate two rounds of Sha256 of data of variable length, see [VarLenSha256Gadget]
+    pub fn evaluate_var_len(data: &VarBytesVar<F>) -> Result<DigestVar<F>> {
+        Sha256Gadget::digest(VarLenSha256Gadget::evaluate(data)?.0.as_slice())
+    }
 }
@@ -0,0 +1,677 @@
+//! In-circuit interpreter for a subset of Bitcoin Script
+//!
+//! The locking script is fixed in the circuit, so it is executed symbolically: the sequence of opcodes,
+//! and hence the shape of the stack, is known when generating the constraints, while the values on the stack
+//! are R1CS variables derived from the unlocking script.
+use std::io::{Cursor, Read};
+use std::marker::PhantomData;
+
+use ark_crypto_primitives::crh::sha256::constraints::Sha256Gadget;
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    R1CSVar,
+    alloc::AllocVar,
+    eq::EqGadget,
+    fields::{FieldVar, fp::FpVar},
+    prelude::{Boolean, ToBitsGadget},
+    select::CondSelectGadget,
+    uint8::UInt8,
+};
+use ark_relations::r1cs::SynthesisError;
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use chain_gang::script::Script;
+use chain_gang::script::op_codes::*;
+
+use crate::constraints::{hash256::Hash256Gadget, script::ScriptVar, var_bytes::VarBytesVar};
+use crate::error::Error;
+use crate::util::{is_less_than, to_fp, to_u64};
+
+/// Maximum length in bytes of the operands of arithmetic opcodes
+pub const MAX_NUM_LENGTH: usize = 4;
+
+/// Opcodes supported by [InterpreterGadget], on top of push opcodes
+pub const SUPPORTED_OPCODES: &[u8] = &[
+    OP_NOP,
+    OP_VERIFY,
+    OP_DROP,
+    OP_DUP,
+    OP_NIP,
+    OP_OVER,
+    OP_SWAP,
+    OP_SIZE,
+    OP_EQUAL,
+    OP_EQUALVERIFY,
+    OP_1ADD,
+    OP_1SUB,
+    OP_NEGATE,
+    OP_ADD,
+    OP_SUB,
+    OP_NUMEQUAL,
+    OP_NUMEQUALVERIFY,
+    OP_LESSTHAN,
+    OP_GREATERTHAN,
+    OP_SHA256,
+    OP_HASH256,
+];
+
+/// An element of the stack
+#[derive(Clone)]
+enum StackItem<F: PrimeField> {
+    /// A byte string, e.g., pushed data or the output of a hash function
+    Bytes(Vec<UInt8<F>>),
+    /// A number, i.e., the output of an arithmetic or boolean opcode
+    Num(FpVar<F>),
+}
+
+/// An instruction of the locking script
+enum Instruction {
+    Push(Vec<u8>),
+    Op(u8),
+}
+
+/// Gadget executing a locking script of at most `MAX_SCRIPT_SIZE` bytes on a stack of at most `MAX_STACK_SIZE` elements.
+///
+/// Arithmetic opcodes decode their operands (of at most [MAX_NUM_LENGTH] bytes) as Bitcoin numbers, and their
+/// results are kept as field elements. Opcodes acting on byte strings (e.g., [OP_EQUAL], [OP_SHA256] or [OP_SIZE])
+/// re-encode such results as minimally encoded numbers, whose length is a variable of the circuit.
+pub struct InterpreterGadget<
+    F: PrimeField,
+    const MAX_SCRIPT_SIZE: usize,
+    const MAX_STACK_SIZE: usize,
+> {
+    _field: PhantomData<F>,
+}
+
+impl<F: PrimeField, const MAX_SCRIPT_SIZE: usize, const MAX_STACK_SIZE: usize>
+    InterpreterGadget<F, MAX_SCRIPT_SIZE, MAX_STACK_SIZE>
+{
+    /// Return a [Boolean] which is `TRUE` if `unlock_script` has the push layout `push_lengths` (see [ScriptVar::has_push_layout])
+    /// and executing `lock_script` on the stack built by `unlock_script` succeeds, i.e., all the `VERIFY` opcodes
+    /// succeed and the top element of the final stack is truthy.
+    ///
+    /// The function returns an error if `lock_script` is malformed, longer than `MAX_SCRIPT_SIZE`, or contains an
+    /// opcode which is not in [SUPPORTED_OPCODES].
+    pub fn verify(
+        unlock_script: &ScriptVar<F>,
+        push_lengths: &[usize],
+        lock_script: &Script,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        if lock_script.0.len() > MAX_SCRIPT_SIZE {
+            return Err(Error::ConfigMismatch(format!(
+                "The length of the locking script: {} is larger than the maximum script size: {}",
+                lock_script.0.len(),
+                MAX_SCRIPT_SIZE
+            ))
+            .into());
+        }
+
+        let is_push_only = unlock_script.has_push_layout(push_lengths)?;
+        if let Boolean::Constant(false) = is_push_only {
+            return Ok(Boolean::<F>::FALSE);
+        }
+        let stack: Vec<StackItem<F>> = unlock_script
+            .split_pushes(push_lengths)?
+            .into_iter()
+            .map(StackItem::Bytes)
+            .collect();
+
+        match Self::execute(stack, &parse_script(&lock_script.0)?)? {
+            Some(mut checks) => {
+                checks.push(is_push_only);
+                Boolean::<F>::kary_and(&checks)
+            }
+            None => Ok(Boolean::<F>::FALSE),
+        }
+    }
+
+    /// Execute `instructions` on `stack`. The function returns the list of booleans which must be `TRUE` for the
+    /// execution to succeed, or `None` if the execution fails independently of the values on the stack.
+    fn execute(
+        mut stack: Vec<StackItem<F>>,
+        instructions: &[Instruction],
+    ) -> Result<Option<Vec<Boolean<F>>>, SynthesisError> {
+        let mut checks: Vec<Boolean<F>> = Vec::new();
+
+        for instruction in instructions.iter() {
+            if stack.len() > MAX_STACK_SIZE {
+                return Ok(None);
+            }
+
+            let opcode = match instruction {
+                Instruction::Push(data) => {
+                    stack.push(StackItem::Bytes(UInt8::<F>::constant_vec(data)));
+                    continue;
+                }
+                Instruction::Op(opcode) => *opcode,
+            };
+
+            // Number of elements consumed by the opcode
+            let n_args: usize = match opcode {
+                OP_NOP => 0,
+                OP_DUP | OP_SIZE | OP_VERIFY | OP_DROP | OP_1ADD | OP_1SUB | OP_NEGATE
+                | OP_SHA256 | OP_HASH256 => 1,
+                _ => 2,
+            };
+            if stack.len() < n_args {
+                return Ok(None);
+            }
+
+            match opcode {
+                OP_NOP => (),
+                OP_VERIFY => {
+                    let item = stack.pop().unwrap();
+                    checks.push(is_truthy(&item)?);
+                }
+                OP_DROP => {
+                    stack.pop();
+                }
+                OP_DUP => {
+                    let item = stack[stack.len() - 1].clone();
+                    stack.push(item);
+                }
+                OP_NIP => {
+                    stack.remove(stack.len() - 2);
+                }
+                OP_OVER => {
+                    let item = stack[stack.len() - 2].clone();
+                    stack.push(item);
+                }
+                OP_SWAP => {
+                    let n = stack.len();
+                    stack.swap(n - 1, n - 2);
+                }
+                OP_SIZE => {
+                    let size = bytes(&stack[stack.len() - 1])?.len;
+                    stack.push(StackItem::Num(size));
+                }
+                OP_EQUAL | OP_EQUALVERIFY => {
+                    let b = stack.pop().unwrap();
+                    let a = stack.pop().unwrap();
+                    let is_equal = is_equal(&bytes(&a)?, &bytes(&b)?)?;
+                    if opcode == OP_EQUALVERIFY {
+                        checks.push(is_equal);
+                    } else {
+                        stack.push(StackItem::Num(FpVar::<F>::from(is_equal)));
+                    }
+                }
+                OP_1ADD | OP_1SUB | OP_NEGATE => {
+                    let a = to_num(&stack.pop().unwrap(), &mut checks)?;
+                    let result = match opcode {
+                        OP_1ADD => a + F::one(),
+                        OP_1SUB => a - F::one(),
+                        _ => a.negate()?,
+                    };
+                    stack.push(StackItem::Num(result));
+                }
+                OP_ADD | OP_SUB | OP_NUMEQUAL | OP_NUMEQUALVERIFY | OP_LESSTHAN
+                | OP_GREATERTHAN => {
+                    let b = to_num(&stack.pop().unwrap(), &mut checks)?;
+                    let a = to_num(&stack.pop().unwrap(), &mut checks)?;
+                    let result = match opcode {
+                        OP_ADD => a + b,
+                        OP_SUB => a - b,
+                        OP_NUMEQUAL => FpVar::<F>::from(a.is_eq(&b)?),
+                        OP_NUMEQUALVERIFY => {
+                            checks.push(a.is_eq(&b)?);
+                            continue;
+                        }
+                        OP_LESSTHAN => FpVar::<F>::from(is_less_than_signed(&a, &b)?),
+                        _ => FpVar::<F>::from(is_less_than_signed(&b, &a)?),
+                    };
+                    stack.push(StackItem::Num(result));
+                }
+                OP_SHA256 => {
+                    let a = stack.pop().unwrap();
+                    stack.push(StackItem::Bytes(digest(&bytes(&a)?, |data| {
+                        Ok(Sha256Gadget::<F>::digest(data)?.0)
+                    })?));
+                }
+                OP_HASH256 => {
+                    let a = stack.pop().unwrap();
+                    stack.push(StackItem::Bytes(digest(&bytes(&a)?, |data| {
+                        Ok(Hash256Gadget::<F>::evaluate(data)?.0)
+                    })?));
+                }
+                _ => {
+                    return Err(Error::NotImplemented(format!(
+                        "The opcode: {:#04x} is not supported",
+                        opcode
+                    ))
+                    .into());
+                }
+            }
+        }
+
+        match stack.last() {
+            Some(item) if stack.len() <= MAX_STACK_SIZE => {
+                checks.push(is_truthy(item)?);
+                Ok(Some(checks))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Parse a script into its instructions. The function returns an error if a push is malformed or exceeds the
+/// length of the script, or if the script contains an opcode which is not in [SUPPORTED_OPCODES].
+fn parse_script(script: &[u8]) -> Result<Vec<Instruction>, Error> {
+    let mut cursor = Cursor::new(script);
+    let mut instructions: Vec<Instruction> = Vec::new();
+    while let Ok(opcode) = cursor.read_u8() {
+        let length = match opcode {
+            OP_PUSHDATA1 => cursor.read_u8().map(|l| l as usize),
+            OP_PUSHDATA2 => cursor.read_u16::<LittleEndian>().map(|l| l as usize),
+            OP_PUSHDATA4 => cursor.read_u32::<LittleEndian>().map(|l| l as usize),
+            n if n < OP_PUSHDATA1 => Ok(n as usize),
+            OP_1NEGATE => {
+                instructions.push(Instruction::Push(vec![0x81]));
+                continue;
+            }
+            n if (OP_1..=OP_16).contains(&n) => {
+                instructions.push(Instruction::Push(vec![n - OP_1 + 1]));
+                continue;
+            }
+            n if SUPPORTED_OPCODES.contains(&n) => {
+                instructions.push(Instruction::Op(n));
+                continue;
+            }
+            n => {
+                return Err(Error::NotImplemented(format!(
+                    "The opcode: {:#04x} is not supported",
+                    n
+                )));
+            }
+        }
+        .map_err(|_| Error::ConfigMismatch("Malformed push in the locking script".to_string()))?;
+        let mut data: Vec<u8> = vec![0; length];
+        cursor.read_exact(&mut data).map_err(|_| {
+            Error::ConfigMismatch("Push exceeds the length of the locking script".to_string())
+        })?;
+        instructions.push(Instruction::Push(data));
+    }
+
+    Ok(instructions)
+}
+
+/// Convert a field element into the integer it represents, if it lies in `(-2^63, 2^63)`
+fn to_i64<F: PrimeField>(value: &F) -> Option<i64> {
+    match to_u64(value) {
+        Some(value) => i64::try_from(value).ok(),
+        None => to_u64(&-*value)
+            .and_then(|value| i64::try_from(value).ok())
+            .map(|value| -value),
+    }
+}
+
+/// Minimal encoding of `value` as a Bitcoin number: sign-magnitude little endian, without trailing zero bytes
+fn encode_num(value: i64) -> Vec<u8> {
+    let mut magnitude = value.unsigned_abs();
+    let mut bytes: Vec<u8> = Vec::new();
+    while magnitude > 0 {
+        bytes.push(magnitude as u8);
+        magnitude >>= 8;
+    }
+    let sign: u8 = if value < 0 { 0x80 } else { 0 };
+    match bytes.last_mut() {
+        Some(last) if *last & 0x80 == 0 => *last |= sign,
+        Some(_) => bytes.push(sign),
+        None => (),
+    }
+    bytes
+}
+
+/// Compute the minimal encoding of a number which passed the range check of [to_num], i.e., lying in `(-2^32, 2^32)`.
+///
+/// The magnitude is allocated as [MAX_NUM_LENGTH] bytes, and the sign bit is set in its last non-zero byte, or in an
+/// extra byte if the last non-zero byte has its most significant bit set. The result has length at most
+/// `MAX_NUM_LENGTH + 1`, and zero is encoded as the empty string.
+fn num_to_bytes<F: PrimeField>(num: &FpVar<F>) -> Result<VarBytesVar<F>, SynthesisError> {
+    if let FpVar::Constant(value) = num {
+        let value = to_i64(value).ok_or(SynthesisError::Unsatisfiable)?;
+        return Ok(VarBytesVar::<F>::constant(&encode_num(value)));
+    }
+
+    let cs = num.cs();
+    let native_num =
+        || -> Result<i64, SynthesisError> { Ok(to_i64(&num.value()?).unwrap_or_default()) };
+    let is_negative = Boolean::<F>::new_witness(cs.clone(), || Ok(native_num()? < 0))?;
+    let magnitude = (0..MAX_NUM_LENGTH)
+        .map(|i| {
+            UInt8::<F>::new_witness(cs.clone(), || {
+                Ok((native_num()?.unsigned_abs() >> (8 * i)) as u8)
+            })
+        })
+        .collect::<Result<Vec<UInt8<F>>, SynthesisError>>()?;
+
+    // num = magnitude if the number is positive, -magnitude otherwise
+    let mut magnitude_fp = FpVar::<F>::zero();
+    for byte in magnitude.iter().rev() {
+        magnitude_fp = magnitude_fp * F::from(256u64) + to_fp(byte)?;
+    }
+    (&magnitude_fp - FpVar::<F>::from(is_negative.clone()) * magnitude_fp.double()?)
+        .enforce_equal(num)?;
+
+    // is_zero_from[k] is TRUE if and only if the bytes of the magnitude from index `k` are zero
+    let mut is_zero_from: Vec<Boolean<F>> = vec![Boolean::<F>::TRUE; MAX_NUM_LENGTH + 1];
+    for k in (0..MAX_NUM_LENGTH).rev() {
+        is_zero_from[k] = &is_zero_from[k + 1] & &magnitude[k].is_eq(&UInt8::<F>::constant(0))?;
+    }
+    // Zero is not negative
+    (&is_negative & &is_zero_from[0]).enforce_equal(&Boolean::<F>::FALSE)?;
+
+    // The magnitude has `k` significant bytes if and only if is_zero_from[k] and not is_zero_from[k - 1]
+    let mut len = FpVar::<F>::zero();
+    let mut is_sign_byte: Vec<Boolean<F>> = vec![Boolean::<F>::FALSE; MAX_NUM_LENGTH + 1];
+    for k in 1..=MAX_NUM_LENGTH {
+        let is_len = &is_zero_from[k] & &!&is_zero_from[k - 1];
+        let is_extended = &is_len & &magnitude[k - 1].to_bits_le()?[7];
+        is_sign_byte[k - 1] = &is_sign_byte[k - 1] | &(&is_len & &!&is_extended);
+        is_sign_byte[k] = &is_sign_byte[k] | &is_extended;
+        len += FpVar::<F>::from(is_len) * F::from(k as u64) + FpVar::<F>::from(is_extended);
+    }
+
+    // The most significant bit of the sign byte is zero, so setting it adds the sign
+    let zero = UInt8::<F>::constant(0);
+    let bytes = is_sign_byte
+        .iter()
+        .enumerate()
+        .map(|(i, is_sign_byte)| {
+            let mut bits = magnitude.get(i).unwrap_or(&zero).to_bits_le()?;
+            bits[7] = &bits[7] | &(is_sign_byte & &is_negative);
+            Ok(UInt8::<F>::from_bits_le(&bits))
+        })
+        .collect::<Result<Vec<UInt8<F>>, SynthesisError>>()?;
+
+    Ok(VarBytesVar { bytes, len })
+}
+
+/// Return the bytes of a stack item. Numbers are minimally encoded, see [num_to_bytes].
+fn bytes<F: PrimeField>(item: &StackItem<F>) -> Result<VarBytesVar<F>, SynthesisError> {
+    match item {
+        StackItem::Bytes(bytes) => Ok(VarBytesVar::<F>::from_bytes(bytes)),
+        StackItem::Num(num) => num_to_bytes(num),
+    }
+}
+
+/// Check whether two byte strings are equal. As their padding is zero, this is the case if and only if
+/// their lengths are equal and their bytes, padded to the same length, are equal.
+fn is_equal<F: PrimeField>(
+    a: &VarBytesVar<F>,
+    b: &VarBytesVar<F>,
+) -> Result<Boolean<F>, SynthesisError> {
+    if let (FpVar::Constant(a_len), FpVar::Constant(b_len)) = (&a.len, &b.len) {
+        if a_len != b_len {
+            return Ok(Boolean::<F>::FALSE);
+        }
+    }
+    let max_len = a.max_len().max(b.max_len());
+    if max_len == 0 {
+        return Ok(Boolean::<F>::TRUE);
+    }
+
+    let pad = |bytes: &[UInt8<F>]| -> Vec<UInt8<F>> {
+        let mut padded = bytes.to_vec();
+        padded.resize(max_len, UInt8::<F>::constant(0));
+        padded
+    };
+    Ok(a.len.is_eq(&b.len)? & pad(&a.bytes).is_eq(&pad(&b.bytes))?)
+}
+
+/// Hash a byte string with `hash`. If its length is not a constant, every prefix of the string up to the
+/// maximum length is hashed and the digest is selected according to the length.
+fn digest<F: PrimeField>(
+    data: &VarBytesVar<F>,
+    hash: impl Fn(&[UInt8<F>]) -> Result<Vec<UInt8<F>>, SynthesisError>,
+) -> Result<Vec<UInt8<F>>, SynthesisError> {
+    if let FpVar::Constant(len) = &data.len {
+        return hash(&data.bytes[..to_u64(len).ok_or(SynthesisError::Unsatisfiable)? as usize]);
+    }
+
+    let is_len = data.len_indicator()?;
+    let mut digest = hash(&[])?;
+    for (k, is_len) in is_len.iter().enumerate().skip(1) {
+        digest = hash(&data.bytes[..k])?
+            .iter()
+            .zip(digest.iter())
+            .map(|(new, old)| UInt8::<F>::conditionally_select(is_len, new, old))
+            .collect::<Result<Vec<UInt8<F>>, SynthesisError>>()?;
+    }
+    Ok(digest)
+}
+
+/// Check whether a stack item is truthy: a byte string is false if and only if all its bytes are zero,
+/// except possibly the last one which can be `0x80` (negative zero)
+fn is_truthy<F: PrimeField>(item: &StackItem<F>) -> Result<Boolean<F>, SynthesisError> {
+    let bytes = match item {
+        StackItem::Num(num) => return num.is_neq(&FpVar::<F>::zero()),
+        StackItem::Bytes(bytes) => bytes,
+    };
+    let Some((last, rest)) = bytes.split_last() else {
+        return Ok(Boolean::<F>::FALSE);
+    };
+
+    let mut is_zero: Vec<Boolean<F>> = Vec::with_capacity(bytes.len());
+    for byte in rest.iter() {
+        is_zero.push(byte.is_eq(&UInt8::<F>::constant(0))?);
+    }
+    is_zero.push(last.is_eq(&UInt8::<F>::constant(0))? | last.is_eq(&UInt8::<F>::constant(0x80))?);
+
+    Ok(!Boolean::<F>::kary_and(&is_zero)?)
+}
+
+/// Convert a stack item into a number. Byte strings are decoded as sign-magnitude little endian integers of at
+/// most [MAX_NUM_LENGTH] bytes. Numbers are range checked against the same bound, and the check is added to `checks`.
+fn to_num<F: PrimeField>(
+    item: &StackItem<F>,
+    checks: &mut Vec<Boolean<F>>,
+) -> Result<FpVar<F>, SynthesisError> {
+    let bytes = match item {
+        StackItem::Num(num) => {
+            // The operands of arithmetic opcodes are range checked, so `num` lies in (-2^32, 2^32)
+            let max = F::from((1u64 << (8 * MAX_NUM_LENGTH - 1)) - 1);
+            checks.push(is_less_than(
+                &(num + max),
+                &FpVar::<F>::constant(max.double() + F::one()),
+                8 * MAX_NUM_LENGTH + 1,
+            )?);
+            return Ok(num.clone());
+        }
+        StackItem::Bytes(bytes) => bytes,
+    };
+    let Some((last, rest)) = bytes.split_last() else {
+        return Ok(FpVar::<F>::zero());
+    };
+    if bytes.len() > MAX_NUM_LENGTH {
+        // Operands longer than MAX_NUM_LENGTH bytes make the execution fail, as in Bitcoin
+        checks.push(Boolean::<F>::FALSE);
+        return Ok(FpVar::<F>::zero());
+    }
+
+    let last_bits = last.to_bits_le()?;
+    let mut magnitude = Boolean::<F>::le_bits_to_fp(&last_bits[..7])?;
+    for byte in rest.iter().rev() {
+        magnitude = magnitude * F::from(256u64) + to_fp(byte)?;
+    }
+    let sign = FpVar::<F>::from(last_bits[7].clone());
+
+    Ok(&magnitude - sign * magnitude.double()?)
+}
+
+/// Check whether `a < b` for numbers which passed [to_num], i.e., lying in `(-2^31, 2^31)`
+fn is_less_than_signed<F: PrimeField>(
+    a: &FpVar<F>,
+    b: &FpVar<F>,
+) -> Result<Boolean<F>, SynthesisError> {
+    let offset = F::from(1u64 << (8 * MAX_NUM_LENGTH - 1));
+
+    is_less_than(&(a + offset), &(b + offset), 8 * MAX_NUM_LENGTH)
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::Fr as F;
+    use ark_crypto_primitives::crh::{CRHScheme, sha256::Sha256};
+    use ark_r1cs_std::{alloc::AllocVar, eq::EqGadget, prelude::Boolean};
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::script::Script;
+    use chain_gang::script::op_codes::*;
+
+    use crate::constraints::script::ScriptVar;
+    use crate::util::parse_push_lengths;
+
+    use super::InterpreterGadget;
+
+    fn test_interpreter(unlock_script: Script, lock_script: Script, expected: bool) {
+        let push_lengths = parse_push_lengths(&unlock_script.0).unwrap();
+        let cs = ConstraintSystem::<F>::new_ref();
+        let unlock_script_var =
+            ScriptVar::<F>::new_witness(cs.clone(), || Ok(unlock_script)).unwrap();
+
+        InterpreterGadget::<F, 100, 10>::verify(&unlock_script_var, &push_lengths, &lock_script)
+            .unwrap()
+            .enforce_equal(&Boolean::<F>::TRUE)
+            .unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), expected);
+    }
+
+    fn push(data: &[u8]) -> Script {
+        let mut script = Script::new();
+        script.append_data(data);
+        script
+    }
+
+    #[test]
+    fn test_hash_lock() {
+        let preimage = b"secret".to_vec();
+        let digest = <Sha256 as CRHScheme>::evaluate(&(), preimage.as_slice()).unwrap();
+        let mut lock_script = Script::new();
+        lock_script.append(OP_SHA256);
+        lock_script.append_data(&digest);
+        lock_script.append(OP_EQUAL);
+
+        test_interpreter(push(&preimage), lock_script.clone(), true);
+        test_interpreter(push(b"public"), lock_script, false);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        // Check that a + b = 5 and a < b
+        let lock_script = Script(vec![
+            OP_OVER,
+            OP_OVER,
+            OP_ADD,
+            OP_5,
+            OP_NUMEQUALVERIFY,
+            OP_LESSTHAN,
+        ]);
+        let mut unlock_script = push(&[2]);
+        unlock_script.append_data(&[3]);
+        test_interpreter(unlock_script, lock_script.clone(), true);
+
+        let mut unlock_script = push(&[3]);
+        unlock_script.append_data(&[2]);
+        test_interpreter(unlock_script, lock_script.clone(), false);
+
+        // -1 + 6 = 5, and -1 < 6
+        let mut unlock_script = push(&[0x81]);
+        unlock_script.append_data(&[6]);
+        test_interpreter(unlock_script, lock_script, true);
+    }
+
+    #[test]
+    fn test_stack_operations() {
+        // [0xaa, 0xbb] -> [0xbb, 0xaa] -> [0xbb] -> [0xbb, 0xbb] -> [0xbb, 0xbb, 1] -> [0xbb, 1] -> [0xbb, 1, 1] -> [0xbb, 1]
+        let lock_script = Script(vec![
+            OP_SWAP,
+            OP_DROP,
+            OP_DUP,
+            OP_SIZE,
+            OP_NIP,
+            OP_1,
+            OP_NUMEQUAL,
+        ]);
+        let mut unlock_script = push(&[0xaa]);
+        unlock_script.append_data(&[0xbb]);
+        test_interpreter(unlock_script, lock_script, true);
+
+        let lock_script = Script(vec![OP_SWAP, OP_DROP, 1, 0xbb, OP_EQUAL]);
+        let mut unlock_script = push(&[0xaa]);
+        unlock_script.append_data(&[0xbb]);
+        test_interpreter(unlock_script.clone(), lock_script.clone(), false);
+        let mut unlock_script = push(&[0xbb]);
+        unlock_script.append_data(&[0xaa]);
+        test_interpreter(unlock_script, lock_script, true);
+    }
+
+    #[test]
+    fn test_arithmetic_results_as_bytes() {
+        // The result of an arithmetic opcode is compared with its minimal encoding
+        for (a, b, encoding) in [
+            (vec![2], vec![0x81], vec![1]),
+            (vec![1], vec![0x81], vec![]),
+            (vec![0x7f], vec![1], vec![0x80, 0]),
+            (vec![0x81], vec![0xff], vec![0x80, 0x80]),
+            (vec![0xff, 0x7f], vec![0x82], vec![0xfd, 0x7f]),
+        ] {
+            let mut lock_script = Script::new();
+            lock_script.append(OP_ADD);
+            lock_script.append_data(&encoding);
+            lock_script.append(OP_EQUAL);
+            let mut unlock_script = push(&a);
+            unlock_script.append_data(&b);
+            test_interpreter(unlock_script.clone(), lock_script, true);
+
+            // The size of the result is the length of its minimal encoding
+            let mut lock_script = Script::new();
+            lock_script.append(OP_ADD);
+            lock_script.append(OP_SIZE);
+            lock_script.append_data(&[encoding.len() as u8]);
+            lock_script.append(OP_NUMEQUALVERIFY);
+            lock_script.append(OP_SHA256);
+            lock_script
+                .append_data(&<Sha256 as CRHScheme>::evaluate(&(), encoding.as_slice()).unwrap());
+            lock_script.append(OP_EQUAL);
+            test_interpreter(unlock_script, lock_script, true);
+        }
+
+        let mut unlock_script = push(&[2]);
+        unlock_script.append_data(&[2]);
+        test_interpreter(unlock_script, Script(vec![OP_ADD, OP_1, OP_EQUAL]), false);
+    }
+
+    #[test]
+    fn test_invalid_lock_script() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let unlock_script_var = ScriptVar::<F>::new_witness(cs.clone(), || Ok(push(&[1]))).unwrap();
+        for lock_script in [
+            // Unsupported opcode
+            Script(vec![OP_CHECKSIG]),
+            // Malformed push
+            Script(vec![OP_PUSHDATA2, 1]),
+            // Push exceeding the length of the script
+            Script(vec![2, 1]),
+            // Script longer than the maximum script size
+            Script(vec![OP_NOP; 101]),
+        ] {
+            assert!(
+                InterpreterGadget::<F, 100, 10>::verify(&unlock_script_var, &[1], &lock_script)
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_failures() {
+        // Stack underflow
+        test_interpreter(push(&[1]), Script(vec![OP_DROP, OP_DROP]), false);
+        // Empty final stack
+        test_interpreter(push(&[1]), Script(vec![OP_DROP]), false);
+        // Falsy final stack
+        test_interpreter(push(&[0x80]), Script(vec![OP_NOP]), false);
+        test_interpreter(push(&[0, 0]), Script(vec![OP_NOP]), false);
+        // Failing OP_VERIFY
+        test_interpreter(push(&[0]), Script(vec![OP_VERIFY, OP_1]), false);
+        test_interpreter(push(&[1]), Script(vec![OP_VERIFY, OP_1]), true);
+    }
+}
@@ -1,7 +1,18 @@
+pub mod envelope;
+pub mod hash160;
 pub mod hash256;
+pub mod interpreter;
 pub mod outpoint;
+pub mod push_tx;
+pub mod ripemd160;
 pub mod script;
+pub mod script_template;
+pub mod sha256;
 pub mod sighash_cache;
+pub mod sighash_preimage;
 pub mod tx;
 pub mod txin;
 pub mod txout;
+pub mod var_bytes;
+pub mod var_len_tx;
+pub mod witness;
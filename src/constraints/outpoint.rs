@@ -10,6 +10,7 @@ use ark_r1cs_std::{
 use ark_crypto_primitives::crh::sha256::constraints::DigestVar;
 
 use crate::traits::PreSigHashSerialise;
+use crate::util::to_fp_bits;
 use ark_relations::r1cs::{Namespace, SynthesisError};
 use chain_gang::messages::OutPoint;
 use chain_gang::util::Hash256;
@@ -17,10 +18,14 @@ use std::borrow::Borrow;
 
 use ark_r1cs_std::boolean::Boolean;
 use ark_r1cs_std::eq::EqGadget;
+use ark_r1cs_std::select::CondSelectGadget;
 
 use ark_r1cs_std::R1CSVar;
 use ark_relations::r1cs::ConstraintSystemRef;
 
+/// The index of the null outpoint, spent by coinbase inputs together with the null transaction ID `[0; 32]`
+pub const NULL_OUTPOINT_INDEX: u32 = 0xffffffff;
+
 // R1CS version of [OutPoint]
 #[derive(Debug, Clone)]
 pub struct OutPointVar<F: PrimeField> {
@@ -51,6 +56,26 @@ impl<F: PrimeField> AllocVar<OutPoint, F> for OutPointVar<F> {
     }
 }
 
+impl<F: PrimeField> OutPointVar<F> {
+    /// Compute the field elements allocated by [OutPointVar::new_input] for `outpoint`
+    pub fn public_input(outpoint: &OutPoint) -> Vec<F> {
+        let mut input = to_fp_bits(&outpoint.hash.0);
+        input.extend_from_slice(&to_fp_bits(&outpoint.index.to_le_bytes()));
+        input
+    }
+
+    /// Check whether the outpoint is the null outpoint, i.e., the one spent by coinbase inputs
+    pub fn is_null(&self) -> Result<Boolean<F>, SynthesisError> {
+        Boolean::<F>::kary_and(&[
+            self.prev_tx
+                .0
+                .is_eq(&UInt8::<F>::constant_vec(&[0u8; 32]))?,
+            self.prev_index
+                .is_eq(&UInt32::<F>::constant(NULL_OUTPOINT_INDEX))?,
+        ])
+    }
+}
+
 impl<F: PrimeField> EqGadget<F> for OutPointVar<F> {
     fn is_eq(&self, other: &Self) -> Result<Boolean<F>, SynthesisError> {
         Boolean::<F>::kary_and(&[
@@ -60,6 +85,27 @@ impl<F: PrimeField> EqGadget<F> for OutPointVar<F> {
     }
 }
 
+impl<F: PrimeField> CondSelectGadget<F> for OutPointVar<F> {
+    fn conditionally_select(
+        cond: &Boolean<F>,
+        true_value: &Self,
+        false_value: &Self,
+    ) -> Result<Self, SynthesisError> {
+        Ok(Self {
+            prev_tx: DigestVar::<F>::conditionally_select(
+                cond,
+                &true_value.prev_tx,
+                &false_value.prev_tx,
+            )?,
+            prev_index: UInt32::<F>::conditionally_select(
+                cond,
+                &true_value.prev_index,
+                &false_value.prev_index,
+            )?,
+        })
+    }
+}
+
 impl<F: PrimeField> ToBytesGadget<F> for OutPointVar<F> {
     fn to_bytes_le(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
         self.pre_sighash_serialise()
@@ -179,4 +225,24 @@ mod tests {
 
         assert_eq!(outpoint_bytes, outpoint_gadget_bytes);
     }
+
+    #[test]
+    fn test_is_null() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        for (hash, index, expected) in [
+            ([0u8; 32], NULL_OUTPOINT_INDEX, true),
+            ([0u8; 32], 0, false),
+            ([1u8; 32], NULL_OUTPOINT_INDEX, false),
+        ] {
+            let outpoint: OutPointVar<F> = OutPointVar::<F>::new_input(cs.clone(), || {
+                Ok(OutPoint {
+                    hash: Hash256(hash),
+                    index,
+                })
+            })
+            .unwrap();
+            assert_eq!(outpoint.is_null().unwrap().value().unwrap(), expected);
+        }
+        assert!(cs.is_satisfied().unwrap());
+    }
 }
@@ -0,0 +1,239 @@
+//! OP_PUSH_TX-style gadget: verification of a sighash preimage embedded in an unlocking script
+use std::marker::PhantomData;
+
+use ark_crypto_primitives::crh::sha256::constraints::DigestVar;
+use ark_ff::PrimeField;
+use ark_r1cs_std::{eq::EqGadget, prelude::Boolean, prelude::ToBytesGadget};
+use ark_relations::r1cs::SynthesisError;
+
+use crate::constraints::{
+    sighash_cache::SigHashCacheVar,
+    sighash_preimage::SigHashPreimageVar,
+    tx::{TxVar, TxVarConfig},
+};
+use crate::error::Error;
+
+/// Gadget verifying that an unlocking script pushes the sighash preimage of the input it belongs to.
+///
+/// This is the R1CS version of the OP_PUSH_TX technique used by covenants: the preimage is pushed in the unlocking
+/// script, and the locking script checks it against the spending transaction.
+pub struct PushTxGadget<F: PrimeField, P: TxVarConfig + Clone> {
+    _field: PhantomData<F>,
+    _config: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> PushTxGadget<F, P> {
+    /// Interpret the push at `push_index` of the unlocking script of the input at `n_input` as a sighash preimage
+    /// with a script code of `script_code_len` bytes, and check that it is the preimage of the sighash of `tx` for
+    /// the input at `n_input` with flags `sighash_flags`.
+    ///
+    /// The function returns a [Boolean] which is `TRUE` if the unlocking script has the push layout `push_lengths`
+    /// and the preimage is valid, the parsed preimage and its digest. The script code and the amount are taken from
+    /// the preimage, as they are not part of the spending transaction.
+    ///
+    /// Returns [Error::IndexOutOfBounds] if `n_input` or `push_index` are out of range, and [Error::ConfigMismatch] if
+    /// `push_lengths[push_index]` is not the length of a preimage with a script code of `script_code_len` bytes.
+    pub fn verify(
+        tx: &TxVar<F, P>,
+        n_input: usize,
+        push_lengths: &[usize],
+        push_index: usize,
+        script_code_len: usize,
+        sighash_flags: &u8,
+        cache: &mut SigHashCacheVar<F>,
+    ) -> Result<(Boolean<F>, SigHashPreimageVar<F>, DigestVar<F>), SynthesisError> {
+        if n_input >= tx.inputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of inputs: {}",
+                n_input,
+                tx.inputs.len()
+            ))
+            .into());
+        }
+        if push_index >= push_lengths.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of pushes: {}",
+                push_index,
+                push_lengths.len()
+            ))
+            .into());
+        }
+
+        let unlock_script = &tx.inputs[n_input].unlock_script;
+        let is_push_layout = unlock_script.has_push_layout(push_lengths)?;
+        let pushed_preimage = unlock_script
+            .split_pushes(push_lengths)?
+            .swap_remove(push_index);
+        let preimage = SigHashPreimageVar::<F>::from_bytes(&pushed_preimage, script_code_len)?;
+
+        // Recompute the preimage from `tx`, with the script code and the amount in the pushed preimage
+        let expected_preimage = tx
+            .sighash_preimage(
+                n_input,
+                &preimage.script_code,
+                &preimage.amount,
+                sighash_flags,
+                cache,
+            )?
+            .to_bytes_le()?;
+        let is_valid =
+            Boolean::<F>::kary_and(&[is_push_layout, pushed_preimage.is_eq(&expected_preimage)?])?;
+        let digest = preimage.digest()?;
+
+        Ok((is_valid, preimage, digest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::{R1CSVar, alloc::AllocVar};
+    use ark_relations::r1cs::{ConstraintSystem, SynthesisError};
+    use chain_gang::address::addr_decode;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::network::Network;
+    use chain_gang::script::Script;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::transaction::sighash::{
+        SIGHASH_ALL, SIGHASH_FORKID, SigHashCache, sig_hash_preimage, sighash,
+    };
+    use chain_gang::util::Hash256;
+
+    use crate::constraints::{
+        sighash_cache::SigHashCacheVar,
+        tx::{TxVar, TxVarConfig},
+    };
+
+    use super::PushTxGadget;
+
+    /// Length of the preimage for a P2PKH script code
+    const PREIMAGE_LEN: usize = 182;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[2 + PREIMAGE_LEN];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    fn p2pkh_script(addr: &str) -> Script {
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        p2pkh::create_lock_script(&hash160)
+    }
+
+    fn tx(unlock_script: Script) -> Tx {
+        Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script,
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 100,
+                lock_script: p2pkh_script("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec"),
+            }],
+            lock_time: 0,
+        }
+    }
+
+    fn test_push_tx(tamper: impl Fn(&mut Vec<u8>), expected: bool) {
+        let script_code = p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz");
+        let sighash_flags = SIGHASH_ALL | SIGHASH_FORKID;
+        // The preimage does not depend on the unlocking scripts
+        let mut preimage = sig_hash_preimage(
+            &tx(Script(vec![])),
+            0,
+            &script_code.0,
+            1000,
+            sighash_flags,
+            &mut SigHashCache::new(),
+        )
+        .unwrap();
+        assert_eq!(preimage.len(), PREIMAGE_LEN);
+        tamper(&mut preimage);
+        let mut unlock_script = Script::new();
+        unlock_script.append_data(&preimage);
+        let spending_tx = tx(unlock_script);
+        let expected_sighash = sighash(
+            &spending_tx,
+            0,
+            &script_code.0,
+            1000,
+            sighash_flags,
+            &mut SigHashCache::new(),
+        )
+        .unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_witness(cs.clone(), || Ok(spending_tx)).unwrap();
+        let (is_valid, preimage_var, digest) = PushTxGadget::<F, Config>::verify(
+            &tx_var,
+            0,
+            &[PREIMAGE_LEN],
+            0,
+            0x19,
+            &sighash_flags,
+            &mut SigHashCacheVar::<F>::new(),
+        )
+        .unwrap();
+
+        assert_eq!(is_valid.value().unwrap(), expected);
+        assert_eq!(preimage_var.value().unwrap(), preimage);
+        if expected {
+            assert_eq!(preimage_var.script_code.value().unwrap(), script_code);
+            assert_eq!(digest.value().unwrap(), expected_sighash.0.to_vec());
+        }
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_push_tx_is_ok() {
+        test_push_tx(|_| {}, true);
+    }
+
+    #[test]
+    fn test_push_tx_fails() {
+        // Wrong version
+        test_push_tx(|preimage| preimage[0] ^= 1, false);
+        // Wrong outpoint
+        test_push_tx(|preimage| preimage[100] ^= 1, false);
+        // Wrong lock time
+        test_push_tx(|preimage| preimage[PREIMAGE_LEN - 8] ^= 1, false);
+    }
+
+    #[test]
+    fn test_push_tx_out_of_range() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_witness(cs.clone(), || {
+            let mut unlock_script = Script::new();
+            unlock_script.append_data(&[0; PREIMAGE_LEN]);
+            Ok(tx(unlock_script))
+        })
+        .unwrap();
+        let sighash_flags = SIGHASH_ALL | SIGHASH_FORKID;
+
+        for (n_input, push_index) in [(1, 0), (0, 1)] {
+            assert!(matches!(
+                PushTxGadget::<F, Config>::verify(
+                    &tx_var,
+                    n_input,
+                    &[PREIMAGE_LEN],
+                    push_index,
+                    0x19,
+                    &sighash_flags,
+                    &mut SigHashCacheVar::<F>::new(),
+                ),
+                Err(SynthesisError::Unsatisfiable)
+            ));
+        }
+    }
+}
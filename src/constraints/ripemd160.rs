@@ -0,0 +1,149 @@
+//! R1CS implementation of Ripemd160
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{convert::ToBytesGadget, uint8::UInt8, uint32::UInt32};
+use ark_relations::r1cs::Result;
+
+/// Initial value of the chaining variables
+const IV: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+/// Additive constants of the left and right lines, one per round of 16 steps
+const K_LEFT: [u32; 5] = [0x00000000, 0x5a827999, 0x6ed9eba1, 0x8f1bbcdc, 0xa953fd4e];
+const K_RIGHT: [u32; 5] = [0x50a28be6, 0x5c4dd124, 0x6d703ef3, 0x7a6d76e9, 0x00000000];
+/// Selection of the message words in the left and right lines
+const R_LEFT: [usize; 80] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 7, 4, 13, 1, 10, 6, 15, 3, 12, 0, 9, 5,
+    2, 14, 11, 8, 3, 10, 14, 4, 9, 15, 8, 1, 2, 7, 0, 6, 13, 11, 5, 12, 1, 9, 11, 10, 0, 8, 12, 4,
+    13, 3, 7, 15, 14, 5, 6, 2, 4, 0, 5, 9, 7, 12, 2, 10, 14, 1, 3, 8, 11, 6, 15, 13,
+];
+const R_RIGHT: [usize; 80] = [
+    5, 14, 7, 0, 9, 2, 11, 4, 13, 6, 15, 8, 1, 10, 3, 12, 6, 11, 3, 7, 0, 13, 5, 10, 14, 15, 8, 12,
+    4, 9, 1, 2, 15, 5, 1, 3, 7, 14, 6, 9, 11, 8, 12, 2, 10, 0, 4, 13, 8, 6, 4, 1, 3, 11, 15, 0, 5,
+    12, 2, 13, 9, 7, 10, 14, 12, 15, 10, 4, 1, 5, 8, 7, 6, 2, 13, 14, 0, 3, 9, 11,
+];
+/// Amounts of the left rotations in the left and right lines
+const S_LEFT: [usize; 80] = [
+    11, 14, 15, 12, 5, 8, 7, 9, 11, 13, 14, 15, 6, 7, 9, 8, 7, 6, 8, 13, 11, 9, 7, 15, 7, 12, 15,
+    9, 11, 7, 13, 12, 11, 13, 6, 7, 14, 9, 13, 15, 14, 8, 13, 6, 5, 12, 7, 5, 11, 12, 14, 15, 14,
+    15, 9, 8, 9, 14, 5, 6, 8, 6, 5, 12, 9, 15, 5, 11, 6, 8, 13, 12, 5, 12, 13, 14, 11, 8, 5, 6,
+];
+const S_RIGHT: [usize; 80] = [
+    8, 9, 9, 11, 13, 15, 15, 5, 7, 7, 8, 11, 14, 14, 12, 6, 9, 13, 15, 7, 12, 8, 9, 11, 7, 7, 12,
+    7, 6, 15, 13, 11, 9, 7, 15, 11, 8, 6, 6, 14, 12, 13, 5, 14, 13, 13, 7, 5, 15, 5, 8, 11, 14, 14,
+    6, 14, 6, 9, 12, 9, 12, 5, 15, 8, 8, 5, 12, 9, 12, 5, 14, 6, 8, 13, 6, 5, 15, 13, 11, 11,
+];
+
+/// Gadget for calculating Ripemd160
+pub struct Ripemd160Gadget<F: PrimeField>(PhantomData<F>);
+
+impl<F: PrimeField> Ripemd160Gadget<F> {
+    pub fn evaluate(data: &[UInt8<F>]) -> Result<Vec<UInt8<F>>> {
+        // Padding: the length of the data is a constant of the circuit
+        let mut message: Vec<UInt8<F>> = data.to_vec();
+        message.push(UInt8::<F>::constant(0x80));
+        while message.len() % 64 != 56 {
+            message.push(UInt8::<F>::constant(0));
+        }
+        message.extend(UInt8::<F>::constant_vec(
+            &((data.len() as u64) * 8).to_le_bytes(),
+        ));
+
+        let mut state: [UInt32<F>; 5] = IV.map(UInt32::<F>::constant);
+        for block in message.chunks(64) {
+            let words = block
+                .chunks(4)
+                .map(UInt32::<F>::from_bytes_le)
+                .collect::<Result<Vec<UInt32<F>>>>()?;
+            state = Self::compress(&state, &words)?;
+        }
+
+        Ok(state
+            .iter()
+            .map(|word| word.to_bytes_le())
+            .collect::<Result<Vec<Vec<UInt8<F>>>>>()?
+            .concat())
+    }
+
+    /// The non-linear function of step `j`
+    fn f(j: usize, x: &UInt32<F>, y: &UInt32<F>, z: &UInt32<F>) -> UInt32<F> {
+        match j / 16 {
+            0 => x ^ y ^ z,
+            1 => (x & y) | (!x & z),
+            2 => (x | !y) ^ z,
+            3 => (x & z) | (y & !z),
+            _ => x ^ (y | !z),
+        }
+    }
+
+    /// The compression function, processing one block of 16 words
+    fn compress(state: &[UInt32<F>; 5], words: &[UInt32<F>]) -> Result<[UInt32<F>; 5]> {
+        let [mut a, mut b, mut c, mut d, mut e] = state.clone();
+        let [mut a_r, mut b_r, mut c_r, mut d_r, mut e_r] = state.clone();
+
+        for j in 0..80 {
+            let t = UInt32::<F>::wrapping_add_many(&[
+                a,
+                Self::f(j, &b, &c, &d),
+                words[R_LEFT[j]].clone(),
+                UInt32::<F>::constant(K_LEFT[j / 16]),
+            ])?
+            .rotate_left(S_LEFT[j]);
+            let t = UInt32::<F>::wrapping_add_many(&[t, e.clone()])?;
+            a = e;
+            e = d;
+            d = c.rotate_left(10);
+            c = b;
+            b = t;
+
+            let t = UInt32::<F>::wrapping_add_many(&[
+                a_r,
+                Self::f(79 - j, &b_r, &c_r, &d_r),
+                words[R_RIGHT[j]].clone(),
+                UInt32::<F>::constant(K_RIGHT[j / 16]),
+            ])?
+            .rotate_left(S_RIGHT[j]);
+            let t = UInt32::<F>::wrapping_add_many(&[t, e_r.clone()])?;
+            a_r = e_r;
+            e_r = d_r;
+            d_r = c_r.rotate_left(10);
+            c_r = b_r;
+            b_r = t;
+        }
+
+        Ok([
+            UInt32::<F>::wrapping_add_many(&[state[1].clone(), c, d_r])?,
+            UInt32::<F>::wrapping_add_many(&[state[2].clone(), d, e_r])?,
+            UInt32::<F>::wrapping_add_many(&[state[3].clone(), e, a_r])?,
+            UInt32::<F>::wrapping_add_many(&[state[4].clone(), a, b_r])?,
+            UInt32::<F>::wrapping_add_many(&[state[0].clone(), b, c_r])?,
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::{R1CSVar, alloc::AllocVar, uint8::UInt8};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    use super::Ripemd160Gadget;
+
+    fn test_vector(data: &[u8], expected: &str) {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let data_var = Vec::<UInt8<F>>::new_witness(cs.clone(), || Ok(data)).unwrap();
+        let digest = Ripemd160Gadget::<F>::evaluate(&data_var).unwrap();
+        assert_eq!(hex::encode(digest.value().unwrap()), expected);
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_ripemd160() {
+        test_vector(b"", "9c1185a5c5e9fc54612808977ee8f548b2258d31");
+        test_vector(b"abc", "8eb208f7e05d987a9b044a8e98c6b087f15a0bfc");
+        // Two blocks after padding
+        test_vector(
+            b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+            "12a053384a9c0c88e405a06c27dcf49ada62eb2b",
+        );
+    }
+}
@@ -8,26 +8,33 @@ use ark_r1cs_std::{
 
 use chain_gang::script::Script;
 
+use crate::error::Error;
 use crate::traits::PreSigHashSerialise;
 
 use ark_relations::r1cs::{Namespace, SynthesisError};
-use std::{borrow::Borrow, vec::Vec};
+use std::{borrow::Borrow, ops::Range, vec::Vec};
 
 use ark_r1cs_std::boolean::Boolean;
 use ark_r1cs_std::eq::EqGadget;
+use ark_r1cs_std::select::CondSelectGadget;
 
 use ark_r1cs_std::R1CSVar;
 use ark_relations::r1cs::ConstraintSystemRef;
 
 use std::io::Result as IoResult;
 
-use crate::util::usize_to_var_int;
+use crate::util::{push_data_prefix, to_fp_bits, usize_to_var_int};
 
 /// R1CS version of a [Script]
 #[derive(Debug, Clone)]
 pub struct ScriptVar<F: PrimeField>(pub Vec<UInt8<F>>);
 
 impl<F: PrimeField> ScriptVar<F> {
+    /// Compute the field elements allocated by [ScriptVar::new_input] for `script`
+    pub fn public_input(script: &Script) -> Vec<F> {
+        to_fp_bits(&script.0)
+    }
+
     /// Computes var_int length of `self`.
     /// It allocates the elements of the length as constants.
     /// This is ok because the size of the script (unlocking or locking script) is fixed in a circuit.
@@ -37,6 +44,158 @@ impl<F: PrimeField> ScriptVar<F> {
             .map(|el| UInt8::<F>::constant(el))
             .collect::<Vec<UInt8<F>>>())
     }
+
+    /// The length of `self`, which is a constant of the circuit
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Check whether `self` is the empty script
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The concatenation `self || other`
+    pub fn concat(&self, other: &Self) -> Self {
+        Self([self.0.as_slice(), other.0.as_slice()].concat())
+    }
+
+    /// The sub-script of `self` in `range`, or [Error::IndexOutOfBounds] if `range` does not fit in `self`
+    pub fn slice(&self, range: Range<usize>) -> Result<Self, Error> {
+        if range.start > range.end || range.end > self.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Range: {}..{} is not compatible with the size of the script: {}",
+                range.start,
+                range.end,
+                self.len()
+            )));
+        }
+
+        Ok(Self(self.0[range].to_vec()))
+    }
+
+    /// Check whether `prefix` is a prefix of `self`. If `prefix` is longer than `self`, the result is `FALSE`.
+    pub fn starts_with(&self, prefix: &Self) -> Result<Boolean<F>, SynthesisError> {
+        if prefix.len() > self.len() {
+            return Ok(Boolean::<F>::FALSE);
+        }
+        if prefix.is_empty() {
+            return Ok(Boolean::<F>::TRUE);
+        }
+
+        self.0[..prefix.len()].is_eq(&prefix.0)
+    }
+
+    /// Check whether `suffix` is a suffix of `self`. If `suffix` is longer than `self`, the result is `FALSE`.
+    pub fn ends_with(&self, suffix: &Self) -> Result<Boolean<F>, SynthesisError> {
+        if suffix.len() > self.len() {
+            return Ok(Boolean::<F>::FALSE);
+        }
+        if suffix.is_empty() {
+            return Ok(Boolean::<F>::TRUE);
+        }
+
+        self.0[self.len() - suffix.len()..].is_eq(&suffix.0)
+    }
+
+    /// Check whether `self` is a push-only script whose i-th push carries `push_lengths[i]` bytes.
+    /// The layout is fixed in the circuit, so the opcodes preceding each chunk are compared against
+    /// the corresponding push opcodes (`OP_PUSHBYTES_n`, `OP_PUSHDATA1`, `OP_PUSHDATA2`, `OP_PUSHDATA4`).
+    ///
+    /// If the declared lengths are inconsistent with the length of `self`, the result is `FALSE`.
+    pub fn has_push_layout(&self, push_lengths: &[usize]) -> Result<Boolean<F>, SynthesisError> {
+        let prefixes = push_prefixes(push_lengths)?;
+        if self.check_push_layout_len(&prefixes, push_lengths).is_err() {
+            return Ok(Boolean::<F>::FALSE);
+        }
+
+        if push_lengths.is_empty() {
+            return Ok(Boolean::<F>::TRUE);
+        }
+
+        let mut is_push: Vec<Boolean<F>> = Vec::new();
+        let mut offset: usize = 0;
+        for (prefix, length) in prefixes.iter().zip(push_lengths.iter()) {
+            for (byte, expected) in self.0[offset..offset + prefix.len()]
+                .iter()
+                .zip(prefix.iter())
+            {
+                is_push.push(byte.is_eq(&UInt8::<F>::constant(*expected))?);
+            }
+            offset += prefix.len() + length;
+        }
+
+        Boolean::<F>::kary_and(&is_push)
+    }
+
+    /// Split `self` into the data chunks pushed by its opcodes, enforcing that `self` has
+    /// the push layout described by `push_lengths` (see [ScriptVar::has_push_layout]).
+    ///
+    /// Returns [Error::ConfigMismatch] if the declared lengths are inconsistent with the length of `self`.
+    pub fn parse_pushes(
+        &self,
+        push_lengths: &[usize],
+    ) -> Result<Vec<Vec<UInt8<F>>>, SynthesisError> {
+        let chunks = self.split_pushes(push_lengths)?;
+        self.has_push_layout(push_lengths)?
+            .enforce_equal(&Boolean::<F>::TRUE)?;
+
+        Ok(chunks)
+    }
+
+    /// Split `self` into the data chunks pushed by its opcodes, assuming that `self` has
+    /// the push layout described by `push_lengths`. No constraint is generated.
+    ///
+    /// Returns [Error::ConfigMismatch] if the declared lengths are inconsistent with the length of `self`.
+    pub(crate) fn split_pushes(
+        &self,
+        push_lengths: &[usize],
+    ) -> Result<Vec<Vec<UInt8<F>>>, SynthesisError> {
+        let prefixes = push_prefixes(push_lengths)?;
+        self.check_push_layout_len(&prefixes, push_lengths)?;
+
+        let mut chunks: Vec<Vec<UInt8<F>>> = Vec::with_capacity(push_lengths.len());
+        let mut offset: usize = 0;
+        for (prefix, length) in prefixes.iter().zip(push_lengths.iter()) {
+            offset += prefix.len();
+            chunks.push(self.0[offset..offset + length].to_vec());
+            offset += length;
+        }
+
+        Ok(chunks)
+    }
+
+    /// Check that the pushes with opcodes `prefixes` and lengths `push_lengths` span the whole script
+    fn check_push_layout_len(
+        &self,
+        prefixes: &[Vec<u8>],
+        push_lengths: &[usize],
+    ) -> Result<(), Error> {
+        let declared_len: usize = prefixes
+            .iter()
+            .zip(push_lengths.iter())
+            .map(|(prefix, length)| prefix.len() + length)
+            .sum();
+        if declared_len != self.0.len() {
+            return Err(Error::ConfigMismatch(format!(
+                "The declared pushes span {} bytes, but the length of the script is {}",
+                declared_len,
+                self.0.len()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Compute the push opcodes of each push in `push_lengths`, see [push_data_prefix]
+fn push_prefixes(push_lengths: &[usize]) -> Result<Vec<Vec<u8>>, Error> {
+    push_lengths
+        .iter()
+        .map(|length| {
+            push_data_prefix(*length).map_err(|error| Error::ConfigMismatch(error.to_string()))
+        })
+        .collect()
 }
 
 impl<F: PrimeField> AllocVar<Script, F> for ScriptVar<F> {
@@ -68,6 +227,33 @@ impl<F: PrimeField> EqGadget<F> for ScriptVar<F> {
     }
 }
 
+impl<F: PrimeField> CondSelectGadget<F> for ScriptVar<F> {
+    /// Select between two scripts of the same length: if `cond` is `TRUE` the output is `true_value`, otherwise it is `false_value`.
+    ///
+    /// Returns [Error::ConfigMismatch] if the scripts have different lengths.
+    fn conditionally_select(
+        cond: &Boolean<F>,
+        true_value: &Self,
+        false_value: &Self,
+    ) -> Result<Self, SynthesisError> {
+        if true_value.0.len() != false_value.0.len() {
+            return Err(Error::ConfigMismatch(format!(
+                "Cannot select between scripts of different lengths: {} and {}",
+                true_value.0.len(),
+                false_value.0.len()
+            ))
+            .into());
+        }
+
+        let mut selected: Vec<UInt8<F>> = Vec::with_capacity(true_value.0.len());
+        for (t, f) in true_value.0.iter().zip(false_value.0.iter()) {
+            selected.push(UInt8::<F>::conditionally_select(cond, t, f)?);
+        }
+
+        Ok(ScriptVar(selected))
+    }
+}
+
 impl<F: PrimeField> ToBytesGadget<F> for ScriptVar<F> {
     fn to_bytes_le(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
         self.0.to_bytes_le()
@@ -96,8 +282,9 @@ impl<F: PrimeField> PreSigHashSerialise<F> for ScriptVar<F> {
     /// `var_int_len(ScripVar) || ScriptVar`
     fn pre_sighash_serialise(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
         // var_int length
-        let script_size: Vec<UInt8<F>> =
-            self.size().map_err(|_| SynthesisError::AssignmentMissing)?;
+        let script_size: Vec<UInt8<F>> = self
+            .size()
+            .map_err(|error| Error::ConfigMismatch(error.to_string()))?;
         // serialised script
         let ser_script = self.to_bytes_le()?;
         // finalised serialisation
@@ -117,6 +304,76 @@ mod tests {
     use ark_relations::r1cs::ConstraintSystem;
     use chain_gang::script::op_codes::*;
 
+    #[test]
+    fn test_utility_methods() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let script = ScriptVar::<F>::new_witness(cs.clone(), || {
+            Ok(Script(vec![OP_DUP, OP_HASH160, OP_EQUAL]))
+        })
+        .unwrap();
+        let constant = |bytes: &[u8]| {
+            ScriptVar::<F>::new_constant(cs.clone(), Script(bytes.to_vec())).unwrap()
+        };
+
+        assert_eq!(script.len(), 3);
+        assert!(constant(&[]).is_empty());
+        assert_eq!(
+            script.concat(&constant(&[OP_1])).value().unwrap().0,
+            vec![OP_DUP, OP_HASH160, OP_EQUAL, OP_1]
+        );
+        assert_eq!(
+            script.slice(1..3).unwrap().value().unwrap().0,
+            vec![OP_HASH160, OP_EQUAL]
+        );
+        assert!(script.slice(0..0).unwrap().is_empty());
+        assert!(matches!(
+            script.slice(2..4),
+            Err(Error::IndexOutOfBounds(_))
+        ));
+        assert!(matches!(
+            script.slice(2..1),
+            Err(Error::IndexOutOfBounds(_))
+        ));
+
+        assert!(
+            script
+                .starts_with(&constant(&[OP_DUP, OP_HASH160]))
+                .unwrap()
+                .value()
+                .unwrap()
+        );
+        assert!(
+            !script
+                .starts_with(&constant(&[OP_HASH160]))
+                .unwrap()
+                .value()
+                .unwrap()
+        );
+        assert!(script.starts_with(&constant(&[])).unwrap().value().unwrap());
+        assert!(
+            script
+                .ends_with(&constant(&[OP_EQUAL]))
+                .unwrap()
+                .value()
+                .unwrap()
+        );
+        assert!(
+            !script
+                .ends_with(&constant(&[OP_DUP]))
+                .unwrap()
+                .value()
+                .unwrap()
+        );
+        assert!(
+            !script
+                .ends_with(&constant(&[OP_1; 4]))
+                .unwrap()
+                .value()
+                .unwrap()
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+
     #[test]
     fn script_serialisation() {
         let mut script = Script::new();
@@ -192,4 +449,72 @@ mod tests {
 
         assert_eq!(script.0, allocated_script_bytes)
     }
+
+    #[test]
+    fn test_parse_pushes() {
+        let mut script = Script::new();
+        script.append_data(&[1, 2, 3]);
+        script.append_data(&[4; 0x50]);
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let allocated_script =
+            ScriptVar::<F>::new_witness(cs.clone(), || Ok(script.clone())).unwrap();
+        let chunks = allocated_script.parse_pushes(&[3, 0x50]).unwrap();
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].value().unwrap(), vec![1, 2, 3]);
+        assert_eq!(chunks[1].value().unwrap(), vec![4; 0x50]);
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_has_push_layout_fails() {
+        // Same length as a push of 3 bytes, but the first opcode is not a push
+        let script = Script(vec![OP_DUP, 1, 2, 3]);
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let allocated_script =
+            ScriptVar::<F>::new_witness(cs.clone(), || Ok(script.clone())).unwrap();
+
+        assert!(
+            !allocated_script
+                .has_push_layout(&[3])
+                .unwrap()
+                .value()
+                .unwrap()
+        );
+        // The declared pushes do not span the whole script
+        assert!(
+            !allocated_script
+                .has_push_layout(&[2])
+                .unwrap()
+                .value()
+                .unwrap()
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_parse_pushes_config_mismatch() {
+        let mut script = Script::new();
+        script.append_data(&[1, 2, 3]);
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let allocated_script =
+            ScriptVar::<F>::new_witness(cs.clone(), || Ok(script.clone())).unwrap();
+
+        assert!(allocated_script.parse_pushes(&[4]).is_err());
+        assert!(allocated_script.parse_pushes(&[3, 0]).is_err());
+    }
+
+    #[test]
+    fn test_conditionally_select_different_lengths() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let script = ScriptVar::<F>::new_witness(cs.clone(), || Ok(Script(vec![1, 2]))).unwrap();
+        let other = ScriptVar::<F>::new_witness(cs.clone(), || Ok(Script(vec![1]))).unwrap();
+
+        assert!(
+            ScriptVar::<F>::conditionally_select(&Boolean::<F>::TRUE, &script, &other).is_err()
+        );
+    }
 }
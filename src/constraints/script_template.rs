@@ -0,0 +1,327 @@
+//! Implementation of [ScriptTemplate], a script in which some bytes are fixed and others are named holes,
+//! and of [ScriptTemplateVar], its R1CS version
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::{AllocVar, AllocationMode},
+    eq::EqGadget,
+    prelude::Boolean,
+    uint8::UInt8,
+};
+use ark_relations::r1cs::{Namespace, SynthesisError};
+
+use chain_gang::script::Script;
+
+use crate::constraints::script::ScriptVar;
+use crate::error::Error;
+
+/// A part of a [ScriptTemplate]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplatePart {
+    /// Bytes the script must contain
+    Fixed(Vec<u8>),
+    /// `len` arbitrary bytes, extracted under `name`
+    Hole { name: String, len: usize },
+}
+
+impl TemplatePart {
+    /// The number of bytes covered by the part
+    pub fn len(&self) -> usize {
+        match self {
+            TemplatePart::Fixed(bytes) => bytes.len(),
+            TemplatePart::Hole { len, .. } => *len,
+        }
+    }
+
+    /// Check whether the part covers no bytes
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A script made of the concatenation of its parts: the fixed ones must appear as they are, while the holes
+/// can hold any bytes, e.g., the public key hash of a P2PKH locking script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptTemplate {
+    pub parts: Vec<TemplatePart>,
+}
+
+impl ScriptTemplate {
+    /// Returns [Error::ConfigMismatch] if the names of the holes are not unique
+    pub fn new(parts: Vec<TemplatePart>) -> Result<Self, Error> {
+        let mut names: Vec<&String> = parts
+            .iter()
+            .filter_map(|part| match part {
+                TemplatePart::Hole { name, .. } => Some(name),
+                TemplatePart::Fixed(_) => None,
+            })
+            .collect();
+        let n_holes = names.len();
+        names.sort();
+        names.dedup();
+        if names.len() != n_holes {
+            return Err(Error::ConfigMismatch(
+                "The names of the holes must be unique".to_string(),
+            ));
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// The length of the scripts matching the template
+    pub fn len(&self) -> usize {
+        self.parts.iter().map(|part| part.len()).sum()
+    }
+
+    /// Check whether the template only matches the empty script
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Match `script` against the template: if it matches, return the contents of the holes by name
+    pub fn matches(&self, script: &Script) -> Option<BTreeMap<String, Vec<u8>>> {
+        if script.0.len() != self.len() {
+            return None;
+        }
+
+        let mut holes: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+        let mut offset: usize = 0;
+        for part in self.parts.iter() {
+            let chunk = &script.0[offset..offset + part.len()];
+            match part {
+                TemplatePart::Fixed(bytes) => {
+                    if chunk != bytes.as_slice() {
+                        return None;
+                    }
+                }
+                TemplatePart::Hole { name, .. } => {
+                    holes.insert(name.clone(), chunk.to_vec());
+                }
+            }
+            offset += part.len();
+        }
+
+        Some(holes)
+    }
+}
+
+/// R1CS version of [TemplatePart]
+#[derive(Debug, Clone)]
+pub enum TemplatePartVar<F: PrimeField> {
+    Fixed(Vec<UInt8<F>>),
+    Hole { name: String, len: usize },
+}
+
+impl<F: PrimeField> TemplatePartVar<F> {
+    /// The number of bytes covered by the part
+    pub fn len(&self) -> usize {
+        match self {
+            TemplatePartVar::Fixed(bytes) => bytes.len(),
+            TemplatePartVar::Hole { len, .. } => *len,
+        }
+    }
+
+    /// Check whether the part covers no bytes
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// R1CS version of [ScriptTemplate]: the fixed bytes are allocated, while the layout of the template is a
+/// constant of the circuit
+#[derive(Debug, Clone)]
+pub struct ScriptTemplateVar<F: PrimeField> {
+    pub parts: Vec<TemplatePartVar<F>>,
+}
+
+impl<F: PrimeField> ScriptTemplateVar<F> {
+    /// The length of the scripts matching the template
+    pub fn len(&self) -> usize {
+        self.parts.iter().map(|part| part.len()).sum()
+    }
+
+    /// Check whether the template only matches the empty script
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Check whether `script` matches the template, and return the contents of the holes by name.
+    ///
+    /// Returns [Error::ConfigMismatch] if the length of `script` is different from the one of the template,
+    /// as both are constants of the circuit.
+    pub fn matches(
+        &self,
+        script: &ScriptVar<F>,
+    ) -> Result<(Boolean<F>, BTreeMap<String, Vec<UInt8<F>>>), SynthesisError> {
+        if script.len() != self.len() {
+            return Err(Error::ConfigMismatch(format!(
+                "The length of the script: {} is different from the length of the template: {}",
+                script.len(),
+                self.len()
+            ))
+            .into());
+        }
+
+        let mut is_fixed: Vec<Boolean<F>> = vec![Boolean::<F>::TRUE];
+        let mut holes: BTreeMap<String, Vec<UInt8<F>>> = BTreeMap::new();
+        let mut offset: usize = 0;
+        for part in self.parts.iter() {
+            let chunk = &script.0[offset..offset + part.len()];
+            match part {
+                TemplatePartVar::Fixed(bytes) => {
+                    for (byte, expected) in chunk.iter().zip(bytes.iter()) {
+                        is_fixed.push(byte.is_eq(expected)?);
+                    }
+                }
+                TemplatePartVar::Hole { name, .. } => {
+                    holes.insert(name.clone(), chunk.to_vec());
+                }
+            }
+            offset += part.len();
+        }
+
+        Ok((Boolean::<F>::kary_and(&is_fixed)?, holes))
+    }
+
+    /// Enforce that `script` matches the template, see [ScriptTemplateVar::matches], and return the contents
+    /// of the holes by name
+    pub fn enforce_match(
+        &self,
+        script: &ScriptVar<F>,
+    ) -> Result<BTreeMap<String, Vec<UInt8<F>>>, SynthesisError> {
+        let (is_match, holes) = self.matches(script)?;
+        is_match.enforce_equal(&Boolean::<F>::TRUE)?;
+        Ok(holes)
+    }
+}
+
+impl<F: PrimeField> AllocVar<ScriptTemplate, F> for ScriptTemplateVar<F> {
+    fn new_variable<T: Borrow<ScriptTemplate>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let template: ScriptTemplate = f().map(|template| template.borrow().clone())?;
+
+        let mut parts: Vec<TemplatePartVar<F>> = Vec::with_capacity(template.parts.len());
+        for part in template.parts.into_iter() {
+            parts.push(match part {
+                TemplatePart::Fixed(bytes) => {
+                    let mut allocated_bytes: Vec<UInt8<F>> = Vec::with_capacity(bytes.len());
+                    for byte in bytes.iter() {
+                        allocated_bytes.push(UInt8::<F>::new_variable(
+                            cs.clone(),
+                            || Ok(byte),
+                            mode,
+                        )?);
+                    }
+                    TemplatePartVar::Fixed(allocated_bytes)
+                }
+                TemplatePart::Hole { name, len } => TemplatePartVar::Hole { name, len },
+            });
+        }
+
+        Ok(Self { parts })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::R1CSVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::script::op_codes::*;
+
+    fn p2pkh_template() -> ScriptTemplate {
+        ScriptTemplate::new(vec![
+            TemplatePart::Fixed(vec![OP_DUP, OP_HASH160, 20]),
+            TemplatePart::Hole {
+                name: "pubkey_hash".to_string(),
+                len: 20,
+            },
+            TemplatePart::Fixed(vec![OP_EQUALVERIFY, OP_CHECKSIG]),
+        ])
+        .unwrap()
+    }
+
+    fn p2pkh_script(pubkey_hash: &[u8]) -> Script {
+        let mut script = vec![OP_DUP, OP_HASH160, 20];
+        script.extend_from_slice(pubkey_hash);
+        script.extend_from_slice(&[OP_EQUALVERIFY, OP_CHECKSIG]);
+        Script(script)
+    }
+
+    #[test]
+    fn test_matches() {
+        let template = p2pkh_template();
+        let script = p2pkh_script(&[7; 20]);
+        let mut other = script.clone();
+        other.0[24] = OP_CHECKSIGVERIFY;
+
+        assert_eq!(template.len(), 25);
+        assert_eq!(
+            template.matches(&script).unwrap()["pubkey_hash"],
+            vec![7; 20]
+        );
+        assert!(template.matches(&other).is_none());
+        assert!(template.matches(&Script(vec![OP_1])).is_none());
+
+        for (mode, s) in [
+            (AllocationMode::Constant, script),
+            (AllocationMode::Input, other),
+        ] {
+            let cs = ConstraintSystem::<F>::new_ref();
+            let template_var =
+                ScriptTemplateVar::<F>::new_variable(cs.clone(), || Ok(template.clone()), mode)
+                    .unwrap();
+            let script_var = ScriptVar::<F>::new_witness(cs.clone(), || Ok(s.clone())).unwrap();
+            let (is_match, holes) = template_var.matches(&script_var).unwrap();
+            assert_eq!(is_match.value().unwrap(), template.matches(&s).is_some());
+            assert_eq!(holes["pubkey_hash"].value().unwrap(), vec![7; 20]);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_enforce_match() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let template_var =
+            ScriptTemplateVar::<F>::new_constant(cs.clone(), p2pkh_template()).unwrap();
+        let mut script = p2pkh_script(&[7; 20]);
+        script.0[0] = OP_NOP;
+        let script_var = ScriptVar::<F>::new_witness(cs.clone(), || Ok(script)).unwrap();
+        template_var.enforce_match(&script_var).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_duplicate_holes() {
+        let hole = TemplatePart::Hole {
+            name: "hash".to_string(),
+            len: 20,
+        };
+        assert!(matches!(
+            ScriptTemplate::new(vec![hole.clone(), hole]),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_matches_wrong_length() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let template_var =
+            ScriptTemplateVar::<F>::new_constant(cs.clone(), p2pkh_template()).unwrap();
+        let script_var =
+            ScriptVar::<F>::new_witness(cs.clone(), || Ok(Script(vec![OP_1]))).unwrap();
+        assert!(matches!(
+            template_var.matches(&script_var),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+}
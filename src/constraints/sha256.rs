@@ -0,0 +1,228 @@
+//! R1CS implementation of Sha256 for data of variable length
+use std::marker::PhantomData;
+
+use ark_crypto_primitives::crh::sha256::constraints::{DigestVar, Sha256Gadget};
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    R1CSVar,
+    alloc::AllocVar,
+    convert::{ToBitsGadget, ToBytesGadget},
+    eq::EqGadget,
+    fields::{FieldVar, fp::FpVar},
+    prelude::Boolean,
+    select::CondSelectGadget,
+    uint8::UInt8,
+    uint32::UInt32,
+};
+use ark_relations::r1cs::Result;
+
+use crate::constraints::var_bytes::VarBytesVar;
+use crate::error::Error;
+use crate::util::{is_less_than, to_fp, to_u64};
+
+/// Initial value of the chaining variables
+const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+/// Additive constants, one per step
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Gadget for calculating Sha256 of a [VarBytesVar].
+///
+/// All the blocks that fit in the maximum length of the data are compressed, and the digest is the chaining
+/// value after the block holding the length of the data, which is selected in the circuit.
+pub struct VarLenSha256Gadget<F: PrimeField>(PhantomData<F>);
+
+impl<F: PrimeField> VarLenSha256Gadget<F> {
+    /// Returns [Error::ConfigMismatch] if the length in bits of `data` may not fit in 32 bits
+    pub fn evaluate(data: &VarBytesVar<F>) -> Result<DigestVar<F>> {
+        // If the length is a constant of the circuit, so is the padding
+        if let FpVar::Constant(len) = &data.len {
+            return Sha256Gadget::digest(&data.bytes[..to_u64(len).unwrap() as usize]);
+        }
+
+        let max_len = data.max_len();
+        if max_len >= 1 << 29 {
+            return Err(Error::ConfigMismatch(format!(
+                "The maximum length: {} is too large, the length in bits must fit in 32 bits",
+                max_len
+            ))
+            .into());
+        }
+        let n_blocks = (max_len + 8) / 64 + 1;
+        let n_bits = (usize::BITS - (64 * n_blocks).leading_zeros()) as usize;
+
+        // The block at index `b` is the final one if and only if `64 * b <= len + 8 < 64 * (b + 1)`
+        let shifted_len = &data.len + FpVar::<F>::constant(F::from(8u64));
+        let is_below = (0..=n_blocks)
+            .map(|b| {
+                is_less_than(
+                    &shifted_len,
+                    &FpVar::<F>::constant(F::from(64 * b as u64)),
+                    n_bits,
+                )
+            })
+            .collect::<Result<Vec<Boolean<F>>>>()?;
+        let is_final: Vec<Boolean<F>> = (0..n_blocks)
+            .map(|b| &!is_below[b].clone() & &is_below[b + 1])
+            .collect();
+
+        // The length in bits, as a big-endian 64-bit number
+        let bit_len = UInt32::<F>::new_witness(data.len.cs(), || {
+            Ok(8 * to_u64(&data.len.value()?).unwrap_or_default() as u32)
+        })?;
+        to_fp(&bit_len)?.enforce_equal(&(&data.len * FpVar::<F>::constant(F::from(8u64))))?;
+        let mut len_bytes: Vec<UInt8<F>> = vec![UInt8::<F>::constant(0); 4];
+        len_bytes.extend(bit_len.to_bytes_le()?.into_iter().rev());
+
+        // Padding: the data is zero after its length, so the padding is added to it bitwise
+        let is_end = data.len_indicator()?;
+        let zero = UInt8::<F>::constant(0);
+        let mut message: Vec<UInt8<F>> = Vec::with_capacity(64 * n_blocks);
+        for i in 0..64 * n_blocks {
+            let mut byte = data.bytes.get(i).cloned().unwrap_or_else(|| zero.clone());
+            if i <= max_len {
+                byte = &byte
+                    | &UInt8::<F>::conditionally_select(
+                        &is_end[i],
+                        &UInt8::<F>::constant(0x80),
+                        &zero,
+                    )?;
+            }
+            if i % 64 >= 56 {
+                byte = &byte
+                    | &UInt8::<F>::conditionally_select(
+                        &is_final[i / 64],
+                        &len_bytes[i % 64 - 56],
+                        &zero,
+                    )?;
+            }
+            message.push(byte);
+        }
+
+        let mut state: [UInt32<F>; 8] = IV.map(UInt32::<F>::constant);
+        let mut digest: [UInt32<F>; 8] = state.clone();
+        for (block, is_final) in message.chunks(64).zip(is_final.iter()) {
+            let words = block
+                .chunks(4)
+                .map(|word| {
+                    UInt32::<F>::from_bytes_le(&word.iter().rev().cloned().collect::<Vec<_>>())
+                })
+                .collect::<Result<Vec<UInt32<F>>>>()?;
+            state = Self::compress(&state, &words)?;
+            for (digest_word, state_word) in digest.iter_mut().zip(state.iter()) {
+                *digest_word =
+                    UInt32::<F>::conditionally_select(is_final, state_word, digest_word)?;
+            }
+        }
+
+        let mut bytes: Vec<UInt8<F>> = Vec::with_capacity(32);
+        for word in digest.iter() {
+            bytes.extend(word.to_bytes_le()?.into_iter().rev());
+        }
+        Ok(DigestVar(bytes))
+    }
+
+    /// Shift `x` right by `by` bits
+    fn shr(x: &UInt32<F>, by: usize) -> Result<UInt32<F>> {
+        let mut bits = x.to_bits_le()?[by..].to_vec();
+        bits.resize(32, Boolean::<F>::FALSE);
+        Ok(UInt32::<F>::from_bits_le(&bits))
+    }
+
+    /// The compression function, processing one block of 16 words
+    fn compress(state: &[UInt32<F>; 8], words: &[UInt32<F>]) -> Result<[UInt32<F>; 8]> {
+        // Message schedule
+        let mut w: Vec<UInt32<F>> = words.to_vec();
+        for t in 16..64 {
+            let s0 =
+                w[t - 15].rotate_right(7) ^ w[t - 15].rotate_right(18) ^ Self::shr(&w[t - 15], 3)?;
+            let s1 =
+                w[t - 2].rotate_right(17) ^ w[t - 2].rotate_right(19) ^ Self::shr(&w[t - 2], 10)?;
+            w.push(UInt32::<F>::wrapping_add_many(&[
+                w[t - 16].clone(),
+                s0,
+                w[t - 7].clone(),
+                s1,
+            ])?);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state.clone();
+        for (k, w) in K.iter().zip(w.iter()) {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (&e & &f) ^ (!&e & &g);
+            let t1 =
+                UInt32::<F>::wrapping_add_many(&[h, s1, ch, UInt32::<F>::constant(*k), w.clone()])?;
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (&a & &b) ^ (&a & &c) ^ (&b & &c);
+            let t2 = UInt32::<F>::wrapping_add_many(&[s0, maj])?;
+            h = g;
+            g = f;
+            f = e;
+            e = UInt32::<F>::wrapping_add_many(&[d, t1.clone()])?;
+            d = c;
+            c = b;
+            b = a;
+            a = UInt32::<F>::wrapping_add_many(&[t1, t2])?;
+        }
+
+        let mut result = state.clone();
+        for (word, value) in result.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *word = UInt32::<F>::wrapping_add_many(&[word.clone(), value])?;
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::Fr as F;
+    use ark_crypto_primitives::crh::sha256::constraints::Sha256Gadget;
+    use ark_r1cs_std::{R1CSVar, uint8::UInt8};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    use crate::constraints::var_bytes::VarBytesVar;
+
+    use super::VarLenSha256Gadget;
+
+    fn test_lengths(max_len: usize) {
+        for len in 0..=max_len {
+            let data: Vec<u8> = (0..len).map(|i| (7 * i + 3) as u8).collect();
+            let cs = ConstraintSystem::<F>::new_ref();
+            let data_var =
+                VarBytesVar::<F>::new_witness(cs.clone(), || Ok(data.clone()), max_len).unwrap();
+            let digest = VarLenSha256Gadget::<F>::evaluate(&data_var).unwrap();
+            let expected = Sha256Gadget::<F>::digest(&UInt8::<F>::constant_vec(&data)).unwrap();
+            assert_eq!(digest.0.value().unwrap(), expected.0.value().unwrap());
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_var_len_sha256() {
+        // The maximum length fits in one block, or needs a second one for the padding
+        test_lengths(10);
+        test_lengths(60);
+        // Two blocks, and the message may end in either of them
+        test_lengths(70);
+    }
+
+    #[test]
+    fn test_constant_length() {
+        let data = VarBytesVar::<F>::constant(b"abc");
+        let digest = VarLenSha256Gadget::<F>::evaluate(&data).unwrap();
+        assert_eq!(
+            hex::encode(digest.0.value().unwrap()),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
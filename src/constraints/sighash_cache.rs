@@ -2,9 +2,10 @@ use std::borrow::Borrow;
 
 use ark_crypto_primitives::crh::sha256::constraints::DigestVar;
 use ark_ff::PrimeField;
-use ark_r1cs_std::{alloc::AllocVar, prelude::AllocationMode};
-use ark_relations::r1cs::{Namespace, SynthesisError};
+use ark_r1cs_std::{R1CSVar, alloc::AllocVar, prelude::AllocationMode};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
 use chain_gang::transaction::sighash::SigHashCache;
+use chain_gang::util::Hash256;
 
 /// R1CS version of [SigHashCache]
 #[derive(Debug, Clone)]
@@ -28,6 +29,23 @@ impl<F: PrimeField> SigHashCacheVar<F> {
             hash_outputs: None,
         }
     }
+
+    /// Return the [SigHashCache] held by `self`
+    pub fn sighash_cache(&self) -> Result<SigHashCache, SynthesisError> {
+        let (hash_prevouts, hash_sequence, hash_outputs) = self.value()?;
+        let mut sighash_cache = SigHashCache::new();
+        if let Some(hash_prevouts) = hash_prevouts {
+            sighash_cache.set_hash_prevouts(hash_prevouts);
+        }
+        if let Some(hash_sequence) = hash_sequence {
+            sighash_cache.set_hash_sequence(hash_sequence);
+        }
+        if let Some(hash_outputs) = hash_outputs {
+            sighash_cache.set_hash_outputs(hash_outputs);
+        }
+
+        Ok(sighash_cache)
+    }
 }
 
 impl<F: PrimeField> AllocVar<SigHashCache, F> for SigHashCacheVar<F> {
@@ -85,3 +103,70 @@ impl<F: PrimeField> AllocVar<SigHashCache, F> for SigHashCacheVar<F> {
         })
     }
 }
+
+/// Convert an optional [DigestVar] into the [Hash256] it holds
+fn digest_value<F: PrimeField>(
+    digest: &Option<DigestVar<F>>,
+) -> Result<Option<Hash256>, SynthesisError> {
+    match digest {
+        Some(digest) => Ok(Some(Hash256(
+            digest
+                .0
+                .value()?
+                .try_into()
+                .map_err(|_| SynthesisError::AssignmentMissing)?,
+        ))),
+        None => Ok(None),
+    }
+}
+
+impl<F: PrimeField> R1CSVar<F> for SigHashCacheVar<F> {
+    /// The values of `(hash_prevouts, hash_sequence, hash_outputs)`.
+    /// Use [SigHashCacheVar::sighash_cache] to get them as a [SigHashCache]
+    type Value = (Option<Hash256>, Option<Hash256>, Option<Hash256>);
+
+    fn cs(&self) -> ConstraintSystemRef<F> {
+        let mut result = ConstraintSystemRef::None;
+        for digest in [&self.hash_prevouts, &self.hash_sequence, &self.hash_outputs]
+            .into_iter()
+            .flatten()
+        {
+            result = digest.0.cs().or(result);
+        }
+        result
+    }
+
+    fn value(&self) -> Result<Self::Value, SynthesisError> {
+        Ok((
+            digest_value(&self.hash_prevouts)?,
+            digest_value(&self.hash_sequence)?,
+            digest_value(&self.hash_outputs)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fq as F;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    #[test]
+    fn test_value() {
+        let mut cache = SigHashCache::new();
+        cache.set_hash_prevouts(Hash256([1; 32]));
+        cache.set_hash_outputs(Hash256([3; 32]));
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let cache_var = SigHashCacheVar::<F>::new_input(cs.clone(), || Ok(cache)).unwrap();
+
+        assert_eq!(
+            cache_var.value().unwrap(),
+            (Some(Hash256([1; 32])), None, Some(Hash256([3; 32])))
+        );
+        let native_cache = cache_var.sighash_cache().unwrap();
+        assert_eq!(native_cache.hash_prevouts(), Some(&Hash256([1; 32])));
+        assert_eq!(native_cache.hash_sequence(), None);
+        assert_eq!(native_cache.hash_outputs(), Some(&Hash256([3; 32])));
+    }
+}
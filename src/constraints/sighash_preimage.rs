@@ -0,0 +1,250 @@
+//! Implementation of [SigHashPreimageVar], R1CS version of the sighash preimage
+use ark_crypto_primitives::crh::sha256::constraints::DigestVar;
+use ark_ff::PrimeField;
+use ark_r1cs_std::{R1CSVar, prelude::ToBytesGadget, uint8::UInt8, uint32::UInt32, uint64::UInt64};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+
+use crate::constraints::{hash256::Hash256Gadget, outpoint::OutPointVar, script::ScriptVar};
+use crate::error::Error;
+use crate::traits::PreSigHashSerialise;
+use crate::util::usize_to_var_int;
+
+/// R1CS version of the sighash preimage, see [Message Digest Algorithm](https://github.com/bitcoin-sv/bitcoin-sv/blob/master/doc/abc/replay-protected-sighash.md#digest-algorithm).
+///
+/// The components of the preimage are exposed as separate variables, so that predicates can constrain them individually.
+/// Use [TxVar::sighash_preimage](crate::constraints::tx::TxVar::sighash_preimage) to construct the preimage of a transaction.
+#[derive(Debug, Clone)]
+pub struct SigHashPreimageVar<F: PrimeField> {
+    /// nVersion
+    pub version: UInt32<F>,
+    /// hashPrevouts
+    pub hash_prevouts: DigestVar<F>,
+    /// hashSequence
+    pub hash_sequence: DigestVar<F>,
+    /// The outpoint being spent
+    pub outpoint: OutPointVar<F>,
+    /// scriptCode
+    pub script_code: ScriptVar<F>,
+    /// The amount of the outpoint being spent
+    pub amount: UInt64<F>,
+    /// nSequence of the input being signed
+    pub sequence: UInt32<F>,
+    /// hashOutputs
+    pub hash_outputs: DigestVar<F>,
+    /// nLocktime
+    pub lock_time: UInt32<F>,
+    /// The sighash type
+    pub sighash_type: UInt32<F>,
+}
+
+impl<F: PrimeField> SigHashPreimageVar<F> {
+    /// The length of the serialised preimage for a script code of `script_code_len` bytes
+    pub fn serialised_len(script_code_len: usize) -> usize {
+        104 + usize_to_var_int(script_code_len).unwrap().len() + script_code_len + 52
+    }
+
+    /// Parse the serialised preimage `bytes`, whose script code is `script_code_len` bytes long.
+    /// No constraint is generated: in particular, the var_int length of the script code is not checked.
+    ///
+    /// Returns [Error::ConfigMismatch] if the length of `bytes` is not [SigHashPreimageVar::serialised_len].
+    pub fn from_bytes(bytes: &[UInt8<F>], script_code_len: usize) -> Result<Self, SynthesisError> {
+        if bytes.len() != Self::serialised_len(script_code_len) {
+            return Err(Error::ConfigMismatch(format!(
+                "The length of the preimage: {} is different from the one expected for a script code of length {}: {}",
+                bytes.len(),
+                script_code_len,
+                Self::serialised_len(script_code_len)
+            ))
+            .into());
+        }
+
+        let script_code_start = 104 + usize_to_var_int(script_code_len).unwrap().len();
+        let tail = &bytes[script_code_start + script_code_len..];
+
+        Ok(Self {
+            version: UInt32::<F>::from_bytes_le(&bytes[..4])?,
+            hash_prevouts: DigestVar(bytes[4..36].to_vec()),
+            hash_sequence: DigestVar(bytes[36..68].to_vec()),
+            outpoint: OutPointVar {
+                prev_tx: DigestVar(bytes[68..100].to_vec()),
+                prev_index: UInt32::<F>::from_bytes_le(&bytes[100..104])?,
+            },
+            script_code: ScriptVar(
+                bytes[script_code_start..script_code_start + script_code_len].to_vec(),
+            ),
+            amount: UInt64::<F>::from_bytes_le(&tail[..8])?,
+            sequence: UInt32::<F>::from_bytes_le(&tail[8..12])?,
+            hash_outputs: DigestVar(tail[12..44].to_vec()),
+            lock_time: UInt32::<F>::from_bytes_le(&tail[44..48])?,
+            sighash_type: UInt32::<F>::from_bytes_le(&tail[48..52])?,
+        })
+    }
+
+    /// Compute the sighash, i.e., the double Sha256 of the serialised preimage
+    pub fn digest(&self) -> Result<DigestVar<F>, SynthesisError> {
+        Hash256Gadget::<F>::evaluate(&self.to_bytes_le()?)
+    }
+}
+
+impl<F: PrimeField> ToBytesGadget<F> for SigHashPreimageVar<F> {
+    /// Serialise the preimage:
+    ///
+    /// `version || hash_prevouts || hash_sequence || outpoint || script_code || amount || sequence || hash_outputs || lock_time || sighash_type`
+    fn to_bytes_le(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        let mut ser: Vec<UInt8<F>> = Vec::new();
+        ser.extend_from_slice(self.version.to_bytes_le()?.as_slice());
+        ser.extend_from_slice(self.hash_prevouts.to_bytes_le()?.as_slice());
+        ser.extend_from_slice(self.hash_sequence.to_bytes_le()?.as_slice());
+        ser.extend_from_slice(self.outpoint.pre_sighash_serialise()?.as_slice());
+        ser.extend_from_slice(self.script_code.pre_sighash_serialise()?.as_slice());
+        ser.extend_from_slice(self.amount.to_bytes_le()?.as_slice());
+        ser.extend_from_slice(self.sequence.to_bytes_le()?.as_slice());
+        ser.extend_from_slice(self.hash_outputs.to_bytes_le()?.as_slice());
+        ser.extend_from_slice(self.lock_time.to_bytes_le()?.as_slice());
+        ser.extend_from_slice(self.sighash_type.to_bytes_le()?.as_slice());
+
+        Ok(ser)
+    }
+}
+
+impl<F: PrimeField> R1CSVar<F> for SigHashPreimageVar<F> {
+    /// The serialised preimage
+    type Value = Vec<u8>;
+
+    fn cs(&self) -> ConstraintSystemRef<F> {
+        let mut result = ConstraintSystemRef::None;
+        result = self.version.cs().or(result);
+        result = self.hash_prevouts.0.cs().or(result);
+        result = self.hash_sequence.0.cs().or(result);
+        result = self.outpoint.cs().or(result);
+        result = self.script_code.cs().or(result);
+        result = self.amount.cs().or(result);
+        result = self.sequence.cs().or(result);
+        result = self.hash_outputs.0.cs().or(result);
+        result = self.lock_time.cs().or(result);
+        result = self.sighash_type.cs().or(result);
+        result
+    }
+
+    fn value(&self) -> Result<Self::Value, SynthesisError> {
+        self.to_bytes_le()?.value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::Fq as F;
+    use ark_r1cs_std::{R1CSVar, alloc::AllocVar, prelude::ToBytesGadget, uint64::UInt64};
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::address::addr_decode;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::network::Network;
+    use chain_gang::script::Script;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::transaction::sighash::{
+        SIGHASH_ALL, SIGHASH_FORKID, SigHashCache, sig_hash_preimage, sighash,
+    };
+    use chain_gang::util::Hash256;
+
+    use crate::constraints::{
+        script::ScriptVar,
+        sighash_cache::SigHashCacheVar,
+        tx::{TxVar, TxVarConfig},
+    };
+
+    use super::SigHashPreimageVar;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    #[test]
+    fn test_sighash_preimage() {
+        let addr = "mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec";
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        let lock_script = p2pkh::create_lock_script(&hash160);
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 1,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xfffffffe,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 100,
+                lock_script: lock_script.clone(),
+            }],
+            lock_time: 800_000,
+        };
+        let sighash_flags = SIGHASH_ALL | SIGHASH_FORKID;
+        let expected_preimage = sig_hash_preimage(
+            &tx,
+            0,
+            &lock_script.0,
+            1000,
+            sighash_flags,
+            &mut SigHashCache::new(),
+        )
+        .unwrap();
+        let expected_sighash = sighash(
+            &tx,
+            0,
+            &lock_script.0,
+            1000,
+            sighash_flags,
+            &mut SigHashCache::new(),
+        )
+        .unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx.clone())).unwrap();
+        let preimage = tx_var
+            .sighash_preimage(
+                0,
+                &ScriptVar::<F>::new_input(cs.clone(), || Ok(lock_script)).unwrap(),
+                &UInt64::<F>::new_input(cs.clone(), || Ok(1000)).unwrap(),
+                &sighash_flags,
+                &mut SigHashCacheVar::<F>::new(),
+            )
+            .unwrap();
+
+        assert_eq!(preimage.value().unwrap(), expected_preimage);
+        assert_eq!(
+            preimage.digest().unwrap().value().unwrap(),
+            expected_sighash.0.to_vec()
+        );
+        // The components are exposed individually
+        assert_eq!(preimage.version.value().unwrap(), tx.version);
+        assert_eq!(preimage.outpoint.value().unwrap(), tx.inputs[0].prev_output);
+        assert_eq!(preimage.amount.value().unwrap(), 1000);
+        assert_eq!(preimage.sequence.value().unwrap(), tx.inputs[0].sequence);
+        assert_eq!(preimage.lock_time.value().unwrap(), tx.lock_time);
+        assert_eq!(preimage.sighash_type.value().unwrap(), sighash_flags as u32);
+
+        // Parse the serialised preimage
+        let preimage_bytes = preimage.to_bytes_le().unwrap();
+        assert_eq!(
+            preimage_bytes.len(),
+            SigHashPreimageVar::<F>::serialised_len(0x19)
+        );
+        let parsed = SigHashPreimageVar::<F>::from_bytes(&preimage_bytes, 0x19).unwrap();
+        assert_eq!(parsed.value().unwrap(), expected_preimage);
+        assert_eq!(parsed.amount.value().unwrap(), 1000);
+        // The length of the script code does not match the length of the preimage
+        assert!(matches!(
+            SigHashPreimageVar::<F>::from_bytes(&preimage_bytes, 0x18),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+}
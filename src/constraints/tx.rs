@@ -4,25 +4,35 @@ use ark_ff::PrimeField;
 use ark_r1cs_std::{
     R1CSVar,
     alloc::AllocVar,
+    fields::{FieldVar, fp::FpVar},
     prelude::{AllocationMode, ToBytesGadget},
     uint8::UInt8,
     uint32::UInt32,
     uint64::UInt64,
 };
 
-use crate::constraints::{script::ScriptVar, txin::TxInVar, txout::TxOutVar};
+use crate::constraints::{
+    outpoint::OutPointVar,
+    script::ScriptVar,
+    txin::TxInVar,
+    txout::TxOutVar,
+    witness::{SegwitTx, WitnessVar},
+};
+use crate::error::Error;
 use crate::traits::PreSigHashSerialise;
-use crate::util::usize_to_var_int;
+use crate::util::{to_fp, to_fp_bits, usize_to_var_int};
 use chain_gang::messages::Tx;
 
 use ark_relations::r1cs::{Namespace, SynthesisError};
-use std::{borrow::Borrow, marker::PhantomData};
+use std::{borrow::Borrow, fmt, marker::PhantomData};
 
 use crate::constraints::hash256::Hash256Gadget;
 use crate::constraints::sighash_cache::SigHashCacheVar;
+use crate::constraints::sighash_preimage::SigHashPreimageVar;
 
 use ark_r1cs_std::boolean::Boolean;
 use ark_r1cs_std::eq::EqGadget;
+use ark_r1cs_std::select::CondSelectGadget;
 
 use ark_relations::r1cs::ConstraintSystemRef;
 
@@ -45,9 +55,199 @@ pub trait TxVarConfig {
     const LEN_UNLOCK_SCRIPTS: &[usize];
     /// Length of locking scripts
     const LEN_LOCK_SCRIPTS: &[usize];
+    /// Lengths of the items of the witness stacks, one stack per input, for SegWit transactions.
+    /// `None`, the default, for transactions without witness data.
+    const LEN_WITNESS_ITEMS: Option<&[&[usize]]> = None;
+    /// Whether to enforce at allocation that the amounts of the outputs are in the valid monetary range,
+    /// see [TxOutVar::enforce_money_range]. `false` by default.
+    const ENFORCE_MONEY_RANGE: bool = false;
+}
+
+/// Check the invariants of a [TxVarConfig]: there must be one unlocking script length per input,
+/// one locking script length per output, and one witness stack per input if the transaction has witnesses.
+///
+/// The function is `const`, so that the check can be performed at compile time, see [assert_tx_var_config](crate::assert_tx_var_config).
+pub const fn check_tx_var_config<P: TxVarConfig>() {
+    assert!(
+        P::LEN_UNLOCK_SCRIPTS.len() == P::N_INPUTS,
+        "P::LEN_UNLOCK_SCRIPTS.len() is different from P::N_INPUTS"
+    );
+    assert!(
+        P::LEN_LOCK_SCRIPTS.len() == P::N_OUTPUTS,
+        "P::LEN_LOCK_SCRIPTS.len() is different from P::N_OUTPUTS"
+    );
+    if let Some(len_witness_items) = P::LEN_WITNESS_ITEMS {
+        assert!(
+            len_witness_items.len() == P::N_INPUTS,
+            "P::LEN_WITNESS_ITEMS.len() is different from P::N_INPUTS"
+        );
+    }
+}
+
+/// The structure of a transaction, i.e., the data set by a [TxVarConfig], known at runtime.
+///
+/// It can be derived from a transaction with [DynTxVarConfig::from_tx], e.g., to find the [TxVarConfig] matching
+/// the transaction: its [Display](fmt::Display) implementation prints the corresponding implementation of
+/// [TxVarConfig].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DynTxVarConfig {
+    pub n_inputs: usize,
+    pub n_outputs: usize,
+    pub len_unlock_scripts: Vec<usize>,
+    pub len_lock_scripts: Vec<usize>,
+}
+
+impl DynTxVarConfig {
+    /// The structure of `tx`
+    pub fn from_tx(tx: &Tx) -> Self {
+        Self {
+            n_inputs: tx.inputs.len(),
+            n_outputs: tx.outputs.len(),
+            len_unlock_scripts: tx
+                .inputs
+                .iter()
+                .map(|input| input.unlock_script.0.len())
+                .collect(),
+            len_lock_scripts: tx
+                .outputs
+                .iter()
+                .map(|output| output.lock_script.0.len())
+                .collect(),
+        }
+    }
+
+    /// The structure set in `P`
+    pub fn from_config<P: TxVarConfig>() -> Self {
+        Self {
+            n_inputs: P::N_INPUTS,
+            n_outputs: P::N_OUTPUTS,
+            len_unlock_scripts: P::LEN_UNLOCK_SCRIPTS.to_vec(),
+            len_lock_scripts: P::LEN_LOCK_SCRIPTS.to_vec(),
+        }
+    }
+
+    /// Check that `self` is the structure set in `P`.
+    ///
+    /// Returns [Error::ConfigMismatch] describing the first mismatch.
+    pub fn check<P: TxVarConfig>(&self) -> Result<(), Error> {
+        if self.n_inputs != P::N_INPUTS || self.n_outputs != P::N_OUTPUTS {
+            return Err(Error::ConfigMismatch(format!(
+                "The transaction has {} inputs and {} outputs, while the parameters set {} inputs and {} outputs",
+                self.n_inputs,
+                self.n_outputs,
+                P::N_INPUTS,
+                P::N_OUTPUTS
+            )));
+        }
+        for (i, (len, expected)) in self
+            .len_unlock_scripts
+            .iter()
+            .zip(P::LEN_UNLOCK_SCRIPTS.iter())
+            .enumerate()
+        {
+            if len != expected {
+                return Err(Error::ConfigMismatch(format!(
+                    "The length of the unlocking script of input {}: {} is different from the one set in the parameters: {}",
+                    i, len, expected
+                )));
+            }
+        }
+        for (i, (len, expected)) in self
+            .len_lock_scripts
+            .iter()
+            .zip(P::LEN_LOCK_SCRIPTS.iter())
+            .enumerate()
+        {
+            if len != expected {
+                return Err(Error::ConfigMismatch(format!(
+                    "The length of the locking script of output {}: {} is different from the one set in the parameters: {}",
+                    i, len, expected
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Print the implementation of [TxVarConfig] for a struct named `Config`
+impl fmt::Display for DynTxVarConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |lengths: &[usize]| {
+            lengths
+                .iter()
+                .map(|len| len.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        writeln!(f, "impl TxVarConfig for Config {{")?;
+        writeln!(f, "    const N_INPUTS: usize = {};", self.n_inputs)?;
+        writeln!(f, "    const N_OUTPUTS: usize = {};", self.n_outputs)?;
+        writeln!(
+            f,
+            "    const LEN_UNLOCK_SCRIPTS: &[usize] = &[{}];",
+            list(&self.len_unlock_scripts)
+        )?;
+        writeln!(
+            f,
+            "    const LEN_LOCK_SCRIPTS: &[usize] = &[{}];",
+            list(&self.len_lock_scripts)
+        )?;
+        write!(f, "}}")
+    }
+}
+
+/// Check that `tx` has the structure set in the [TxVarConfig]: the number of inputs and outputs, and the lengths
+/// of the unlocking and locking scripts, see [DynTxVarConfig::check].
+///
+/// Returns [Error::ConfigMismatch] describing the first mismatch, together with the [TxVarConfig] matching `tx`.
+pub fn check_tx<P: TxVarConfig>(tx: &Tx) -> Result<(), Error> {
+    let config = DynTxVarConfig::from_tx(tx);
+    config.check::<P>().map_err(|error| match error {
+        Error::ConfigMismatch(reason) => Error::ConfigMismatch(format!(
+            "{}. The configuration matching the transaction is:\n{}",
+            reason, config
+        )),
+        error => error,
+    })
+}
+
+/// Check that the witness stacks of `tx` have the structure set in `P::LEN_WITNESS_ITEMS`, and that the rest of
+/// the transaction has the structure set in `P`, see [check_tx].
+///
+/// Returns [Error::ConfigMismatch] describing the first mismatch.
+pub fn check_segwit_tx<P: TxVarConfig>(tx: &SegwitTx) -> Result<(), Error> {
+    check_tx::<P>(&tx.tx)?;
+    let len_witness_items = P::LEN_WITNESS_ITEMS.unwrap_or(&[]);
+    if tx.witnesses.len() != len_witness_items.len() {
+        return Err(Error::ConfigMismatch(format!(
+            "The transaction has {} witness stacks, while the parameters set {}",
+            tx.witnesses.len(),
+            len_witness_items.len()
+        )));
+    }
+    for (i, (witness, expected)) in tx
+        .witnesses
+        .iter()
+        .zip(len_witness_items.iter())
+        .enumerate()
+    {
+        let lengths: Vec<usize> = witness.iter().map(|item| item.len()).collect();
+        if lengths != *expected {
+            return Err(Error::ConfigMismatch(format!(
+                "The lengths of the witness items of input {}: {:?} are different from the ones set in the parameters: {:?}",
+                i, lengths, expected
+            )));
+        }
+    }
+
+    Ok(())
 }
 
 /// R1CS version of [Tx]
+///
+/// If `P` sets [TxVarConfig::LEN_WITNESS_ITEMS], the witness stacks of the inputs are allocated as well,
+/// see [TxVar::new_segwit_variable]. Otherwise, `witnesses` is empty.
 #[derive(Debug)]
 pub struct TxVar<F: PrimeField, P: TxVarConfig + Clone> {
     _config: PhantomData<P>,
@@ -55,6 +255,7 @@ pub struct TxVar<F: PrimeField, P: TxVarConfig + Clone> {
     pub inputs: Vec<TxInVar<F>>,
     pub outputs: Vec<TxOutVar<F>>,
     pub lock_time: UInt32<F>,
+    pub witnesses: Vec<WitnessVar<F>>,
 }
 
 impl<F: PrimeField, P: TxVarConfig + Clone> Clone for TxVar<F, P> {
@@ -65,19 +266,403 @@ impl<F: PrimeField, P: TxVarConfig + Clone> Clone for TxVar<F, P> {
             inputs: self.inputs.clone(),
             outputs: self.outputs.clone(),
             lock_time: self.lock_time.clone(),
+            witnesses: self.witnesses.clone(),
         }
     }
 }
 
 impl<F: PrimeField, P: TxVarConfig + Clone> TxVar<F, P> {
+    /// Compute the field elements allocated by [TxVar::new_input] for `tx`.
+    /// Each byte is allocated as its bits, see [to_fp_bits].
+    pub fn public_input(tx: &Tx) -> Vec<F> {
+        let mut input = to_fp_bits(&tx.version.to_le_bytes());
+        for txin in tx.inputs.iter() {
+            input.extend_from_slice(&TxInVar::<F>::public_input(txin));
+        }
+        for txout in tx.outputs.iter() {
+            input.extend_from_slice(&TxOutVar::<F>::public_input(txout));
+        }
+        input.extend_from_slice(&to_fp_bits(&tx.lock_time.to_le_bytes()));
+        input
+    }
+
+    /// Compute the field elements allocated by [TxVar::new_segwit_variable] in [AllocationMode::Input] for `tx`:
+    /// the ones of [TxVar::public_input], followed by the items of the witness stacks
+    pub fn segwit_public_input(tx: &SegwitTx) -> Vec<F> {
+        let mut input = Self::public_input(&tx.tx);
+        for witness in tx.witnesses.iter() {
+            input.extend_from_slice(&WitnessVar::<F>::public_input(witness));
+        }
+        input
+    }
+
+    /// Allocate a SegWit transaction, i.e., a transaction together with the witness stacks of its inputs.
+    ///
+    /// Returns the error of [check_segwit_tx] if `tx` does not have the structure set in `P`.
+    pub fn new_segwit_variable<T: Borrow<SegwitTx>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let tx: SegwitTx = f().map(|tx| tx.borrow().clone())?;
+
+        /*
+         *
+         * Validate configuration
+         *
+         */
+
+        // Check the invariants of the configuration at compile time
+        const { check_tx_var_config::<P>() };
+
+        // Check that the transaction has the structure set in the parameters
+        check_segwit_tx::<P>(&tx)?;
+
+        /*
+         *
+         * Allocation
+         *
+         */
+
+        let SegwitTx { tx, witnesses } = tx;
+        // Version
+        let version: UInt32<F> = UInt32::<F>::new_variable(cs.clone(), || Ok(tx.version), mode)?;
+        // TxIns
+        let inputs: Vec<TxInVar<F>> =
+            TxInVar::new_variable_vec(cs.clone(), || Ok(tx.inputs), mode)?;
+        // TxOuts
+        let outputs: Vec<TxOutVar<F>> =
+            TxOutVar::new_variable_vec(cs.clone(), || Ok(tx.outputs), mode)?;
+        // Locktime
+        let lock_time: UInt32<F> =
+            UInt32::<F>::new_variable(cs.clone(), || Ok(tx.lock_time), mode)?;
+        // Witnesses
+        let witnesses: Vec<WitnessVar<F>> =
+            WitnessVar::new_variable_vec(cs.clone(), || Ok(witnesses), mode)?;
+
+        if P::ENFORCE_MONEY_RANGE {
+            for output in outputs.iter() {
+                output.enforce_money_range()?;
+            }
+        }
+
+        Ok(Self {
+            _config: PhantomData,
+            version,
+            inputs,
+            outputs,
+            lock_time,
+            witnesses,
+        })
+    }
+
+    /// The length of the serialisation with the witness data, see [TxVar::segwit_serialise].
+    /// It is [TxVar::serialised_len] if the transactions described by `P` carry no witness data.
+    pub fn segwit_serialised_len() -> usize {
+        if !Self::has_witness() {
+            return Self::serialised_len();
+        }
+
+        let var_int_len = |length: usize| usize_to_var_int(length).unwrap().len();
+        let witnesses_len: usize = P::LEN_WITNESS_ITEMS
+            .unwrap_or(&[])
+            .iter()
+            .map(|items| {
+                var_int_len(items.len())
+                    + items
+                        .iter()
+                        .map(|len| var_int_len(*len) + len)
+                        .sum::<usize>()
+            })
+            .sum();
+
+        // Marker, flag and witnesses
+        Self::serialised_len() + 2 + witnesses_len
+    }
+
+    /// Allocate a transaction from its raw serialisation, see [TxVar::segwit_serialise], and parse it in the circuit.
+    ///
+    /// As the structure of the transaction is set in `P`, so is the position of each field in the serialisation:
+    /// the bytes encoding the numbers of inputs and outputs, the lengths of the scripts and of the witness items,
+    /// and the SegWit marker and flag are enforced to be the constants set in `P`.
+    ///
+    /// Returns [Error::ConfigMismatch] if the length of the serialisation is not [TxVar::segwit_serialised_len].
+    pub fn new_from_bytes(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<Vec<u8>, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        // Check the invariants of the configuration at compile time
+        const { check_tx_var_config::<P>() };
+
+        let serialisation: Vec<u8> = f()?;
+        if serialisation.len() != Self::segwit_serialised_len() {
+            return Err(Error::ConfigMismatch(format!(
+                "The length of the serialisation: {} is different from the one set in the parameters: {}",
+                serialisation.len(),
+                Self::segwit_serialised_len()
+            ))
+            .into());
+        }
+        let mut bytes: Vec<UInt8<F>> = Vec::with_capacity(serialisation.len());
+        for byte in serialisation.iter() {
+            bytes.push(UInt8::<F>::new_variable(cs.clone(), || Ok(byte), mode)?);
+        }
+
+        let mut offset: usize = 0;
+        let mut take = |n: usize| -> Vec<UInt8<F>> {
+            let chunk = bytes[offset..offset + n].to_vec();
+            offset += n;
+            chunk
+        };
+        let var_int = |length: usize| UInt8::<F>::constant_vec(&usize_to_var_int(length).unwrap());
+
+        // Version, marker and flag
+        let version = UInt32::<F>::from_bytes_le(&take(4))?;
+        if Self::has_witness() {
+            take(2).enforce_equal(&UInt8::<F>::constant_vec(&[0x00, 0x01]))?;
+        }
+        // TxIns
+        let n_inputs = var_int(P::N_INPUTS);
+        take(n_inputs.len()).enforce_equal(&n_inputs)?;
+        let mut inputs: Vec<TxInVar<F>> = Vec::with_capacity(P::N_INPUTS);
+        for len in P::LEN_UNLOCK_SCRIPTS.iter() {
+            let prev_output = OutPointVar {
+                prev_tx: DigestVar(take(32)),
+                prev_index: UInt32::<F>::from_bytes_le(&take(4))?,
+            };
+            let script_len = var_int(*len);
+            take(script_len.len()).enforce_equal(&script_len)?;
+            let unlock_script = ScriptVar(take(*len));
+            let sequence = UInt32::<F>::from_bytes_le(&take(4))?;
+            inputs.push(TxInVar {
+                prev_output,
+                unlock_script,
+                sequence,
+            });
+        }
+        // TxOuts
+        let n_outputs = var_int(P::N_OUTPUTS);
+        take(n_outputs.len()).enforce_equal(&n_outputs)?;
+        let mut outputs: Vec<TxOutVar<F>> = Vec::with_capacity(P::N_OUTPUTS);
+        for len in P::LEN_LOCK_SCRIPTS.iter() {
+            let satoshis = UInt64::<F>::from_bytes_le(&take(8))?;
+            let script_len = var_int(*len);
+            take(script_len.len()).enforce_equal(&script_len)?;
+            outputs.push(TxOutVar {
+                satoshis,
+                lock_script: ScriptVar(take(*len)),
+            });
+        }
+        // Witnesses: if no input carries witness data, the stacks are empty and not serialised
+        let mut witnesses: Vec<WitnessVar<F>> = Vec::new();
+        for items in P::LEN_WITNESS_ITEMS.unwrap_or(&[]).iter() {
+            let mut stack: Vec<Vec<UInt8<F>>> = Vec::with_capacity(items.len());
+            if Self::has_witness() {
+                let n_items = var_int(items.len());
+                take(n_items.len()).enforce_equal(&n_items)?;
+                for len in items.iter() {
+                    let item_len = var_int(*len);
+                    take(item_len.len()).enforce_equal(&item_len)?;
+                    stack.push(take(*len));
+                }
+            }
+            witnesses.push(WitnessVar(stack));
+        }
+        // Locktime
+        let lock_time = UInt32::<F>::from_bytes_le(&take(4))?;
+
+        if P::ENFORCE_MONEY_RANGE {
+            for output in outputs.iter() {
+                output.enforce_money_range()?;
+            }
+        }
+
+        Ok(Self {
+            _config: PhantomData,
+            version,
+            inputs,
+            outputs,
+            lock_time,
+            witnesses,
+        })
+    }
+
+    /// Check whether the transactions described by `P` carry witness data: if not, they are serialised in the
+    /// legacy format, and their wtxid is their txid
+    pub fn has_witness() -> bool {
+        P::LEN_WITNESS_ITEMS.is_some_and(|len_witness_items| {
+            len_witness_items.iter().any(|items| !items.is_empty())
+        })
+    }
+
+    /// Serialise `Self` with the witness data, see [SegwitTx::serialise]
+    pub fn segwit_serialise(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        if !Self::has_witness() {
+            return self.serialise();
+        }
+
+        let legacy = self.serialise()?;
+        let lock_time_start = legacy.len() - 4;
+        let mut ser: Vec<UInt8<F>> = Vec::with_capacity(legacy.len() + 2);
+        // Version
+        ser.extend_from_slice(&legacy[..4]);
+        // Marker and flag
+        ser.extend_from_slice(&UInt8::<F>::constant_vec(&[0x00, 0x01]));
+        // Inputs and outputs
+        ser.extend_from_slice(&legacy[4..lock_time_start]);
+        for witness in self.witnesses.iter() {
+            ser.extend(witness.to_bytes_le()?);
+        }
+        // Locktime
+        ser.extend_from_slice(&legacy[lock_time_start..]);
+        Ok(ser)
+    }
+
+    /// Calculate the wtxid of `Self`, see [SegwitTx::wtxid]
+    pub fn wtxid(&self) -> Result<DigestVar<F>, SynthesisError> {
+        Hash256Gadget::<F>::evaluate(self.segwit_serialise()?.as_slice())
+    }
+
+    /// The length of the serialisation of the transaction, see [ToBytesGadget::to_bytes_le].
+    /// As the lengths of the scripts are set in `P`, the length is a constant of the circuit.
+    pub fn serialised_len() -> usize {
+        let var_int_len = |length: usize| usize_to_var_int(length).unwrap().len();
+        // Outpoint, unlocking script and sequence
+        let inputs_len: usize = P::LEN_UNLOCK_SCRIPTS
+            .iter()
+            .map(|len| 36 + var_int_len(*len) + len + 4)
+            .sum();
+        // Amount and locking script
+        let outputs_len: usize = P::LEN_LOCK_SCRIPTS
+            .iter()
+            .map(|len| 8 + var_int_len(*len) + len)
+            .sum();
+
+        4 + var_int_len(P::N_INPUTS) + inputs_len + var_int_len(P::N_OUTPUTS) + outputs_len + 4
+    }
+
+    /// The input at `index`, or [Error::IndexOutOfBounds] if there is no such input
+    pub fn input(&self, index: usize) -> Result<&TxInVar<F>, Error> {
+        self.inputs.get(index).ok_or_else(|| {
+            Error::IndexOutOfBounds(format!(
+                "Input index: {} is larger than the number of inputs: {}",
+                index,
+                self.inputs.len()
+            ))
+        })
+    }
+
+    /// The output at `index`, or [Error::IndexOutOfBounds] if there is no such output
+    pub fn output(&self, index: usize) -> Result<&TxOutVar<F>, Error> {
+        self.outputs.get(index).ok_or_else(|| {
+            Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of outputs: {}",
+                index,
+                self.outputs.len()
+            ))
+        })
+    }
+
+    /// The sum of the amounts of the outputs, as an R1CS variable. It is smaller than `2^72` as long as there
+    /// are fewer than `2^8` outputs.
+    pub fn total_output_amount(&self) -> Result<FpVar<F>, SynthesisError> {
+        let mut total = FpVar::<F>::zero();
+        for output in self.outputs.iter() {
+            total += to_fp(&output.satoshis)?;
+        }
+        Ok(total)
+    }
+
+    /// Find the first output whose locking script is `lock_script`. Returns the selector of the output, i.e.,
+    /// the [Boolean]s that are `TRUE` exactly at the index of the output found, together with its amount.
+    /// If there is no such output, all the selectors are `FALSE` and the amount is zero.
+    ///
+    /// As the lengths of the scripts are set in `P`, only the outputs whose locking script has the length of
+    /// `lock_script` are compared.
+    pub fn find_output_paying_to(
+        &self,
+        lock_script: &ScriptVar<F>,
+    ) -> Result<(Vec<Boolean<F>>, FpVar<F>), SynthesisError> {
+        let mut selector: Vec<Boolean<F>> = Vec::with_capacity(self.outputs.len());
+        let mut is_found = Boolean::<F>::FALSE;
+        let mut amount = FpVar::<F>::zero();
+        for output in self.outputs.iter() {
+            let is_match = if output.lock_script.0.len() == lock_script.0.len() {
+                output.lock_script.is_eq(lock_script)?
+            } else {
+                Boolean::<F>::FALSE
+            };
+            let is_selected = &is_match & &!is_found.clone();
+            is_found = &is_found | &is_match;
+            amount += FpVar::<F>::from(is_selected.clone()) * to_fp(&output.satoshis)?;
+            selector.push(is_selected);
+        }
+        Ok((selector, amount))
+    }
+
+    /// Serialise `Self` as in the consensus encoding of [Tx], the one hashed for the txid.
+    /// As the lengths of the scripts are set in `P`, the layout of the serialisation is fixed, so predicates can
+    /// slice it at known offsets. The witness data of SegWit transactions is excluded, see [TxVar::segwit_serialise].
+    pub fn serialise(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        // Var Int length of inputs, outputs, and locking scripts
+        let var_int_n_inputs = usize_to_var_int(P::N_INPUTS).unwrap();
+        let var_int_n_outputs = usize_to_var_int(P::N_OUTPUTS).unwrap();
+
+        // Serialisation
+        let mut ser: Vec<UInt8<F>> = Vec::new();
+        ser.extend_from_slice(self.version.to_bytes_le()?.as_slice());
+        ser.extend_from_slice(
+            var_int_n_inputs
+                .iter()
+                .map(|el| UInt8::<F>::constant(*el))
+                .collect::<Vec<UInt8<F>>>()
+                .as_slice(),
+        );
+        for input in self.inputs.iter() {
+            ser.extend_from_slice(input.to_bytes_le()?.as_slice());
+        }
+        ser.extend_from_slice(
+            var_int_n_outputs
+                .iter()
+                .map(|el| UInt8::<F>::constant(*el))
+                .collect::<Vec<UInt8<F>>>()
+                .as_slice(),
+        );
+        for output in self.outputs.iter() {
+            ser.extend_from_slice(output.to_bytes_le()?.as_slice());
+        }
+        ser.extend_from_slice(self.lock_time.to_bytes_le()?.as_slice());
+        Ok(ser)
+    }
+
     /// Calculate the txid of `Self`
     pub fn txid(&self) -> Result<DigestVar<F>, SynthesisError> {
-        Hash256Gadget::<F>::evaluate(self.to_bytes_le()?.as_slice())
+        Hash256Gadget::<F>::evaluate(self.serialise()?.as_slice())
+    }
+
+    /// Check whether `Self` is a coinbase transaction: it has a single input, which is a coinbase input,
+    /// see [TxInVar::is_coinbase]
+    pub fn is_coinbase(&self) -> Result<Boolean<F>, SynthesisError> {
+        if self.inputs.len() != 1 {
+            return Ok(Boolean::<F>::FALSE);
+        }
+        self.inputs[0].is_coinbase()
     }
+
     /// Compute the serialisation of [Tx] for `pre_sighash` calculation.
     /// See [Message Digest Algorithm](https://github.com/bitcoin-sv/bitcoin-sv/blob/master/doc/abc/replay-protected-sighash.md#digest-algorithm) for a description of the algorithm.
     ///
     /// **Note**: The function assumes that `prev_lock_script` has already been modified to handle `OP_CODESEPARATOR`.
+    ///
+    /// If `SIGHASH_FORKID` is not set in `sighash_flags`, the legacy serialisation is computed instead,
+    /// see [TxVar::legacy_pre_sighash_serialise].
     pub fn pre_sighash_serialise(
         &self,
         n_input: usize,
@@ -86,101 +671,207 @@ impl<F: PrimeField, P: TxVarConfig + Clone> TxVar<F, P> {
         sighash_flags: &u8,
         cache: &mut SigHashCacheVar<F>,
     ) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        if sighash_flags & SIGHASH_FORKID == 0 {
+            return self.legacy_pre_sighash_serialise(n_input, prev_lock_script, sighash_flags);
+        }
+
+        self.sighash_preimage(n_input, prev_lock_script, prev_amount, sighash_flags, cache)?
+            .to_bytes_le()
+    }
+
+    /// Compute the [SigHashPreimageVar] of the input at `n_input`, exposing the components of the preimage.
+    /// See [Message Digest Algorithm](https://github.com/bitcoin-sv/bitcoin-sv/blob/master/doc/abc/replay-protected-sighash.md#digest-algorithm) for a description of the algorithm.
+    ///
+    /// **Note**: The function assumes that `prev_lock_script` has already been modified to handle `OP_CODESEPARATOR`.
+    /// The function returns an error if `SIGHASH_FORKID` is not set in `sighash_flags`, as the legacy algorithm has no
+    /// such preimage, or if there is no input at `n_input`.
+    pub fn sighash_preimage(
+        &self,
+        n_input: usize,
+        prev_lock_script: &ScriptVar<F>,
+        prev_amount: &UInt64<F>,
+        sighash_flags: &u8,
+        cache: &mut SigHashCacheVar<F>,
+    ) -> Result<SigHashPreimageVar<F>, SynthesisError> {
+        if sighash_flags & SIGHASH_FORKID == 0 {
+            return Err(Error::ConfigMismatch(format!(
+                "The sighash flags: {} do not have SIGHASH_FORKID set",
+                sighash_flags
+            ))
+            .into());
+        }
+        self.check_input_index(n_input)?;
+
         // Handle sighash flags
         let base_flags = sighash_flags & 31;
         let anyone_can_pay = sighash_flags & SIGHASH_ANYONECANPAY != 0;
 
-        // 1. Serialised version
-        let version: Vec<UInt8<F>> = self.version.to_bytes_le()?;
-        // 2. HashPrevOut
-        // The first condition to be checked is that the cache is None.
-        // If it is, then we either compute or set the value. Otherwise, we do nothing.
-        if cache.hash_prevouts.is_none() {
-            if !anyone_can_pay {
+        // 1. HashPrevOut
+        // If `SIGHASH_ANYONECANPAY` is set, hash_prevouts is zero and the cache is not used.
+        // Otherwise, the value in the cache is used if present, and it is computed and cached if not.
+        let hash_prevouts: DigestVar<F> = if anyone_can_pay {
+            DigestVar(vec![UInt8::<F>::constant(0); 32])
+        } else {
+            if cache.hash_prevouts.is_none() {
                 let mut s: Vec<UInt8<F>> = Vec::new();
                 for input in self.inputs.iter() {
                     s.extend_from_slice(input.prev_output.pre_sighash_serialise()?.as_slice());
                 }
                 cache.hash_prevouts = Some(Hash256Gadget::<F>::evaluate(&s)?);
-            } else {
-                cache.hash_prevouts = Some(DigestVar(vec![UInt8::<F>::constant(0); 32]));
             }
+            cache.hash_prevouts.clone().unwrap()
         };
-        // 3. HashSequence
-        // The first condition to be checked is that the cache is None.
-        // If it is, then we either compute or set the value. Otherwise, we do nothing.
-        if cache.hash_sequence.is_none() {
-            if !anyone_can_pay && base_flags != SIGHASH_SINGLE && base_flags != SIGHASH_NONE {
-                let mut s: Vec<UInt8<F>> = Vec::new();
-                for input in self.inputs.iter() {
-                    s.extend_from_slice(input.sequence.to_bytes_le()?.as_slice());
+        // 2. HashSequence
+        // If `SIGHASH_ANYONECANPAY`, `SIGHASH_SINGLE` or `SIGHASH_NONE` is set, hash_sequence is zero and the cache is not used.
+        // Otherwise, the value in the cache is used if present, and it is computed and cached if not.
+        let hash_sequence: DigestVar<F> =
+            if anyone_can_pay || base_flags == SIGHASH_SINGLE || base_flags == SIGHASH_NONE {
+                DigestVar(vec![UInt8::<F>::constant(0); 32])
+            } else {
+                if cache.hash_sequence.is_none() {
+                    let mut s: Vec<UInt8<F>> = Vec::new();
+                    for input in self.inputs.iter() {
+                        s.extend_from_slice(input.sequence.to_bytes_le()?.as_slice());
+                    }
+                    cache.hash_sequence = Some(Hash256Gadget::<F>::evaluate(&s)?);
                 }
-                cache.hash_sequence = Some(Hash256Gadget::<F>::evaluate(&s)?);
+                cache.hash_sequence.clone().unwrap()
+            };
+        // 3. HashOutputs
+        // If the base flag is `SIGHASH_SINGLE`, hash_outputs is the hash of the output at `n_input` (zero if there is no such output).
+        // If the base flag is `SIGHASH_NONE`, hash_outputs is zero. In both cases the cache is not used.
+        // Otherwise, the value in the cache is used if present, and it is computed and cached if not.
+        let hash_outputs: DigestVar<F> = if base_flags == SIGHASH_SINGLE {
+            if n_input < self.outputs.len() {
+                Hash256Gadget::<F>::evaluate(&self.outputs[n_input].pre_sighash_serialise()?)?
             } else {
-                cache.hash_sequence = Some(DigestVar(vec![UInt8::<F>::constant(0); 32]));
+                DigestVar(vec![UInt8::<F>::constant(0); 32])
             }
-        };
-        // 4. Input specific part
-        let input_specific_serialisation =
-            self.inputs[n_input].pre_sighash_serialise(prev_lock_script, prev_amount)?;
-        // 5. HashOutputs
-        // The first condition to be checked is that the cache is None.
-        // If it is, then we either compute or set the value. Otherwise, we do nothing.
-        if cache.hash_outputs.is_none() {
-            if base_flags != SIGHASH_SINGLE && base_flags != SIGHASH_NONE {
+        } else if base_flags == SIGHASH_NONE {
+            DigestVar(vec![UInt8::<F>::constant(0); 32])
+        } else {
+            if cache.hash_outputs.is_none() {
                 let mut s: Vec<UInt8<F>> = Vec::new();
                 for output in self.outputs.iter() {
                     s.extend_from_slice(output.pre_sighash_serialise()?.as_slice());
                 }
                 cache.hash_outputs = Some(Hash256Gadget::<F>::evaluate(&s)?);
-            } else if base_flags == SIGHASH_SINGLE && n_input < self.outputs.len() {
-                cache.hash_outputs = Some(Hash256Gadget::<F>::evaluate(
-                    &self.outputs[n_input].pre_sighash_serialise()?,
-                )?);
-            } else {
-                cache.hash_outputs = Some(DigestVar(vec![UInt8::<F>::constant(0); 32]));
             }
+            cache.hash_outputs.clone().unwrap()
+        };
+
+        Ok(SigHashPreimageVar {
+            version: self.version.clone(),
+            hash_prevouts,
+            hash_sequence,
+            outpoint: self.inputs[n_input].prev_output.clone(),
+            script_code: prev_lock_script.clone(),
+            amount: prev_amount.clone(),
+            sequence: self.inputs[n_input].sequence.clone(),
+            hash_outputs,
+            lock_time: self.lock_time.clone(),
+            sighash_type: UInt32::<F>::constant(*sighash_flags as u32),
+        })
+    }
+
+    /// Compute the serialisation of [Tx] for the legacy (pre `SIGHASH_FORKID`) `pre_sighash` calculation.
+    /// The transaction is serialised with the unlocking script of the input at `n_input` replaced by `prev_lock_script`,
+    /// and the other unlocking scripts replaced by the empty script. Then:
+    /// - if `SIGHASH_ANYONECANPAY` is set, only the input at `n_input` is serialised
+    /// - if the base flag is `SIGHASH_NONE`, no output is serialised
+    /// - if the base flag is `SIGHASH_SINGLE`, the outputs are truncated at `n_input` (included), and the outputs preceding it are replaced by `TxOut { satoshis: -1, lock_script: Script(vec![]) }`
+    /// - if the base flag is `SIGHASH_NONE` or `SIGHASH_SINGLE`, the sequence of the inputs other than `n_input` is set to zero
+    ///
+    /// **Note**: The function assumes that `prev_lock_script` has already been modified to handle `OP_CODESEPARATOR`.
+    /// The function returns an error if there is no input at `n_input`, or if the base flag is `SIGHASH_SINGLE` and
+    /// there is no output at `n_input`, as there is no serialisation in this case: see [TxVar::sighash].
+    pub fn legacy_pre_sighash_serialise(
+        &self,
+        n_input: usize,
+        prev_lock_script: &ScriptVar<F>,
+        sighash_flags: &u8,
+    ) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        // Handle sighash flags
+        let base_flags = sighash_flags & 31;
+        let anyone_can_pay = sighash_flags & SIGHASH_ANYONECANPAY != 0;
+
+        self.check_input_index(n_input)?;
+        if base_flags == SIGHASH_SINGLE && n_input >= self.outputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Index: {} is larger than the number of outputs: {}",
+                n_input,
+                self.outputs.len()
+            ))
+            .into());
+        }
+
+        let to_constant_bytes = |bytes: Vec<u8>| -> Vec<UInt8<F>> {
+            bytes.into_iter().map(UInt8::<F>::constant).collect()
         };
-        // 6. Locktime
-        let lock_time = self.lock_time.to_bytes_le()?;
 
         let mut ser: Vec<UInt8<F>> = Vec::new();
-        ser.extend_from_slice(version.as_slice());
-        ser.extend_from_slice(
-            cache
-                .hash_prevouts
-                .as_ref()
-                .unwrap()
-                .to_bytes_le()?
-                .as_slice(),
-        );
-        ser.extend_from_slice(
-            cache
-                .hash_sequence
-                .as_ref()
-                .unwrap()
-                .to_bytes_le()?
-                .as_slice(),
-        );
-        ser.extend_from_slice(input_specific_serialisation.as_slice());
-        ser.extend_from_slice(
-            cache
-                .hash_outputs
-                .as_ref()
-                .unwrap()
-                .to_bytes_le()?
-                .as_slice(),
-        );
-        ser.extend_from_slice(lock_time.as_slice());
+        // 1. Serialised version
+        ser.extend_from_slice(self.version.to_bytes_le()?.as_slice());
+        // 2. Inputs
+        let n_inputs: usize = if anyone_can_pay { 1 } else { self.inputs.len() };
+        ser.extend_from_slice(&to_constant_bytes(usize_to_var_int(n_inputs).unwrap()));
+        for (i, input) in self.inputs.iter().enumerate() {
+            if anyone_can_pay && i != n_input {
+                continue;
+            }
+            ser.extend_from_slice(input.prev_output.to_bytes_le()?.as_slice());
+            if i == n_input {
+                ser.extend_from_slice(prev_lock_script.pre_sighash_serialise()?.as_slice());
+                ser.extend_from_slice(input.sequence.to_bytes_le()?.as_slice());
+            } else {
+                // Empty unlocking script
+                ser.push(UInt8::<F>::constant(0));
+                if base_flags == SIGHASH_NONE || base_flags == SIGHASH_SINGLE {
+                    ser.extend_from_slice(UInt32::<F>::constant(0).to_bytes_le()?.as_slice());
+                } else {
+                    ser.extend_from_slice(input.sequence.to_bytes_le()?.as_slice());
+                }
+            }
+        }
+        // 3. Outputs
+        match base_flags {
+            SIGHASH_NONE => ser.push(UInt8::<F>::constant(0)),
+            SIGHASH_SINGLE => {
+                ser.extend_from_slice(&to_constant_bytes(usize_to_var_int(n_input + 1).unwrap()));
+                for _ in 0..n_input {
+                    // Blank output: satoshis = -1, empty locking script
+                    ser.extend_from_slice(&to_constant_bytes(vec![0xff; 8]));
+                    ser.push(UInt8::<F>::constant(0));
+                }
+                ser.extend_from_slice(self.outputs[n_input].pre_sighash_serialise()?.as_slice());
+            }
+            _ => {
+                ser.extend_from_slice(&to_constant_bytes(
+                    usize_to_var_int(self.outputs.len()).unwrap(),
+                ));
+                for output in self.outputs.iter() {
+                    ser.extend_from_slice(output.pre_sighash_serialise()?.as_slice());
+                }
+            }
+        }
+        // 4. Locktime
+        ser.extend_from_slice(self.lock_time.to_bytes_le()?.as_slice());
+        // 5. Sighash flags
         ser.extend_from_slice(
-            UInt32::<F>::constant((SIGHASH_FORKID | sighash_flags) as u32)
+            UInt32::<F>::constant(*sighash_flags as u32)
                 .to_bytes_le()?
                 .as_slice(),
         );
 
         Ok(ser)
     }
+
     /// Sighash calculation
+    ///
+    /// The algorithm is selected by `sighash_flags`: if `SIGHASH_FORKID` is set, the sighash is computed according to
+    /// the [Message Digest Algorithm](https://github.com/bitcoin-sv/bitcoin-sv/blob/master/doc/abc/replay-protected-sighash.md#digest-algorithm),
+    /// otherwise the legacy algorithm is used. In the latter case, if the base flag is `SIGHASH_SINGLE` and there is no output at `n_input`,
+    /// the sighash is the constant `1` (the `SIGHASH_SINGLE` bug).
     pub fn sighash(
         &self,
         n_input: usize,
@@ -189,6 +880,15 @@ impl<F: PrimeField, P: TxVarConfig + Clone> TxVar<F, P> {
         sighash_flags: &u8,
         cache: &mut SigHashCacheVar<F>,
     ) -> Result<DigestVar<F>, SynthesisError> {
+        if sighash_flags & SIGHASH_FORKID == 0
+            && sighash_flags & 31 == SIGHASH_SINGLE
+            && n_input >= self.outputs.len()
+        {
+            let mut one = vec![UInt8::<F>::constant(0); 32];
+            one[0] = UInt8::<F>::constant(1);
+            return Ok(DigestVar(one));
+        }
+
         let pre_sighash = self.pre_sighash_serialise(
             n_input,
             prev_lock_script,
@@ -198,153 +898,104 @@ impl<F: PrimeField, P: TxVarConfig + Clone> TxVar<F, P> {
         )?;
         Hash256Gadget::<F>::evaluate(&pre_sighash)
     }
+
+    /// Check that there is an input at `n_input`
+    fn check_input_index(&self, n_input: usize) -> Result<(), Error> {
+        if n_input >= self.inputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Input index: {} is larger than the number of inputs: {}",
+                n_input,
+                self.inputs.len()
+            )));
+        }
+
+        Ok(())
+    }
 }
 
 impl<F: PrimeField, P: TxVarConfig + Clone> AllocVar<Tx, F> for TxVar<F, P> {
+    /// Allocate a transaction without witness data, see [TxVar::new_segwit_variable]
     fn new_variable<T: Borrow<Tx>>(
         cs: impl Into<Namespace<F>>,
         f: impl FnOnce() -> Result<T, SynthesisError>,
         mode: AllocationMode,
     ) -> Result<Self, SynthesisError> {
-        let ns = cs.into();
-        let cs = ns.cs();
-
-        let tx: Tx = f().map(|tx| tx.borrow().clone())?;
-
-        /*
-         *
-         * Validate configuration
-         *
-         */
-
-        // Check that the number of inputs is the correct one
-        assert_eq!(
-            tx.inputs.len(),
-            P::N_INPUTS,
-            "The number of inputs: {} is different from the one set in the parameters: {}",
-            tx.inputs.len(),
-            P::N_INPUTS
-        );
-        // Check that the number of outputs is the correct one
-        assert_eq!(
-            tx.outputs.len(),
-            P::N_OUTPUTS,
-            "The number of outputs: {} is different from the one set in the parameters: {}",
-            tx.outputs.len(),
-            P::N_OUTPUTS
-        );
-        // Check that there are as many unlocking script lengths as required
-        let len_unlock_script = P::LEN_UNLOCK_SCRIPTS.len();
-        assert_eq!(
-            len_unlock_script,
-            tx.inputs.len(),
-            "The number of inputs: {} is different from P::LEN_UNLOCK_SCRIPTS.len(): {}",
-            len_unlock_script,
-            tx.inputs.len(),
-        );
-        // Check that there are as many locking script lengths as required
-        let len_lock_scripts = P::LEN_LOCK_SCRIPTS.len();
-        assert_eq!(
-            tx.outputs.len(),
-            len_lock_scripts,
-            "The number of outputs: {} is different from P::LEN_LOCK_SCRIPTS.len(): {}",
-            len_lock_scripts,
-            tx.outputs.len(),
-        );
-        // Check that the unlocking scripts have the correct length
-        for i in 0..len_unlock_script {
-            assert_eq!(
-                tx.inputs[i].unlock_script.0.len(),
-                P::LEN_UNLOCK_SCRIPTS[i],
-                "tx.inputs[{}].len() = {} is different from the one set in the parameters: {}",
-                i,
-                tx.inputs[i].unlock_script.0.len(),
-                P::LEN_UNLOCK_SCRIPTS[i]
-            );
-        }
-        // Check that the locking scripts have the correct length
-        for i in 0..len_lock_scripts {
-            assert_eq!(
-                tx.outputs[i].lock_script.0.len(),
-                P::LEN_LOCK_SCRIPTS[i],
-                "P::LEN_LOCK_SCRIPT[{}].len() = {} is different from the one set in the parameters: {}",
-                i,
-                tx.outputs[i].lock_script.0.len(),
-                P::LEN_LOCK_SCRIPTS[i]
-            );
-        }
-
-        /*
-         *
-         * Allocation
-         *
-         */
-
-        // Version
-        let version: UInt32<F> = UInt32::<F>::new_variable(cs.clone(), || Ok(tx.version), mode)?;
-        // TxIns
-        let inputs: Vec<TxInVar<F>> =
-            TxInVar::new_variable_vec(cs.clone(), || Ok(tx.inputs), mode)?;
-        // TxOuts
-        let outputs: Vec<TxOutVar<F>> =
-            TxOutVar::new_variable_vec(cs.clone(), || Ok(tx.outputs), mode)?;
-        // Locktime
-        let lock_time: UInt32<F> =
-            UInt32::<F>::new_variable(cs.clone(), || Ok(tx.lock_time), mode)?;
-
-        Ok(Self {
-            _config: PhantomData,
-            version,
-            inputs,
-            outputs,
-            lock_time,
-        })
+        Self::new_segwit_variable(
+            cs,
+            || {
+                f().map(|tx| SegwitTx {
+                    tx: tx.borrow().clone(),
+                    witnesses: Vec::new(),
+                })
+            },
+            mode,
+        )
     }
 }
 
 impl<F: PrimeField, P: TxVarConfig + Clone> EqGadget<F> for TxVar<F, P> {
     fn is_eq(&self, other: &Self) -> Result<Boolean<F>, SynthesisError> {
+        let mut witnesses_eq: Vec<Boolean<F>> = vec![Boolean::<F>::TRUE];
+        for (witness, other_witness) in self.witnesses.iter().zip(other.witnesses.iter()) {
+            witnesses_eq.push(witness.is_eq(other_witness)?);
+        }
         Boolean::<F>::kary_and(&[
             self.version.is_eq(&other.version)?,
             self.inputs.is_eq(&other.inputs)?,
             self.outputs.is_eq(&other.outputs)?,
             self.lock_time.is_eq(&other.lock_time)?,
+            Boolean::<F>::kary_and(&witnesses_eq)?,
         ])
     }
 }
 
-impl<F: PrimeField, P: TxVarConfig + Clone> ToBytesGadget<F> for TxVar<F, P> {
-    /// Serialise `Self` for TxID calculation
-    fn to_bytes_le(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
-        // Var Int length of inputs, outputs, and locking scripts
-        let var_int_n_inputs = usize_to_var_int(P::N_INPUTS).unwrap();
-        let var_int_n_outputs = usize_to_var_int(P::N_OUTPUTS).unwrap();
-
-        // Serialisation
-        let mut ser: Vec<UInt8<F>> = Vec::new();
-        ser.extend_from_slice(self.version.to_bytes_le()?.as_slice());
-        ser.extend_from_slice(
-            var_int_n_inputs
-                .iter()
-                .map(|el| UInt8::<F>::constant(*el))
-                .collect::<Vec<UInt8<F>>>()
-                .as_slice(),
-        );
-        for input in self.inputs.iter() {
-            ser.extend_from_slice(input.to_bytes_le()?.as_slice());
+impl<F: PrimeField, P: TxVarConfig + Clone> CondSelectGadget<F> for TxVar<F, P> {
+    /// Select between two transactions: both have the structure set in `P`, so the selection is done elementwise
+    fn conditionally_select(
+        cond: &Boolean<F>,
+        true_value: &Self,
+        false_value: &Self,
+    ) -> Result<Self, SynthesisError> {
+        let mut inputs: Vec<TxInVar<F>> = Vec::with_capacity(P::N_INPUTS);
+        for (t, f) in true_value.inputs.iter().zip(false_value.inputs.iter()) {
+            inputs.push(TxInVar::<F>::conditionally_select(cond, t, f)?);
         }
-        ser.extend_from_slice(
-            var_int_n_outputs
-                .iter()
-                .map(|el| UInt8::<F>::constant(*el))
-                .collect::<Vec<UInt8<F>>>()
-                .as_slice(),
-        );
-        for output in self.outputs.iter() {
-            ser.extend_from_slice(output.to_bytes_le()?.as_slice());
+        let mut outputs: Vec<TxOutVar<F>> = Vec::with_capacity(P::N_OUTPUTS);
+        for (t, f) in true_value.outputs.iter().zip(false_value.outputs.iter()) {
+            outputs.push(TxOutVar::<F>::conditionally_select(cond, t, f)?);
         }
-        ser.extend_from_slice(self.lock_time.to_bytes_le()?.as_slice());
-        Ok(ser)
+        let mut witnesses: Vec<WitnessVar<F>> = Vec::with_capacity(true_value.witnesses.len());
+        for (t, f) in true_value
+            .witnesses
+            .iter()
+            .zip(false_value.witnesses.iter())
+        {
+            witnesses.push(WitnessVar::<F>::conditionally_select(cond, t, f)?);
+        }
+
+        Ok(Self {
+            _config: PhantomData,
+            version: UInt32::<F>::conditionally_select(
+                cond,
+                &true_value.version,
+                &false_value.version,
+            )?,
+            inputs,
+            outputs,
+            lock_time: UInt32::<F>::conditionally_select(
+                cond,
+                &true_value.lock_time,
+                &false_value.lock_time,
+            )?,
+            witnesses,
+        })
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> ToBytesGadget<F> for TxVar<F, P> {
+    /// Serialise `Self` for TxID calculation, see [TxVar::serialise]
+    fn to_bytes_le(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        self.serialise()
     }
 }
 
@@ -357,6 +1008,9 @@ impl<F: PrimeField, P: TxVarConfig + Clone> R1CSVar<F> for TxVar<F, P> {
         result = self.inputs.cs().or(result);
         result = self.outputs.cs().or(result);
         result = self.lock_time.cs().or(result);
+        for witness in self.witnesses.iter() {
+            result = witness.cs().or(result);
+        }
         result
     }
 
@@ -375,7 +1029,7 @@ mod tests {
     use super::*;
     use chain_gang::address::addr_decode;
     use chain_gang::transaction::sighash::{
-        SIGHASH_ALL, SIGHASH_FORKID, SigHashCache, sig_hash_preimage,
+        SIGHASH_ALL, SIGHASH_FORKID, SigHashCache, sig_hash_preimage, sighash,
     };
     use chain_gang::wallet::create_sighash;
 
@@ -391,6 +1045,8 @@ mod tests {
     use chain_gang::script::Script;
     use chain_gang::util::{Hash256, Serializable};
 
+    use crate::util::{MAX_MONEY, default_tx};
+
     #[derive(Clone)]
     struct Config;
     impl TxVarConfig for Config {
@@ -400,78 +1056,344 @@ mod tests {
         const LEN_LOCK_SCRIPTS: &[usize] = &[0x19, 0x19]; // len P2PKH
     }
 
-    fn test_pre_sighash_serialisation(sighash_flags: u8) {
-        let lock_script =
-            Script(hex::decode("76a91402b74813b047606b4b3fbdfb1a6e8e053fdb8dab88ac").unwrap());
+    fn test_pre_sighash_serialisation(sighash_flags: u8) {
+        let lock_script =
+            Script(hex::decode("76a91402b74813b047606b4b3fbdfb1a6e8e053fdb8dab88ac").unwrap());
+        let addr = "mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec";
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0,
+            }],
+            outputs: vec![
+                TxOut {
+                    satoshis: 100,
+                    lock_script: p2pkh::create_lock_script(&hash160),
+                },
+                TxOut {
+                    satoshis: 259899900,
+                    lock_script: p2pkh::create_lock_script(&hash160),
+                },
+            ],
+            lock_time: 0,
+        };
+        let mut cache = SigHashCache::new();
+        let pre_sighash =
+            sig_hash_preimage(&tx, 0, &lock_script.0, 260000000, sighash_flags, &mut cache)
+                .unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        let mut cache_var = SigHashCacheVar::<F>::new();
+        let pre_sighash_var = tx_var
+            .pre_sighash_serialise(
+                0,
+                &ScriptVar::<F>::new_input(cs.clone(), || Ok(lock_script)).unwrap(),
+                &UInt64::<F>::new_input(cs.clone(), || Ok(260000000)).unwrap(),
+                &sighash_flags,
+                &mut cache_var,
+            )
+            .unwrap();
+
+        assert_eq!(pre_sighash.len(), pre_sighash_var.len());
+        for (b1, b2) in pre_sighash_var.iter().zip(pre_sighash.iter()) {
+            assert_eq!(b1.value().unwrap(), *b2);
+        }
+    }
+
+    #[test]
+    fn test_pre_sighash_all_serialisation() {
+        test_pre_sighash_serialisation(SIGHASH_ALL | SIGHASH_FORKID);
+    }
+
+    #[test]
+    fn test_pre_sighash_none_serialisation() {
+        test_pre_sighash_serialisation(SIGHASH_NONE | SIGHASH_FORKID);
+    }
+
+    #[test]
+    fn test_pre_sighash_single_serialisation() {
+        test_pre_sighash_serialisation(SIGHASH_SINGLE | SIGHASH_FORKID);
+    }
+
+    #[test]
+    fn test_pre_sighash_anyone_can_pay_serialisation() {
+        test_pre_sighash_serialisation(SIGHASH_ALL | SIGHASH_ANYONECANPAY | SIGHASH_FORKID);
+    }
+
+    #[derive(Clone)]
+    struct TwoOutputsConfig;
+    impl TxVarConfig for TwoOutputsConfig {
+        const N_INPUTS: usize = 3;
+        const N_OUTPUTS: usize = 2;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0, 0, 0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19, 0x19];
+    }
+
+    /// A transaction with three inputs and two outputs
+    fn two_outputs_tx() -> Tx {
+        let addr = "mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec";
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        Tx {
+            version: 1,
+            inputs: (0..3)
+                .map(|i| TxIn {
+                    prev_output: OutPoint {
+                        hash: Hash256::decode(
+                            "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                        )
+                        .unwrap(),
+                        index: i,
+                    },
+                    unlock_script: Script(vec![]),
+                    sequence: 0xfffffffe - i,
+                })
+                .collect(),
+            outputs: vec![
+                TxOut {
+                    satoshis: 100,
+                    lock_script: p2pkh::create_lock_script(&hash160),
+                },
+                TxOut {
+                    satoshis: 259899900,
+                    lock_script: p2pkh::create_lock_script(&hash160),
+                },
+            ],
+            lock_time: 800_000,
+        }
+    }
+
+    /// Compute the sighash of a transaction with three inputs and two outputs
+    fn test_sighash_two_outputs(n_input: usize, sighash_flags: u8) {
+        let lock_script =
+            Script(hex::decode("76a91402b74813b047606b4b3fbdfb1a6e8e053fdb8dab88ac").unwrap());
+        let tx = two_outputs_tx();
+        let expected_sighash = sighash(
+            &tx,
+            n_input,
+            &lock_script.0,
+            260000000,
+            sighash_flags,
+            &mut SigHashCache::new(),
+        )
+        .unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, TwoOutputsConfig>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        let mut cache_var = SigHashCacheVar::<F>::new();
+        let sighash_var = tx_var
+            .sighash(
+                n_input,
+                &ScriptVar::<F>::new_input(cs.clone(), || Ok(lock_script)).unwrap(),
+                &UInt64::<F>::new_input(cs.clone(), || Ok(260000000)).unwrap(),
+                &sighash_flags,
+                &mut cache_var,
+            )
+            .unwrap();
+
+        assert_eq!(sighash_var.value().unwrap(), expected_sighash.0.to_vec());
+    }
+
+    #[test]
+    fn test_legacy_sighash_calculation() {
+        for n_input in 0..2 {
+            for sighash_flags in [SIGHASH_ALL, SIGHASH_NONE, SIGHASH_SINGLE] {
+                test_sighash_two_outputs(n_input, sighash_flags);
+                test_sighash_two_outputs(n_input, sighash_flags | SIGHASH_ANYONECANPAY);
+            }
+        }
+    }
+
+    #[test]
+    fn test_legacy_sighash_single_bug() {
+        // There is no output at index 2
+        test_sighash_two_outputs(2, SIGHASH_SINGLE);
+        test_sighash_two_outputs(2, SIGHASH_SINGLE | SIGHASH_ANYONECANPAY);
+    }
+
+    #[derive(Clone)]
+    struct MultiInputConfig;
+    impl TxVarConfig for MultiInputConfig {
+        const N_INPUTS: usize = 3;
+        const N_OUTPUTS: usize = 3;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0, 0, 0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19, 0x19, 0x19];
+    }
+
+    fn multi_input_tx() -> Tx {
         let addr = "mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec";
         let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
-        let tx = Tx {
+        Tx {
             version: 2,
-            inputs: vec![TxIn {
-                prev_output: OutPoint {
-                    hash: Hash256::decode(
-                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
-                    )
-                    .unwrap(),
-                    index: 0,
-                },
-                unlock_script: Script(vec![]),
-                sequence: 0,
-            }],
-            outputs: vec![
-                TxOut {
-                    satoshis: 100,
-                    lock_script: p2pkh::create_lock_script(&hash160),
-                },
-                TxOut {
-                    satoshis: 259899900,
+            inputs: (0..3)
+                .map(|i| TxIn {
+                    prev_output: OutPoint {
+                        hash: Hash256::decode(
+                            "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                        )
+                        .unwrap(),
+                        index: i,
+                    },
+                    unlock_script: Script(vec![]),
+                    sequence: i,
+                })
+                .collect(),
+            outputs: (0..3)
+                .map(|i| TxOut {
+                    satoshis: 100 * (i + 1),
                     lock_script: p2pkh::create_lock_script(&hash160),
-                },
-            ],
+                })
+                .collect(),
             lock_time: 0,
-        };
-        let mut cache = SigHashCache::new();
-        let pre_sighash =
-            sig_hash_preimage(&tx, 0, &lock_script.0, 260000000, sighash_flags, &mut cache)
-                .unwrap();
+        }
+    }
+
+    /// Compute the sighashes of `tx` for the pairs `(n_input, sighash_flags)` in `calls`, in circuit and natively,
+    /// sharing the same cache among the calls
+    fn test_sighash_with_cache(calls: &[(usize, u8)]) {
+        let lock_script =
+            Script(hex::decode("76a91402b74813b047606b4b3fbdfb1a6e8e053fdb8dab88ac").unwrap());
+        let tx = multi_input_tx();
 
         let cs = ConstraintSystem::<F>::new_ref();
-        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        let tx_var =
+            TxVar::<F, MultiInputConfig>::new_input(cs.clone(), || Ok(tx.clone())).unwrap();
+        let lock_script_var =
+            ScriptVar::<F>::new_input(cs.clone(), || Ok(lock_script.clone())).unwrap();
+        let amount_var = UInt64::<F>::new_input(cs.clone(), || Ok(260000000)).unwrap();
+
+        let mut cache = SigHashCache::new();
         let mut cache_var = SigHashCacheVar::<F>::new();
-        let pre_sighash_var = tx_var
-            .pre_sighash_serialise(
-                0,
-                &ScriptVar::<F>::new_input(cs.clone(), || Ok(lock_script)).unwrap(),
-                &UInt64::<F>::new_input(cs.clone(), || Ok(260000000)).unwrap(),
-                &sighash_flags,
-                &mut cache_var,
+        for (n_input, sighash_flags) in calls.iter() {
+            let expected_sighash = sighash(
+                &tx,
+                *n_input,
+                &lock_script.0,
+                260000000,
+                *sighash_flags,
+                &mut cache,
             )
             .unwrap();
+            let sighash_var = tx_var
+                .sighash(
+                    *n_input,
+                    &lock_script_var,
+                    &amount_var,
+                    sighash_flags,
+                    &mut cache_var,
+                )
+                .unwrap();
 
-        assert_eq!(pre_sighash.len(), pre_sighash_var.len());
-        for (b1, b2) in pre_sighash_var.iter().zip(pre_sighash.iter()) {
-            assert_eq!(b1.value().unwrap(), *b2);
+            assert_eq!(
+                sighash_var.value().unwrap(),
+                expected_sighash.0.to_vec(),
+                "Wrong sighash for input {} and flags {}",
+                n_input,
+                sighash_flags
+            );
         }
+        assert!(cs.is_satisfied().unwrap());
     }
 
     #[test]
-    fn test_pre_sighash_all_serialisation() {
-        test_pre_sighash_serialisation(SIGHASH_ALL | SIGHASH_FORKID);
+    fn test_sighash_all_flags() {
+        for base_flags in [SIGHASH_ALL, SIGHASH_NONE, SIGHASH_SINGLE] {
+            for anyone_can_pay in [0, SIGHASH_ANYONECANPAY] {
+                for n_input in 0..3 {
+                    test_sighash_with_cache(&[(
+                        n_input,
+                        base_flags | anyone_can_pay | SIGHASH_FORKID,
+                    )]);
+                }
+            }
+        }
     }
 
     #[test]
-    fn test_pre_sighash_none_serialisation() {
-        test_pre_sighash_serialisation(SIGHASH_NONE | SIGHASH_FORKID);
+    fn test_sighash_single_without_output() {
+        // There is no output at index 2: hash_outputs is zero
+        test_sighash_two_outputs(2, SIGHASH_SINGLE | SIGHASH_FORKID);
+        test_sighash_two_outputs(2, SIGHASH_SINGLE | SIGHASH_ANYONECANPAY | SIGHASH_FORKID);
     }
 
     #[test]
-    fn test_pre_sighash_single_serialisation() {
-        test_pre_sighash_serialisation(SIGHASH_SINGLE | SIGHASH_FORKID);
+    fn test_sighash_invalid_index_or_flags() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var =
+            TxVar::<F, TwoOutputsConfig>::new_input(cs.clone(), || Ok(two_outputs_tx())).unwrap();
+        let lock_script_var = ScriptVar::<F>::new_input(cs.clone(), || {
+            Ok(Script(
+                hex::decode("76a91402b74813b047606b4b3fbdfb1a6e8e053fdb8dab88ac").unwrap(),
+            ))
+        })
+        .unwrap();
+        let amount_var = UInt64::<F>::new_input(cs.clone(), || Ok(260000000)).unwrap();
+
+        // There is no input at index 3
+        assert!(
+            tx_var
+                .sighash_preimage(
+                    3,
+                    &lock_script_var,
+                    &amount_var,
+                    &(SIGHASH_ALL | SIGHASH_FORKID),
+                    &mut SigHashCacheVar::<F>::new(),
+                )
+                .is_err()
+        );
+        assert!(
+            tx_var
+                .legacy_pre_sighash_serialise(3, &lock_script_var, &SIGHASH_ALL)
+                .is_err()
+        );
+        // The legacy algorithm has no preimage
+        assert!(
+            tx_var
+                .sighash_preimage(
+                    0,
+                    &lock_script_var,
+                    &amount_var,
+                    &SIGHASH_ALL,
+                    &mut SigHashCacheVar::<F>::new(),
+                )
+                .is_err()
+        );
+        // There is no output at index 2
+        assert!(
+            tx_var
+                .legacy_pre_sighash_serialise(2, &lock_script_var, &SIGHASH_SINGLE)
+                .is_err()
+        );
     }
 
     #[test]
-    fn test_pre_sighash_anyone_can_pay_serialisation() {
-        test_pre_sighash_serialisation(SIGHASH_ALL | SIGHASH_ANYONECANPAY | SIGHASH_FORKID);
+    fn test_sighash_single_and_none_do_not_pollute_cache() {
+        test_sighash_with_cache(&[
+            (0, SIGHASH_SINGLE | SIGHASH_FORKID),
+            (1, SIGHASH_SINGLE | SIGHASH_FORKID),
+            (2, SIGHASH_NONE | SIGHASH_FORKID),
+            (0, SIGHASH_ALL | SIGHASH_FORKID),
+            (1, SIGHASH_SINGLE | SIGHASH_FORKID),
+            (2, SIGHASH_ALL | SIGHASH_FORKID),
+        ]);
+    }
+
+    #[test]
+    fn test_sighash_anyone_can_pay_does_not_pollute_cache() {
+        test_sighash_with_cache(&[
+            (0, SIGHASH_ALL | SIGHASH_ANYONECANPAY | SIGHASH_FORKID),
+            (1, SIGHASH_ALL | SIGHASH_FORKID),
+            (2, SIGHASH_ALL | SIGHASH_ANYONECANPAY | SIGHASH_FORKID),
+        ]);
     }
 
     #[test]
@@ -563,6 +1485,89 @@ mod tests {
         assert!(tx_var1.is_eq(&tx_var2).unwrap().value().unwrap());
     }
 
+    #[test]
+    fn test_public_input() {
+        let addr = "mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec";
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 3,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xfffffffe,
+            }],
+            outputs: vec![
+                TxOut {
+                    satoshis: 100,
+                    lock_script: p2pkh::create_lock_script(&hash160),
+                },
+                TxOut {
+                    satoshis: 259899900,
+                    lock_script: p2pkh::create_lock_script(&hash160),
+                },
+            ],
+            lock_time: 800_000,
+        };
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx.clone())).unwrap();
+
+        assert_eq!(
+            cs.borrow().unwrap().instance_assignment[1..],
+            TxVar::<F, Config>::public_input(&tx)
+        );
+    }
+
+    #[test]
+    fn test_conditionally_select() {
+        let addr = "mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec";
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        let tx = |satoshis: i64, sequence: u32| Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence,
+            }],
+            outputs: vec![
+                TxOut {
+                    satoshis,
+                    lock_script: p2pkh::create_lock_script(&hash160),
+                },
+                TxOut {
+                    satoshis: 259899900,
+                    lock_script: p2pkh::create_lock_script(&hash160),
+                },
+            ],
+            lock_time: 0,
+        };
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var1 = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx(100, 0))).unwrap();
+        let tx_var2 = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx(200, 1))).unwrap();
+
+        for cond in [true, false] {
+            let cond_var = Boolean::<F>::new_witness(cs.clone(), || Ok(cond)).unwrap();
+            let selected =
+                TxVar::<F, Config>::conditionally_select(&cond_var, &tx_var1, &tx_var2).unwrap();
+            let expected = if cond { tx(100, 0) } else { tx(200, 1) };
+            assert_eq!(selected.value().unwrap(), expected);
+        }
+        assert!(cs.is_satisfied().unwrap());
+    }
+
     #[test]
     fn test_to_bytes_gadget() {
         let addr = "mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec";
@@ -600,6 +1605,25 @@ mod tests {
         let tx_var_bytes = tx_var.to_bytes_le().unwrap().value().unwrap();
 
         assert_eq!(tx_bytes, tx_var_bytes);
+        assert_eq!(TxVar::<F, Config>::serialised_len(), tx_bytes.len());
+    }
+
+    #[test]
+    fn test_serialise() {
+        let tx = multi_input_tx();
+        let mut tx_bytes: Vec<u8> = Vec::new();
+        tx.write(&mut tx_bytes).unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, MultiInputConfig>::new_witness(cs.clone(), || Ok(tx)).unwrap();
+        let ser = tx_var.serialise().unwrap();
+
+        assert_eq!(ser.value().unwrap(), tx_bytes);
+        assert_eq!(ser.len(), TxVar::<F, MultiInputConfig>::serialised_len());
+        assert_eq!(
+            tx_var.to_bytes_le().unwrap().value().unwrap(),
+            ser.value().unwrap()
+        );
     }
 
     #[test]
@@ -639,4 +1663,312 @@ mod tests {
 
         assert_eq!(txid.0, txid_var);
     }
+
+    #[test]
+    fn test_dyn_tx_var_config() {
+        let tx = default_tx::<Config>();
+        let config = DynTxVarConfig::from_tx(&tx);
+        assert_eq!(config, DynTxVarConfig::from_config::<Config>());
+        assert!(check_tx::<Config>(&tx).is_ok());
+        assert_eq!(
+            config.to_string(),
+            "impl TxVarConfig for Config {\n    const N_INPUTS: usize = 1;\n    const N_OUTPUTS: usize = 2;\n    \
+             const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];\n    const LEN_LOCK_SCRIPTS: &[usize] = &[25, 25];\n}"
+        );
+    }
+
+    #[test]
+    fn test_check_tx_fails() {
+        let mut tx = default_tx::<Config>();
+        tx.outputs[1].lock_script = Script(vec![0; 0x17]);
+        match check_tx::<Config>(&tx) {
+            Err(Error::ConfigMismatch(reason)) => {
+                assert!(reason.contains("locking script of output 1: 23"));
+                assert!(reason.contains("const LEN_LOCK_SCRIPTS: &[usize] = &[25, 23];"));
+            }
+            _ => panic!("The transaction does not match the configuration"),
+        }
+
+        tx.outputs.pop();
+        assert!(matches!(
+            check_tx::<Config>(&tx),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_accessors() {
+        let mut tx = default_tx::<Config>();
+        tx.outputs[0].satoshis = 100;
+        tx.outputs[1].satoshis = 250;
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_witness(cs.clone(), || Ok(tx.clone())).unwrap();
+
+        assert!(tx_var.input(0).unwrap().value().unwrap().prev_output == tx.inputs[0].prev_output);
+        assert_eq!(tx_var.output(1).unwrap().value().unwrap().satoshis, 250);
+        assert!(matches!(tx_var.input(1), Err(Error::IndexOutOfBounds(_))));
+        assert!(matches!(tx_var.output(2), Err(Error::IndexOutOfBounds(_))));
+        assert_eq!(
+            tx_var.total_output_amount().unwrap().value().unwrap(),
+            F::from(350u64)
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_find_output_paying_to() {
+        let hash160 = addr_decode("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec", Network::BSV_Testnet)
+            .unwrap()
+            .0;
+        let lock_script = p2pkh::create_lock_script(&hash160);
+        let mut tx = default_tx::<Config>();
+        tx.outputs[1] = TxOut {
+            satoshis: 250,
+            lock_script: lock_script.clone(),
+        };
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let find = |tx: &Tx, script: &Script| {
+            let tx_var = TxVar::<F, Config>::new_witness(cs.clone(), || Ok(tx.clone())).unwrap();
+            let script_var = ScriptVar::<F>::new_constant(cs.clone(), script.clone()).unwrap();
+            let (selector, amount) = tx_var.find_output_paying_to(&script_var).unwrap();
+            (
+                selector
+                    .iter()
+                    .map(|is_selected| is_selected.value().unwrap())
+                    .collect::<Vec<bool>>(),
+                amount.value().unwrap(),
+            )
+        };
+
+        assert_eq!(
+            find(&tx, &lock_script),
+            (vec![false, true], F::from(250u64))
+        );
+        assert_eq!(
+            find(&tx, &tx.outputs[0].lock_script),
+            (vec![true, false], F::from(0u64))
+        );
+        // Only the first output paying to the script is selected
+        tx.outputs[0] = TxOut {
+            satoshis: 100,
+            lock_script: lock_script.clone(),
+        };
+        assert_eq!(
+            find(&tx, &lock_script),
+            (vec![true, false], F::from(100u64))
+        );
+        // Scripts with a different length never match
+        assert_eq!(
+            find(&tx, &Script(vec![0; 3])),
+            (vec![false, false], F::from(0u64))
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[derive(Clone)]
+    struct SegwitConfig;
+    impl TxVarConfig for SegwitConfig {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 2;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19, 0x19];
+        const LEN_WITNESS_ITEMS: Option<&[&[usize]]> = Some(&[&[71, 33]]); // len P2WPKH witness
+    }
+
+    fn segwit_tx() -> SegwitTx {
+        let mut tx = default_tx::<SegwitConfig>();
+        tx.version = 2;
+        tx.outputs[0].satoshis = 1000;
+        SegwitTx {
+            tx,
+            witnesses: vec![vec![vec![0x30; 71], vec![0x02; 33]]],
+        }
+    }
+
+    #[test]
+    fn test_wtxid() {
+        let tx = segwit_tx();
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, SegwitConfig>::new_segwit_variable(
+            cs.clone(),
+            || Ok(tx.clone()),
+            AllocationMode::Witness,
+        )
+        .unwrap();
+
+        let ser = tx_var.segwit_serialise().unwrap().value().unwrap();
+        assert_eq!(ser, tx.serialise());
+        assert_eq!(ser[4..6], [0x00, 0x01]);
+        assert_eq!(tx_var.wtxid().unwrap().value().unwrap(), tx.wtxid().0);
+        // The txid does not commit to the witnesses
+        assert_eq!(tx_var.txid().unwrap().value().unwrap(), tx.tx.hash().0);
+        assert_ne!(tx.wtxid(), tx.tx.hash());
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_wtxid_without_witness() {
+        let tx = default_tx::<Config>();
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_witness(cs.clone(), || Ok(tx.clone())).unwrap();
+
+        assert!(!TxVar::<F, Config>::has_witness());
+        assert_eq!(
+            tx_var.wtxid().unwrap().value().unwrap(),
+            tx_var.txid().unwrap().value().unwrap()
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_segwit_public_input() {
+        let tx = segwit_tx();
+        let cs = ConstraintSystem::<F>::new_ref();
+        TxVar::<F, SegwitConfig>::new_segwit_variable(
+            cs.clone(),
+            || Ok(tx.clone()),
+            AllocationMode::Input,
+        )
+        .unwrap();
+
+        assert_eq!(
+            cs.borrow().unwrap().instance_assignment[1..],
+            TxVar::<F, SegwitConfig>::segwit_public_input(&tx)
+        );
+    }
+
+    #[test]
+    fn test_check_segwit_tx() {
+        let mut tx = segwit_tx();
+        assert!(check_segwit_tx::<SegwitConfig>(&tx).is_ok());
+        assert!(matches!(
+            check_segwit_tx::<Config>(&tx),
+            Err(Error::ConfigMismatch(_))
+        ));
+
+        tx.witnesses[0][0].pop();
+        match check_segwit_tx::<SegwitConfig>(&tx) {
+            Err(Error::ConfigMismatch(reason)) => {
+                assert!(reason.contains("witness items of input 0: [70, 33]"))
+            }
+            _ => panic!("The witness does not match the configuration"),
+        }
+    }
+
+    #[test]
+    fn test_new_variable_without_witness() {
+        let tx = segwit_tx().tx;
+        let cs = ConstraintSystem::<F>::new_ref();
+        assert!(TxVar::<F, SegwitConfig>::new_witness(cs, || Ok(tx)).is_err());
+    }
+
+    #[test]
+    fn test_new_from_bytes() {
+        let tx = multi_input_tx();
+        let mut bytes: Vec<u8> = Vec::new();
+        tx.write(&mut bytes).unwrap();
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, MultiInputConfig>::new_from_bytes(
+            cs.clone(),
+            || Ok(bytes.clone()),
+            AllocationMode::Witness,
+        )
+        .unwrap();
+
+        assert_eq!(tx_var.value().unwrap(), tx);
+        assert_eq!(tx_var.txid().unwrap().value().unwrap(), tx.hash().0);
+        assert!(cs.is_satisfied().unwrap());
+
+        // The serialisation must have the length set in the parameters
+        assert!(matches!(
+            TxVar::<F, MultiInputConfig>::new_from_bytes(
+                cs.clone(),
+                || Ok(bytes[1..].to_vec()),
+                AllocationMode::Witness
+            ),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+
+    #[test]
+    fn test_new_from_bytes_with_wrong_var_int() {
+        let tx = default_tx::<Config>();
+        let mut bytes: Vec<u8> = Vec::new();
+        tx.write(&mut bytes).unwrap();
+        // Claim two inputs instead of one
+        bytes[4] = 2;
+        let cs = ConstraintSystem::<F>::new_ref();
+        let _ =
+            TxVar::<F, Config>::new_from_bytes(cs.clone(), || Ok(bytes), AllocationMode::Witness)
+                .unwrap();
+
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_segwit_new_from_bytes() {
+        let tx = segwit_tx();
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, SegwitConfig>::new_from_bytes(
+            cs.clone(),
+            || Ok(tx.serialise()),
+            AllocationMode::Witness,
+        )
+        .unwrap();
+
+        assert_eq!(tx_var.witnesses[0].value().unwrap(), tx.witnesses[0]);
+        assert_eq!(tx_var.wtxid().unwrap().value().unwrap(), tx.wtxid().0);
+        assert_eq!(tx_var.txid().unwrap().value().unwrap(), tx.tx.hash().0);
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[derive(Clone)]
+    struct MoneyRangeConfig;
+    impl TxVarConfig for MoneyRangeConfig {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 2;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19, 0x19];
+        const ENFORCE_MONEY_RANGE: bool = true;
+    }
+
+    #[test]
+    fn test_enforce_money_range() {
+        let mut tx = default_tx::<MoneyRangeConfig>();
+        tx.outputs[1].satoshis = MAX_MONEY as i64 + 1;
+        let mut bytes: Vec<u8> = Vec::new();
+        tx.write(&mut bytes).unwrap();
+
+        // The range is only enforced if set in the configuration
+        let cs = ConstraintSystem::<F>::new_ref();
+        let _ = TxVar::<F, Config>::new_witness(cs.clone(), || Ok(tx.clone())).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let _ = TxVar::<F, MoneyRangeConfig>::new_witness(cs.clone(), || Ok(tx.clone())).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let _ = TxVar::<F, MoneyRangeConfig>::new_from_bytes(
+            cs.clone(),
+            || Ok(bytes),
+            AllocationMode::Witness,
+        )
+        .unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+
+        tx.outputs[1].satoshis = MAX_MONEY as i64;
+        let cs = ConstraintSystem::<F>::new_ref();
+        let _ = TxVar::<F, MoneyRangeConfig>::new_witness(cs.clone(), || Ok(tx)).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_new_variable_with_wrong_config() {
+        let mut tx = default_tx::<Config>();
+        tx.inputs[0].unlock_script = Script(vec![0]);
+        let cs = ConstraintSystem::<F>::new_ref();
+        assert!(TxVar::<F, Config>::new_input(cs, || Ok(tx)).is_err());
+    }
 }
@@ -15,8 +15,11 @@ use crate::constraints::{outpoint::OutPointVar, script::ScriptVar};
 use crate::traits::PreSigHashSerialise;
 use chain_gang::messages::TxIn;
 
+use crate::util::to_fp_bits;
+
 use ark_r1cs_std::boolean::Boolean;
 use ark_r1cs_std::eq::EqGadget;
+use ark_r1cs_std::select::CondSelectGadget;
 
 use ark_r1cs_std::R1CSVar;
 use ark_relations::r1cs::ConstraintSystemRef;
@@ -68,6 +71,32 @@ impl<F: PrimeField> EqGadget<F> for TxInVar<F> {
     }
 }
 
+impl<F: PrimeField> CondSelectGadget<F> for TxInVar<F> {
+    fn conditionally_select(
+        cond: &Boolean<F>,
+        true_value: &Self,
+        false_value: &Self,
+    ) -> Result<Self, SynthesisError> {
+        Ok(Self {
+            prev_output: OutPointVar::<F>::conditionally_select(
+                cond,
+                &true_value.prev_output,
+                &false_value.prev_output,
+            )?,
+            unlock_script: ScriptVar::<F>::conditionally_select(
+                cond,
+                &true_value.unlock_script,
+                &false_value.unlock_script,
+            )?,
+            sequence: UInt32::<F>::conditionally_select(
+                cond,
+                &true_value.sequence,
+                &false_value.sequence,
+            )?,
+        })
+    }
+}
+
 impl<F: PrimeField> ToBytesGadget<F> for TxInVar<F> {
     fn to_bytes_le(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
         let mut ser: Vec<UInt8<F>> = Vec::new();
@@ -99,6 +128,19 @@ impl<F: PrimeField> R1CSVar<F> for TxInVar<F> {
 }
 
 impl<F: PrimeField> TxInVar<F> {
+    /// Compute the field elements allocated by [TxInVar::new_input] for `txin`
+    pub fn public_input(txin: &TxIn) -> Vec<F> {
+        let mut input = OutPointVar::<F>::public_input(&txin.prev_output);
+        input.extend_from_slice(&ScriptVar::<F>::public_input(&txin.unlock_script));
+        input.extend_from_slice(&to_fp_bits(&txin.sequence.to_le_bytes()));
+        input
+    }
+
+    /// Check whether the input is a coinbase input, i.e., it spends the null outpoint, see [OutPointVar::is_null]
+    pub fn is_coinbase(&self) -> Result<Boolean<F>, SynthesisError> {
+        self.prev_output.is_null()
+    }
+
     /// Allocate vectors of [TxIn]s
     pub fn new_variable_vec<T: Borrow<Vec<TxIn>>>(
         cs: impl Into<Namespace<F>>,
@@ -11,10 +11,13 @@ use crate::constraints::script::ScriptVar;
 use crate::traits::PreSigHashSerialise;
 use ark_relations::r1cs::{Namespace, SynthesisError};
 use chain_gang::messages::TxOut;
+
+use crate::util::{MAX_MONEY, to_fp_bits, uint64};
 use std::borrow::Borrow;
 
 use ark_r1cs_std::boolean::Boolean;
 use ark_r1cs_std::eq::EqGadget;
+use ark_r1cs_std::select::CondSelectGadget;
 
 use ark_r1cs_std::R1CSVar;
 use ark_relations::r1cs::ConstraintSystemRef;
@@ -60,6 +63,27 @@ impl<F: PrimeField> EqGadget<F> for TxOutVar<F> {
     }
 }
 
+impl<F: PrimeField> CondSelectGadget<F> for TxOutVar<F> {
+    fn conditionally_select(
+        cond: &Boolean<F>,
+        true_value: &Self,
+        false_value: &Self,
+    ) -> Result<Self, SynthesisError> {
+        Ok(Self {
+            satoshis: UInt64::<F>::conditionally_select(
+                cond,
+                &true_value.satoshis,
+                &false_value.satoshis,
+            )?,
+            lock_script: ScriptVar::<F>::conditionally_select(
+                cond,
+                &true_value.lock_script,
+                &false_value.lock_script,
+            )?,
+        })
+    }
+}
+
 impl<F: PrimeField> ToBytesGadget<F> for TxOutVar<F> {
     fn to_bytes_le(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
         self.pre_sighash_serialise()
@@ -85,6 +109,19 @@ impl<F: PrimeField> R1CSVar<F> for TxOutVar<F> {
 }
 
 impl<F: PrimeField> TxOutVar<F> {
+    /// Compute the field elements allocated by [TxOutVar::new_input] for `txout`
+    pub fn public_input(txout: &TxOut) -> Vec<F> {
+        let mut input = to_fp_bits(&(txout.satoshis as u64).to_le_bytes());
+        input.extend_from_slice(&ScriptVar::<F>::public_input(&txout.lock_script));
+        input
+    }
+
+    /// Enforce that the amount is in the valid monetary range, i.e., it is at most [MAX_MONEY]
+    pub fn enforce_money_range(&self) -> Result<(), SynthesisError> {
+        uint64::is_le(&self.satoshis, &UInt64::<F>::constant(MAX_MONEY))?
+            .enforce_equal(&Boolean::<F>::TRUE)
+    }
+
     /// Allocate vectors of [TxOut]s
     pub fn new_variable_vec<T: Borrow<Vec<TxOut>>>(
         cs: impl Into<Namespace<F>>,
@@ -130,6 +167,26 @@ mod tests {
     use chain_gang::script::Script;
     use chain_gang::util::Serializable;
 
+    #[test]
+    fn test_enforce_money_range() {
+        for (satoshis, is_valid) in [
+            (0, true),
+            (MAX_MONEY as i64, true),
+            (MAX_MONEY as i64 + 1, false),
+        ] {
+            let cs = ConstraintSystem::<F>::new_ref();
+            let txout = TxOutVar::<F>::new_witness(cs.clone(), || {
+                Ok(TxOut {
+                    satoshis,
+                    lock_script: Script(vec![]),
+                })
+            })
+            .unwrap();
+            txout.enforce_money_range().unwrap();
+            assert_eq!(cs.is_satisfied().unwrap(), is_valid);
+        }
+    }
+
     #[test]
     fn txout_serialisation() {
         let mut v = Vec::new();
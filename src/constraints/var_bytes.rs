@@ -0,0 +1,428 @@
+//! Implementation of [VarBytesVar], R1CS version of a byte string whose length is a variable of the circuit
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    R1CSVar,
+    alloc::AllocVar,
+    eq::EqGadget,
+    fields::{FieldVar, fp::FpVar},
+    prelude::{AllocationMode, Boolean},
+    select::CondSelectGadget,
+    uint8::UInt8,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+
+use crate::error::Error;
+use crate::util::{is_less_than, to_fp, to_fp_bits, to_u64, usize_to_var_int};
+
+/// R1CS version of a byte string of variable length, at most `bytes.len()`.
+///
+/// The string is made of the first `len` elements of `bytes`, and the remaining elements are zero.
+/// If `len` is a constant of the circuit, the string has a fixed length and its operations are as cheap as those on `bytes`.
+#[derive(Debug, Clone)]
+pub struct VarBytesVar<F: PrimeField> {
+    /// The bytes of the string, padded with zeros to the maximum length
+    pub bytes: Vec<UInt8<F>>,
+    /// The length of the string
+    pub len: FpVar<F>,
+}
+
+/// Convert each element of `values` into the byte it represents, enforcing that they are equal.
+/// Constant values give constant bytes.
+fn bytes_from_fp<F: PrimeField>(values: &[FpVar<F>]) -> Result<Vec<UInt8<F>>, SynthesisError> {
+    values
+        .iter()
+        .map(|value| {
+            if let FpVar::Constant(value) = value {
+                return Ok(UInt8::<F>::constant(to_u64(value).unwrap_or_default() as u8));
+            }
+            let byte = UInt8::<F>::new_witness(value.cs(), || {
+                Ok(to_u64(&value.value()?).unwrap_or_default() as u8)
+            })?;
+            to_fp(&byte)?.enforce_equal(value)?;
+            Ok(byte)
+        })
+        .collect()
+}
+
+impl<F: PrimeField> VarBytesVar<F> {
+    /// Construct the string of fixed length `bytes`
+    pub fn from_bytes(bytes: &[UInt8<F>]) -> Self {
+        Self {
+            bytes: bytes.to_vec(),
+            len: FpVar::<F>::constant(F::from(bytes.len() as u64)),
+        }
+    }
+
+    /// Construct the constant string `bytes`
+    pub fn constant(bytes: &[u8]) -> Self {
+        Self::from_bytes(&UInt8::<F>::constant_vec(bytes))
+    }
+
+    /// The maximum length of the string
+    pub fn max_len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Compute the field elements allocated by [VarBytesVar::new_input] for `bytes`: the length,
+    /// followed by the bits of `bytes` padded with zeros to `max_len`, see [to_fp_bits]
+    pub fn public_input(bytes: &[u8], max_len: usize) -> Vec<F> {
+        let mut padded = bytes.to_vec();
+        padded.resize(max_len, 0);
+        let mut input = vec![F::from(bytes.len() as u64)];
+        input.extend_from_slice(&to_fp_bits(&padded));
+        input
+    }
+
+    /// Allocate the string returned by `f`, of length at most `max_len`.
+    /// The function enforces that the allocated length is at most `max_len`, and that the padding is zero.
+    ///
+    /// Returns [Error::ConfigMismatch] if the string is longer than `max_len`.
+    pub fn new_variable(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<Vec<u8>, SynthesisError>,
+        max_len: usize,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let mut bytes: Vec<u8> = f()?;
+        if bytes.len() > max_len {
+            return Err(Error::ConfigMismatch(format!(
+                "The length of the string: {} is larger than the maximum length: {}",
+                bytes.len(),
+                max_len
+            ))
+            .into());
+        }
+
+        let len = FpVar::<F>::new_variable(cs.clone(), || Ok(F::from(bytes.len() as u64)), mode)?;
+        bytes.resize(max_len, 0);
+        let bytes = Vec::<UInt8<F>>::new_variable(cs.clone(), || Ok(bytes), mode)?;
+
+        let result = Self { bytes, len };
+        result.enforce_padding()?;
+        Ok(result)
+    }
+
+    /// Allocate the string returned by `f` as a public input, see [VarBytesVar::new_variable]
+    pub fn new_input(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<Vec<u8>, SynthesisError>,
+        max_len: usize,
+    ) -> Result<Self, SynthesisError> {
+        Self::new_variable(cs, f, max_len, AllocationMode::Input)
+    }
+
+    /// Allocate the string returned by `f` as a witness, see [VarBytesVar::new_variable]
+    pub fn new_witness(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<Vec<u8>, SynthesisError>,
+        max_len: usize,
+    ) -> Result<Self, SynthesisError> {
+        Self::new_variable(cs, f, max_len, AllocationMode::Witness)
+    }
+
+    /// The one-hot encoding of the length: the element at index `k` is `TRUE` if and only if `len == k`,
+    /// for `k` from zero to the maximum length
+    pub fn len_indicator(&self) -> Result<Vec<Boolean<F>>, SynthesisError> {
+        (0..=self.bytes.len())
+            .map(|k| self.len.is_eq(&FpVar::<F>::constant(F::from(k as u64))))
+            .collect()
+    }
+
+    /// Enforce that the length is at most the maximum length, and that the bytes after the length are zero
+    fn enforce_padding(&self) -> Result<(), SynthesisError> {
+        let is_len = self.len_indicator()?;
+        Boolean::<F>::kary_or(&is_len)?.enforce_equal(&Boolean::<F>::TRUE)?;
+
+        // The byte at index `i` is padding if and only if `len <= i`
+        let mut is_padding = Boolean::<F>::FALSE;
+        for (byte, is_len) in self.bytes.iter().zip(is_len.iter()) {
+            is_padding = &is_padding | is_len;
+            byte.conditional_enforce_equal(&UInt8::<F>::constant(0), &is_padding)?;
+        }
+
+        Ok(())
+    }
+
+    /// Concatenate `self` and `other`. The maximum length of the result is the sum of the maximum lengths.
+    ///
+    /// If the length of `self` is a constant, the bytes are simply joined. Otherwise, every byte of the result
+    /// is selected according to the length of `self`, at the cost of a constraint per pair of bytes of `self` and `other`.
+    pub fn concat(&self, other: &Self) -> Result<Self, SynthesisError> {
+        let len = &self.len + &other.len;
+        if let FpVar::Constant(self_len) = &self.len {
+            let mut bytes = self.bytes[..to_u64(self_len).unwrap() as usize].to_vec();
+            bytes.extend_from_slice(&other.bytes);
+            return Ok(Self { bytes, len });
+        }
+
+        // As the padding is zero, the byte at index `j` is `self.bytes[j] + other.bytes[j - self.len]`
+        let is_len = self.len_indicator()?;
+        let self_values = self
+            .bytes
+            .iter()
+            .map(to_fp)
+            .collect::<Result<Vec<FpVar<F>>, SynthesisError>>()?;
+        let other_values = other
+            .bytes
+            .iter()
+            .map(to_fp)
+            .collect::<Result<Vec<FpVar<F>>, SynthesisError>>()?;
+
+        let mut values: Vec<FpVar<F>> = Vec::with_capacity(self.bytes.len() + other.bytes.len());
+        for j in 0..self.bytes.len() + other.bytes.len() {
+            let mut value = self_values.get(j).cloned().unwrap_or_else(FpVar::<F>::zero);
+            for (k, is_len) in is_len.iter().enumerate().take(j + 1) {
+                if j - k < other_values.len() {
+                    value += FpVar::<F>::from(is_len.clone()) * &other_values[j - k];
+                }
+            }
+            values.push(value);
+        }
+
+        Ok(Self {
+            bytes: bytes_from_fp(&values)?,
+            len,
+        })
+    }
+
+    /// Concatenate `strings`, see [VarBytesVar::concat]
+    pub fn concat_all(strings: &[Self]) -> Result<Self, SynthesisError> {
+        let mut result = Self::constant(&[]);
+        for string in strings.iter() {
+            result = result.concat(string)?;
+        }
+        Ok(result)
+    }
+
+    /// Compute the var_int encoding of `value`, which is at most `max_value`, as a string of variable length.
+    ///
+    /// Returns [Error::ConfigMismatch] if `value` is not a constant and `max_value` is larger than `0xffff`,
+    /// as var_ints longer than three bytes are not supported.
+    pub fn var_int(value: &FpVar<F>, max_value: usize) -> Result<Self, SynthesisError> {
+        if let FpVar::Constant(value) = value {
+            return Ok(Self::constant(
+                &usize_to_var_int(to_u64(value).unwrap() as usize).unwrap(),
+            ));
+        }
+        if max_value > 0xffff {
+            return Err(Error::ConfigMismatch(format!(
+                "The maximum value: {} is larger than 0xffff",
+                max_value
+            ))
+            .into());
+        }
+
+        let cs = value.cs();
+        let native_value =
+            || -> Result<u64, SynthesisError> { Ok(to_u64(&value.value()?).unwrap_or_default()) };
+        let low = UInt8::<F>::new_witness(cs.clone(), || Ok(native_value()? as u8))?;
+        if max_value < 0xfd {
+            to_fp(&low)?.enforce_equal(value)?;
+            return Ok(Self::from_bytes(&[low]));
+        }
+        let high = UInt8::<F>::new_witness(cs.clone(), || Ok((native_value()? >> 8) as u8))?;
+        (to_fp(&low)? + to_fp(&high)? * FpVar::<F>::constant(F::from(256u64)))
+            .enforce_equal(value)?;
+
+        // Values up to 0xfc are encoded in a single byte, the others as `0xfd || low || high`
+        let is_short = is_less_than(value, &FpVar::<F>::constant(F::from(0xfdu64)), 16)?;
+        let zero = UInt8::<F>::constant(0);
+        Ok(Self {
+            bytes: vec![
+                UInt8::<F>::conditionally_select(&is_short, &low, &UInt8::<F>::constant(0xfd))?,
+                UInt8::<F>::conditionally_select(&is_short, &zero, &low)?,
+                UInt8::<F>::conditionally_select(&is_short, &zero, &high)?,
+            ],
+            len: FpVar::<F>::constant(F::from(3u64))
+                - FpVar::<F>::from(is_short) * FpVar::<F>::constant(F::from(2u64)),
+        })
+    }
+
+    /// Compute the var_int encoding of the length of `self`, see [VarBytesVar::var_int]
+    pub fn size(&self) -> Result<Self, SynthesisError> {
+        Self::var_int(&self.len, self.bytes.len())
+    }
+
+    /// Return `self` if `is_enabled` is `TRUE`, and the empty string, with the same maximum length, otherwise
+    pub fn or_empty(&self, is_enabled: &Boolean<F>) -> Result<Self, SynthesisError> {
+        let zero = UInt8::<F>::constant(0);
+        Ok(Self {
+            bytes: self
+                .bytes
+                .iter()
+                .map(|byte| UInt8::<F>::conditionally_select(is_enabled, byte, &zero))
+                .collect::<Result<Vec<UInt8<F>>, SynthesisError>>()?,
+            len: FpVar::<F>::conditionally_select(is_enabled, &self.len, &FpVar::<F>::zero())?,
+        })
+    }
+
+    /// Prepend the var_int encoding of the length to `self`, see [VarBytesVar::size].
+    /// This is the serialisation of a script.
+    pub fn with_size(&self) -> Result<Self, SynthesisError> {
+        self.size()?.concat(self)
+    }
+
+    /// Check whether the string is a prefix of `bytes`, i.e., the first `len` elements of `bytes` are the
+    /// ones of the string. The result is `FALSE` if the string is longer than `bytes`.
+    pub fn is_prefix_of(&self, bytes: &[UInt8<F>]) -> Result<Boolean<F>, SynthesisError> {
+        let is_len = self.len_indicator()?;
+        let mut checks: Vec<Boolean<F>> = vec![Boolean::<F>::TRUE];
+        // The byte at index `i` is part of the string if and only if `i < len`
+        let mut is_padding = Boolean::<F>::FALSE;
+        for (i, (byte, is_len)) in self.bytes.iter().zip(is_len.iter()).enumerate() {
+            is_padding = &is_padding | is_len;
+            match bytes.get(i) {
+                Some(other) => checks.push(&is_padding | &byte.is_eq(other)?),
+                None => checks.push(is_padding.clone()),
+            }
+        }
+        Boolean::<F>::kary_and(&checks)
+    }
+}
+
+impl<F: PrimeField> R1CSVar<F> for VarBytesVar<F> {
+    /// The bytes of the string, without padding
+    type Value = Vec<u8>;
+
+    fn cs(&self) -> ConstraintSystemRef<F> {
+        self.bytes.cs().or(self.len.cs())
+    }
+
+    fn value(&self) -> Result<Self::Value, SynthesisError> {
+        let len = to_u64(&self.len.value()?).ok_or(SynthesisError::Unsatisfiable)? as usize;
+        let mut bytes = self.bytes.value()?;
+        bytes.truncate(len);
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::{
+        R1CSVar, alloc::AllocVar, fields::fp::FpVar, prelude::Boolean, uint8::UInt8,
+    };
+    use ark_relations::r1cs::{ConstraintSystem, SynthesisError};
+
+    use crate::util::usize_to_var_int;
+
+    use super::VarBytesVar;
+
+    #[test]
+    fn test_concat() {
+        for (a, b) in [
+            (vec![], vec![3, 4]),
+            (vec![1, 2], vec![]),
+            (vec![1], vec![3, 4]),
+        ] {
+            let cs = ConstraintSystem::<F>::new_ref();
+            let a_var = VarBytesVar::<F>::new_witness(cs.clone(), || Ok(a.clone()), 2).unwrap();
+            let b_var = VarBytesVar::<F>::new_witness(cs.clone(), || Ok(b.clone()), 2).unwrap();
+            let result = a_var.concat(&b_var).unwrap();
+            assert_eq!(result.max_len(), 4);
+            assert_eq!(result.value().unwrap(), [a.clone(), b.clone()].concat());
+            // The padding is zero
+            assert!(
+                result.bytes.value().unwrap()[a.len() + b.len()..]
+                    .iter()
+                    .all(|byte| *byte == 0)
+            );
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_concat_constant_length() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let a_var = VarBytesVar::<F>::constant(&[1, 2]);
+        let b_var = VarBytesVar::<F>::new_witness(cs.clone(), || Ok(vec![3]), 2).unwrap();
+        let n_constraints = cs.num_constraints();
+        let result = a_var.concat(&b_var).unwrap();
+        assert_eq!(cs.num_constraints(), n_constraints);
+        assert_eq!(result.max_len(), 4);
+        assert_eq!(result.value().unwrap(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_size() {
+        for (len, max_len) in [(0, 10), (10, 10), (0xfc, 300), (0xfd, 300), (300, 300)] {
+            let cs = ConstraintSystem::<F>::new_ref();
+            let bytes =
+                VarBytesVar::<F>::new_witness(cs.clone(), || Ok(vec![1; len]), max_len).unwrap();
+            assert_eq!(
+                bytes.size().unwrap().value().unwrap(),
+                usize_to_var_int(len).unwrap()
+            );
+            assert_eq!(
+                bytes.with_size().unwrap().value().unwrap(),
+                [usize_to_var_int(len).unwrap(), vec![1; len]].concat()
+            );
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_or_empty() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let bytes = VarBytesVar::<F>::new_witness(cs.clone(), || Ok(vec![1, 2]), 3).unwrap();
+        let string = bytes.or_empty(&Boolean::<F>::TRUE).unwrap();
+        assert_eq!(string.value().unwrap(), vec![1, 2]);
+        let empty = bytes.or_empty(&Boolean::<F>::FALSE).unwrap();
+        assert_eq!(empty.value().unwrap(), Vec::<u8>::new());
+        assert_eq!(empty.bytes.value().unwrap(), vec![0; 3]);
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_is_prefix_of() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let bytes = Vec::<UInt8<F>>::new_witness(cs.clone(), || Ok(vec![1u8, 2, 3])).unwrap();
+        for (string, is_prefix) in [
+            (vec![], true),
+            (vec![1, 2], true),
+            (vec![1, 2, 3], true),
+            (vec![1, 3], false),
+            (vec![1, 2, 3, 4], false),
+        ] {
+            let string_var = VarBytesVar::<F>::new_witness(cs.clone(), || Ok(string), 4).unwrap();
+            assert_eq!(
+                string_var.is_prefix_of(&bytes).unwrap().value().unwrap(),
+                is_prefix
+            );
+        }
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_wrong_padding() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let bytes = VarBytesVar::<F> {
+            bytes: Vec::<UInt8<F>>::new_witness(cs.clone(), || Ok(vec![1, 2])).unwrap(),
+            len: FpVar::<F>::new_witness(cs.clone(), || Ok(F::from(1u64))).unwrap(),
+        };
+        bytes.enforce_padding().unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_too_long() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        assert!(matches!(
+            VarBytesVar::<F>::new_witness(cs, || Ok(vec![1, 2, 3]), 2),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+
+    #[test]
+    fn test_var_int_too_large() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let value = FpVar::<F>::new_witness(cs, || Ok(F::from(1u64))).unwrap();
+        assert!(matches!(
+            VarBytesVar::<F>::var_int(&value, 0x10000),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+}
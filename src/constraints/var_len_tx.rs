@@ -0,0 +1,798 @@
+//! Implementation of [VarLenTxVar], R1CS version of a Bitcoin [Tx] whose scripts have variable length,
+//! and whose numbers of inputs and outputs are variable up to a maximum
+use std::{borrow::Borrow, marker::PhantomData};
+
+use ark_crypto_primitives::crh::sha256::constraints::DigestVar;
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    R1CSVar,
+    alloc::AllocVar,
+    eq::EqGadget,
+    fields::{FieldVar, fp::FpVar},
+    prelude::{AllocationMode, Boolean, ToBytesGadget},
+    select::CondSelectGadget,
+    uint8::UInt8,
+    uint32::UInt32,
+    uint64::UInt64,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+use chain_gang::script::Script;
+use chain_gang::transaction::sighash::{
+    SIGHASH_ANYONECANPAY, SIGHASH_FORKID, SIGHASH_NONE, SIGHASH_SINGLE,
+};
+use chain_gang::util::Hash256;
+
+use crate::constraints::{hash256::Hash256Gadget, outpoint::OutPointVar, var_bytes::VarBytesVar};
+use crate::error::Error;
+use crate::traits::PreSigHashSerialise;
+use crate::util::{to_fp_bits, to_u64};
+
+/// Configuration trait for [VarLenTxVar]: like [TxVarConfig](crate::constraints::tx::TxVarConfig), but the
+/// numbers of inputs and outputs and the lengths of the scripts are upper bounds, and the actual values are
+/// variables of the circuit.
+///
+/// A single circuit, and hence a single proving key, covers all the transactions that fit in the bounds.
+pub trait VarLenTxVarConfig {
+    /// Maximum number of inputs
+    const MAX_INPUTS: usize;
+    /// Maximum number of outputs
+    const MAX_OUTPUTS: usize;
+    /// Maximum length of unlocking scripts, one per input slot
+    const MAX_LEN_UNLOCK_SCRIPTS: &[usize];
+    /// Maximum length of locking scripts, one per output slot
+    const MAX_LEN_LOCK_SCRIPTS: &[usize];
+}
+
+/// Check the invariants of a [VarLenTxVarConfig]: there must be one maximum length per input and per output slot,
+/// and the numbers of inputs and outputs must be encoded in a single byte.
+pub const fn check_var_len_tx_var_config<P: VarLenTxVarConfig>() {
+    assert!(
+        P::MAX_LEN_UNLOCK_SCRIPTS.len() == P::MAX_INPUTS,
+        "P::MAX_LEN_UNLOCK_SCRIPTS.len() is different from P::MAX_INPUTS"
+    );
+    assert!(
+        P::MAX_LEN_LOCK_SCRIPTS.len() == P::MAX_OUTPUTS,
+        "P::MAX_LEN_LOCK_SCRIPTS.len() is different from P::MAX_OUTPUTS"
+    );
+    assert!(
+        P::MAX_INPUTS < 0xfd && P::MAX_OUTPUTS < 0xfd,
+        "P::MAX_INPUTS or P::MAX_OUTPUTS is larger than 0xfc"
+    );
+}
+
+/// Check that `tx` fits in the structure set in the [VarLenTxVarConfig]: it has at most the number of inputs and
+/// outputs set in `P`, and its scripts are not longer than the maximum lengths of their slots.
+///
+/// Returns [Error::ConfigMismatch] describing the first mismatch.
+pub fn check_var_len_tx<P: VarLenTxVarConfig>(tx: &Tx) -> Result<(), Error> {
+    if tx.inputs.len() > P::MAX_INPUTS || tx.outputs.len() > P::MAX_OUTPUTS {
+        return Err(Error::ConfigMismatch(format!(
+            "The transaction has {} inputs and {} outputs, while the parameters set at most {} inputs and {} outputs",
+            tx.inputs.len(),
+            tx.outputs.len(),
+            P::MAX_INPUTS,
+            P::MAX_OUTPUTS
+        )));
+    }
+    for (i, (input, max_len)) in tx
+        .inputs
+        .iter()
+        .zip(P::MAX_LEN_UNLOCK_SCRIPTS.iter())
+        .enumerate()
+    {
+        if input.unlock_script.0.len() > *max_len {
+            return Err(Error::ConfigMismatch(format!(
+                "The length of the unlocking script of input {}: {} is larger than the maximum length set in the parameters: {}",
+                i,
+                input.unlock_script.0.len(),
+                max_len
+            )));
+        }
+    }
+    for (i, (output, max_len)) in tx
+        .outputs
+        .iter()
+        .zip(P::MAX_LEN_LOCK_SCRIPTS.iter())
+        .enumerate()
+    {
+        if output.lock_script.0.len() > *max_len {
+            return Err(Error::ConfigMismatch(format!(
+                "The length of the locking script of output {}: {} is larger than the maximum length set in the parameters: {}",
+                i,
+                output.lock_script.0.len(),
+                max_len
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// The input allocated in the disabled slots
+fn disabled_input() -> TxIn {
+    TxIn {
+        prev_output: OutPoint {
+            hash: Hash256([0; 32]),
+            index: 0,
+        },
+        unlock_script: Script(vec![]),
+        sequence: 0,
+    }
+}
+
+/// The output allocated in the disabled slots
+fn disabled_output() -> TxOut {
+    TxOut {
+        satoshis: 0,
+        lock_script: Script(vec![]),
+    }
+}
+
+/// Compute which of `max_count` slots are enabled when `count` of them are used: the slot at index `i` is enabled
+/// if and only if `i < count`. The function enforces that `count` is at most `max_count`.
+fn enabled_slots<F: PrimeField>(
+    count: &FpVar<F>,
+    max_count: usize,
+) -> Result<Vec<Boolean<F>>, SynthesisError> {
+    let is_count = (0..=max_count)
+        .map(|k| count.is_eq(&FpVar::<F>::constant(F::from(k as u64))))
+        .collect::<Result<Vec<Boolean<F>>, SynthesisError>>()?;
+    Boolean::<F>::kary_or(&is_count)?.enforce_equal(&Boolean::<F>::TRUE)?;
+
+    let mut is_disabled = Boolean::<F>::FALSE;
+    let mut is_enabled: Vec<Boolean<F>> = Vec::with_capacity(max_count);
+    for is_count in is_count.iter().take(max_count) {
+        is_disabled = &is_disabled | is_count;
+        is_enabled.push(!is_disabled.clone());
+    }
+
+    Ok(is_enabled)
+}
+
+/// R1CS version of [TxIn] with an unlocking script of variable length, allocated in a slot that may be disabled
+#[derive(Debug, Clone)]
+pub struct VarLenTxInVar<F: PrimeField> {
+    /// Whether the slot holds an input of the transaction
+    pub is_enabled: Boolean<F>,
+    /// OutPoint being spent
+    pub prev_output: OutPointVar<F>,
+    /// Unlocking script
+    pub unlock_script: VarBytesVar<F>,
+    /// Sequence
+    pub sequence: UInt32<F>,
+}
+
+impl<F: PrimeField> VarLenTxInVar<F> {
+    /// Serialise `self` for TxID calculation: the serialisation of a disabled slot is empty
+    pub fn serialise(&self) -> Result<VarBytesVar<F>, SynthesisError> {
+        VarBytesVar::<F>::from_bytes(&self.prev_output.to_bytes_le()?)
+            .concat(&self.unlock_script.with_size()?)?
+            .concat(&VarBytesVar::<F>::from_bytes(&self.sequence.to_bytes_le()?))?
+            .or_empty(&self.is_enabled)
+    }
+
+    /// Enforce that a disabled slot holds [disabled_input]
+    fn enforce_disabled_is_empty(&self) -> Result<(), SynthesisError> {
+        let is_disabled = !self.is_enabled.clone();
+        let zero = UInt8::<F>::constant(0);
+        for byte in self
+            .prev_output
+            .to_bytes_le()?
+            .iter()
+            .chain(self.sequence.to_bytes_le()?.iter())
+        {
+            byte.conditional_enforce_equal(&zero, &is_disabled)?;
+        }
+        self.unlock_script
+            .len
+            .conditional_enforce_equal(&FpVar::<F>::zero(), &is_disabled)
+    }
+}
+
+/// R1CS version of [TxOut] with a locking script of variable length, allocated in a slot that may be disabled
+#[derive(Debug, Clone)]
+pub struct VarLenTxOutVar<F: PrimeField> {
+    /// Whether the slot holds an output of the transaction
+    pub is_enabled: Boolean<F>,
+    /// Amount
+    pub satoshis: UInt64<F>,
+    /// Locking script
+    pub lock_script: VarBytesVar<F>,
+}
+
+impl<F: PrimeField> VarLenTxOutVar<F> {
+    /// Serialise `self` for TxID and sighash calculation: the serialisation of a disabled slot is empty
+    pub fn serialise(&self) -> Result<VarBytesVar<F>, SynthesisError> {
+        VarBytesVar::<F>::from_bytes(&self.satoshis.to_bytes_le()?)
+            .concat(&self.lock_script.with_size()?)?
+            .or_empty(&self.is_enabled)
+    }
+
+    /// Enforce that a disabled slot holds [disabled_output]
+    fn enforce_disabled_is_empty(&self) -> Result<(), SynthesisError> {
+        let is_disabled = !self.is_enabled.clone();
+        self.satoshis
+            .conditional_enforce_equal(&UInt64::<F>::constant(0), &is_disabled)?;
+        self.lock_script
+            .len
+            .conditional_enforce_equal(&FpVar::<F>::zero(), &is_disabled)
+    }
+}
+
+/// R1CS version of [Tx] fitting in the bounds set in `P`: the numbers of inputs and outputs are variables of the
+/// circuit, and so are the lengths of the scripts.
+///
+/// The inputs and outputs are allocated in `P::MAX_INPUTS` and `P::MAX_OUTPUTS` slots: the first `n_inputs`
+/// (resp. `n_outputs`) are enabled, and the others hold empty inputs (resp. outputs). The serialisation, the TxID
+/// and the sighash exclude the disabled slots, and only depend on the actual lengths of the scripts.
+#[derive(Debug)]
+pub struct VarLenTxVar<F: PrimeField, P: VarLenTxVarConfig + Clone> {
+    _config: PhantomData<P>,
+    pub version: UInt32<F>,
+    /// Number of inputs
+    pub n_inputs: FpVar<F>,
+    pub inputs: Vec<VarLenTxInVar<F>>,
+    /// Number of outputs
+    pub n_outputs: FpVar<F>,
+    pub outputs: Vec<VarLenTxOutVar<F>>,
+    pub lock_time: UInt32<F>,
+}
+
+impl<F: PrimeField, P: VarLenTxVarConfig + Clone> Clone for VarLenTxVar<F, P> {
+    fn clone(&self) -> Self {
+        Self {
+            _config: PhantomData,
+            version: self.version.clone(),
+            n_inputs: self.n_inputs.clone(),
+            inputs: self.inputs.clone(),
+            n_outputs: self.n_outputs.clone(),
+            outputs: self.outputs.clone(),
+            lock_time: self.lock_time.clone(),
+        }
+    }
+}
+
+impl<F: PrimeField, P: VarLenTxVarConfig + Clone> VarLenTxVar<F, P> {
+    /// Compute the field elements allocated by [VarLenTxVar::new_input] for `tx`: the disabled slots hold empty
+    /// inputs and outputs, and the scripts are allocated as in [VarBytesVar::public_input].
+    pub fn public_input(tx: &Tx) -> Vec<F> {
+        let mut input = to_fp_bits(&tx.version.to_le_bytes());
+        input.push(F::from(tx.inputs.len() as u64));
+        for (i, max_len) in P::MAX_LEN_UNLOCK_SCRIPTS.iter().enumerate() {
+            let txin = tx.inputs.get(i).cloned().unwrap_or_else(disabled_input);
+            input.extend_from_slice(&OutPointVar::<F>::public_input(&txin.prev_output));
+            input.extend_from_slice(&VarBytesVar::<F>::public_input(
+                &txin.unlock_script.0,
+                *max_len,
+            ));
+            input.extend_from_slice(&to_fp_bits(&txin.sequence.to_le_bytes()));
+        }
+        input.push(F::from(tx.outputs.len() as u64));
+        for (i, max_len) in P::MAX_LEN_LOCK_SCRIPTS.iter().enumerate() {
+            let txout = tx.outputs.get(i).cloned().unwrap_or_else(disabled_output);
+            input.extend_from_slice(&to_fp_bits(&(txout.satoshis as u64).to_le_bytes()));
+            input.extend_from_slice(&VarBytesVar::<F>::public_input(
+                &txout.lock_script.0,
+                *max_len,
+            ));
+        }
+        input.extend_from_slice(&to_fp_bits(&tx.lock_time.to_le_bytes()));
+        input
+    }
+
+    /// Serialise `self` for TxID calculation
+    pub fn serialise(&self) -> Result<VarBytesVar<F>, SynthesisError> {
+        let mut parts: Vec<VarBytesVar<F>> = vec![
+            VarBytesVar::<F>::from_bytes(&self.version.to_bytes_le()?),
+            VarBytesVar::<F>::var_int(&self.n_inputs, P::MAX_INPUTS)?,
+        ];
+        for input in self.inputs.iter() {
+            parts.push(input.serialise()?);
+        }
+        parts.push(VarBytesVar::<F>::var_int(&self.n_outputs, P::MAX_OUTPUTS)?);
+        for output in self.outputs.iter() {
+            parts.push(output.serialise()?);
+        }
+        parts.push(VarBytesVar::<F>::from_bytes(&self.lock_time.to_bytes_le()?));
+
+        VarBytesVar::<F>::concat_all(&parts)
+    }
+
+    /// The length of the serialisation of `self`, see [VarLenTxVar::serialise]
+    pub fn size(&self) -> Result<FpVar<F>, SynthesisError> {
+        // Version, lock time and the var_int numbers of inputs and outputs, which are at most 0xfc
+        let mut size = FpVar::<F>::constant(F::from(10u64));
+        for input in self.inputs.iter() {
+            let input_size = FpVar::<F>::constant(F::from(40u64))
+                + &input.unlock_script.size()?.len
+                + &input.unlock_script.len;
+            size += input_size * FpVar::<F>::from(input.is_enabled.clone());
+        }
+        for output in self.outputs.iter() {
+            let output_size = FpVar::<F>::constant(F::from(8u64))
+                + &output.lock_script.size()?.len
+                + &output.lock_script.len;
+            size += output_size * FpVar::<F>::from(output.is_enabled.clone());
+        }
+
+        Ok(size)
+    }
+
+    /// Calculate the txid of `self`
+    pub fn txid(&self) -> Result<DigestVar<F>, SynthesisError> {
+        Hash256Gadget::<F>::evaluate_var_len(&self.serialise()?)
+    }
+
+    /// Compute the serialisation of [Tx] for `pre_sighash` calculation, see [TxVar::sighash_preimage](crate::constraints::tx::TxVar::sighash_preimage).
+    /// The function enforces that the slot at `n_input` is enabled.
+    ///
+    /// **Note**: The function assumes that `prev_lock_script` has already been modified to handle `OP_CODESEPARATOR`.
+    /// The function returns an error if `SIGHASH_FORKID` is not set in `sighash_flags`, as the legacy algorithm is not supported,
+    /// or if `n_input` is not smaller than `P::MAX_INPUTS`.
+    pub fn pre_sighash_serialise(
+        &self,
+        n_input: usize,
+        prev_lock_script: &VarBytesVar<F>,
+        prev_amount: &UInt64<F>,
+        sighash_flags: &u8,
+    ) -> Result<VarBytesVar<F>, SynthesisError> {
+        if sighash_flags & SIGHASH_FORKID == 0 {
+            return Err(Error::ConfigMismatch(format!(
+                "The sighash flags: {} do not have SIGHASH_FORKID set",
+                sighash_flags
+            ))
+            .into());
+        }
+        if n_input >= P::MAX_INPUTS {
+            return Err(Error::IndexOutOfBounds(format!(
+                "Input index: {} is larger than the maximum number of inputs: {}",
+                n_input,
+                P::MAX_INPUTS
+            ))
+            .into());
+        }
+        self.inputs[n_input]
+            .is_enabled
+            .enforce_equal(&Boolean::<F>::TRUE)?;
+
+        // Handle sighash flags
+        let base_flags = sighash_flags & 31;
+        let anyone_can_pay = sighash_flags & SIGHASH_ANYONECANPAY != 0;
+        let zero = || DigestVar(vec![UInt8::<F>::constant(0); 32]);
+
+        // 1. HashPrevOut: the disabled slots are excluded
+        let hash_prevouts: DigestVar<F> = if anyone_can_pay {
+            zero()
+        } else {
+            let mut prevouts: Vec<VarBytesVar<F>> = Vec::with_capacity(P::MAX_INPUTS);
+            for input in self.inputs.iter() {
+                prevouts.push(
+                    VarBytesVar::<F>::from_bytes(&input.prev_output.pre_sighash_serialise()?)
+                        .or_empty(&input.is_enabled)?,
+                );
+            }
+            Hash256Gadget::<F>::evaluate_var_len(&VarBytesVar::<F>::concat_all(&prevouts)?)?
+        };
+        // 2. HashSequence: the disabled slots are excluded
+        let hash_sequence: DigestVar<F> =
+            if anyone_can_pay || base_flags == SIGHASH_SINGLE || base_flags == SIGHASH_NONE {
+                zero()
+            } else {
+                let mut sequences: Vec<VarBytesVar<F>> = Vec::with_capacity(P::MAX_INPUTS);
+                for input in self.inputs.iter() {
+                    sequences.push(
+                        VarBytesVar::<F>::from_bytes(&input.sequence.to_bytes_le()?)
+                            .or_empty(&input.is_enabled)?,
+                    );
+                }
+                Hash256Gadget::<F>::evaluate_var_len(&VarBytesVar::<F>::concat_all(&sequences)?)?
+            };
+        // 3. HashOutputs: for `SIGHASH_SINGLE`, it is zero if the slot at `n_input` is disabled
+        let hash_outputs: DigestVar<F> = if base_flags == SIGHASH_SINGLE {
+            if n_input < self.outputs.len() {
+                DigestVar::<F>::conditionally_select(
+                    &self.outputs[n_input].is_enabled,
+                    &Hash256Gadget::<F>::evaluate_var_len(&self.outputs[n_input].serialise()?)?,
+                    &zero(),
+                )?
+            } else {
+                zero()
+            }
+        } else if base_flags == SIGHASH_NONE {
+            zero()
+        } else {
+            let outputs = self
+                .outputs
+                .iter()
+                .map(|output| output.serialise())
+                .collect::<Result<Vec<VarBytesVar<F>>, SynthesisError>>()?;
+            Hash256Gadget::<F>::evaluate_var_len(&VarBytesVar::<F>::concat_all(&outputs)?)?
+        };
+
+        // The script code is the only part of the preimage of variable length
+        let mut head: Vec<UInt8<F>> = Vec::new();
+        head.extend_from_slice(self.version.to_bytes_le()?.as_slice());
+        head.extend_from_slice(hash_prevouts.to_bytes_le()?.as_slice());
+        head.extend_from_slice(hash_sequence.to_bytes_le()?.as_slice());
+        head.extend_from_slice(
+            self.inputs[n_input]
+                .prev_output
+                .pre_sighash_serialise()?
+                .as_slice(),
+        );
+        let mut tail: Vec<UInt8<F>> = Vec::new();
+        tail.extend_from_slice(prev_amount.to_bytes_le()?.as_slice());
+        tail.extend_from_slice(self.inputs[n_input].sequence.to_bytes_le()?.as_slice());
+        tail.extend_from_slice(hash_outputs.to_bytes_le()?.as_slice());
+        tail.extend_from_slice(self.lock_time.to_bytes_le()?.as_slice());
+        tail.extend_from_slice(
+            UInt32::<F>::constant(*sighash_flags as u32)
+                .to_bytes_le()?
+                .as_slice(),
+        );
+
+        VarBytesVar::<F>::from_bytes(&head)
+            .concat(&prev_lock_script.with_size()?)?
+            .concat(&VarBytesVar::<F>::from_bytes(&tail))
+    }
+
+    /// Sighash calculation according to the [Message Digest Algorithm](https://github.com/bitcoin-sv/bitcoin-sv/blob/master/doc/abc/replay-protected-sighash.md#digest-algorithm),
+    /// see [VarLenTxVar::pre_sighash_serialise]
+    pub fn sighash(
+        &self,
+        n_input: usize,
+        prev_lock_script: &VarBytesVar<F>,
+        prev_amount: &UInt64<F>,
+        sighash_flags: &u8,
+    ) -> Result<DigestVar<F>, SynthesisError> {
+        Hash256Gadget::<F>::evaluate_var_len(&self.pre_sighash_serialise(
+            n_input,
+            prev_lock_script,
+            prev_amount,
+            sighash_flags,
+        )?)
+    }
+}
+
+impl<F: PrimeField, P: VarLenTxVarConfig + Clone> AllocVar<Tx, F> for VarLenTxVar<F, P> {
+    fn new_variable<T: Borrow<Tx>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let tx: Tx = f().map(|tx| tx.borrow().clone())?;
+
+        // Check the invariants of the configuration at compile time
+        const { check_var_len_tx_var_config::<P>() };
+
+        // Check that the transaction fits in the structure set in the parameters
+        check_var_len_tx::<P>(&tx)?;
+
+        let version: UInt32<F> = UInt32::<F>::new_variable(cs.clone(), || Ok(tx.version), mode)?;
+
+        // Inputs: the slots after the number of inputs hold empty inputs
+        let n_inputs: FpVar<F> =
+            FpVar::<F>::new_variable(cs.clone(), || Ok(F::from(tx.inputs.len() as u64)), mode)?;
+        let mut inputs: Vec<VarLenTxInVar<F>> = Vec::with_capacity(P::MAX_INPUTS);
+        for (i, (is_enabled, max_len)) in enabled_slots(&n_inputs, P::MAX_INPUTS)?
+            .into_iter()
+            .zip(P::MAX_LEN_UNLOCK_SCRIPTS.iter())
+            .enumerate()
+        {
+            let txin = tx.inputs.get(i).cloned().unwrap_or_else(disabled_input);
+            let input = VarLenTxInVar {
+                is_enabled,
+                prev_output: OutPointVar::<F>::new_variable(
+                    cs.clone(),
+                    || Ok(txin.prev_output.clone()),
+                    mode,
+                )?,
+                unlock_script: VarBytesVar::<F>::new_variable(
+                    cs.clone(),
+                    || Ok(txin.unlock_script.0.clone()),
+                    *max_len,
+                    mode,
+                )?,
+                sequence: UInt32::<F>::new_variable(cs.clone(), || Ok(txin.sequence), mode)?,
+            };
+            input.enforce_disabled_is_empty()?;
+            inputs.push(input);
+        }
+
+        // Outputs: the slots after the number of outputs hold empty outputs
+        let n_outputs: FpVar<F> =
+            FpVar::<F>::new_variable(cs.clone(), || Ok(F::from(tx.outputs.len() as u64)), mode)?;
+        let mut outputs: Vec<VarLenTxOutVar<F>> = Vec::with_capacity(P::MAX_OUTPUTS);
+        for (i, (is_enabled, max_len)) in enabled_slots(&n_outputs, P::MAX_OUTPUTS)?
+            .into_iter()
+            .zip(P::MAX_LEN_LOCK_SCRIPTS.iter())
+            .enumerate()
+        {
+            let txout = tx.outputs.get(i).cloned().unwrap_or_else(disabled_output);
+            let output = VarLenTxOutVar {
+                is_enabled,
+                satoshis: UInt64::<F>::new_variable(
+                    cs.clone(),
+                    || Ok(txout.satoshis as u64),
+                    mode,
+                )?,
+                lock_script: VarBytesVar::<F>::new_variable(
+                    cs.clone(),
+                    || Ok(txout.lock_script.0.clone()),
+                    *max_len,
+                    mode,
+                )?,
+            };
+            output.enforce_disabled_is_empty()?;
+            outputs.push(output);
+        }
+
+        let lock_time: UInt32<F> =
+            UInt32::<F>::new_variable(cs.clone(), || Ok(tx.lock_time), mode)?;
+
+        Ok(Self {
+            _config: PhantomData,
+            version,
+            n_inputs,
+            inputs,
+            n_outputs,
+            outputs,
+            lock_time,
+        })
+    }
+}
+
+impl<F: PrimeField, P: VarLenTxVarConfig + Clone> R1CSVar<F> for VarLenTxVar<F, P> {
+    type Value = Tx;
+
+    fn cs(&self) -> ConstraintSystemRef<F> {
+        let mut result = ConstraintSystemRef::None;
+        result = self.version.cs().or(result);
+        result = self.n_inputs.cs().or(result);
+        for input in self.inputs.iter() {
+            result = input.prev_output.cs().or(result);
+            result = input.unlock_script.cs().or(result);
+            result = input.sequence.cs().or(result);
+        }
+        result = self.n_outputs.cs().or(result);
+        for output in self.outputs.iter() {
+            result = output.satoshis.cs().or(result);
+            result = output.lock_script.cs().or(result);
+        }
+        result = self.lock_time.cs().or(result);
+        result
+    }
+
+    /// The transaction made of the enabled slots
+    fn value(&self) -> Result<Self::Value, SynthesisError> {
+        let count = |n: &FpVar<F>| -> Result<usize, SynthesisError> {
+            Ok(to_u64(&n.value()?).ok_or(SynthesisError::Unsatisfiable)? as usize)
+        };
+
+        let mut inputs: Vec<TxIn> = Vec::with_capacity(self.inputs.len());
+        for input in self.inputs.iter().take(count(&self.n_inputs)?) {
+            inputs.push(TxIn {
+                prev_output: input.prev_output.value()?,
+                unlock_script: Script(input.unlock_script.value()?),
+                sequence: input.sequence.value()?,
+            });
+        }
+        let mut outputs: Vec<TxOut> = Vec::with_capacity(self.outputs.len());
+        for output in self.outputs.iter().take(count(&self.n_outputs)?) {
+            outputs.push(TxOut {
+                satoshis: output.satoshis.value()? as i64,
+                lock_script: Script(output.lock_script.value()?),
+            });
+        }
+
+        Ok(Tx {
+            version: self.version.value()?,
+            inputs,
+            outputs,
+            lock_time: self.lock_time.value()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::{R1CSVar, alloc::AllocVar, uint32::UInt32, uint64::UInt64};
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::script::Script;
+    use chain_gang::transaction::sighash::{
+        SIGHASH_ALL, SIGHASH_ANYONECANPAY, SIGHASH_FORKID, SIGHASH_SINGLE, SigHashCache, sighash,
+    };
+    use chain_gang::util::{Hash256, Serializable};
+
+    use crate::constraints::var_bytes::VarBytesVar;
+    use crate::util::tx_size;
+
+    use super::{VarLenTxVar, VarLenTxVarConfig, check_var_len_tx};
+
+    #[derive(Clone)]
+    struct Config;
+    impl VarLenTxVarConfig for Config {
+        const MAX_INPUTS: usize = 2;
+        const MAX_OUTPUTS: usize = 2;
+        const MAX_LEN_UNLOCK_SCRIPTS: &[usize] = &[3, 0];
+        const MAX_LEN_LOCK_SCRIPTS: &[usize] = &[4, 2];
+    }
+
+    fn tx(unlock_scripts: Vec<Vec<u8>>, lock_scripts: Vec<Vec<u8>>) -> Tx {
+        Tx {
+            version: 2,
+            inputs: unlock_scripts
+                .into_iter()
+                .enumerate()
+                .map(|(i, unlock_script)| TxIn {
+                    prev_output: OutPoint {
+                        hash: Hash256::decode(
+                            "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                        )
+                        .unwrap(),
+                        index: i as u32,
+                    },
+                    unlock_script: Script(unlock_script),
+                    sequence: 0xffffffff - i as u32,
+                })
+                .collect(),
+            outputs: lock_scripts
+                .into_iter()
+                .enumerate()
+                .map(|(i, lock_script)| TxOut {
+                    satoshis: 100 * (i as i64 + 1),
+                    lock_script: Script(lock_script),
+                })
+                .collect(),
+            lock_time: 0,
+        }
+    }
+
+    #[test]
+    fn test_serialisation_and_txid() {
+        for tx in [
+            tx(vec![vec![], vec![]], vec![vec![], vec![]]),
+            tx(vec![vec![1, 2], vec![]], vec![vec![3, 4, 5, 6], vec![7]]),
+            tx(vec![vec![1, 2, 3], vec![]], vec![vec![3], vec![7, 8]]),
+            // Disabled slots
+            tx(vec![vec![1]], vec![vec![3, 4]]),
+            tx(vec![vec![1], vec![]], vec![]),
+            tx(vec![], vec![vec![3, 4]]),
+        ] {
+            let cs = ConstraintSystem::<F>::new_ref();
+            let tx_var =
+                VarLenTxVar::<F, Config>::new_witness(cs.clone(), || Ok(tx.clone())).unwrap();
+            let mut tx_bytes: Vec<u8> = Vec::new();
+            tx.write(&mut tx_bytes).unwrap();
+
+            assert_eq!(tx_var.value().unwrap(), tx);
+            assert_eq!(tx_var.serialise().unwrap().value().unwrap(), tx_bytes);
+            assert_eq!(
+                tx_var.size().unwrap().value().unwrap(),
+                F::from(tx_size(&tx) as u64)
+            );
+            assert_eq!(tx_var.txid().unwrap().value().unwrap(), tx.hash().0);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_sighash() {
+        for (tx, n_input, prev_lock_script, sighash_flags) in [
+            (
+                tx(vec![vec![1], vec![]], vec![vec![3, 4], vec![7]]),
+                0,
+                vec![],
+                SIGHASH_ALL | SIGHASH_FORKID,
+            ),
+            (
+                tx(vec![vec![1], vec![]], vec![vec![3, 4], vec![7]]),
+                0,
+                vec![0x51, 0x52],
+                SIGHASH_ALL | SIGHASH_FORKID,
+            ),
+            (
+                tx(vec![vec![1], vec![]], vec![vec![3, 4], vec![7]]),
+                0,
+                vec![0x51],
+                SIGHASH_ALL | SIGHASH_ANYONECANPAY | SIGHASH_FORKID,
+            ),
+            (
+                tx(vec![vec![1]], vec![vec![3, 4]]),
+                0,
+                vec![0x51],
+                SIGHASH_ALL | SIGHASH_FORKID,
+            ),
+            // The output at the index of the input is in a disabled slot
+            (
+                tx(vec![vec![1], vec![]], vec![vec![3, 4]]),
+                1,
+                vec![0x51],
+                SIGHASH_SINGLE | SIGHASH_FORKID,
+            ),
+        ] {
+            let expected = sighash(
+                &tx,
+                n_input,
+                &prev_lock_script,
+                1000,
+                sighash_flags,
+                &mut SigHashCache::new(),
+            )
+            .unwrap();
+
+            let cs = ConstraintSystem::<F>::new_ref();
+            let tx_var =
+                VarLenTxVar::<F, Config>::new_witness(cs.clone(), || Ok(tx.clone())).unwrap();
+            let prev_lock_script_var =
+                VarBytesVar::<F>::new_witness(cs.clone(), || Ok(prev_lock_script), 2).unwrap();
+            let prev_amount = UInt64::<F>::new_witness(cs.clone(), || Ok(1000)).unwrap();
+            let sighash_var = tx_var
+                .sighash(n_input, &prev_lock_script_var, &prev_amount, &sighash_flags)
+                .unwrap();
+
+            assert_eq!(sighash_var.value().unwrap(), expected.0);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_sighash_of_disabled_input() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = VarLenTxVar::<F, Config>::new_witness(cs.clone(), || {
+            Ok(tx(vec![vec![1]], vec![vec![3, 4]]))
+        })
+        .unwrap();
+        tx_var
+            .sighash(
+                1,
+                &VarBytesVar::<F>::constant(&[]),
+                &UInt64::<F>::constant(1000),
+                &(SIGHASH_ALL | SIGHASH_FORKID),
+            )
+            .unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_disabled_slot_is_empty() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = VarLenTxVar::<F, Config>::new_witness(cs.clone(), || {
+            Ok(tx(vec![vec![1]], vec![vec![3, 4]]))
+        })
+        .unwrap();
+        assert!(cs.is_satisfied().unwrap());
+
+        let mut input = tx_var.inputs[1].clone();
+        input.sequence = UInt32::<F>::new_witness(cs.clone(), || Ok(1)).unwrap();
+        input.enforce_disabled_is_empty().unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_check_var_len_tx() {
+        assert!(
+            check_var_len_tx::<Config>(&tx(vec![vec![1; 3], vec![]], vec![vec![1; 4], vec![1; 2]]))
+                .is_ok()
+        );
+        assert!(check_var_len_tx::<Config>(&tx(vec![], vec![])).is_ok());
+        assert!(check_var_len_tx::<Config>(&tx(vec![vec![1; 4]], vec![])).is_err());
+        assert!(check_var_len_tx::<Config>(&tx(vec![], vec![vec![], vec![1; 3]])).is_err());
+        assert!(check_var_len_tx::<Config>(&tx(vec![vec![]; 3], vec![])).is_err());
+    }
+
+    #[test]
+    fn test_new_variable_with_too_many_inputs() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        assert!(
+            VarLenTxVar::<F, Config>::new_witness(cs, || Ok(tx(vec![vec![]; 3], vec![])))
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,284 @@
+//! Implementation of [WitnessVar], R1CS version of the witness stack of a SegWit input,
+//! see [BIP141](https://github.com/bitcoin/bips/blob/master/bip-0141.mediawiki)
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::AllocVar,
+    prelude::{AllocationMode, ToBytesGadget},
+    uint8::UInt8,
+};
+
+use ark_relations::r1cs::{Namespace, SynthesisError};
+use std::borrow::Borrow;
+
+use chain_gang::messages::Tx;
+use chain_gang::util::{Hash256, Serializable, sha256d};
+
+use crate::error::Error;
+use crate::util::{to_fp_bits, usize_to_var_int};
+
+use ark_r1cs_std::boolean::Boolean;
+use ark_r1cs_std::eq::EqGadget;
+use ark_r1cs_std::select::CondSelectGadget;
+
+use ark_r1cs_std::R1CSVar;
+use ark_relations::r1cs::ConstraintSystemRef;
+
+/// The witness stack of an input: the list of its items
+pub type Witness = Vec<Vec<u8>>;
+
+/// A [Tx] together with the witness stacks of its inputs, one per input
+#[derive(Debug, Clone)]
+pub struct SegwitTx {
+    pub tx: Tx,
+    pub witnesses: Vec<Witness>,
+}
+
+impl SegwitTx {
+    /// Check whether any input carries witness data. If not, the transaction is serialised in the legacy format.
+    pub fn has_witness(&self) -> bool {
+        self.witnesses.iter().any(|witness| !witness.is_empty())
+    }
+
+    /// Serialise the transaction with the witness data, see [BIP141](https://github.com/bitcoin/bips/blob/master/bip-0141.mediawiki#transaction-id)
+    pub fn serialise(&self) -> Vec<u8> {
+        let mut ser: Vec<u8> = Vec::new();
+        if !self.has_witness() {
+            self.tx.write(&mut ser).unwrap();
+            return ser;
+        }
+
+        ser.extend_from_slice(&self.tx.version.to_le_bytes());
+        // Marker and flag
+        ser.extend_from_slice(&[0x00, 0x01]);
+        ser.extend(usize_to_var_int(self.tx.inputs.len()).unwrap());
+        for input in self.tx.inputs.iter() {
+            input.write(&mut ser).unwrap();
+        }
+        ser.extend(usize_to_var_int(self.tx.outputs.len()).unwrap());
+        for output in self.tx.outputs.iter() {
+            output.write(&mut ser).unwrap();
+        }
+        for witness in self.witnesses.iter() {
+            ser.extend(usize_to_var_int(witness.len()).unwrap());
+            for item in witness.iter() {
+                ser.extend(usize_to_var_int(item.len()).unwrap());
+                ser.extend_from_slice(item);
+            }
+        }
+        ser.extend_from_slice(&self.tx.lock_time.to_le_bytes());
+        ser
+    }
+
+    /// The wtxid of the transaction: the hash256 of [SegwitTx::serialise]
+    pub fn wtxid(&self) -> Hash256 {
+        sha256d(&self.serialise())
+    }
+}
+
+/// R1CS version of [Witness]
+#[derive(Debug, Clone)]
+pub struct WitnessVar<F: PrimeField>(pub Vec<Vec<UInt8<F>>>);
+
+impl<F: PrimeField> WitnessVar<F> {
+    /// Compute the field elements allocated by [WitnessVar::new_input] for `witness`
+    pub fn public_input(witness: &Witness) -> Vec<F> {
+        witness.iter().flat_map(|item| to_fp_bits(item)).collect()
+    }
+
+    /// Allocate vectors of [Witness]es
+    pub fn new_variable_vec<T: Borrow<Vec<Witness>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Vec<Self>, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let witnesses: Vec<Witness> = f().map(|witnesses| witnesses.borrow().clone())?;
+
+        let mut allocated_witnesses: Vec<WitnessVar<F>> = Vec::with_capacity(witnesses.len());
+        for witness in witnesses.iter() {
+            allocated_witnesses.push(WitnessVar::<F>::new_variable(
+                cs.clone(),
+                || Ok(witness),
+                mode,
+            )?);
+        }
+
+        Ok(allocated_witnesses)
+    }
+}
+
+impl<F: PrimeField> AllocVar<Witness, F> for WitnessVar<F> {
+    fn new_variable<T: Borrow<Witness>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let witness: Witness = f().map(|witness| witness.borrow().clone())?;
+
+        let mut items: Vec<Vec<UInt8<F>>> = Vec::with_capacity(witness.len());
+        for item in witness.iter() {
+            let mut allocated_item: Vec<UInt8<F>> = Vec::with_capacity(item.len());
+            for byte in item.iter() {
+                allocated_item.push(UInt8::<F>::new_variable(cs.clone(), || Ok(byte), mode)?);
+            }
+            items.push(allocated_item);
+        }
+
+        Ok(Self(items))
+    }
+}
+
+impl<F: PrimeField> EqGadget<F> for WitnessVar<F> {
+    /// Compare two witness stacks with the same structure.
+    ///
+    /// Returns [Error::ConfigMismatch] if the number of items or their lengths are different.
+    fn is_eq(&self, other: &Self) -> Result<Boolean<F>, SynthesisError> {
+        if self.0.len() != other.0.len() {
+            return Err(Error::ConfigMismatch(
+                "The witness stacks have a different number of items".to_string(),
+            )
+            .into());
+        }
+        let mut is_eq: Vec<Boolean<F>> = vec![Boolean::<F>::TRUE];
+        for (item, other_item) in self.0.iter().zip(other.0.iter()) {
+            if item.len() != other_item.len() {
+                return Err(Error::ConfigMismatch(
+                    "The items of the witness stacks have different lengths".to_string(),
+                )
+                .into());
+            }
+            for (byte, other_byte) in item.iter().zip(other_item.iter()) {
+                is_eq.push(byte.is_eq(other_byte)?);
+            }
+        }
+        Boolean::<F>::kary_and(&is_eq)
+    }
+}
+
+impl<F: PrimeField> CondSelectGadget<F> for WitnessVar<F> {
+    /// Select between two witness stacks with the same structure, elementwise
+    fn conditionally_select(
+        cond: &Boolean<F>,
+        true_value: &Self,
+        false_value: &Self,
+    ) -> Result<Self, SynthesisError> {
+        let mut items: Vec<Vec<UInt8<F>>> = Vec::with_capacity(true_value.0.len());
+        for (t, f) in true_value.0.iter().zip(false_value.0.iter()) {
+            let mut item: Vec<UInt8<F>> = Vec::with_capacity(t.len());
+            for (t_byte, f_byte) in t.iter().zip(f.iter()) {
+                item.push(UInt8::<F>::conditionally_select(cond, t_byte, f_byte)?);
+            }
+            items.push(item);
+        }
+        Ok(Self(items))
+    }
+}
+
+impl<F: PrimeField> ToBytesGadget<F> for WitnessVar<F> {
+    /// Serialise `Self` as in the witness section of a SegWit transaction: the number of items, followed by
+    /// each item prefixed by its length. The lengths are constants of the circuit.
+    fn to_bytes_le(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        let var_int = |length: usize| {
+            usize_to_var_int(length)
+                .unwrap()
+                .into_iter()
+                .map(UInt8::<F>::constant)
+                .collect::<Vec<UInt8<F>>>()
+        };
+        let mut ser: Vec<UInt8<F>> = var_int(self.0.len());
+        for item in self.0.iter() {
+            ser.extend(var_int(item.len()));
+            ser.extend_from_slice(item);
+        }
+        Ok(ser)
+    }
+}
+
+impl<F: PrimeField> R1CSVar<F> for WitnessVar<F> {
+    type Value = Witness;
+
+    fn cs(&self) -> ConstraintSystemRef<F> {
+        let mut result = ConstraintSystemRef::None;
+        for item in self.0.iter() {
+            result = item.cs().or(result);
+        }
+        result
+    }
+
+    fn value(&self) -> Result<Self::Value, SynthesisError> {
+        self.0.iter().map(|item| item.value()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr as F;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    fn witness() -> Witness {
+        vec![vec![0x30; 71], vec![0x02; 33], vec![]]
+    }
+
+    #[test]
+    fn test_serialisation() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let witness_var = WitnessVar::<F>::new_witness(cs.clone(), || Ok(witness())).unwrap();
+
+        let mut expected: Vec<u8> = vec![3, 71];
+        expected.extend_from_slice(&[0x30; 71]);
+        expected.push(33);
+        expected.extend_from_slice(&[0x02; 33]);
+        expected.push(0);
+        assert_eq!(
+            witness_var.to_bytes_le().unwrap().value().unwrap(),
+            expected
+        );
+        assert_eq!(witness_var.value().unwrap(), witness());
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_is_eq() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let witness_var = WitnessVar::<F>::new_witness(cs.clone(), || Ok(witness())).unwrap();
+        let mut other = witness();
+        other[1][0] = 0x03;
+        let other_var = WitnessVar::<F>::new_witness(cs.clone(), || Ok(other.clone())).unwrap();
+
+        assert!(witness_var.is_eq(&witness_var).unwrap().value().unwrap());
+        assert!(!witness_var.is_eq(&other_var).unwrap().value().unwrap());
+        let selected =
+            WitnessVar::<F>::conditionally_select(&Boolean::<F>::FALSE, &witness_var, &other_var)
+                .unwrap();
+        assert_eq!(selected.value().unwrap(), other);
+
+        let empty = WitnessVar::<F>::new_witness(cs.clone(), || Ok(Witness::new())).unwrap();
+        assert!(empty.is_eq(&empty).unwrap().value().unwrap());
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_is_eq_different_structure() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let witness_var = WitnessVar::<F>::new_witness(cs.clone(), || Ok(witness())).unwrap();
+        let mut other = witness();
+        other[1].push(0x00);
+        let other_var = WitnessVar::<F>::new_witness(cs.clone(), || Ok(other)).unwrap();
+        let empty = WitnessVar::<F>::new_witness(cs.clone(), || Ok(Witness::new())).unwrap();
+
+        assert!(matches!(
+            witness_var.is_eq(&other_var),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+        assert!(matches!(
+            witness_var.is_eq(&empty),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+}
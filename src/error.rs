@@ -0,0 +1,90 @@
+//! Errors returned by the library
+use std::fmt;
+
+use ark_relations::r1cs::SynthesisError;
+
+/// Outcome of a failed verification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationError {
+    /// The proof does not verify against the public input
+    ProofInvalid,
+    /// The public input is malformed, e.g., it has the wrong number of elements
+    PublicInputMalformed(String),
+    /// The data being verified is incompatible with the configuration of the circuit
+    ConfigMismatch(String),
+    /// The integrity tag does not match the transaction it is verified against
+    TagMismatch,
+    /// The serialised verifying key snapshot cannot be deserialised
+    SnapshotMalformed(String),
+    /// The verifying key is used outside of its validity window
+    KeyNotValid(String),
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerificationError::ProofInvalid => write!(f, "Invalid proof"),
+            VerificationError::PublicInputMalformed(reason) => {
+                write!(f, "Malformed public input: {}", reason)
+            }
+            VerificationError::ConfigMismatch(reason) => {
+                write!(f, "Configuration mismatch: {}", reason)
+            }
+            VerificationError::TagMismatch => write!(f, "Integrity tag mismatch"),
+            VerificationError::SnapshotMalformed(reason) => {
+                write!(f, "Malformed verifying key snapshot: {}", reason)
+            }
+            VerificationError::KeyNotValid(reason) => {
+                write!(f, "Verifying key not valid: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for VerificationError {}
+
+/// Errors returned by the library
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The data is incompatible with the configuration, e.g., a script has the wrong length
+    ConfigMismatch(String),
+    /// An index, e.g., of an input or an output, is out of the bounds of the transaction
+    IndexOutOfBounds(String),
+    /// The sighash of the transaction cannot be computed
+    SighashFailed(String),
+    /// A piece of data required to build a circuit is missing
+    MissingData(String),
+    /// A verification failed
+    Verification(VerificationError),
+    /// The operation is not supported, e.g., a predicate cannot be evaluated natively
+    NotImplemented(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::ConfigMismatch(reason) => write!(f, "Configuration mismatch: {}", reason),
+            Error::IndexOutOfBounds(reason) => write!(f, "Index out of bounds: {}", reason),
+            Error::SighashFailed(reason) => write!(f, "Sighash computation failed: {}", reason),
+            Error::MissingData(reason) => write!(f, "Missing data: {}", reason),
+            Error::Verification(error) => write!(f, "Verification failed: {}", error),
+            Error::NotImplemented(reason) => write!(f, "Not implemented: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<VerificationError> for Error {
+    fn from(error: VerificationError) -> Self {
+        Error::Verification(error)
+    }
+}
+
+/// Errors raised while generating the constraints are returned as [SynthesisError::Unsatisfiable], as
+/// [SynthesisError] cannot carry the reason of the failure
+impl From<Error> for SynthesisError {
+    fn from(_error: Error) -> Self {
+        SynthesisError::Unsatisfiable
+    }
+}
@@ -27,8 +27,14 @@
 pub mod bitcoin_predicates;
 /// R1CS version of Bitcoin structures
 pub mod constraints;
+/// Errors returned by the library
+pub mod error;
 /// RefTx circuit, enforcing conditions of the form `C'((spent_data, unlocking_data, integrity_tag), (witness, spending_data)) = 1`
 pub mod reftx;
+/// Schnorr signatures over twisted Edwards curves embedded in the field of the circuit, and the gadget to verify them
+pub mod schnorr;
+/// Serialisable snapshots of verifying keys bound to the schema of the public input of the circuit
+pub mod snapshot;
 /// Transaction integrity gadget, used to validate integrity of the `integrity_tag` against the spending data in REFTX
 pub mod transaction_integrity_gadget;
 
@@ -37,3 +43,5 @@ pub mod macros;
 
 pub mod traits;
 pub mod util;
+
+pub use error::Error;
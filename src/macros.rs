@@ -9,7 +9,7 @@
 //! use chain_gang::script::Script;
 //!
 //! and_combine_predicates!(
-//!     AndFixTwoOutputsLockingData, // The name of the LockingData struct for the new predicate
+//!     pub AndFixTwoOutputsLockingData, // The visibility of the generated structs (private if omitted), and the name of the LockingData struct for the new predicate
 //!     AndFixTwoOutputsUnlockingData, // The name of the UnlockingData struct for the new predicate
 //!     AndFixTwoOutputsWitness, // The name of the Witness struct for the new predicate
 //!     AndFixTwoOutputsLockingDataVar, // The name of the LockingDataVar struct for the new predicate
@@ -37,12 +37,20 @@
 //! ```
 
 /// Private macro used to combine different Bitcoin Predicates
-/// The macro assumes that the Bitcoin Predicates involved only depend on two generics:
-/// F: PrimeField, P: TxVarConfig + Clone.
+/// The combined predicate only depends on two generics: F: PrimeField, P: TxVarConfig + Clone.
+/// The Bitcoin Predicates involved can depend on further generics, e.g., `PercentageSplit<2, F, P>`,
+/// as long as these are fixed when the macro is invoked. Each generic must be a single token tree: use
+/// type aliases or braces for more complex generics.
+/// The results of the predicates are combined by `$combine`, a function from `&[Boolean<F>]` to
+/// `Result<Boolean<F>, SynthesisError>`, and by `$combine_native`, the native version from `&[bool]` to `bool`
+/// used in [BitcoinPredicate::evaluate_native](crate::traits::BitcoinPredicate::evaluate_native).
+#[doc(hidden)]
+#[macro_export]
 macro_rules! _combine_predicates {
     (
-        $logical_condition:expr,
-        $combined_locking_data:ident,
+        $combine:expr,
+        $combine_native:expr,
+        $vis:vis $combined_locking_data:ident,
         $combined_unlocking_data:ident,
         $combined_witness:ident,
         $combined_locking_data_var:ident,
@@ -55,61 +63,61 @@ macro_rules! _combine_predicates {
 
         paste::paste! {
             // Generate the SpentData struct
-            combine_bp_structs!(
-                LockingData,
+            $crate::combine_bp_structs!(
+                $vis LockingData,
                 $combined_locking_data,
                 $( ($type < $($gen),* >, $n) ),+
             );
 
             // Generate the UnlockingData struct
-            combine_bp_structs!(
-                UnlockingData,
+            $crate::combine_bp_structs!(
+                $vis UnlockingData,
                 $combined_unlocking_data,
                 $( ($type < $($gen),* >, $n) ),+
             );
 
             // Generate the Witness struct
-            combine_witness_structs!(
-                Witness,
+            $crate::combine_witness_structs!(
+                $vis Witness,
                 $combined_witness,
                 $( ($type < $($gen),* >, $n) ),+
             );
 
             // Generate the SpentDataVar struct
-            combine_bp_vars!(
-                LockingDataVar,
+            $crate::combine_bp_vars!(
+                $vis LockingDataVar,
                 $combined_locking_data,
                 $combined_locking_data_var,
                 $( ($type < $($gen),* >, $n) ),+
             );
 
             // Generate the UnlockingDataVar struct
-            combine_bp_vars!(
-                UnlockingDataVar,
+            $crate::combine_bp_vars!(
+                $vis UnlockingDataVar,
                 $combined_unlocking_data,
                 $combined_unlocking_data_var,
                 $( ($type < $($gen),* >, $n) ),+
             );
 
             // Generate the WitnessVar struct
-            combine_bp_vars!(
-                WitnessVar,
+            $crate::combine_bp_vars!(
+                $vis WitnessVar,
                 $combined_witness,
                 $combined_witness_var,
                 $( ($type < $($gen),* >, $n) ),+
             );
 
             // Generate the output struct
-            struct $output<F: PrimeField, P: TxVarConfig + Clone> {
+            $vis struct $output<F: PrimeField, P: TxVarConfig + Clone> {
                 $(
-                    pub [<$type:snake _$n>]: $type<F,P>,
+                    pub [<$type:snake _$n>]: $type<$($gen),*>,
                 )+
             }
 
             impl<F: PrimeField, P: TxVarConfig + Clone> $output<F, P> {
                 pub fn new(
                     $(
-                        [<$type:snake _$n>]: $type<F,P>,
+                        [<$type:snake _$n>]: $type<$($gen),*>,
                     )+
                 ) -> Self {
                     Self {
@@ -130,6 +138,22 @@ macro_rules! _combine_predicates {
                 type UnlockingDataVar = $combined_unlocking_data_var<F,P>;
                 type WitnessVar = $combined_witness_var<F,P>;
 
+                fn name(&self) -> &'static str {
+                    stringify!($output)
+                }
+
+                fn locking_data_len(&self) -> usize {
+                    0 $(
+                        + self.[<$type:snake _$n>].locking_data_len()
+                    )+
+                }
+
+                fn unlocking_data_len(&self) -> usize {
+                    0 $(
+                        + self.[<$type:snake _$n>].unlocking_data_len()
+                    )+
+                }
+
                 fn generate_constraints(
                     &self,
                     cs: ark_relations::r1cs::ConstraintSystemRef<F>,
@@ -138,31 +162,56 @@ macro_rules! _combine_predicates {
                     spending_data: &TxVar<F, P>,
                     witness: &Self::WitnessVar,
                 ) -> Result<ark_r1cs_std::prelude::Boolean<F>, ark_relations::r1cs::SynthesisError> {
-                    if $logical_condition {
-                        ark_r1cs_std::prelude::Boolean::<F>::kary_and(&[
-                            $(
-                                self.[<$type:snake _$n>].generate_constraints(
-                                    cs.clone(),
-                                    &locking_data.[<$type:snake _$n>],
-                                    &unlocking_data.[<$type:snake _$n>],
-                                    &spending_data,
-                                    &witness.[<$type:snake _$n>],
-                                )?,
-                            )+
-                        ])
-                    } else {
-                        ark_r1cs_std::prelude::Boolean::<F>::kary_or(&[
-                            $(
-                                self.[<$type:snake _$n>].generate_constraints(
-                                    cs.clone(),
-                                    &locking_data.[<$type:snake _$n>],
-                                    &unlocking_data.[<$type:snake _$n>],
-                                    &spending_data,
-                                    &witness.[<$type:snake _$n>],
-                                )?,
-                            )+
-                        ])
-                    }
+                    self.generate_constraints_with_context(
+                        cs,
+                        locking_data,
+                        unlocking_data,
+                        spending_data,
+                        witness,
+                        &$crate::traits::SpendingContext::default(),
+                    )
+                }
+
+                fn generate_constraints_with_context(
+                    &self,
+                    cs: ark_relations::r1cs::ConstraintSystemRef<F>,
+                    locking_data: &Self::LockingDataVar,
+                    unlocking_data: &Self::UnlockingDataVar,
+                    spending_data: &TxVar<F, P>,
+                    witness: &Self::WitnessVar,
+                    context: &$crate::traits::SpendingContext<F>,
+                ) -> Result<ark_r1cs_std::prelude::Boolean<F>, ark_relations::r1cs::SynthesisError> {
+                    ($combine)(&[
+                        $(
+                            self.[<$type:snake _$n>].generate_constraints_with_context(
+                                cs.clone(),
+                                &locking_data.[<$type:snake _$n>],
+                                &unlocking_data.[<$type:snake _$n>],
+                                &spending_data,
+                                &witness.[<$type:snake _$n>],
+                                context,
+                            )?,
+                        )+
+                    ])
+                }
+
+                fn evaluate_native(
+                    &self,
+                    locking_data: &Self::LockingData,
+                    unlocking_data: &Self::UnlockingData,
+                    tx: &chain_gang::messages::Tx,
+                    witness: &Self::Witness,
+                ) -> Result<bool, $crate::error::Error> {
+                    Ok(($combine_native)(&[
+                        $(
+                            self.[<$type:snake _$n>].evaluate_native(
+                                &locking_data.[<$type:snake _$n>],
+                                &unlocking_data.[<$type:snake _$n>],
+                                tx,
+                                &witness.[<$type:snake _$n>],
+                            )?,
+                        )+
+                    ]))
                 }
             }
         }
@@ -172,16 +221,16 @@ macro_rules! _combine_predicates {
 #[macro_export]
 macro_rules! combine_bp_structs {
     (
-        $bp_type: ident,
+        $vis:vis $bp_type: ident,
         $combined_struct: ident,
         $( ($type:ident < $($gen:tt),* >, $n:expr) ),+
         $(,)?
     ) => {
         paste::paste! {
             // Generate the combined struct
-            struct $combined_struct<F: PrimeField, P: TxVarConfig + Clone> {
+            $vis struct $combined_struct<F: PrimeField, P: TxVarConfig + Clone> {
                 $(
-                    pub [<$type:snake _$n>]: <$type<F,P> as BitcoinPredicate<F,P>>::$bp_type,
+                    pub [<$type:snake _$n>]: <$type<$($gen),*> as BitcoinPredicate<F,P>>::$bp_type,
                 )+
             }
 
@@ -198,7 +247,7 @@ macro_rules! combine_bp_structs {
             impl<F: PrimeField, P: TxVarConfig + Clone> $combined_struct<F, P> {
                 pub fn new(
                     $(
-                        [<$type:snake _$n>]: <$type<F,P> as BitcoinPredicate<F,P>>::$bp_type,
+                        [<$type:snake _$n>]: <$type<$($gen),*> as BitcoinPredicate<F,P>>::$bp_type,
                     )+
                 ) -> Self {
                     Self {
@@ -209,6 +258,17 @@ macro_rules! combine_bp_structs {
                 }
             }
 
+            impl<F: PrimeField, P: TxVarConfig + Clone> $crate::traits::PublicInputSize<F> for $combined_struct<F, P>
+            where
+                $(
+                    <$type<$($gen),*> as BitcoinPredicate<F,P>>::$bp_type: $crate::traits::PublicInputSize<F>,
+                )+
+            {
+                const N_PUBLIC_INPUTS: usize = 0 $(
+                    + <<$type<$($gen),*> as BitcoinPredicate<F,P>>::$bp_type as $crate::traits::PublicInputSize<F>>::N_PUBLIC_INPUTS
+                )+;
+            }
+
             impl<F: PrimeField, P: TxVarConfig + Clone> From<$combined_struct<F, P>> for Vec<F> {
                 fn from(data: $combined_struct<F, P>) -> Vec<F> {
                     let mut out = Vec::<F>::new();
@@ -225,16 +285,16 @@ macro_rules! combine_bp_structs {
 #[macro_export]
 macro_rules! combine_witness_structs {
     (
-        $bp_type: ident,
+        $vis:vis $bp_type: ident,
         $combined_struct: ident,
         $( ($type:ident < $($gen:tt),* >, $n:expr) ),+
         $(,)?
     ) => {
         paste::paste! {
             // Generate the combined struct
-            struct $combined_struct<F: PrimeField, P: TxVarConfig + Clone> {
+            $vis struct $combined_struct<F: PrimeField, P: TxVarConfig + Clone> {
                 $(
-                    pub [<$type:snake _$n>]: <$type<F,P> as BitcoinPredicate<F,P>>::$bp_type,
+                    pub [<$type:snake _$n>]: <$type<$($gen),*> as BitcoinPredicate<F,P>>::$bp_type,
                 )+
             }
 
@@ -251,7 +311,7 @@ macro_rules! combine_witness_structs {
             impl<F: PrimeField, P: TxVarConfig + Clone> $combined_struct<F, P> {
                 pub fn new(
                     $(
-                        [<$type:snake _$n>]: <$type<F,P> as BitcoinPredicate<F,P>>::$bp_type,
+                        [<$type:snake _$n>]: <$type<$($gen),*> as BitcoinPredicate<F,P>>::$bp_type,
                     )+
                 ) -> Self {
                     Self {
@@ -268,7 +328,7 @@ macro_rules! combine_witness_structs {
 #[macro_export]
 macro_rules! combine_bp_vars {
     (
-        $bp_type: ident,
+        $vis:vis $bp_type: ident,
         $combined_struct: ident,
         $combined_var: ident,
         $(($type:ident < $($gen:tt),* >, $n:expr)),+
@@ -276,9 +336,9 @@ macro_rules! combine_bp_vars {
     ) => {
         paste::paste! {
             // Generate the combined struct
-            struct $combined_var<F: PrimeField, P: TxVarConfig + Clone> {
+            $vis struct $combined_var<F: PrimeField, P: TxVarConfig + Clone> {
                 $(
-                    pub [<$type:snake _$n>]: <$type<F,P> as BitcoinPredicate<F,P>>::$bp_type,
+                    pub [<$type:snake _$n>]: <$type<$($gen),*> as BitcoinPredicate<F,P>>::$bp_type,
                 )+
             }
 
@@ -293,7 +353,7 @@ macro_rules! combine_bp_vars {
                         let struct_data: $combined_struct<F,P> = f().map(|data| data.borrow().clone())?;
                         Ok($combined_var {
                             $(
-                                [<$type:snake _$n>]: <$type<F,P> as BitcoinPredicate<F,P>>::$bp_type::new_variable(cs.clone(), || Ok(struct_data.[<$type:snake _$n>]), mode)?,
+                                [<$type:snake _$n>]: <$type<$($gen),*> as BitcoinPredicate<F,P>>::$bp_type::new_variable(cs.clone(), || Ok(struct_data.[<$type:snake _$n>]), mode)?,
                             )+
                         })
                     }
@@ -305,7 +365,7 @@ macro_rules! combine_bp_vars {
 #[macro_export]
 macro_rules! and_combine_predicates {
     (
-        $combined_locking_data:ident,
+        $vis:vis $combined_locking_data:ident,
         $combined_unlocking_data:ident,
         $combined_witness:ident,
         $combined_locking_data_var:ident,
@@ -315,9 +375,10 @@ macro_rules! and_combine_predicates {
         $(($type:ident < $($gen:tt),* >, $n:expr)),+
         $(,)?
     ) => {
-        _combine_predicates!(
-            true,
-            $combined_locking_data,
+        $crate::_combine_predicates!(
+            ark_r1cs_std::prelude::Boolean::<F>::kary_and,
+            |results: &[bool]| results.iter().all(|result| *result),
+            $vis $combined_locking_data,
             $combined_unlocking_data,
             $combined_witness,
             $combined_locking_data_var,
@@ -332,7 +393,7 @@ macro_rules! and_combine_predicates {
 #[macro_export]
 macro_rules! or_combine_predicates {
     (
-        $combined_spent_data:ident,
+        $vis:vis $combined_spent_data:ident,
         $combined_unlocking_data:ident,
         $combined_witness:ident,
         $combined_spent_data_var:ident,
@@ -342,9 +403,10 @@ macro_rules! or_combine_predicates {
         $(($type:ident < $($gen:tt),* >, $n:expr)),+
         $(,)?
     ) => {
-        _combine_predicates!(
-            false,
-            $combined_spent_data,
+        $crate::_combine_predicates!(
+            ark_r1cs_std::prelude::Boolean::<F>::kary_or,
+            |results: &[bool]| results.iter().any(|result| *result),
+            $vis $combined_spent_data,
             $combined_unlocking_data,
             $combined_witness,
             $combined_spent_data_var,
@@ -356,19 +418,198 @@ macro_rules! or_combine_predicates {
     }
 }
 
+/// Combine the predicates so that at least `$k` of them must hold, see [is_at_least](crate::util::is_at_least).
+/// The arguments following `$k` are the same as those of `and_combine_predicates!`.
+#[macro_export]
+macro_rules! threshold_combine_predicates {
+    (
+        $k:expr,
+        $vis:vis $combined_locking_data:ident,
+        $combined_unlocking_data:ident,
+        $combined_witness:ident,
+        $combined_locking_data_var:ident,
+        $combined_unlocking_data_var:ident,
+        $combined_witness_var:ident,
+        $output:ident,
+        $(($type:ident < $($gen:tt),* >, $n:expr)),+
+        $(,)?
+    ) => {
+        $crate::_combine_predicates!(
+            |booleans: &[ark_r1cs_std::prelude::Boolean<F>]| $crate::util::is_at_least(booleans, $k),
+            |results: &[bool]| results.iter().filter(|result| **result).count() >= $k,
+            $vis $combined_locking_data,
+            $combined_unlocking_data,
+            $combined_witness,
+            $combined_locking_data_var,
+            $combined_unlocking_data_var,
+            $combined_witness_var,
+            $output,
+            $( ($type < $($gen),* >, $n) ),+
+        );
+    }
+}
+
+/// Define a data structure for Bitcoin Predicates, and its R1CS version, nesting other data structures, e.g.,
+/// a [ByteArray](crate::bitcoin_predicates::data_structures::byte_array::ByteArray) and a
+/// [FieldArray](crate::bitcoin_predicates::data_structures::field_array::FieldArray).
+/// The conversion into `Vec<F>`, [PublicInputSize](crate::traits::PublicInputSize),
+/// [AllocVar](ark_r1cs_std::alloc::AllocVar) and [ToPublicInputGadget](crate::traits::ToPublicInputGadget)
+/// are composed from the ones of the fields, in the order of declaration. The generated structs depend on the
+/// generics `F: PrimeField` and `P: TxVarConfig + Clone`, and can themselves be nested.
+///
+/// ```
+/// use ark_bls12_381::Fr as F;
+/// use ark_ff::PrimeField;
+/// use bitcoin_r1cs::bitcoin_predicates::data_structures::{
+///     byte_array::{ByteArray, ByteArrayVar},
+///     field_array::{FieldArray, FieldArrayVar},
+/// };
+/// use bitcoin_r1cs::constraints::tx::TxVarConfig;
+/// use bitcoin_r1cs::predicate_data_struct;
+/// use bitcoin_r1cs::traits::PublicInputSize;
+///
+/// predicate_data_struct!(
+///     pub EscrowData, // The visibility of the generated structs (private if omitted), and the name of the data structure
+///     EscrowDataVar, // The name of its R1CS version
+///     (recipient, ByteArray<20, F, P>, ByteArrayVar<20, F, P>), // The name of the field, its type and its R1CS version
+///     (amounts, FieldArray<2, F, P>, FieldArrayVar<2, F, P>),
+/// );
+///
+/// #[derive(Clone)]
+/// struct Config;
+/// impl TxVarConfig for Config {
+///    const N_INPUTS: usize = 1;
+///    const N_OUTPUTS: usize = 0;
+///    const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+///    const LEN_LOCK_SCRIPTS: &[usize] = &[];
+/// }
+///
+/// let data = EscrowData::<F, Config>::new(ByteArray::new([1; 20]), FieldArray::new([F::from(1), F::from(2)]));
+/// assert_eq!(Into::<Vec<F>>::into(data).len(), EscrowData::<F, Config>::N_PUBLIC_INPUTS);
+/// ```
+#[macro_export]
+macro_rules! predicate_data_struct {
+    (
+        $vis:vis $data_struct: ident,
+        $data_var: ident,
+        $( ($field:ident, $ty:ty, $var_ty:ty) ),+
+        $(,)?
+    ) => {
+        $vis struct $data_struct<F: PrimeField, P: TxVarConfig + Clone> {
+            $(
+                pub $field: $ty,
+            )+
+        }
+
+        impl<F: PrimeField, P: TxVarConfig + Clone> Clone for $data_struct<F, P> {
+            fn clone(&self) -> Self {
+                Self {
+                    $(
+                        $field: self.$field.clone(),
+                    )+
+                }
+            }
+        }
+
+        impl<F: PrimeField, P: TxVarConfig + Clone> $data_struct<F, P> {
+            pub fn new($($field: $ty,)+) -> Self {
+                Self {
+                    $(
+                        $field,
+                    )+
+                }
+            }
+        }
+
+        impl<F: PrimeField, P: TxVarConfig + Clone> Default for $data_struct<F, P>
+        where
+            $(
+                $ty: Default,
+            )+
+        {
+            fn default() -> Self {
+                Self {
+                    $(
+                        $field: Default::default(),
+                    )+
+                }
+            }
+        }
+
+        impl<F: PrimeField, P: TxVarConfig + Clone> From<$data_struct<F, P>> for Vec<F> {
+            fn from(data: $data_struct<F, P>) -> Vec<F> {
+                let mut out = Vec::<F>::new();
+                $(
+                    out.extend_from_slice(&Into::<Vec<F>>::into(data.$field));
+                )+
+                out
+            }
+        }
+
+        impl<F: PrimeField, P: TxVarConfig + Clone> $crate::traits::PublicInputSize<F> for $data_struct<F, P> {
+            const N_PUBLIC_INPUTS: usize = 0 $(
+                + <$ty as $crate::traits::PublicInputSize<F>>::N_PUBLIC_INPUTS
+            )+;
+        }
+
+        $vis struct $data_var<F: PrimeField, P: TxVarConfig + Clone> {
+            $(
+                pub $field: $var_ty,
+            )+
+        }
+
+        impl<F: PrimeField, P: TxVarConfig + Clone> ark_r1cs_std::prelude::AllocVar<$data_struct<F, P>, F> for $data_var<F, P> {
+            fn new_variable<T: std::borrow::Borrow<$data_struct<F, P>>>(
+                cs: impl Into<ark_relations::r1cs::Namespace<F>>,
+                f: impl FnOnce() -> Result<T, ark_relations::r1cs::SynthesisError>,
+                mode: ark_r1cs_std::prelude::AllocationMode,
+            ) -> Result<Self, ark_relations::r1cs::SynthesisError> {
+                let ns = cs.into();
+                let cs = ns.cs();
+                let data = f()?;
+                let data: &$data_struct<F, P> = data.borrow();
+                Ok(Self {
+                    $(
+                        $field: <$var_ty as ark_r1cs_std::prelude::AllocVar<$ty, F>>::new_variable(cs.clone(), || Ok(&data.$field), mode)?,
+                    )+
+                })
+            }
+        }
+
+        impl<F: PrimeField, P: TxVarConfig + Clone> $crate::traits::ToPublicInputGadget<F> for $data_var<F, P> {
+            fn to_public_input(
+                &self,
+            ) -> Result<Vec<ark_r1cs_std::fields::fp::FpVar<F>>, ark_relations::r1cs::SynthesisError> {
+                let mut out = Vec::<ark_r1cs_std::fields::fp::FpVar<F>>::new();
+                $(
+                    out.extend_from_slice(&$crate::traits::ToPublicInputGadget::<F>::to_public_input(&self.$field)?);
+                )+
+                Ok(out)
+            }
+        }
+    };
+}
+
 #[cfg(test)]
 mod test {
     use ark_bls12_381::Fr as F;
     use ark_ff::PrimeField;
-    use ark_r1cs_std::alloc::AllocVar;
+    use ark_r1cs_std::{R1CSVar, alloc::AllocVar, uint64::UInt64};
     use ark_relations::r1cs::ConstraintSystem;
     use chain_gang::{
-        messages::{Tx, TxOut},
+        messages::{OutPoint, Tx, TxIn, TxOut},
         script::Script,
+        util::Hash256,
     };
 
+    use crate::bitcoin_predicates::data_structures::{
+        bool_array::{BoolArray, BoolArrayVar},
+        byte_array::{ByteArray, ByteArrayVar},
+        field_array::{FieldArray, FieldArrayVar},
+    };
     use crate::bitcoin_predicates::fixed_lock_script::FixedLockScript;
-    use crate::traits::BitcoinPredicate;
+    use crate::bitcoin_predicates::value::ValueConservation;
+    use crate::traits::{BitcoinPredicate, PublicInputSize, SpendingContext, ToPublicInputGadget};
     use crate::{
         bitcoin_predicates::data_structures::unit::BitcoinUnit,
         constraints::tx::{TxVar, TxVarConfig},
@@ -407,6 +648,32 @@ mod test {
         (FixedLockScript<F,P>, 2),
     );
 
+    and_combine_predicates!(
+        pub AndFixValueLockingData,
+        AndFixValueUnlockingData,
+        AndFixValueWitness,
+        AndFixValueLockingDataVar,
+        AndFixValueUnlockingDataVar,
+        AndFixValueWitnessVar,
+        AndFixValue,
+        (FixedLockScript<F,P>, 1),
+        (ValueConservation<F,P>, 1),
+    );
+
+    threshold_combine_predicates!(
+        2,
+        ThresholdFixTwoOutputsLockingData,
+        ThresholdFixTwoOutputsUnlockingData,
+        ThresholdFixTwoOutputsWitness,
+        ThresholdFixTwoOutputsLockingDataVar,
+        ThresholdFixTwoOutputsUnlockingDataVar,
+        ThresholdFixTwoOutputsWitnessVar,
+        ThresholdFixTwoOutputs,
+        (FixedLockScript<F,P>, 1),
+        (FixedLockScript<F,P>, 2),
+        (FixedLockScript<F,P>, 3),
+    );
+
     fn test_combine_predicates(
         is_and: bool,
         lock_script_one: Script,
@@ -475,6 +742,145 @@ mod test {
         assert_eq!(cs.is_satisfied().unwrap(), expected);
     }
 
+    fn test_threshold_combine_predicates(
+        lock_script_one: Script,
+        lock_script_two: Script,
+        expected: bool,
+    ) {
+        let tx = Tx {
+            version: 2,
+            inputs: vec![],
+            outputs: vec![
+                TxOut {
+                    satoshis: 100,
+                    lock_script: lock_script_one,
+                },
+                TxOut {
+                    satoshis: 259899900,
+                    lock_script: lock_script_two,
+                },
+            ],
+            lock_time: 0,
+        };
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx.clone())).unwrap();
+
+        // At least two of: output 0 has script [0], output 1 has script [1], output 1 has script [2]
+        let fix_combined = ThresholdFixTwoOutputs::<F, Config>::new(
+            FixedLockScript::<F, Config>::new(Script(vec![0]), 0),
+            FixedLockScript::<F, Config>::new(Script(vec![1]), 1),
+            FixedLockScript::<F, Config>::new(Script(vec![2]), 1),
+        );
+        let dummy = BitcoinUnit::<F, Config>::default();
+        let dummy_spent =
+            ThresholdFixTwoOutputsLockingData::new(dummy.clone(), dummy.clone(), dummy.clone());
+        let dummy_unlock =
+            ThresholdFixTwoOutputsUnlockingData::new(dummy.clone(), dummy.clone(), dummy.clone());
+        let dummy_wit =
+            ThresholdFixTwoOutputsWitness::new(dummy.clone(), dummy.clone(), dummy.clone());
+        let spent_var =
+            ThresholdFixTwoOutputsLockingDataVar::new_input(cs.clone(), || Ok(dummy_spent.clone()))
+                .unwrap();
+        let unlock_var = ThresholdFixTwoOutputsUnlockingDataVar::new_input(cs.clone(), || {
+            Ok(dummy_unlock.clone())
+        })
+        .unwrap();
+        let wit_var =
+            ThresholdFixTwoOutputsWitnessVar::new_input(cs.clone(), || Ok(dummy_wit.clone()))
+                .unwrap();
+
+        fix_combined
+            .enforce_constraints(cs.clone(), &spent_var, &unlock_var, &tx_var, &wit_var)
+            .unwrap();
+
+        assert_eq!(cs.is_satisfied().unwrap(), expected);
+        assert_eq!(
+            fix_combined
+                .evaluate_native(&dummy_spent, &dummy_unlock, &tx, &dummy_wit)
+                .unwrap(),
+            expected
+        );
+    }
+
+    #[derive(Clone)]
+    struct OneInputConfig;
+    impl TxVarConfig for OneInputConfig {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[1];
+    }
+
+    fn test_combine_generic_predicates(lock_script: Script, amount: u64, expected: bool) {
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 900,
+                lock_script,
+            }],
+            lock_time: 0,
+        };
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, OneInputConfig>::new_input(cs.clone(), || Ok(tx.clone())).unwrap();
+
+        let fix_combined = AndFixValue::<F, OneInputConfig>::new(
+            FixedLockScript::<F, OneInputConfig>::new(Script(vec![0]), 0),
+            ValueConservation::<F, OneInputConfig>::new(100).unwrap(),
+        );
+        let dummy = BitcoinUnit::<F, OneInputConfig>::default();
+        let spent = AndFixValueLockingData::new(
+            dummy.clone(),
+            FieldArray::<1, F, OneInputConfig>::new([F::from(amount)]),
+        );
+        let unlock = AndFixValueUnlockingData::new(dummy.clone(), dummy.clone());
+        let wit = AndFixValueWitness::new(dummy.clone(), dummy.clone());
+        let spent_var =
+            AndFixValueLockingDataVar::new_input(cs.clone(), || Ok(spent.clone())).unwrap();
+        let unlock_var =
+            AndFixValueUnlockingDataVar::new_input(cs.clone(), || Ok(unlock.clone())).unwrap();
+        let wit_var = AndFixValueWitnessVar::new_witness(cs.clone(), || Ok(wit.clone())).unwrap();
+        // The context is passed on to the combined predicates
+        let prev_amount = UInt64::<F>::new_witness(cs.clone(), || Ok(amount)).unwrap();
+
+        fix_combined
+            .enforce_constraints_with_context(
+                cs.clone(),
+                &spent_var,
+                &unlock_var,
+                &tx_var,
+                &wit_var,
+                &SpendingContext {
+                    prev_amount: Some(&prev_amount),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(fix_combined.name(), "AndFixValue");
+        assert_eq!(fix_combined.locking_data_len(), 1);
+        assert_eq!(fix_combined.unlocking_data_len(), 0);
+        assert_eq!(cs.is_satisfied().unwrap(), expected);
+        assert_eq!(
+            fix_combined
+                .evaluate_native(&spent, &unlock, &tx, &wit)
+                .unwrap(),
+            expected
+        );
+    }
+
     #[test]
     fn test_and_is_ok() {
         test_combine_predicates(true, Script(vec![0]), Script(vec![1]), true);
@@ -496,4 +902,129 @@ mod test {
     fn test_or_fails() {
         test_combine_predicates(false, Script(vec![1]), Script(vec![2]), false);
     }
+
+    #[test]
+    fn test_and_generic_predicates() {
+        test_combine_generic_predicates(Script(vec![0]), 1000, true);
+        // Fee too high
+        test_combine_generic_predicates(Script(vec![0]), 1001, false);
+        // Wrong script
+        test_combine_generic_predicates(Script(vec![1]), 1000, false);
+    }
+
+    #[test]
+    fn test_threshold_is_ok() {
+        test_threshold_combine_predicates(Script(vec![0]), Script(vec![1]), true);
+        test_threshold_combine_predicates(Script(vec![0]), Script(vec![2]), true);
+    }
+
+    #[test]
+    fn test_threshold_fails() {
+        test_threshold_combine_predicates(Script(vec![1]), Script(vec![1]), false);
+        test_threshold_combine_predicates(Script(vec![0]), Script(vec![0]), false);
+    }
+
+    predicate_data_struct!(
+        InnerData,
+        InnerDataVar,
+        (recipient, ByteArray<2, F, P>, ByteArrayVar<2, F, P>),
+        (amount, FieldArray<1, F, P>, FieldArrayVar<1, F, P>),
+    );
+
+    predicate_data_struct!(
+        OuterData,
+        OuterDataVar,
+        (inner, InnerData<F, P>, InnerDataVar<F, P>),
+        (flags, BoolArray<2, F, P>, BoolArrayVar<2, F, P>),
+    );
+
+    #[test]
+    fn test_nested_data_struct() {
+        let data = OuterData::<F, Config>::new(
+            InnerData::new(ByteArray::new([1, 2]), FieldArray::new([F::from(3)])),
+            BoolArray::new([true, false]),
+        );
+        let public_input: Vec<F> = data.clone().into();
+        assert_eq!(public_input.len(), OuterData::<F, Config>::N_PUBLIC_INPUTS);
+        assert_eq!(public_input.len(), 8 * 2 + 1 + 2);
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let data_var = OuterDataVar::<F, Config>::new_input(cs.clone(), || Ok(data)).unwrap();
+        assert_eq!(cs.borrow().unwrap().instance_assignment[1..], public_input);
+        assert_eq!(
+            data_var.to_public_input().unwrap().value().unwrap(),
+            public_input
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
+
+/// Check the invariants of a [TxVarConfig](crate::constraints::tx::TxVarConfig) at compile time,
+/// see [check_tx_var_config](crate::constraints::tx::check_tx_var_config)
+///
+/// ```
+/// use bitcoin_r1cs::assert_tx_var_config;
+/// use bitcoin_r1cs::constraints::tx::TxVarConfig;
+///
+/// struct Config;
+/// impl TxVarConfig for Config {
+///    const N_INPUTS: usize = 1;
+///    const N_OUTPUTS: usize = 2;
+///    const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+///    const LEN_LOCK_SCRIPTS: &[usize] = &[0x19, 0x19];
+/// }
+///
+/// assert_tx_var_config!(Config);
+/// ```
+///
+/// ```compile_fail
+/// use bitcoin_r1cs::assert_tx_var_config;
+/// use bitcoin_r1cs::constraints::tx::TxVarConfig;
+///
+/// struct Config;
+/// impl TxVarConfig for Config {
+///    const N_INPUTS: usize = 2;
+///    const N_OUTPUTS: usize = 2;
+///    const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+///    const LEN_LOCK_SCRIPTS: &[usize] = &[0x19, 0x19];
+/// }
+///
+/// assert_tx_var_config!(Config);
+/// ```
+#[macro_export]
+macro_rules! assert_tx_var_config {
+    ($config:ty) => {
+        const _: () = $crate::constraints::tx::check_tx_var_config::<$config>();
+    };
+}
+
+/// Check the invariants of a configuration implementing both [TxVarConfig](crate::constraints::tx::TxVarConfig)
+/// and [TransactionIntegrityConfig](crate::transaction_integrity_gadget::TransactionIntegrityConfig) at compile time,
+/// see [check_integrity_config](crate::transaction_integrity_gadget::check_integrity_config)
+///
+/// ```compile_fail
+/// use bitcoin_r1cs::assert_integrity_config;
+/// use bitcoin_r1cs::constraints::tx::TxVarConfig;
+/// use bitcoin_r1cs::transaction_integrity_gadget::TransactionIntegrityConfig;
+///
+/// struct Config;
+/// impl TxVarConfig for Config {
+///    const N_INPUTS: usize = 1;
+///    const N_OUTPUTS: usize = 0;
+///    const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+///    const LEN_LOCK_SCRIPTS: &[usize] = &[];
+/// }
+/// impl TransactionIntegrityConfig for Config {
+///    const N_INPUT: usize = 1;
+///    const LEN_PREV_LOCK_SCRIPT: usize = 0;
+///    const SIGHASH_FLAG: u8 = 0x41;
+/// }
+///
+/// assert_integrity_config!(Config);
+/// ```
+#[macro_export]
+macro_rules! assert_integrity_config {
+    ($config:ty) => {
+        const _: () = $crate::transaction_integrity_gadget::check_integrity_config::<$config>();
+    };
 }
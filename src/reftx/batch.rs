@@ -0,0 +1,218 @@
+//! RefTx circuit verifying several independent spends in a single proof
+//!
+//! The constraints of each [RefTxCircuit] are generated in the same constraint system, so that constants are
+//! shared between the instances, and the public input is the concatenation of the public inputs of the
+//! instances. The number of instances is part of the structure of the circuit: the parameters generated for a
+//! batch of `K` circuits only verify batches of `K` circuits.
+use ark_ff::PrimeField;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::{
+    constraints::tx::TxVarConfig,
+    error::Error,
+    reftx::RefTxCircuit,
+    traits::{BitcoinPredicate, IntegrityScheme},
+};
+
+/// A batch of [RefTxCircuit] proven together
+pub struct BatchRefTxCircuit<
+    B: BitcoinPredicate<F, P>,
+    F: PrimeField + Clone,
+    P: TxVarConfig + Clone,
+    S: IntegrityScheme<F, P>,
+> {
+    pub circuits: Vec<RefTxCircuit<B, F, P, S>>,
+}
+
+impl<B, F, P, S> BatchRefTxCircuit<B, F, P, S>
+where
+    B: BitcoinPredicate<F, P>,
+    F: PrimeField + Clone,
+    P: TxVarConfig + Clone,
+    S: IntegrityScheme<F, P>,
+{
+    /// Returns [Error::MissingData] if `circuits` is empty
+    pub fn new(circuits: Vec<RefTxCircuit<B, F, P, S>>) -> Result<Self, Error> {
+        if circuits.is_empty() {
+            return Err(Error::MissingData("The batch is empty".to_string()));
+        }
+        Ok(Self { circuits })
+    }
+
+    /// The number of instances in the batch
+    pub fn n_instances(&self) -> usize {
+        self.circuits.len()
+    }
+
+    /// The public input of the batch: the concatenation of the public inputs of the instances, in order
+    pub fn public_input(&self) -> Vec<F> {
+        self.circuits
+            .iter()
+            .flat_map(|circuit| circuit.public_input())
+            .collect()
+    }
+}
+
+impl<B, F, P, S> BatchRefTxCircuit<B, F, P, S>
+where
+    B: BitcoinPredicate<F, P>,
+    F: PrimeField + Clone,
+    P: TxVarConfig + Clone,
+    S: IntegrityScheme<F, P>,
+{
+    /// The offset of the public input of instance `i` in [BatchRefTxCircuit::public_input]
+    pub fn instance_offset(i: usize) -> usize {
+        i * RefTxCircuit::<B, F, P, S>::N_PUBLIC_INPUTS
+    }
+}
+
+impl<B, F, P, S> ConstraintSynthesizer<F> for BatchRefTxCircuit<B, F, P, S>
+where
+    B: BitcoinPredicate<F, P>,
+    F: PrimeField + Clone,
+    P: TxVarConfig + Clone,
+    S: IntegrityScheme<F, P>,
+{
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        for circuit in self.circuits {
+            circuit.generate_constraints(cs.clone())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::Fr as F;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use chain_gang::address::addr_decode;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::network::Network;
+    use chain_gang::script::Script;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::transaction::sighash::{SIGHASH_ALL, SIGHASH_FORKID};
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::unit::BitcoinUnit;
+    use crate::bitcoin_predicates::fixed_lock_script::FixedLockScript;
+    use crate::constraints::tx::TxVarConfig;
+    use crate::error::Error;
+    use crate::reftx::RefTxCircuit;
+    use crate::traits::IntegrityScheme;
+    use crate::transaction_integrity_gadget::{
+        TransactionIntegrityConfig,
+        scheme::{PoseidonIntegrity, PrevOutput},
+    };
+
+    use super::BatchRefTxCircuit;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    impl TransactionIntegrityConfig for Config {
+        const N_INPUT: usize = 0;
+        const LEN_PREV_LOCK_SCRIPT: usize = 0x00;
+        const SIGHASH_FLAG: u8 = SIGHASH_ALL | SIGHASH_FORKID;
+    }
+
+    type Predicate = FixedLockScript<F, Config>;
+    type Circuit = RefTxCircuit<Predicate, F, Config, PoseidonIntegrity<F, Config>>;
+
+    fn p2pkh_script(addr: &str) -> Script {
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        p2pkh::create_lock_script(&hash160)
+    }
+
+    fn tx(index: u32, addr: &str) -> Tx {
+        Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 900,
+                lock_script: p2pkh_script(addr),
+            }],
+            lock_time: 0,
+        }
+    }
+
+    fn circuit(tx: Tx) -> Circuit {
+        let integrity_scheme = PoseidonIntegrity::<F, Config>::default();
+        let mut integrity_witness = PrevOutput {
+            prev_lock_script: Script(vec![]),
+            prev_amount: 1000,
+        };
+        let integrity_tag = integrity_scheme
+            .commit(&tx, &mut integrity_witness)
+            .unwrap();
+        Circuit {
+            locking_data: BitcoinUnit::default(),
+            integrity_tag: Some(integrity_tag),
+            unlocking_data: BitcoinUnit::default(),
+            witness: BitcoinUnit::default(),
+            spending_data: Some(tx),
+            integrity_witness: Some(integrity_witness),
+            predicate: Predicate::new(p2pkh_script("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec"), 0),
+            integrity_scheme,
+            setup_mode: false,
+        }
+    }
+
+    fn test_batch(txs: Vec<Tx>) -> bool {
+        let batch = BatchRefTxCircuit::new(txs.into_iter().map(circuit).collect()).unwrap();
+        let n_instances = batch.n_instances();
+        let public_input = batch.public_input();
+        let tag_offset = batch.circuits[1].tag_offset();
+        let second_tag: Vec<F> = batch.circuits[1].integrity_tag.clone().unwrap().into();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        batch.generate_constraints(cs.clone()).unwrap();
+
+        assert_eq!(public_input.len(), n_instances * Circuit::N_PUBLIC_INPUTS);
+        assert_eq!(public_input.len(), cs.num_instance_variables() - 1);
+        let offset =
+            BatchRefTxCircuit::<Predicate, F, Config, PoseidonIntegrity<F, Config>>::instance_offset(
+                1,
+            ) + tag_offset;
+        assert_eq!(public_input[offset..offset + second_tag.len()], second_tag);
+
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_batch_is_ok() {
+        let addr = "mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec";
+        assert!(test_batch(vec![tx(0, addr), tx(1, addr)]));
+    }
+
+    #[test]
+    fn test_batch_fails() {
+        let addr = "mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec";
+        let wrong_addr = "mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz";
+        assert!(!test_batch(vec![tx(0, addr), tx(1, wrong_addr)]));
+    }
+
+    #[test]
+    fn test_empty_batch() {
+        assert!(matches!(
+            BatchRefTxCircuit::<Predicate, F, Config, PoseidonIntegrity<F, Config>>::new(vec![]),
+            Err(Error::MissingData(_))
+        ));
+    }
+}
@@ -0,0 +1,354 @@
+//! Builder of [RefTxCircuit] validating its data before constraint generation
+//!
+//! Building a [RefTxCircuit] field by field gives no feedback until the constraint system is checked: a
+//! transaction with the wrong shape or an inconsistent integrity tag only shows up as an unsatisfied circuit.
+//! [RefTxCircuitBuilder::build] instead checks the spending transaction against the [TxVarConfig] and the
+//! integrity witness and tag against the [IntegrityScheme], and returns a descriptive [Error].
+use ark_ff::PrimeField;
+use chain_gang::messages::Tx;
+
+use crate::{
+    constraints::tx::{TxVarConfig, check_tx},
+    error::Error,
+    reftx::RefTxCircuit,
+    traits::{BitcoinPredicate, IntegrityScheme},
+};
+
+/// Builder of a [RefTxCircuit] for the prover
+pub struct RefTxCircuitBuilder<
+    B: BitcoinPredicate<F, P>,
+    F: PrimeField + Clone,
+    P: TxVarConfig + Clone,
+    S: IntegrityScheme<F, P>,
+> {
+    locking_data: Option<B::LockingData>,
+    unlocking_data: Option<B::UnlockingData>,
+    witness: Option<B::Witness>,
+    spending_data: Option<Tx>,
+    integrity_witness: Option<S::Witness>,
+    integrity_tag: Option<S::Tag>,
+    predicate: B,
+    integrity_scheme: S,
+}
+
+impl<B, F, P, S> RefTxCircuitBuilder<B, F, P, S>
+where
+    B: BitcoinPredicate<F, P>,
+    F: PrimeField + Clone,
+    P: TxVarConfig + Clone,
+    S: IntegrityScheme<F, P>,
+{
+    pub fn new(predicate: B, integrity_scheme: S) -> Self {
+        Self {
+            locking_data: None,
+            unlocking_data: None,
+            witness: None,
+            spending_data: None,
+            integrity_witness: None,
+            integrity_tag: None,
+            predicate,
+            integrity_scheme,
+        }
+    }
+
+    pub fn locking_data(mut self, locking_data: B::LockingData) -> Self {
+        self.locking_data = Some(locking_data);
+        self
+    }
+
+    pub fn unlocking_data(mut self, unlocking_data: B::UnlockingData) -> Self {
+        self.unlocking_data = Some(unlocking_data);
+        self
+    }
+
+    pub fn witness(mut self, witness: B::Witness) -> Self {
+        self.witness = Some(witness);
+        self
+    }
+
+    /// Set the spending transaction
+    pub fn spending_data(mut self, tx: Tx) -> Self {
+        self.spending_data = Some(tx);
+        self
+    }
+
+    pub fn integrity_witness(mut self, integrity_witness: S::Witness) -> Self {
+        self.integrity_witness = Some(integrity_witness);
+        self
+    }
+
+    /// Set the integrity tag. If missing, [RefTxCircuitBuilder::build] computes it from the spending transaction
+    /// and the integrity witness.
+    pub fn integrity_tag(mut self, integrity_tag: S::Tag) -> Self {
+        self.integrity_tag = Some(integrity_tag);
+        self
+    }
+
+    /// Validate the data and build the circuit
+    ///
+    /// Returns [Error::MissingData] if a piece of data other than the integrity tag is missing,
+    /// [Error::ConfigMismatch] if the spending transaction or the integrity witness are incompatible with the
+    /// parameters, and [Error::Verification] if the integrity tag does not match them.
+    pub fn build(self) -> Result<RefTxCircuit<B, F, P, S>, Error> {
+        let locking_data = self
+            .locking_data
+            .ok_or(Error::MissingData("locking_data".to_string()))?;
+        let unlocking_data = self
+            .unlocking_data
+            .ok_or(Error::MissingData("unlocking_data".to_string()))?;
+        let witness = self
+            .witness
+            .ok_or(Error::MissingData("witness".to_string()))?;
+        let tx = self
+            .spending_data
+            .ok_or(Error::MissingData("spending_data".to_string()))?;
+        let mut integrity_witness = self
+            .integrity_witness
+            .ok_or(Error::MissingData("integrity_witness".to_string()))?;
+
+        check_tx::<P>(&tx)?;
+
+        let integrity_tag = match self.integrity_tag {
+            Some(tag) => {
+                self.integrity_scheme
+                    .verify(&tx, &mut integrity_witness, &tag)?;
+                tag
+            }
+            None => self.integrity_scheme.commit(&tx, &mut integrity_witness)?,
+        };
+
+        Ok(RefTxCircuit {
+            locking_data,
+            integrity_tag: Some(integrity_tag),
+            unlocking_data,
+            witness,
+            spending_data: Some(tx),
+            integrity_witness: Some(integrity_witness),
+            predicate: self.predicate,
+            integrity_scheme: self.integrity_scheme,
+            setup_mode: false,
+        })
+    }
+
+    /// Build a circuit to generate the keys with. The spending transaction, the integrity witness and the
+    /// integrity tag are optional and are not validated, as only the structure of the circuit matters.
+    ///
+    /// Returns [Error::MissingData] if the locking data, the unlocking data or the witness are missing.
+    pub fn build_for_setup(self) -> Result<RefTxCircuit<B, F, P, S>, Error> {
+        let locking_data = self
+            .locking_data
+            .ok_or(Error::MissingData("locking_data".to_string()))?;
+        let unlocking_data = self
+            .unlocking_data
+            .ok_or(Error::MissingData("unlocking_data".to_string()))?;
+        let witness = self
+            .witness
+            .ok_or(Error::MissingData("witness".to_string()))?;
+
+        Ok(RefTxCircuit {
+            locking_data,
+            integrity_tag: self.integrity_tag,
+            unlocking_data,
+            witness,
+            spending_data: self.spending_data,
+            integrity_witness: self.integrity_witness,
+            predicate: self.predicate,
+            integrity_scheme: self.integrity_scheme,
+            setup_mode: true,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::Fr as F;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, SynthesisError};
+    use chain_gang::address::addr_decode;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::network::Network;
+    use chain_gang::script::Script;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::transaction::sighash::{SIGHASH_ALL, SIGHASH_FORKID, SigHashCache};
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::unit::BitcoinUnit;
+    use crate::bitcoin_predicates::fixed_lock_script::FixedLockScript;
+    use crate::constraints::tx::TxVarConfig;
+    use crate::error::{Error, VerificationError};
+    use crate::transaction_integrity_gadget::{
+        TransactionIntegrityConfig, TransactionIntegrityTag,
+        scheme::{PrevOutput, SighashIntegrity, SighashWitness},
+    };
+
+    use super::RefTxCircuitBuilder;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    impl TransactionIntegrityConfig for Config {
+        const N_INPUT: usize = 0;
+        const LEN_PREV_LOCK_SCRIPT: usize = 0x00;
+        const SIGHASH_FLAG: u8 = SIGHASH_ALL | SIGHASH_FORKID;
+    }
+
+    type Predicate = FixedLockScript<F, Config>;
+    type Builder = RefTxCircuitBuilder<Predicate, F, Config, SighashIntegrity<Config>>;
+
+    fn p2pkh_script() -> Script {
+        let hash160 = addr_decode("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec", Network::BSV_Testnet)
+            .unwrap()
+            .0;
+        p2pkh::create_lock_script(&hash160)
+    }
+
+    fn tx() -> Tx {
+        Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 900,
+                lock_script: p2pkh_script(),
+            }],
+            lock_time: 0,
+        }
+    }
+
+    fn integrity_witness(prev_lock_script: Script) -> SighashWitness {
+        SighashWitness {
+            prev_output: PrevOutput {
+                prev_lock_script,
+                prev_amount: 1000,
+            },
+            sighash_cache: SigHashCache::new(),
+        }
+    }
+
+    fn builder(tx: Tx) -> Builder {
+        Builder::new(Predicate::new(p2pkh_script(), 0), SighashIntegrity::new())
+            .locking_data(BitcoinUnit::default())
+            .unlocking_data(BitcoinUnit::default())
+            .witness(BitcoinUnit::default())
+            .spending_data(tx)
+    }
+
+    #[test]
+    fn test_build_is_ok() {
+        let circuit = builder(tx())
+            .integrity_witness(integrity_witness(Script(vec![])))
+            .build()
+            .unwrap();
+        let tag = circuit.integrity_tag.clone().unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+
+        // A matching tag is accepted
+        assert!(
+            builder(tx())
+                .integrity_witness(integrity_witness(Script(vec![])))
+                .integrity_tag(tag)
+                .build()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_build_for_setup() {
+        let setup_builder = || {
+            Builder::new(Predicate::new(p2pkh_script(), 0), SighashIntegrity::new())
+                .locking_data(BitcoinUnit::default())
+                .unlocking_data(BitcoinUnit::default())
+                .witness(BitcoinUnit::default())
+        };
+
+        // The spending transaction may be missing in setup mode only
+        let cs = ConstraintSystem::<F>::new_ref();
+        assert!(
+            setup_builder()
+                .build_for_setup()
+                .unwrap()
+                .generate_constraints(cs)
+                .is_ok()
+        );
+
+        let mut circuit = builder(tx())
+            .integrity_witness(integrity_witness(Script(vec![])))
+            .build()
+            .unwrap();
+        circuit.spending_data = None;
+        let cs = ConstraintSystem::<F>::new_ref();
+        assert!(matches!(
+            circuit.generate_constraints(cs),
+            Err(SynthesisError::AssignmentMissing)
+        ));
+
+        assert!(matches!(
+            Builder::new(Predicate::new(p2pkh_script(), 0), SighashIntegrity::new()).build_for_setup(),
+            Err(Error::MissingData(data)) if data == "locking_data"
+        ));
+    }
+
+    #[test]
+    fn test_build_missing_data() {
+        assert!(matches!(
+            builder(tx()).build(),
+            Err(Error::MissingData(data)) if data == "integrity_witness"
+        ));
+    }
+
+    #[test]
+    fn test_build_config_mismatch() {
+        let mut wrong_tx = tx();
+        wrong_tx.outputs.push(wrong_tx.outputs[0].clone());
+        assert!(matches!(
+            builder(wrong_tx)
+                .integrity_witness(integrity_witness(Script(vec![])))
+                .build(),
+            Err(Error::ConfigMismatch(_))
+        ));
+
+        let mut wrong_tx = tx();
+        wrong_tx.inputs[0].unlock_script = Script(vec![0x00]);
+        assert!(matches!(
+            builder(wrong_tx)
+                .integrity_witness(integrity_witness(Script(vec![])))
+                .build(),
+            Err(Error::ConfigMismatch(_))
+        ));
+
+        assert!(matches!(
+            builder(tx())
+                .integrity_witness(integrity_witness(p2pkh_script()))
+                .build(),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_build_wrong_tag() {
+        assert!(matches!(
+            builder(tx())
+                .integrity_witness(integrity_witness(Script(vec![])))
+                .integrity_tag(TransactionIntegrityTag::default())
+                .build(),
+            Err(Error::Verification(VerificationError::TagMismatch))
+        ));
+    }
+}
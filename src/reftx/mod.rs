@@ -0,0 +1,1167 @@
+/// RefTx circuit verifying several independent spends in a single proof
+pub mod batch;
+/// Builder of [RefTxCircuit] validating its data
+pub mod builder;
+/// Groth16 setup, proving and verification specialised to [RefTxCircuit]
+#[cfg(feature = "snark")]
+pub mod snark;
+
+use std::marker::PhantomData;
+
+#[cfg(feature = "snark")]
+use ark_ec::pairing::Pairing;
+use ark_ff::PrimeField;
+#[cfg(feature = "snark")]
+use ark_groth16::{Groth16, PreparedVerifyingKey, Proof, ProvingKey};
+use ark_r1cs_std::alloc::AllocVar;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use chain_gang::{messages::Tx, script::Script, transaction::sighash::SigHashCache};
+
+#[cfg(feature = "snark")]
+use crate::error::VerificationError;
+use crate::{
+    constraints::tx::{TxVar, TxVarConfig},
+    error::Error,
+    snapshot::{PublicInputField, PublicInputSchema},
+    traits::{BitcoinPredicate, IntegrityScheme, PublicInputSize, SpendingContext},
+    transaction_integrity_gadget::{
+        TransactionIntegrityTag,
+        multi_input::{MultiInputIntegrityConfig, MultiInputIntegrityScheme},
+        scheme::{MultiInputSighashIntegrity, PrevOutput, SighashIntegrity, SighashWitness},
+    },
+    util::default_tx,
+};
+#[cfg(feature = "snark")]
+use rand::Rng;
+
+/// The RefTx circuit, generic over the [IntegrityScheme] binding `integrity_tag` to the spending transaction.
+/// The default scheme is [SighashIntegrity], whose tag is the sighash of the spending transaction.
+pub struct RefTxCircuit<
+    B: BitcoinPredicate<F, P>,
+    F: PrimeField + Clone,
+    P: TxVarConfig + Clone,
+    S: IntegrityScheme<F, P> = SighashIntegrity<P>,
+> {
+    /// Public inputs
+    pub locking_data: B::LockingData,
+    pub integrity_tag: Option<S::Tag>,
+    pub unlocking_data: B::UnlockingData,
+    /// Witness values
+    pub witness: B::Witness,
+    pub spending_data: Option<Tx>,
+    pub integrity_witness: Option<S::Witness>,
+    /// Predicate
+    pub predicate: B,
+    /// Integrity scheme
+    pub integrity_scheme: S,
+    /// Whether the circuit is only used to generate the keys, in which case the spending transaction may be
+    /// missing, see [builder::RefTxCircuitBuilder::build_for_setup]
+    pub(crate) setup_mode: bool,
+}
+
+impl<B, F, P, S> RefTxCircuit<B, F, P, S>
+where
+    B: BitcoinPredicate<F, P>,
+    F: PrimeField + Clone,
+    P: TxVarConfig + Clone,
+    S: IntegrityScheme<F, P>,
+{
+    pub fn public_input(&self) -> Vec<F> {
+        PublicInputs::new(
+            self.locking_data.clone(),
+            self.integrity_tag.clone().unwrap_or_default(),
+            self.unlocking_data.clone(),
+        )
+        .to_vec()
+    }
+
+    /// The offset of the integrity tag in [RefTxCircuit::public_input]. It is computed from the locking data, as
+    /// their length is only known at runtime for some predicates, e.g.,
+    /// [BoxedPredicate](crate::bitcoin_predicates::boxed::BoxedPredicate)
+    pub fn tag_offset(&self) -> usize {
+        Into::<Vec<F>>::into(self.locking_data.clone()).len()
+    }
+
+    /// The statement proven by the circuit, i.e., its public data
+    pub fn instance(&self) -> RefTxInstance<B, F, P, S> {
+        RefTxInstance::new(
+            self.locking_data.clone(),
+            self.integrity_tag.clone().unwrap_or_default(),
+            self.unlocking_data.clone(),
+        )
+    }
+}
+
+/// The statement of a [RefTxCircuit] as known to the verifier: the locking data, the integrity tag and the
+/// unlocking data. Contrary to a [RefTxCircuit] with `None` witnesses, it carries no witness and does not
+/// implement [ConstraintSynthesizer], so it cannot be used for proving.
+pub struct RefTxInstance<
+    B: BitcoinPredicate<F, P>,
+    F: PrimeField + Clone,
+    P: TxVarConfig + Clone,
+    S: IntegrityScheme<F, P> = SighashIntegrity<P>,
+> {
+    pub locking_data: B::LockingData,
+    pub integrity_tag: S::Tag,
+    pub unlocking_data: B::UnlockingData,
+    _phantom_field: PhantomData<F>,
+    _phantom_config: PhantomData<P>,
+}
+
+impl<B, F, P, S> RefTxInstance<B, F, P, S>
+where
+    B: BitcoinPredicate<F, P>,
+    F: PrimeField + Clone,
+    P: TxVarConfig + Clone,
+    S: IntegrityScheme<F, P>,
+{
+    pub fn new(
+        locking_data: B::LockingData,
+        integrity_tag: S::Tag,
+        unlocking_data: B::UnlockingData,
+    ) -> Self {
+        Self {
+            locking_data,
+            integrity_tag,
+            unlocking_data,
+            _phantom_field: PhantomData,
+            _phantom_config: PhantomData,
+        }
+    }
+
+    /// The public input of the circuit, see [RefTxCircuit::public_input]
+    pub fn public_input(&self) -> Vec<F> {
+        PublicInputs::new(
+            self.locking_data.clone(),
+            self.integrity_tag.clone(),
+            self.unlocking_data.clone(),
+        )
+        .to_vec()
+    }
+
+    /// Verify a Groth16 proof for the instance, see [verify_proof]
+    #[cfg(feature = "snark")]
+    pub fn verify<E: Pairing<ScalarField = F>>(
+        &self,
+        pvk: &PreparedVerifyingKey<E>,
+        proof: &Proof<E>,
+    ) -> Result<(), VerificationError> {
+        verify_proof(pvk, proof, &self.public_input())
+    }
+}
+
+impl<B, F, P, S> RefTxCircuit<B, F, P, S>
+where
+    B: BitcoinPredicate<F, P>,
+    F: PrimeField + Clone,
+    P: TxVarConfig + Clone,
+    S: IntegrityScheme<F, P>,
+{
+    /// The number of field elements of the locking data in [RefTxCircuit::public_input]
+    pub const N_LOCKING_INPUTS: usize = <B::LockingData as PublicInputSize<F>>::N_PUBLIC_INPUTS;
+    /// The number of field elements of the integrity tag in [RefTxCircuit::public_input]
+    pub const N_TAG_INPUTS: usize = <S::Tag as PublicInputSize<F>>::N_PUBLIC_INPUTS;
+    /// The number of field elements of the unlocking data in [RefTxCircuit::public_input]
+    pub const N_UNLOCKING_INPUTS: usize = <B::UnlockingData as PublicInputSize<F>>::N_PUBLIC_INPUTS;
+    /// The number of public inputs of the circuit, i.e., the length of [RefTxCircuit::public_input].
+    /// The verifying key of the circuit has `N_PUBLIC_INPUTS + 1` elements in `gamma_abc_g1`.
+    pub const N_PUBLIC_INPUTS: usize =
+        Self::N_LOCKING_INPUTS + Self::N_TAG_INPUTS + Self::N_UNLOCKING_INPUTS;
+
+    /// The schema of [RefTxCircuit::public_input], to be bound to the verifying key of the circuit,
+    /// see [VerifyingKeySnapshot](crate::snapshot::VerifyingKeySnapshot)
+    pub fn public_input_schema() -> PublicInputSchema {
+        PublicInputSchema::default()
+            .with_segment("locking_data", Self::N_LOCKING_INPUTS)
+            .with_segment("integrity_tag", Self::N_TAG_INPUTS)
+            .with_segment("unlocking_data", Self::N_UNLOCKING_INPUTS)
+    }
+
+    /// The name, offset and length in field elements of `locking_data`, `integrity_tag` and `unlocking_data`
+    /// in [RefTxCircuit::public_input]
+    pub fn public_input_layout() -> Vec<PublicInputField> {
+        Self::public_input_schema().layout()
+    }
+}
+
+/// The public input of a [RefTxCircuit], assembled from its components without constructing the circuit,
+/// e.g., by a verifier who only knows the locking data, the unlocking data and the integrity tag
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicInputs<F: PrimeField> {
+    pub locking_data: Vec<F>,
+    pub integrity_tag: Vec<F>,
+    pub unlocking_data: Vec<F>,
+}
+
+impl<F: PrimeField> PublicInputs<F> {
+    pub fn new(
+        locking_data: impl Into<Vec<F>>,
+        integrity_tag: impl Into<Vec<F>>,
+        unlocking_data: impl Into<Vec<F>>,
+    ) -> Self {
+        Self {
+            locking_data: locking_data.into(),
+            integrity_tag: integrity_tag.into(),
+            unlocking_data: unlocking_data.into(),
+        }
+    }
+
+    /// The offset of the integrity tag in [PublicInputs::to_vec]
+    pub fn tag_offset(&self) -> usize {
+        self.locking_data.len()
+    }
+
+    /// The public input in the order expected by the verifying key of the circuit, see
+    /// [RefTxCircuit::public_input_layout]
+    pub fn to_vec(&self) -> Vec<F> {
+        [
+            self.locking_data.as_slice(),
+            self.integrity_tag.as_slice(),
+            self.unlocking_data.as_slice(),
+        ]
+        .concat()
+    }
+}
+
+impl<B, F, P, S> ConstraintSynthesizer<F> for RefTxCircuit<B, F, P, S>
+where
+    B: BitcoinPredicate<F, P>,
+    F: PrimeField + Clone,
+    P: TxVarConfig + Clone,
+    S: IntegrityScheme<F, P>,
+{
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        // The spending transaction may only be missing during the setup
+        let spending_tx = match self.spending_data {
+            Some(tx) => tx,
+            None if self.setup_mode => default_tx::<P>(),
+            None => return Err(SynthesisError::AssignmentMissing),
+        };
+
+        // Allocate the inputs
+        let locking_data: B::LockingDataVar =
+            B::LockingDataVar::new_input(cs.clone(), || Ok(self.locking_data))?;
+        let integrity_tag: S::TagVar =
+            S::TagVar::new_input(cs.clone(), || Ok(self.integrity_tag.unwrap_or_default()))?;
+        let unlocking_data: B::UnlockingDataVar =
+            B::UnlockingDataVar::new_input(cs.clone(), || Ok(self.unlocking_data))?;
+        // Allocate the witnesses
+        let witness: B::WitnessVar = B::WitnessVar::new_witness(cs.clone(), || Ok(self.witness))?;
+        let spending_data: TxVar<F, P> =
+            TxVar::<F, P>::new_witness(cs.clone(), || Ok(spending_tx))?;
+        let default_integrity_witness = self.integrity_scheme.default_witness();
+        let mut integrity_witness: S::WitnessVar = S::WitnessVar::new_witness(cs.clone(), || {
+            Ok(self.integrity_witness.unwrap_or(default_integrity_witness))
+        })?;
+
+        // Enforce the integrity of the tag
+        let sighash = self.integrity_scheme.enforce_integrity(
+            cs.clone(),
+            &spending_data,
+            &mut integrity_witness,
+            &integrity_tag,
+        )?;
+
+        // Enforce the predicate
+        self.predicate.enforce_constraints_with_context(
+            cs.clone(),
+            &locking_data,
+            &unlocking_data,
+            &spending_data,
+            &witness,
+            &SpendingContext {
+                prev_lock_script: self.integrity_scheme.prev_lock_script(&integrity_witness),
+                prev_amount: self.integrity_scheme.prev_amount(&integrity_witness),
+                sighash: sighash.as_ref(),
+            },
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Verify a Groth16 proof for a [RefTxCircuit] against `public_input`, see [RefTxCircuit::public_input].
+///
+/// Returns [VerificationError::PublicInputMalformed] if `public_input` does not have the number of elements
+/// expected by `pvk` (or `pvk` is malformed), and [VerificationError::ProofInvalid] if the proof does not verify
+/// against it.
+#[cfg(feature = "snark")]
+pub fn verify_proof<E: Pairing>(
+    pvk: &PreparedVerifyingKey<E>,
+    proof: &Proof<E>,
+    public_input: &[E::ScalarField],
+) -> Result<(), VerificationError> {
+    // The first element of `gamma_abc_g1` is the one of the constant term, which is not part of the public input
+    let expected_len = pvk.vk.gamma_abc_g1.len().checked_sub(1).ok_or_else(|| {
+        VerificationError::PublicInputMalformed(
+            "The verifying key has no element for the constant term of the public input"
+                .to_string(),
+        )
+    })?;
+    if public_input.len() != expected_len {
+        return Err(VerificationError::PublicInputMalformed(format!(
+            "The number of elements of the public input: {} is different from the one expected by the verifying key: {}",
+            public_input.len(),
+            expected_len
+        )));
+    }
+
+    match Groth16::<E>::verify_proof(pvk, proof, public_input) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(VerificationError::ProofInvalid),
+        Err(e) => Err(VerificationError::PublicInputMalformed(e.to_string())),
+    }
+}
+
+/// A prepared verifying key together with the offset of the integrity tag in the public input of the circuit.
+/// The offset is set by the verifier, e.g., with [RefTxCircuit::tag_offset], so that a prover cannot point it to a
+/// copy of the tag, e.g., in the unlocking data
+#[cfg(feature = "snark")]
+pub struct LinkedVerifyingKey<'a, E: Pairing> {
+    pub pvk: &'a PreparedVerifyingKey<E>,
+    tag_offset: usize,
+}
+
+#[cfg(feature = "snark")]
+impl<'a, E: Pairing> LinkedVerifyingKey<'a, E> {
+    pub fn new(pvk: &'a PreparedVerifyingKey<E>, tag_offset: usize) -> Self {
+        Self { pvk, tag_offset }
+    }
+
+    /// The offset of the integrity tag in the public input of the circuit
+    pub fn tag_offset(&self) -> usize {
+        self.tag_offset
+    }
+
+    /// Check whether `public_input` carries `tag` at the offset of the integrity tag
+    fn has_tag(&self, public_input: &[E::ScalarField], tag: &[E::ScalarField]) -> bool {
+        public_input.get(self.tag_offset..self.tag_offset + tag.len()) == Some(tag)
+    }
+}
+
+/// A proof about a spending transaction meant to be verified together with other proofs about the same transaction,
+/// e.g., proofs of different predicates committing to the same integrity tag. See [verify_linked_proofs]
+#[cfg(feature = "snark")]
+pub struct LinkedProof<'a, E: Pairing> {
+    pub vk: &'a LinkedVerifyingKey<'a, E>,
+    pub proof: Proof<E>,
+    pub public_input: Vec<E::ScalarField>,
+}
+
+#[cfg(feature = "snark")]
+impl<'a, E: Pairing> LinkedProof<'a, E> {
+    /// Generate a Groth16 proof for `circuit`, to be linked to the other proofs about the same spending transaction
+    pub fn prove<B, P, S, R>(
+        circuit: RefTxCircuit<B, E::ScalarField, P, S>,
+        pk: &ProvingKey<E>,
+        vk: &'a LinkedVerifyingKey<'a, E>,
+        rng: &mut R,
+    ) -> Result<Self, SynthesisError>
+    where
+        B: BitcoinPredicate<E::ScalarField, P>,
+        P: TxVarConfig + Clone,
+        S: IntegrityScheme<E::ScalarField, P>,
+        R: Rng,
+    {
+        let public_input = circuit.public_input();
+        let proof = Groth16::<E>::create_random_proof_with_reduction(circuit, pk, rng)?;
+
+        Ok(Self {
+            vk,
+            proof,
+            public_input,
+        })
+    }
+}
+
+/// Verify a set of proofs about the same spending transaction: every proof must commit to `integrity_tag`,
+/// and must verify against its public input. The tag is looked up at the offset of its verifying key, see
+/// [LinkedVerifyingKey].
+///
+/// Returns [VerificationError::TagMismatch] if a proof commits to a different tag (or the tag cannot be found
+/// at the offset of its verifying key), otherwise the error returned by [verify_proof].
+#[cfg(feature = "snark")]
+pub fn verify_linked_proofs<E: Pairing, T: Clone + Into<Vec<E::ScalarField>>>(
+    integrity_tag: &T,
+    proofs: &[LinkedProof<E>],
+) -> Result<(), VerificationError> {
+    let tag: Vec<E::ScalarField> = integrity_tag.clone().into();
+    if proofs
+        .iter()
+        .any(|linked_proof| !linked_proof.vk.has_tag(&linked_proof.public_input, &tag))
+    {
+        return Err(VerificationError::TagMismatch);
+    }
+    for linked_proof in proofs.iter() {
+        verify_proof(
+            linked_proof.vk.pvk,
+            &linked_proof.proof,
+            &linked_proof.public_input,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// A RefTx-locked input of a spending transaction spending several RefTx-locked outputs, see [multi_input_circuits]
+pub struct RefTxInput<B: BitcoinPredicate<F, P>, F: PrimeField, P: TxVarConfig + Clone> {
+    pub locking_data: B::LockingData,
+    pub unlocking_data: B::UnlockingData,
+    pub witness: B::Witness,
+    /// The output spent by the input
+    pub prev_output: PrevOutput,
+    pub predicate: B,
+}
+
+/// Compute the integrity tags of the inputs in `P::N_INPUTS_TO_TAG` of `tx`. The i-th tag is computed using
+/// `prev_outputs[i]`, and the computations share the same sighash cache.
+///
+/// Returns [Error::ConfigMismatch] if the number of previous outputs is different from the number of inputs to tag,
+/// otherwise the error returned by [MultiInputIntegrityScheme::commit].
+pub fn multi_input_tags<P: MultiInputIntegrityConfig>(
+    tx: &Tx,
+    prev_outputs: &[PrevOutput],
+) -> Result<Vec<TransactionIntegrityTag>, Error> {
+    if prev_outputs.len() != P::N_INPUTS_TO_TAG.len() {
+        return Err(Error::ConfigMismatch(format!(
+            "The number of previous outputs: {} is different from the number of inputs to tag: {}",
+            prev_outputs.len(),
+            P::N_INPUTS_TO_TAG.len()
+        )));
+    }
+
+    let (prev_lock_scripts, prev_amounts): (Vec<Script>, Vec<u64>) = prev_outputs
+        .iter()
+        .map(|prev_output| {
+            (
+                prev_output.prev_lock_script.clone(),
+                prev_output.prev_amount,
+            )
+        })
+        .unzip();
+    MultiInputIntegrityScheme::<P>::commit(
+        tx,
+        &prev_lock_scripts,
+        &prev_amounts,
+        &mut SigHashCache::new(),
+    )
+}
+
+/// Build the circuits proving that `tx` satisfies the predicates of its RefTx-locked inputs. The i-th circuit
+/// proves the predicate of `inputs[i]` against the tag of the input `P::N_INPUTS_TO_TAG[i]`, and all the
+/// circuits share `tx` as spending data.
+///
+/// Returns [Error::ConfigMismatch] if the number of inputs is different from the number of inputs to tag.
+pub fn multi_input_circuits<B, F, P>(
+    tx: &Tx,
+    inputs: Vec<RefTxInput<B, F, P>>,
+) -> Result<Vec<RefTxCircuit<B, F, P, MultiInputSighashIntegrity<P>>>, Error>
+where
+    B: BitcoinPredicate<F, P>,
+    F: PrimeField + Clone,
+    P: MultiInputIntegrityConfig + TxVarConfig + Clone,
+{
+    if inputs.len() != P::N_INPUTS_TO_TAG.len() {
+        return Err(Error::ConfigMismatch(format!(
+            "The number of inputs: {} is different from the number of inputs to tag: {}",
+            inputs.len(),
+            P::N_INPUTS_TO_TAG.len()
+        )));
+    }
+
+    let prev_outputs: Vec<PrevOutput> = inputs
+        .iter()
+        .map(|input| PrevOutput {
+            prev_lock_script: input.prev_output.prev_lock_script.clone(),
+            prev_amount: input.prev_output.prev_amount,
+        })
+        .collect();
+    let tags = multi_input_tags::<P>(tx, &prev_outputs)?;
+
+    inputs
+        .into_iter()
+        .zip(tags)
+        .enumerate()
+        .map(|(position, (input, tag))| {
+            Ok(RefTxCircuit {
+                locking_data: input.locking_data,
+                integrity_tag: Some(tag),
+                unlocking_data: input.unlocking_data,
+                witness: input.witness,
+                spending_data: Some(tx.clone()),
+                integrity_witness: Some(SighashWitness {
+                    prev_output: input.prev_output,
+                    sighash_cache: SigHashCache::new(),
+                }),
+                predicate: input.predicate,
+                integrity_scheme: MultiInputSighashIntegrity::new(position)?,
+                setup_mode: false,
+            })
+        })
+        .collect()
+}
+
+/// Verify the proofs about a spending transaction spending several RefTx-locked outputs: the i-th proof must
+/// commit to the tag of the input `P::N_INPUTS_TO_TAG[i]` of `tx`, recomputed from `prev_outputs[i]`, and must
+/// verify against its public input. The tags are looked up at the offsets of the verifying keys of the proofs, see
+/// [LinkedVerifyingKey].
+///
+/// Returns [VerificationError::ConfigMismatch] if the tags cannot be computed (see [multi_input_tags]) or the number
+/// of proofs is different from the number of tags, [VerificationError::TagMismatch] if a proof commits to a different
+/// tag, otherwise the error returned by [verify_proof].
+#[cfg(feature = "snark")]
+pub fn verify_multi_input_proofs<E: Pairing, P: MultiInputIntegrityConfig>(
+    tx: &Tx,
+    prev_outputs: &[PrevOutput],
+    proofs: &[LinkedProof<E>],
+) -> Result<(), VerificationError> {
+    // Check all the tags before verifying any proof
+    let tags = multi_input_tags::<P>(tx, prev_outputs)
+        .map_err(|error| VerificationError::ConfigMismatch(error.to_string()))?;
+    if proofs.len() != tags.len() {
+        return Err(VerificationError::ConfigMismatch(format!(
+            "The number of proofs: {} is different from the number of tags: {}",
+            proofs.len(),
+            tags.len()
+        )));
+    }
+    for (tag, linked_proof) in tags.into_iter().zip(proofs.iter()) {
+        let tag: Vec<E::ScalarField> = tag.into();
+        if !linked_proof.vk.has_tag(&linked_proof.public_input, &tag) {
+            return Err(VerificationError::TagMismatch);
+        }
+    }
+    for linked_proof in proofs.iter() {
+        verify_proof(
+            linked_proof.vk.pvk,
+            &linked_proof.proof,
+            &linked_proof.public_input,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fr as F;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use chain_gang::address::addr_decode;
+    use chain_gang::script::Script;
+    use chain_gang::transaction::sighash::{SIGHASH_ALL, SIGHASH_FORKID, SigHashCache};
+
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::network::Network;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::util::Hash256;
+
+    use crate::bitcoin_predicates::data_structures::unit::BitcoinUnit;
+    use crate::bitcoin_predicates::fixed_lock_script::FixedLockScript;
+    use crate::constraints::tx::TxVarConfig;
+    use crate::error::Error;
+    use crate::traits::IntegrityScheme;
+    use crate::transaction_integrity_gadget::{
+        TransactionIntegrityConfig, TransactionIntegrityScheme,
+        multi_input::MultiInputIntegrityConfig,
+        scheme::{
+            MultiInputIntegrity, MultiInputWitness, PoseidonIntegrity, PrevOutput,
+            SighashIntegrity, SighashWitness,
+        },
+    };
+
+    use super::{PublicInputs, RefTxCircuit, RefTxInput, multi_input_circuits, multi_input_tags};
+
+    #[cfg(feature = "snark")]
+    use ark_bls12_381::Bls12_381;
+    #[cfg(feature = "snark")]
+    use ark_groth16::{Groth16, PreparedVerifyingKey, ProvingKey, prepare_verifying_key};
+    #[cfg(feature = "snark")]
+    use ark_r1cs_std::{alloc::AllocVar, eq::EqGadget, fields::fp::FpVar};
+    #[cfg(feature = "snark")]
+    use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+    #[cfg(feature = "snark")]
+    use rand::SeedableRng;
+    #[cfg(feature = "snark")]
+    use rand_chacha::ChaCha20Rng;
+
+    #[cfg(feature = "snark")]
+    use crate::error::VerificationError;
+    #[cfg(feature = "snark")]
+    use crate::transaction_integrity_gadget::{
+        TransactionIntegrityTag, constraints::TransactionIntegrityTagVar,
+    };
+
+    #[cfg(feature = "snark")]
+    use super::{
+        LinkedProof, LinkedVerifyingKey, verify_linked_proofs, verify_multi_input_proofs,
+        verify_proof,
+    };
+
+    type TestPredicate = FixedLockScript<F, Config>;
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 2;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19, 0x19];
+    }
+
+    impl TransactionIntegrityConfig for Config {
+        const N_INPUT: usize = 0;
+        const LEN_PREV_LOCK_SCRIPT: usize = 0x00;
+        const SIGHASH_FLAG: u8 = SIGHASH_ALL | SIGHASH_FORKID;
+    }
+
+    fn spending_tx(addr: &str, lock_script: Script) -> Tx {
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0,
+            }],
+            outputs: vec![
+                TxOut {
+                    satoshis: 100,
+                    lock_script,
+                },
+                TxOut {
+                    satoshis: 259899900,
+                    lock_script: p2pkh::create_lock_script(&hash160),
+                },
+            ],
+            lock_time: 0,
+        }
+    }
+
+    fn test_reftx(addr: &str, lock_script: Script, expected: bool) {
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        let tx = spending_tx(addr, lock_script);
+        let mut cache = SigHashCache::new();
+
+        let tag = TransactionIntegrityScheme::<Config>::commit(
+            &tx.clone(),
+            &Script(vec![]),
+            260000,
+            &mut cache,
+        )
+        .unwrap();
+        let tag_copy = tag.clone();
+        let test_predicate = TestPredicate::new(p2pkh::create_lock_script(&hash160), 0);
+        let test_circuit = RefTxCircuit::<TestPredicate, F, Config> {
+            locking_data: BitcoinUnit::default(),
+            integrity_tag: Some(tag),
+            unlocking_data: BitcoinUnit::default(),
+            witness: BitcoinUnit::default(),
+            spending_data: Some(tx),
+            integrity_witness: Some(SighashWitness {
+                prev_output: PrevOutput {
+                    prev_lock_script: Script(vec![]),
+                    prev_amount: 260000,
+                },
+                sighash_cache: SigHashCache::new(),
+            }),
+            predicate: test_predicate,
+            integrity_scheme: SighashIntegrity::new(),
+            setup_mode: false,
+        };
+
+        let public_input = test_circuit.public_input();
+        let test_circuit_instance = test_circuit.instance();
+        let cs = ConstraintSystem::<F>::new_ref();
+        test_circuit.generate_constraints(cs.clone()).unwrap();
+        let is_satisfied = cs.is_satisfied().unwrap();
+
+        // The number of public inputs is known without synthesising the circuit
+        assert_eq!(
+            RefTxCircuit::<TestPredicate, F, Config>::N_PUBLIC_INPUTS,
+            public_input.len()
+        );
+        assert_eq!(
+            RefTxCircuit::<TestPredicate, F, Config>::N_PUBLIC_INPUTS,
+            cs.num_instance_variables() - 1
+        );
+        assert_eq!(
+            RefTxCircuit::<TestPredicate, F, Config>::public_input_schema().len(),
+            public_input.len()
+        );
+
+        // The public input can be assembled from the tag alone, following the layout
+        let layout = RefTxCircuit::<TestPredicate, F, Config>::public_input_layout();
+        let public_inputs = PublicInputs::<F>::new(
+            BitcoinUnit::<F, Config>::default(),
+            tag_copy,
+            BitcoinUnit::<F, Config>::default(),
+        );
+        assert_eq!(public_inputs.to_vec(), public_input);
+        assert_eq!(test_circuit_instance.public_input(), public_input);
+        assert_eq!(layout[1].name, "integrity_tag");
+        assert_eq!(layout[1].offset, public_inputs.tag_offset());
+        assert_eq!(layout[1].len, public_inputs.integrity_tag.len());
+
+        assert_eq!(is_satisfied, expected);
+    }
+
+    #[test]
+    fn test_reftx_is_ok() {
+        let addr = "mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec";
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        let lock_script = p2pkh::create_lock_script(&hash160);
+        test_reftx(addr, lock_script, true);
+    }
+
+    #[test]
+    fn test_reftx_fails() {
+        let addr = "mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec";
+        let wrong_addr = "mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz";
+        let hash160 = addr_decode(wrong_addr, Network::BSV_Testnet).unwrap().0;
+        let lock_script = p2pkh::create_lock_script(&hash160);
+        test_reftx(addr, lock_script, false);
+    }
+
+    #[test]
+    fn test_reftx_with_poseidon_integrity() {
+        let addr = "mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec";
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        let tx = spending_tx(addr, p2pkh::create_lock_script(&hash160));
+
+        let integrity_scheme = PoseidonIntegrity::<F, Config>::default();
+        let mut integrity_witness = PrevOutput {
+            prev_lock_script: Script(vec![]),
+            prev_amount: 260000,
+        };
+        let tag = integrity_scheme
+            .commit(&tx, &mut integrity_witness)
+            .unwrap();
+        assert_eq!(
+            integrity_scheme.verify(&tx, &mut integrity_witness, &tag),
+            Ok(())
+        );
+
+        let test_circuit = RefTxCircuit::<TestPredicate, F, Config, PoseidonIntegrity<F, Config>> {
+            locking_data: BitcoinUnit::default(),
+            integrity_tag: Some(tag),
+            unlocking_data: BitcoinUnit::default(),
+            witness: BitcoinUnit::default(),
+            spending_data: Some(tx),
+            integrity_witness: Some(integrity_witness),
+            predicate: TestPredicate::new(p2pkh::create_lock_script(&hash160), 0),
+            integrity_scheme,
+            setup_mode: false,
+        };
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        test_circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+        assert_eq!(
+            RefTxCircuit::<TestPredicate, F, Config, PoseidonIntegrity<F, Config>>::N_PUBLIC_INPUTS,
+            cs.num_instance_variables() - 1
+        );
+    }
+
+    /// Circuit enforcing `x * x = y`, with `y` public
+    #[cfg(feature = "snark")]
+    struct SquareCircuit {
+        x: F,
+    }
+
+    #[cfg(feature = "snark")]
+    impl ConstraintSynthesizer<F> for SquareCircuit {
+        fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+            let x = FpVar::<F>::new_witness(cs.clone(), || Ok(self.x))?;
+            let y = FpVar::<F>::new_input(cs.clone(), || Ok(self.x * self.x))?;
+            (&x * &x).enforce_equal(&y)
+        }
+    }
+
+    #[cfg(feature = "snark")]
+    #[test]
+    fn test_verify_proof() {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let pk = Groth16::<Bls12_381>::generate_random_parameters_with_reduction(
+            SquareCircuit { x: F::from(0u64) },
+            &mut rng,
+        )
+        .unwrap();
+        let pvk = prepare_verifying_key(&pk.vk);
+        let proof = Groth16::<Bls12_381>::create_random_proof_with_reduction(
+            SquareCircuit { x: F::from(3u64) },
+            &pk,
+            &mut rng,
+        )
+        .unwrap();
+
+        assert_eq!(verify_proof(&pvk, &proof, &[F::from(9u64)]), Ok(()));
+        assert_eq!(
+            verify_proof(&pvk, &proof, &[F::from(10u64)]),
+            Err(VerificationError::ProofInvalid)
+        );
+        assert!(matches!(
+            verify_proof(&pvk, &proof, &[F::from(9u64), F::from(9u64)]),
+            Err(VerificationError::PublicInputMalformed(_))
+        ));
+
+        // A verifying key without the element of the constant term
+        let mut malformed_pvk = pvk.clone();
+        malformed_pvk.vk.gamma_abc_g1.clear();
+        assert!(matches!(
+            verify_proof(&malformed_pvk, &proof, &[]),
+            Err(VerificationError::PublicInputMalformed(_))
+        ));
+    }
+
+    /// Circuit committing to an integrity tag and enforcing `x * x = y`, with `y` public, laid out as a
+    /// [RefTxCircuit]: `y` is the locking data, and `unlocking_data` is an unconstrained public input
+    #[cfg(feature = "snark")]
+    struct TaggedSquareCircuit {
+        x: F,
+        tag: TransactionIntegrityTag,
+        unlocking_data: TransactionIntegrityTag,
+    }
+
+    #[cfg(feature = "snark")]
+    impl ConstraintSynthesizer<F> for TaggedSquareCircuit {
+        fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+            let x = FpVar::<F>::new_witness(cs.clone(), || Ok(self.x))?;
+            let y = FpVar::<F>::new_input(cs.clone(), || Ok(self.x * self.x))?;
+            TransactionIntegrityTagVar::<F>::new_input(cs.clone(), || Ok(self.tag))?;
+            TransactionIntegrityTagVar::<F>::new_input(cs.clone(), || Ok(self.unlocking_data))?;
+            (&x * &x).enforce_equal(&y)
+        }
+    }
+
+    /// The proving key of [TaggedSquareCircuit], and its verifying key with the tag after `y`
+    #[cfg(feature = "snark")]
+    fn tagged_square_keys(
+        rng: &mut ChaCha20Rng,
+    ) -> (ProvingKey<Bls12_381>, PreparedVerifyingKey<Bls12_381>) {
+        let pk = Groth16::<Bls12_381>::generate_random_parameters_with_reduction(
+            TaggedSquareCircuit {
+                x: F::from(0u64),
+                tag: TransactionIntegrityTag::default(),
+                unlocking_data: TransactionIntegrityTag::default(),
+            },
+            rng,
+        )
+        .unwrap();
+        let pvk = prepare_verifying_key(&pk.vk);
+        (pk, pvk)
+    }
+
+    #[cfg(feature = "snark")]
+    fn tagged_square_proof<'a>(
+        pk: &ProvingKey<Bls12_381>,
+        vk: &'a LinkedVerifyingKey<'a, Bls12_381>,
+        x: u64,
+        tag: &TransactionIntegrityTag,
+        unlocking_data: &TransactionIntegrityTag,
+        rng: &mut ChaCha20Rng,
+    ) -> LinkedProof<'a, Bls12_381> {
+        let proof = Groth16::<Bls12_381>::create_random_proof_with_reduction(
+            TaggedSquareCircuit {
+                x: F::from(x),
+                tag: tag.clone(),
+                unlocking_data: unlocking_data.clone(),
+            },
+            pk,
+            rng,
+        )
+        .unwrap();
+        let mut public_input = vec![F::from(x * x)];
+        public_input.extend_from_slice(&Into::<Vec<F>>::into(tag.clone()));
+        public_input.extend_from_slice(&Into::<Vec<F>>::into(unlocking_data.clone()));
+        LinkedProof {
+            vk,
+            proof,
+            public_input,
+        }
+    }
+
+    #[cfg(feature = "snark")]
+    #[test]
+    fn test_verify_linked_proofs() {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let tag = TransactionIntegrityTag { inner: [1; 32] };
+        let other_tag = TransactionIntegrityTag { inner: [2; 32] };
+        let (pk, pvk) = tagged_square_keys(&mut rng);
+        let vk = LinkedVerifyingKey::new(&pvk, 1);
+        let unlocking_data = TransactionIntegrityTag::default();
+
+        let proofs = vec![
+            tagged_square_proof(&pk, &vk, 3, &tag, &unlocking_data, &mut rng),
+            tagged_square_proof(&pk, &vk, 4, &tag, &unlocking_data, &mut rng),
+        ];
+        assert_eq!(verify_linked_proofs(&tag, &proofs), Ok(()));
+        assert_eq!(
+            verify_linked_proofs(&other_tag, &proofs),
+            Err(VerificationError::TagMismatch)
+        );
+
+        let proofs = vec![
+            tagged_square_proof(&pk, &vk, 3, &tag, &unlocking_data, &mut rng),
+            tagged_square_proof(&pk, &vk, 4, &other_tag, &unlocking_data, &mut rng),
+        ];
+        assert_eq!(
+            verify_linked_proofs(&tag, &proofs),
+            Err(VerificationError::TagMismatch)
+        );
+    }
+
+    #[cfg(feature = "snark")]
+    #[test]
+    fn test_verify_linked_proofs_tag_in_unlocking_data() {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let tag = TransactionIntegrityTag { inner: [1; 32] };
+        let other_tag = TransactionIntegrityTag { inner: [2; 32] };
+        let (pk, pvk) = tagged_square_keys(&mut rng);
+        let vk = LinkedVerifyingKey::new(&pvk, 1);
+
+        // The second proof is about a different transaction, and copies the expected tag in its unlocking data
+        let proofs = vec![
+            tagged_square_proof(&pk, &vk, 3, &tag, &other_tag, &mut rng),
+            tagged_square_proof(&pk, &vk, 4, &other_tag, &tag, &mut rng),
+        ];
+        let unlocking_data_offset = 1 + Into::<Vec<F>>::into(tag.clone()).len();
+        assert_eq!(
+            proofs[1].public_input[unlocking_data_offset..],
+            Into::<Vec<F>>::into(tag.clone())
+        );
+        assert_eq!(
+            verify_linked_proofs(&tag, &proofs),
+            Err(VerificationError::TagMismatch)
+        );
+    }
+
+    #[derive(Clone)]
+    struct MultiInputConfig;
+    impl TxVarConfig for MultiInputConfig {
+        const N_INPUTS: usize = 2;
+        const N_OUTPUTS: usize = 2;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0, 0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19, 0x19];
+    }
+
+    impl MultiInputIntegrityConfig for MultiInputConfig {
+        const N_INPUTS_TO_TAG: &[usize] = &[0, 1];
+        const LEN_PREV_LOCK_SCRIPTS: &[usize] = &[0x00, 0x00];
+        const SIGHASH_FLAG: u8 = SIGHASH_ALL | SIGHASH_FORKID;
+    }
+
+    type MultiInputPredicate = FixedLockScript<F, MultiInputConfig>;
+
+    fn multi_input_spending_tx(addr: &str) -> Tx {
+        let mut tx = spending_tx(addr, p2pkh_script(addr));
+        let mut input = tx.inputs[0].clone();
+        input.prev_output.index = 1;
+        tx.inputs.push(input);
+        tx
+    }
+
+    fn p2pkh_script(addr: &str) -> Script {
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        p2pkh::create_lock_script(&hash160)
+    }
+
+    fn prev_outputs() -> Vec<PrevOutput> {
+        [1000, 2000]
+            .into_iter()
+            .map(|prev_amount| PrevOutput {
+                prev_lock_script: Script(vec![]),
+                prev_amount,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_multi_input_circuits() {
+        let addr = "mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec";
+        let other_addr = "mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz";
+        let tx = multi_input_spending_tx(addr);
+
+        // The first input requires its output 0 to pay `addr`, the second its output 1 to pay `other_addr`
+        let inputs: Vec<RefTxInput<MultiInputPredicate, F, MultiInputConfig>> = prev_outputs()
+            .into_iter()
+            .zip([(addr, 0), (other_addr, 1)])
+            .map(|(prev_output, (addr, index))| RefTxInput {
+                locking_data: BitcoinUnit::default(),
+                unlocking_data: BitcoinUnit::default(),
+                witness: BitcoinUnit::default(),
+                prev_output,
+                predicate: MultiInputPredicate::new(p2pkh_script(addr), index),
+            })
+            .collect();
+
+        let tags = multi_input_tags::<MultiInputConfig>(&tx, &prev_outputs()).unwrap();
+        let circuits = multi_input_circuits(&tx, inputs).unwrap();
+        assert_eq!(circuits.len(), 2);
+        assert_ne!(tags[0], tags[1]);
+
+        let results: Vec<bool> = circuits
+            .into_iter()
+            .zip(tags.iter())
+            .map(|(circuit, tag)| {
+                assert_eq!(circuit.integrity_tag.as_ref(), Some(tag));
+                let cs = ConstraintSystem::<F>::new_ref();
+                circuit.generate_constraints(cs.clone()).unwrap();
+                cs.is_satisfied().unwrap()
+            })
+            .collect();
+        // The second output pays `addr`, so only the first predicate is satisfied
+        assert_eq!(results, vec![true, false]);
+    }
+
+    fn test_multi_input_reftx(prev_amounts_tag: [u64; 2], prev_amounts_witness: [u64; 2]) -> bool {
+        let addr = "mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec";
+        let tx = multi_input_spending_tx(addr);
+        let prev_outputs = |amounts: [u64; 2]| -> Vec<PrevOutput> {
+            amounts
+                .into_iter()
+                .map(|prev_amount| PrevOutput {
+                    prev_lock_script: Script(vec![]),
+                    prev_amount,
+                })
+                .collect()
+        };
+
+        let integrity_scheme = MultiInputIntegrity::<MultiInputConfig>::new();
+        let tag = IntegrityScheme::<F, MultiInputConfig>::commit(
+            &integrity_scheme,
+            &tx,
+            &mut MultiInputWitness {
+                prev_outputs: prev_outputs(prev_amounts_tag),
+                sighash_cache: SigHashCache::new(),
+            },
+        )
+        .unwrap();
+        let circuit = RefTxCircuit::<
+            MultiInputPredicate,
+            F,
+            MultiInputConfig,
+            MultiInputIntegrity<MultiInputConfig>,
+        > {
+            locking_data: BitcoinUnit::default(),
+            integrity_tag: Some(tag),
+            unlocking_data: BitcoinUnit::default(),
+            witness: BitcoinUnit::default(),
+            spending_data: Some(tx),
+            integrity_witness: Some(MultiInputWitness {
+                prev_outputs: prev_outputs(prev_amounts_witness),
+                sighash_cache: SigHashCache::new(),
+            }),
+            predicate: MultiInputPredicate::new(p2pkh_script(addr), 0),
+            integrity_scheme,
+            setup_mode: false,
+        };
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        // One tag per tagged input
+        assert_eq!(
+            RefTxCircuit::<
+                MultiInputPredicate,
+                F,
+                MultiInputConfig,
+                MultiInputIntegrity<MultiInputConfig>,
+            >::N_PUBLIC_INPUTS,
+            2 * RefTxCircuit::<TestPredicate, F, Config>::N_TAG_INPUTS
+        );
+        assert_eq!(
+            cs.num_instance_variables() - 1,
+            2 * RefTxCircuit::<TestPredicate, F, Config>::N_TAG_INPUTS
+        );
+
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_multi_input_reftx_is_ok() {
+        assert!(test_multi_input_reftx([1000, 2000], [1000, 2000]));
+    }
+
+    #[test]
+    fn test_multi_input_reftx_fails() {
+        // The amount of the second input is not the one committed to by its tag
+        assert!(!test_multi_input_reftx([1000, 2000], [1000, 2001]));
+    }
+
+    #[test]
+    fn test_multi_input_circuits_config_mismatch() {
+        let addr = "mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec";
+        let tx = multi_input_spending_tx(addr);
+        let inputs: Vec<RefTxInput<MultiInputPredicate, F, MultiInputConfig>> = vec![RefTxInput {
+            locking_data: BitcoinUnit::default(),
+            unlocking_data: BitcoinUnit::default(),
+            witness: BitcoinUnit::default(),
+            prev_output: prev_outputs().remove(0),
+            predicate: MultiInputPredicate::new(p2pkh_script(addr), 0),
+        }];
+
+        assert!(matches!(
+            multi_input_circuits(&tx, inputs),
+            Err(Error::ConfigMismatch(_))
+        ));
+        assert!(matches!(
+            multi_input_tags::<MultiInputConfig>(&tx, &prev_outputs()[..1]),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+
+    #[cfg(feature = "snark")]
+    #[test]
+    fn test_verify_multi_input_proofs() {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let tx = multi_input_spending_tx("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec");
+        let tags = multi_input_tags::<MultiInputConfig>(&tx, &prev_outputs()).unwrap();
+
+        let (pk, pvk) = tagged_square_keys(&mut rng);
+        let vk = LinkedVerifyingKey::new(&pvk, 1);
+        let unlocking_data = TransactionIntegrityTag::default();
+        let mut linked_proof = |x: u64, tag: &TransactionIntegrityTag| {
+            tagged_square_proof(&pk, &vk, x, tag, &unlocking_data, &mut rng)
+        };
+
+        let proofs = vec![linked_proof(3, &tags[0]), linked_proof(4, &tags[1])];
+        assert_eq!(
+            verify_multi_input_proofs::<Bls12_381, MultiInputConfig>(&tx, &prev_outputs(), &proofs),
+            Ok(())
+        );
+
+        // The proofs are in the wrong order
+        let swapped = vec![linked_proof(3, &tags[1]), linked_proof(4, &tags[0])];
+        assert_eq!(
+            verify_multi_input_proofs::<Bls12_381, MultiInputConfig>(
+                &tx,
+                &prev_outputs(),
+                &swapped
+            ),
+            Err(VerificationError::TagMismatch)
+        );
+
+        // A proof is missing
+        assert!(matches!(
+            verify_multi_input_proofs::<Bls12_381, MultiInputConfig>(
+                &tx,
+                &prev_outputs(),
+                &proofs[..1]
+            ),
+            Err(VerificationError::ConfigMismatch(_))
+        ));
+
+        // A proof too many
+        let mut extra = proofs;
+        extra.push(linked_proof(5, &tags[0]));
+        assert!(matches!(
+            verify_multi_input_proofs::<Bls12_381, MultiInputConfig>(&tx, &prev_outputs(), &extra),
+            Err(VerificationError::ConfigMismatch(_))
+        ));
+    }
+}
@@ -0,0 +1,200 @@
+//! Groth16 setup, proving and verification specialised to [RefTxCircuit]
+//!
+//! The functions take care of the ordering of the public input, see [RefTxCircuit::public_input], and of the
+//! (de)serialisation of proving keys, verifying keys and proofs.
+use ark_ec::pairing::Pairing;
+use ark_groth16::{Groth16, Proof, ProvingKey, VerifyingKey, prepare_verifying_key};
+use ark_relations::r1cs::SynthesisError;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use rand::Rng;
+
+use crate::{
+    constraints::tx::TxVarConfig,
+    error::VerificationError,
+    reftx::{RefTxCircuit, verify_proof},
+    traits::{BitcoinPredicate, IntegrityScheme},
+};
+
+/// Generate the proving key of `circuit`. The values assigned to the circuit are irrelevant, only its
+/// structure matters: build it with [crate::reftx::builder::RefTxCircuitBuilder::build_for_setup].
+pub fn setup<E, B, P, S, R>(
+    circuit: RefTxCircuit<B, E::ScalarField, P, S>,
+    rng: &mut R,
+) -> Result<ProvingKey<E>, SynthesisError>
+where
+    E: Pairing,
+    B: BitcoinPredicate<E::ScalarField, P>,
+    P: TxVarConfig + Clone,
+    S: IntegrityScheme<E::ScalarField, P>,
+    R: Rng,
+{
+    Groth16::<E>::generate_random_parameters_with_reduction(circuit, rng)
+}
+
+/// Generate a proof for `circuit`. Returns the proof together with the public input it verifies against.
+pub fn prove<E, B, P, S, R>(
+    pk: &ProvingKey<E>,
+    circuit: RefTxCircuit<B, E::ScalarField, P, S>,
+    rng: &mut R,
+) -> Result<(Proof<E>, Vec<E::ScalarField>), SynthesisError>
+where
+    E: Pairing,
+    B: BitcoinPredicate<E::ScalarField, P>,
+    P: TxVarConfig + Clone,
+    S: IntegrityScheme<E::ScalarField, P>,
+    R: Rng,
+{
+    let public_input = circuit.public_input();
+    let proof = Groth16::<E>::create_random_proof_with_reduction(circuit, pk, rng)?;
+
+    Ok((proof, public_input))
+}
+
+/// Verify a proof against `public_input`, see [verify_proof]
+pub fn verify<E: Pairing>(
+    vk: &VerifyingKey<E>,
+    proof: &Proof<E>,
+    public_input: &[E::ScalarField],
+) -> Result<(), VerificationError> {
+    verify_proof(&prepare_verifying_key(vk), proof, public_input)
+}
+
+/// Serialise a proving key, a verifying key or a proof in compressed form
+pub fn to_bytes<T: CanonicalSerialize>(value: &T) -> Result<Vec<u8>, SerializationError> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(value.compressed_size());
+    value.serialize_compressed(&mut bytes)?;
+
+    Ok(bytes)
+}
+
+/// Deserialise a proving key, a verifying key or a proof serialised with [to_bytes]
+pub fn from_bytes<T: CanonicalDeserialize>(bytes: &[u8]) -> Result<T, SerializationError> {
+    T::deserialize_compressed(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::{Bls12_381, Fr as F};
+    use ark_groth16::{Proof, ProvingKey, VerifyingKey, prepare_verifying_key};
+    use chain_gang::address::addr_decode;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::network::Network;
+    use chain_gang::script::Script;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::transaction::sighash::{SIGHASH_ALL, SIGHASH_FORKID};
+    use chain_gang::util::Hash256;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::bitcoin_predicates::data_structures::unit::BitcoinUnit;
+    use crate::bitcoin_predicates::fixed_lock_script::FixedLockScript;
+    use crate::constraints::tx::TxVarConfig;
+    use crate::error::VerificationError;
+    use crate::reftx::{RefTxCircuit, builder::RefTxCircuitBuilder};
+    use crate::transaction_integrity_gadget::{
+        TransactionIntegrityConfig,
+        scheme::{PoseidonIntegrity, PrevOutput},
+    };
+
+    use super::{from_bytes, prove, setup, to_bytes, verify};
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    impl TransactionIntegrityConfig for Config {
+        const N_INPUT: usize = 0;
+        const LEN_PREV_LOCK_SCRIPT: usize = 0x00;
+        const SIGHASH_FLAG: u8 = SIGHASH_ALL | SIGHASH_FORKID;
+    }
+
+    type Predicate = FixedLockScript<F, Config>;
+    type Circuit = RefTxCircuit<Predicate, F, Config, PoseidonIntegrity<F, Config>>;
+
+    fn p2pkh_script(addr: &str) -> Script {
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        p2pkh::create_lock_script(&hash160)
+    }
+
+    fn builder() -> RefTxCircuitBuilder<Predicate, F, Config, PoseidonIntegrity<F, Config>> {
+        RefTxCircuitBuilder::new(
+            Predicate::new(p2pkh_script("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec"), 0),
+            PoseidonIntegrity::<F, Config>::default(),
+        )
+        .locking_data(BitcoinUnit::default())
+        .unlocking_data(BitcoinUnit::default())
+        .witness(BitcoinUnit::default())
+    }
+
+    fn circuit(tx: Tx) -> Circuit {
+        builder()
+            .spending_data(tx)
+            .integrity_witness(PrevOutput {
+                prev_lock_script: Script(vec![]),
+                prev_amount: 1000,
+            })
+            .build()
+            .unwrap()
+    }
+
+    fn tx() -> Tx {
+        Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![TxOut {
+                satoshis: 900,
+                lock_script: p2pkh_script("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec"),
+            }],
+            lock_time: 0,
+        }
+    }
+
+    #[test]
+    fn test_setup_prove_verify() {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let pk =
+            setup::<Bls12_381, _, _, _, _>(builder().build_for_setup().unwrap(), &mut rng).unwrap();
+        let (proof, public_input) = prove(&pk, circuit(tx()), &mut rng).unwrap();
+
+        assert_eq!(public_input.len(), Circuit::N_PUBLIC_INPUTS);
+        assert_eq!(verify(&pk.vk, &proof, &public_input), Ok(()));
+
+        // The keys and the proof survive a serialisation round trip
+        let pk: ProvingKey<Bls12_381> = from_bytes(&to_bytes(&pk).unwrap()).unwrap();
+        let vk: VerifyingKey<Bls12_381> = from_bytes(&to_bytes(&pk.vk).unwrap()).unwrap();
+        let proof: Proof<Bls12_381> = from_bytes(&to_bytes(&proof).unwrap()).unwrap();
+        assert_eq!(verify(&vk, &proof, &public_input), Ok(()));
+
+        // The verifier only needs the statement
+        let instance = circuit(tx()).instance();
+        assert_eq!(instance.verify(&prepare_verifying_key(&vk), &proof), Ok(()));
+
+        // The proof does not verify against a different tag
+        let mut other_tx = tx();
+        other_tx.outputs[0].satoshis = 901;
+        assert_eq!(
+            verify(&vk, &proof, &circuit(other_tx).public_input()),
+            Err(VerificationError::ProofInvalid)
+        );
+    }
+
+    #[test]
+    fn test_from_bytes_fails() {
+        assert!(from_bytes::<VerifyingKey<Bls12_381>>(&[0u8; 8]).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+//! Gadget to verify Schnorr signatures
+
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use ark_crypto_primitives::crh::sha256::constraints::Sha256Gadget;
+use ark_ec::{AffineRepr, twisted_edwards::TECurveConfig};
+use ark_ff::{BigInteger, PrimeField};
+use ark_r1cs_std::{
+    alloc::{AllocVar, AllocationMode},
+    eq::EqGadget,
+    fields::fp::FpVar,
+    groups::{CurveVar, curves::twisted_edwards::AffineVar},
+    prelude::{Boolean, ToBitsGadget, ToBytesGadget},
+    uint8::UInt8,
+};
+use ark_relations::r1cs::{Namespace, SynthesisError};
+
+use crate::schnorr::SchnorrSignature;
+
+/// The R1CS version of a Schnorr public key
+pub type SchnorrPublicKeyVar<F, C> = AffineVar<C, FpVar<F>>;
+
+/// The R1CS version of [SchnorrSignature]
+/// The scalar `s` is stored as its little endian bit decomposition
+pub struct SchnorrSignatureVar<F: PrimeField, C: TECurveConfig<BaseField = F>> {
+    pub r: AffineVar<C, FpVar<F>>,
+    pub s: Vec<Boolean<F>>,
+}
+
+impl<F: PrimeField, C: TECurveConfig<BaseField = F>> AllocVar<SchnorrSignature<C>, F>
+    for SchnorrSignatureVar<F, C>
+{
+    fn new_variable<T: Borrow<SchnorrSignature<C>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let signature: SchnorrSignature<C> = f().map(|s| s.borrow().clone())?;
+        let mut s_bits = signature.s.into_bigint().to_bits_le();
+        s_bits.truncate(C::ScalarField::MODULUS_BIT_SIZE as usize);
+
+        Ok(Self {
+            r: AffineVar::<C, FpVar<F>>::new_variable(cs.clone(), || Ok(signature.r), mode)?,
+            s: Vec::<Boolean<F>>::new_variable(cs.clone(), || Ok(s_bits), mode)?,
+        })
+    }
+}
+
+/// The gadget version of [Schnorr](crate::schnorr::Schnorr)
+pub struct SchnorrGadget<F: PrimeField, C: TECurveConfig<BaseField = F>> {
+    _curve: PhantomData<C>,
+    _field: PhantomData<F>,
+}
+
+impl<F: PrimeField, C: TECurveConfig<BaseField = F>> SchnorrGadget<F, C> {
+    /// Return a [Boolean] which is `TRUE` if `signature` is a valid signature for `message` against `public_key`
+    pub fn verify(
+        public_key: &SchnorrPublicKeyVar<F, C>,
+        message: &[UInt8<F>],
+        signature: &SchnorrSignatureVar<F, C>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        // Compute the challenge e = Sha256(R.x || R.y || PK.x || PK.y || m)
+        let mut data: Vec<UInt8<F>> = Vec::new();
+        for coordinate in [&signature.r.x, &signature.r.y, &public_key.x, &public_key.y] {
+            data.extend_from_slice(&coordinate.to_bytes_le()?);
+        }
+        data.extend_from_slice(message);
+        let challenge = Sha256Gadget::<F>::digest(&data)?.0.to_bits_le()?;
+
+        // Check s * G == R + e * PK
+        let generator = AffineVar::<C, FpVar<F>>::constant(C::GENERATOR.into_group());
+        let lhs = generator.scalar_mul_le(signature.s.iter())?;
+        let rhs = signature.r.clone() + public_key.scalar_mul_le(challenge.iter())?;
+
+        lhs.is_eq(&rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::Fr as F;
+    use ark_ed_on_bls12_381::EdwardsConfig;
+    use ark_r1cs_std::{alloc::AllocVar, eq::EqGadget, prelude::Boolean, uint8::UInt8};
+    use ark_relations::r1cs::ConstraintSystem;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::schnorr::Schnorr;
+
+    use super::{SchnorrGadget, SchnorrPublicKeyVar, SchnorrSignatureVar};
+
+    fn test_verify(signed_message: &[u8], message: &[u8], expected: bool) {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let (secret_key, public_key) = Schnorr::<EdwardsConfig>::keygen(&mut rng);
+        let signature = Schnorr::<EdwardsConfig>::sign(&mut rng, &secret_key, signed_message);
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let public_key_var =
+            SchnorrPublicKeyVar::<F, EdwardsConfig>::new_input(cs.clone(), || Ok(public_key))
+                .unwrap();
+        let message_var = UInt8::<F>::new_input_vec(cs.clone(), message).unwrap();
+        let signature_var =
+            SchnorrSignatureVar::<F, EdwardsConfig>::new_witness(cs.clone(), || Ok(signature))
+                .unwrap();
+
+        SchnorrGadget::<F, EdwardsConfig>::verify(&public_key_var, &message_var, &signature_var)
+            .unwrap()
+            .enforce_equal(&Boolean::<F>::TRUE)
+            .unwrap();
+        assert_eq!(cs.is_satisfied().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_verify_valid_signature() {
+        test_verify(b"message", b"message", true);
+    }
+
+    #[test]
+    fn test_verify_invalid_signature() {
+        test_verify(b"message", b"another message", false);
+    }
+}
@@ -0,0 +1,129 @@
+//! Schnorr signatures over a twisted Edwards curve whose base field is the field over which the circuit is defined
+//!
+//! The signature of a message `m` under the public key `PK = sk * G` is a pair `(R, s)` such that
+//! `s * G = R + e * PK`, where `e = Sha256(R.x || R.y || PK.x || PK.y || m)` interpreted as a little endian integer.
+//! Coordinates are serialised as the 32-byte little endian representation of their canonical form.
+
+use std::marker::PhantomData;
+
+use ark_crypto_primitives::crh::{CRHScheme, sha256::Sha256};
+use ark_ec::{
+    AffineRepr, CurveGroup,
+    twisted_edwards::{Affine, TECurveConfig},
+};
+use ark_ff::{BigInteger, PrimeField, UniformRand, Zero};
+use rand::Rng;
+
+pub mod constraints;
+
+/// A Schnorr signature
+pub struct SchnorrSignature<C: TECurveConfig> {
+    pub r: Affine<C>,
+    pub s: C::ScalarField,
+}
+
+impl<C: TECurveConfig> Clone for SchnorrSignature<C> {
+    fn clone(&self) -> Self {
+        Self {
+            r: self.r,
+            s: self.s,
+        }
+    }
+}
+
+/// The default signature is `(O, 0)`, where `O` is the identity of the curve. It is not a valid signature
+/// for any public key different from `O`, and it is used as a placeholder when a signature is missing.
+impl<C: TECurveConfig> Default for SchnorrSignature<C> {
+    fn default() -> Self {
+        Self {
+            r: Affine::<C>::zero(),
+            s: C::ScalarField::zero(),
+        }
+    }
+}
+
+/// The Schnorr signature scheme
+pub struct Schnorr<C: TECurveConfig> {
+    _curve: PhantomData<C>,
+}
+
+impl<C: TECurveConfig> Schnorr<C>
+where
+    C::BaseField: PrimeField,
+{
+    /// Generate a key pair `(sk, PK)`
+    pub fn keygen<R: Rng>(rng: &mut R) -> (C::ScalarField, Affine<C>) {
+        let secret_key = C::ScalarField::rand(rng);
+        let public_key = (C::GENERATOR * secret_key).into_affine();
+
+        (secret_key, public_key)
+    }
+
+    /// Sign `message` with the secret key `secret_key`
+    pub fn sign<R: Rng>(
+        rng: &mut R,
+        secret_key: &C::ScalarField,
+        message: &[u8],
+    ) -> SchnorrSignature<C> {
+        let public_key = (C::GENERATOR * secret_key).into_affine();
+        let nonce = C::ScalarField::rand(rng);
+        let r = (C::GENERATOR * nonce).into_affine();
+        let challenge = Self::challenge(&r, &public_key, message);
+
+        SchnorrSignature {
+            r,
+            s: nonce + challenge * secret_key,
+        }
+    }
+
+    /// Verify the validity of `signature` for `message` against `public_key`
+    pub fn verify(public_key: &Affine<C>, message: &[u8], signature: &SchnorrSignature<C>) -> bool {
+        let challenge = Self::challenge(&signature.r, public_key, message);
+
+        C::GENERATOR * signature.s == signature.r.into_group() + *public_key * challenge
+    }
+
+    /// Compute the challenge `e = Sha256(R.x || R.y || PK.x || PK.y || m)`
+    fn challenge(r: &Affine<C>, public_key: &Affine<C>, message: &[u8]) -> C::ScalarField {
+        let mut data: Vec<u8> = Vec::new();
+        for coordinate in [r.x, r.y, public_key.x, public_key.y] {
+            data.extend_from_slice(&coordinate.into_bigint().to_bytes_le());
+        }
+        data.extend_from_slice(message);
+
+        let digest = <Sha256 as CRHScheme>::evaluate(&(), data.as_slice()).unwrap();
+        C::ScalarField::from_le_bytes_mod_order(&digest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_ed_on_bls12_381::EdwardsConfig;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use super::{Schnorr, SchnorrSignature};
+
+    #[test]
+    fn test_sign_and_verify() {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let (secret_key, public_key) = Schnorr::<EdwardsConfig>::keygen(&mut rng);
+        let signature = Schnorr::<EdwardsConfig>::sign(&mut rng, &secret_key, b"message");
+
+        assert!(Schnorr::<EdwardsConfig>::verify(
+            &public_key,
+            b"message",
+            &signature
+        ));
+        assert!(!Schnorr::<EdwardsConfig>::verify(
+            &public_key,
+            b"another message",
+            &signature
+        ));
+        assert!(!Schnorr::<EdwardsConfig>::verify(
+            &public_key,
+            b"message",
+            &SchnorrSignature::default()
+        ));
+    }
+}
@@ -0,0 +1,511 @@
+//! Snapshots of verifying keys bound to the schema of the public input and to the circuit they were generated for
+//!
+//! A [VerifyingKeySnapshot] is serialised together with the [PublicInputSchema] of the circuit and its fingerprint,
+//! see [circuit_fingerprint]. When loaded, the snapshot is checked against the schema and the fingerprint expected by
+//! the verifier, so that a verifying key generated for a different layout of the public input is never used.
+//!
+//! A snapshot is also valid for a range of block heights only, see [VerifyingKeySnapshot::with_validity]. During
+//! the migration to a new circuit, the verifier loads the snapshots of both circuits and each of them refuses the
+//! proofs outside of its window.
+use ark_crypto_primitives::crh::{CRHScheme, sha256::Sha256};
+#[cfg(feature = "snark")]
+use ark_ec::pairing::Pairing;
+use ark_ff::PrimeField;
+#[cfg(feature = "snark")]
+use ark_groth16::{PreparedVerifyingKey, Proof, VerifyingKey, prepare_verifying_key};
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, OptimizationGoal, SynthesisError, SynthesisMode,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+#[cfg(feature = "snark")]
+use crate::error::{Error, VerificationError};
+#[cfg(feature = "snark")]
+use crate::reftx::verify_proof;
+
+/// The version of the serialisation format of [VerifyingKeySnapshot]
+#[cfg(feature = "snark")]
+pub const SNAPSHOT_VERSION: u8 = 2;
+
+/// A named segment of the public input, made of `len` field elements
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PublicInputSegment {
+    pub name: String,
+    pub len: usize,
+}
+
+/// The position of a segment in the public input: the offset of its first field element and its length
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicInputField {
+    pub name: String,
+    pub offset: usize,
+    pub len: usize,
+}
+
+/// The layout of the public input of a circuit: the list of its segments, in order
+#[derive(Clone, Debug, PartialEq, Eq, Default, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PublicInputSchema {
+    pub segments: Vec<PublicInputSegment>,
+}
+
+impl PublicInputSchema {
+    /// Append the segment `name` made of `len` field elements
+    pub fn with_segment(mut self, name: &str, len: usize) -> Self {
+        self.segments.push(PublicInputSegment {
+            name: name.to_string(),
+            len,
+        });
+        self
+    }
+
+    /// The number of field elements of the public input
+    pub fn len(&self) -> usize {
+        self.segments.iter().map(|segment| segment.len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The position of every segment in the public input, in order
+    pub fn layout(&self) -> Vec<PublicInputField> {
+        let mut offset = 0;
+        self.segments
+            .iter()
+            .map(|segment| {
+                let field = PublicInputField {
+                    name: segment.name.clone(),
+                    offset,
+                    len: segment.len,
+                };
+                offset += segment.len;
+                field
+            })
+            .collect()
+    }
+
+    /// The position of the segment `name` in the public input
+    pub fn field(&self, name: &str) -> Option<PublicInputField> {
+        self.layout().into_iter().find(|field| field.name == name)
+    }
+}
+
+/// Compute the fingerprint of `circuit`: the Sha256 hash of its constraint matrices, see [ConstraintSystem::to_matrices].
+/// The fingerprint does not depend on the values of the inputs and the witnesses.
+pub fn circuit_fingerprint<F: PrimeField, C: ConstraintSynthesizer<F>>(
+    circuit: C,
+) -> Result<[u8; 32], SynthesisError> {
+    let cs = ConstraintSystem::<F>::new_ref();
+    cs.set_optimization_goal(OptimizationGoal::Constraints);
+    cs.set_mode(SynthesisMode::Setup);
+    circuit.generate_constraints(cs.clone())?;
+    cs.finalize();
+    let matrices = cs.to_matrices().ok_or(SynthesisError::MissingCS)?;
+
+    let mut data: Vec<u8> = Vec::new();
+    for n in [
+        matrices.num_instance_variables,
+        matrices.num_witness_variables,
+        matrices.num_constraints,
+    ] {
+        data.extend_from_slice(&(n as u64).to_le_bytes());
+    }
+    for matrix in [&matrices.a, &matrices.b, &matrices.c] {
+        for row in matrix.iter() {
+            data.extend_from_slice(&(row.len() as u64).to_le_bytes());
+            for (coefficient, index) in row.iter() {
+                coefficient.serialize_compressed(&mut data).unwrap();
+                data.extend_from_slice(&(*index as u64).to_le_bytes());
+            }
+        }
+    }
+
+    Ok(<Sha256 as CRHScheme>::evaluate(&(), data.as_slice())
+        .unwrap()
+        .try_into()
+        .unwrap())
+}
+
+/// A Groth16 verifying key together with the schema of the public input, the fingerprint of the circuit and the
+/// block heights at which the key is valid
+#[cfg(feature = "snark")]
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct VerifyingKeySnapshot<E: Pairing> {
+    pub version: u8,
+    pub schema: PublicInputSchema,
+    pub fingerprint: [u8; 32],
+    /// First block height at which the key is valid
+    pub valid_from: u32,
+    /// First block height at which the key is no longer valid, `None` if the key does not expire
+    pub valid_until: Option<u32>,
+    pub vk: VerifyingKey<E>,
+}
+
+#[cfg(feature = "snark")]
+impl<E: Pairing> VerifyingKeySnapshot<E> {
+    /// Build a snapshot valid at every block height.
+    ///
+    /// Returns [Error::ConfigMismatch] if the length of `schema` is different from the length of the public input
+    /// expected by `vk`
+    pub fn new(
+        vk: VerifyingKey<E>,
+        schema: PublicInputSchema,
+        fingerprint: [u8; 32],
+    ) -> Result<Self, Error> {
+        if schema.len() + 1 != vk.gamma_abc_g1.len() {
+            return Err(Error::ConfigMismatch(format!(
+                "The number of elements of the public input in the schema: {} is different from the one expected by the verifying key: {}",
+                schema.len(),
+                vk.gamma_abc_g1.len() - 1
+            )));
+        }
+        Ok(Self {
+            version: SNAPSHOT_VERSION,
+            schema,
+            fingerprint,
+            valid_from: 0,
+            valid_until: None,
+            vk,
+        })
+    }
+
+    /// Restrict the validity of the key to the block heights from `valid_from` (included) to `valid_until`
+    /// (excluded), or to every block height from `valid_from` if `valid_until` is `None`.
+    ///
+    /// Returns [Error::ConfigMismatch] if the window is empty
+    pub fn with_validity(
+        mut self,
+        valid_from: u32,
+        valid_until: Option<u32>,
+    ) -> Result<Self, Error> {
+        if valid_until.is_some_and(|valid_until| valid_until <= valid_from) {
+            return Err(Error::ConfigMismatch(format!(
+                "The validity window of the snapshot: [{}, {:?}) is empty",
+                valid_from, valid_until
+            )));
+        }
+        self.valid_from = valid_from;
+        self.valid_until = valid_until;
+        Ok(self)
+    }
+
+    /// Whether the key is valid at block height `height`
+    pub fn is_valid_at(&self, height: u32) -> bool {
+        height >= self.valid_from
+            && self
+                .valid_until
+                .is_none_or(|valid_until| height < valid_until)
+    }
+
+    /// Serialise the snapshot
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        self.serialize_compressed(&mut bytes).unwrap();
+        bytes
+    }
+
+    /// Deserialise a snapshot, refusing it if its schema or its fingerprint differ from the expected ones.
+    ///
+    /// Returns [VerificationError::SnapshotMalformed] if `bytes` is not a valid snapshot, and
+    /// [VerificationError::ConfigMismatch] if the snapshot does not match `schema` or `fingerprint`.
+    pub fn load(
+        bytes: &[u8],
+        schema: &PublicInputSchema,
+        fingerprint: &[u8; 32],
+    ) -> Result<Self, VerificationError> {
+        let snapshot = Self::deserialize_compressed(bytes)
+            .map_err(|e| VerificationError::SnapshotMalformed(e.to_string()))?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(VerificationError::SnapshotMalformed(format!(
+                "The version of the snapshot: {} is different from the supported one: {}",
+                snapshot.version, SNAPSHOT_VERSION
+            )));
+        }
+        if snapshot.schema.len() + 1 != snapshot.vk.gamma_abc_g1.len() {
+            return Err(VerificationError::SnapshotMalformed(format!(
+                "The number of elements of the public input in the schema: {} is different from the one expected by the verifying key: {}",
+                snapshot.schema.len(),
+                snapshot.vk.gamma_abc_g1.len() - 1
+            )));
+        }
+        if snapshot
+            .valid_until
+            .is_some_and(|valid_until| valid_until <= snapshot.valid_from)
+        {
+            return Err(VerificationError::SnapshotMalformed(format!(
+                "The validity window of the snapshot: [{}, {:?}) is empty",
+                snapshot.valid_from, snapshot.valid_until
+            )));
+        }
+        if snapshot.schema != *schema {
+            return Err(VerificationError::ConfigMismatch(format!(
+                "The schema of the snapshot: {:?} is different from the expected one: {:?}",
+                snapshot.schema, schema
+            )));
+        }
+        if snapshot.fingerprint != *fingerprint {
+            return Err(VerificationError::ConfigMismatch(format!(
+                "The fingerprint of the snapshot: {} is different from the expected one: {}",
+                hex::encode(snapshot.fingerprint),
+                hex::encode(fingerprint)
+            )));
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Prepare the verifying key of the snapshot
+    pub fn prepared_verifying_key(&self) -> PreparedVerifyingKey<E> {
+        prepare_verifying_key(&self.vk)
+    }
+
+    /// Verify `proof` against `public_input`, laid out according to `schema`, at block height `height`.
+    ///
+    /// Returns [VerificationError::KeyNotValid] if the key is not valid at `height`,
+    /// [VerificationError::ConfigMismatch] if `schema` is different from the schema of the snapshot, otherwise the
+    /// error returned by [verify_proof].
+    pub fn verify(
+        &self,
+        schema: &PublicInputSchema,
+        proof: &Proof<E>,
+        public_input: &[E::ScalarField],
+        height: u32,
+    ) -> Result<(), VerificationError> {
+        if !self.is_valid_at(height) {
+            return Err(VerificationError::KeyNotValid(format!(
+                "The block height: {} is outside of the validity window of the snapshot: [{}, {:?})",
+                height, self.valid_from, self.valid_until
+            )));
+        }
+        if self.schema != *schema {
+            return Err(VerificationError::ConfigMismatch(format!(
+                "The schema of the snapshot: {:?} is different from the expected one: {:?}",
+                self.schema, schema
+            )));
+        }
+
+        verify_proof(&self.prepared_verifying_key(), proof, public_input)
+    }
+}
+
+#[cfg(all(test, feature = "snark"))]
+mod tests {
+    use ark_bls12_381::{Bls12_381, Fr as F};
+    use ark_groth16::Groth16;
+    use ark_r1cs_std::{alloc::AllocVar, eq::EqGadget, fields::fp::FpVar};
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::error::{Error, VerificationError};
+
+    use super::{PublicInputField, PublicInputSchema, VerifyingKeySnapshot, circuit_fingerprint};
+
+    /// Circuit enforcing `x * x = y` and `x + z = w`, with `y` and `w` public
+    struct TwoOutputCircuit {
+        x: F,
+        z: F,
+        square_first: bool,
+    }
+
+    impl ConstraintSynthesizer<F> for TwoOutputCircuit {
+        fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+            let x = FpVar::<F>::new_witness(cs.clone(), || Ok(self.x))?;
+            let z = FpVar::<F>::new_witness(cs.clone(), || Ok(self.z))?;
+            let (y, w) = if self.square_first {
+                let y = FpVar::<F>::new_input(cs.clone(), || Ok(self.x * self.x))?;
+                let w = FpVar::<F>::new_input(cs.clone(), || Ok(self.x + self.z))?;
+                (y, w)
+            } else {
+                let w = FpVar::<F>::new_input(cs.clone(), || Ok(self.x + self.z))?;
+                let y = FpVar::<F>::new_input(cs.clone(), || Ok(self.x * self.x))?;
+                (y, w)
+            };
+            (&x * &x).enforce_equal(&y)?;
+            (&x + &z).enforce_equal(&w)
+        }
+    }
+
+    fn circuit(square_first: bool) -> TwoOutputCircuit {
+        TwoOutputCircuit {
+            x: F::from(3u64),
+            z: F::from(4u64),
+            square_first,
+        }
+    }
+
+    fn schema() -> PublicInputSchema {
+        PublicInputSchema::default()
+            .with_segment("square", 1)
+            .with_segment("sum", 1)
+    }
+
+    #[test]
+    fn test_schema_layout() {
+        let schema = PublicInputSchema::default()
+            .with_segment("first", 2)
+            .with_segment("empty", 0)
+            .with_segment("last", 3);
+        let layout = schema.layout();
+
+        assert_eq!(
+            layout.iter().map(|field| field.offset).collect::<Vec<_>>(),
+            vec![0, 2, 2]
+        );
+        assert_eq!(
+            schema.field("last"),
+            Some(PublicInputField {
+                name: "last".to_string(),
+                offset: 2,
+                len: 3
+            })
+        );
+        assert_eq!(schema.field("missing"), None);
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let pk = Groth16::<Bls12_381>::generate_random_parameters_with_reduction(
+            circuit(true),
+            &mut rng,
+        )
+        .unwrap();
+        let fingerprint = circuit_fingerprint(circuit(true)).unwrap();
+        let snapshot = VerifyingKeySnapshot::new(pk.vk.clone(), schema(), fingerprint).unwrap();
+
+        let loaded =
+            VerifyingKeySnapshot::<Bls12_381>::load(&snapshot.to_bytes(), &schema(), &fingerprint)
+                .unwrap();
+        assert_eq!(loaded, snapshot);
+
+        let proof =
+            Groth16::<Bls12_381>::create_random_proof_with_reduction(circuit(true), &pk, &mut rng)
+                .unwrap();
+        assert_eq!(
+            loaded.verify(&schema(), &proof, &[F::from(9u64), F::from(7u64)], 0),
+            Ok(())
+        );
+        assert_eq!(
+            loaded.verify(&schema(), &proof, &[F::from(7u64), F::from(9u64)], 0),
+            Err(VerificationError::ProofInvalid)
+        );
+    }
+
+    #[test]
+    fn test_snapshot_refuses_mismatches() {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let pk = Groth16::<Bls12_381>::generate_random_parameters_with_reduction(
+            circuit(true),
+            &mut rng,
+        )
+        .unwrap();
+        let fingerprint = circuit_fingerprint(circuit(true)).unwrap();
+        let bytes = VerifyingKeySnapshot::new(pk.vk, schema(), fingerprint)
+            .unwrap()
+            .to_bytes();
+
+        // The fingerprint depends on the circuit, not on the values of the inputs
+        assert_eq!(
+            circuit_fingerprint(TwoOutputCircuit {
+                x: F::from(5u64),
+                z: F::from(6u64),
+                square_first: true
+            })
+            .unwrap(),
+            fingerprint
+        );
+        // Swapping the public inputs changes the fingerprint
+        let swapped_fingerprint = circuit_fingerprint(circuit(false)).unwrap();
+        assert_ne!(swapped_fingerprint, fingerprint);
+        assert!(matches!(
+            VerifyingKeySnapshot::<Bls12_381>::load(&bytes, &schema(), &swapped_fingerprint),
+            Err(VerificationError::ConfigMismatch(_))
+        ));
+
+        let swapped_schema = PublicInputSchema::default()
+            .with_segment("sum", 1)
+            .with_segment("square", 1);
+        assert!(matches!(
+            VerifyingKeySnapshot::<Bls12_381>::load(&bytes, &swapped_schema, &fingerprint),
+            Err(VerificationError::ConfigMismatch(_))
+        ));
+        assert!(matches!(
+            VerifyingKeySnapshot::<Bls12_381>::load(&bytes[1..], &schema(), &fingerprint),
+            Err(VerificationError::SnapshotMalformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_snapshot_validity_window() {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let pk = Groth16::<Bls12_381>::generate_random_parameters_with_reduction(
+            circuit(true),
+            &mut rng,
+        )
+        .unwrap();
+        let fingerprint = circuit_fingerprint(circuit(true)).unwrap();
+        let snapshot = VerifyingKeySnapshot::new(pk.vk.clone(), schema(), fingerprint)
+            .unwrap()
+            .with_validity(100, Some(200))
+            .unwrap();
+
+        // The window survives a serialisation round trip
+        let loaded =
+            VerifyingKeySnapshot::<Bls12_381>::load(&snapshot.to_bytes(), &schema(), &fingerprint)
+                .unwrap();
+        assert_eq!(loaded, snapshot);
+
+        let proof =
+            Groth16::<Bls12_381>::create_random_proof_with_reduction(circuit(true), &pk, &mut rng)
+                .unwrap();
+        let public_input = [F::from(9u64), F::from(7u64)];
+        for (height, is_valid) in [(99, false), (100, true), (199, true), (200, false)] {
+            assert_eq!(loaded.is_valid_at(height), is_valid);
+            assert_eq!(
+                loaded
+                    .verify(&schema(), &proof, &public_input, height)
+                    .is_ok(),
+                is_valid
+            );
+        }
+        assert!(matches!(
+            loaded.verify(&schema(), &proof, &public_input, 200),
+            Err(VerificationError::KeyNotValid(_))
+        ));
+
+        // A key without an end of validity does not expire
+        let snapshot = snapshot.with_validity(100, None).unwrap();
+        assert!(!snapshot.is_valid_at(99));
+        assert!(snapshot.is_valid_at(u32::MAX));
+
+        // Empty windows are refused
+        assert!(matches!(
+            snapshot.clone().with_validity(100, Some(100)),
+            Err(Error::ConfigMismatch(_))
+        ));
+        let mut empty = snapshot;
+        empty.valid_until = Some(50);
+        assert!(matches!(
+            VerifyingKeySnapshot::<Bls12_381>::load(&empty.to_bytes(), &schema(), &fingerprint),
+            Err(VerificationError::SnapshotMalformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_snapshot_schema_length_mismatch() {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let pk = Groth16::<Bls12_381>::generate_random_parameters_with_reduction(
+            circuit(true),
+            &mut rng,
+        )
+        .unwrap();
+        let fingerprint = circuit_fingerprint(circuit(true)).unwrap();
+        let schema = PublicInputSchema::default().with_segment("square", 1);
+
+        assert!(matches!(
+            VerifyingKeySnapshot::new(pk.vk, schema, fingerprint),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+}
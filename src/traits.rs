@@ -1,24 +1,79 @@
+use ark_crypto_primitives::crh::sha256::constraints::DigestVar;
 use ark_ff::{Field, PrimeField};
-use ark_r1cs_std::{alloc::AllocVar, eq::EqGadget, prelude::Boolean, uint8::UInt8};
+use ark_r1cs_std::{
+    alloc::AllocVar, eq::EqGadget, fields::fp::FpVar, prelude::Boolean, uint8::UInt8,
+    uint64::UInt64,
+};
 use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use chain_gang::messages::Tx;
 
-use crate::constraints::tx::{TxVar, TxVarConfig};
+use crate::constraints::{
+    script::ScriptVar,
+    tx::{TxVar, TxVarConfig},
+};
+use crate::error::Error;
 
 /// Serialisation according to Bitcoin software specification for PreSigHash calculation
 pub trait PreSigHashSerialise<F: Field> {
     fn pre_sighash_serialise(&self) -> Result<Vec<UInt8<F>>, SynthesisError>;
 }
 
+/// Number of field elements allocated as public inputs by the R1CS version of a type, i.e., the length of its
+/// conversion into `Vec<F>`. It allows sizing verifying keys and public inputs without synthesising the circuit
+pub trait PublicInputSize<F: PrimeField> {
+    const N_PUBLIC_INPUTS: usize;
+}
+
+/// The field elements allocated by an R1CS variable when it is a public input, i.e., the R1CS version of the
+/// conversion into `Vec<F>` of the corresponding type
+pub trait ToPublicInputGadget<F: PrimeField> {
+    fn to_public_input(&self) -> Result<Vec<FpVar<F>>, SynthesisError>;
+}
+
+/// The data about the spending transaction made available to the predicate by the integrity scheme, see
+/// [BitcoinPredicate::generate_constraints_with_context]. Each field is `None` if the integrity scheme does not
+/// provide it.
+#[derive(Clone, Copy, Default)]
+pub struct SpendingContext<'a, F: PrimeField> {
+    /// The locking script of the output being spent, if the integrity scheme binds it to the tag, see
+    /// [IntegrityScheme::prev_lock_script]
+    pub prev_lock_script: Option<&'a ScriptVar<F>>,
+    /// The amount of the output being spent, if the integrity scheme binds it to the tag, see
+    /// [IntegrityScheme::prev_amount]
+    pub prev_amount: Option<&'a UInt64<F>>,
+    /// The sighash of the spending transaction, if the integrity scheme computes it in the circuit, see
+    /// [IntegrityScheme::enforce_integrity]
+    pub sighash: Option<&'a DigestVar<F>>,
+}
+
 /// Predicate to enforce conditions of the form `C((l_out, u_stx, stx), w) = 1`
 pub trait BitcoinPredicate<F: PrimeField, P: TxVarConfig + Clone> {
-    type LockingData: Clone + Into<Vec<F>>;
-    type UnlockingData: Clone + Into<Vec<F>>;
+    type LockingData: Clone + Into<Vec<F>> + PublicInputSize<F>;
+    type UnlockingData: Clone + Into<Vec<F>> + PublicInputSize<F>;
     type Witness: Clone;
 
     type LockingDataVar: AllocVar<Self::LockingData, F>;
     type UnlockingDataVar: AllocVar<Self::UnlockingData, F>;
     type WitnessVar: AllocVar<Self::Witness, F>;
 
+    /// The name of the predicate, used in diagnostics. By default, the name of the type
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// The number of field elements in the conversion into `Vec<F>` of the locking data. By default,
+    /// [PublicInputSize::N_PUBLIC_INPUTS] of the locking data. It is a method, as the length is only known at
+    /// runtime for some predicates, e.g., [BoxedPredicate](crate::bitcoin_predicates::boxed::BoxedPredicate)
+    fn locking_data_len(&self) -> usize {
+        <Self::LockingData as PublicInputSize<F>>::N_PUBLIC_INPUTS
+    }
+
+    /// The number of field elements in the conversion into `Vec<F>` of the unlocking data, see
+    /// [BitcoinPredicate::locking_data_len]
+    fn unlocking_data_len(&self) -> usize {
+        <Self::UnlockingData as PublicInputSize<F>>::N_PUBLIC_INPUTS
+    }
+
     fn generate_constraints(
         &self,
         cs: ConstraintSystemRef<F>,
@@ -28,6 +83,45 @@ pub trait BitcoinPredicate<F: PrimeField, P: TxVarConfig + Clone> {
         witness: &Self::WitnessVar,
     ) -> Result<Boolean<F>, SynthesisError>;
 
+    /// Version of [BitcoinPredicate::generate_constraints] with access to the data about the spending transaction
+    /// made available by the integrity scheme, see [SpendingContext]. By default, `context` is ignored
+    fn generate_constraints_with_context(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+        _context: &SpendingContext<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        self.generate_constraints(cs, locking_data, unlocking_data, spending_data, witness)
+    }
+
+    /// Evaluate the predicate outside of the circuit, e.g., to check the witness before generating a proof.
+    /// It returns the value [BitcoinPredicate::generate_constraints] would return, and the same errors.
+    /// By default, it returns [Error::NotImplemented]
+    ///
+    /// Native evaluation is not supported by the predicates depending on data which is only available in the
+    /// circuit: the [SpendingContext] (e.g., [SignedBy](crate::bitcoin_predicates::signed_by::SignedBy),
+    /// [MultiSig](crate::bitcoin_predicates::multisig::MultiSig), [Escrow](crate::bitcoin_predicates::escrow::Escrow),
+    /// [Inheritance](crate::bitcoin_predicates::inheritance::Inheritance),
+    /// [Auction](crate::bitcoin_predicates::auction::Auction) and
+    /// [SelfReplicatingScript](crate::bitcoin_predicates::self_replicating_script::SelfReplicatingScript)), or a
+    /// gadget supplied by the caller ([StateTransition](crate::bitcoin_predicates::state_transition::StateTransition)).
+    /// Wrappers and combinators support it if the wrapped predicates do
+    fn evaluate_native(
+        &self,
+        _locking_data: &Self::LockingData,
+        _unlocking_data: &Self::UnlockingData,
+        _tx: &Tx,
+        _witness: &Self::Witness,
+    ) -> Result<bool, Error> {
+        Err(Error::NotImplemented(format!(
+            "Native evaluation of the predicate: {}",
+            self.name()
+        )))
+    }
+
     fn enforce_constraints(
         &self,
         cs: ConstraintSystemRef<F>,
@@ -45,4 +139,67 @@ pub trait BitcoinPredicate<F: PrimeField, P: TxVarConfig + Clone> {
         )?
         .enforce_equal(&Boolean::<F>::TRUE)
     }
+
+    fn enforce_constraints_with_context(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        locking_data: &Self::LockingDataVar,
+        unlocking_data: &Self::UnlockingDataVar,
+        spending_data: &TxVar<F, P>,
+        witness: &Self::WitnessVar,
+        context: &SpendingContext<F>,
+    ) -> Result<(), SynthesisError> {
+        self.generate_constraints_with_context(
+            cs.clone(),
+            locking_data,
+            unlocking_data,
+            spending_data,
+            witness,
+            context,
+        )?
+        .enforce_equal(&Boolean::<F>::TRUE)
+    }
+}
+
+/// Scheme binding the integrity tag, a public input of [RefTxCircuit](crate::reftx::RefTxCircuit), to the spending transaction.
+/// See [scheme](crate::transaction_integrity_gadget::scheme) for the implementations provided by the library.
+pub trait IntegrityScheme<F: PrimeField, P: TxVarConfig + Clone> {
+    /// The integrity tag
+    type Tag: Clone + Default + Into<Vec<F>> + PublicInputSize<F>;
+    /// The private data, other than the spending transaction, the tag commits to
+    type Witness;
+
+    type TagVar: AllocVar<Self::Tag, F>;
+    type WitnessVar: AllocVar<Self::Witness, F>;
+
+    /// The witness allocated when the actual one is missing, e.g., when generating the parameters of the circuit
+    fn default_witness(&self) -> Self::Witness;
+
+    /// Generate a tag
+    fn commit(&self, tx: &Tx, witness: &mut Self::Witness) -> Result<Self::Tag, Error>;
+
+    /// Verify the validity of a tag
+    fn verify(&self, tx: &Tx, witness: &mut Self::Witness, tag: &Self::Tag) -> Result<(), Error>;
+
+    /// Enforce the validity of a tag in the circuit. If the scheme computes the sighash of the spending transaction
+    /// in the circuit, e.g., [SighashIntegrity](crate::transaction_integrity_gadget::scheme::SighashIntegrity),
+    /// it returns it, so that predicates can verify signatures over it without computing it a second time, see
+    /// [BitcoinPredicate::generate_constraints_with_context]
+    fn enforce_integrity(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        tx: &TxVar<F, P>,
+        witness: &mut Self::WitnessVar,
+        tag: &Self::TagVar,
+    ) -> Result<Option<DigestVar<F>>, SynthesisError>;
+
+    /// The locking script of the output being spent, if the tag binds it to the spending transaction
+    fn prev_lock_script<'a>(&self, _witness: &'a Self::WitnessVar) -> Option<&'a ScriptVar<F>> {
+        None
+    }
+
+    /// The amount of the output being spent, if the tag binds it to the spending transaction
+    fn prev_amount<'a>(&self, _witness: &'a Self::WitnessVar) -> Option<&'a UInt64<F>> {
+        None
+    }
 }
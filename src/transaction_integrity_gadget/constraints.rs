@@ -4,13 +4,15 @@ use std::borrow::Borrow;
 use std::marker::PhantomData;
 use std::result::Result;
 
-use ark_crypto_primitives::crh::sha256::constraints::DigestVar;
-use ark_ff::PrimeField;
+use ark_crypto_primitives::crh::sha256::constraints::{DigestVar, Sha256Gadget};
+use ark_ff::{BigInteger, PrimeField};
 use ark_r1cs_std::{
+    R1CSVar,
     alloc::AllocVar,
     eq::EqGadget,
     fields::fp::FpVar,
     prelude::{Boolean, ToBytesGadget},
+    select::CondSelectGadget,
     uint8::UInt8,
     uint64::UInt64,
 };
@@ -21,8 +23,13 @@ use crate::constraints::{
     sighash_cache::SigHashCacheVar,
     tx::{TxVar, TxVarConfig},
 };
-use crate::transaction_integrity_gadget::utils::{get_chunk_size, to_fp_chunks};
-use crate::transaction_integrity_gadget::{TransactionIntegrityConfig, TransactionIntegrityTag};
+use crate::error::Error;
+use crate::transaction_integrity_gadget::utils::{
+    check_prev_lock_script_len, get_chunk_size, to_fp_chunks,
+};
+use crate::transaction_integrity_gadget::{
+    TransactionIntegrityConfig, TransactionIntegrityTag, check_integrity_config,
+};
 
 /// The R1CS version [TransactionIntegrityTag]
 /// It is a a vector because the tag needs to be chunked according to
@@ -48,6 +55,21 @@ impl<F: PrimeField> TransactionIntegrityTagVar<F> {
 
         Ok(result)
     }
+
+    /// Enforce that `self` is equal to `digest`, chunked as the tag
+    pub fn enforce_equal_digest(&self, digest: &DigestVar<F>) -> Result<(), SynthesisError> {
+        let chunk_size = get_chunk_size::<F>();
+        let mut is_valid_tag: Vec<Boolean<F>> = Vec::new();
+        for (public, computed) in self
+            .to_bytes()?
+            .iter()
+            .zip(digest.0.chunks_exact(chunk_size))
+        {
+            is_valid_tag.push(public.is_eq(&computed.to_vec())?);
+        }
+
+        Boolean::<F>::kary_and(&is_valid_tag)?.enforce_equal(&Boolean::<F>::TRUE)
+    }
 }
 
 impl<F: PrimeField> AllocVar<TransactionIntegrityTag, F> for TransactionIntegrityTagVar<F> {
@@ -75,26 +97,58 @@ impl<F: PrimeField> EqGadget<F> for TransactionIntegrityTagVar<F> {
     }
 }
 
+impl<F: PrimeField> R1CSVar<F> for TransactionIntegrityTagVar<F> {
+    type Value = TransactionIntegrityTag;
+
+    fn cs(&self) -> ConstraintSystemRef<F> {
+        self.inner.cs()
+    }
+
+    fn value(&self) -> Result<Self::Value, SynthesisError> {
+        let chunk_size = get_chunk_size::<F>();
+        let mut bytes: Vec<u8> = Vec::with_capacity(32);
+        for fp in self.inner.iter() {
+            bytes.extend_from_slice(&fp.value()?.into_bigint().to_bytes_le()[..chunk_size]);
+        }
+
+        Ok(TransactionIntegrityTag {
+            inner: bytes
+                .try_into()
+                .map_err(|_| SynthesisError::AssignmentMissing)?,
+        })
+    }
+}
+
 impl<F: PrimeField, P: TransactionIntegrityConfig + TxVarConfig + Clone>
     TransactionIntegrityGadget<F, P>
 {
     /// Verify the integrity of a tag
     pub fn verify(
-        _cs: ConstraintSystemRef<F>,
+        cs: ConstraintSystemRef<F>,
         tx: &TxVar<F, P>,
         prev_lock_script: &ScriptVar<F>,
         prev_amount: &UInt64<F>,
         sighash_cache: &mut SigHashCacheVar<F>,
         tag: &TransactionIntegrityTagVar<F>,
     ) -> Result<(), SynthesisError> {
+        Self::verify_and_return(cs, tx, prev_lock_script, prev_amount, sighash_cache, tag)
+            .map(|_| ())
+    }
+
+    /// Verify the integrity of a tag, and return the sighash computed in the circuit, so that it can be reused,
+    /// e.g., to verify a signature over it, without computing it a second time
+    pub fn verify_and_return(
+        _cs: ConstraintSystemRef<F>,
+        tx: &TxVar<F, P>,
+        prev_lock_script: &ScriptVar<F>,
+        prev_amount: &UInt64<F>,
+        sighash_cache: &mut SigHashCacheVar<F>,
+        tag: &TransactionIntegrityTagVar<F>,
+    ) -> Result<DigestVar<F>, SynthesisError> {
+        // Check the invariants of the configuration at compile time
+        const { check_integrity_config::<P>() };
         // Validate data against the configuration
-        assert_eq!(
-            prev_lock_script.0.len(),
-            P::LEN_PREV_LOCK_SCRIPT,
-            "The length of the previous locking script: {} is different from the one set in the parameters: P::LEN_PREV_LOCK_SCRIPT = {}",
-            prev_lock_script.0.len(),
-            P::LEN_PREV_LOCK_SCRIPT
-        );
+        check_prev_lock_script_len(prev_lock_script.0.len(), P::LEN_PREV_LOCK_SCRIPT)?;
         // Compute the tag from `tx`, `prev_lock_script` and `prev_amount`
         let computed_tag: DigestVar<F> = tx.sighash(
             P::N_INPUT,
@@ -103,20 +157,124 @@ impl<F: PrimeField, P: TransactionIntegrityConfig + TxVarConfig + Clone>
             &P::SIGHASH_FLAG,
             sighash_cache,
         )?;
+        tag.enforce_equal_digest(&computed_tag)?;
 
-        let chunk_size = get_chunk_size::<F>();
-        let mut is_valid_tag: Vec<Boolean<F>> = Vec::new();
-        for (public, computed) in tag
-            .to_bytes()?
-            .iter()
-            .zip(computed_tag.0.chunks_exact(chunk_size))
-        {
-            is_valid_tag.push(public.is_eq(&computed.to_vec())?);
+        Ok(computed_tag)
+    }
+
+    /// Verify the integrity of a tag computed with the sighash flag `sighash_flag`, allocated in the circuit,
+    /// instead of `P::SIGHASH_FLAG`. The function enforces that `sighash_flag` is one of `allowed_flags`, so that
+    /// the same circuit covers spends under several sighash modes.
+    ///
+    /// **Note**: the sighash is computed for every flag in `allowed_flags`, sharing `sighash_cache` among them,
+    /// hence the number of constraints grows with the number of allowed flags.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_with_flag(
+        _cs: ConstraintSystemRef<F>,
+        tx: &TxVar<F, P>,
+        prev_lock_script: &ScriptVar<F>,
+        prev_amount: &UInt64<F>,
+        sighash_cache: &mut SigHashCacheVar<F>,
+        sighash_flag: &UInt8<F>,
+        allowed_flags: &[u8],
+        tag: &TransactionIntegrityTagVar<F>,
+    ) -> Result<(), SynthesisError> {
+        // Check the invariants of the configuration at compile time
+        const { check_integrity_config::<P>() };
+        // Validate data against the configuration
+        check_prev_lock_script_len(prev_lock_script.0.len(), P::LEN_PREV_LOCK_SCRIPT)?;
+        if allowed_flags.is_empty() {
+            return Err(Error::ConfigMismatch(
+                "The list of allowed sighash flags is empty".to_string(),
+            )
+            .into());
+        }
+
+        // Compute the tag for each allowed flag, and select the one corresponding to `sighash_flag`
+        let mut is_allowed: Vec<Boolean<F>> = Vec::with_capacity(allowed_flags.len());
+        let mut computed_tag: Vec<UInt8<F>> = vec![UInt8::<F>::constant(0); 32];
+        for flag in allowed_flags.iter() {
+            let is_flag = sighash_flag.is_eq(&UInt8::<F>::constant(*flag))?;
+            let sighash: DigestVar<F> = tx.sighash(
+                P::N_INPUT,
+                prev_lock_script,
+                prev_amount,
+                flag,
+                sighash_cache,
+            )?;
+            computed_tag = sighash
+                .0
+                .iter()
+                .zip(computed_tag.iter())
+                .map(|(t, f)| UInt8::<F>::conditionally_select(&is_flag, t, f))
+                .collect::<Result<Vec<UInt8<F>>, SynthesisError>>()?;
+            is_allowed.push(is_flag);
+        }
+
+        Boolean::<F>::kary_or(&is_allowed)?.enforce_equal(&Boolean::<F>::TRUE)?;
+
+        tag.enforce_equal_digest(&DigestVar(computed_tag))
+    }
+
+    /// Verify the integrity of a salted tag, see
+    /// [TransactionIntegrityScheme::commit_salted](crate::transaction_integrity_gadget::TransactionIntegrityScheme::commit_salted).
+    /// The salt should be allocated as a witness, otherwise the tag is not hiding.
+    pub fn verify_salted(
+        cs: ConstraintSystemRef<F>,
+        tx: &TxVar<F, P>,
+        prev_lock_script: &ScriptVar<F>,
+        prev_amount: &UInt64<F>,
+        salt: &[UInt8<F>],
+        sighash_cache: &mut SigHashCacheVar<F>,
+        tag: &TransactionIntegrityTagVar<F>,
+    ) -> Result<(), SynthesisError> {
+        Self::verify_salted_and_return(
+            cs,
+            tx,
+            prev_lock_script,
+            prev_amount,
+            salt,
+            sighash_cache,
+            tag,
+        )
+        .map(|_| ())
+    }
+
+    /// Verify the integrity of a salted tag, and return the (unsalted) sighash computed in the circuit, see
+    /// [TransactionIntegrityGadget::verify_and_return]
+    pub fn verify_salted_and_return(
+        _cs: ConstraintSystemRef<F>,
+        tx: &TxVar<F, P>,
+        prev_lock_script: &ScriptVar<F>,
+        prev_amount: &UInt64<F>,
+        salt: &[UInt8<F>],
+        sighash_cache: &mut SigHashCacheVar<F>,
+        tag: &TransactionIntegrityTagVar<F>,
+    ) -> Result<DigestVar<F>, SynthesisError> {
+        // Check the invariants of the configuration at compile time
+        const { check_integrity_config::<P>() };
+        // Validate data against the configuration
+        check_prev_lock_script_len(prev_lock_script.0.len(), P::LEN_PREV_LOCK_SCRIPT)?;
+        if salt.len() != 32 {
+            return Err(Error::ConfigMismatch(format!(
+                "The length of the salt: {} is different from 32",
+                salt.len()
+            ))
+            .into());
         }
 
-        Boolean::<F>::kary_and(&is_valid_tag)?.enforce_equal(&Boolean::<F>::TRUE)?;
+        let sighash: DigestVar<F> = tx.sighash(
+            P::N_INPUT,
+            prev_lock_script,
+            prev_amount,
+            &P::SIGHASH_FLAG,
+            sighash_cache,
+        )?;
+        let mut data: Vec<UInt8<F>> = sighash.0.clone();
+        data.extend_from_slice(salt);
+        tag.enforce_equal_digest(&Sha256Gadget::<F>::digest(&data)?)?;
 
-        Ok(())
+        Ok(sighash)
     }
 }
 
@@ -124,7 +282,10 @@ impl<F: PrimeField, P: TransactionIntegrityConfig + TxVarConfig + Clone>
 mod tests {
     use super::*;
     use chain_gang::address::addr_decode;
-    use chain_gang::transaction::sighash::{SIGHASH_ALL, SIGHASH_FORKID, SigHashCache};
+    use chain_gang::transaction::sighash::{
+        SIGHASH_ALL, SIGHASH_ANYONECANPAY, SIGHASH_FORKID, SIGHASH_NONE, SIGHASH_SINGLE,
+        SigHashCache, sighash,
+    };
 
     use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
     use chain_gang::network::Network;
@@ -133,6 +294,7 @@ mod tests {
 
     use ark_bls12_381::Fq as F;
 
+    use ark_ff::ToConstraintField;
     use ark_relations::r1cs::ConstraintSystem;
 
     use chain_gang::script::Script;
@@ -195,7 +357,10 @@ mod tests {
             &prev_lock_script,
             prev_amount_tag,
             &mut cache,
-        );
+        )
+        .unwrap();
+
+        let expected_sighash = tag.inner;
 
         let cs = ConstraintSystem::<F>::new_ref();
         let allocated_tag =
@@ -206,7 +371,7 @@ mod tests {
         let allocated_prev_amount =
             UInt64::<F>::new_input(cs.clone(), || Ok(prev_amount_allocated)).unwrap();
         let mut allocated_cache = SigHashCacheVar::<F>::new();
-        TransactionIntegrityGadget::<F, Config>::verify(
+        let computed_sighash = TransactionIntegrityGadget::<F, Config>::verify_and_return(
             cs.clone(),
             &allocated_tx,
             &allocated_prev_lock_script,
@@ -218,6 +383,8 @@ mod tests {
 
         let is_verified = cs.is_satisfied().unwrap();
         assert!(is_verified);
+        // The returned sighash is the one of the allocated data
+        assert_eq!(computed_sighash.value().unwrap(), expected_sighash.to_vec());
 
         cs
     }
@@ -233,6 +400,291 @@ mod tests {
         test_ti_verify(2600000, 26);
     }
 
+    #[derive(Clone)]
+    struct FlagConfig<const SIGHASH_FLAG: u8>;
+    impl<const SIGHASH_FLAG: u8> TxVarConfig for FlagConfig<SIGHASH_FLAG> {
+        const N_INPUTS: usize = 2;
+        const N_OUTPUTS: usize = 2;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0, 0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19, 0x19];
+    }
+
+    impl<const SIGHASH_FLAG: u8> TransactionIntegrityConfig for FlagConfig<SIGHASH_FLAG> {
+        const N_INPUT: usize = 1;
+        const LEN_PREV_LOCK_SCRIPT: usize = 0x19;
+        const SIGHASH_FLAG: u8 = SIGHASH_FLAG;
+    }
+
+    fn test_ti_verify_with_flag<const SIGHASH_FLAG: u8>() {
+        let prev_lock_script =
+            Script(hex::decode("76a91402b74813b047606b4b3fbdfb1a6e8e053fdb8dab88ac").unwrap());
+        let addr = "mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec";
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        let tx = Tx {
+            version: 2,
+            inputs: (0..2)
+                .map(|i| TxIn {
+                    prev_output: OutPoint {
+                        hash: Hash256::decode(
+                            "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                        )
+                        .unwrap(),
+                        index: i,
+                    },
+                    unlock_script: Script(vec![]),
+                    sequence: i,
+                })
+                .collect(),
+            outputs: vec![
+                TxOut {
+                    satoshis: 100,
+                    lock_script: p2pkh::create_lock_script(&hash160),
+                },
+                TxOut {
+                    satoshis: 259899900,
+                    lock_script: p2pkh::create_lock_script(&hash160),
+                },
+            ],
+            lock_time: 0,
+        };
+
+        let tag = TransactionIntegrityScheme::<FlagConfig<SIGHASH_FLAG>>::commit(
+            &tx,
+            &prev_lock_script,
+            2600000,
+            &mut SigHashCache::new(),
+        )
+        .unwrap();
+        // Cross-check against the native sighash
+        let expected_tag = sighash(
+            &tx,
+            1,
+            &prev_lock_script.0,
+            2600000,
+            SIGHASH_FLAG,
+            &mut SigHashCache::new(),
+        )
+        .unwrap();
+        assert_eq!(tag.inner, expected_tag.0);
+        assert_eq!(
+            TransactionIntegrityScheme::<FlagConfig<SIGHASH_FLAG>>::verify(
+                &tx,
+                &prev_lock_script,
+                2600000,
+                &mut SigHashCache::new(),
+                tag.clone(),
+            ),
+            Ok(())
+        );
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let allocated_tag =
+            TransactionIntegrityTagVar::<F>::new_input(cs.clone(), || Ok(tag)).unwrap();
+        let allocated_tx =
+            TxVar::<F, FlagConfig<SIGHASH_FLAG>>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        let allocated_prev_lock_script =
+            ScriptVar::<F>::new_input(cs.clone(), || Ok(prev_lock_script)).unwrap();
+        let allocated_prev_amount = UInt64::<F>::new_input(cs.clone(), || Ok(2600000)).unwrap();
+        TransactionIntegrityGadget::<F, FlagConfig<SIGHASH_FLAG>>::verify(
+            cs.clone(),
+            &allocated_tx,
+            &allocated_prev_lock_script,
+            &allocated_prev_amount,
+            &mut SigHashCacheVar::<F>::new(),
+            &allocated_tag,
+        )
+        .unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_ti_verify_all_flags() {
+        test_ti_verify_with_flag::<{ SIGHASH_ALL | SIGHASH_FORKID }>();
+        test_ti_verify_with_flag::<{ SIGHASH_NONE | SIGHASH_FORKID }>();
+        test_ti_verify_with_flag::<{ SIGHASH_SINGLE | SIGHASH_FORKID }>();
+        test_ti_verify_with_flag::<{ SIGHASH_ALL | SIGHASH_ANYONECANPAY | SIGHASH_FORKID }>();
+        test_ti_verify_with_flag::<{ SIGHASH_NONE | SIGHASH_ANYONECANPAY | SIGHASH_FORKID }>();
+        test_ti_verify_with_flag::<{ SIGHASH_SINGLE | SIGHASH_ANYONECANPAY | SIGHASH_FORKID }>();
+    }
+
+    fn test_ti_verify_with_flag_var(sighash_flag: u8, allowed_flags: &[u8]) -> bool {
+        let prev_lock_script =
+            Script(hex::decode("76a91402b74813b047606b4b3fbdfb1a6e8e053fdb8dab88ac").unwrap());
+        let addr = "mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec";
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0,
+            }],
+            outputs: vec![
+                TxOut {
+                    satoshis: 100,
+                    lock_script: p2pkh::create_lock_script(&hash160),
+                },
+                TxOut {
+                    satoshis: 259899900,
+                    lock_script: p2pkh::create_lock_script(&hash160),
+                },
+            ],
+            lock_time: 0,
+        };
+
+        let tag = TransactionIntegrityScheme::<Config>::commit_with_flag(
+            &tx,
+            &prev_lock_script,
+            2600000,
+            sighash_flag,
+            &mut SigHashCache::new(),
+        )
+        .unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let allocated_tag =
+            TransactionIntegrityTagVar::<F>::new_input(cs.clone(), || Ok(tag)).unwrap();
+        let allocated_tx = TxVar::<F, Config>::new_input(cs.clone(), || Ok(tx)).unwrap();
+        let allocated_prev_lock_script =
+            ScriptVar::<F>::new_input(cs.clone(), || Ok(prev_lock_script)).unwrap();
+        let allocated_prev_amount = UInt64::<F>::new_input(cs.clone(), || Ok(2600000)).unwrap();
+        let allocated_flag = UInt8::<F>::new_witness(cs.clone(), || Ok(sighash_flag)).unwrap();
+        TransactionIntegrityGadget::<F, Config>::verify_with_flag(
+            cs.clone(),
+            &allocated_tx,
+            &allocated_prev_lock_script,
+            &allocated_prev_amount,
+            &mut SigHashCacheVar::<F>::new(),
+            &allocated_flag,
+            allowed_flags,
+            &allocated_tag,
+        )
+        .unwrap();
+
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_ti_verify_with_flag_var() {
+        let allowed_flags = [
+            SIGHASH_ALL | SIGHASH_FORKID,
+            SIGHASH_SINGLE | SIGHASH_ANYONECANPAY | SIGHASH_FORKID,
+        ];
+        for sighash_flag in allowed_flags {
+            assert!(test_ti_verify_with_flag_var(sighash_flag, &allowed_flags));
+        }
+        // The flag is not allowed
+        assert!(!test_ti_verify_with_flag_var(
+            SIGHASH_NONE | SIGHASH_FORKID,
+            &allowed_flags
+        ));
+    }
+
+    fn test_ti_verify_salted(salt_tag: [u8; 32], salt_allocated: [u8; 32]) -> bool {
+        let prev_lock_script =
+            Script(hex::decode("76a91402b74813b047606b4b3fbdfb1a6e8e053fdb8dab88ac").unwrap());
+        let addr = "mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec";
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        let tx = Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0,
+            }],
+            outputs: vec![
+                TxOut {
+                    satoshis: 100,
+                    lock_script: p2pkh::create_lock_script(&hash160),
+                },
+                TxOut {
+                    satoshis: 259899900,
+                    lock_script: p2pkh::create_lock_script(&hash160),
+                },
+            ],
+            lock_time: 0,
+        };
+
+        let tag = TransactionIntegrityScheme::<Config>::commit_salted(
+            &tx,
+            &prev_lock_script,
+            2600000,
+            &salt_tag,
+            &mut SigHashCache::new(),
+        )
+        .unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let allocated_tag =
+            TransactionIntegrityTagVar::<F>::new_input(cs.clone(), || Ok(tag)).unwrap();
+        let allocated_tx = TxVar::<F, Config>::new_witness(cs.clone(), || Ok(tx)).unwrap();
+        let allocated_prev_lock_script =
+            ScriptVar::<F>::new_witness(cs.clone(), || Ok(prev_lock_script)).unwrap();
+        let allocated_prev_amount = UInt64::<F>::new_witness(cs.clone(), || Ok(2600000)).unwrap();
+        let allocated_salt =
+            Vec::<UInt8<F>>::new_witness(cs.clone(), || Ok(salt_allocated.to_vec())).unwrap();
+        TransactionIntegrityGadget::<F, Config>::verify_salted(
+            cs.clone(),
+            &allocated_tx,
+            &allocated_prev_lock_script,
+            &allocated_prev_amount,
+            &allocated_salt,
+            &mut SigHashCacheVar::<F>::new(),
+            &allocated_tag,
+        )
+        .unwrap();
+
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_ti_verify_salted_is_ok() {
+        assert!(test_ti_verify_salted([42; 32], [42; 32]));
+    }
+
+    #[test]
+    fn test_ti_verify_salted_fails() {
+        assert!(!test_ti_verify_salted([42; 32], [43; 32]));
+    }
+
+    #[test]
+    fn test_tag_value() {
+        let tag = TransactionIntegrityTag {
+            inner: core::array::from_fn(|i| i as u8),
+        };
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tag_var =
+            TransactionIntegrityTagVar::<F>::new_input(cs.clone(), || Ok(tag.clone())).unwrap();
+
+        assert_eq!(tag_var.value().unwrap(), tag);
+    }
+
+    #[test]
+    fn test_tag_public_input() {
+        let tag = TransactionIntegrityTag {
+            inner: core::array::from_fn(|i| i as u8),
+        };
+        let cs = ConstraintSystem::<F>::new_ref();
+        TransactionIntegrityTagVar::<F>::new_input(cs.clone(), || Ok(tag.clone())).unwrap();
+
+        let public_input: Vec<F> = tag.clone().into();
+        assert_eq!(cs.borrow().unwrap().instance_assignment[1..], public_input);
+        assert_eq!(tag.to_field_elements().unwrap(), public_input);
+    }
+
     #[test]
     fn print_constraints() {
         let cs = test_ti_verify(2600000, 2600000);
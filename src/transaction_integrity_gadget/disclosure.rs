@@ -0,0 +1,264 @@
+//! Disclosure of the preimage of a transaction integrity tag
+//!
+//! The preimage is split into annotated fields, so that two parties disputing the verification of a tag can
+//! pinpoint which field of the transaction differs from the one that was committed to.
+use chain_gang::{
+    messages::Tx,
+    script::Script,
+    transaction::sighash::{SIGHASH_FORKID, SigHashCache, sig_hash_preimage},
+    util::sha256d,
+};
+
+use crate::error::Error;
+use crate::transaction_integrity_gadget::{
+    TransactionIntegrityConfig, TransactionIntegrityScheme, TransactionIntegrityTag,
+};
+use crate::util::usize_to_var_int;
+
+/// A field of the preimage of a tag
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TagPreimageField {
+    /// Name of the field
+    pub name: &'static str,
+    /// Offset of the field in the preimage
+    pub offset: usize,
+    /// Bytes of the field
+    pub bytes: Vec<u8>,
+}
+
+/// The preimage of a tag, split into its fields
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TagPreimage {
+    pub fields: Vec<TagPreimageField>,
+}
+
+impl TagPreimage {
+    /// Split the serialised `preimage` into its fields. The lengths of the fields are those of
+    /// the [Message Digest Algorithm](https://github.com/bitcoin-sv/bitcoin-sv/blob/master/doc/abc/replay-protected-sighash.md#digest-algorithm)
+    pub fn new(preimage: &[u8], script_code_len: usize) -> Self {
+        let var_int_len = usize_to_var_int(script_code_len).unwrap().len();
+        let layout: [(&'static str, usize); 11] = [
+            ("version", 4),
+            ("hash_prevouts", 32),
+            ("hash_sequence", 32),
+            ("outpoint", 36),
+            ("script_code_len", var_int_len),
+            ("script_code", script_code_len),
+            ("amount", 8),
+            ("sequence", 4),
+            ("hash_outputs", 32),
+            ("lock_time", 4),
+            ("sighash_type", 4),
+        ];
+
+        let mut fields: Vec<TagPreimageField> = Vec::with_capacity(layout.len());
+        let mut offset: usize = 0;
+        for (name, len) in layout.into_iter() {
+            fields.push(TagPreimageField {
+                name,
+                offset,
+                bytes: preimage[offset..offset + len].to_vec(),
+            });
+            offset += len;
+        }
+
+        Self { fields }
+    }
+
+    /// The serialised preimage
+    pub fn bytes(&self) -> Vec<u8> {
+        self.fields
+            .iter()
+            .flat_map(|field| field.bytes.iter().copied())
+            .collect()
+    }
+
+    /// The tag produced by the preimage
+    pub fn tag(&self) -> TransactionIntegrityTag {
+        TransactionIntegrityTag {
+            inner: sha256d(&self.bytes()).0,
+        }
+    }
+
+    /// The names of the fields in which `self` and `other` differ
+    pub fn diff(&self, other: &Self) -> Vec<&'static str> {
+        self.fields
+            .iter()
+            .zip(other.fields.iter())
+            .filter(|(a, b)| a != b)
+            .map(|(a, _)| a.name)
+            .collect()
+    }
+}
+
+impl<P: TransactionIntegrityConfig> TransactionIntegrityScheme<P> {
+    /// Export the preimage of the tag generated by [TransactionIntegrityScheme::commit]
+    ///
+    /// Returns [Error::ConfigMismatch] if `SIGHASH_FORKID` is not set in `P::SIGHASH_FLAG` or if the data
+    /// does not match the parameters, and [Error::SighashFailed] if the preimage cannot be computed.
+    pub fn disclose(
+        tx: &Tx,
+        prev_lock_script: &Script,
+        prev_amount: u64,
+        sighash_cache: &mut SigHashCache,
+    ) -> Result<TagPreimage, Error> {
+        if P::SIGHASH_FLAG & SIGHASH_FORKID == 0 {
+            return Err(Error::ConfigMismatch(format!(
+                "The sighash flag: {} does not have SIGHASH_FORKID set",
+                P::SIGHASH_FLAG
+            )));
+        }
+        TransactionIntegrityScheme::<P>::check_data(tx, prev_lock_script)?;
+
+        let preimage = sig_hash_preimage(
+            tx,
+            P::N_INPUT,
+            &prev_lock_script.0,
+            prev_amount as i64,
+            P::SIGHASH_FLAG,
+            sighash_cache,
+        )
+        .map_err(|e| Error::SighashFailed(e.to_string()))?;
+
+        Ok(TagPreimage::new(&preimage, prev_lock_script.0.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chain_gang::address::addr_decode;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::network::Network;
+    use chain_gang::script::Script;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::transaction::sighash::{SIGHASH_ALL, SIGHASH_FORKID, SigHashCache};
+    use chain_gang::util::Hash256;
+
+    use crate::error::Error;
+    use crate::transaction_integrity_gadget::{
+        TransactionIntegrityConfig, TransactionIntegrityScheme,
+    };
+
+    struct Config;
+    impl TransactionIntegrityConfig for Config {
+        const N_INPUT: usize = 0;
+        const LEN_PREV_LOCK_SCRIPT: usize = 0x19;
+        const SIGHASH_FLAG: u8 = SIGHASH_ALL | SIGHASH_FORKID;
+    }
+
+    struct NoForkIdConfig;
+    impl TransactionIntegrityConfig for NoForkIdConfig {
+        const N_INPUT: usize = 0;
+        const LEN_PREV_LOCK_SCRIPT: usize = 0x19;
+        const SIGHASH_FLAG: u8 = SIGHASH_ALL;
+    }
+
+    struct OutOfRangeConfig;
+    impl TransactionIntegrityConfig for OutOfRangeConfig {
+        const N_INPUT: usize = 1;
+        const LEN_PREV_LOCK_SCRIPT: usize = 0x19;
+        const SIGHASH_FLAG: u8 = SIGHASH_ALL | SIGHASH_FORKID;
+    }
+
+    fn p2pkh_script(addr: &str) -> Script {
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        p2pkh::create_lock_script(&hash160)
+    }
+
+    fn tx(satoshis: i64, lock_time: u32) -> Tx {
+        Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0,
+            }],
+            outputs: vec![TxOut {
+                satoshis,
+                lock_script: p2pkh_script("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec"),
+            }],
+            lock_time,
+        }
+    }
+
+    #[test]
+    fn test_disclose() {
+        let prev_lock_script = p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz");
+        let disclosed = TransactionIntegrityScheme::<Config>::disclose(
+            &tx(100, 0),
+            &prev_lock_script,
+            1000,
+            &mut SigHashCache::new(),
+        )
+        .unwrap();
+        let tag = TransactionIntegrityScheme::<Config>::commit(
+            &tx(100, 0),
+            &prev_lock_script,
+            1000,
+            &mut SigHashCache::new(),
+        )
+        .unwrap();
+
+        assert_eq!(disclosed.tag(), tag);
+        assert_eq!(disclosed.fields[5].name, "script_code");
+        assert_eq!(disclosed.fields[5].bytes, prev_lock_script.0);
+        assert_eq!(disclosed.fields[6].bytes, 1000u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_diff() {
+        let prev_lock_script = p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz");
+        let disclose = |tx: &Tx, prev_amount: u64| {
+            TransactionIntegrityScheme::<Config>::disclose(
+                tx,
+                &prev_lock_script,
+                prev_amount,
+                &mut SigHashCache::new(),
+            )
+            .unwrap()
+        };
+
+        let committed = disclose(&tx(100, 0), 1000);
+        assert!(committed.diff(&disclose(&tx(100, 0), 1000)).is_empty());
+        assert_eq!(
+            committed.diff(&disclose(&tx(101, 0), 1000)),
+            vec!["hash_outputs"]
+        );
+        assert_eq!(
+            committed.diff(&disclose(&tx(100, 10), 1001)),
+            vec!["amount", "lock_time"]
+        );
+    }
+
+    #[test]
+    fn test_disclose_without_forkid() {
+        let prev_lock_script = p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz");
+        let disclosed = TransactionIntegrityScheme::<NoForkIdConfig>::disclose(
+            &tx(100, 0),
+            &prev_lock_script,
+            1000,
+            &mut SigHashCache::new(),
+        );
+
+        assert!(matches!(disclosed, Err(Error::ConfigMismatch(_))));
+    }
+
+    #[test]
+    fn test_disclose_input_out_of_range() {
+        let prev_lock_script = p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz");
+        let disclosed = TransactionIntegrityScheme::<OutOfRangeConfig>::disclose(
+            &tx(100, 0),
+            &prev_lock_script,
+            1000,
+            &mut SigHashCache::new(),
+        );
+
+        assert!(matches!(disclosed, Err(Error::ConfigMismatch(_))));
+    }
+}
@@ -2,7 +2,8 @@
 
 use std::marker::PhantomData;
 
-use ark_ff::PrimeField;
+use ark_crypto_primitives::crh::{CRHScheme, sha256::Sha256};
+use ark_ff::{PrimeField, ToConstraintField};
 use chain_gang::{
     messages::Tx,
     script::Script,
@@ -10,18 +11,42 @@ use chain_gang::{
 };
 
 pub mod constraints;
+pub mod disclosure;
+pub mod multi_input;
+pub mod poseidon;
+pub mod scheme;
+pub mod txid;
 pub mod utils;
 
+use crate::constraints::tx::{TxVarConfig, check_tx_var_config};
+use crate::error::{Error, VerificationError};
+use crate::traits::PublicInputSize;
+use crate::transaction_integrity_gadget::utils::{get_chunk_size, to_fp_chunks};
+
 /// Configuration of the Transaction Integrity scheme
 pub trait TransactionIntegrityConfig {
     /// The length of the locking script used to construct the sighash
     const LEN_PREV_LOCK_SCRIPT: usize;
     /// The index of the input for which we construct the sighash
     const N_INPUT: usize;
-    /// The sighash flag used to construct the sighash
+    /// The sighash flag used to construct the sighash. All the combinations of the base flags
+    /// `SIGHASH_ALL`, `SIGHASH_NONE` and `SIGHASH_SINGLE` with `SIGHASH_ANYONECANPAY` are supported.
+    /// If `SIGHASH_ANYONECANPAY` is set, the tag only commits to the input at `N_INPUT`
     const SIGHASH_FLAG: u8;
 }
 
+/// Check the invariants of a [TransactionIntegrityConfig] used together with a [TxVarConfig]: the index of the input
+/// for which the sighash is constructed must be smaller than the number of inputs.
+///
+/// The function is `const`, so that the check can be performed at compile time, see [assert_integrity_config](crate::assert_integrity_config).
+pub const fn check_integrity_config<P: TxVarConfig + TransactionIntegrityConfig>() {
+    check_tx_var_config::<P>();
+    assert!(
+        P::N_INPUT < P::N_INPUTS,
+        "P::N_INPUT is larger than the number of inputs P::N_INPUTS"
+    );
+}
+
 /// The Transaction Integrity Scheme
 pub struct TransactionIntegrityScheme<P: TransactionIntegrityConfig> {
     _ti_structure: PhantomData<P>,
@@ -39,51 +64,328 @@ impl PartialEq for TransactionIntegrityTag {
     }
 }
 
+/// The tag is packed into the chunks allocated by [TransactionIntegrityTagVar](constraints::TransactionIntegrityTagVar)
+impl<F: PrimeField> ToConstraintField<F> for TransactionIntegrityTag {
+    fn to_field_elements(&self) -> Option<Vec<F>> {
+        Some(to_fp_chunks(&self.inner))
+    }
+}
+
+impl<F: PrimeField> PublicInputSize<F> for TransactionIntegrityTag {
+    const N_PUBLIC_INPUTS: usize = 32 / get_chunk_size::<F>();
+}
+
 impl<F: PrimeField> From<TransactionIntegrityTag> for Vec<F> {
     fn from(value: TransactionIntegrityTag) -> Self {
-        vec![F::from_le_bytes_mod_order(&value.inner)]
+        to_fp_chunks(&value.inner)
     }
 }
 
 impl<P: TransactionIntegrityConfig> TransactionIntegrityScheme<P> {
     /// Generate a tag
+    ///
+    /// Returns [Error::ConfigMismatch] if the data is incompatible with the configuration, and
+    /// [Error::SighashFailed] if the sighash cannot be computed.
     pub fn commit(
         tx: &Tx,
         prev_lock_script: &Script,
         prev_amount: u64,
         sighash_cache: &mut SigHashCache,
-    ) -> TransactionIntegrityTag {
-        // Validate data against the configuration
-        assert_eq!(
-            prev_lock_script.0.len(),
-            P::LEN_PREV_LOCK_SCRIPT,
-            "The length of the previous locking script: {} is different from the one set in the parameters: P::LEN_PREV_LOCK_SCRIPT = {}",
-            prev_lock_script.0.len(),
-            P::LEN_PREV_LOCK_SCRIPT
-        );
+    ) -> Result<TransactionIntegrityTag, Error> {
+        TransactionIntegrityScheme::<P>::commit_with_flag(
+            tx,
+            prev_lock_script,
+            prev_amount,
+            P::SIGHASH_FLAG,
+            sighash_cache,
+        )
+    }
+
+    /// Generate a tag using `sighash_flag` instead of `P::SIGHASH_FLAG`. The tag can be verified in the circuit with
+    /// [TransactionIntegrityGadget::verify_with_flag](constraints::TransactionIntegrityGadget::verify_with_flag)
+    pub fn commit_with_flag(
+        tx: &Tx,
+        prev_lock_script: &Script,
+        prev_amount: u64,
+        sighash_flag: u8,
+        sighash_cache: &mut SigHashCache,
+    ) -> Result<TransactionIntegrityTag, Error> {
+        TransactionIntegrityScheme::<P>::check_data(tx, prev_lock_script)?;
 
         let sighash = sighash(
             tx,
             P::N_INPUT,
             &prev_lock_script.0,
             prev_amount as i64,
-            P::SIGHASH_FLAG,
+            sighash_flag,
             sighash_cache,
         )
-        .unwrap();
+        .map_err(|e| Error::SighashFailed(e.to_string()))?;
 
-        TransactionIntegrityTag { inner: sighash.0 }
+        Ok(TransactionIntegrityTag { inner: sighash.0 })
+    }
+
+    /// Generate a salted tag: `Sha256(sighash || salt)`. If `salt` is random and kept private, the tag is a
+    /// hiding commitment to the spending transaction: knowing the transaction is not enough to link it to the tag.
+    /// The tag can be verified in the circuit with
+    /// [TransactionIntegrityGadget::verify_salted](constraints::TransactionIntegrityGadget::verify_salted)
+    pub fn commit_salted(
+        tx: &Tx,
+        prev_lock_script: &Script,
+        prev_amount: u64,
+        salt: &[u8; 32],
+        sighash_cache: &mut SigHashCache,
+    ) -> Result<TransactionIntegrityTag, Error> {
+        let sighash = TransactionIntegrityScheme::<P>::commit(
+            tx,
+            prev_lock_script,
+            prev_amount,
+            sighash_cache,
+        )?;
+        let mut data: Vec<u8> = sighash.inner.to_vec();
+        data.extend_from_slice(salt);
+
+        Ok(TransactionIntegrityTag {
+            inner: <Sha256 as CRHScheme>::evaluate(&(), data.as_slice())
+                .unwrap()
+                .try_into()
+                .unwrap(),
+        })
     }
 
     /// Verify the validity of a tag
+    ///
+    /// Returns the errors of [TransactionIntegrityScheme::commit], and [VerificationError::TagMismatch] if the tag
+    /// does not match the data.
     pub fn verify(
         tx: &Tx,
         prev_lock_script: &Script,
         prev_amount: u64,
         sighash_cache: &mut SigHashCache,
         tag: TransactionIntegrityTag,
-    ) -> bool {
-        TransactionIntegrityScheme::<P>::commit(tx, prev_lock_script, prev_amount, sighash_cache)
-            == tag
+    ) -> Result<(), Error> {
+        if TransactionIntegrityScheme::<P>::commit(
+            tx,
+            prev_lock_script,
+            prev_amount,
+            sighash_cache,
+        )? == tag
+        {
+            Ok(())
+        } else {
+            Err(VerificationError::TagMismatch.into())
+        }
+    }
+
+    /// Check that the data is compatible with the configuration
+    fn check_data(tx: &Tx, prev_lock_script: &Script) -> Result<(), Error> {
+        if prev_lock_script.0.len() != P::LEN_PREV_LOCK_SCRIPT {
+            return Err(Error::ConfigMismatch(format!(
+                "The length of the previous locking script: {} is different from the one set in the parameters: P::LEN_PREV_LOCK_SCRIPT = {}",
+                prev_lock_script.0.len(),
+                P::LEN_PREV_LOCK_SCRIPT
+            )));
+        }
+        if P::N_INPUT >= tx.inputs.len() {
+            return Err(Error::ConfigMismatch(format!(
+                "The index of the input: P::N_INPUT = {} is larger than the number of inputs: {}",
+                P::N_INPUT,
+                tx.inputs.len()
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Verify the validity of a salted tag, see [TransactionIntegrityScheme::commit_salted]
+    ///
+    /// Returns the errors of [TransactionIntegrityScheme::commit], and [VerificationError::TagMismatch] if the tag
+    /// does not match the data.
+    pub fn verify_salted(
+        tx: &Tx,
+        prev_lock_script: &Script,
+        prev_amount: u64,
+        salt: &[u8; 32],
+        sighash_cache: &mut SigHashCache,
+        tag: TransactionIntegrityTag,
+    ) -> Result<(), Error> {
+        if TransactionIntegrityScheme::<P>::commit_salted(
+            tx,
+            prev_lock_script,
+            prev_amount,
+            salt,
+            sighash_cache,
+        )? == tag
+        {
+            Ok(())
+        } else {
+            Err(VerificationError::TagMismatch.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chain_gang::address::addr_decode;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::network::Network;
+    use chain_gang::script::Script;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::transaction::sighash::{SIGHASH_ALL, SIGHASH_FORKID, SigHashCache};
+    use chain_gang::util::Hash256;
+
+    use crate::error::{Error, VerificationError};
+
+    use super::{TransactionIntegrityConfig, TransactionIntegrityScheme};
+
+    struct Config;
+    impl TransactionIntegrityConfig for Config {
+        const N_INPUT: usize = 0;
+        const LEN_PREV_LOCK_SCRIPT: usize = 0x19;
+        const SIGHASH_FLAG: u8 = SIGHASH_ALL | SIGHASH_FORKID;
+    }
+
+    fn p2pkh_script(addr: &str) -> Script {
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        p2pkh::create_lock_script(&hash160)
+    }
+
+    fn tx(satoshis: i64) -> Tx {
+        Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0,
+            }],
+            outputs: vec![TxOut {
+                satoshis,
+                lock_script: p2pkh_script("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec"),
+            }],
+            lock_time: 0,
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let prev_lock_script = p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz");
+        let tag = TransactionIntegrityScheme::<Config>::commit(
+            &tx(100),
+            &prev_lock_script,
+            1000,
+            &mut SigHashCache::new(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            TransactionIntegrityScheme::<Config>::verify(
+                &tx(100),
+                &prev_lock_script,
+                1000,
+                &mut SigHashCache::new(),
+                tag.clone()
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            TransactionIntegrityScheme::<Config>::verify(
+                &tx(101),
+                &prev_lock_script,
+                1000,
+                &mut SigHashCache::new(),
+                tag.clone()
+            ),
+            Err(Error::Verification(VerificationError::TagMismatch))
+        );
+        assert!(matches!(
+            TransactionIntegrityScheme::<Config>::verify(
+                &tx(100),
+                &Script(vec![]),
+                1000,
+                &mut SigHashCache::new(),
+                tag
+            ),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_commit_config_mismatch() {
+        // The previous locking script is shorter than the one set in the parameters
+        assert!(matches!(
+            TransactionIntegrityScheme::<Config>::commit(
+                &tx(100),
+                &Script(vec![]),
+                1000,
+                &mut SigHashCache::new()
+            ),
+            Err(Error::ConfigMismatch(_))
+        ));
+        // The input does not exist
+        let mut tx_without_inputs = tx(100);
+        tx_without_inputs.inputs.clear();
+        assert!(matches!(
+            TransactionIntegrityScheme::<Config>::commit(
+                &tx_without_inputs,
+                &p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz"),
+                1000,
+                &mut SigHashCache::new()
+            ),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_salted() {
+        let prev_lock_script = p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz");
+        let salt = [7u8; 32];
+        let tag = TransactionIntegrityScheme::<Config>::commit_salted(
+            &tx(100),
+            &prev_lock_script,
+            1000,
+            &salt,
+            &mut SigHashCache::new(),
+        )
+        .unwrap();
+
+        // The salted tag is not the sighash
+        assert_ne!(
+            tag,
+            TransactionIntegrityScheme::<Config>::commit(
+                &tx(100),
+                &prev_lock_script,
+                1000,
+                &mut SigHashCache::new()
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            TransactionIntegrityScheme::<Config>::verify_salted(
+                &tx(100),
+                &prev_lock_script,
+                1000,
+                &salt,
+                &mut SigHashCache::new(),
+                tag.clone()
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            TransactionIntegrityScheme::<Config>::verify_salted(
+                &tx(100),
+                &prev_lock_script,
+                1000,
+                &[8u8; 32],
+                &mut SigHashCache::new(),
+                tag
+            ),
+            Err(Error::Verification(VerificationError::TagMismatch))
+        );
     }
 }
@@ -0,0 +1,600 @@
+//! Transaction integrity for several inputs of the same transaction: one tag per input
+//!
+//! The tags are computed with the same sighash flag, so the in-circuit verification shares
+//! `hash_prevouts`, `hash_sequence` and `hash_outputs` among the inputs.
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::{AllocVar, AllocationMode},
+    uint64::UInt64,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+use chain_gang::{
+    messages::Tx,
+    script::Script,
+    transaction::sighash::{SigHashCache, sighash},
+};
+
+use crate::constraints::{
+    script::ScriptVar,
+    sighash_cache::SigHashCacheVar,
+    tx::{TxVar, TxVarConfig, check_tx_var_config},
+};
+use crate::error::{Error, VerificationError};
+use crate::traits::PublicInputSize;
+use crate::transaction_integrity_gadget::{
+    TransactionIntegrityTag, constraints::TransactionIntegrityTagVar,
+    utils::check_prev_lock_script_len,
+};
+
+/// Configuration of the multi-input Transaction Integrity scheme
+pub trait MultiInputIntegrityConfig {
+    /// The indices of the inputs for which we construct the sighashes
+    const N_INPUTS_TO_TAG: &[usize];
+    /// The lengths of the locking scripts used to construct the sighashes, one per input in `N_INPUTS_TO_TAG`
+    const LEN_PREV_LOCK_SCRIPTS: &[usize];
+    /// The sighash flag used to construct the sighashes
+    const SIGHASH_FLAG: u8;
+}
+
+/// Check the invariants of a [MultiInputIntegrityConfig] used together with a [TxVarConfig]: there must be
+/// one locking script length per tagged input, and the indices of the tagged inputs must be smaller than the number of inputs.
+///
+/// The function is `const`, so that the check can be performed at compile time.
+pub const fn check_multi_input_integrity_config<P: TxVarConfig + MultiInputIntegrityConfig>() {
+    check_tx_var_config::<P>();
+    assert!(
+        P::N_INPUTS_TO_TAG.len() == P::LEN_PREV_LOCK_SCRIPTS.len(),
+        "P::N_INPUTS_TO_TAG.len() is different from P::LEN_PREV_LOCK_SCRIPTS.len()"
+    );
+    let mut i = 0;
+    while i < P::N_INPUTS_TO_TAG.len() {
+        assert!(
+            P::N_INPUTS_TO_TAG[i] < P::N_INPUTS,
+            "An index in P::N_INPUTS_TO_TAG is larger than the number of inputs P::N_INPUTS"
+        );
+        i += 1;
+    }
+}
+
+/// The tags of the inputs in `P::N_INPUTS_TO_TAG`, used as a single public input of
+/// [RefTxCircuit](crate::reftx::RefTxCircuit) through
+/// [MultiInputIntegrity](crate::transaction_integrity_gadget::scheme::MultiInputIntegrity)
+#[derive(Clone)]
+pub struct MultiInputIntegrityTag<P: MultiInputIntegrityConfig> {
+    pub tags: Vec<TransactionIntegrityTag>,
+    _ti_structure: PhantomData<P>,
+}
+
+impl<P: MultiInputIntegrityConfig> MultiInputIntegrityTag<P> {
+    /// Returns [Error::ConfigMismatch] if the number of tags is different from the number of inputs to tag
+    pub fn new(tags: Vec<TransactionIntegrityTag>) -> Result<Self, Error> {
+        if tags.len() != P::N_INPUTS_TO_TAG.len() {
+            return Err(Error::ConfigMismatch(format!(
+                "The number of tags: {} is different from the number of inputs to tag: {}",
+                tags.len(),
+                P::N_INPUTS_TO_TAG.len()
+            )));
+        }
+        Ok(Self {
+            tags,
+            _ti_structure: PhantomData,
+        })
+    }
+}
+
+impl<P: MultiInputIntegrityConfig> PartialEq for MultiInputIntegrityTag<P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.tags == other.tags
+    }
+}
+
+impl<P: MultiInputIntegrityConfig> Default for MultiInputIntegrityTag<P> {
+    fn default() -> Self {
+        Self {
+            tags: vec![TransactionIntegrityTag::default(); P::N_INPUTS_TO_TAG.len()],
+            _ti_structure: PhantomData,
+        }
+    }
+}
+
+/// The tags are concatenated in the order of `P::N_INPUTS_TO_TAG`
+impl<F: PrimeField, P: MultiInputIntegrityConfig> From<MultiInputIntegrityTag<P>> for Vec<F> {
+    fn from(value: MultiInputIntegrityTag<P>) -> Self {
+        value
+            .tags
+            .into_iter()
+            .flat_map(Into::<Vec<F>>::into)
+            .collect()
+    }
+}
+
+impl<F: PrimeField, P: MultiInputIntegrityConfig> PublicInputSize<F> for MultiInputIntegrityTag<P> {
+    const N_PUBLIC_INPUTS: usize =
+        P::N_INPUTS_TO_TAG.len() * <TransactionIntegrityTag as PublicInputSize<F>>::N_PUBLIC_INPUTS;
+}
+
+/// R1CS version of [MultiInputIntegrityTag]
+pub struct MultiInputIntegrityTagVar<F: PrimeField> {
+    pub tags: Vec<TransactionIntegrityTagVar<F>>,
+}
+
+impl<F: PrimeField, P: MultiInputIntegrityConfig> AllocVar<MultiInputIntegrityTag<P>, F>
+    for MultiInputIntegrityTagVar<F>
+{
+    fn new_variable<T: Borrow<MultiInputIntegrityTag<P>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let data = f()?;
+        let tag: &MultiInputIntegrityTag<P> = data.borrow();
+        Ok(Self {
+            tags: Vec::<TransactionIntegrityTagVar<F>>::new_variable(
+                cs.clone(),
+                || Ok(tag.tags.clone()),
+                mode,
+            )?,
+        })
+    }
+}
+
+/// The multi-input Transaction Integrity Scheme
+pub struct MultiInputIntegrityScheme<P: MultiInputIntegrityConfig> {
+    _ti_structure: PhantomData<P>,
+}
+
+impl<P: MultiInputIntegrityConfig> MultiInputIntegrityScheme<P> {
+    /// Generate the tags of the inputs in `P::N_INPUTS_TO_TAG`. The i-th tag is computed using `prev_lock_scripts[i]`
+    /// and `prev_amounts[i]`
+    ///
+    /// Returns [Error::ConfigMismatch] if the number of previous locking scripts or amounts is different from the
+    /// number of inputs to tag, otherwise the error returned by [MultiInputIntegrityScheme::commit_input].
+    pub fn commit(
+        tx: &Tx,
+        prev_lock_scripts: &[Script],
+        prev_amounts: &[u64],
+        sighash_cache: &mut SigHashCache,
+    ) -> Result<Vec<TransactionIntegrityTag>, Error> {
+        // Validate data against the configuration
+        if prev_lock_scripts.len() != P::N_INPUTS_TO_TAG.len() {
+            return Err(Error::ConfigMismatch(format!(
+                "The number of previous locking scripts: {} is different from the number of inputs to tag: {}",
+                prev_lock_scripts.len(),
+                P::N_INPUTS_TO_TAG.len()
+            )));
+        }
+        if prev_amounts.len() != P::N_INPUTS_TO_TAG.len() {
+            return Err(Error::ConfigMismatch(format!(
+                "The number of previous amounts: {} is different from the number of inputs to tag: {}",
+                prev_amounts.len(),
+                P::N_INPUTS_TO_TAG.len()
+            )));
+        }
+
+        prev_lock_scripts
+            .iter()
+            .zip(prev_amounts.iter())
+            .enumerate()
+            .map(|(position, (prev_lock_script, prev_amount))| {
+                Self::commit_input(tx, position, prev_lock_script, *prev_amount, sighash_cache)
+            })
+            .collect()
+    }
+
+    /// Generate the tag of the input `P::N_INPUTS_TO_TAG[position]`, computed using `prev_lock_script`
+    /// and `prev_amount`
+    ///
+    /// Returns [Error::IndexOutOfBounds] if `position` is not the position of an input to tag or the input is not
+    /// in `tx`, [Error::ConfigMismatch] if the length of `prev_lock_script` is different from the one set in the
+    /// parameters, and [Error::SighashFailed] if the sighash cannot be computed.
+    pub fn commit_input(
+        tx: &Tx,
+        position: usize,
+        prev_lock_script: &Script,
+        prev_amount: u64,
+        sighash_cache: &mut SigHashCache,
+    ) -> Result<TransactionIntegrityTag, Error> {
+        // Validate data against the configuration
+        if position >= P::N_INPUTS_TO_TAG.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "The position: {} is larger than the number of inputs to tag: {}",
+                position,
+                P::N_INPUTS_TO_TAG.len()
+            )));
+        }
+        if P::N_INPUTS_TO_TAG[position] >= tx.inputs.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "The input to tag: {} is larger than the number of inputs: {}",
+                P::N_INPUTS_TO_TAG[position],
+                tx.inputs.len()
+            )));
+        }
+        check_prev_lock_script_len(prev_lock_script.0.len(), P::LEN_PREV_LOCK_SCRIPTS[position])?;
+
+        let sighash = sighash(
+            tx,
+            P::N_INPUTS_TO_TAG[position],
+            &prev_lock_script.0,
+            prev_amount as i64,
+            P::SIGHASH_FLAG,
+            sighash_cache,
+        )
+        .map_err(|e| Error::SighashFailed(e.to_string()))?;
+
+        Ok(TransactionIntegrityTag { inner: sighash.0 })
+    }
+
+    /// Verify the validity of the tags
+    ///
+    /// Returns [VerificationError::ConfigMismatch] if the data is incompatible with the configuration, and
+    /// [VerificationError::TagMismatch] if a tag does not match the data.
+    pub fn verify(
+        tx: &Tx,
+        prev_lock_scripts: &[Script],
+        prev_amounts: &[u64],
+        sighash_cache: &mut SigHashCache,
+        tags: &[TransactionIntegrityTag],
+    ) -> Result<(), VerificationError> {
+        let n_tags = P::N_INPUTS_TO_TAG.len();
+        if prev_lock_scripts.len() != n_tags || prev_amounts.len() != n_tags || tags.len() != n_tags
+        {
+            return Err(VerificationError::ConfigMismatch(format!(
+                "The number of previous locking scripts: {}, previous amounts: {} and tags: {} must be equal to the number of inputs to tag: {}",
+                prev_lock_scripts.len(),
+                prev_amounts.len(),
+                tags.len(),
+                n_tags
+            )));
+        }
+        if prev_lock_scripts
+            .iter()
+            .zip(P::LEN_PREV_LOCK_SCRIPTS.iter())
+            .any(|(script, len)| script.0.len() != *len)
+        {
+            return Err(VerificationError::ConfigMismatch(
+                "The lengths of the previous locking scripts are different from the ones set in the parameters".to_string(),
+            ));
+        }
+        if P::N_INPUTS_TO_TAG
+            .iter()
+            .any(|n_input| *n_input >= tx.inputs.len())
+        {
+            return Err(VerificationError::ConfigMismatch(format!(
+                "An index in P::N_INPUTS_TO_TAG is larger than the number of inputs: {}",
+                tx.inputs.len()
+            )));
+        }
+
+        let computed_tags = MultiInputIntegrityScheme::<P>::commit(
+            tx,
+            prev_lock_scripts,
+            prev_amounts,
+            sighash_cache,
+        )
+        .map_err(|error| VerificationError::ConfigMismatch(error.to_string()))?;
+        if computed_tags == tags {
+            Ok(())
+        } else {
+            Err(VerificationError::TagMismatch)
+        }
+    }
+}
+
+/// The gadget version of [MultiInputIntegrityScheme]
+pub struct MultiInputIntegrityGadget<F: PrimeField, P: MultiInputIntegrityConfig> {
+    _ti_structure: PhantomData<P>,
+    _field: PhantomData<F>,
+}
+
+impl<F: PrimeField, P: MultiInputIntegrityConfig + TxVarConfig + Clone>
+    MultiInputIntegrityGadget<F, P>
+{
+    /// Verify the integrity of the tags of the inputs in `P::N_INPUTS_TO_TAG`. The i-th tag is verified
+    /// against `prev_lock_scripts[i]` and `prev_amounts[i]`. The sighash computations share `sighash_cache`.
+    pub fn verify(
+        cs: ConstraintSystemRef<F>,
+        tx: &TxVar<F, P>,
+        prev_lock_scripts: &[ScriptVar<F>],
+        prev_amounts: &[UInt64<F>],
+        sighash_cache: &mut SigHashCacheVar<F>,
+        tags: &[TransactionIntegrityTagVar<F>],
+    ) -> Result<(), SynthesisError> {
+        // Check the invariants of the configuration at compile time
+        const { check_multi_input_integrity_config::<P>() };
+        // Validate data against the configuration
+        let n_tags = P::N_INPUTS_TO_TAG.len();
+        if prev_lock_scripts.len() != n_tags || prev_amounts.len() != n_tags || tags.len() != n_tags
+        {
+            return Err(Error::ConfigMismatch(format!(
+                "The number of previous locking scripts: {}, previous amounts: {} and tags: {} must be equal to the number of inputs to tag: {}",
+                prev_lock_scripts.len(),
+                prev_amounts.len(),
+                tags.len(),
+                n_tags
+            ))
+            .into());
+        }
+
+        for (position, tag) in tags.iter().enumerate() {
+            Self::verify_input(
+                cs.clone(),
+                tx,
+                position,
+                &prev_lock_scripts[position],
+                &prev_amounts[position],
+                sighash_cache,
+                tag,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Verify the integrity of the tag of the input `P::N_INPUTS_TO_TAG[position]` against `prev_lock_script`
+    /// and `prev_amount`
+    pub fn verify_input(
+        _cs: ConstraintSystemRef<F>,
+        tx: &TxVar<F, P>,
+        position: usize,
+        prev_lock_script: &ScriptVar<F>,
+        prev_amount: &UInt64<F>,
+        sighash_cache: &mut SigHashCacheVar<F>,
+        tag: &TransactionIntegrityTagVar<F>,
+    ) -> Result<(), SynthesisError> {
+        // Check the invariants of the configuration at compile time
+        const { check_multi_input_integrity_config::<P>() };
+        // Validate data against the configuration
+        if position >= P::N_INPUTS_TO_TAG.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "The position: {} is larger than the number of inputs to tag: {}",
+                position,
+                P::N_INPUTS_TO_TAG.len()
+            ))
+            .into());
+        }
+        check_prev_lock_script_len(prev_lock_script.0.len(), P::LEN_PREV_LOCK_SCRIPTS[position])?;
+
+        let computed_tag = tx.sighash(
+            P::N_INPUTS_TO_TAG[position],
+            prev_lock_script,
+            prev_amount,
+            &P::SIGHASH_FLAG,
+            sighash_cache,
+        )?;
+        tag.enforce_equal_digest(&computed_tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::{alloc::AllocVar, uint64::UInt64};
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::address::addr_decode;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::network::Network;
+    use chain_gang::script::Script;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::transaction::sighash::{SIGHASH_ALL, SIGHASH_FORKID, SigHashCache};
+    use chain_gang::util::Hash256;
+
+    use crate::constraints::{
+        script::ScriptVar,
+        sighash_cache::SigHashCacheVar,
+        tx::{TxVar, TxVarConfig},
+    };
+    use crate::error::{Error, VerificationError};
+    use crate::transaction_integrity_gadget::constraints::TransactionIntegrityTagVar;
+    use crate::transaction_integrity_gadget::scheme::MultiInputSighashIntegrity;
+
+    use super::{MultiInputIntegrityConfig, MultiInputIntegrityGadget, MultiInputIntegrityScheme};
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 3;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0, 0, 0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    impl MultiInputIntegrityConfig for Config {
+        const N_INPUTS_TO_TAG: &[usize] = &[0, 2];
+        const LEN_PREV_LOCK_SCRIPTS: &[usize] = &[0x19, 0x00];
+        const SIGHASH_FLAG: u8 = SIGHASH_ALL | SIGHASH_FORKID;
+    }
+
+    fn p2pkh_script(addr: &str) -> Script {
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        p2pkh::create_lock_script(&hash160)
+    }
+
+    fn tx() -> Tx {
+        Tx {
+            version: 2,
+            inputs: (0..3)
+                .map(|i| TxIn {
+                    prev_output: OutPoint {
+                        hash: Hash256::decode(
+                            "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                        )
+                        .unwrap(),
+                        index: i,
+                    },
+                    unlock_script: Script(vec![]),
+                    sequence: 0xffffffff,
+                })
+                .collect(),
+            outputs: vec![TxOut {
+                satoshis: 100,
+                lock_script: p2pkh_script("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec"),
+            }],
+            lock_time: 0,
+        }
+    }
+
+    fn test_multi_input_verify(
+        prev_amounts_tag: [u64; 2],
+        prev_amounts_allocated: [u64; 2],
+    ) -> bool {
+        let prev_lock_scripts = vec![
+            p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz"),
+            Script(vec![]),
+        ];
+        let tags = MultiInputIntegrityScheme::<Config>::commit(
+            &tx(),
+            &prev_lock_scripts,
+            &prev_amounts_tag,
+            &mut SigHashCache::new(),
+        )
+        .unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_witness(cs.clone(), || Ok(tx())).unwrap();
+        let tag_vars: Vec<TransactionIntegrityTagVar<F>> = tags
+            .into_iter()
+            .map(|tag| TransactionIntegrityTagVar::<F>::new_input(cs.clone(), || Ok(tag)).unwrap())
+            .collect();
+        let prev_lock_script_vars: Vec<ScriptVar<F>> = prev_lock_scripts
+            .into_iter()
+            .map(|script| ScriptVar::<F>::new_witness(cs.clone(), || Ok(script)).unwrap())
+            .collect();
+        let prev_amount_vars: Vec<UInt64<F>> = prev_amounts_allocated
+            .into_iter()
+            .map(|amount| UInt64::<F>::new_witness(cs.clone(), || Ok(amount)).unwrap())
+            .collect();
+        let mut cache_var = SigHashCacheVar::<F>::new();
+        MultiInputIntegrityGadget::<F, Config>::verify(
+            cs.clone(),
+            &tx_var,
+            &prev_lock_script_vars,
+            &prev_amount_vars,
+            &mut cache_var,
+            &tag_vars,
+        )
+        .unwrap();
+
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_multi_input_verify_is_ok() {
+        assert!(test_multi_input_verify([1000, 2000], [1000, 2000]));
+    }
+
+    #[test]
+    fn test_multi_input_verify_fails() {
+        assert!(!test_multi_input_verify([1000, 2000], [1000, 2001]));
+    }
+
+    #[test]
+    fn test_multi_input_native_verify() {
+        let prev_lock_scripts = vec![
+            p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz"),
+            Script(vec![]),
+        ];
+        let tags = MultiInputIntegrityScheme::<Config>::commit(
+            &tx(),
+            &prev_lock_scripts,
+            &[1000, 2000],
+            &mut SigHashCache::new(),
+        )
+        .unwrap();
+
+        assert_eq!(tags.len(), 2);
+        assert_ne!(tags[0], tags[1]);
+        assert_eq!(
+            MultiInputIntegrityScheme::<Config>::verify(
+                &tx(),
+                &prev_lock_scripts,
+                &[1000, 2000],
+                &mut SigHashCache::new(),
+                &tags
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            MultiInputIntegrityScheme::<Config>::verify(
+                &tx(),
+                &prev_lock_scripts,
+                &[1000, 2001],
+                &mut SigHashCache::new(),
+                &tags
+            ),
+            Err(VerificationError::TagMismatch)
+        );
+        assert!(matches!(
+            MultiInputIntegrityScheme::<Config>::verify(
+                &tx(),
+                &prev_lock_scripts[..1],
+                &[1000],
+                &mut SigHashCache::new(),
+                &tags[..1]
+            ),
+            Err(VerificationError::ConfigMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_multi_input_commit_errors() {
+        let prev_lock_script = p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz");
+        assert!(matches!(
+            MultiInputIntegrityScheme::<Config>::commit(
+                &tx(),
+                &[prev_lock_script.clone()],
+                &[1000],
+                &mut SigHashCache::new(),
+            ),
+            Err(Error::ConfigMismatch(_))
+        ));
+        assert!(matches!(
+            MultiInputIntegrityScheme::<Config>::commit_input(
+                &tx(),
+                2,
+                &prev_lock_script,
+                1000,
+                &mut SigHashCache::new(),
+            ),
+            Err(Error::IndexOutOfBounds(_))
+        ));
+        // The locking script of the input 2 has length zero
+        assert!(matches!(
+            MultiInputIntegrityScheme::<Config>::commit_input(
+                &tx(),
+                1,
+                &prev_lock_script,
+                1000,
+                &mut SigHashCache::new(),
+            ),
+            Err(Error::ConfigMismatch(_))
+        ));
+        // The transaction has a single input, so the input 2 cannot be tagged
+        let mut short_tx = tx();
+        short_tx.inputs.truncate(1);
+        assert!(matches!(
+            MultiInputIntegrityScheme::<Config>::commit_input(
+                &short_tx,
+                1,
+                &Script(vec![]),
+                1000,
+                &mut SigHashCache::new(),
+            ),
+            Err(Error::IndexOutOfBounds(_))
+        ));
+    }
+
+    #[test]
+    fn test_scheme_position_out_of_bounds() {
+        assert!(MultiInputSighashIntegrity::<Config>::new(1).is_ok());
+        assert!(matches!(
+            MultiInputSighashIntegrity::<Config>::new(2),
+            Err(Error::IndexOutOfBounds(_))
+        ));
+    }
+}
@@ -0,0 +1,447 @@
+//! Transaction integrity scheme committing to the spending data with Poseidon
+//!
+//! The tag is the Poseidon hash of the serialisation of the spending transaction, the previous locking script
+//! and the previous amount. Contrary to [TransactionIntegrityScheme](super::TransactionIntegrityScheme),
+//! the tag is not a Bitcoin sighash, but it is much cheaper to verify in the circuit, as no SHA256 is computed.
+//!
+//! **Note**: the tag commits to the whole spending transaction, hence `P::SIGHASH_FLAG` is ignored.
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use ark_crypto_primitives::sponge::{
+    CryptographicSponge,
+    constraints::CryptographicSpongeVar,
+    poseidon::{
+        PoseidonConfig, PoseidonSponge, constraints::PoseidonSpongeVar, find_poseidon_ark_and_mds,
+    },
+};
+use ark_ff::{PrimeField, ToConstraintField};
+use ark_r1cs_std::{
+    R1CSVar,
+    alloc::{AllocVar, AllocationMode},
+    convert::{ToBytesGadget, ToConstraintFieldGadget},
+    eq::EqGadget,
+    fields::fp::FpVar,
+    prelude::Boolean,
+    uint8::UInt8,
+    uint32::UInt32,
+    uint64::UInt64,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+use chain_gang::{messages::Tx, script::Script, util::Serializable};
+
+use crate::constraints::{
+    script::ScriptVar,
+    tx::{TxVar, TxVarConfig},
+};
+use crate::error::{Error, VerificationError};
+use crate::traits::PublicInputSize;
+use crate::transaction_integrity_gadget::{
+    TransactionIntegrityConfig, check_integrity_config, utils::check_prev_lock_script_len,
+};
+
+/// Poseidon parameters used by [PoseidonIntegrityScheme]: rate 2, capacity 1, `alpha = 17`,
+/// 8 full rounds and 31 partial rounds
+pub fn poseidon_config<F: PrimeField>() -> PoseidonConfig<F> {
+    let (full_rounds, partial_rounds, alpha, rate) = (8, 31, 17, 2);
+    let (ark, mds) = find_poseidon_ark_and_mds::<F>(
+        F::MODULUS_BIT_SIZE as u64,
+        rate,
+        full_rounds,
+        partial_rounds,
+        0,
+    );
+    PoseidonConfig::new(
+        full_rounds as usize,
+        partial_rounds as usize,
+        alpha,
+        mds,
+        ark,
+        rate,
+        1,
+    )
+}
+
+/// The Poseidon Transaction Integrity Tag
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct PoseidonIntegrityTag<F: PrimeField> {
+    pub inner: F,
+}
+
+impl<F: PrimeField> ToConstraintField<F> for PoseidonIntegrityTag<F> {
+    fn to_field_elements(&self) -> Option<Vec<F>> {
+        Some(vec![self.inner])
+    }
+}
+
+impl<F: PrimeField> From<PoseidonIntegrityTag<F>> for Vec<F> {
+    fn from(value: PoseidonIntegrityTag<F>) -> Self {
+        vec![value.inner]
+    }
+}
+
+impl<F: PrimeField> PublicInputSize<F> for PoseidonIntegrityTag<F> {
+    const N_PUBLIC_INPUTS: usize = 1;
+}
+
+/// The Poseidon Transaction Integrity Scheme
+pub struct PoseidonIntegrityScheme<F: PrimeField, P: TransactionIntegrityConfig> {
+    _ti_structure: PhantomData<P>,
+    _field: PhantomData<F>,
+}
+
+impl<F: PrimeField, P: TransactionIntegrityConfig> PoseidonIntegrityScheme<F, P> {
+    /// Serialise the data committed to by the tag: `P::N_INPUT` (4 bytes, little endian), `tx`, `prev_lock_script`
+    /// (without length prefix) and `prev_amount` (8 bytes, little endian)
+    fn serialise(tx: &Tx, prev_lock_script: &Script, prev_amount: u64) -> Vec<u8> {
+        let mut ser: Vec<u8> = (P::N_INPUT as u32).to_le_bytes().to_vec();
+        tx.write(&mut ser).unwrap();
+        ser.extend_from_slice(&prev_lock_script.0);
+        ser.extend_from_slice(&prev_amount.to_le_bytes());
+        ser
+    }
+
+    /// Generate a tag
+    ///
+    /// Returns [Error::ConfigMismatch] if the data is incompatible with the configuration.
+    pub fn commit(
+        tx: &Tx,
+        prev_lock_script: &Script,
+        prev_amount: u64,
+        poseidon_config: &PoseidonConfig<F>,
+    ) -> Result<PoseidonIntegrityTag<F>, Error> {
+        Self::check_data(tx, prev_lock_script)?;
+
+        let elements: Vec<F> = Self::serialise(tx, prev_lock_script, prev_amount)
+            .to_field_elements()
+            .unwrap();
+        let mut sponge = PoseidonSponge::<F>::new(poseidon_config);
+        sponge.absorb(&elements);
+
+        Ok(PoseidonIntegrityTag {
+            inner: sponge.squeeze_field_elements::<F>(1)[0],
+        })
+    }
+
+    /// Verify the validity of a tag
+    ///
+    /// Returns [VerificationError::ConfigMismatch] if the data is incompatible with the configuration, and
+    /// [VerificationError::TagMismatch] if the tag does not match the data.
+    pub fn verify(
+        tx: &Tx,
+        prev_lock_script: &Script,
+        prev_amount: u64,
+        poseidon_config: &PoseidonConfig<F>,
+        tag: PoseidonIntegrityTag<F>,
+    ) -> Result<(), VerificationError> {
+        let computed_tag = Self::commit(tx, prev_lock_script, prev_amount, poseidon_config)
+            .map_err(|error| VerificationError::ConfigMismatch(error.to_string()))?;
+        if computed_tag == tag {
+            Ok(())
+        } else {
+            Err(VerificationError::TagMismatch)
+        }
+    }
+
+    /// Check that the data is compatible with the configuration
+    fn check_data(tx: &Tx, prev_lock_script: &Script) -> Result<(), Error> {
+        check_prev_lock_script_len(prev_lock_script.0.len(), P::LEN_PREV_LOCK_SCRIPT)?;
+        if P::N_INPUT >= tx.inputs.len() {
+            return Err(Error::ConfigMismatch(format!(
+                "The index of the input: P::N_INPUT = {} is larger than the number of inputs: {}",
+                P::N_INPUT,
+                tx.inputs.len()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// The R1CS version of [PoseidonIntegrityTag]
+pub struct PoseidonIntegrityTagVar<F: PrimeField> {
+    pub inner: FpVar<F>,
+}
+
+impl<F: PrimeField> AllocVar<PoseidonIntegrityTag<F>, F> for PoseidonIntegrityTagVar<F> {
+    fn new_variable<T: Borrow<PoseidonIntegrityTag<F>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        Ok(Self {
+            inner: FpVar::<F>::new_variable(cs, || f().map(|tag| tag.borrow().inner), mode)?,
+        })
+    }
+}
+
+impl<F: PrimeField> EqGadget<F> for PoseidonIntegrityTagVar<F> {
+    fn is_eq(&self, other: &Self) -> Result<Boolean<F>, SynthesisError> {
+        self.inner.is_eq(&other.inner)
+    }
+}
+
+impl<F: PrimeField> R1CSVar<F> for PoseidonIntegrityTagVar<F> {
+    type Value = PoseidonIntegrityTag<F>;
+
+    fn cs(&self) -> ConstraintSystemRef<F> {
+        self.inner.cs()
+    }
+
+    fn value(&self) -> Result<Self::Value, SynthesisError> {
+        Ok(PoseidonIntegrityTag {
+            inner: self.inner.value()?,
+        })
+    }
+}
+
+/// The gadget version of [PoseidonIntegrityScheme]
+pub struct PoseidonIntegrityGadget<F: PrimeField, P: TransactionIntegrityConfig> {
+    _ti_structure: PhantomData<P>,
+    _field: PhantomData<F>,
+}
+
+impl<F: PrimeField, P: TransactionIntegrityConfig + TxVarConfig + Clone>
+    PoseidonIntegrityGadget<F, P>
+{
+    /// Verify the integrity of a tag
+    pub fn verify(
+        cs: ConstraintSystemRef<F>,
+        tx: &TxVar<F, P>,
+        prev_lock_script: &ScriptVar<F>,
+        prev_amount: &UInt64<F>,
+        poseidon_config: &PoseidonConfig<F>,
+        tag: &PoseidonIntegrityTagVar<F>,
+    ) -> Result<(), SynthesisError> {
+        // Check the invariants of the configuration at compile time
+        const { check_integrity_config::<P>() };
+        // Validate data against the configuration
+        check_prev_lock_script_len(prev_lock_script.0.len(), P::LEN_PREV_LOCK_SCRIPT)?;
+
+        // Serialise the data as in [PoseidonIntegrityScheme::serialise]
+        let mut ser: Vec<UInt8<F>> = UInt32::<F>::constant(P::N_INPUT as u32).to_bytes_le()?;
+        ser.extend_from_slice(tx.to_bytes_le()?.as_slice());
+        ser.extend_from_slice(prev_lock_script.to_bytes_le()?.as_slice());
+        ser.extend_from_slice(prev_amount.to_bytes_le()?.as_slice());
+
+        let mut sponge = PoseidonSpongeVar::<F>::new(cs, poseidon_config);
+        sponge.absorb(&ser.to_constraint_field()?)?;
+        let computed_tag = sponge.squeeze_field_elements(1)?;
+
+        tag.inner.enforce_equal(&computed_tag[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::{alloc::AllocVar, uint64::UInt64};
+    use ark_relations::r1cs::{ConstraintSystem, ConstraintSystemRef};
+    use chain_gang::address::addr_decode;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::network::Network;
+    use chain_gang::script::Script;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::transaction::sighash::{SIGHASH_ALL, SIGHASH_FORKID, SigHashCache};
+    use chain_gang::util::Hash256;
+
+    use crate::constraints::{
+        script::ScriptVar,
+        sighash_cache::SigHashCacheVar,
+        tx::{TxVar, TxVarConfig},
+    };
+    use crate::error::{Error, VerificationError};
+    use crate::transaction_integrity_gadget::{
+        TransactionIntegrityConfig, TransactionIntegrityScheme,
+        constraints::{TransactionIntegrityGadget, TransactionIntegrityTagVar},
+    };
+
+    use super::{
+        PoseidonIntegrityGadget, PoseidonIntegrityScheme, PoseidonIntegrityTagVar, poseidon_config,
+    };
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    impl TransactionIntegrityConfig for Config {
+        const N_INPUT: usize = 0;
+        const LEN_PREV_LOCK_SCRIPT: usize = 0x19;
+        const SIGHASH_FLAG: u8 = SIGHASH_ALL | SIGHASH_FORKID;
+    }
+
+    fn p2pkh_script(addr: &str) -> Script {
+        let hash160 = addr_decode(addr, Network::BSV_Testnet).unwrap().0;
+        p2pkh::create_lock_script(&hash160)
+    }
+
+    fn tx(satoshis: i64) -> Tx {
+        Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![TxOut {
+                satoshis,
+                lock_script: p2pkh_script("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec"),
+            }],
+            lock_time: 0,
+        }
+    }
+
+    fn test_poseidon_verify(
+        satoshis_tag: i64,
+        satoshis_allocated: i64,
+    ) -> (bool, ConstraintSystemRef<F>) {
+        let prev_lock_script = p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz");
+        let config = poseidon_config::<F>();
+        let tag = PoseidonIntegrityScheme::<F, Config>::commit(
+            &tx(satoshis_tag),
+            &prev_lock_script,
+            1000,
+            &config,
+        )
+        .unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tag_var = PoseidonIntegrityTagVar::<F>::new_input(cs.clone(), || Ok(tag)).unwrap();
+        let tx_var =
+            TxVar::<F, Config>::new_witness(cs.clone(), || Ok(tx(satoshis_allocated))).unwrap();
+        let prev_lock_script_var =
+            ScriptVar::<F>::new_witness(cs.clone(), || Ok(prev_lock_script)).unwrap();
+        let prev_amount_var = UInt64::<F>::new_witness(cs.clone(), || Ok(1000)).unwrap();
+        PoseidonIntegrityGadget::<F, Config>::verify(
+            cs.clone(),
+            &tx_var,
+            &prev_lock_script_var,
+            &prev_amount_var,
+            &config,
+            &tag_var,
+        )
+        .unwrap();
+
+        (cs.is_satisfied().unwrap(), cs)
+    }
+
+    #[test]
+    fn test_poseidon_verify_is_ok() {
+        assert!(test_poseidon_verify(100, 100).0);
+    }
+
+    #[test]
+    fn test_poseidon_verify_fails() {
+        assert!(!test_poseidon_verify(100, 101).0);
+    }
+
+    #[test]
+    fn test_poseidon_native_verify() {
+        let prev_lock_script = p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz");
+        let config = poseidon_config::<F>();
+        let tag = PoseidonIntegrityScheme::<F, Config>::commit(
+            &tx(100),
+            &prev_lock_script,
+            1000,
+            &config,
+        )
+        .unwrap();
+
+        assert_eq!(
+            PoseidonIntegrityScheme::<F, Config>::verify(
+                &tx(100),
+                &prev_lock_script,
+                1000,
+                &config,
+                tag.clone()
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            PoseidonIntegrityScheme::<F, Config>::verify(
+                &tx(100),
+                &prev_lock_script,
+                1001,
+                &config,
+                tag.clone()
+            ),
+            Err(VerificationError::TagMismatch)
+        );
+        assert!(matches!(
+            PoseidonIntegrityScheme::<F, Config>::verify(
+                &tx(100),
+                &Script(vec![]),
+                1000,
+                &config,
+                tag
+            ),
+            Err(VerificationError::ConfigMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_poseidon_commit_config_mismatch() {
+        let config = poseidon_config::<F>();
+        assert!(matches!(
+            PoseidonIntegrityScheme::<F, Config>::commit(&tx(100), &Script(vec![]), 1000, &config),
+            Err(Error::ConfigMismatch(_))
+        ));
+        let mut no_inputs_tx = tx(100);
+        no_inputs_tx.inputs.clear();
+        assert!(matches!(
+            PoseidonIntegrityScheme::<F, Config>::commit(
+                &no_inputs_tx,
+                &p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz"),
+                1000,
+                &config
+            ),
+            Err(Error::ConfigMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_poseidon_is_cheaper_than_sighash() {
+        let (_, poseidon_cs) = test_poseidon_verify(100, 100);
+
+        let prev_lock_script = p2pkh_script("mzXd2pQG2dbgK9trYAZcpKycWDEfjVbeMz");
+        let tag = TransactionIntegrityScheme::<Config>::commit(
+            &tx(100),
+            &prev_lock_script,
+            1000,
+            &mut SigHashCache::new(),
+        )
+        .unwrap();
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tag_var = TransactionIntegrityTagVar::<F>::new_input(cs.clone(), || Ok(tag)).unwrap();
+        let tx_var = TxVar::<F, Config>::new_witness(cs.clone(), || Ok(tx(100))).unwrap();
+        let prev_lock_script_var =
+            ScriptVar::<F>::new_witness(cs.clone(), || Ok(prev_lock_script)).unwrap();
+        let prev_amount_var = UInt64::<F>::new_witness(cs.clone(), || Ok(1000)).unwrap();
+        TransactionIntegrityGadget::<F, Config>::verify(
+            cs.clone(),
+            &tx_var,
+            &prev_lock_script_var,
+            &prev_amount_var,
+            &mut SigHashCacheVar::<F>::new(),
+            &tag_var,
+        )
+        .unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+        assert!(poseidon_cs.num_constraints() < cs.num_constraints());
+    }
+}
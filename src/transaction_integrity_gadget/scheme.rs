@@ -0,0 +1,732 @@
+//! Implementations of [IntegrityScheme] for the schemes of the library
+//!
+//! - [SighashIntegrity]: the tag is the sighash of the spending transaction, see [TransactionIntegrityScheme]
+//! - [SaltedSighashIntegrity]: the tag is the salted sighash of the spending transaction, see [TransactionIntegrityScheme::commit_salted]
+//! - [MultiInputSighashIntegrity]: the tag is the sighash of one of several tagged inputs, see [MultiInputIntegrityScheme]
+//! - [MultiInputIntegrity]: the tag is the list of the sighashes of all the tagged inputs, see [MultiInputIntegrityScheme]
+//! - [PoseidonIntegrity]: the tag is the Poseidon hash of the spending data, see [PoseidonIntegrityScheme]
+//! - [TxIdIntegrity]: the tag is the txid of the spending transaction, see [TxIdIntegrityScheme]
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use ark_crypto_primitives::{
+    crh::sha256::constraints::DigestVar, sponge::poseidon::PoseidonConfig,
+};
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::{AllocVar, AllocationMode},
+    uint8::UInt8,
+    uint64::UInt64,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+use chain_gang::{messages::Tx, script::Script, transaction::sighash::SigHashCache};
+
+use crate::bitcoin_predicates::data_structures::unit::{BitcoinUnit, BitcoinUnitVar};
+use crate::constraints::{
+    script::ScriptVar,
+    sighash_cache::SigHashCacheVar,
+    tx::{TxVar, TxVarConfig},
+};
+use crate::error::{Error, VerificationError};
+use crate::traits::IntegrityScheme;
+use crate::transaction_integrity_gadget::{
+    TransactionIntegrityConfig, TransactionIntegrityScheme, TransactionIntegrityTag,
+    constraints::{TransactionIntegrityGadget, TransactionIntegrityTagVar},
+    multi_input::{
+        MultiInputIntegrityConfig, MultiInputIntegrityGadget, MultiInputIntegrityScheme,
+        MultiInputIntegrityTag, MultiInputIntegrityTagVar,
+    },
+    poseidon::{
+        PoseidonIntegrityGadget, PoseidonIntegrityScheme, PoseidonIntegrityTag,
+        PoseidonIntegrityTagVar, poseidon_config,
+    },
+    txid::{TxIdIntegrityGadget, TxIdIntegrityScheme},
+};
+
+/// The data of the output being spent: its locking script and its amount
+#[derive(Clone)]
+pub struct PrevOutput {
+    pub prev_lock_script: Script,
+    pub prev_amount: u64,
+}
+
+/// R1CS version of [PrevOutput]
+pub struct PrevOutputVar<F: PrimeField> {
+    pub prev_lock_script: ScriptVar<F>,
+    pub prev_amount: UInt64<F>,
+}
+
+impl<F: PrimeField> AllocVar<PrevOutput, F> for PrevOutputVar<F> {
+    fn new_variable<T: Borrow<PrevOutput>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let data = f()?;
+        let prev_output: &PrevOutput = data.borrow();
+        Ok(Self {
+            prev_lock_script: ScriptVar::<F>::new_variable(
+                cs.clone(),
+                || Ok(&prev_output.prev_lock_script),
+                mode,
+            )?,
+            prev_amount: UInt64::<F>::new_variable(
+                cs.clone(),
+                || Ok(prev_output.prev_amount),
+                mode,
+            )?,
+        })
+    }
+}
+
+/// The witness of [SighashIntegrity]: the output being spent and the sighash cache
+pub struct SighashWitness {
+    pub prev_output: PrevOutput,
+    pub sighash_cache: SigHashCache,
+}
+
+/// R1CS version of [SighashWitness]
+pub struct SighashWitnessVar<F: PrimeField> {
+    pub prev_output: PrevOutputVar<F>,
+    pub sighash_cache: SigHashCacheVar<F>,
+}
+
+impl<F: PrimeField> AllocVar<SighashWitness, F> for SighashWitnessVar<F> {
+    fn new_variable<T: Borrow<SighashWitness>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let data = f()?;
+        let witness: &SighashWitness = data.borrow();
+        Ok(Self {
+            prev_output: PrevOutputVar::<F>::new_variable(
+                cs.clone(),
+                || Ok(&witness.prev_output),
+                mode,
+            )?,
+            sighash_cache: SigHashCacheVar::<F>::new_variable(
+                cs.clone(),
+                || Ok(&witness.sighash_cache),
+                mode,
+            )?,
+        })
+    }
+}
+
+/// [IntegrityScheme] whose tag is the sighash of the spending transaction, see [TransactionIntegrityScheme]
+#[derive(Clone)]
+pub struct SighashIntegrity<P: TransactionIntegrityConfig> {
+    _ti_structure: PhantomData<P>,
+}
+
+impl<P: TransactionIntegrityConfig> SighashIntegrity<P> {
+    pub fn new() -> Self {
+        Self {
+            _ti_structure: PhantomData,
+        }
+    }
+}
+
+impl<P: TransactionIntegrityConfig> Default for SighashIntegrity<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: PrimeField, P: TransactionIntegrityConfig + TxVarConfig + Clone> IntegrityScheme<F, P>
+    for SighashIntegrity<P>
+{
+    type Tag = TransactionIntegrityTag;
+    type Witness = SighashWitness;
+
+    type TagVar = TransactionIntegrityTagVar<F>;
+    type WitnessVar = SighashWitnessVar<F>;
+
+    fn default_witness(&self) -> Self::Witness {
+        SighashWitness {
+            prev_output: PrevOutput {
+                prev_lock_script: Script(vec![0; P::LEN_PREV_LOCK_SCRIPT]),
+                prev_amount: 0,
+            },
+            sighash_cache: SigHashCache::new(),
+        }
+    }
+
+    fn commit(&self, tx: &Tx, witness: &mut Self::Witness) -> Result<Self::Tag, Error> {
+        TransactionIntegrityScheme::<P>::commit(
+            tx,
+            &witness.prev_output.prev_lock_script,
+            witness.prev_output.prev_amount,
+            &mut witness.sighash_cache,
+        )
+    }
+
+    fn verify(&self, tx: &Tx, witness: &mut Self::Witness, tag: &Self::Tag) -> Result<(), Error> {
+        TransactionIntegrityScheme::<P>::verify(
+            tx,
+            &witness.prev_output.prev_lock_script,
+            witness.prev_output.prev_amount,
+            &mut witness.sighash_cache,
+            tag.clone(),
+        )
+    }
+
+    fn enforce_integrity(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        tx: &TxVar<F, P>,
+        witness: &mut Self::WitnessVar,
+        tag: &Self::TagVar,
+    ) -> Result<Option<DigestVar<F>>, SynthesisError> {
+        TransactionIntegrityGadget::<F, P>::verify_and_return(
+            cs,
+            tx,
+            &witness.prev_output.prev_lock_script,
+            &witness.prev_output.prev_amount,
+            &mut witness.sighash_cache,
+            tag,
+        )
+        .map(Some)
+    }
+
+    fn prev_lock_script<'a>(&self, witness: &'a Self::WitnessVar) -> Option<&'a ScriptVar<F>> {
+        Some(&witness.prev_output.prev_lock_script)
+    }
+
+    fn prev_amount<'a>(&self, witness: &'a Self::WitnessVar) -> Option<&'a UInt64<F>> {
+        Some(&witness.prev_output.prev_amount)
+    }
+}
+
+/// The witness of [SaltedSighashIntegrity]: the witness of [SighashIntegrity] and the salt
+pub struct SaltedSighashWitness {
+    pub sighash_witness: SighashWitness,
+    pub salt: [u8; 32],
+}
+
+/// R1CS version of [SaltedSighashWitness]
+pub struct SaltedSighashWitnessVar<F: PrimeField> {
+    pub sighash_witness: SighashWitnessVar<F>,
+    pub salt: Vec<UInt8<F>>,
+}
+
+impl<F: PrimeField> AllocVar<SaltedSighashWitness, F> for SaltedSighashWitnessVar<F> {
+    fn new_variable<T: Borrow<SaltedSighashWitness>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let data = f()?;
+        let witness: &SaltedSighashWitness = data.borrow();
+        Ok(Self {
+            sighash_witness: SighashWitnessVar::<F>::new_variable(
+                cs.clone(),
+                || Ok(&witness.sighash_witness),
+                mode,
+            )?,
+            salt: Vec::<UInt8<F>>::new_variable(cs.clone(), || Ok(witness.salt.to_vec()), mode)?,
+        })
+    }
+}
+
+/// [IntegrityScheme] whose tag is the salted sighash of the spending transaction, see
+/// [TransactionIntegrityScheme::commit_salted]. The tag is a hiding commitment to the spending transaction.
+#[derive(Clone)]
+pub struct SaltedSighashIntegrity<P: TransactionIntegrityConfig> {
+    _ti_structure: PhantomData<P>,
+}
+
+impl<P: TransactionIntegrityConfig> SaltedSighashIntegrity<P> {
+    pub fn new() -> Self {
+        Self {
+            _ti_structure: PhantomData,
+        }
+    }
+}
+
+impl<P: TransactionIntegrityConfig> Default for SaltedSighashIntegrity<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: PrimeField, P: TransactionIntegrityConfig + TxVarConfig + Clone> IntegrityScheme<F, P>
+    for SaltedSighashIntegrity<P>
+{
+    type Tag = TransactionIntegrityTag;
+    type Witness = SaltedSighashWitness;
+
+    type TagVar = TransactionIntegrityTagVar<F>;
+    type WitnessVar = SaltedSighashWitnessVar<F>;
+
+    fn default_witness(&self) -> Self::Witness {
+        SaltedSighashWitness {
+            sighash_witness: IntegrityScheme::<F, P>::default_witness(&SighashIntegrity::<P>::new()),
+            salt: [0; 32],
+        }
+    }
+
+    fn commit(&self, tx: &Tx, witness: &mut Self::Witness) -> Result<Self::Tag, Error> {
+        TransactionIntegrityScheme::<P>::commit_salted(
+            tx,
+            &witness.sighash_witness.prev_output.prev_lock_script,
+            witness.sighash_witness.prev_output.prev_amount,
+            &witness.salt,
+            &mut witness.sighash_witness.sighash_cache,
+        )
+    }
+
+    fn verify(&self, tx: &Tx, witness: &mut Self::Witness, tag: &Self::Tag) -> Result<(), Error> {
+        TransactionIntegrityScheme::<P>::verify_salted(
+            tx,
+            &witness.sighash_witness.prev_output.prev_lock_script,
+            witness.sighash_witness.prev_output.prev_amount,
+            &witness.salt,
+            &mut witness.sighash_witness.sighash_cache,
+            tag.clone(),
+        )
+    }
+
+    fn enforce_integrity(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        tx: &TxVar<F, P>,
+        witness: &mut Self::WitnessVar,
+        tag: &Self::TagVar,
+    ) -> Result<Option<DigestVar<F>>, SynthesisError> {
+        TransactionIntegrityGadget::<F, P>::verify_salted_and_return(
+            cs,
+            tx,
+            &witness.sighash_witness.prev_output.prev_lock_script,
+            &witness.sighash_witness.prev_output.prev_amount,
+            &witness.salt,
+            &mut witness.sighash_witness.sighash_cache,
+            tag,
+        )
+        .map(Some)
+    }
+
+    fn prev_lock_script<'a>(&self, witness: &'a Self::WitnessVar) -> Option<&'a ScriptVar<F>> {
+        Some(&witness.sighash_witness.prev_output.prev_lock_script)
+    }
+
+    fn prev_amount<'a>(&self, witness: &'a Self::WitnessVar) -> Option<&'a UInt64<F>> {
+        Some(&witness.sighash_witness.prev_output.prev_amount)
+    }
+}
+
+/// [IntegrityScheme] whose tag is the sighash of the input `P::N_INPUTS_TO_TAG[position]` of the spending
+/// transaction, see [MultiInputIntegrityScheme]. It allows one proof per tagged input of the same transaction.
+#[derive(Clone)]
+pub struct MultiInputSighashIntegrity<P: MultiInputIntegrityConfig> {
+    pub position: usize,
+    _ti_structure: PhantomData<P>,
+}
+
+impl<P: MultiInputIntegrityConfig> MultiInputSighashIntegrity<P> {
+    /// Returns [Error::IndexOutOfBounds] if `position` is not the position of one of the inputs to tag
+    pub fn new(position: usize) -> Result<Self, Error> {
+        if position >= P::N_INPUTS_TO_TAG.len() {
+            return Err(Error::IndexOutOfBounds(format!(
+                "The position: {} is larger than the number of inputs to tag: {}",
+                position,
+                P::N_INPUTS_TO_TAG.len()
+            )));
+        }
+        Ok(Self {
+            position,
+            _ti_structure: PhantomData,
+        })
+    }
+
+    /// Check that the data is compatible with the configuration
+    fn check_data(&self, tx: &Tx, prev_lock_script: &Script) -> Result<(), Error> {
+        if prev_lock_script.0.len() != P::LEN_PREV_LOCK_SCRIPTS[self.position]
+            || P::N_INPUTS_TO_TAG[self.position] >= tx.inputs.len()
+        {
+            return Err(Error::ConfigMismatch(format!(
+                "The data of the input {} is incompatible with the parameters",
+                self.position
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl<F: PrimeField, P: MultiInputIntegrityConfig + TxVarConfig + Clone> IntegrityScheme<F, P>
+    for MultiInputSighashIntegrity<P>
+{
+    type Tag = TransactionIntegrityTag;
+    type Witness = SighashWitness;
+
+    type TagVar = TransactionIntegrityTagVar<F>;
+    type WitnessVar = SighashWitnessVar<F>;
+
+    fn default_witness(&self) -> Self::Witness {
+        SighashWitness {
+            prev_output: PrevOutput {
+                prev_lock_script: Script(vec![0; P::LEN_PREV_LOCK_SCRIPTS[self.position]]),
+                prev_amount: 0,
+            },
+            sighash_cache: SigHashCache::new(),
+        }
+    }
+
+    fn commit(&self, tx: &Tx, witness: &mut Self::Witness) -> Result<Self::Tag, Error> {
+        self.check_data(tx, &witness.prev_output.prev_lock_script)?;
+
+        MultiInputIntegrityScheme::<P>::commit_input(
+            tx,
+            self.position,
+            &witness.prev_output.prev_lock_script,
+            witness.prev_output.prev_amount,
+            &mut witness.sighash_cache,
+        )
+    }
+
+    fn verify(&self, tx: &Tx, witness: &mut Self::Witness, tag: &Self::Tag) -> Result<(), Error> {
+        if IntegrityScheme::<F, P>::commit(self, tx, witness)? == *tag {
+            Ok(())
+        } else {
+            Err(VerificationError::TagMismatch.into())
+        }
+    }
+
+    fn enforce_integrity(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        tx: &TxVar<F, P>,
+        witness: &mut Self::WitnessVar,
+        tag: &Self::TagVar,
+    ) -> Result<Option<DigestVar<F>>, SynthesisError> {
+        MultiInputIntegrityGadget::<F, P>::verify_input(
+            cs,
+            tx,
+            self.position,
+            &witness.prev_output.prev_lock_script,
+            &witness.prev_output.prev_amount,
+            &mut witness.sighash_cache,
+            tag,
+        )?;
+
+        // The sighash is computed for the tagged input `P::N_INPUTS_TO_TAG[position]`, not for `P::N_INPUT`
+        Ok(None)
+    }
+
+    fn prev_lock_script<'a>(&self, witness: &'a Self::WitnessVar) -> Option<&'a ScriptVar<F>> {
+        Some(&witness.prev_output.prev_lock_script)
+    }
+
+    fn prev_amount<'a>(&self, witness: &'a Self::WitnessVar) -> Option<&'a UInt64<F>> {
+        Some(&witness.prev_output.prev_amount)
+    }
+}
+
+/// The witness of [MultiInputIntegrity]: the outputs being spent, one per input in `P::N_INPUTS_TO_TAG`, and
+/// the sighash cache
+pub struct MultiInputWitness {
+    pub prev_outputs: Vec<PrevOutput>,
+    pub sighash_cache: SigHashCache,
+}
+
+/// R1CS version of [MultiInputWitness]
+pub struct MultiInputWitnessVar<F: PrimeField> {
+    pub prev_outputs: Vec<PrevOutputVar<F>>,
+    pub sighash_cache: SigHashCacheVar<F>,
+}
+
+impl<F: PrimeField> AllocVar<MultiInputWitness, F> for MultiInputWitnessVar<F> {
+    fn new_variable<T: Borrow<MultiInputWitness>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let data = f()?;
+        let witness: &MultiInputWitness = data.borrow();
+        Ok(Self {
+            prev_outputs: Vec::<PrevOutputVar<F>>::new_variable(
+                cs.clone(),
+                || Ok(witness.prev_outputs.as_slice()),
+                mode,
+            )?,
+            sighash_cache: SigHashCacheVar::<F>::new_variable(
+                cs.clone(),
+                || Ok(&witness.sighash_cache),
+                mode,
+            )?,
+        })
+    }
+}
+
+/// [IntegrityScheme] whose tag is the list of the sighashes of the inputs in `P::N_INPUTS_TO_TAG`, see
+/// [MultiInputIntegrityScheme]. It allows a single proof to cover all the tagged inputs of the spending transaction.
+#[derive(Clone)]
+pub struct MultiInputIntegrity<P: MultiInputIntegrityConfig> {
+    _ti_structure: PhantomData<P>,
+}
+
+impl<P: MultiInputIntegrityConfig> MultiInputIntegrity<P> {
+    pub fn new() -> Self {
+        Self {
+            _ti_structure: PhantomData,
+        }
+    }
+
+    /// Check that the data is compatible with the configuration
+    fn check_data(tx: &Tx, prev_outputs: &[PrevOutput]) -> Result<(), Error> {
+        if prev_outputs.len() != P::N_INPUTS_TO_TAG.len()
+            || prev_outputs
+                .iter()
+                .zip(P::LEN_PREV_LOCK_SCRIPTS.iter())
+                .any(|(prev_output, len)| prev_output.prev_lock_script.0.len() != *len)
+            || P::N_INPUTS_TO_TAG
+                .iter()
+                .any(|n_input| *n_input >= tx.inputs.len())
+        {
+            return Err(Error::ConfigMismatch(
+                "The data of the inputs to tag is incompatible with the parameters".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl<P: MultiInputIntegrityConfig> Default for MultiInputIntegrity<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: PrimeField, P: MultiInputIntegrityConfig + TxVarConfig + Clone> IntegrityScheme<F, P>
+    for MultiInputIntegrity<P>
+{
+    type Tag = MultiInputIntegrityTag<P>;
+    type Witness = MultiInputWitness;
+
+    type TagVar = MultiInputIntegrityTagVar<F>;
+    type WitnessVar = MultiInputWitnessVar<F>;
+
+    fn default_witness(&self) -> Self::Witness {
+        MultiInputWitness {
+            prev_outputs: P::LEN_PREV_LOCK_SCRIPTS
+                .iter()
+                .map(|len| PrevOutput {
+                    prev_lock_script: Script(vec![0; *len]),
+                    prev_amount: 0,
+                })
+                .collect(),
+            sighash_cache: SigHashCache::new(),
+        }
+    }
+
+    fn commit(&self, tx: &Tx, witness: &mut Self::Witness) -> Result<Self::Tag, Error> {
+        Self::check_data(tx, &witness.prev_outputs)?;
+
+        let (prev_lock_scripts, prev_amounts): (Vec<Script>, Vec<u64>) = witness
+            .prev_outputs
+            .iter()
+            .map(|prev_output| {
+                (
+                    prev_output.prev_lock_script.clone(),
+                    prev_output.prev_amount,
+                )
+            })
+            .unzip();
+        MultiInputIntegrityTag::new(MultiInputIntegrityScheme::<P>::commit(
+            tx,
+            &prev_lock_scripts,
+            &prev_amounts,
+            &mut witness.sighash_cache,
+        )?)
+    }
+
+    fn verify(&self, tx: &Tx, witness: &mut Self::Witness, tag: &Self::Tag) -> Result<(), Error> {
+        if IntegrityScheme::<F, P>::commit(self, tx, witness)? == *tag {
+            Ok(())
+        } else {
+            Err(VerificationError::TagMismatch.into())
+        }
+    }
+
+    fn enforce_integrity(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        tx: &TxVar<F, P>,
+        witness: &mut Self::WitnessVar,
+        tag: &Self::TagVar,
+    ) -> Result<Option<DigestVar<F>>, SynthesisError> {
+        let (prev_lock_scripts, prev_amounts): (Vec<ScriptVar<F>>, Vec<UInt64<F>>) = witness
+            .prev_outputs
+            .iter()
+            .map(|prev_output| {
+                (
+                    prev_output.prev_lock_script.clone(),
+                    prev_output.prev_amount.clone(),
+                )
+            })
+            .unzip();
+        MultiInputIntegrityGadget::<F, P>::verify(
+            cs,
+            tx,
+            &prev_lock_scripts,
+            &prev_amounts,
+            &mut witness.sighash_cache,
+            &tag.tags,
+        )?;
+
+        Ok(None)
+    }
+}
+
+/// [IntegrityScheme] whose tag is the Poseidon hash of the spending data, see [PoseidonIntegrityScheme]
+#[derive(Clone)]
+pub struct PoseidonIntegrity<F: PrimeField, P: TransactionIntegrityConfig> {
+    pub poseidon_config: PoseidonConfig<F>,
+    _ti_structure: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: TransactionIntegrityConfig> PoseidonIntegrity<F, P> {
+    pub fn new(poseidon_config: PoseidonConfig<F>) -> Self {
+        Self {
+            poseidon_config,
+            _ti_structure: PhantomData,
+        }
+    }
+}
+
+/// The scheme instantiated with the parameters returned by [poseidon_config]
+impl<F: PrimeField, P: TransactionIntegrityConfig> Default for PoseidonIntegrity<F, P> {
+    fn default() -> Self {
+        Self::new(poseidon_config::<F>())
+    }
+}
+
+impl<F: PrimeField, P: TransactionIntegrityConfig + TxVarConfig + Clone> IntegrityScheme<F, P>
+    for PoseidonIntegrity<F, P>
+{
+    type Tag = PoseidonIntegrityTag<F>;
+    type Witness = PrevOutput;
+
+    type TagVar = PoseidonIntegrityTagVar<F>;
+    type WitnessVar = PrevOutputVar<F>;
+
+    fn default_witness(&self) -> Self::Witness {
+        PrevOutput {
+            prev_lock_script: Script(vec![0; P::LEN_PREV_LOCK_SCRIPT]),
+            prev_amount: 0,
+        }
+    }
+
+    fn commit(&self, tx: &Tx, witness: &mut Self::Witness) -> Result<Self::Tag, Error> {
+        PoseidonIntegrityScheme::<F, P>::commit(
+            tx,
+            &witness.prev_lock_script,
+            witness.prev_amount,
+            &self.poseidon_config,
+        )
+    }
+
+    fn verify(&self, tx: &Tx, witness: &mut Self::Witness, tag: &Self::Tag) -> Result<(), Error> {
+        Ok(PoseidonIntegrityScheme::<F, P>::verify(
+            tx,
+            &witness.prev_lock_script,
+            witness.prev_amount,
+            &self.poseidon_config,
+            tag.clone(),
+        )?)
+    }
+
+    fn enforce_integrity(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        tx: &TxVar<F, P>,
+        witness: &mut Self::WitnessVar,
+        tag: &Self::TagVar,
+    ) -> Result<Option<DigestVar<F>>, SynthesisError> {
+        PoseidonIntegrityGadget::<F, P>::verify(
+            cs,
+            tx,
+            &witness.prev_lock_script,
+            &witness.prev_amount,
+            &self.poseidon_config,
+            tag,
+        )?;
+
+        Ok(None)
+    }
+
+    fn prev_lock_script<'a>(&self, witness: &'a Self::WitnessVar) -> Option<&'a ScriptVar<F>> {
+        Some(&witness.prev_lock_script)
+    }
+
+    fn prev_amount<'a>(&self, witness: &'a Self::WitnessVar) -> Option<&'a UInt64<F>> {
+        Some(&witness.prev_amount)
+    }
+}
+
+/// [IntegrityScheme] whose tag is the txid of the spending transaction, see [TxIdIntegrityScheme]
+#[derive(Clone)]
+pub struct TxIdIntegrity<P: TxVarConfig> {
+    _tx_structure: PhantomData<P>,
+}
+
+impl<P: TxVarConfig> TxIdIntegrity<P> {
+    pub fn new() -> Self {
+        Self {
+            _tx_structure: PhantomData,
+        }
+    }
+}
+
+impl<P: TxVarConfig> Default for TxIdIntegrity<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> IntegrityScheme<F, P> for TxIdIntegrity<P> {
+    type Tag = TransactionIntegrityTag;
+    type Witness = BitcoinUnit<F, P>;
+
+    type TagVar = TransactionIntegrityTagVar<F>;
+    type WitnessVar = BitcoinUnitVar<F, P>;
+
+    fn default_witness(&self) -> Self::Witness {
+        BitcoinUnit::default()
+    }
+
+    fn commit(&self, tx: &Tx, _witness: &mut Self::Witness) -> Result<Self::Tag, Error> {
+        Ok(TxIdIntegrityScheme::<P>::commit(tx))
+    }
+
+    fn verify(&self, tx: &Tx, _witness: &mut Self::Witness, tag: &Self::Tag) -> Result<(), Error> {
+        Ok(TxIdIntegrityScheme::<P>::verify(tx, tag.clone())?)
+    }
+
+    fn enforce_integrity(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        tx: &TxVar<F, P>,
+        _witness: &mut Self::WitnessVar,
+        tag: &Self::TagVar,
+    ) -> Result<Option<DigestVar<F>>, SynthesisError> {
+        TxIdIntegrityGadget::<F, P>::verify(cs, tx, tag)?;
+
+        Ok(None)
+    }
+}
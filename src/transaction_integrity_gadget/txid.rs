@@ -0,0 +1,175 @@
+//! Transaction integrity scheme committing to the txid of the spending transaction
+//!
+//! Contrary to [TransactionIntegrityScheme](super::TransactionIntegrityScheme), the tag binds the proof to the exact
+//! spending transaction, including its unlocking scripts, and not to a sighash under a particular flag.
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use chain_gang::messages::Tx;
+
+use crate::constraints::tx::{TxVar, TxVarConfig, check_tx_var_config};
+use crate::error::VerificationError;
+use crate::transaction_integrity_gadget::{
+    TransactionIntegrityTag, constraints::TransactionIntegrityTagVar,
+};
+
+/// The txid Transaction Integrity Scheme: the tag is the double-SHA256 txid of the spending transaction
+pub struct TxIdIntegrityScheme<P: TxVarConfig> {
+    _tx_structure: PhantomData<P>,
+}
+
+impl<P: TxVarConfig> TxIdIntegrityScheme<P> {
+    /// Generate a tag
+    pub fn commit(tx: &Tx) -> TransactionIntegrityTag {
+        TransactionIntegrityTag { inner: tx.hash().0 }
+    }
+
+    /// Verify the validity of a tag
+    ///
+    /// Returns [VerificationError::ConfigMismatch] if the transaction is incompatible with the configuration, and
+    /// [VerificationError::TagMismatch] if the tag does not match the transaction.
+    pub fn verify(tx: &Tx, tag: TransactionIntegrityTag) -> Result<(), VerificationError> {
+        if tx.inputs.len() != P::N_INPUTS
+            || tx.outputs.len() != P::N_OUTPUTS
+            || tx
+                .inputs
+                .iter()
+                .zip(P::LEN_UNLOCK_SCRIPTS.iter())
+                .any(|(input, len)| input.unlock_script.0.len() != *len)
+            || tx
+                .outputs
+                .iter()
+                .zip(P::LEN_LOCK_SCRIPTS.iter())
+                .any(|(output, len)| output.lock_script.0.len() != *len)
+        {
+            return Err(VerificationError::ConfigMismatch(
+                "The structure of the transaction is different from the one set in the parameters"
+                    .to_string(),
+            ));
+        }
+
+        if Self::commit(tx) == tag {
+            Ok(())
+        } else {
+            Err(VerificationError::TagMismatch)
+        }
+    }
+}
+
+/// The gadget version of [TxIdIntegrityScheme]
+pub struct TxIdIntegrityGadget<F: PrimeField, P: TxVarConfig> {
+    _tx_structure: PhantomData<P>,
+    _field: PhantomData<F>,
+}
+
+impl<F: PrimeField, P: TxVarConfig + Clone> TxIdIntegrityGadget<F, P> {
+    /// Verify the integrity of the tag: enforce that it is equal to the txid of `tx`
+    pub fn verify(
+        _cs: ConstraintSystemRef<F>,
+        tx: &TxVar<F, P>,
+        tag: &TransactionIntegrityTagVar<F>,
+    ) -> Result<(), SynthesisError> {
+        // Check the invariants of the configuration at compile time
+        const { check_tx_var_config::<P>() };
+
+        tag.enforce_equal_digest(&tx.txid()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use chain_gang::address::addr_decode;
+    use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
+    use chain_gang::network::Network;
+    use chain_gang::script::Script;
+    use chain_gang::transaction::p2pkh;
+    use chain_gang::util::Hash256;
+
+    use crate::constraints::tx::{TxVar, TxVarConfig};
+    use crate::error::VerificationError;
+    use crate::transaction_integrity_gadget::{
+        TransactionIntegrityTag, constraints::TransactionIntegrityTagVar,
+    };
+
+    use super::{TxIdIntegrityGadget, TxIdIntegrityScheme};
+
+    #[derive(Clone)]
+    struct Config;
+    impl TxVarConfig for Config {
+        const N_INPUTS: usize = 1;
+        const N_OUTPUTS: usize = 1;
+        const LEN_UNLOCK_SCRIPTS: &[usize] = &[0];
+        const LEN_LOCK_SCRIPTS: &[usize] = &[0x19];
+    }
+
+    fn tx(satoshis: i64) -> Tx {
+        let hash160 = addr_decode("mfmKD4cP6Na7T8D87XRSiR7shA1HNGSaec", Network::BSV_Testnet)
+            .unwrap()
+            .0;
+        Tx {
+            version: 2,
+            inputs: vec![TxIn {
+                prev_output: OutPoint {
+                    hash: Hash256::decode(
+                        "f671dc000ad12795e86b59b27e0c367d9b026bbd4141c227b9285867a53bb6f7",
+                    )
+                    .unwrap(),
+                    index: 0,
+                },
+                unlock_script: Script(vec![]),
+                sequence: 0xffffffff,
+            }],
+            outputs: vec![TxOut {
+                satoshis,
+                lock_script: p2pkh::create_lock_script(&hash160),
+            }],
+            lock_time: 0,
+        }
+    }
+
+    fn test_txid_verify(tag: TransactionIntegrityTag, tx: Tx) -> bool {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let tx_var = TxVar::<F, Config>::new_witness(cs.clone(), || Ok(tx)).unwrap();
+        let tag_var = TransactionIntegrityTagVar::<F>::new_input(cs.clone(), || Ok(tag)).unwrap();
+        TxIdIntegrityGadget::<F, Config>::verify(cs.clone(), &tx_var, &tag_var).unwrap();
+
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_txid_verify_is_ok() {
+        let tag = TxIdIntegrityScheme::<Config>::commit(&tx(100));
+        assert_eq!(tag.inner, tx(100).hash().0);
+        assert!(test_txid_verify(tag, tx(100)));
+    }
+
+    #[test]
+    fn test_txid_verify_fails() {
+        let tag = TxIdIntegrityScheme::<Config>::commit(&tx(100));
+        assert!(!test_txid_verify(tag, tx(101)));
+    }
+
+    #[test]
+    fn test_txid_native_verify() {
+        let tag = TxIdIntegrityScheme::<Config>::commit(&tx(100));
+        assert_eq!(
+            TxIdIntegrityScheme::<Config>::verify(&tx(100), tag.clone()),
+            Ok(())
+        );
+        assert_eq!(
+            TxIdIntegrityScheme::<Config>::verify(&tx(101), tag.clone()),
+            Err(VerificationError::TagMismatch)
+        );
+
+        let mut wrong_tx = tx(100);
+        wrong_tx.outputs.push(wrong_tx.outputs[0].clone());
+        assert!(matches!(
+            TxIdIntegrityScheme::<Config>::verify(&wrong_tx, tag),
+            Err(VerificationError::ConfigMismatch(_))
+        ));
+    }
+}
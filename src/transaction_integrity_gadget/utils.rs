@@ -1,5 +1,7 @@
 use ark_ff::PrimeField;
 
+use crate::error::Error;
+
 /// Converts a slice of [`u8`] to [`Vec<F>`].
 /// This is used to convert public inputs / witnesses into their R1CS counterparts
 /// It leverages [get_chunk_size] below
@@ -11,7 +13,7 @@ pub fn to_fp_chunks<F: PrimeField>(data: &[u8]) -> Vec<F> {
 
 /// Get the size of the chunks the data should be split into
 /// Used in [to_fp_chunks]
-pub fn get_chunk_size<F: PrimeField>() -> usize {
+pub const fn get_chunk_size<F: PrimeField>() -> usize {
     if F::MODULUS_BIT_SIZE > 256 {
         32
     } else if F::MODULUS_BIT_SIZE > 128 {
@@ -26,3 +28,15 @@ pub fn get_chunk_size<F: PrimeField>() -> usize {
         1
     }
 }
+
+/// Check that the length of the previous locking script is the one set in the parameters
+pub(crate) fn check_prev_lock_script_len(len: usize, expected_len: usize) -> Result<(), Error> {
+    if len != expected_len {
+        return Err(Error::ConfigMismatch(format!(
+            "The length of the previous locking script: {} is different from the one set in the parameters: {}",
+            len, expected_len
+        )));
+    }
+
+    Ok(())
+}
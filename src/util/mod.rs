@@ -1,10 +1,33 @@
-use byteorder::{LittleEndian, WriteBytesExt};
+use ark_ff::{BigInteger, PrimeField};
+use ark_r1cs_std::{
+    R1CSVar,
+    alloc::AllocVar,
+    eq::EqGadget,
+    fields::{FieldVar, fp::FpVar},
+    prelude::{Boolean, ToBitsGadget},
+    uint8::UInt8,
+};
+use ark_relations::r1cs::SynthesisError;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use chain_gang::messages::{OutPoint, Tx, TxIn, TxOut};
 use chain_gang::script::Script;
+use chain_gang::script::op_codes::{OP_PUSHDATA1, OP_PUSHDATA2, OP_PUSHDATA4};
 use chain_gang::util::Hash256;
-use std::io::Result as IoResult;
+use std::io::{Cursor, Error as IoError, ErrorKind, Result as IoResult};
 
 use crate::constraints::tx::TxVarConfig;
+use crate::error::Error;
+
+pub mod uint64;
+
+/// Lock times below this threshold are interpreted as block heights, otherwise as UNIX timestamps
+pub const LOCKTIME_THRESHOLD: u32 = 500_000_000;
+
+/// Sequence number of a final input: if all inputs are final, the lock time of the transaction is ignored
+pub const SEQUENCE_FINAL: u32 = 0xffffffff;
+
+/// Maximum amount of an output, in satoshis: 21,000,000 coins of 10^8 satoshis each
+pub const MAX_MONEY: u64 = 21_000_000 * 100_000_000;
 
 /// Convert usize to var_int
 pub fn usize_to_var_int(length: usize) -> IoResult<Vec<u8>> {
@@ -25,6 +48,270 @@ pub fn usize_to_var_int(length: usize) -> IoResult<Vec<u8>> {
     Ok(s)
 }
 
+/// Compute the opcodes used to push `length` bytes of data onto the stack:
+/// `OP_PUSHBYTES_n` (up to 75 bytes), `OP_PUSHDATA1`, `OP_PUSHDATA2` or `OP_PUSHDATA4`
+pub fn push_data_prefix(length: usize) -> IoResult<Vec<u8>> {
+    let mut s: Vec<u8> = Vec::new();
+    if length < OP_PUSHDATA1 as usize {
+        s.write_u8(length as u8)?;
+    } else if length <= 0xff {
+        s.write_u8(OP_PUSHDATA1)?;
+        s.write_u8(length as u8)?;
+    } else if length <= 0xffff {
+        s.write_u8(OP_PUSHDATA2)?;
+        s.write_u16::<LittleEndian>(length as u16)?;
+    } else if length <= 0xffffffff {
+        s.write_u8(OP_PUSHDATA4)?;
+        s.write_u32::<LittleEndian>(length as u32)?;
+    } else {
+        return Err(IoError::new(
+            ErrorKind::InvalidInput,
+            "Data too long to be pushed",
+        ));
+    }
+
+    Ok(s)
+}
+
+/// Construct the unlocking script `<signature || sighash_flag> <public_key>` spending an output
+/// locked by `OP_CHECKSIG` (e.g., P2PKH), where `signature` is the DER encoded signature
+pub fn checksig_unlock_script(signature: &[u8], sighash_flag: u8, public_key: &[u8]) -> Script {
+    let mut signature_with_flag = signature.to_vec();
+    signature_with_flag.push(sighash_flag);
+
+    let mut unlock_script = Script::new();
+    unlock_script.append_data(&signature_with_flag);
+    unlock_script.append_data(public_key);
+    unlock_script
+}
+
+/// Check whether `signature` is a strict DER encoded ECDSA signature, without the sighash flag,
+/// see [BIP66](https://github.com/bitcoin/bips/blob/master/bip-0066.mediawiki)
+pub fn is_der_signature(signature: &[u8]) -> bool {
+    let len = signature.len();
+    if !(8..=72).contains(&len) || signature[0] != 0x30 || signature[1] as usize != len - 2 {
+        return false;
+    }
+
+    let len_r = signature[3] as usize;
+    if 5 + len_r >= len {
+        return false;
+    }
+    let len_s = signature[5 + len_r] as usize;
+    if len_r + len_s + 6 != len {
+        return false;
+    }
+
+    // Both integers must be positive and minimally encoded
+    let is_valid_integer = |offset: usize, int_len: usize| {
+        signature[offset - 2] == 0x02
+            && int_len != 0
+            && signature[offset] & 0x80 == 0
+            && !(int_len > 1 && signature[offset] == 0 && signature[offset + 1] & 0x80 == 0)
+    };
+    is_valid_integer(4, len_r) && is_valid_integer(len_r + 6, len_s)
+}
+
+/// Walk a push-only script and return the lengths of the data chunks it pushes.
+/// The result can be used as the layout passed to [ScriptVar::parse_pushes](crate::constraints::script::ScriptVar::parse_pushes)
+pub fn parse_push_lengths(script: &[u8]) -> IoResult<Vec<usize>> {
+    let mut cursor = Cursor::new(script);
+    let mut lengths: Vec<usize> = Vec::new();
+    while (cursor.position() as usize) < script.len() {
+        let opcode = cursor.read_u8()?;
+        let length = match opcode {
+            OP_PUSHDATA1 => cursor.read_u8()? as usize,
+            OP_PUSHDATA2 => cursor.read_u16::<LittleEndian>()? as usize,
+            OP_PUSHDATA4 => cursor.read_u32::<LittleEndian>()? as usize,
+            n if n < OP_PUSHDATA1 => n as usize,
+            _ => {
+                return Err(IoError::new(
+                    ErrorKind::InvalidData,
+                    format!("Opcode {} is not a push", opcode),
+                ));
+            }
+        };
+        if cursor.position() as usize + length > script.len() {
+            return Err(IoError::new(
+                ErrorKind::UnexpectedEof,
+                "Push exceeds the length of the script",
+            ));
+        }
+        cursor.set_position(cursor.position() + length as u64);
+        lengths.push(length);
+    }
+
+    Ok(lengths)
+}
+
+/// Convert bytes into the field elements allocated when the bytes are allocated as public inputs of type [UInt8](ark_r1cs_std::uint8::UInt8):
+/// each byte is allocated as its eight little endian bits, and each bit is a field element
+pub fn to_fp_bits<F: PrimeField>(bytes: &[u8]) -> Vec<F> {
+    bytes
+        .iter()
+        .flat_map(|byte| (0..8).map(move |i| F::from((byte >> i) & 1)))
+        .collect()
+}
+
+/// Convert an R1CS variable into the [FpVar] represented by its little endian bits
+pub fn to_fp<F: PrimeField, T: ToBitsGadget<F>>(value: &T) -> Result<FpVar<F>, SynthesisError> {
+    Boolean::<F>::le_bits_to_fp(&value.to_bits_le()?)
+}
+
+/// Check whether `a < b`.
+///
+/// **NOTE**: the function assumes that both `a` and `b` are smaller than `2^n_bits`, which is the case, e.g.,
+/// if they were obtained from [UInt64](ark_r1cs_std::uint64::UInt64) variables and `n_bits = 64`.
+/// The caller is responsible for enforcing this condition.
+///
+/// Returns [Error::ConfigMismatch] if `n_bits` is too large for the comparison to be sound in `F`.
+pub fn is_less_than<F: PrimeField>(
+    a: &FpVar<F>,
+    b: &FpVar<F>,
+    n_bits: usize,
+) -> Result<Boolean<F>, SynthesisError> {
+    if n_bits + 1 >= F::MODULUS_BIT_SIZE as usize {
+        return Err(Error::ConfigMismatch(format!(
+            "Cannot compare elements of {} bits in a field of {} bits",
+            n_bits,
+            F::MODULUS_BIT_SIZE
+        ))
+        .into());
+    }
+
+    // `shifted` lies in [1, 2^(n_bits + 1)), and its bit of index `n_bits` is set if and only if `a >= b`
+    let shifted = a - b + FpVar::<F>::constant(F::from(2u64).pow([n_bits as u64]));
+
+    let cs = shifted.cs();
+    if cs.is_none() {
+        return Ok(Boolean::<F>::constant(
+            !shifted.value()?.into_bigint().get_bit(n_bits),
+        ));
+    }
+
+    let mut bits: Vec<Boolean<F>> = Vec::with_capacity(n_bits + 1);
+    for i in 0..=n_bits {
+        bits.push(Boolean::<F>::new_witness(cs.clone(), || {
+            Ok(shifted.value()?.into_bigint().get_bit(i))
+        })?);
+    }
+    Boolean::<F>::le_bits_to_fp(&bits)?.enforce_equal(&shifted)?;
+
+    Ok(!bits[n_bits].clone())
+}
+
+/// Check whether `a < 2^n_bits`.
+/// Unlike [is_less_than], the function makes no assumptions on `a`, at the cost of a full bit decomposition of `a`.
+pub fn fits_in_bits<F: PrimeField>(
+    a: &FpVar<F>,
+    n_bits: usize,
+) -> Result<Boolean<F>, SynthesisError> {
+    let bits = a.to_bits_le()?;
+    if n_bits >= bits.len() {
+        return Ok(Boolean::<F>::TRUE);
+    }
+
+    Ok(!Boolean::<F>::kary_or(&bits[n_bits..])?)
+}
+
+/// Check whether `a <= b`. See [is_less_than] for the assumptions on `a` and `b`.
+pub fn is_less_or_equal<F: PrimeField>(
+    a: &FpVar<F>,
+    b: &FpVar<F>,
+    n_bits: usize,
+) -> Result<Boolean<F>, SynthesisError> {
+    Ok(!is_less_than(b, a, n_bits)?)
+}
+
+/// Check whether at least `k` of `booleans` are `TRUE`
+pub fn is_at_least<F: PrimeField>(
+    booleans: &[Boolean<F>],
+    k: usize,
+) -> Result<Boolean<F>, SynthesisError> {
+    if k == 0 {
+        return Ok(Boolean::<F>::TRUE);
+    }
+    if k > booleans.len() {
+        return Ok(Boolean::<F>::FALSE);
+    }
+
+    let mut count = FpVar::<F>::zero();
+    for boolean in booleans.iter() {
+        count += FpVar::<F>::from(boolean.clone());
+    }
+    // Both `k` and `count` are at most `booleans.len()`
+    let n_bits = (usize::BITS - booleans.len().leading_zeros()) as usize;
+    is_less_or_equal(&FpVar::<F>::constant(F::from(k as u64)), &count, n_bits)
+}
+
+/// Compare `a` and `b` lexicographically, byte by byte: return `(a < b, a == b)`.
+/// If one of the two is a prefix of the other, the shorter one is the smaller.
+fn bytes_compare<F: PrimeField>(
+    a: &[UInt8<F>],
+    b: &[UInt8<F>],
+) -> Result<(Boolean<F>, Boolean<F>), SynthesisError> {
+    let mut is_less = Boolean::<F>::FALSE;
+    let mut is_equal = Boolean::<F>::TRUE;
+    for (x, y) in a.iter().zip(b.iter()) {
+        let byte_is_less = is_less_than(&to_fp(x)?, &to_fp(y)?, 8)?;
+        is_less = &is_less | &(&is_equal & &byte_is_less);
+        is_equal = &is_equal & &x.is_eq(y)?;
+    }
+
+    Ok(match a.len().cmp(&b.len()) {
+        std::cmp::Ordering::Less => (&is_less | &is_equal, Boolean::<F>::FALSE),
+        std::cmp::Ordering::Greater => (is_less, Boolean::<F>::FALSE),
+        std::cmp::Ordering::Equal => (is_less, is_equal),
+    })
+}
+
+/// Check whether `a < b` in lexicographic order, see [bytes_is_less_or_equal]
+pub fn bytes_is_less_than<F: PrimeField>(
+    a: &[UInt8<F>],
+    b: &[UInt8<F>],
+) -> Result<Boolean<F>, SynthesisError> {
+    Ok(bytes_compare(a, b)?.0)
+}
+
+/// Check whether `a <= b` in lexicographic order: the first differing byte decides, and if one of the two is a
+/// prefix of the other, the shorter one is the smaller. This is the order of `Vec<u8>`
+pub fn bytes_is_less_or_equal<F: PrimeField>(
+    a: &[UInt8<F>],
+    b: &[UInt8<F>],
+) -> Result<Boolean<F>, SynthesisError> {
+    let (is_less, is_equal) = bytes_compare(a, b)?;
+    Ok(&is_less | &is_equal)
+}
+
+/// The length of the serialisation of `tx`, the native version of
+/// [TxVar::serialised_len](crate::constraints::tx::TxVar::serialised_len)
+pub fn tx_size(tx: &Tx) -> usize {
+    let var_int_len = |length: usize| usize_to_var_int(length).unwrap().len();
+    let inputs_len: usize = tx
+        .inputs
+        .iter()
+        .map(|input| {
+            36 + var_int_len(input.unlock_script.0.len()) + input.unlock_script.0.len() + 4
+        })
+        .sum();
+    let outputs_len: usize = tx
+        .outputs
+        .iter()
+        .map(|output| 8 + var_int_len(output.lock_script.0.len()) + output.lock_script.0.len())
+        .sum();
+
+    4 + var_int_len(tx.inputs.len()) + inputs_len + var_int_len(tx.outputs.len()) + outputs_len + 4
+}
+
+/// Convert a field element into a [u64], if it fits in 64 bits
+pub fn to_u64<F: PrimeField>(value: &F) -> Option<u64> {
+    let bigint = value.into_bigint();
+    if bigint.num_bits() > 64 {
+        return None;
+    }
+    Some(bigint.as_ref()[0])
+}
+
 /// Generate default [Tx] according to [TxVarConfig]
 pub fn default_tx<P: TxVarConfig>() -> Tx {
     let version: u32 = 0;
@@ -59,7 +346,18 @@ pub fn default_tx<P: TxVarConfig>() -> Tx {
 mod tests {
     use crate::constraints::tx::TxVarConfig;
 
-    use super::default_tx;
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::{
+        R1CSVar, alloc::AllocVar, fields::fp::FpVar, prelude::Boolean, uint8::UInt8,
+    };
+    use ark_relations::r1cs::{ConstraintSystem, SynthesisError};
+    use chain_gang::util::Serializable;
+
+    use super::{
+        bytes_is_less_or_equal, bytes_is_less_than, checksig_unlock_script, default_tx,
+        fits_in_bits, is_at_least, is_der_signature, is_less_or_equal, is_less_than,
+        parse_push_lengths, push_data_prefix, to_fp_bits, to_u64, tx_size,
+    };
 
     #[derive(Clone)]
     struct Config;
@@ -78,4 +376,198 @@ mod tests {
         assert_eq!(test_tx.inputs[0].unlock_script.0.len(), 0x6b);
         assert_eq!(test_tx.outputs[0].lock_script.0.len(), 0x19);
     }
+
+    #[test]
+    fn test_push_data_prefix() {
+        assert_eq!(push_data_prefix(0).unwrap(), vec![0x00]);
+        assert_eq!(push_data_prefix(0x4b).unwrap(), vec![0x4b]);
+        assert_eq!(push_data_prefix(0x4c).unwrap(), vec![0x4c, 0x4c]);
+        assert_eq!(push_data_prefix(0x100).unwrap(), vec![0x4d, 0x00, 0x01]);
+        assert_eq!(
+            push_data_prefix(0x10000).unwrap(),
+            vec![0x4e, 0x00, 0x00, 0x01, 0x00]
+        );
+    }
+
+    #[test]
+    fn test_checksig_unlock_script() {
+        let script = checksig_unlock_script(&[0x30; 71], 0x41, &[0x02; 33]);
+        assert_eq!(parse_push_lengths(&script.0).unwrap(), vec![72, 33]);
+        assert_eq!(script.0[72], 0x41);
+    }
+
+    #[test]
+    fn test_is_der_signature() {
+        let mut signature = vec![0x30, 0x44, 0x02, 0x20];
+        signature.extend_from_slice(&[0x11; 32]);
+        signature.extend_from_slice(&[0x02, 0x20]);
+        signature.extend_from_slice(&[0x22; 32]);
+        assert!(is_der_signature(&signature));
+
+        // Negative r
+        let mut negative = signature.clone();
+        negative[4] = 0x80;
+        assert!(!is_der_signature(&negative));
+        // Non-minimal r
+        let mut non_minimal = signature.clone();
+        non_minimal[4] = 0x00;
+        assert!(!is_der_signature(&non_minimal));
+        // Wrong total length
+        let mut wrong_len = signature.clone();
+        wrong_len[1] = 0x45;
+        assert!(!is_der_signature(&wrong_len));
+        assert!(!is_der_signature(&signature[..64]));
+    }
+
+    #[test]
+    fn test_parse_push_lengths() {
+        let mut script: Vec<u8> = vec![0x02, 0xaa, 0xbb, 0x4c, 0x50];
+        script.extend_from_slice(&[0; 0x50]);
+        assert_eq!(parse_push_lengths(&script).unwrap(), vec![2, 0x50]);
+        // Non-push opcode
+        assert!(parse_push_lengths(&[0x76]).is_err());
+        // Truncated push
+        assert!(parse_push_lengths(&[0x02, 0xaa]).is_err());
+    }
+
+    fn test_comparison(a: u64, b: u64, allocate: bool) {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let (a_var, b_var) = if allocate {
+            (
+                FpVar::<F>::new_witness(cs.clone(), || Ok(F::from(a))).unwrap(),
+                FpVar::<F>::new_witness(cs.clone(), || Ok(F::from(b))).unwrap(),
+            )
+        } else {
+            (
+                FpVar::<F>::constant(F::from(a)),
+                FpVar::<F>::constant(F::from(b)),
+            )
+        };
+
+        assert_eq!(
+            is_less_than(&a_var, &b_var, 64).unwrap().value().unwrap(),
+            a < b
+        );
+        assert_eq!(
+            is_less_or_equal(&a_var, &b_var, 64)
+                .unwrap()
+                .value()
+                .unwrap(),
+            a <= b
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_is_less_than() {
+        for allocate in [true, false] {
+            test_comparison(1, 2, allocate);
+            test_comparison(2, 1, allocate);
+            test_comparison(7, 7, allocate);
+            test_comparison(0, u64::MAX, allocate);
+            test_comparison(u64::MAX, 0, allocate);
+        }
+    }
+
+    #[test]
+    fn test_bytes_comparison() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        for (a, b) in [
+            (vec![1u8, 2], vec![1u8, 3]),
+            (vec![1, 3], vec![1, 2]),
+            (vec![2, 0], vec![1, 0xff]),
+            (vec![1, 2], vec![1, 2]),
+            (vec![1], vec![1, 0]),
+            (vec![1, 0], vec![1]),
+            (vec![2], vec![1, 0]),
+            (vec![], vec![0]),
+        ] {
+            let a_var = Vec::<UInt8<F>>::new_witness(cs.clone(), || Ok(a.clone())).unwrap();
+            let b_var = Vec::<UInt8<F>>::new_witness(cs.clone(), || Ok(b.clone())).unwrap();
+            assert_eq!(
+                bytes_is_less_than(&a_var, &b_var).unwrap().value().unwrap(),
+                a < b
+            );
+            assert_eq!(
+                bytes_is_less_or_equal(&a_var, &b_var)
+                    .unwrap()
+                    .value()
+                    .unwrap(),
+                a <= b
+            );
+        }
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_is_at_least() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let booleans =
+            Vec::<Boolean<F>>::new_witness(cs.clone(), || Ok(vec![true, false, true])).unwrap();
+        for (k, expected) in [(0, true), (1, true), (2, true), (3, false), (4, false)] {
+            assert_eq!(
+                is_at_least(&booleans, k).unwrap().value().unwrap(),
+                expected
+            );
+        }
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_fits_in_bits() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        for (value, n_bits, expected) in [
+            (F::from(u64::MAX), 64, true),
+            (F::from(u64::MAX) + F::from(1u64), 64, false),
+            (-F::from(1u64), 64, false),
+            (F::from(0u64), 0, true),
+        ] {
+            let value_var = FpVar::<F>::new_witness(cs.clone(), || Ok(value)).unwrap();
+            assert_eq!(
+                fits_in_bits(&value_var, n_bits).unwrap().value().unwrap(),
+                expected
+            );
+        }
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_to_fp_bits() {
+        let bytes: Vec<u8> = vec![0x01, 0x80];
+        let cs = ConstraintSystem::<F>::new_ref();
+        for byte in bytes.iter() {
+            UInt8::<F>::new_input(cs.clone(), || Ok(byte)).unwrap();
+        }
+
+        assert_eq!(
+            cs.borrow().unwrap().instance_assignment[1..],
+            to_fp_bits::<F>(&bytes)
+        );
+    }
+
+    #[test]
+    fn test_tx_size() {
+        let tx = default_tx::<Config>();
+        let mut tx_bytes: Vec<u8> = Vec::new();
+        tx.write(&mut tx_bytes).unwrap();
+        assert_eq!(tx_size(&tx), tx_bytes.len());
+    }
+
+    #[test]
+    fn test_to_u64() {
+        assert_eq!(to_u64(&F::from(u64::MAX)), Some(u64::MAX));
+        assert_eq!(to_u64(&F::from(0u64)), Some(0));
+        assert_eq!(to_u64(&(F::from(u64::MAX) + F::from(1u64))), None);
+        assert_eq!(to_u64(&-F::from(1u64)), None);
+    }
+
+    #[test]
+    fn test_is_less_than_too_many_bits() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let a = FpVar::<F>::new_witness(cs.clone(), || Ok(F::from(1u64))).unwrap();
+        assert!(matches!(
+            is_less_than(&a, &a, 254),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
 }
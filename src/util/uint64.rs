@@ -0,0 +1,165 @@
+//! Comparisons and checked arithmetic for [UInt64] variables, e.g., amounts in satoshis.
+//!
+//! The checked operations enforce that the result does not overflow (or underflow): if it does, the
+//! constraint system is unsatisfiable.
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    R1CSVar,
+    alloc::AllocVar,
+    eq::EqGadget,
+    fields::{FieldVar, fp::FpVar},
+    prelude::Boolean,
+    uint64::UInt64,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+
+use crate::error::Error;
+use crate::util::{is_less_or_equal, is_less_than, to_fp};
+
+/// Check whether `a < b`
+pub fn is_lt<F: PrimeField>(a: &UInt64<F>, b: &UInt64<F>) -> Result<Boolean<F>, SynthesisError> {
+    is_less_than(&to_fp(a)?, &to_fp(b)?, 64)
+}
+
+/// Check whether `a <= b`
+pub fn is_le<F: PrimeField>(a: &UInt64<F>, b: &UInt64<F>) -> Result<Boolean<F>, SynthesisError> {
+    is_less_or_equal(&to_fp(a)?, &to_fp(b)?, 64)
+}
+
+/// Allocate the [UInt64] whose value is `value`, and enforce that it is equal to `expected`.
+/// As the result fits in 64 bits, the equality fails if `expected` does not.
+fn enforce_result<F: PrimeField>(
+    cs: ConstraintSystemRef<F>,
+    value: impl FnOnce() -> Result<Option<u64>, SynthesisError>,
+    expected: &FpVar<F>,
+) -> Result<UInt64<F>, SynthesisError> {
+    if cs.is_none() {
+        return value()?
+            .map(UInt64::<F>::constant)
+            .ok_or(SynthesisError::Unsatisfiable);
+    }
+
+    // On overflow, any value is inconsistent with `expected`
+    let result = UInt64::<F>::new_witness(cs, || Ok(value()?.unwrap_or_default()))?;
+    to_fp(&result)?.enforce_equal(expected)?;
+    Ok(result)
+}
+
+/// Compute `a + b`, enforcing that the sum is smaller than `2^64`
+pub fn checked_add<F: PrimeField>(
+    a: &UInt64<F>,
+    b: &UInt64<F>,
+) -> Result<UInt64<F>, SynthesisError> {
+    enforce_result(
+        a.cs().or(b.cs()),
+        || Ok(a.value()?.checked_add(b.value()?)),
+        &(to_fp(a)? + to_fp(b)?),
+    )
+}
+
+/// Compute `a - b`, enforcing that `b <= a`
+pub fn checked_sub<F: PrimeField>(
+    a: &UInt64<F>,
+    b: &UInt64<F>,
+) -> Result<UInt64<F>, SynthesisError> {
+    enforce_result(
+        a.cs().or(b.cs()),
+        || Ok(a.value()?.checked_sub(b.value()?)),
+        &(to_fp(a)? - to_fp(b)?),
+    )
+}
+
+/// Compute `a * c`, enforcing that the product is smaller than `2^64`. Returns [Error::ConfigMismatch] (as
+/// [SynthesisError::Unsatisfiable]) if the product of two 64-bit numbers does not fit in the field
+pub fn mul_by_const<F: PrimeField>(a: &UInt64<F>, c: u64) -> Result<UInt64<F>, SynthesisError> {
+    if F::MODULUS_BIT_SIZE <= 128 {
+        return Err(Error::ConfigMismatch(format!(
+            "The product of two 64-bit numbers does not fit in a field of {} bits",
+            F::MODULUS_BIT_SIZE
+        ))
+        .into());
+    }
+    enforce_result(
+        a.cs(),
+        || Ok(a.value()?.checked_mul(c)),
+        &(to_fp(a)? * FpVar::<F>::constant(F::from(c))),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::{R1CSVar, alloc::AllocVar, uint64::UInt64};
+    use ark_relations::r1cs::{ConstraintSystem, SynthesisError};
+
+    use super::{checked_add, checked_sub, is_le, is_lt, mul_by_const};
+
+    #[test]
+    fn test_comparisons() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let a = UInt64::<F>::new_witness(cs.clone(), || Ok(5)).unwrap();
+        let b = UInt64::<F>::new_witness(cs.clone(), || Ok(u64::MAX)).unwrap();
+
+        assert!(is_lt(&a, &b).unwrap().value().unwrap());
+        assert!(!is_lt(&b, &a).unwrap().value().unwrap());
+        assert!(!is_lt(&a, &a).unwrap().value().unwrap());
+        assert!(is_le(&a, &a).unwrap().value().unwrap());
+        assert!(!is_le(&b, &a).unwrap().value().unwrap());
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_comparisons_against_native() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        for (a, b) in [(1, 2), (2, 1), (7, 7), (0, u64::MAX), (u64::MAX, 0)] {
+            let a_var = UInt64::<F>::new_witness(cs.clone(), || Ok(a)).unwrap();
+            let b_var = UInt64::<F>::new_witness(cs.clone(), || Ok(b)).unwrap();
+            assert_eq!(is_lt(&a_var, &b_var).unwrap().value().unwrap(), a < b);
+            assert_eq!(is_le(&a_var, &b_var).unwrap().value().unwrap(), a <= b);
+        }
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let a = UInt64::<F>::new_witness(cs.clone(), || Ok(1_000)).unwrap();
+        let b = UInt64::<F>::new_witness(cs.clone(), || Ok(300)).unwrap();
+
+        assert_eq!(checked_add(&a, &b).unwrap().value().unwrap(), 1_300);
+        assert_eq!(checked_sub(&a, &b).unwrap().value().unwrap(), 700);
+        assert_eq!(mul_by_const(&a, 7).unwrap().value().unwrap(), 7_000);
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_overflow() {
+        let overflows: [fn(&UInt64<F>, &UInt64<F>) -> Result<UInt64<F>, SynthesisError>; 3] = [
+            checked_add,
+            |a, b| checked_sub(b, a),
+            |a, _| mul_by_const(a, 3),
+        ];
+        for overflow in overflows.iter() {
+            let cs = ConstraintSystem::<F>::new_ref();
+            let a = UInt64::<F>::new_witness(cs.clone(), || Ok(u64::MAX / 2)).unwrap();
+            let b = UInt64::<F>::new_witness(cs.clone(), || Ok(u64::MAX / 2 + 2)).unwrap();
+            let _ = overflow(&b, &a).unwrap();
+            assert!(!cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_constant_overflow() {
+        let a = UInt64::<F>::constant(u64::MAX);
+        let b = UInt64::<F>::constant(1);
+        assert_eq!(checked_add(&b, &b).unwrap().value().unwrap(), 2);
+        assert!(matches!(
+            checked_add(&a, &b),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+        assert!(matches!(
+            checked_sub(&b, &a),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+}